@@ -0,0 +1,84 @@
+// src-tauri/src/changes.rs
+// 变更订阅：前端按实体类型订阅后，后台每隔 POLL_INTERVAL 取出触发器写入的变更记录（见 db::take_changes），
+// 按订阅分组通过 Tauri 事件 CHANGE_EVENT 推送，前端据此只刷新变动的条目，不必整页重新加载
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+
+use crate::db::{EntityChange, CHANGE_FEED_TABLES};
+
+// 推送变更的事件名
+pub const CHANGE_EVENT: &str = "entity-changes";
+
+// 检查变更记录的间隔
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// 一个订阅：entity_types 为空表示订阅全部类型
+#[derive(Debug, Clone)]
+struct Subscription {
+    id: u64,
+    entity_types: Vec<String>,
+}
+
+// 推送给一个订阅的一批变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    pub subscription_id: u64,
+    pub changes: Vec<EntityChange>,
+}
+
+static SUBSCRIPTIONS: Lazy<Mutex<Vec<Subscription>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+
+// 可订阅的实体类型
+pub fn supported_entity_types() -> Vec<&'static str> {
+    CHANGE_FEED_TABLES.iter().map(|(_, entity_type)| *entity_type).collect()
+}
+
+// 登记订阅，返回订阅 ID（未知的实体类型报错）
+pub fn subscribe(entity_types: &[String]) -> Result<u64, String> {
+    let known = supported_entity_types();
+    let mut types: Vec<String> = Vec::new();
+    for entity_type in entity_types {
+        let entity_type = entity_type.trim();
+        if !known.contains(&entity_type) {
+            return Err(format!("不支持订阅的类型: {}（支持 {}）", entity_type, known.join("、")));
+        }
+        if !types.iter().any(|t| t == entity_type) {
+            types.push(entity_type.to_string());
+        }
+    }
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::SeqCst);
+    let mut subscriptions = SUBSCRIPTIONS.lock().map_err(|e| e.to_string())?;
+    subscriptions.push(Subscription { id, entity_types: types });
+    Ok(id)
+}
+
+// 取消订阅，返回订阅是否存在
+pub fn unsubscribe(subscription_id: u64) -> bool {
+    let Ok(mut subscriptions) = SUBSCRIPTIONS.lock() else {
+        return false;
+    };
+    let before = subscriptions.len();
+    subscriptions.retain(|s| s.id != subscription_id);
+    subscriptions.len() < before
+}
+
+// 按订阅分组变更（没有匹配变更的订阅不推送）
+pub fn dispatch(changes: &[EntityChange]) -> Vec<ChangeBatch> {
+    let Ok(subscriptions) = SUBSCRIPTIONS.lock() else {
+        return Vec::new();
+    };
+    subscriptions.iter()
+        .map(|s| ChangeBatch {
+            subscription_id: s.id,
+            changes: changes.iter()
+                .filter(|c| s.entity_types.is_empty() || s.entity_types.contains(&c.entity_type))
+                .cloned()
+                .collect(),
+        })
+        .filter(|batch| !batch.changes.is_empty())
+        .collect()
+}
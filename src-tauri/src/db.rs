@@ -2,211 +2,760 @@
 use rusqlite::{Connection, Result};
 use std::path::PathBuf;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use once_cell::sync::OnceCell;
 use chrono::Datelike;
+use crate::import::{ImportedActivity, ImportedContact, ImportedEvent, ImportedHoliday, MarkdownEventImport, NotionImport};
 
 // 使用 OnceCell 创建全局的、懒加载的数据库连接
 static DB_CONN: OnceCell<Mutex<Connection>> = OnceCell::new();
 
+// 应用退出时关闭数据库后置为 true，之后的访问直接返回错误
+static DB_CLOSED: AtomicBool = AtomicBool::new(false);
+
+// 数据库文件名
+const DB_FILE_NAME: &str = "mindmirror_local.db";
+
+// 应用退出时关闭数据库连接（未建立连接时不做任何事）
+pub fn close_db() -> Result<()> {
+    let Some(db) = DB_CONN.get() else {
+        return Ok(());
+    };
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    DB_CLOSED.store(true, Ordering::SeqCst);
+    conn.cache_flush()?;
+    let _ = conn.execute_batch("PRAGMA optimize");
+    // 全局连接无法移出，换成内存连接后关闭原连接
+    let file_conn = std::mem::replace(&mut *conn, Connection::open_in_memory()?);
+    file_conn.close().map_err(|(_, e)| e)
+}
+
+// 应用数据目录（存放默认数据库和存储配置），不可用时回退到当前目录
+pub fn app_data_dir() -> PathBuf {
+    if let Some(app_data_dir) = dirs::data_local_dir() {
+        let app_dir = app_data_dir.join("mindmirror");
+        // 确保目录存在
+        std::fs::create_dir_all(&app_dir).ok();
+        app_dir
+    } else {
+        // 回退到当前目录（开发环境）
+        PathBuf::from(".")
+    }
+}
+
+// 指定数据库位置的环境变量，设为 :memory: 时使用内存数据库（用于测试和演示）
+pub const DB_ENV_VAR: &str = "MEMORYSTACK_DB";
+const MEMORY_DB_PATH: &str = ":memory:";
+
+// 是否运行在内存数据库模式
+pub fn is_memory_db() -> bool {
+    std::env::var(DB_ENV_VAR).is_ok_and(|v| v.trim() == MEMORY_DB_PATH)
+}
+
+// 解析数据库路径：优先使用环境变量，其次是存储设置中的自定义目录
+pub fn resolve_db_path() -> PathBuf {
+    resolve_db_location().0
+}
+
+// 解析数据库路径；存储设置中的自定义目录不可用（如外接磁盘未连接）时改用默认位置，并一同返回该目录
+fn resolve_db_location() -> (PathBuf, Option<String>) {
+    if let Ok(path) = std::env::var(DB_ENV_VAR) {
+        if !path.trim().is_empty() {
+            return (PathBuf::from(path.trim()), None);
+        }
+    }
+    match load_storage_settings().db_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) if std::path::Path::new(&dir).is_dir() => (PathBuf::from(dir).join(DB_FILE_NAME), None),
+        unavailable => (app_data_dir().join(DB_FILE_NAME), unavailable),
+    }
+}
+
 pub fn get_db() -> Result<&'static Mutex<Connection>> {
+    if DB_CLOSED.load(Ordering::SeqCst) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("数据库已关闭".to_string())
+        ));
+    }
     DB_CONN.get_or_try_init(|| {
-        // 优先使用应用数据目录，如果不可用则使用当前目录
-        let db_path = if let Some(app_data_dir) = dirs::data_local_dir() {
-            let app_dir = app_data_dir.join("mindmirror");
-            // 确保目录存在
-            std::fs::create_dir_all(&app_dir).ok();
-            app_dir.join("mindmirror_local.db")
-        } else {
-            // 回退到当前目录（开发环境）
-            PathBuf::from(".").join("mindmirror_local.db")
-        };
+        let db_path = resolve_db_path();
         
         println!("📁 首次建立数据库连接，路径: {:?}", db_path.canonicalize().unwrap_or(db_path.clone()));
         
-        let conn = Connection::open(db_path)?;
-        
-        // 创建 projects 表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS projects (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                description TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建 contacts 表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS contacts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                title TEXT,                -- 职位/头衔
-                notes TEXT,                -- 备注或背景信息
-                tags TEXT,                 -- 逗号分隔的标签，如 '客户,技术,紧急'
-                phone TEXT,                -- 电话（JSON数组格式，支持多个）
-                email TEXT,                -- 邮箱
-                address TEXT,              -- 地址
-                company TEXT,              -- 单位名称
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+        let conn = open_connection(&db_path)?;
+        println!("✅ 数据库和表初始化成功！");
+        Ok(Mutex::new(conn))
+    })
+}
 
-        // 为旧数据库添加新字段（如果不存在）
-        let _ = conn.execute("ALTER TABLE contacts ADD COLUMN phone TEXT", []);
-        let _ = conn.execute("ALTER TABLE contacts ADD COLUMN email TEXT", []);
-        let _ = conn.execute("ALTER TABLE contacts ADD COLUMN address TEXT", []);
-        let _ = conn.execute("ALTER TABLE contacts ADD COLUMN company TEXT", []);
+// 打开数据库文件并完成建表、迁移和连接设置
+fn open_connection(db_path: &std::path::Path) -> Result<Connection> {
+    let conn = Connection::open(db_path)?;
+    init_schema(&conn)?;
+    // 缓存常用语句，并记录每条语句的耗时（见 get_query_metrics）
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    conn.trace_v2(rusqlite::trace::TraceEventCodes::SQLITE_TRACE_PROFILE, Some(record_query_time));
+    Ok(conn)
+}
 
-        // 创建 projects_contacts 关联表 (多对多关系)
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS projects_contacts (
-                project_id INTEGER NOT NULL,
-                contact_id INTEGER NOT NULL,
-                role TEXT,                 -- 在此项目中的角色，如 '产品负责人','技术顾问'
-                notes TEXT,                -- 在此项目中的特别备注
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (project_id, contact_id),           -- 联合主键，防止重复关联
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
-                FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+// 当前打开的数据库文件路径（内存数据库时为解析出的配置路径）
+fn connection_path(conn: &Connection) -> PathBuf {
+    conn.path()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(resolve_db_path)
+}
 
-        // 创建 events 表（事件记录）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS events (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                description TEXT,
-                event_date TEXT NOT NULL,
-                project_id INTEGER,
-                event_type TEXT,
-                reminder_time TEXT,
-                reminder_triggered INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL
-            )",
-            [],
-        )?;
-        
-        // 为已存在的 events 表添加提醒字段（数据库迁移）
-        let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_time TEXT", []);
-        let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_triggered INTEGER DEFAULT 0", []);
+// 只需执行一次的数据迁移：执行后记入 schema_migrations，之后启动时跳过
+fn run_migration_once(conn: &Connection, name: &str, migrate: impl FnOnce(&Connection) -> Result<()>) -> Result<()> {
+    let applied: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM schema_migrations WHERE name = ?1)",
+        [name],
+        |row| row.get(0),
+    )?;
+    if applied {
+        return Ok(());
+    }
+    migrate(conn)?;
+    conn.execute("INSERT INTO schema_migrations (name) VALUES (?1)", [name])?;
+    Ok(())
+}
 
-        // 创建 events_contacts 关联表（事件-联系人多对多关系）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS events_contacts (
-                event_id INTEGER NOT NULL,
-                contact_id INTEGER NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (event_id, contact_id),
-                FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE,
-                FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+// 建表并执行迁移（测试中可对内存数据库调用）
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    // 建表和迁移期间关闭外键检查，避免旧数据中的孤立记录导致迁移失败；清理完孤立记录后再开启
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+    
+    // 创建 schema_migrations 表（已执行的一次性数据迁移）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            name TEXT PRIMARY KEY,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // 创建 projects 表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT,
+            color TEXT,                -- 项目颜色
+            icon TEXT,                 -- 项目图标
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // 为已存在的 projects 表添加颜色和图标字段
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN color TEXT", []);
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN icon TEXT", []);
+    // 完成活动时是否自动在项目时间线上记录事件
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN log_activity_completion INTEGER DEFAULT 0", []);
+    // 归档：已结束的项目默认不出现在项目列表中
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN archived INTEGER NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN archived_at TEXT", []);
+    // 项目阶段：规划中、进行中、已搁置、已完成（见 PROJECT_STATUS_TRANSITIONS）
+    let _ = conn.execute("ALTER TABLE projects ADD COLUMN status TEXT NOT NULL DEFAULT '进行中'", []);
+    
+    // 创建 contacts 表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contacts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            title TEXT,                -- 职位/头衔
+            notes TEXT,                -- 备注或背景信息
+            tags TEXT,                 -- 逗号分隔的标签，如 '客户,技术,紧急'
+            phone TEXT,                -- 电话（JSON数组格式，支持多个）
+            email TEXT,                -- 邮箱
+            address TEXT,              -- 地址
+            company TEXT,              -- 单位名称
+            pinned INTEGER DEFAULT 0,  -- 是否置顶
+            phone_normalized TEXT,     -- E.164 格式电话（JSON数组，与 phone 对应）
+            city TEXT,                 -- 从地址解析出的城市
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
 
-        // 创建 project_files 表（项目文件管理）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS project_files (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                project_id INTEGER NOT NULL,
-                original_name TEXT NOT NULL,
-                stored_name TEXT NOT NULL,
-                file_path TEXT NOT NULL,
-                file_size INTEGER,
-                file_type TEXT,
-                version INTEGER DEFAULT 1,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+    // 为旧数据库添加新字段（如果不存在）
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN phone TEXT", []);
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN email TEXT", []);
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN address TEXT", []);
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN company TEXT", []);
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN pinned INTEGER DEFAULT 0", []);
+    // 规范化为 E.164 的电话（JSON 数组，与 phone 一一对应，无法识别的为空字符串）
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN phone_normalized TEXT", []);
+    backfill_normalized_phones(conn)?;
+    // 从地址解析出的城市，用于按城市查询联系人
+    let _ = conn.execute("ALTER TABLE contacts ADD COLUMN city TEXT", []);
+    backfill_contact_cities(conn)?;
 
-        // 创建 project_activities 表（项目活动管理）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS project_activities (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                project_id INTEGER NOT NULL,
-                name TEXT NOT NULL,
-                description TEXT,
-                estimated_completion_date TEXT,
-                status TEXT NOT NULL DEFAULT '待分配',
-                activated_at DATETIME,
-                paused_at DATETIME,
-                completed_at DATETIME,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+    // 创建 projects_contacts 关联表 (多对多关系)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS projects_contacts (
+            project_id INTEGER NOT NULL,
+            contact_id INTEGER NOT NULL,
+            role TEXT,                 -- 在此项目中的角色，如 '产品负责人','技术顾问'
+            notes TEXT,                -- 在此项目中的特别备注
+            reports_to INTEGER,        -- 在此项目中的上级（同一项目的另一位联系人）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (project_id, contact_id),           -- 联合主键，防止重复关联
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    // 项目内的汇报关系，用于生成组织架构图
+    let _ = conn.execute("ALTER TABLE projects_contacts ADD COLUMN reports_to INTEGER", []);
 
-        // 创建 activities_contacts 关联表（活动-负责人多对多关系）
+    // 创建 events 表（事件记录）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            description TEXT,
+            event_date TEXT NOT NULL,
+            project_id INTEGER,
+            event_type TEXT,
+            reminder_time TEXT,
+            reminder_triggered INTEGER DEFAULT 0,
+            pinned INTEGER DEFAULT 0,
+            reminder_insistent INTEGER DEFAULT 0,
+            reminder_repeat_minutes INTEGER,
+            reminder_acknowledged_at TEXT,
+            reminder_last_notified_at TEXT,
+            channel TEXT,              -- 沟通渠道
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    
+    // 为已存在的 events 表添加提醒字段（数据库迁移）
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_time TEXT", []);
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_triggered INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN pinned INTEGER DEFAULT 0", []);
+    // 持续提醒：每隔 N 分钟重复通知，直到用户确认
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_insistent INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_repeat_minutes INTEGER", []);
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_acknowledged_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN reminder_last_notified_at TEXT", []);
+    // 沟通渠道（电话、微信、邮件、面谈、视频），未填写时按事件类型补齐
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN channel TEXT", []);
+    // 私密事件：不出现在导出、分享快照和自动总结中
+    let _ = conn.execute("ALTER TABLE events ADD COLUMN is_private INTEGER NOT NULL DEFAULT 0", []);
+    // 只在加列后补齐一次，之后用户清空的渠道不会被重新填上
+    run_migration_once(conn, "backfill_event_channels", |conn| {
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS activities_contacts (
-                activity_id INTEGER NOT NULL,
-                contact_id INTEGER NOT NULL,
-                assigned_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                PRIMARY KEY (activity_id, contact_id),
-                FOREIGN KEY (activity_id) REFERENCES project_activities(id) ON DELETE CASCADE,
-                FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
-            )",
+            "UPDATE events SET channel = CASE event_type
+                WHEN '电话' THEN '电话' WHEN '微信' THEN '微信' WHEN '邮件' THEN '邮件'
+                WHEN '会议' THEN '面谈' WHEN '视频会议' THEN '视频' END
+             WHERE channel IS NULL AND event_type IN ('电话', '微信', '邮件', '会议', '视频会议')",
             [],
         )?;
+        Ok(())
+    })?;
 
-        // 创建 operation_logs 操作日志表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS operation_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                operation_type TEXT NOT NULL,
-                entity_type TEXT NOT NULL,
-                entity_id INTEGER NOT NULL,
-                entity_name TEXT NOT NULL,
-                old_value TEXT,
-                new_value TEXT,
-                related_entities TEXT,
-                project_id INTEGER,
-                project_name TEXT,
-                description TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建操作日志索引
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_logs_created_at ON operation_logs(created_at)", []);
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_logs_entity ON operation_logs(entity_type, entity_id)", []);
+    // 创建 events_contacts 关联表（事件-联系人多对多关系）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events_contacts (
+            event_id INTEGER NOT NULL,
+            contact_id INTEGER NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (event_id, contact_id),
+            FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
 
-        // 创建 summaries 总结表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS summaries (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                summary_type TEXT NOT NULL,
-                start_date TEXT NOT NULL,
-                end_date TEXT NOT NULL,
-                content TEXT NOT NULL,
-                statistics TEXT,
-                is_auto_generated INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建总结索引
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_summaries_date ON summaries(start_date, end_date)", []);
-        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_summaries_type ON summaries(summary_type)", []);
+    // 创建 events_projects 关联表（事件-项目多对多关系，events.project_id 保留为主项目）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events_projects (
+            event_id INTEGER NOT NULL,
+            project_id INTEGER NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (event_id, project_id),
+            FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    // 将已有事件的主项目迁移到关联表（已存在的跳过）
+    conn.execute(
+        "INSERT OR IGNORE INTO events_projects (event_id, project_id)
+         SELECT id, project_id FROM events WHERE project_id IS NOT NULL",
+        [],
+    )?;
 
-        println!("✅ 数据库和表初始化成功！");
-        Ok(Mutex::new(conn))
-    })
+    // 创建 project_files 表（项目文件管理）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_files (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            original_name TEXT NOT NULL,
+            stored_name TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER,
+            file_type TEXT,
+            version INTEGER DEFAULT 1,
+            event_id INTEGER,          -- 上传时自动创建的事件（文件作为该事件的附件）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    
+    // 为已存在的 project_files 表添加事件关联字段
+    let _ = conn.execute("ALTER TABLE project_files ADD COLUMN event_id INTEGER", []);
+
+    // 创建 project_activities 表（项目活动管理）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_activities (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            estimated_completion_date TEXT,
+            status TEXT NOT NULL DEFAULT '待分配',
+            activated_at DATETIME,
+            paused_at DATETIME,
+            completed_at DATETIME,
+            template_id INTEGER,       -- 来源活动模板
+            estimated_hours REAL,      -- 预估工时（小时）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 由模板生成的活动记录来源模板
+    let _ = conn.execute("ALTER TABLE project_activities ADD COLUMN template_id INTEGER", []);
+    // 活动预估工时（小时）
+    let _ = conn.execute("ALTER TABLE project_activities ADD COLUMN estimated_hours REAL", []);
+    // 累计暂停时长（秒），每次结束暂停时累加
+    let _ = conn.execute("ALTER TABLE project_activities ADD COLUMN paused_seconds INTEGER NOT NULL DEFAULT 0", []);
+
+    // 创建 activity_templates 活动模板表（可设置周期，由后台任务按周期自动创建活动）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            due_offset_days INTEGER,     -- 生成后多少天到期（预计完成日期）
+            recurrence TEXT,             -- daily / weekly:1-7 / monthly:1-31，为空表示仅手动使用
+            last_generated_on TEXT,      -- 最近一次按周期生成的日期
+            active INTEGER DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 创建 activities_contacts 关联表（活动-负责人多对多关系）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activities_contacts (
+            activity_id INTEGER NOT NULL,
+            contact_id INTEGER NOT NULL,
+            assigned_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (activity_id, contact_id),
+            FOREIGN KEY (activity_id) REFERENCES project_activities(id) ON DELETE CASCADE,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 创建 operation_logs 操作日志表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS operation_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation_type TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            entity_name TEXT NOT NULL,
+            old_value TEXT,
+            new_value TEXT,
+            related_entities TEXT,
+            project_id INTEGER,
+            project_name TEXT,
+            description TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // 创建操作日志索引
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_logs_created_at ON operation_logs(created_at)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_logs_entity ON operation_logs(entity_type, entity_id)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_logs_project ON operation_logs(project_id, created_at)", []);
+
+    // 创建 summaries 总结表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS summaries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            summary_type TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            statistics TEXT,
+            is_auto_generated INTEGER DEFAULT 0,
+            tags TEXT,                 -- 逗号分隔的标签
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // 为已存在的 summaries 表添加标签字段
+    let _ = conn.execute("ALTER TABLE summaries ADD COLUMN tags TEXT", []);
+    
+    // 创建总结索引
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_summaries_date ON summaries(start_date, end_date)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_summaries_type ON summaries(summary_type)", []);
+
+    // 创建 event_types 事件类型目录表（name 与 events.event_type 中存储的值对应）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS event_types (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT,
+            icon TEXT,
+            sort_order INTEGER DEFAULT 0,
+            default_reminder_offset_minutes INTEGER,  -- 默认提前提醒的分钟数
+            notification_urgency TEXT,                -- 提醒通知的紧急程度：低、普通、紧急
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    let _ = conn.execute("ALTER TABLE event_types ADD COLUMN default_reminder_offset_minutes INTEGER", []);
+    let _ = conn.execute("ALTER TABLE event_types ADD COLUMN notification_urgency TEXT", []);
+    
+    // 预置内置事件类型（只在首次初始化时写入，用户删除的内置类型不会恢复）：名称、颜色、图标、默认提前提醒分钟数
+    let builtin_event_types: &[(&str, &str, &str, Option<i32>)] = &[
+        ("会议", "#1677ff", "📅", Some(30)),
+        ("电话", "#52c41a", "📞", None),
+        ("邮件", "#722ed1", "📧", None),
+        ("微信", "#07c160", "💬", None),
+        ("QQ", "#12b7f5", "🐧", None),
+        ("钉钉", "#3296fa", "📌", None),
+        ("飞书", "#3370ff", "🪶", None),
+        ("短信", "#13c2c2", "✉️", None),
+        ("工单", "#fa8c16", "🎫", None),
+        ("视频会议", "#2f54eb", "🎥", Some(15)),
+        ("里程碑", "#f5222d", "🏁", Some(24 * 60)),
+        ("截止", "#cf1322", "⏰", Some(24 * 60)),
+        ("个人", "#eb2f96", "👤", None),
+        ("文件", "#8c8c8c", "📎", None),
+        ("完成", "#389e0d", "✅", None),
+        ("其他", "#bfbfbf", "🔖", None),
+    ];
+    run_migration_once(conn, "seed_builtin_event_types", |conn| {
+        for (i, (name, color, icon, offset)) in builtin_event_types.iter().enumerate() {
+            conn.execute(
+                "INSERT OR IGNORE INTO event_types (name, color, icon, sort_order, default_reminder_offset_minutes) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![name, color, icon, i as i32, offset],
+            )?;
+        }
+        Ok(())
+    })?;
+
+    // 创建 contact_files 表（联系人文件管理，如合同、证件扫描件）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contact_files (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            contact_id INTEGER NOT NULL,
+            original_name TEXT NOT NULL,
+            stored_name TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER,
+            file_type TEXT,
+            version INTEGER DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 创建 entity_links 通用关联表（任意两条记录之间的"相关"关系，无方向）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_type TEXT NOT NULL,   -- project / contact / event / activity / file / contact_file / summary
+            source_id INTEGER NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id INTEGER NOT NULL,
+            label TEXT,                  -- 关系说明，如 '参考'、'后续'
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (source_type, source_id, target_type, target_id)
+        )",
+        [],
+    )?;
+
+    // 创建 holidays 节假日表（法定假日和调休上班日，周末默认休息）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS holidays (
+            date TEXT PRIMARY KEY,       -- YYYY-MM-DD
+            name TEXT NOT NULL,
+            is_workday INTEGER DEFAULT 0, -- 1 表示调休上班（周末补班）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // 创建 settings 应用设置表（键值对）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // 创建 activity_reviews 活动审核记录表（提交审核、通过、退回）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity_reviews (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            activity_id INTEGER NOT NULL,
+            action TEXT NOT NULL,              -- submitted / approved / rejected
+            reviewer_contact_id INTEGER,       -- 审核人
+            note TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (activity_id) REFERENCES project_activities(id) ON DELETE CASCADE,
+            FOREIGN KEY (reviewer_contact_id) REFERENCES contacts(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 创建 activity_status_history 活动状态变更表（暂停时长统一由 project_activities.paused_seconds 累计）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity_status_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            activity_id INTEGER NOT NULL,
+            from_status TEXT NOT NULL,
+            to_status TEXT NOT NULL,
+            changed_at TEXT NOT NULL,          -- YYYY-MM-DD HH:MM:SS
+            FOREIGN KEY (activity_id) REFERENCES project_activities(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_status_history ON activity_status_history(activity_id, changed_at)", []);
+
+    // 创建 activity_time_entries 活动工时记录表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity_time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            activity_id INTEGER NOT NULL,
+            contact_id INTEGER,            -- 投入工时的人
+            hours REAL NOT NULL,
+            entry_date TEXT NOT NULL,      -- YYYY-MM-DD
+            note TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (activity_id) REFERENCES project_activities(id) ON DELETE CASCADE,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 创建 company_domains 邮箱域名与单位对照表（用于按邮箱推荐单位）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS company_domains (
+            domain TEXT PRIMARY KEY,   -- 小写域名，如 example.com
+            company TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // 创建 reminder_inbox 提醒收件箱（系统通知发出时记录，通知消失后仍可在应用内查看）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reminder_inbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            event_id INTEGER NOT NULL,
+            title TEXT NOT NULL,           -- 通知标题（发出时的快照）
+            body TEXT,                     -- 通知正文
+            notify_count INTEGER DEFAULT 1, -- 持续提醒重复通知的次数
+            first_notified_at TEXT NOT NULL,
+            last_notified_at TEXT NOT NULL,
+            acknowledged_at TEXT,
+            FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 创建 job_runs 后台任务执行记录表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS job_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            job_name TEXT NOT NULL,
+            trigger TEXT NOT NULL,         -- scheduled 定时 / manual 手动
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            message TEXT                   -- 执行结果或错误信息
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job_name, id)", [])?;
+
+    // 创建 drafts 草稿表（未保存的表单内容，崩溃或误关闭后可恢复）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS drafts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,     -- event / contact / project / activity
+            entity_id INTEGER,             -- 编辑已有记录时的ID，新建时为空
+            payload TEXT NOT NULL,         -- 表单内容（JSON）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // 创建 day_notes 每日记录表（一天可记多条，生成总结时一并列出）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS day_notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_date TEXT NOT NULL,       -- YYYY-MM-DD
+            content TEXT NOT NULL,
+            mood TEXT,                     -- 心情（如 不错 / 疲惫）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_day_notes_date ON day_notes(note_date)", []);
+    // 心情和精力评分（1-5）
+    let _ = conn.execute("ALTER TABLE day_notes ADD COLUMN mood_rating INTEGER", []);
+    let _ = conn.execute("ALTER TABLE day_notes ADD COLUMN energy_rating INTEGER", []);
+    // 私密记录：不出现在导出和自动总结中
+    let _ = conn.execute("ALTER TABLE day_notes ADD COLUMN is_private INTEGER NOT NULL DEFAULT 0", []);
+
+    // 创建 upload_staging 上传暂存表（记录进行中的上传，崩溃后启动时据此清理）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS upload_staging (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER,          -- 项目文件上传
+            contact_id INTEGER,          -- 联系人文件上传
+            target_path TEXT NOT NULL,   -- 最终存储路径（复制过程中先写入 target_path.partial）
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    // 创建 file_shares 文件分享表（复制到分享文件夹的文件副本，过期后由后台任务删除）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS file_shares (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_id INTEGER,             -- 来源项目文件（原文件删除后副本仍保留到过期）
+            original_name TEXT NOT NULL,
+            share_path TEXT NOT NULL,    -- 副本路径：分享文件夹/<随机名>/<原文件名>
+            expires_at TEXT NOT NULL,    -- YYYY-MM-DD HH:MM:SS
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_file_shares_expires ON file_shares(expires_at)", []);
+
+    // 创建 contact_availability 联系人工作时间表（可选，用于推荐会议时间）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contact_availability (
+            contact_id INTEGER PRIMARY KEY,
+            timezone TEXT,                                 -- UTC 偏移，如 +08:00（为空时与本机相同）
+            work_start TEXT NOT NULL DEFAULT '09:00',      -- 当地时间 HH:MM
+            work_end TEXT NOT NULL DEFAULT '18:00',
+            work_days TEXT NOT NULL DEFAULT '1,2,3,4,5',   -- 工作日，1 为周一、7 为周日
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 创建 commitments 合同/发票/付款台账表（到期前自动提醒）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS commitments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            commitment_type TEXT NOT NULL,                -- 合同 / 发票 / 付款
+            title TEXT NOT NULL,
+            amount_minor INTEGER,                         -- 金额（最小货币单位，如分），见 money.rs
+            currency TEXT,                                -- 币种代码，如 CNY
+            due_date TEXT NOT NULL,                       -- YYYY-MM-DD
+            contact_id INTEGER,                           -- 对方联系人
+            project_id INTEGER,
+            status TEXT NOT NULL DEFAULT '待处理',         -- 待处理 / 已完成 / 已取消
+            remind_days_before INTEGER NOT NULL DEFAULT 3,
+            reminder_at TEXT,                             -- 提醒时间（到期日前 N 天 09:00），由 remind_days_before 计算
+            reminded_at TEXT,                             -- 已发送提醒的时间
+            notes TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE SET NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_commitments_due ON commitments(status, due_date)", []);
+    // 金额改为最小货币单位的整数加币种，旧的浮点金额按默认币种换算
+    let _ = conn.execute("ALTER TABLE commitments ADD COLUMN amount_minor INTEGER", []);
+    let _ = conn.execute("ALTER TABLE commitments ADD COLUMN currency TEXT", []);
+    let currency = crate::money::default_currency(&fetch_settings_with(conn)?);
+    let _ = conn.execute(
+        "UPDATE commitments SET amount_minor = CAST(ROUND(amount * ?1) AS INTEGER), currency = ?2
+         WHERE amount IS NOT NULL AND amount_minor IS NULL",
+        rusqlite::params![10i64.pow(crate::money::currency_exponent(&currency)), currency],
+    );
+
+    // 创建 access_log 打开记录表（全局搜索按最近使用时间和频率排序）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS access_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,    -- contact / project / event / file
+            entity_id INTEGER NOT NULL,
+            accessed_at TEXT NOT NULL     -- YYYY-MM-DD HH:MM:SS
+        )",
+        [],
+    )?;
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_access_log_entity ON access_log(entity_type, entity_id, accessed_at)", []);
+
+    // 创建 change_feed 变更记录表（由触发器写入，后台轮询后推送给前端订阅者并删除）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS change_feed (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,    -- 见 CHANGE_FEED_TABLES
+            entity_id INTEGER NOT NULL,
+            op TEXT NOT NULL,             -- insert / update / delete
+            changed_at TEXT NOT NULL      -- YYYY-MM-DD HH:MM:SS
+        )",
+        [],
+    )?;
+    create_change_feed_triggers(conn)?;
+    create_updated_at_triggers(conn)?;
+    create_delete_log_triggers(conn)?;
+    // 上次运行留下的变更已没有订阅者
+    conn.execute("DELETE FROM change_feed", [])?;
+
+    // 为旧日志补上所属项目
+    backfill_log_projects(conn)?;
+
+    // 一次性清理孤立记录，然后开启外键约束
+    migrate_foreign_keys(conn)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON")?;
+    Ok(())
 }
 
 
@@ -218,6 +767,11 @@ pub struct Project {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
+    pub color: Option<String>,   // 项目颜色，如 #1677ff
+    pub icon: Option<String>,    // 项目图标（emoji 或图标名）
+    pub status: String,          // 规划中、进行中、已搁置、已完成
+    pub archived: bool,
+    pub archived_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -234,6 +788,11 @@ pub struct Contact {
     pub email: Option<String>,      // 邮箱
     pub address: Option<String>,    // 地址
     pub company: Option<String>,    // 单位名称
+    pub pinned: bool,               // 是否置顶
+    #[serde(default)]
+    pub phone_normalized: Option<String>,  // E.164 格式电话（JSON数组，与 phone 对应）
+    #[serde(default)]
+    pub city: Option<String>,       // 从地址解析出的城市
     pub created_at: String,
     pub updated_at: String,
 }
@@ -261,6 +820,16 @@ pub struct Event {
     pub event_type: Option<String>,
     pub reminder_time: Option<String>,
     pub reminder_triggered: bool,
+    pub pinned: bool,
+    pub reminder_insistent: bool,                 // 持续提醒模式
+    pub reminder_repeat_minutes: Option<i32>,     // 持续提醒的重复间隔（分钟）
+    pub reminder_acknowledged_at: Option<String>, // 用户确认提醒的时间
+    #[serde(default)]
+    pub project_ids: Vec<i32>,                    // 关联的所有项目（包含主项目 project_id）
+    #[serde(default)]
+    pub channel: Option<String>,                  // 沟通渠道：电话、微信、邮件、面谈、视频
+    #[serde(default)]
+    pub is_private: bool,                         // 私密：不出现在导出、分享快照和自动总结中
     pub created_at: String,
     pub updated_at: String,
 }
@@ -271,6 +840,61 @@ pub struct EventWithDetails {
     pub event: Event,
     pub contacts: Vec<Contact>,
     pub project_name: Option<String>,
+    #[serde(default)]
+    pub project_names: Vec<String>,   // 与 event.project_ids 一一对应
+    pub project_color: Option<String>,
+    pub project_icon: Option<String>,
+    pub type_color: Option<String>,   // 事件类型颜色（来自事件类型目录）
+    pub type_icon: Option<String>,
+}
+
+// contacts 表查询列（表别名 c，与 contact_from_row 对应）
+const CONTACT_COLUMNS: &str = "c.id, c.name, c.title, c.notes, c.tags, c.phone, c.email, c.address, c.company, c.created_at, c.updated_at, c.pinned, c.phone_normalized, c.city";
+
+// 将查询行转换为联系人结构体
+fn contact_from_row(row: &rusqlite::Row) -> Result<Contact> {
+    Ok(Contact {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        title: row.get(2)?,
+        notes: row.get(3)?,
+        tags: row.get(4)?,
+        phone: row.get(5)?,
+        email: row.get(6)?,
+        address: row.get(7)?,
+        company: row.get(8)?,
+        pinned: row.get::<_, Option<i32>>(11)?.unwrap_or(0) != 0,
+        phone_normalized: row.get(12)?,
+        city: row.get(13)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+// events 表查询列（表别名 e，与 event_from_row 对应）
+const EVENT_COLUMNS: &str = "e.id, e.title, e.description, e.event_date, e.project_id, e.event_type, e.reminder_time, e.reminder_triggered, e.created_at, e.updated_at, e.pinned, e.reminder_insistent, e.reminder_repeat_minutes, e.reminder_acknowledged_at, e.channel, e.is_private";
+
+// 将查询行转换为事件结构体
+fn event_from_row(row: &rusqlite::Row) -> Result<Event> {
+    Ok(Event {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        event_date: row.get(3)?,
+        project_id: row.get(4)?,
+        event_type: row.get(5)?,
+        reminder_time: row.get(6)?,
+        reminder_triggered: row.get::<_, i32>(7).unwrap_or(0) != 0,
+        pinned: row.get::<_, Option<i32>>(10)?.unwrap_or(0) != 0,
+        reminder_insistent: row.get::<_, Option<i32>>(11)?.unwrap_or(0) != 0,
+        reminder_repeat_minutes: row.get(12)?,
+        reminder_acknowledged_at: row.get(13)?,
+        project_ids: Vec::new(),  // 由 assemble_event_details 补充
+        channel: row.get(14)?,
+        is_private: row.get::<_, Option<i32>>(15)?.unwrap_or(0) != 0,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
 }
 
 // 插入新项目
@@ -281,7 +905,10 @@ pub fn insert_project(name: &str, description: Option<&str>) -> Result<i64> {
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
+    insert_project_with(&conn, name, description)
+}
+
+pub fn insert_project_with(conn: &Connection, name: &str, description: Option<&str>) -> Result<i64> {
     conn.execute(
         "INSERT INTO projects (name, description) VALUES (?1, ?2)",
         &[name, description.unwrap_or("")],
@@ -294,8 +921,8 @@ pub fn insert_project(name: &str, description: Option<&str>) -> Result<i64> {
     let desc = format!("{}，新增项目「{}」", now.format("%Y年%m月%d日 %H:%M"), name);
     
     conn.execute(
-        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description) 
-         VALUES ('create', 'project', ?1, ?2, ?3)",
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description) 
+         VALUES ('create', 'project', ?1, ?2, ?1, ?2, ?3)",
         rusqlite::params![project_id, name, desc],
     )?;
     
@@ -319,32 +946,139 @@ pub fn get_project_name(project_id: i32) -> Result<String> {
     Ok(name)
 }
 
-// 查询所有项目
-pub fn fetch_projects() -> Result<Vec<Project>> {
+// 查询项目（include_archived 为 false 时不含已归档的项目），最近更新的在前
+pub fn fetch_projects(include_archived: bool) -> Result<Vec<Project>> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
-    let mut stmt = conn.prepare("SELECT id, name, description, created_at, updated_at FROM projects ORDER BY updated_at DESC")?;
-    let project_iter = stmt.query_map([], |row| {
-        Ok(Project {
+    fetch_projects_with(&conn, include_archived)
+}
+
+pub fn fetch_projects_with(conn: &Connection, include_archived: bool) -> Result<Vec<Project>> {
+    ListQuery::new("SELECT id, name, description, created_at, updated_at, color, icon, archived, archived_at, status FROM projects")
+        .archived("archived", include_archived)
+        .order_by("updated_at DESC")
+        .fetch(conn, |row| Ok(Project {
             id: row.get(0)?,
             name: row.get(1)?,
             description: row.get(2)?,
+            color: row.get(5)?,
+            icon: row.get(6)?,
+            status: row.get(9)?,
+            archived: row.get::<_, i32>(7)? != 0,
+            archived_at: row.get(8)?,
             created_at: row.get(3)?,
             updated_at: row.get(4)?,
-        })
+        }))
+}
+
+// 项目阶段及可以转到的阶段
+pub const PROJECT_STATUS_TRANSITIONS: &[(&str, &[&str])] = &[
+    ("规划中", &["进行中", "已搁置"]),
+    ("进行中", &["规划中", "已搁置", "已完成"]),
+    ("已搁置", &["规划中", "进行中", "已完成"]),
+    ("已完成", &["进行中"]),
+];
+
+// 修改项目阶段（只允许 PROJECT_STATUS_TRANSITIONS 中的转换），变更记入操作日志，返回项目名称
+pub fn set_project_status(project_id: i32, status: &str) -> Result<String> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    set_project_status_with(&conn, project_id, status)
+}
+
+pub fn set_project_status_with(conn: &Connection, project_id: i32, status: &str) -> Result<String> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let status = status.trim();
+    if !PROJECT_STATUS_TRANSITIONS.iter().any(|(s, _)| *s == status) {
+        let all: Vec<&str> = PROJECT_STATUS_TRANSITIONS.iter().map(|(s, _)| *s).collect();
+        return Err(invalid(format!("无效的项目阶段: {}（可选 {}）", status, all.join("、"))));
+    }
+    let (name, from_status): (String, String) = conn.query_row(
+        "SELECT name, status FROM projects WHERE id = ?1",
+        [project_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => invalid(format!("项目不存在: {}", project_id)),
+        e => e,
     })?;
-    
-    let mut projects = Vec::new();
-    for project in project_iter {
-        projects.push(project?);
+    if from_status == status {
+        return Ok(name);
     }
-    Ok(projects)
+    let allowed = PROJECT_STATUS_TRANSITIONS.iter()
+        .find(|(s, _)| *s == from_status)
+        .map(|(_, next)| *next)
+        .unwrap_or(&[]);
+    if !allowed.contains(&status) {
+        return Err(invalid(format!("项目「{}」当前为「{}」，不能改为「{}」", name, from_status, status)));
+    }
+    
+    conn.execute(
+        "UPDATE projects SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![status, project_id],
+    )?;
+    
+    let desc = format!("{}，项目「{}」由「{}」改为「{}」", chrono::Local::now().format("%Y年%m月%d日 %H:%M"), name, from_status, status);
+    conn.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, old_value, new_value, project_id, project_name, description)
+         VALUES ('update', 'project', ?1, ?2, ?3, ?4, ?1, ?2, ?5)",
+        rusqlite::params![project_id, name, from_status, status, desc],
+    )?;
+    Ok(name)
+}
+
+// 归档或取消归档项目，返回项目名称
+pub fn set_project_archived(project_id: i32, archived: bool) -> Result<String> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    set_project_archived_with(&conn, project_id, archived)
 }
 
+pub fn set_project_archived_with(conn: &Connection, project_id: i32, archived: bool) -> Result<String> {
+    let (name, was_archived): (String, bool) = conn.query_row(
+        "SELECT name, archived FROM projects WHERE id = ?1",
+        [project_id],
+        |row| Ok((row.get(0)?, row.get::<_, i32>(1)? != 0))
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("项目不存在: {}", project_id))
+        ),
+        e => e,
+    })?;
+    if was_archived == archived {
+        return Ok(name);
+    }
+    
+    let now = chrono::Local::now();
+    conn.execute(
+        "UPDATE projects SET archived = ?1, archived_at = ?2 WHERE id = ?3",
+        rusqlite::params![archived as i32, archived.then(|| now.format("%Y-%m-%d %H:%M:%S").to_string()), project_id],
+    )?;
+    
+    let desc = format!("{}，{}项目「{}」", now.format("%Y年%m月%d日 %H:%M"), if archived { "归档" } else { "取消归档" }, name);
+    conn.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description)
+         VALUES ('update', 'project', ?1, ?2, ?1, ?2, ?3)",
+        rusqlite::params![project_id, name, desc],
+    )?;
+    
+    // 归档时生成项目复盘，失败不影响归档
+    if archived {
+        if let Err(e) = generate_project_retrospective_with(conn, project_id, now.date_naive()) {
+            println!("⚠️ 生成项目「{}」的复盘失败: {}", name, e);
+        }
+    }
+    Ok(name)
+}
 
 // 插入新联系人
 pub fn insert_contact(
@@ -363,17 +1097,35 @@ pub fn insert_contact(
         Some(format!("锁失败: {}", e))
     ))?;
     
+    let owned = |value: Option<&str>| value.map(str::to_string);
+    insert_contact_with(&conn, &NewContact {
+        name: name.to_string(),
+        title: owned(title),
+        notes: owned(notes),
+        tags: owned(tags),
+        phone: owned(phone),
+        email: owned(email),
+        address: owned(address),
+        company: owned(company),
+    })
+}
+
+pub fn insert_contact_with(conn: &Connection, contact: &NewContact) -> Result<i64> {
+    let phone = contact.phone.as_deref();
+    let address = contact.address.as_deref();
     conn.execute(
-        "INSERT INTO contacts (name, title, notes, tags, phone, email, address, company) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO contacts (name, title, notes, tags, phone, email, address, company, phone_normalized, city) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         rusqlite::params![
-            name,
-            title.unwrap_or(""),
-            notes.unwrap_or(""),
-            tags.unwrap_or(""),
+            contact.name,
+            contact.title.as_deref().unwrap_or(""),
+            contact.notes.as_deref().unwrap_or(""),
+            contact.tags.as_deref().unwrap_or(""),
             phone.unwrap_or(""),
-            email.unwrap_or(""),
+            contact.email.as_deref().unwrap_or(""),
             address.unwrap_or(""),
-            company.unwrap_or("")
+            contact.company.as_deref().unwrap_or(""),
+            normalized_phone_json(phone),
+            address.and_then(parse_city)
         ],
     )?;
     
@@ -381,8 +1133,8 @@ pub fn insert_contact(
     
     // 记录操作日志
     let now = chrono::Local::now();
-    let mut desc = format!("{}，新增联系人「{}」", now.format("%Y年%m月%d日 %H:%M"), name);
-    if let Some(t) = tags {
+    let mut desc = format!("{}，新增联系人「{}」", now.format("%Y年%m月%d日 %H:%M"), contact.name);
+    if let Some(t) = &contact.tags {
         if !t.is_empty() {
             desc.push_str(&format!("，标签：{}", t));
         }
@@ -391,7 +1143,7 @@ pub fn insert_contact(
     conn.execute(
         "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description) 
          VALUES ('create', 'contact', ?1, ?2, ?3)",
-        rusqlite::params![contact_id, name, desc],
+        rusqlite::params![contact_id, contact.name, desc],
     )?;
     
     Ok(contact_id)
@@ -406,22 +1158,11 @@ pub fn fetch_contacts() -> Result<Vec<Contact>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare("SELECT id, name, title, notes, tags, phone, email, address, company, created_at, updated_at FROM contacts ORDER BY updated_at DESC")?;
-    let contact_iter = stmt.query_map([], |row| {
-        Ok(Contact {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            title: row.get(2)?,
-            notes: row.get(3)?,
-            tags: row.get(4)?,
-            phone: row.get(5)?,
-            email: row.get(6)?,
-            address: row.get(7)?,
-            company: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM contacts c ORDER BY c.pinned DESC, c.updated_at DESC",
+        CONTACT_COLUMNS
+    ))?;
+    let contact_iter = stmt.query_map([], contact_from_row)?;
     
     let mut contacts = Vec::new();
     for contact in contact_iter {
@@ -437,14 +1178,40 @@ pub fn link_contact_to_project(project_id: i32, contact_id: i32, role: Option<&s
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
+    link_contact_to_project_with(&conn, project_id, contact_id, role, notes)
+}
+
+pub fn link_contact_to_project_with(conn: &Connection, project_id: i32, contact_id: i32, role: Option<&str>, notes: Option<&str>) -> Result<()> {
+    // 已关联时只更新角色和备注，保留汇报关系
     conn.execute(
-        "INSERT OR REPLACE INTO projects_contacts (project_id, contact_id, role, notes) VALUES (?1, ?2, ?3, ?4)",
+        "INSERT INTO projects_contacts (project_id, contact_id, role, notes) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(project_id, contact_id) DO UPDATE SET role = excluded.role, notes = excluded.notes",
         rusqlite::params![project_id, contact_id, role, notes],
     )?;
     Ok(())
 }
 
+// 把联系人加入项目，已关联的联系人保持原有角色、备注和汇报关系不变；返回新加入的人数
+pub fn add_contacts_to_project(project_id: i32, contact_ids: &[i32]) -> Result<usize> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    add_contacts_to_project_with(&conn, project_id, contact_ids)
+}
+
+pub fn add_contacts_to_project_with(conn: &Connection, project_id: i32, contact_ids: &[i32]) -> Result<usize> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO projects_contacts (project_id, contact_id) VALUES (?1, ?2)"
+    )?;
+    let mut added = 0;
+    for contact_id in contact_ids {
+        added += stmt.execute([project_id, *contact_id])?;
+    }
+    Ok(added)
+}
+
 // 获取项目关联的所有联系人
 pub fn fetch_contacts_for_project(project_id: i32) -> Result<Vec<(Contact, Option<String>, Option<String>)>> {
     let db = get_db()?;
@@ -453,31 +1220,20 @@ pub fn fetch_contacts_for_project(project_id: i32) -> Result<Vec<(Contact, Optio
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
-        "SELECT c.id, c.name, c.title, c.notes, c.tags, c.phone, c.email, c.address, c.company, c.created_at, c.updated_at, pc.role, pc.notes
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, pc.role, pc.notes
          FROM contacts c
          INNER JOIN projects_contacts pc ON c.id = pc.contact_id
          WHERE pc.project_id = ?1
-         ORDER BY pc.created_at DESC"
-    )?;
+         ORDER BY pc.created_at DESC",
+        CONTACT_COLUMNS
+    ))?;
     
     let results = stmt.query_map([project_id], |row| {
         Ok((
-            Contact {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                title: row.get(2)?,
-                notes: row.get(3)?,
-                tags: row.get(4)?,
-                phone: row.get(5)?,
-                email: row.get(6)?,
-                address: row.get(7)?,
-                company: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            },
-            row.get(11)?,  // role
-            row.get(12)?,  // project-specific notes
+            contact_from_row(row)?,
+            row.get(12)?,  // role
+            row.get(13)?,  // project-specific notes
         ))
     })?;
     
@@ -500,11 +1256,354 @@ pub fn unlink_contact_from_project(project_id: i32, contact_id: i32) -> Result<(
         "DELETE FROM projects_contacts WHERE project_id = ?1 AND contact_id = ?2",
         rusqlite::params![project_id, contact_id],
     )?;
+    conn.execute(
+        "UPDATE projects_contacts SET reports_to = NULL WHERE project_id = ?1 AND reports_to = ?2",
+        rusqlite::params![project_id, contact_id],
+    )?;
+    Ok(())
+}
+
+// ==================== 项目组织架构 ====================
+
+// 组织架构中的一位成员，children 为直接下属
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgChartNode {
+    pub contact_id: i32,
+    pub name: String,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub role: Option<String>,        // 在此项目中的角色
+    pub reports_to: Option<i32>,
+    pub children: Vec<OrgChartNode>,
+}
+
+// 同一单位的成员
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgChartCompany {
+    pub company: String,
+    pub contact_ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectOrgChart {
+    pub project_id: i32,
+    pub project_name: String,
+    pub roots: Vec<OrgChartNode>,         // 没有上级（或上级不在项目中）的成员
+    pub companies: Vec<OrgChartCompany>,  // 按单位分组，成员多的在前，未填写单位的不列出
+    pub member_count: usize,
+}
+
+// 设置联系人在项目中的上级（None 表示清除），上级必须是同一项目的成员且不能形成循环
+pub fn set_project_reports_to(project_id: i32, contact_id: i32, reports_to: Option<i32>) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    set_project_reports_to_with(&conn, project_id, contact_id, reports_to)
+}
+
+pub fn set_project_reports_to_with(conn: &Connection, project_id: i32, contact_id: i32, reports_to: Option<i32>) -> Result<()> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let is_member = |id: i32| -> Result<bool> {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM projects_contacts WHERE project_id = ?1 AND contact_id = ?2)",
+            [project_id, id],
+            |row| row.get(0),
+        )
+    };
+    if !is_member(contact_id)? {
+        return Err(invalid(format!("联系人 {} 不是项目 {} 的成员", contact_id, project_id)));
+    }
+    if let Some(manager) = reports_to {
+        if manager == contact_id {
+            return Err(invalid("不能把自己设为上级".to_string()));
+        }
+        if !is_member(manager)? {
+            return Err(invalid(format!("上级 {} 不是项目 {} 的成员", manager, project_id)));
+        }
+        // 沿上级链向上查找，遇到自己说明会形成循环
+        let mut current = Some(manager);
+        let mut steps = 0;
+        while let Some(id) = current {
+            if id == contact_id {
+                return Err(invalid("汇报关系不能形成循环".to_string()));
+            }
+            steps += 1;
+            if steps > 1000 {
+                break;
+            }
+            current = conn.query_row(
+                "SELECT reports_to FROM projects_contacts WHERE project_id = ?1 AND contact_id = ?2",
+                [project_id, id],
+                |row| row.get(0),
+            ).ok().flatten();
+        }
+    }
+    conn.execute(
+        "UPDATE projects_contacts SET reports_to = ?3 WHERE project_id = ?1 AND contact_id = ?2",
+        rusqlite::params![project_id, contact_id, reports_to],
+    )?;
     Ok(())
 }
 
+// 获取项目的组织架构：成员角色、汇报关系组成的树，以及按单位的分组
+pub fn fetch_project_org_chart(project_id: i32) -> Result<ProjectOrgChart> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_project_org_chart_with(&conn, project_id)
+}
+
+pub fn fetch_project_org_chart_with(conn: &Connection, project_id: i32) -> Result<ProjectOrgChart> {
+    let project_name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [project_id], |row| row.get(0))
+        .map_err(|_| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("项目不存在: {}", project_id))
+        ))?;
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.id, c.name, c.title, NULLIF(TRIM(c.company), ''), pc.role, pc.reports_to
+         FROM projects_contacts pc
+         INNER JOIN contacts c ON c.id = pc.contact_id
+         WHERE pc.project_id = ?1
+         ORDER BY pc.created_at, c.id"
+    )?;
+    let members: Vec<OrgChartNode> = stmt.query_map([project_id], |row| {
+        Ok(OrgChartNode {
+            contact_id: row.get(0)?,
+            name: row.get(1)?,
+            title: row.get(2)?,
+            company: row.get(3)?,
+            role: row.get(4)?,
+            reports_to: row.get(5)?,
+            children: Vec::new(),
+        })
+    })?.filter_map(|r| r.ok()).collect();
+
+    let mut companies: Vec<OrgChartCompany> = Vec::new();
+    for member in &members {
+        let Some(company) = &member.company else { continue };
+        match companies.iter_mut().find(|c| &c.company == company) {
+            Some(group) => group.contact_ids.push(member.contact_id),
+            None => companies.push(OrgChartCompany { company: company.clone(), contact_ids: vec![member.contact_id] }),
+        }
+    }
+    companies.sort_by_key(|c| std::cmp::Reverse(c.contact_ids.len()));
+
+    // 上级不在项目中的成员作为根；剩下没有挂到树上的（数据中残留的循环）也作为根
+    let ids: std::collections::HashSet<i32> = members.iter().map(|m| m.contact_id).collect();
+    let mut placed = std::collections::HashSet::new();
+    let mut roots = Vec::new();
+    for member in &members {
+        if member.reports_to.is_none_or(|id| !ids.contains(&id)) {
+            roots.push(build_org_chart_node(member, &members, &mut placed));
+        }
+    }
+    for member in &members {
+        if !placed.contains(&member.contact_id) {
+            roots.push(build_org_chart_node(member, &members, &mut placed));
+        }
+    }
+
+    Ok(ProjectOrgChart {
+        project_id,
+        project_name,
+        roots,
+        companies,
+        member_count: members.len(),
+    })
+}
+
+// 递归挂上直接下属（每位成员只出现一次）
+fn build_org_chart_node(member: &OrgChartNode, members: &[OrgChartNode], placed: &mut std::collections::HashSet<i32>) -> OrgChartNode {
+    placed.insert(member.contact_id);
+    let mut node = member.clone();
+    for child in members {
+        if child.reports_to == Some(member.contact_id) && !placed.contains(&child.contact_id) {
+            node.children.push(build_org_chart_node(child, members, placed));
+        }
+    }
+    node
+}
+
+// ==================== 列表查询构造 ====================
+
+use rusqlite::types::Value as SqlValue;
+
+// 带筛选、排序和分页的列表查询：可选的筛选条件只在有值时加入 WHERE，
+// 条件片段中的 ? 表示本条件的参数（同一片段中出现多次时都指向同一个参数），构造时按加入顺序编号为 ?1、?2……
+// （引号内的 ? 和已编号的 ?N 保持不变）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ListQuery {
+    select: String,           // SELECT ... FROM ...（含 JOIN，不含 WHERE）
+    conditions: Vec<String>,
+    params: Vec<SqlValue>,
+    order_by: Vec<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl ListQuery {
+    pub(crate) fn new(select: impl Into<String>) -> Self {
+        ListQuery { select: select.into(), ..Default::default() }
+    }
+
+    // 固定条件（不带参数）
+    pub(crate) fn condition(mut self, sql: &str) -> Self {
+        self.conditions.push(sql.to_string());
+        self
+    }
+
+    // 带一个参数的条件，如 filter("e.event_type = ?", "会议")
+    pub(crate) fn filter(mut self, sql: &str, value: impl Into<SqlValue>) -> Self {
+        self.params.push(value.into());
+        let placeholder = format!("?{}", self.params.len());
+        self.conditions.push(number_placeholders(sql, &placeholder));
+        self
+    }
+
+    // 排除已归档的记录（include_archived 为 true 时不过滤），各列表统一经由这里处理归档筛选
+    pub(crate) fn archived(self, archived_column: &str, include_archived: bool) -> Self {
+        if include_archived {
+            self
+        } else {
+            self.condition(&format!("{} = 0", archived_column))
+        }
+    }
+
+    // 不包含已归档项目时，排除所属项目已归档的记录（project_column 为所属项目 ID 列，没有所属项目的记录保留）
+    pub(crate) fn archived_project(self, project_column: &str, include_archived: bool) -> Self {
+        self.archived(
+            &format!("COALESCE((SELECT ap.archived FROM projects ap WHERE ap.id = {}), 0)", project_column),
+            include_archived,
+        )
+    }
+
+    // 可选条件：值为空时不加入
+    pub(crate) fn filter_opt<T: Into<SqlValue>>(self, sql: &str, value: Option<T>) -> Self {
+        match value {
+            Some(value) => self.filter(sql, value),
+            None => self,
+        }
+    }
+
+    // 排序（多次调用按先后作为第一、第二……排序键）
+    pub(crate) fn order_by(mut self, sql: &str) -> Self {
+        self.order_by.push(sql.to_string());
+        self
+    }
+
+    // 分页：page 从 0 开始
+    pub(crate) fn page(mut self, page: i64, page_size: i64) -> Self {
+        self.limit = Some(page_size);
+        self.offset = Some(page.max(0) * page_size);
+        self
+    }
+
+    // 生成 SQL（LIMIT / OFFSET 以参数传入）
+    pub(crate) fn sql(&self) -> String {
+        let mut sql = self.select.clone();
+        if !self.conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.conditions.iter().map(|c| format!("({})", c)).collect::<Vec<_>>().join(" AND "));
+        }
+        if !self.order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order_by.join(", "));
+        }
+        let mut next = self.params.len();
+        if self.limit.is_some() || self.offset.is_some() {
+            next += 1;
+            sql.push_str(&format!(" LIMIT ?{}", next));
+        }
+        if self.offset.is_some() {
+            next += 1;
+            sql.push_str(&format!(" OFFSET ?{}", next));
+        }
+        sql
+    }
+
+    // 与 sql() 中占位符对应的参数（未设置 LIMIT 而设置了 OFFSET 时 LIMIT 为 -1，即不限）
+    pub(crate) fn params(&self) -> Vec<SqlValue> {
+        let mut params = self.params.clone();
+        if self.limit.is_some() || self.offset.is_some() {
+            params.push(SqlValue::Integer(self.limit.unwrap_or(-1)));
+        }
+        if let Some(offset) = self.offset {
+            params.push(SqlValue::Integer(offset));
+        }
+        params
+    }
+
+    // 执行查询并逐行转换（转换失败的行跳过，与各列表函数原有的处理一致）
+    pub(crate) fn fetch<T>(&self, conn: &Connection, f: impl FnMut(&rusqlite::Row) -> Result<T>) -> Result<Vec<T>> {
+        let mut stmt = conn.prepare_cached(&self.sql())?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(self.params()), f)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+}
+
+// 将条件片段中未编号的 ? 替换为 placeholder，跳过字符串字面量（'...'）和带引号的标识符（"..."）
+fn number_placeholders(sql: &str, placeholder: &str) -> String {
+    let mut out = String::with_capacity(sql.len() + placeholder.len());
+    let mut quote: Option<char> = None;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // 引号内连续两个引号是转义，进出各一次后状态不变
+            (Some(q), _) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?') if !chars.peek().is_some_and(|next| next.is_ascii_digit()) => {
+                out.push_str(placeholder);
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
 // ==================== 事件相关函数 ====================
 
+// 只有日期的事件默认视为当天 9:00 开始
+const DEFAULT_EVENT_HOUR: u32 = 9;
+
+// 解析事件日期（支持 YYYY-MM-DD、YYYY-MM-DD HH:MM[:SS]、YYYY-MM-DDTHH:MM）
+pub fn parse_event_datetime(event_date: &str) -> Option<chrono::NaiveDateTime> {
+    let value = event_date.trim().replace('T', " ");
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(&value, fmt) {
+            return Some(dt);
+        }
+    }
+    chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(DEFAULT_EVENT_HOUR, 0, 0))
+}
+
+// 根据事件时间和提前分钟数计算提醒时间（已过去的不再设置）
+// 提前量跨天时，落在节假日的提醒提前到之前最近的工作日（保持时刻不变）
+fn default_reminder_time(event_date: &str, offset_minutes: i32, calendar: &WorkCalendar) -> Option<String> {
+    let event_time = parse_event_datetime(event_date)?;
+    let mut reminder = event_time - chrono::Duration::minutes(offset_minutes as i64);
+    if offset_minutes >= 24 * 60 && !calendar.is_working_day(reminder.date()) {
+        // 之前一个月都没有工作日时保持原提醒时间
+        if let Some(working_day) = calendar.previous_working_day(reminder.date()) {
+            reminder = working_day.and_time(reminder.time());
+        }
+    }
+    if reminder < chrono::Local::now().naive_local() {
+        return None;
+    }
+    Some(reminder.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
 // 插入新事件，返回新创建的事件 ID
 pub fn insert_event(
     title: &str,
@@ -520,12 +1619,47 @@ pub fn insert_event(
         Some(format!("锁失败: {}", e))
     ))?;
     
+    insert_event_with(&conn, title, description, event_date, project_id, event_type, reminder_time)
+}
+
+pub fn insert_event_with(
+    conn: &Connection,
+    title: &str,
+    description: Option<&str>,
+    event_date: &str,
+    project_id: Option<i32>,
+    event_type: Option<&str>,
+    reminder_time: Option<&str>,
+) -> Result<i64> {
+    // 未指定提醒时间时，按事件类型的默认提前量自动设置
+    let default_reminder = match (reminder_time, event_type) {
+        (None, Some(t)) => {
+            let offset: Option<i32> = conn.query_row(
+                "SELECT default_reminder_offset_minutes FROM event_types WHERE name = ?1",
+                [t],
+                |row| row.get(0)
+            ).unwrap_or(None);
+            let calendar = WorkCalendar::load(conn)?;
+            offset.and_then(|minutes| default_reminder_time(event_date, minutes, &calendar))
+        }
+        _ => None,
+    };
+    let reminder_time = reminder_time.map(|r| r.to_string()).or(default_reminder);
+    
     conn.execute(
-        "INSERT INTO events (title, description, event_date, project_id, event_type, reminder_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![title, description, event_date, project_id, event_type, reminder_time],
+        "INSERT INTO events (title, description, event_date, project_id, event_type, reminder_time, channel) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![title, description, event_date, project_id, event_type, reminder_time, event_type.and_then(channel_for_event_type)],
     )?;
+    let event_id = conn.last_insert_rowid();
     
-    Ok(conn.last_insert_rowid())
+    if let Some(pid) = project_id {
+        conn.execute(
+            "INSERT OR IGNORE INTO events_projects (event_id, project_id) VALUES (?1, ?2)",
+            rusqlite::params![event_id, pid],
+        )?;
+    }
+    
+    Ok(event_id)
 }
 
 // 记录事件创建日志（在关联联系人后调用）
@@ -543,6 +1677,18 @@ pub fn log_event_creation(
         Some(format!("锁失败: {}", e))
     ))?;
     
+    log_event_creation_with(&conn, event_id, title, event_type, project_id, project_name, contact_names)
+}
+
+pub fn log_event_creation_with(
+    conn: &Connection,
+    event_id: i64,
+    title: &str,
+    event_type: Option<&str>,
+    project_id: Option<i32>,
+    project_name: Option<&str>,
+    contact_names: &[String],
+) -> Result<()> {
     let now = chrono::Local::now();
     let event_type_str = event_type.unwrap_or("事件");
     let mut desc = format!("{}，", now.format("%Y年%m月%d日 %H:%M"));
@@ -574,6 +1720,10 @@ pub fn link_contacts_to_event(event_id: i64, contact_ids: &[i32]) -> Result<()>
         Some(format!("锁失败: {}", e))
     ))?;
     
+    link_contacts_to_event_with(&conn, event_id, contact_ids)
+}
+
+pub fn link_contacts_to_event_with(conn: &Connection, event_id: i64, contact_ids: &[i32]) -> Result<()> {
     for contact_id in contact_ids {
         conn.execute(
             "INSERT OR IGNORE INTO events_contacts (event_id, contact_id) VALUES (?1, ?2)",
@@ -591,29 +1741,16 @@ pub fn fetch_contacts_for_event(event_id: i32) -> Result<Vec<Contact>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
-        "SELECT c.id, c.name, c.title, c.notes, c.tags, c.phone, c.email, c.address, c.company, c.created_at, c.updated_at
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM contacts c
          INNER JOIN events_contacts ec ON c.id = ec.contact_id
          WHERE ec.event_id = ?1
-         ORDER BY c.name"
-    )?;
+         ORDER BY c.name",
+        CONTACT_COLUMNS
+    ))?;
     
-    let results = stmt.query_map([event_id], |row| {
-        Ok(Contact {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            title: row.get(2)?,
-            notes: row.get(3)?,
-            tags: row.get(4)?,
-            phone: row.get(5)?,
-            email: row.get(6)?,
-            address: row.get(7)?,
-            company: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
-        })
-    })?;
+    let results = stmt.query_map([event_id], contact_from_row)?;
     
     let mut contacts = Vec::new();
     for result in results {
@@ -622,181 +1759,171 @@ pub fn fetch_contacts_for_event(event_id: i32) -> Result<Vec<Contact>> {
     Ok(contacts)
 }
 
-// 获取联系人的所有事件（时间线）
-pub fn fetch_events_for_contact(contact_id: i32) -> Result<Vec<EventWithDetails>> {
-    let (events, project_names) = {
-        let db = get_db()?;
-        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(1),
-            Some(format!("锁失败: {}", e))
-        ))?;
-        
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT e.id, e.title, e.description, e.event_date, e.project_id, e.event_type, e.reminder_time, e.reminder_triggered, e.created_at, e.updated_at
-             FROM events e
-             INNER JOIN events_contacts ec ON e.id = ec.event_id
-             WHERE ec.contact_id = ?1
-             ORDER BY e.event_date DESC"
-        )?;
-        
-        let events: Vec<Event> = stmt.query_map([contact_id], |row| {
-            Ok(Event {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                event_date: row.get(3)?,
-                project_id: row.get(4)?,
-                event_type: row.get(5)?,
-                reminder_time: row.get(6)?,
-                reminder_triggered: row.get::<_, i32>(7).unwrap_or(0) != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
-        
-        // 获取项目名称映射
-        let mut project_names: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
-        let mut p_stmt = conn.prepare("SELECT id, name FROM projects")?;
+// 事件展示所需的项目名称、项目配色和事件类型配色
+struct EventDisplayContext {
+    project_names: std::collections::HashMap<i32, String>,
+    project_styles: std::collections::HashMap<i32, (Option<String>, Option<String>)>,
+    type_styles: std::collections::HashMap<String, (Option<String>, Option<String>)>,
+}
+
+impl EventDisplayContext {
+    fn load(conn: &Connection) -> Result<Self> {
+        let mut project_names = std::collections::HashMap::new();
+        let mut project_styles = std::collections::HashMap::new();
+        let mut p_stmt = conn.prepare_cached("SELECT id, name, color, icon FROM projects")?;
         let projects = p_stmt.query_map([], |row| {
-            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
         })?;
-        for p in projects {
-            if let Ok((id, name)) = p {
-                project_names.insert(id, name);
-            }
+        for (id, name, color, icon) in projects.flatten() {
+            project_names.insert(id, name);
+            project_styles.insert(id, (color, icon));
         }
         
-        (events, project_names)
-    };
-    
-    // 组装详细信息
+        let mut type_styles = std::collections::HashMap::new();
+        let mut t_stmt = conn.prepare_cached("SELECT name, color, icon FROM event_types")?;
+        let types = t_stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        for (name, color, icon) in types.flatten() {
+            type_styles.insert(name, (color, icon));
+        }
+        
+        Ok(EventDisplayContext { project_names, project_styles, type_styles })
+    }
+}
+
+// 为事件补充联系人、项目名称和配色信息（需在释放数据库锁后调用）
+fn assemble_event_details(events: Vec<Event>, ctx: &EventDisplayContext) -> Result<Vec<EventWithDetails>> {
     let mut results = Vec::new();
-    for event in events {
+    for mut event in events {
         let contacts = fetch_contacts_for_event(event.id)?;
-        let project_name = event.project_id.and_then(|pid| project_names.get(&pid).cloned());
+        event.project_ids = fetch_project_ids_for_event(event.id, event.project_id)?;
+        let project_names = event.project_ids.iter()
+            .filter_map(|pid| ctx.project_names.get(pid).cloned())
+            .collect();
+        let project_name = event.project_id.and_then(|pid| ctx.project_names.get(&pid).cloned());
+        let (project_color, project_icon) = event.project_id
+            .and_then(|pid| ctx.project_styles.get(&pid).cloned())
+            .unwrap_or_default();
+        let (type_color, type_icon) = event.event_type.as_ref()
+            .and_then(|t| ctx.type_styles.get(t).cloned())
+            .unwrap_or_default();
         results.push(EventWithDetails {
             event,
             contacts,
             project_name,
+            project_names,
+            project_color,
+            project_icon,
+            type_color,
+            type_icon,
         });
     }
     
     Ok(results)
 }
 
+// 时间线筛选条件（均为可选，日期为 YYYY-MM-DD 闭区间）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimelineFilter {
+    pub event_type: Option<String>,
+    pub project_id: Option<i32>,   // 主项目或跨项目关联
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub include_archived: bool,    // 是否包含已归档项目的事件
+}
+
+// 获取联系人的所有事件（时间线）
+pub fn fetch_events_for_contact(contact_id: i32, filter: &TimelineFilter) -> Result<Vec<EventWithDetails>> {
+    let (events, ctx) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let events = ListQuery::new(format!(
+                "SELECT DISTINCT {} FROM events e INNER JOIN events_contacts ec ON e.id = ec.event_id",
+                EVENT_COLUMNS
+            ))
+            .filter("ec.contact_id = ?", contact_id)
+            .filter_opt("e.event_type = ?", filter.event_type.clone())
+            .filter_opt(
+                "e.project_id = ? OR EXISTS(SELECT 1 FROM events_projects ep WHERE ep.event_id = e.id AND ep.project_id = ?)",
+                filter.project_id,
+            )
+            .filter_opt("substr(e.event_date, 1, 10) >= ?", filter.start_date.clone())
+            .filter_opt("substr(e.event_date, 1, 10) <= ?", filter.end_date.clone())
+            .archived_project("e.project_id", filter.include_archived)
+            .order_by("e.pinned DESC")
+            .order_by("e.event_date DESC")
+            .fetch(&conn, event_from_row)?;
+        
+        (events, EventDisplayContext::load(&conn)?)
+    };
+    
+    assemble_event_details(events, &ctx)
+}
+
 // 获取项目的所有事件（时间线）
 pub fn fetch_events_for_project(project_id: i32) -> Result<Vec<EventWithDetails>> {
-    let (events, project_name) = {
+    let (events, ctx) = {
         let db = get_db()?;
         let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(1),
             Some(format!("锁失败: {}", e))
         ))?;
         
-        // 获取项目名称
-        let project_name: Option<String> = conn.query_row(
-            "SELECT name FROM projects WHERE id = ?1",
-            [project_id],
-            |row| row.get(0)
-        ).ok();
-        
-        let mut stmt = conn.prepare(
-            "SELECT e.id, e.title, e.description, e.event_date, e.project_id, e.event_type, e.reminder_time, e.reminder_triggered, e.created_at, e.updated_at
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}
              FROM events e
              WHERE e.project_id = ?1
-             ORDER BY e.event_date DESC"
-        )?;
+                OR EXISTS(SELECT 1 FROM events_projects ep WHERE ep.event_id = e.id AND ep.project_id = ?1)
+             ORDER BY e.pinned DESC, e.event_date DESC",
+            EVENT_COLUMNS
+        ))?;
         
-        let events: Vec<Event> = stmt.query_map([project_id], |row| {
-            Ok(Event {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                event_date: row.get(3)?,
-                project_id: row.get(4)?,
-                event_type: row.get(5)?,
-                reminder_time: row.get(6)?,
-                reminder_triggered: row.get::<_, i32>(7).unwrap_or(0) != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
+        let events: Vec<Event> = stmt.query_map([project_id], event_from_row)?.filter_map(|r| r.ok()).collect();
         
-        (events, project_name)
+        (events, EventDisplayContext::load(&conn)?)
     };
     
-    // 组装详细信息
-    let mut results = Vec::new();
-    for event in events {
-        let contacts = fetch_contacts_for_event(event.id)?;
-        results.push(EventWithDetails {
-            event,
-            contacts,
-            project_name: project_name.clone(),
-        });
-    }
-    
-    Ok(results)
+    assemble_event_details(events, &ctx)
 }
 
-// 获取所有事件
-pub fn fetch_all_events() -> Result<Vec<EventWithDetails>> {
-    let (events, project_names) = {
+// 获取所有事件（include_archived 为 false 时不含已归档项目的事件）
+pub fn fetch_all_events(include_archived: bool) -> Result<Vec<EventWithDetails>> {
+    let (events, ctx) = {
         let db = get_db()?;
         let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(1),
             Some(format!("锁失败: {}", e))
         ))?;
         
-        // 获取项目名称映射
-        let mut project_names: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
-        let mut p_stmt = conn.prepare("SELECT id, name FROM projects")?;
-        let projects = p_stmt.query_map([], |row| {
-            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
-        })?;
-        for p in projects {
-            if let Ok((id, name)) = p {
-                project_names.insert(id, name);
-            }
-        }
-        
-        let mut stmt = conn.prepare(
-            "SELECT e.id, e.title, e.description, e.event_date, e.project_id, e.event_type, e.reminder_time, e.reminder_triggered, e.created_at, e.updated_at
-             FROM events e
-             ORDER BY e.event_date DESC"
-        )?;
+        let events = fetch_all_events_with(&conn, include_archived)?;
         
-        let events: Vec<Event> = stmt.query_map([], |row| {
-            Ok(Event {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                event_date: row.get(3)?,
-                project_id: row.get(4)?,
-                event_type: row.get(5)?,
-                reminder_time: row.get(6)?,
-                reminder_triggered: row.get::<_, i32>(7).unwrap_or(0) != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
-        
-        (events, project_names)
+        (events, EventDisplayContext::load(&conn)?)
     };
     
-    // 组装详细信息
-    let mut results = Vec::new();
-    for event in events {
-        let contacts = fetch_contacts_for_event(event.id)?;
-        let project_name = event.project_id.and_then(|pid| project_names.get(&pid).cloned());
-        results.push(EventWithDetails {
-            event,
-            contacts,
-            project_name,
-        });
-    }
-    
-    Ok(results)
+    assemble_event_details(events, &ctx)
+}
+
+pub fn fetch_all_events_with(conn: &Connection, include_archived: bool) -> Result<Vec<Event>> {
+    ListQuery::new(format!("SELECT {} FROM events e", EVENT_COLUMNS))
+        .archived_project("e.project_id", include_archived)
+        .order_by("e.pinned DESC")
+        .order_by("e.event_date DESC")
+        .fetch(conn, event_from_row)
 }
 
 // 删除事件
@@ -807,10 +1934,71 @@ pub fn delete_event(event_id: i32) -> Result<()> {
         Some(format!("锁失败: {}", e))
     ))?;
     
+    conn.execute("DELETE FROM events_projects WHERE event_id = ?1", [event_id])?;
     conn.execute("DELETE FROM events WHERE id = ?1", [event_id])?;
     Ok(())
 }
 
+// 事件是否存在
+pub fn event_exists(event_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.query_row("SELECT EXISTS(SELECT 1 FROM events WHERE id = ?1)", [event_id], |row| row.get(0))
+}
+
+// 获取事件关联的项目ID（主项目排在最前）
+pub fn fetch_project_ids_for_event(event_id: i32, primary_project_id: Option<i32>) -> Result<Vec<i32>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT project_id FROM events_projects WHERE event_id = ?1 ORDER BY created_at, project_id"
+    )?;
+    let linked: Vec<i32> = stmt.query_map([event_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    let mut project_ids: Vec<i32> = primary_project_id.into_iter().collect();
+    for pid in linked {
+        if !project_ids.contains(&pid) {
+            project_ids.push(pid);
+        }
+    }
+    Ok(project_ids)
+}
+
+// 设置事件关联的项目（先删除旧关联，再添加新关联；主项目始终保留关联）
+pub fn update_event_projects(event_id: i32, project_ids: &[i32]) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let primary: Option<i32> = conn.query_row(
+        "SELECT project_id FROM events WHERE id = ?1",
+        [event_id],
+        |row| row.get(0)
+    )?;
+    
+    conn.execute("DELETE FROM events_projects WHERE event_id = ?1", [event_id])?;
+    for pid in primary.iter().chain(project_ids.iter()) {
+        conn.execute(
+            "INSERT OR IGNORE INTO events_projects (event_id, project_id) VALUES (?1, ?2)",
+            rusqlite::params![event_id, pid],
+        )?;
+    }
+    
+    Ok(())
+}
+
 // ==================== 项目文件相关 ====================
 
 // 项目文件结构体
@@ -824,12 +2012,13 @@ pub struct ProjectFile {
     pub file_size: Option<i64>,
     pub file_type: Option<String>,
     pub version: i32,
+    pub event_id: Option<i32>,  // 所属事件（附件）
     pub created_at: String,
     pub updated_at: String,
 }
 
 // 带项目名称的文件信息（用于全局搜索）
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectFileWithProject {
     pub file: ProjectFile,
     pub project_name: String,
@@ -868,8 +2057,8 @@ pub fn fetch_files_for_project(project_id: i32) -> Result<Vec<ProjectFile>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
-        "SELECT id, project_id, original_name, stored_name, file_path, file_size, file_type, version, created_at, updated_at
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, project_id, original_name, stored_name, file_path, file_size, file_type, version, created_at, updated_at, event_id
          FROM project_files
          WHERE project_id = ?1
          ORDER BY updated_at DESC"
@@ -885,6 +2074,7 @@ pub fn fetch_files_for_project(project_id: i32) -> Result<Vec<ProjectFile>> {
             file_size: row.get(5)?,
             file_type: row.get(6)?,
             version: row.get(7)?,
+            event_id: row.get(10)?,
             created_at: row.get(8)?,
             updated_at: row.get(9)?,
         })
@@ -905,6 +2095,10 @@ pub fn get_latest_file_version(project_id: i32, original_name: &str) -> Result<i
         Some(format!("锁失败: {}", e))
     ))?;
     
+    get_latest_file_version_with(&conn, project_id, original_name)
+}
+
+pub fn get_latest_file_version_with(conn: &Connection, project_id: i32, original_name: &str) -> Result<i32> {
     let version: rusqlite::Result<i32> = conn.query_row(
         "SELECT MAX(version) FROM project_files WHERE project_id = ?1 AND original_name = ?2",
         rusqlite::params![project_id, original_name],
@@ -914,8 +2108,9 @@ pub fn get_latest_file_version(project_id: i32, original_name: &str) -> Result<i
     Ok(version.unwrap_or(0))
 }
 
-// 全局搜索文件（模糊匹配文件名）
-pub fn search_files_global(keyword: &str) -> Result<Vec<ProjectFileWithProject>> {
+// 全局搜索文件（模糊匹配文件名，include_archived 为 false 时不含已归档项目的文件）
+// is_cancelled 返回 true 时停止读取结果（被更新的搜索取代）
+pub fn search_files_global(keyword: &str, include_archived: bool, is_cancelled: &dyn Fn() -> bool) -> Result<Vec<crate::search::Highlighted<ProjectFileWithProject>>> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
@@ -924,19 +2119,16 @@ pub fn search_files_global(keyword: &str) -> Result<Vec<ProjectFileWithProject>>
     
     let search_pattern = format!("%{}%", keyword);
     
-    let mut stmt = conn.prepare(
-        "SELECT f.id, f.project_id, f.original_name, f.stored_name, f.file_path, f.file_size, f.file_type, f.version, f.created_at, f.updated_at, p.name
-         FROM project_files f
-         INNER JOIN projects p ON f.project_id = p.id
-         WHERE f.original_name LIKE ?1
-         ORDER BY 
-           CASE 
-             WHEN f.original_name = ?2 THEN 1
-             WHEN f.original_name LIKE ?3 THEN 2
-             ELSE 3
-           END,
-           f.updated_at DESC"
-    )?;
+    let query = ListQuery::new(
+            "SELECT f.id, f.project_id, f.original_name, f.stored_name, f.file_path, f.file_size, f.file_type, f.version, f.created_at, f.updated_at, f.event_id, p.name
+             FROM project_files f
+             INNER JOIN projects p ON f.project_id = p.id"
+        )
+        .condition("f.original_name LIKE ?1")
+        .archived("p.archived", include_archived)
+        .order_by("CASE WHEN f.original_name = ?2 THEN 1 WHEN f.original_name LIKE ?3 THEN 2 ELSE 3 END")
+        .order_by("f.updated_at DESC");
+    let mut stmt = conn.prepare_cached(&query.sql())?;
     
     let start_pattern = format!("{}%", keyword);
     
@@ -951,20 +2143,34 @@ pub fn search_files_global(keyword: &str) -> Result<Vec<ProjectFileWithProject>>
                 file_size: row.get(5)?,
                 file_type: row.get(6)?,
                 version: row.get(7)?,
+                event_id: row.get(10)?,
                 created_at: row.get(8)?,
                 updated_at: row.get(9)?,
             },
-            project_name: row.get(10)?,
+            project_name: row.get(11)?,
         })
     })?;
     
     let mut files = Vec::new();
     for result in results {
-        files.push(result?);
+        if is_cancelled() {
+            return Err(search_cancelled());
+        }
+        let item = result?;
+        let highlights = crate::search::highlights(&[("original_name", Some(item.file.original_name.as_str()))], keyword);
+        files.push(crate::search::Highlighted { item, highlights });
     }
     Ok(files)
 }
 
+// 搜索被取消时的错误
+fn search_cancelled() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_INTERRUPT),
+        Some("搜索已取消".to_string())
+    )
+}
+
 // 删除文件记录
 pub fn delete_project_file(file_id: i32) -> Result<()> {
     let db = get_db()?;
@@ -986,7 +2192,7 @@ pub fn get_file_by_id(file_id: i32) -> Result<Option<ProjectFile>> {
     ))?;
     
     let result = conn.query_row(
-        "SELECT id, project_id, original_name, stored_name, file_path, file_size, file_type, version, created_at, updated_at
+        "SELECT id, project_id, original_name, stored_name, file_path, file_size, file_type, version, created_at, updated_at, event_id
          FROM project_files WHERE id = ?1",
         [file_id],
         |row| {
@@ -999,6 +2205,7 @@ pub fn get_file_by_id(file_id: i32) -> Result<Option<ProjectFile>> {
                 file_size: row.get(5)?,
                 file_type: row.get(6)?,
                 version: row.get(7)?,
+                event_id: row.get(10)?,
                 created_at: row.get(8)?,
                 updated_at: row.get(9)?,
             })
@@ -1012,6 +2219,53 @@ pub fn get_file_by_id(file_id: i32) -> Result<Option<ProjectFile>> {
     }
 }
 
+// 将文件挂到事件上作为附件
+pub fn attach_file_to_event(file_id: i64, event_id: i64) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE project_files SET event_id = ?1 WHERE id = ?2",
+        rusqlite::params![event_id, file_id],
+    )?;
+    Ok(())
+}
+
+// 获取时间范围内新增/更新的文件（按项目、上传时间排序，用于总结）
+pub fn fetch_files_in_period(conn: &Connection, start_datetime: &str, end_datetime: &str) -> Result<Vec<ProjectFileWithProject>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT f.id, f.project_id, f.original_name, f.stored_name, f.file_path, f.file_size, f.file_type, f.version, f.created_at, f.updated_at, f.event_id, p.name
+         FROM project_files f
+         INNER JOIN projects p ON f.project_id = p.id
+         WHERE f.created_at >= ?1 AND f.created_at <= ?2
+         ORDER BY p.name, f.created_at ASC"
+    )?;
+    
+    let files: Vec<ProjectFileWithProject> = stmt.query_map(rusqlite::params![start_datetime, end_datetime], |row| {
+        Ok(ProjectFileWithProject {
+            file: ProjectFile {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                original_name: row.get(2)?,
+                stored_name: row.get(3)?,
+                file_path: row.get(4)?,
+                file_size: row.get(5)?,
+                file_type: row.get(6)?,
+                version: row.get(7)?,
+                event_id: row.get(10)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            },
+            project_name: row.get(11)?,
+        })
+    })?.filter_map(|r| r.ok()).collect();
+    
+    Ok(files)
+}
+
 // ==================== 项目活动管理相关 ====================
 
 // 项目活动结构体
@@ -1022,19 +2276,82 @@ pub struct ProjectActivity {
     pub name: String,
     pub description: Option<String>,
     pub estimated_completion_date: Option<String>,
-    pub status: String,  // 待分配、未激活、进行中、已暂停、已完成
+    pub status: String,  // 待分配、未激活、进行中、已暂停、待审核、已完成
     pub activated_at: Option<String>,
     pub paused_at: Option<String>,
     pub completed_at: Option<String>,
+    pub template_id: Option<i32>,  // 由模板生成时的来源模板
+    pub estimated_hours: Option<f64>,  // 预估工时
+    pub actual_hours: f64,             // 实际工时（工时记录累计）
+    pub paused_seconds: i64,           // 已结束的暂停累计时长（秒），不含当前仍在进行的暂停
     pub created_at: String,
     pub updated_at: String,
 }
 
+// project_activities 表查询列（表别名 a，与 activity_from_row 对应）
+const ACTIVITY_COLUMNS: &str = "a.id, a.project_id, a.name, a.description, a.estimated_completion_date, a.status, a.activated_at, a.paused_at, a.completed_at, a.created_at, a.updated_at, a.template_id, a.estimated_hours, \
+    (SELECT COALESCE(SUM(t.hours), 0) FROM activity_time_entries t WHERE t.activity_id = a.id), COALESCE(a.paused_seconds, 0)";
+
+// 将查询行转换为活动结构体
+fn activity_from_row(row: &rusqlite::Row) -> Result<ProjectActivity> {
+    Ok(ProjectActivity {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        estimated_completion_date: row.get(4)?,
+        status: row.get(5)?,
+        activated_at: row.get(6)?,
+        paused_at: row.get(7)?,
+        completed_at: row.get(8)?,
+        template_id: row.get(11)?,
+        estimated_hours: row.get(12)?,
+        actual_hours: row.get(13)?,
+        paused_seconds: row.get(14)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
 // 带负责人信息的活动（用于展示）
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ActivityWithDetails {
     pub activity: ProjectActivity,
     pub assignees: Vec<Contact>,
+    pub hours_variance: Option<f64>,  // 实际工时 - 预估工时（未预估时为空）
+    pub active_duration: Option<i64>, // 激活以来扣除暂停后的有效时长（秒），未激活时为空
+}
+
+impl ActivityWithDetails {
+    pub fn new(activity: ProjectActivity, assignees: Vec<Contact>) -> Self {
+        let hours_variance = activity.estimated_hours.map(|estimated| activity.actual_hours - estimated);
+        let active_duration = active_duration_seconds(&activity, chrono::Local::now().naive_local());
+        ActivityWithDetails { activity, assignees, hours_variance, active_duration }
+    }
+}
+
+fn parse_activity_time(value: &Option<String>) -> Option<chrono::NaiveDateTime> {
+    value.as_deref().and_then(|v| chrono::NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").ok())
+}
+
+// 活动的累计暂停时长（秒）：已结束的暂停加上仍在进行的暂停（算到 now）
+pub fn paused_duration_seconds(activity: &ProjectActivity, now: chrono::NaiveDateTime) -> i64 {
+    let open_pause = match (activity.status.as_str(), parse_activity_time(&activity.paused_at)) {
+        ("已暂停", Some(paused_at)) => (now - paused_at).num_seconds().max(0),
+        _ => 0,
+    };
+    activity.paused_seconds + open_pause
+}
+
+// 活动的有效时长（秒）：激活到完成（暂停中为暂停开始，进行中为现在），扣除累计暂停时长
+pub fn active_duration_seconds(activity: &ProjectActivity, now: chrono::NaiveDateTime) -> Option<i64> {
+    let start = parse_activity_time(&activity.activated_at)?;
+    let end = match activity.status.as_str() {
+        "已完成" => parse_activity_time(&activity.completed_at).unwrap_or(now),
+        "已暂停" => parse_activity_time(&activity.paused_at).unwrap_or(now),
+        _ => now,
+    };
+    Some(((end - start).num_seconds() - activity.paused_seconds).max(0))
 }
 
 // 插入新活动
@@ -1043,22 +2360,118 @@ pub fn insert_activity(
     name: &str,
     description: Option<&str>,
     estimated_completion_date: Option<&str>,
+    estimated_hours: Option<f64>,
 ) -> Result<i64> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
+    insert_activity_with(&conn, project_id, name, description, estimated_completion_date, estimated_hours)
+}
+
+pub fn insert_activity_with(
+    conn: &Connection,
+    project_id: i32,
+    name: &str,
+    description: Option<&str>,
+    estimated_completion_date: Option<&str>,
+    estimated_hours: Option<f64>,
+) -> Result<i64> {
+    validate_estimated_hours(estimated_hours)?;
     
     conn.execute(
-        "INSERT INTO project_activities (project_id, name, description, estimated_completion_date, status) 
-         VALUES (?1, ?2, ?3, ?4, '待分配')",
-        rusqlite::params![project_id, name, description, estimated_completion_date],
+        "INSERT INTO project_activities (project_id, name, description, estimated_completion_date, estimated_hours, status) 
+         VALUES (?1, ?2, ?3, ?4, ?5, '待分配')",
+        rusqlite::params![project_id, name, description, estimated_completion_date, estimated_hours],
     )?;
     
     Ok(conn.last_insert_rowid())
 }
 
+// ==================== 相似活动检测 ====================
+
+// 相似度达到此值视为可能重复（与 pg_trgm 的默认阈值相近）
+const SIMILAR_ACTIVITY_THRESHOLD: f64 = 0.4;
+// 最多返回的相似活动数
+const MAX_SIMILAR_ACTIVITIES: usize = 10;
+
+// 名称相近的已有活动
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarActivity {
+    pub activity_id: i32,
+    pub project_id: i32,
+    pub project_name: String,
+    pub name: String,
+    pub status: String,
+    pub similarity: f64,      // 0-1，1 表示规范化后完全相同
+    pub same_project: bool,   // 与新活动在同一项目中
+}
+
+// 名称的三字组集合：转小写、去掉空白和标点，首尾补空格（"设计稿" -> "  设", " 设计", "设计稿", "计稿 "）
+fn name_trigrams(name: &str) -> std::collections::HashSet<String> {
+    let chars: Vec<char> = "  ".chars()
+        .chain(name.to_lowercase().chars().filter(|c| c.is_alphanumeric()))
+        .chain(std::iter::once(' '))
+        .collect();
+    if chars.len() <= 3 {
+        return std::collections::HashSet::new();
+    }
+    chars.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+// 两个名称的相似度（三字组的 Jaccard 系数）
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (name_trigrams(a), name_trigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+// 查找名称相近的活动（指定项目时同项目的排在前面），用于创建活动前提示可能重复
+pub fn find_similar_activities(name: &str, project_id: Option<i32>) -> Result<Vec<SimilarActivity>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    find_similar_activities_with(&conn, name, project_id)
+}
+
+pub fn find_similar_activities_with(conn: &Connection, name: &str, project_id: Option<i32>) -> Result<Vec<SimilarActivity>> {
+    if name_trigrams(name).is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare_cached(
+        "SELECT a.id, a.project_id, p.name, a.name, a.status FROM project_activities a
+         INNER JOIN projects p ON p.id = a.project_id"
+    )?;
+    let mut similar: Vec<SimilarActivity> = stmt.query_map([], |row| Ok((
+        row.get::<_, i32>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?,
+    )))?
+    .filter_map(|r| r.ok())
+    .filter_map(|(activity_id, activity_project_id, project_name, activity_name, status)| {
+        let similarity = name_similarity(name, &activity_name);
+        (similarity >= SIMILAR_ACTIVITY_THRESHOLD).then(|| SimilarActivity {
+            activity_id,
+            project_id: activity_project_id,
+            project_name,
+            name: activity_name,
+            status,
+            similarity: (similarity * 100.0).round() / 100.0,
+            same_project: project_id == Some(activity_project_id),
+        })
+    })
+    .collect();
+    similar.sort_by(|a, b| b.same_project.cmp(&a.same_project)
+        .then(b.similarity.total_cmp(&a.similarity))
+        .then(a.activity_id.cmp(&b.activity_id)));
+    similar.truncate(MAX_SIMILAR_ACTIVITIES);
+    Ok(similar)
+}
+
 // 记录活动创建日志
 pub fn log_activity_creation(
     activity_id: i64,
@@ -1115,9 +2528,9 @@ pub fn log_activity_status_change(
     }
     
     conn.execute(
-        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description) 
-         VALUES ('update', 'activity', ?1, ?2, ?3)",
-        rusqlite::params![activity_id, activity_name, desc],
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description) 
+         VALUES ('update', 'activity', ?1, ?2, (SELECT project_id FROM project_activities WHERE id = ?1), ?3, ?4)",
+        rusqlite::params![activity_id, activity_name, project_name, desc],
     )?;
     
     Ok(())
@@ -1130,7 +2543,10 @@ pub fn assign_contacts_to_activity(activity_id: i64, contact_ids: &[i32]) -> Res
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
+    assign_contacts_to_activity_with(&conn, activity_id, contact_ids)
+}
+
+pub fn assign_contacts_to_activity_with(conn: &Connection, activity_id: i64, contact_ids: &[i32]) -> Result<()> {
     for contact_id in contact_ids {
         conn.execute(
             "INSERT OR IGNORE INTO activities_contacts (activity_id, contact_id) VALUES (?1, ?2)",
@@ -1180,21 +2596,56 @@ pub fn unassign_contact_from_activity(activity_id: i32, contact_id: i32) -> Resu
     Ok(())
 }
 
-// 激活活动
+// 写入活动状态变更记录
+fn record_activity_status(conn: &Connection, activity_id: i32, from_status: &str, to_status: &str, changed_at: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO activity_status_history (activity_id, from_status, to_status, changed_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![activity_id, from_status, to_status, changed_at],
+    )?;
+    Ok(())
+}
+
+// 结束暂停（恢复、完成或提交审核时）：把本次暂停的时长累加到 paused_seconds
+fn close_activity_pause(conn: &Connection, activity_id: i32, now: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE project_activities
+         SET paused_seconds = COALESCE(paused_seconds, 0) + MAX(0, CAST(strftime('%s', ?1) AS INTEGER) - CAST(strftime('%s', paused_at) AS INTEGER))
+         WHERE id = ?2 AND status = '已暂停' AND paused_at IS NOT NULL",
+        rusqlite::params![now, activity_id],
+    )?;
+    Ok(())
+}
+
+// 活动当前状态（不存在时为 None）
+fn current_activity_status(conn: &Connection, activity_id: i32) -> Result<Option<String>> {
+    match conn.query_row("SELECT status FROM project_activities WHERE id = ?1", [activity_id], |row| row.get(0)) {
+        Ok(status) => Ok(Some(status)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 激活活动（从暂停恢复时保留首次激活时间）
 pub fn activate_activity(activity_id: i32) -> Result<()> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    
-    conn.execute(
-        "UPDATE project_activities SET status = '进行中', activated_at = ?1 WHERE id = ?2 AND status IN ('未激活', '已暂停')",
+    activate_activity_with(&conn, activity_id, &chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+pub fn activate_activity_with(conn: &Connection, activity_id: i32, now: &str) -> Result<()> {
+    let from_status = current_activity_status(conn, activity_id)?;
+    close_activity_pause(conn, activity_id, now)?;
+    let updated = conn.execute(
+        "UPDATE project_activities SET status = '进行中', activated_at = COALESCE(activated_at, ?1)
+         WHERE id = ?2 AND status IN ('未激活', '已暂停')",
         rusqlite::params![now, activity_id],
     )?;
-    
+    if let (true, Some(from_status)) = (updated > 0, from_status) {
+        record_activity_status(conn, activity_id, &from_status, "进行中", now)?;
+    }
     Ok(())
 }
 
@@ -1205,35 +2656,161 @@ pub fn pause_activity(activity_id: i32) -> Result<()> {
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    
-    conn.execute(
+    pause_activity_with(&conn, activity_id, &chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+pub fn pause_activity_with(conn: &Connection, activity_id: i32, now: &str) -> Result<()> {
+    let updated = conn.execute(
         "UPDATE project_activities SET status = '已暂停', paused_at = ?1 WHERE id = ?2 AND status = '进行中'",
         rusqlite::params![now, activity_id],
     )?;
-    
+    if updated > 0 {
+        record_activity_status(conn, activity_id, "进行中", "已暂停", now)?;
+    }
     Ok(())
 }
 
-// 完成活动
+// 完成活动（待审核的活动需通过审核完成）
 pub fn complete_activity(activity_id: i32) -> Result<()> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    complete_activity_with(&conn, activity_id, &chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+pub fn complete_activity_with(conn: &Connection, activity_id: i32, now: &str) -> Result<()> {
+    let from_status = current_activity_status(conn, activity_id)?;
+    // 待审核的活动只能通过审核完成
+    if from_status.as_deref() == Some("待审核") {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("活动正在审核中，请通过审核完成".to_string())
+        ));
+    }
+    close_activity_pause(conn, activity_id, now)?;
     
     conn.execute(
         "UPDATE project_activities SET status = '已完成', completed_at = ?1 WHERE id = ?2",
         rusqlite::params![now, activity_id],
     )?;
+    if let Some(from_status) = from_status.filter(|s| s != "已完成") {
+        record_activity_status(conn, activity_id, &from_status, "已完成", now)?;
+    }
     
     Ok(())
 }
 
+// 活动审核记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityReview {
+    pub id: i32,
+    pub activity_id: i32,
+    pub action: String,                   // submitted / approved / rejected
+    pub reviewer_contact_id: Option<i32>,
+    pub reviewer_name: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+// 变更活动审核状态并写入审核记录；活动不在 from_statuses 状态时返回错误
+fn transition_activity_review(
+    activity_id: i32,
+    from_statuses: &[&str],
+    to_status: &str,
+    action: &str,
+    reviewer_contact_id: Option<i32>,
+    note: Option<&str>,
+) -> Result<()> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let tx = conn.transaction()?;
+    
+    let status: String = tx.query_row(
+        "SELECT status FROM project_activities WHERE id = ?1",
+        [activity_id],
+        |row| row.get(0)
+    )?;
+    if !from_statuses.contains(&status.as_str()) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("活动当前状态为「{}」，无法执行此操作", status))
+        ));
+    }
+    
+    close_activity_pause(&tx, activity_id, &now)?;
+    if to_status == "已完成" {
+        tx.execute(
+            "UPDATE project_activities SET status = ?1, completed_at = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+            rusqlite::params![to_status, now, activity_id],
+        )?;
+    } else {
+        tx.execute(
+            "UPDATE project_activities SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            rusqlite::params![to_status, activity_id],
+        )?;
+    }
+    tx.execute(
+        "INSERT INTO activity_reviews (activity_id, action, reviewer_contact_id, note, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![activity_id, action, reviewer_contact_id, note, now],
+    )?;
+    record_activity_status(&tx, activity_id, &status, to_status, &now)?;
+    
+    tx.commit()
+}
+
+// 提交活动审核（进行中/暂停/未激活 -> 待审核）
+pub fn submit_activity_for_review(activity_id: i32, reviewer_contact_id: Option<i32>, note: Option<&str>) -> Result<()> {
+    transition_activity_review(activity_id, &["未激活", "进行中", "已暂停"], "待审核", "submitted", reviewer_contact_id, note)
+}
+
+// 审核通过（待审核 -> 已完成）
+pub fn approve_activity(activity_id: i32, reviewer_contact_id: i32, note: Option<&str>) -> Result<()> {
+    transition_activity_review(activity_id, &["待审核"], "已完成", "approved", Some(reviewer_contact_id), note)
+}
+
+// 审核退回（待审核 -> 进行中）
+pub fn reject_activity(activity_id: i32, reviewer_contact_id: i32, note: Option<&str>) -> Result<()> {
+    transition_activity_review(activity_id, &["待审核"], "进行中", "rejected", Some(reviewer_contact_id), note)
+}
+
+// 获取活动的审核记录（按时间顺序）
+pub fn fetch_activity_reviews(activity_id: i32) -> Result<Vec<ActivityReview>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT r.id, r.activity_id, r.action, r.reviewer_contact_id, c.name, r.note, r.created_at
+         FROM activity_reviews r
+         LEFT JOIN contacts c ON c.id = r.reviewer_contact_id
+         WHERE r.activity_id = ?1
+         ORDER BY r.created_at, r.id"
+    )?;
+    let reviews = stmt.query_map([activity_id], |row| {
+        Ok(ActivityReview {
+            id: row.get(0)?,
+            activity_id: row.get(1)?,
+            action: row.get(2)?,
+            reviewer_contact_id: row.get(3)?,
+            reviewer_name: row.get(4)?,
+            note: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    Ok(reviews)
+}
+
 // 获取活动的负责人
 pub fn fetch_assignees_for_activity(activity_id: i32) -> Result<Vec<Contact>> {
     let db = get_db()?;
@@ -1242,29 +2819,16 @@ pub fn fetch_assignees_for_activity(activity_id: i32) -> Result<Vec<Contact>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
-        "SELECT c.id, c.name, c.title, c.notes, c.tags, c.phone, c.email, c.address, c.company, c.created_at, c.updated_at
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM contacts c
          INNER JOIN activities_contacts ac ON c.id = ac.contact_id
          WHERE ac.activity_id = ?1
-         ORDER BY ac.assigned_at"
-    )?;
+         ORDER BY ac.assigned_at",
+        CONTACT_COLUMNS
+    ))?;
     
-    let results = stmt.query_map([activity_id], |row| {
-        Ok(Contact {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            title: row.get(2)?,
-            notes: row.get(3)?,
-            tags: row.get(4)?,
-            phone: row.get(5)?,
-            email: row.get(6)?,
-            address: row.get(7)?,
-            company: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
-        })
-    })?;
+    let results = stmt.query_map([activity_id], contact_from_row)?;
     
     let mut contacts = Vec::new();
     for result in results {
@@ -1282,28 +2846,15 @@ pub fn fetch_activities_for_project(project_id: i32) -> Result<Vec<ActivityWithD
             Some(format!("锁失败: {}", e))
         ))?;
         
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, name, description, estimated_completion_date, status, activated_at, paused_at, completed_at, created_at, updated_at
-             FROM project_activities
-             WHERE project_id = ?1
-             ORDER BY created_at DESC"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}
+             FROM project_activities a
+             WHERE a.project_id = ?1
+             ORDER BY a.created_at DESC",
+            ACTIVITY_COLUMNS
+        ))?;
         
-        let activities: Vec<ProjectActivity> = stmt.query_map([project_id], |row| {
-            Ok(ProjectActivity {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                estimated_completion_date: row.get(4)?,
-                status: row.get(5)?,
-                activated_at: row.get(6)?,
-                paused_at: row.get(7)?,
-                completed_at: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
+        let activities: Vec<ProjectActivity> = stmt.query_map([project_id], activity_from_row)?.filter_map(|r| r.ok()).collect();
         
         activities
     };
@@ -1311,10 +2862,7 @@ pub fn fetch_activities_for_project(project_id: i32) -> Result<Vec<ActivityWithD
     let mut results = Vec::new();
     for activity in activities {
         let assignees = fetch_assignees_for_activity(activity.id)?;
-        results.push(ActivityWithDetails {
-            activity,
-            assignees,
-        });
+        results.push(ActivityWithDetails::new(activity, assignees));
     }
     
     Ok(results)
@@ -1375,10 +2923,26 @@ pub fn update_contact(
         Some(format!("锁失败: {}", e))
     ))?;
     
-    conn.execute(
-        "UPDATE contacts SET name = ?1, title = ?2, notes = ?3, tags = ?4, phone = ?5, email = ?6, address = ?7, company = ?8, updated_at = CURRENT_TIMESTAMP WHERE id = ?9",
-        rusqlite::params![name, title, notes, tags, phone, email, address, company, contact_id],
-    )?;
+    let owned = |value: Option<&str>| value.map(str::to_string);
+    update_contact_with(&conn, contact_id, &NewContact {
+        name: name.to_string(),
+        title: owned(title),
+        notes: owned(notes),
+        tags: owned(tags),
+        phone: owned(phone),
+        email: owned(email),
+        address: owned(address),
+        company: owned(company),
+    })
+}
+
+pub fn update_contact_with(conn: &Connection, contact_id: i32, contact: &NewContact) -> Result<()> {
+    let phone = contact.phone.as_deref();
+    let address = contact.address.as_deref();
+    conn.execute(
+        "UPDATE contacts SET name = ?1, title = ?2, notes = ?3, tags = ?4, phone = ?5, email = ?6, address = ?7, company = ?8, phone_normalized = ?9, city = ?10, updated_at = CURRENT_TIMESTAMP WHERE id = ?11",
+        rusqlite::params![contact.name, contact.title, contact.notes, contact.tags, phone, contact.email, address, contact.company, normalized_phone_json(phone), address.and_then(parse_city), contact_id],
+    )?;
     
     Ok(())
 }
@@ -1399,12 +2963,34 @@ pub fn update_event(
         Some(format!("锁失败: {}", e))
     ))?;
     
+    let old_project_id: Option<i32> = conn.query_row(
+        "SELECT project_id FROM events WHERE id = ?1",
+        [event_id],
+        |row| row.get(0)
+    ).unwrap_or(None);
+    
     // 如果提醒时间改变，重置 reminder_triggered
     conn.execute(
-        "UPDATE events SET title = ?1, description = ?2, event_date = ?3, project_id = ?4, event_type = ?5, reminder_time = ?6, reminder_triggered = 0, updated_at = CURRENT_TIMESTAMP WHERE id = ?7",
+        "UPDATE events SET title = ?1, description = ?2, event_date = ?3, project_id = ?4, event_type = ?5, reminder_time = ?6, reminder_triggered = 0, reminder_acknowledged_at = NULL, reminder_last_notified_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = ?7",
         rusqlite::params![title, description, event_date, project_id, event_type, reminder_time, event_id],
     )?;
     
+    // 主项目变更时同步关联表（其他关联项目保持不变）
+    if old_project_id != project_id {
+        if let Some(old_pid) = old_project_id {
+            conn.execute(
+                "DELETE FROM events_projects WHERE event_id = ?1 AND project_id = ?2",
+                rusqlite::params![event_id, old_pid],
+            )?;
+        }
+        if let Some(pid) = project_id {
+            conn.execute(
+                "INSERT OR IGNORE INTO events_projects (event_id, project_id) VALUES (?1, ?2)",
+                rusqlite::params![event_id, pid],
+            )?;
+        }
+    }
+    
     Ok(())
 }
 
@@ -1442,8 +3028,8 @@ pub fn delete_activity(activity_id: i32) -> Result<()> {
     Ok(())
 }
 
-// 获取所有项目的所有活动（用于导出）
-pub fn fetch_all_activities_with_project() -> Result<Vec<(ActivityWithDetails, String)>> {
+// 获取所有项目的所有活动（用于导出，include_archived 为 false 时不含已归档项目的活动）
+pub fn fetch_all_activities_with_project(include_archived: bool) -> Result<Vec<(ActivityWithDetails, String)>> {
     let (activities, project_names) = {
         let db = get_db()?;
         let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
@@ -1453,7 +3039,7 @@ pub fn fetch_all_activities_with_project() -> Result<Vec<(ActivityWithDetails, S
         
         // 获取项目名称映射
         let mut project_names: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
-        let mut p_stmt = conn.prepare("SELECT id, name FROM projects")?;
+        let mut p_stmt = conn.prepare_cached("SELECT id, name FROM projects")?;
         let projects = p_stmt.query_map([], |row| {
             Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
         })?;
@@ -1463,27 +3049,11 @@ pub fn fetch_all_activities_with_project() -> Result<Vec<(ActivityWithDetails, S
             }
         }
         
-        let mut stmt = conn.prepare(
-            "SELECT id, project_id, name, description, estimated_completion_date, status, activated_at, paused_at, completed_at, created_at, updated_at
-             FROM project_activities
-             ORDER BY project_id, created_at DESC"
-        )?;
-        
-        let activities: Vec<ProjectActivity> = stmt.query_map([], |row| {
-            Ok(ProjectActivity {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                estimated_completion_date: row.get(4)?,
-                status: row.get(5)?,
-                activated_at: row.get(6)?,
-                paused_at: row.get(7)?,
-                completed_at: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
+        let activities = ListQuery::new(format!("SELECT {} FROM project_activities a", ACTIVITY_COLUMNS))
+            .archived_project("a.project_id", include_archived)
+            .order_by("a.project_id")
+            .order_by("a.created_at DESC")
+            .fetch(&conn, activity_from_row)?;
         
         (activities, project_names)
     };
@@ -1492,10 +3062,7 @@ pub fn fetch_all_activities_with_project() -> Result<Vec<(ActivityWithDetails, S
     for activity in activities {
         let assignees = fetch_assignees_for_activity(activity.id)?;
         let project_name = project_names.get(&activity.project_id).cloned().unwrap_or_default();
-        results.push((ActivityWithDetails {
-            activity,
-            assignees,
-        }, project_name));
+        results.push((ActivityWithDetails::new(activity, assignees), project_name));
     }
     
     Ok(results)
@@ -1505,68 +3072,38 @@ pub fn fetch_all_activities_with_project() -> Result<Vec<(ActivityWithDetails, S
 
 // 获取待触发的提醒（当前时间前后1分钟内且未触发的）
 pub fn fetch_pending_reminders() -> Result<Vec<EventWithDetails>> {
-    let now = chrono::Local::now();
-    let one_minute_ago = (now - chrono::Duration::minutes(1)).format("%Y-%m-%d %H:%M:%S").to_string();
-    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
-    
-    let (events, project_names) = {
+    let (events, ctx) = {
         let db = get_db()?;
         let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(1),
             Some(format!("锁失败: {}", e))
         ))?;
         
-        // 获取项目名称映射
-        let mut project_names: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
-        let mut p_stmt = conn.prepare("SELECT id, name FROM projects")?;
-        let projects = p_stmt.query_map([], |row| {
-            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
-        })?;
-        for p in projects {
-            if let Ok((id, name)) = p {
-                project_names.insert(id, name);
-            }
-        }
-        
-        let mut stmt = conn.prepare(
-            "SELECT e.id, e.title, e.description, e.event_date, e.project_id, e.event_type, e.reminder_time, e.reminder_triggered, e.created_at, e.updated_at
-             FROM events e
-             WHERE e.reminder_time IS NOT NULL 
-             AND e.reminder_time <= ?1 
-             AND e.reminder_time >= ?2
-             AND (e.reminder_triggered = 0 OR e.reminder_triggered IS NULL)"
-        )?;
-        
-        let events: Vec<Event> = stmt.query_map(rusqlite::params![now_str, one_minute_ago], |row| {
-            Ok(Event {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get(2)?,
-                event_date: row.get(3)?,
-                project_id: row.get(4)?,
-                event_type: row.get(5)?,
-                reminder_time: row.get(6)?,
-                reminder_triggered: row.get::<_, i32>(7).unwrap_or(0) != 0,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })?.filter_map(|r| r.ok()).collect();
-        
-        (events, project_names)
+        let events = fetch_pending_reminder_events_with(&conn, chrono::Local::now().naive_local())?;
+        (events, EventDisplayContext::load(&conn)?)
     };
     
-    let mut results = Vec::new();
-    for event in events {
-        let contacts = fetch_contacts_for_event(event.id)?;
-        let project_name = event.project_id.and_then(|pid| project_names.get(&pid).cloned());
-        results.push(EventWithDetails {
-            event,
-            contacts,
-            project_name,
-        });
-    }
+    assemble_event_details(events, &ctx)
+}
+
+// 最近一分钟内到期、尚未触发的普通提醒
+pub fn fetch_pending_reminder_events_with(conn: &Connection, now: chrono::NaiveDateTime) -> Result<Vec<Event>> {
+    let one_minute_ago = (now - chrono::Duration::minutes(1)).format("%Y-%m-%d %H:%M:%S").to_string();
+    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
     
-    Ok(results)
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM events e
+         WHERE e.reminder_time IS NOT NULL 
+         AND e.reminder_time <= ?1 
+         AND e.reminder_time >= ?2
+         AND (e.reminder_triggered = 0 OR e.reminder_triggered IS NULL)
+         AND COALESCE(e.reminder_insistent, 0) = 0",
+        EVENT_COLUMNS
+    ))?;
+    
+    let events: Vec<Event> = stmt.query_map(rusqlite::params![now_str, one_minute_ago], event_from_row)?.filter_map(|r| r.ok()).collect();
+    Ok(events)
 }
 
 // 标记提醒已触发
@@ -1577,9 +3114,10 @@ pub fn mark_reminder_triggered(event_id: i32) -> Result<()> {
         Some(format!("锁失败: {}", e))
     ))?;
     
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     conn.execute(
-        "UPDATE events SET reminder_triggered = 1 WHERE id = ?1",
-        [event_id],
+        "UPDATE events SET reminder_triggered = 1, reminder_last_notified_at = ?1 WHERE id = ?2",
+        rusqlite::params![now, event_id],
     )?;
     
     Ok(())
@@ -1597,7 +3135,7 @@ pub fn fetch_today_reminder_event_ids() -> Result<Vec<i32>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare_cached(
         "SELECT id FROM events 
          WHERE reminder_time IS NOT NULL 
          AND reminder_time >= ?1 
@@ -1620,13 +3158,279 @@ pub fn update_event_reminder(event_id: i32, reminder_time: Option<&str>) -> Resu
     ))?;
     
     conn.execute(
-        "UPDATE events SET reminder_time = ?1, reminder_triggered = 0 WHERE id = ?2",
+        "UPDATE events SET reminder_time = ?1, reminder_triggered = 0, reminder_acknowledged_at = NULL, reminder_last_notified_at = NULL WHERE id = ?2",
         rusqlite::params![reminder_time, event_id],
     )?;
     
     Ok(())
 }
 
+// 批量改期的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RescheduleResult {
+    pub events_shifted: usize,     // 事件日期被移动的事件数
+    pub reminders_shifted: usize,  // 提醒时间被移动的事件数
+}
+
+// 一次平移的最大天数（约 100 年），超出时报错
+const MAX_SHIFT_DAYS: i64 = 36_500;
+
+fn shift_out_of_range() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("平移量超出范围（最多 {} 天）", MAX_SHIFT_DAYS))
+    )
+}
+
+// 按原格式平移日期时间文本（YYYY-MM-DD，或带 HH:MM / HH:MM:SS、以空格或 T 分隔），无法解析时返回 None
+fn shift_datetime_text(value: &str, delta: chrono::Duration) -> Result<Option<String>> {
+    let value = value.trim();
+    // 纯日期只按整天平移
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let shifted = chrono::Duration::try_days(delta.num_days())
+            .and_then(|days| date.checked_add_signed(days))
+            .ok_or_else(shift_out_of_range)?;
+        return Ok(Some(shifted.format("%Y-%m-%d").to_string()));
+    }
+    let Some(dt) = parse_event_datetime(value) else {
+        return Ok(None);
+    };
+    let shifted = dt.checked_add_signed(delta).ok_or_else(shift_out_of_range)?;
+    // 保持原有的分隔符和秒的精度
+    let separator = if value.contains('T') { "T" } else { " " };
+    let time_format = if value.len() > 16 { "%H:%M:%S" } else { "%H:%M" };
+    Ok(Some(shifted.format(&format!("%Y-%m-%d{}{}", separator, time_format)).to_string()))
+}
+
+// 平移一个事件的提醒时间并重置提醒状态，返回是否有提醒被移动
+fn shift_event_reminder(conn: &Connection, event_id: i32, reminder_time: Option<&str>, delta: chrono::Duration) -> Result<bool> {
+    let Some(shifted) = reminder_time.map(|t| shift_datetime_text(t, delta)).transpose()?.flatten() else {
+        return Ok(false);
+    };
+    conn.execute(
+        "UPDATE events SET reminder_time = ?1, reminder_triggered = 0, reminder_acknowledged_at = NULL, reminder_last_notified_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![shifted, event_id],
+    )?;
+    Ok(true)
+}
+
+// 批量平移事件的提醒时间（delta_minutes 可为负），在一个事务中执行，任一事件不存在则全部回滚
+pub fn shift_reminders(event_ids: &[i32], delta_minutes: i64) -> Result<RescheduleResult> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    shift_reminders_with(&mut conn, event_ids, delta_minutes)
+}
+
+pub fn shift_reminders_with(conn: &mut Connection, event_ids: &[i32], delta_minutes: i64) -> Result<RescheduleResult> {
+    let delta = Some(delta_minutes)
+        .filter(|m| m.unsigned_abs() <= (MAX_SHIFT_DAYS * 24 * 60) as u64)
+        .and_then(chrono::Duration::try_minutes)
+        .ok_or_else(shift_out_of_range)?;
+    let tx = conn.transaction()?;
+    let mut result = RescheduleResult::default();
+    for &event_id in event_ids {
+        let reminder_time: Option<String> = tx.query_row(
+            "SELECT reminder_time FROM events WHERE id = ?1",
+            [event_id],
+            |row| row.get(0),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("事件不存在: {}", event_id))
+            ),
+            e => e,
+        })?;
+        if shift_event_reminder(&tx, event_id, reminder_time.as_deref(), delta)? {
+            result.reminders_shifted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(result)
+}
+
+// 项目整体顺延：将项目（主项目或关联项目）中 from_date 及之后的事件连同提醒一起平移 delta_days 天，在一个事务中执行
+pub fn reschedule_project_events(project_id: i32, from_date: &str, delta_days: i64) -> Result<RescheduleResult> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    reschedule_project_events_with(&mut conn, project_id, from_date, delta_days)
+}
+
+pub fn reschedule_project_events_with(conn: &mut Connection, project_id: i32, from_date: &str, delta_days: i64) -> Result<RescheduleResult> {
+    if chrono::NaiveDate::parse_from_str(from_date, "%Y-%m-%d").is_err() {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的日期: {}", from_date))
+        ));
+    }
+    let delta = Some(delta_days)
+        .filter(|d| d.unsigned_abs() <= MAX_SHIFT_DAYS as u64)
+        .and_then(chrono::Duration::try_days)
+        .ok_or_else(shift_out_of_range)?;
+    let tx = conn.transaction()?;
+    let events: Vec<(i32, String, Option<String>)> = {
+        let mut stmt = tx.prepare(
+            "SELECT e.id, e.event_date, e.reminder_time FROM events e
+             WHERE (e.project_id = ?1 OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?1))
+             AND substr(e.event_date, 1, 10) >= ?2
+             ORDER BY e.id"
+        )?;
+        let rows = stmt.query_map(rusqlite::params![project_id, from_date], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+    
+    let mut result = RescheduleResult::default();
+    for (event_id, event_date, reminder_time) in events {
+        if let Some(shifted) = shift_datetime_text(&event_date, delta)? {
+            tx.execute(
+                "UPDATE events SET event_date = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                rusqlite::params![shifted, event_id],
+            )?;
+            result.events_shifted += 1;
+        }
+        if shift_event_reminder(&tx, event_id, reminder_time.as_deref(), delta)? {
+            result.reminders_shifted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(result)
+}
+
+// 默认的持续提醒间隔（分钟）
+pub const DEFAULT_REMINDER_REPEAT_MINUTES: i32 = 5;
+
+// 设置事件的提醒模式（持续提醒及重复间隔）
+pub fn update_event_reminder_mode(event_id: i32, insistent: bool, repeat_minutes: Option<i32>) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let repeat_minutes = repeat_minutes.filter(|m| *m > 0);
+    conn.execute(
+        "UPDATE events SET reminder_insistent = ?1, reminder_repeat_minutes = ?2, reminder_acknowledged_at = NULL WHERE id = ?3",
+        rusqlite::params![if insistent { 1 } else { 0 }, repeat_minutes, event_id],
+    )?;
+    
+    Ok(())
+}
+
+// 获取需要（再次）通知的持续提醒：提醒时间已到、未确认，且距上次通知已超过重复间隔
+pub fn fetch_due_insistent_reminders() -> Result<Vec<EventWithDetails>> {
+    let (events, ctx) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let events = fetch_due_insistent_reminder_events_with(&conn, chrono::Local::now().naive_local())?;
+        (events, EventDisplayContext::load(&conn)?)
+    };
+    
+    assemble_event_details(events, &ctx)
+}
+
+// 已到期、未确认且距上次通知超过重复间隔的持续提醒
+pub fn fetch_due_insistent_reminder_events_with(conn: &Connection, now: chrono::NaiveDateTime) -> Result<Vec<Event>> {
+    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, e.reminder_last_notified_at
+         FROM events e
+         WHERE e.reminder_time IS NOT NULL
+         AND e.reminder_time <= ?1
+         AND e.reminder_insistent = 1
+         AND e.reminder_acknowledged_at IS NULL",
+        EVENT_COLUMNS
+    ))?;
+    
+    // reminder_last_notified_at 紧跟在 EVENT_COLUMNS 的 16 列之后
+    let candidates: Vec<(Event, Option<String>)> = stmt.query_map([&now_str], |row| {
+        Ok((event_from_row(row)?, row.get(16)?))
+    })?.filter_map(|r| r.ok()).collect();
+    
+    // 距上次通知是否已超过重复间隔
+    let events: Vec<Event> = candidates.into_iter().filter(|(event, last_notified)| {
+        let Some(last) = last_notified else {
+            return true;
+        };
+        let repeat = event.reminder_repeat_minutes.unwrap_or(DEFAULT_REMINDER_REPEAT_MINUTES) as i64;
+        match chrono::NaiveDateTime::parse_from_str(last, "%Y-%m-%d %H:%M:%S") {
+            Ok(last) => (now - last).num_minutes() >= repeat,
+            Err(_) => true,
+        }
+    }).map(|(event, _)| event).collect();
+    
+    Ok(events)
+}
+
+// 确认提醒，停止持续提醒
+pub fn acknowledge_reminder(event_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "UPDATE events SET reminder_acknowledged_at = ?1, reminder_triggered = 1 WHERE id = ?2",
+        rusqlite::params![now, event_id],
+    )?;
+    conn.execute(
+        "UPDATE reminder_inbox SET acknowledged_at = ?1 WHERE event_id = ?2 AND acknowledged_at IS NULL",
+        rusqlite::params![now, event_id],
+    )?;
+    
+    Ok(())
+}
+
+// 今日待处理数量（用于托盘和角标）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingCount {
+    pub today_reminders: i64,     // 今日尚未确认的提醒
+    pub overdue_activities: i64,  // 已过预计完成日期但未完成的活动
+    pub total: i64,
+}
+
+// 统计今日未处理的提醒和逾期活动
+pub fn fetch_pending_count() -> Result<PendingCount> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let today_start = format!("{} 00:00:00", today);
+    let today_end = format!("{} 23:59:59", today);
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let today_reminders: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM events
+         WHERE reminder_time IS NOT NULL
+         AND reminder_time >= ?1
+         AND reminder_time <= ?2
+         AND reminder_acknowledged_at IS NULL",
+        rusqlite::params![today_start, today_end],
+        |row| row.get(0)
+    )?;
+    
+    let overdue_activities: i64 = overdue_activity_counts(&conn, &today)?.values().sum();
+    
+    Ok(PendingCount {
+        today_reminders,
+        overdue_activities,
+        total: today_reminders + overdue_activities,
+    })
+}
+
 // ==================== 操作日志相关 ====================
 
 // 操作日志结构体
@@ -1646,66 +3450,41 @@ pub struct OperationLog {
     pub created_at: String,
 }
 
-// 插入操作日志
-#[allow(dead_code)]
-pub fn insert_operation_log(
-    operation_type: &str,
-    entity_type: &str,
-    entity_id: i32,
-    entity_name: &str,
-    old_value: Option<&str>,
-    new_value: Option<&str>,
-    related_entities: Option<&str>,
-    project_id: Option<i32>,
-    project_name: Option<&str>,
-    description: &str,
-) -> Result<i64> {
-    let db = get_db()?;
-    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-        rusqlite::ffi::Error::new(1),
-        Some(format!("锁失败: {}", e))
-    ))?;
-    
-    conn.execute(
-        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, old_value, new_value, related_entities, project_id, project_name, description) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        rusqlite::params![operation_type, entity_type, entity_id, entity_name, old_value, new_value, related_entities, project_id, project_name, description],
-    )?;
-    
-    Ok(conn.last_insert_rowid())
+// 操作日志查询的列（顺序与 operation_log_from_row 一致）
+const OPERATION_LOG_COLUMNS: &str = "id, operation_type, entity_type, entity_id, entity_name, old_value, new_value, related_entities, project_id, project_name, description, created_at";
+
+// 将查询行转换为操作日志结构体
+fn operation_log_from_row(row: &rusqlite::Row) -> Result<OperationLog> {
+    Ok(OperationLog {
+        id: row.get(0)?,
+        operation_type: row.get(1)?,
+        entity_type: row.get(2)?,
+        entity_id: row.get(3)?,
+        entity_name: row.get(4)?,
+        old_value: row.get(5)?,
+        new_value: row.get(6)?,
+        related_entities: row.get(7)?,
+        project_id: row.get(8)?,
+        project_name: row.get(9)?,
+        description: row.get(10)?,
+        created_at: row.get(11)?,
+    })
 }
 
 // 获取时间范围内的操作日志
-pub fn fetch_operation_logs(start_date: &str, end_date: &str) -> Result<Vec<OperationLog>> {
-    let db = get_db()?;
-    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-        rusqlite::ffi::Error::new(1),
-        Some(format!("锁失败: {}", e))
-    ))?;
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, operation_type, entity_type, entity_id, entity_name, old_value, new_value, related_entities, project_id, project_name, description, created_at
+pub fn fetch_operation_logs(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<OperationLog>> {
+    // 私密事件的日志不进入总结
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
          FROM operation_logs
          WHERE created_at >= ?1 AND created_at <= ?2
-         ORDER BY created_at ASC"
-    )?;
+         AND NOT (entity_type = 'event' AND entity_id IN (SELECT id FROM events WHERE is_private = 1))
+         ORDER BY created_at ASC",
+        OPERATION_LOG_COLUMNS
+    ))?;
     
-    let logs: Vec<OperationLog> = stmt.query_map(rusqlite::params![start_date, end_date], |row| {
-        Ok(OperationLog {
-            id: row.get(0)?,
-            operation_type: row.get(1)?,
-            entity_type: row.get(2)?,
-            entity_id: row.get(3)?,
-            entity_name: row.get(4)?,
-            old_value: row.get(5)?,
-            new_value: row.get(6)?,
-            related_entities: row.get(7)?,
-            project_id: row.get(8)?,
-            project_name: row.get(9)?,
-            description: row.get(10)?,
-            created_at: row.get(11)?,
-        })
-    })?.filter_map(|r| r.ok()).collect();
+    let logs: Vec<OperationLog> = stmt.query_map(rusqlite::params![start_date, end_date], operation_log_from_row)?
+        .filter_map(|r| r.ok()).collect();
     
     Ok(logs)
 }
@@ -1717,92 +3496,348 @@ pub fn fetch_operation_logs(start_date: &str, end_date: &str) -> Result<Vec<Oper
 pub struct Summary {
     pub id: i32,
     pub title: String,
-    pub summary_type: String,  // daily, weekly, monthly, yearly, custom
+    pub summary_type: String,  // daily, weekly, monthly, quarterly, yearly, custom, briefing（每日简报）, retrospective（项目复盘）
     pub start_date: String,
     pub end_date: String,
     pub content: String,
     pub statistics: Option<String>,
     pub is_auto_generated: bool,
+    pub tags: Option<String>,  // 逗号分隔的标签
     pub created_at: String,
 }
 
-// 生成总结
-pub fn generate_summary(
-    summary_type: &str,
-    start_date: &str,
-    end_date: &str,
-    is_auto: bool,
-) -> Result<Summary> {
-    // 获取时间范围内的操作日志
+// summaries 表查询列（与 summary_from_row 对应）
+const SUMMARY_COLUMNS: &str = "id, title, summary_type, start_date, end_date, content, statistics, is_auto_generated, created_at, tags";
+
+// 将查询行转换为总结结构体
+fn summary_from_row(row: &rusqlite::Row) -> Result<Summary> {
+    Ok(Summary {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        summary_type: row.get(2)?,
+        start_date: row.get(3)?,
+        end_date: row.get(4)?,
+        content: row.get(5)?,
+        statistics: row.get(6)?,
+        is_auto_generated: row.get::<_, i32>(7).unwrap_or(0) != 0,
+        tags: row.get(9)?,
+        created_at: row.get(8)?,
+    })
+}
+
+// 时间段统计数据（总结生成与周期对比共用，序列化后存入 summaries.statistics）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeriodStatistics {
+    pub total_operations: i64,
+    pub new_projects: i64,
+    pub new_contacts: i64,
+    pub new_events: i64,
+    pub new_activities: i64,
+    #[serde(default)]
+    pub completed_activities: i64,
+    #[serde(default)]
+    pub files_added: i64,
+    #[serde(default)]
+    pub files_updated: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_projects: Vec<RankedEntity>,   // 季度/年度总结：最活跃的项目
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_contacts: Vec<RankedEntity>,   // 季度/年度总结：互动最多的联系人
+}
+
+// 排行榜条目（项目或联系人）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedEntity {
+    pub id: i32,
+    pub name: String,
+    pub count: i64,
+}
+
+// 时间范围内最活跃的项目（事件 + 完成的活动 + 上传的文件）
+pub fn fetch_top_projects(conn: &Connection, start_date: &str, end_date: &str, limit: i32) -> Result<Vec<RankedEntity>> {
     let start_datetime = format!("{} 00:00:00", start_date);
     let end_datetime = format!("{} 23:59:59", end_date);
-    let logs = fetch_operation_logs(&start_datetime, &end_datetime)?;
     
-    // 生成标题
-    let now = chrono::Local::now();
-    let title = format!("{}生成 - {} 至 {} 总结", 
-        now.format("%Y年%m月%d日 %H:%M"),
-        start_date,
-        end_date
-    );
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, COUNT(*) AS cnt
+         FROM (
+             SELECT ep.project_id FROM events_projects ep
+             INNER JOIN events e ON e.id = ep.event_id
+             WHERE substr(e.event_date, 1, 10) >= ?1 AND substr(e.event_date, 1, 10) <= ?2
+             AND e.is_private = 0
+             UNION ALL
+             SELECT project_id FROM project_activities WHERE completed_at >= ?3 AND completed_at <= ?4
+             UNION ALL
+             SELECT project_id FROM project_files WHERE created_at >= ?3 AND created_at <= ?4
+         ) x
+         INNER JOIN projects p ON p.id = x.project_id
+         GROUP BY p.id, p.name
+         ORDER BY cnt DESC, p.name
+         LIMIT ?5"
+    )?;
     
-    // 生成内容
-    let mut content = String::new();
-    content.push_str(&format!("# {} 至 {} 工作总结\n\n", start_date, end_date));
-    content.push_str(&format!("生成时间：{}\n\n", now.format("%Y年%m月%d日 %H:%M:%S")));
-    content.push_str("---\n\n");
+    let ranked: Vec<RankedEntity> = stmt.query_map(
+        rusqlite::params![start_date, end_date, start_datetime, end_datetime, limit],
+        |row| Ok(RankedEntity { id: row.get(0)?, name: row.get(1)?, count: row.get(2)? })
+    )?.filter_map(|r| r.ok()).collect();
     
-    if logs.is_empty() {
-        content.push_str("该时间段内没有操作记录。\n");
-    } else {
-        content.push_str("## 操作记录\n\n");
-        for log in &logs {
-            content.push_str(&format!("- {}\n", log.description));
-        }
-    }
+    Ok(ranked)
+}
+
+// 时间范围内参与事件最多的联系人
+pub fn fetch_top_contacts(conn: &Connection, start_date: &str, end_date: &str, limit: i32) -> Result<Vec<RankedEntity>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.id, c.name, COUNT(DISTINCT e.id) AS cnt
+         FROM events_contacts ec
+         INNER JOIN events e ON e.id = ec.event_id
+         INNER JOIN contacts c ON c.id = ec.contact_id
+         WHERE substr(e.event_date, 1, 10) >= ?1 AND substr(e.event_date, 1, 10) <= ?2
+         AND e.is_private = 0
+         GROUP BY c.id, c.name
+         ORDER BY cnt DESC, c.name
+         LIMIT ?3"
+    )?;
     
-    // 统计数据
-    let mut project_count = 0;
-    let mut contact_count = 0;
-    let mut event_count = 0;
-    let mut activity_count = 0;
+    let ranked: Vec<RankedEntity> = stmt.query_map(
+        rusqlite::params![start_date, end_date, limit],
+        |row| Ok(RankedEntity { id: row.get(0)?, name: row.get(1)?, count: row.get(2)? })
+    )?.filter_map(|r| r.ok()).collect();
+    
+    Ok(ranked)
+}
+
+// 统计时间范围内完成的活动数
+pub fn count_completed_activities(conn: &Connection, start_datetime: &str, end_datetime: &str) -> Result<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM project_activities WHERE completed_at >= ?1 AND completed_at <= ?2",
+        rusqlite::params![start_datetime, end_datetime],
+        |row| row.get(0)
+    )
+}
+
+// 根据日志、文件和完成活动数汇总统计数据
+fn build_period_statistics(
+    logs: &[OperationLog],
+    files: &[ProjectFileWithProject],
+    completed_activities: i64,
+) -> PeriodStatistics {
+    let mut stats = PeriodStatistics {
+        total_operations: logs.len() as i64,
+        completed_activities,
+        ..Default::default()
+    };
     
-    for log in &logs {
+    for log in logs {
         if log.operation_type == "create" {
             match log.entity_type.as_str() {
-                "project" => project_count += 1,
-                "contact" => contact_count += 1,
-                "event" => event_count += 1,
-                "activity" => activity_count += 1,
+                "project" => stats.new_projects += 1,
+                "contact" => stats.new_contacts += 1,
+                "event" => stats.new_events += 1,
+                "activity" => stats.new_activities += 1,
                 _ => {}
             }
         }
     }
     
-    let statistics = serde_json::json!({
-        "total_operations": logs.len(),
-        "new_projects": project_count,
-        "new_contacts": contact_count,
-        "new_events": event_count,
-        "new_activities": activity_count
-    }).to_string();
-    
-    content.push_str("\n## 统计数据\n\n");
-    content.push_str(&format!("- 总操作数：{}\n", logs.len()));
-    content.push_str(&format!("- 新增项目：{}\n", project_count));
-    content.push_str(&format!("- 新增联系人：{}\n", contact_count));
-    content.push_str(&format!("- 新增事件：{}\n", event_count));
-    content.push_str(&format!("- 新增活动：{}\n", activity_count));
+    for item in files {
+        if item.file.version > 1 {
+            stats.files_updated += 1;
+        } else {
+            stats.files_added += 1;
+        }
+    }
     
-    // 插入数据库
+    stats
+}
+
+// 计算某个时间段（闭区间，YYYY-MM-DD）的统计数据
+pub fn compute_period_statistics(start_date: &str, end_date: &str) -> Result<PeriodStatistics> {
     let db = get_db()?;
     let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(1),
         Some(format!("锁失败: {}", e))
     ))?;
-    
-    conn.execute(
-        "INSERT INTO summaries (title, summary_type, start_date, end_date, content, statistics, is_auto_generated) 
+    compute_period_statistics_with(&conn, start_date, end_date)
+}
+
+pub fn compute_period_statistics_with(conn: &Connection, start_date: &str, end_date: &str) -> Result<PeriodStatistics> {
+    let start_datetime = format!("{} 00:00:00", start_date);
+    let end_datetime = format!("{} 23:59:59", end_date);
+    let logs = fetch_operation_logs(conn, &start_datetime, &end_datetime)?;
+    let files = fetch_files_in_period(conn, &start_datetime, &end_datetime)?;
+    let completed = count_completed_activities(conn, &start_datetime, &end_datetime)?;
+    Ok(build_period_statistics(&logs, &files, completed))
+}
+
+// 渲染总结的标题、正文和统计数据（不写数据库）
+fn render_summary(
+    summary_type: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(String, String, String)> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    render_summary_with(&conn, summary_type, start_date, end_date)
+}
+
+pub fn render_summary_with(
+    conn: &Connection,
+    summary_type: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(String, String, String)> {
+    // 获取时间范围内的操作日志
+    let start_datetime = format!("{} 00:00:00", start_date);
+    let end_datetime = format!("{} 23:59:59", end_date);
+    let logs = fetch_operation_logs(conn, &start_datetime, &end_datetime)?;
+    let files = fetch_files_in_period(conn, &start_datetime, &end_datetime)?;
+    let completed_activities = count_completed_activities(conn, &start_datetime, &end_datetime)?;
+    
+    // 生成标题（日期按设置中的显示格式）
+    let prefs = DisplayPreferences::from_settings(&fetch_settings_with(conn)?);
+    let now = chrono::Local::now();
+    let start_display = prefs.format_date(start_date);
+    let end_display = prefs.format_date(end_date);
+    let title = format!("{}生成 - {} 至 {} 总结", 
+        now.format(&prefs.datetime_format),
+        start_display,
+        end_display
+    );
+    
+    // 生成内容
+    let mut content = String::new();
+    content.push_str(&format!("# {} 至 {} 工作总结\n\n", start_display, end_display));
+    content.push_str(&format!("生成时间：{}\n\n", now.format(&prefs.datetime_format)));
+    content.push_str("---\n\n");
+    
+    if logs.is_empty() {
+        content.push_str("该时间段内没有操作记录。\n");
+    } else {
+        content.push_str("## 操作记录\n\n");
+        for log in &logs {
+            content.push_str(&format!("- {}\n", log.description));
+        }
+    }
+    
+    // 文件交付（按项目分组）
+    if !files.is_empty() {
+        content.push_str("\n## 文件交付\n\n");
+        let mut current_project: Option<i32> = None;
+        for item in &files {
+            if current_project != Some(item.file.project_id) {
+                current_project = Some(item.file.project_id);
+                content.push_str(&format!("### {}\n\n", item.project_name));
+            }
+            let note = if item.file.version > 1 {
+                format!("更新至 v{}", item.file.version)
+            } else {
+                "新增 v1".to_string()
+            };
+            content.push_str(&format!("- {}（{}，{}）\n", item.file.original_name, note, item.file.created_at));
+        }
+    }
+    
+    // 每日记录（当天的心情和感想，私密记录不写入总结）
+    let notes: Vec<DayNote> = fetch_day_notes_with(conn, start_date, end_date)?
+        .into_iter()
+        .filter(|note| !note.is_private)
+        .collect();
+    if !notes.is_empty() {
+        content.push_str("\n## 每日记录\n\n");
+        for note in &notes {
+            let mut line = if start_date == end_date { String::new() } else { format!("{} ", prefs.format_date(&note.note_date)) };
+            if let Some(mood) = &note.mood {
+                line.push_str(&format!("【{}】", mood));
+            }
+            let ratings: Vec<String> = [("心情", note.mood_rating), ("精力", note.energy_rating)].iter()
+                .filter_map(|(label, rating)| rating.map(|r| format!("{} {}/{}", label, r, WELLBEING_RATING_MAX)))
+                .collect();
+            if !ratings.is_empty() {
+                line.push_str(&format!("（{}）", ratings.join("，")));
+            }
+            line.push_str(&note.content);
+            content.push_str(&format!("- {}\n", line.trim_end()));
+        }
+    }
+    
+    // 统计数据（季度/年度总结额外包含项目和联系人排行）
+    let mut stats = build_period_statistics(&logs, &files, completed_activities);
+    if summary_type == "quarterly" || summary_type == "yearly" {
+        stats.top_projects = fetch_top_projects(conn, start_date, end_date, 5)?;
+        stats.top_contacts = fetch_top_contacts(conn, start_date, end_date, 5)?;
+    }
+    let statistics = serde_json::to_string(&stats).unwrap_or_default();
+    
+    content.push_str("\n## 统计数据\n\n");
+    content.push_str(&format!("- 总操作数：{}\n", stats.total_operations));
+    content.push_str(&format!("- 新增项目：{}\n", stats.new_projects));
+    content.push_str(&format!("- 新增联系人：{}\n", stats.new_contacts));
+    content.push_str(&format!("- 新增事件：{}\n", stats.new_events));
+    content.push_str(&format!("- 新增活动：{}\n", stats.new_activities));
+    content.push_str(&format!("- 完成活动：{}\n", stats.completed_activities));
+    content.push_str(&format!("- 新增文件：{}\n", stats.files_added));
+    content.push_str(&format!("- 更新文件：{}\n", stats.files_updated));
+    
+    if !stats.top_projects.is_empty() {
+        content.push_str("\n## 最活跃项目\n\n");
+        for (i, p) in stats.top_projects.iter().enumerate() {
+            content.push_str(&format!("{}. {}（{} 项动态）\n", i + 1, p.name, p.count));
+        }
+    }
+    
+    if !stats.top_contacts.is_empty() {
+        content.push_str("\n## 互动最多的联系人\n\n");
+        for (i, c) in stats.top_contacts.iter().enumerate() {
+            content.push_str(&format!("{}. {}（{} 次事件）\n", i + 1, c.name, c.count));
+        }
+    }
+    
+    Ok((title, content, statistics))
+}
+
+// 生成总结
+pub fn generate_summary(
+    summary_type: &str,
+    start_date: &str,
+    end_date: &str,
+    is_auto: bool,
+) -> Result<Summary> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    generate_summary_with(&conn, summary_type, start_date, end_date, is_auto)
+}
+
+pub fn generate_summary_with(
+    conn: &Connection,
+    summary_type: &str,
+    start_date: &str,
+    end_date: &str,
+    is_auto: bool,
+) -> Result<Summary> {
+    let rendered = render_summary_with(conn, summary_type, start_date, end_date)?;
+    insert_summary_with(conn, summary_type, start_date, end_date, rendered, is_auto)
+}
+
+// 保存渲染好的总结（标题、正文、统计数据）
+fn insert_summary_with(
+    conn: &Connection,
+    summary_type: &str,
+    start_date: &str,
+    end_date: &str,
+    (title, content, statistics): (String, String, String),
+    is_auto: bool,
+) -> Result<Summary> {
+    let now = chrono::Local::now();
+    
+    // 插入数据库
+    conn.execute(
+        "INSERT INTO summaries (title, summary_type, start_date, end_date, content, statistics, is_auto_generated) 
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         rusqlite::params![title, summary_type, start_date, end_date, content, statistics, if is_auto { 1 } else { 0 }],
     )?;
@@ -1819,6 +3854,7 @@ pub fn generate_summary(
         content,
         statistics: Some(statistics),
         is_auto_generated: is_auto,
+        tags: None,
         created_at,
     })
 }
@@ -1831,25 +3867,13 @@ pub fn fetch_summaries() -> Result<Vec<Summary>> {
         Some(format!("锁失败: {}", e))
     ))?;
     
-    let mut stmt = conn.prepare(
-        "SELECT id, title, summary_type, start_date, end_date, content, statistics, is_auto_generated, created_at
-         FROM summaries
-         ORDER BY created_at DESC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM summaries ORDER BY created_at DESC",
+        SUMMARY_COLUMNS
+    ))?;
     
-    let summaries: Vec<Summary> = stmt.query_map([], |row| {
-        Ok(Summary {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            summary_type: row.get(2)?,
-            start_date: row.get(3)?,
-            end_date: row.get(4)?,
-            content: row.get(5)?,
-            statistics: row.get(6)?,
-            is_auto_generated: row.get::<_, i32>(7).unwrap_or(0) != 0,
-            created_at: row.get(8)?,
-        })
-    })?.filter_map(|r| r.ok()).collect();
+    let summaries: Vec<Summary> = stmt.query_map([], summary_from_row)?
+        .filter_map(|r| r.ok()).collect();
     
     Ok(summaries)
 }
@@ -1863,22 +3887,9 @@ pub fn fetch_summary_by_id(summary_id: i32) -> Result<Option<Summary>> {
     ))?;
     
     let result = conn.query_row(
-        "SELECT id, title, summary_type, start_date, end_date, content, statistics, is_auto_generated, created_at
-         FROM summaries WHERE id = ?1",
+        &format!("SELECT {} FROM summaries WHERE id = ?1", SUMMARY_COLUMNS),
         [summary_id],
-        |row| {
-            Ok(Summary {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                summary_type: row.get(2)?,
-                start_date: row.get(3)?,
-                end_date: row.get(4)?,
-                content: row.get(5)?,
-                statistics: row.get(6)?,
-                is_auto_generated: row.get::<_, i32>(7).unwrap_or(0) != 0,
-                created_at: row.get(8)?,
-            })
-        }
+        summary_from_row
     );
     
     match result {
@@ -1900,6 +3911,107 @@ pub fn delete_summary(summary_id: i32) -> Result<()> {
     Ok(())
 }
 
+// 设置总结标签（逗号分隔，传 None 清空）
+pub fn update_summary_tags(summary_id: i32, tags: Option<&str>) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let tags = merge_tags(tags, None);
+    conn.execute(
+        "UPDATE summaries SET tags = ?1 WHERE id = ?2",
+        rusqlite::params![tags, summary_id],
+    )?;
+    Ok(())
+}
+
+// 全文搜索总结（标题、标签、正文模糊匹配，标题和标签命中优先）
+pub fn search_summaries(keyword: &str, is_cancelled: &dyn Fn() -> bool) -> Result<Vec<crate::search::Highlighted<Summary>>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let search_pattern = format!("%{}%", keyword);
+    
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM summaries
+         WHERE title LIKE ?1 OR tags LIKE ?1 OR content LIKE ?1
+         ORDER BY
+           CASE
+             WHEN tags LIKE ?1 THEN 1
+             WHEN title LIKE ?1 THEN 2
+             ELSE 3
+           END,
+           start_date DESC",
+        SUMMARY_COLUMNS
+    ))?;
+    
+    let mut summaries = Vec::new();
+    for summary in stmt.query_map([search_pattern], summary_from_row)?.flatten() {
+        if is_cancelled() {
+            return Err(search_cancelled());
+        }
+        let highlights = crate::search::highlights(&[
+            ("title", Some(summary.title.as_str())),
+            ("tags", summary.tags.as_deref()),
+            ("content", Some(summary.content.as_str())),
+        ], keyword);
+        summaries.push(crate::search::Highlighted { item: summary, highlights });
+    }
+    
+    Ok(summaries)
+}
+
+// 按原有类型和时间段重新生成总结，保留原记录ID（导入历史数据后刷新过去的总结）
+pub fn regenerate_summary(summary_id: i32) -> Result<Option<Summary>> {
+    let Some(existing) = fetch_summary_by_id(summary_id)? else {
+        return Ok(None);
+    };
+    
+    let (title, content, statistics) = render_summary(&existing.summary_type, &existing.start_date, &existing.end_date)?;
+    
+    {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        conn.execute(
+            "UPDATE summaries SET title = ?1, content = ?2, statistics = ?3 WHERE id = ?4",
+            rusqlite::params![title, content, statistics, summary_id],
+        )?;
+    }
+    
+    Ok(Some(Summary {
+        title,
+        content,
+        statistics: Some(statistics),
+        ..existing
+    }))
+}
+
+// 某类型、某起始日期的总结是否已存在（自动生成去重用）
+fn summary_exists(summary_type: &str, start_date: &str) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM summaries WHERE summary_type = ?1 AND start_date = ?2",
+        [summary_type, start_date],
+        |row| row.get(0)
+    ).unwrap_or(0);
+    
+    Ok(count > 0)
+}
+
 // 检查是否需要自动生成总结
 pub fn check_and_generate_auto_summaries() -> Result<Vec<Summary>> {
     let today = chrono::Local::now();
@@ -1909,48 +4021,21 @@ pub fn check_and_generate_auto_summaries() -> Result<Vec<Summary>> {
     let yesterday = today - chrono::Duration::days(1);
     let yesterday_str = yesterday.format("%Y-%m-%d").to_string();
     
-    // 检查昨天是否已有日总结
-    let db = get_db()?;
-    {
-        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(1),
-            Some(format!("锁失败: {}", e))
-        ))?;
-        
-        let count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM summaries WHERE summary_type = 'daily' AND start_date = ?1",
-            [&yesterday_str],
-            |row| row.get(0)
-        ).unwrap_or(0);
-        
-        if count == 0 {
-            drop(conn); // 释放锁
-            if let Ok(summary) = generate_summary("daily", &yesterday_str, &yesterday_str, true) {
-                generated.push(summary);
-            }
+    if !summary_exists("daily", &yesterday_str)? {
+        if let Ok(summary) = generate_summary("daily", &yesterday_str, &yesterday_str, true) {
+            generated.push(summary);
         }
     }
     
-    // 检查是否需要生成周总结（每周一生成上周总结）
-    if today.weekday() == chrono::Weekday::Mon {
+    // 检查是否需要生成周总结（每周第一天生成上周总结，周起始日可在设置中配置）
+    let prefs = load_display_preferences()?;
+    if today.weekday() == prefs.week_start {
         let last_week_end = today - chrono::Duration::days(1);
         let last_week_start = today - chrono::Duration::days(7);
         let start_str = last_week_start.format("%Y-%m-%d").to_string();
         let end_str = last_week_end.format("%Y-%m-%d").to_string();
         
-        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(1),
-            Some(format!("锁失败: {}", e))
-        ))?;
-        
-        let count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM summaries WHERE summary_type = 'weekly' AND start_date = ?1",
-            [&start_str],
-            |row| row.get(0)
-        ).unwrap_or(0);
-        
-        if count == 0 {
-            drop(conn);
+        if !summary_exists("weekly", &start_str)? {
             if let Ok(summary) = generate_summary("weekly", &start_str, &end_str, true) {
                 generated.push(summary);
             }
@@ -1963,24 +4048,9797 @@ pub fn check_and_generate_auto_summaries() -> Result<Vec<Summary>> {
         let start_str = format!("{}-{:02}-01", last_month.year(), last_month.month());
         let end_str = last_month.format("%Y-%m-%d").to_string();
         
-        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(1),
-            Some(format!("锁失败: {}", e))
-        ))?;
-        
-        let count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM summaries WHERE summary_type = 'monthly' AND start_date = ?1",
-            [&start_str],
-            |row| row.get(0)
-        ).unwrap_or(0);
-        
-        if count == 0 {
-            drop(conn);
+        if !summary_exists("monthly", &start_str)? {
             if let Ok(summary) = generate_summary("monthly", &start_str, &end_str, true) {
                 generated.push(summary);
             }
         }
+        
+        // 季度首月1日生成上季度总结
+        if matches!(today.month(), 1 | 4 | 7 | 10) {
+            let quarter_start_month = last_month.month() - 2;
+            let start_str = format!("{}-{:02}-01", last_month.year(), quarter_start_month);
+            
+            if !summary_exists("quarterly", &start_str)? {
+                if let Ok(summary) = generate_summary("quarterly", &start_str, &end_str, true) {
+                    generated.push(summary);
+                }
+            }
+        }
+        
+        // 1月1日生成上一年度总结
+        if today.month() == 1 {
+            let start_str = format!("{}-01-01", last_month.year());
+            
+            if !summary_exists("yearly", &start_str)? {
+                if let Ok(summary) = generate_summary("yearly", &start_str, &end_str, true) {
+                    generated.push(summary);
+                }
+            }
+        }
     }
     
     Ok(generated)
-}
\ No newline at end of file
+}
+
+// ==================== 联系人导入相关 ====================
+
+// 与导入行可能重复的已有联系人
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactMatch {
+    pub contact: Contact,
+    pub score: i32,
+    pub reasons: Vec<String>,  // 姓名相同、邮箱相同、电话相同
+}
+
+// 导入预览中的一行
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactImportPreviewRow {
+    pub row_index: usize,
+    pub imported: ImportedContact,
+    pub matches: Vec<ContactMatch>,
+    pub suggested_action: String,  // create, merge, skip
+    pub suggested_target_id: Option<i32>,
+}
+
+// 前端确认后的逐行处理决定
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactImportDecision {
+    pub imported: ImportedContact,
+    pub action: String,  // create, merge, skip
+    pub target_contact_id: Option<i32>,
+}
+
+// 导入结果统计
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactImportResult {
+    pub created: usize,
+    pub merged: usize,
+    pub skipped: usize,
+}
+
+// 解析联系人电话字段（JSON数组，兼容旧的纯文本格式）
+pub fn parse_phone_list(phone: Option<&str>) -> Vec<String> {
+    let Some(raw) = phone.map(|p| p.trim()).filter(|p| !p.is_empty()) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<String>>(raw) {
+        Ok(list) => list.into_iter().filter(|p| !p.trim().is_empty()).collect(),
+        Err(_) => vec![raw.to_string()],
+    }
+}
+
+// 未带国家码的号码按中国大陆号码解析
+const DEFAULT_PHONE_REGION: phonenumber::country::Id = phonenumber::country::Id::CN;
+
+// 将电话号码规范化为 E.164 格式（如 +8613800138000），无法识别时返回 None
+pub fn normalize_phone(raw: &str) -> Option<String> {
+    let number = phonenumber::parse(Some(DEFAULT_PHONE_REGION), raw.trim()).ok()?;
+    phonenumber::is_valid(&number).then(|| number.format().mode(phonenumber::Mode::E164).to_string())
+}
+
+// 为电话字段生成对应的规范化 JSON 数组（无法识别的号码为空字符串）
+fn normalized_phone_json(phone: Option<&str>) -> Option<String> {
+    let phones = parse_phone_list(phone);
+    if phones.is_empty() {
+        return None;
+    }
+    let normalized: Vec<String> = phones.iter().map(|p| normalize_phone(p).unwrap_or_default()).collect();
+    serde_json::to_string(&normalized).ok()
+}
+
+// 为旧数据补齐规范化电话
+fn backfill_normalized_phones(conn: &Connection) -> Result<()> {
+    let rows: Vec<(i32, String)> = {
+        let mut stmt = conn.prepare_cached("SELECT id, phone FROM contacts WHERE phone_normalized IS NULL AND phone IS NOT NULL AND phone != ''")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.filter_map(|r| r.ok()).collect();
+        rows
+    };
+    for (id, phone) in rows {
+        conn.execute(
+            "UPDATE contacts SET phone_normalized = ?1 WHERE id = ?2",
+            rusqlite::params![normalized_phone_json(Some(&phone)), id],
+        )?;
+    }
+    Ok(())
+}
+
+// 电话号码只保留数字用于比较
+fn phone_digits(phone: &str) -> String {
+    phone.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+// 两个电话号码是否相同（忽略分隔符和 +86 之类的国家码前缀）
+fn phones_equal(a: &str, b: &str) -> bool {
+    let (a, b) = (phone_digits(a), phone_digits(b));
+    if a.len() < 6 || b.len() < 6 {
+        return false;
+    }
+    a == b || a.ends_with(&b) || b.ends_with(&a)
+}
+
+// 根据ID获取联系人
+pub fn fetch_contact_by_id(contact_id: i32) -> Result<Option<Contact>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    fetch_contact_by_id_with(&conn, contact_id)
+}
+
+pub fn fetch_contact_by_id_with(conn: &Connection, contact_id: i32) -> Result<Option<Contact>> {
+    let result = conn.query_row(
+        &format!("SELECT {} FROM contacts c WHERE c.id = ?1", CONTACT_COLUMNS),
+        [contact_id],
+        contact_from_row
+    );
+    
+    match result {
+        Ok(contact) => Ok(Some(contact)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 在已有联系人中查找可能重复的记录（按匹配度从高到低）
+fn find_contact_matches(imported: &ImportedContact, existing: &[Contact]) -> Vec<ContactMatch> {
+    let name = imported.name.trim();
+    let email = imported.email.as_deref().map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty());
+    
+    let mut matches: Vec<ContactMatch> = existing.iter().filter_map(|contact| {
+        let mut score = 0;
+        let mut reasons = Vec::new();
+        
+        if let (Some(e), Some(ce)) = (&email, contact.email.as_deref()) {
+            if ce.trim().to_lowercase() == *e {
+                score += 50;
+                reasons.push("邮箱相同".to_string());
+            }
+        }
+        
+        let existing_phones = parse_phone_list(contact.phone.as_deref());
+        if imported.phones.iter().any(|p| existing_phones.iter().any(|ep| phones_equal(p, ep))) {
+            score += 40;
+            reasons.push("电话相同".to_string());
+        }
+        
+        if contact.name.trim() == name {
+            score += 30;
+            reasons.push("姓名相同".to_string());
+            // 同名且同单位基本可以确定是同一个人
+            if let (Some(c1), Some(c2)) = (imported.company.as_deref(), contact.company.as_deref()) {
+                if !c1.trim().is_empty() && c1.trim() == c2.trim() {
+                    score += 20;
+                    reasons.push("单位相同".to_string());
+                }
+            }
+        }
+        
+        if score > 0 {
+            Some(ContactMatch { contact: contact.clone(), score, reasons })
+        } else {
+            None
+        }
+    }).collect();
+    
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches
+}
+
+// 合并两个电话列表（去重）
+fn merge_phone_lists(existing: &[String], imported: &[String]) -> Vec<String> {
+    let mut merged = existing.to_vec();
+    for phone in imported {
+        if !merged.iter().any(|p| phones_equal(p, phone) || p == phone) {
+            merged.push(phone.clone());
+        }
+    }
+    merged
+}
+
+// 合并逗号分隔的标签（去重）
+fn merge_tags(existing: Option<&str>, imported: Option<&str>) -> Option<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for source in [existing, imported].into_iter().flatten() {
+        for tag in source.split([',', '，']) {
+            let tag = tag.trim();
+            if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    if tags.is_empty() { None } else { Some(tags.join(",")) }
+}
+
+// 已有字段为空时才用导入的值补齐
+fn fill_empty(existing: &Option<String>, imported: &Option<String>) -> Option<String> {
+    match existing.as_deref().map(|s| s.trim()) {
+        Some(s) if !s.is_empty() => existing.clone(),
+        _ => imported.clone().filter(|s| !s.trim().is_empty()),
+    }
+}
+
+// 计算合并后的联系人（只补齐空字段，电话和标签取并集，备注追加）
+fn merge_imported_into(contact: &Contact, imported: &ImportedContact) -> Contact {
+    let phones = merge_phone_lists(&parse_phone_list(contact.phone.as_deref()), &imported.phones);
+    let notes = match (contact.notes.as_deref().map(|n| n.trim()), imported.notes.as_deref().map(|n| n.trim())) {
+        (Some(old), Some(new)) if !old.is_empty() && !new.is_empty() && !old.contains(new) => {
+            Some(format!("{}\n{}", old, new))
+        }
+        _ => fill_empty(&contact.notes, &imported.notes),
+    };
+    
+    Contact {
+        title: fill_empty(&contact.title, &imported.title),
+        notes,
+        tags: merge_tags(contact.tags.as_deref(), imported.tags.as_deref()),
+        phone: if phones.is_empty() { None } else { Some(serde_json::to_string(&phones).unwrap_or_default()) },
+        email: fill_empty(&contact.email, &imported.email),
+        address: fill_empty(&contact.address, &imported.address),
+        company: fill_empty(&contact.company, &imported.company),
+        ..contact.clone()
+    }
+}
+
+// 合并是否会带来任何变化
+fn merge_changes_contact(contact: &Contact, imported: &ImportedContact) -> bool {
+    let merged = merge_imported_into(contact, imported);
+    let normalize = |v: &Option<String>| v.as_deref().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    normalize(&merged.title) != normalize(&contact.title)
+        || normalize(&merged.notes) != normalize(&contact.notes)
+        || normalize(&merged.tags) != normalize(&contact.tags)
+        || parse_phone_list(merged.phone.as_deref()) != parse_phone_list(contact.phone.as_deref())
+        || normalize(&merged.email) != normalize(&contact.email)
+        || normalize(&merged.address) != normalize(&contact.address)
+        || normalize(&merged.company) != normalize(&contact.company)
+}
+
+// 生成导入预览：为每一行列出可能重复的联系人并给出建议操作
+pub fn preview_contact_import(rows: Vec<ImportedContact>) -> Result<Vec<ContactImportPreviewRow>> {
+    let existing = fetch_contacts()?;
+    
+    let preview = rows.into_iter().enumerate().map(|(row_index, imported)| {
+        let matches = find_contact_matches(&imported, &existing);
+        // 邮箱或电话相同，或同名同单位，视为同一个人
+        let (suggested_action, suggested_target_id) = match matches.first() {
+            Some(best) if best.score >= 40 => {
+                if merge_changes_contact(&best.contact, &imported) {
+                    ("merge".to_string(), Some(best.contact.id))
+                } else {
+                    ("skip".to_string(), Some(best.contact.id))
+                }
+            }
+            _ => ("create".to_string(), None),
+        };
+        
+        ContactImportPreviewRow {
+            row_index,
+            imported,
+            matches,
+            suggested_action,
+            suggested_target_id,
+        }
+    }).collect();
+    
+    Ok(preview)
+}
+
+// 按逐行决定执行导入（整批在同一事务中，任一行失败则全部回滚）
+pub fn commit_contact_import(decisions: &[ContactImportDecision]) -> Result<ContactImportResult> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    commit_contact_import_with(&mut conn, decisions)
+}
+
+pub fn commit_contact_import_with(conn: &mut Connection, decisions: &[ContactImportDecision]) -> Result<ContactImportResult> {
+    let tx = conn.transaction()?;
+    let result = apply_contact_import(&tx, decisions)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+// 逐行执行导入决定（由调用方负责事务）
+fn apply_contact_import(conn: &Connection, decisions: &[ContactImportDecision]) -> Result<ContactImportResult> {
+    let mut result = ContactImportResult { created: 0, merged: 0, skipped: 0 };
+    
+    for decision in decisions {
+        let imported = &decision.imported;
+        match decision.action.as_str() {
+            "create" => {
+                let phone = if imported.phones.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::to_string(&imported.phones).unwrap_or_default())
+                };
+                insert_contact_with(conn, &NewContact {
+                    name: imported.name.trim().to_string(),
+                    title: imported.title.clone(),
+                    notes: imported.notes.clone(),
+                    tags: imported.tags.clone(),
+                    phone,
+                    email: imported.email.clone(),
+                    address: imported.address.clone(),
+                    company: imported.company.clone(),
+                })?;
+                result.created += 1;
+            }
+            "merge" => {
+                let target_id = decision.target_contact_id.ok_or_else(|| rusqlite::Error::InvalidParameterName(
+                    format!("合并「{}」时未指定目标联系人", imported.name)
+                ))?;
+                let contact = fetch_contact_by_id_with(conn, target_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+                let merged = merge_imported_into(&contact, imported);
+                
+                update_contact_with(conn, contact.id, &NewContact {
+                    name: contact.name.clone(),
+                    title: merged.title,
+                    notes: merged.notes,
+                    tags: merged.tags,
+                    phone: merged.phone,
+                    email: merged.email,
+                    address: merged.address,
+                    company: merged.company,
+                })?;
+                
+                let now = chrono::Local::now();
+                let desc = format!("{}，导入时合并联系人「{}」", now.format("%Y年%m月%d日 %H:%M"), contact.name);
+                conn.execute(
+                    "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description) 
+                     VALUES ('update', 'contact', ?1, ?2, ?3)",
+                    rusqlite::params![contact.id, contact.name, desc],
+                )?;
+                result.merged += 1;
+            }
+            "skip" => result.skipped += 1,
+            other => return Err(rusqlite::Error::InvalidParameterName(
+                format!("无效的导入操作「{}」（联系人「{}」）", other, imported.name)
+            )),
+        }
+    }
+    
+    Ok(result)
+}
+
+// ==================== 总结对比相关 ====================
+
+// 对比用的时间段（闭区间，YYYY-MM-DD）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryPeriod {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+// 单项统计的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatisticDelta {
+    pub key: String,
+    pub label: String,
+    pub value_a: i64,
+    pub value_b: i64,
+    pub delta: i64,
+    pub change_percent: Option<f64>,  // 基准为 0 时无法计算百分比
+}
+
+// 两个时间段的对比结果（period_a 为基准期，period_b 为对比期）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummaryComparison {
+    pub period_a: SummaryPeriod,
+    pub period_b: SummaryPeriod,
+    pub statistics_a: PeriodStatistics,
+    pub statistics_b: PeriodStatistics,
+    pub deltas: Vec<StatisticDelta>,
+    pub report: String,
+}
+
+// 对比两个时间段的统计数据并生成简短的趋势报告
+pub fn compare_summaries(period_a: &SummaryPeriod, period_b: &SummaryPeriod) -> Result<SummaryComparison> {
+    let statistics_a = compute_period_statistics(&period_a.start_date, &period_a.end_date)?;
+    let statistics_b = compute_period_statistics(&period_b.start_date, &period_b.end_date)?;
+    
+    let pairs = [
+        ("new_events", "新增事件", statistics_a.new_events, statistics_b.new_events),
+        ("new_contacts", "新增联系人", statistics_a.new_contacts, statistics_b.new_contacts),
+        ("completed_activities", "完成活动", statistics_a.completed_activities, statistics_b.completed_activities),
+        ("new_activities", "新增活动", statistics_a.new_activities, statistics_b.new_activities),
+        ("new_projects", "新增项目", statistics_a.new_projects, statistics_b.new_projects),
+        ("files_added", "新增文件", statistics_a.files_added, statistics_b.files_added),
+        ("files_updated", "更新文件", statistics_a.files_updated, statistics_b.files_updated),
+        ("total_operations", "总操作数", statistics_a.total_operations, statistics_b.total_operations),
+    ];
+    
+    let deltas: Vec<StatisticDelta> = pairs.iter().map(|(key, label, a, b)| {
+        StatisticDelta {
+            key: key.to_string(),
+            label: label.to_string(),
+            value_a: *a,
+            value_b: *b,
+            delta: b - a,
+            change_percent: if *a > 0 { Some((b - a) as f64 * 100.0 / *a as f64) } else { None },
+        }
+    }).collect();
+    
+    // 生成趋势报告
+    let mut report = String::new();
+    report.push_str(&format!("# {} 至 {} 对比 {} 至 {}\n\n",
+        period_b.start_date, period_b.end_date, period_a.start_date, period_a.end_date));
+    
+    for d in &deltas {
+        let change = match (d.delta, d.change_percent) {
+            (0, _) => "持平".to_string(),
+            (delta, Some(pct)) => format!("{:+}，{:+.0}%", delta, pct),
+            (delta, None) => format!("{:+}", delta),
+        };
+        report.push_str(&format!("- {}：{} → {}（{}）\n", d.label, d.value_a, d.value_b, change));
+    }
+    
+    let up: Vec<&str> = deltas.iter().filter(|d| d.delta > 0 && d.key != "total_operations").map(|d| d.label.as_str()).collect();
+    let down: Vec<&str> = deltas.iter().filter(|d| d.delta < 0 && d.key != "total_operations").map(|d| d.label.as_str()).collect();
+    
+    report.push_str("\n## 趋势\n\n");
+    if up.is_empty() && down.is_empty() {
+        report.push_str("两个时间段的工作量基本持平。\n");
+    } else {
+        if !up.is_empty() {
+            report.push_str(&format!("- 上升：{}\n", up.join("、")));
+        }
+        if !down.is_empty() {
+            report.push_str(&format!("- 下降：{}\n", down.join("、")));
+        }
+    }
+    
+    Ok(SummaryComparison {
+        period_a: period_a.clone(),
+        period_b: period_b.clone(),
+        statistics_a,
+        statistics_b,
+        deltas,
+        report,
+    })
+}
+
+// ==================== 置顶相关 ====================
+
+// 置顶的事件和联系人
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PinnedItems {
+    pub events: Vec<EventWithDetails>,
+    pub contacts: Vec<Contact>,
+}
+
+// 切换事件置顶状态，返回切换后的状态
+pub fn toggle_event_pin(event_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE events SET pinned = CASE WHEN pinned = 1 THEN 0 ELSE 1 END WHERE id = ?1",
+        [event_id],
+    )?;
+    
+    let pinned: i32 = conn.query_row("SELECT pinned FROM events WHERE id = ?1", [event_id], |row| row.get(0))?;
+    Ok(pinned != 0)
+}
+
+// 设置事件是否私密（私密事件不出现在导出、分享快照和自动总结中），返回事件是否存在
+pub fn set_event_private(event_id: i32, is_private: bool) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let updated = conn.execute(
+        "UPDATE events SET is_private = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![is_private as i32, event_id],
+    )?;
+    Ok(updated > 0)
+}
+
+// 切换联系人置顶状态，返回切换后的状态
+pub fn toggle_contact_pin(contact_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE contacts SET pinned = CASE WHEN pinned = 1 THEN 0 ELSE 1 END WHERE id = ?1",
+        [contact_id],
+    )?;
+    
+    let pinned: i32 = conn.query_row("SELECT pinned FROM contacts WHERE id = ?1", [contact_id], |row| row.get(0))?;
+    Ok(pinned != 0)
+}
+
+// 获取所有置顶的事件和联系人
+pub fn fetch_pinned_items() -> Result<PinnedItems> {
+    let events = fetch_all_events(false)?.into_iter().filter(|e| e.event.pinned).collect();
+    let contacts = fetch_contacts()?.into_iter().filter(|c| c.pinned).collect();
+    Ok(PinnedItems { events, contacts })
+}
+
+// ==================== 事件类型目录与配色相关 ====================
+
+// 事件类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventType {
+    pub id: i32,
+    pub name: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub sort_order: i32,
+    pub default_reminder_offset_minutes: Option<i32>,  // 默认提前提醒分钟数（如会议 30，截止 1440）
+    #[serde(default)]
+    pub notification_urgency: Option<String>,          // 提醒通知紧急程度（为空时按"普通"）
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// 获取事件类型目录
+pub fn fetch_event_types() -> Result<Vec<EventType>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, name, color, icon, sort_order, created_at, updated_at, default_reminder_offset_minutes, notification_urgency
+         FROM event_types
+         ORDER BY sort_order, id"
+    )?;
+    
+    let types: Vec<EventType> = stmt.query_map([], |row| {
+        Ok(EventType {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            icon: row.get(3)?,
+            sort_order: row.get::<_, Option<i32>>(4)?.unwrap_or(0),
+            default_reminder_offset_minutes: row.get(7)?,
+            notification_urgency: row.get(8)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    })?.filter_map(|r| r.ok()).collect();
+    
+    Ok(types)
+}
+
+// 新增或更新事件类型（按名称匹配）
+pub fn save_event_type(
+    name: &str,
+    color: Option<&str>,
+    icon: Option<&str>,
+    sort_order: Option<i32>,
+    default_reminder_offset_minutes: Option<i32>,
+    notification_urgency: Option<&str>,
+) -> Result<()> {
+    if let Some(urgency) = notification_urgency {
+        if !NOTIFICATION_URGENCIES.contains(&urgency) {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("无效的通知紧急程度: {}", urgency))
+            ));
+        }
+    }
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT INTO event_types (name, color, icon, sort_order, default_reminder_offset_minutes, notification_urgency)
+         VALUES (?1, ?2, ?3, COALESCE(?4, (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM event_types)), ?5, ?6)
+         ON CONFLICT(name) DO UPDATE SET
+             color = excluded.color,
+             icon = excluded.icon,
+             sort_order = COALESCE(?4, event_types.sort_order),
+             default_reminder_offset_minutes = excluded.default_reminder_offset_minutes,
+             notification_urgency = excluded.notification_urgency,
+             updated_at = CURRENT_TIMESTAMP",
+        rusqlite::params![name, color, icon, sort_order, default_reminder_offset_minutes.filter(|m| *m > 0), notification_urgency],
+    )?;
+    Ok(())
+}
+
+// 删除事件类型（已有事件的 event_type 值保持不变）
+pub fn delete_event_type(name: &str) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM event_types WHERE name = ?1", [name])?;
+    Ok(())
+}
+
+// 设置项目颜色和图标
+pub fn update_project_style(project_id: i32, color: Option<&str>, icon: Option<&str>) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE projects SET color = ?1, icon = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        rusqlite::params![color, icon, project_id],
+    )?;
+    Ok(())
+}
+
+// 获取项目是否在完成活动时自动记录时间线事件
+pub fn get_activity_completion_logging(project_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let enabled: Option<i32> = conn.query_row(
+        "SELECT log_activity_completion FROM projects WHERE id = ?1",
+        [project_id],
+        |row| row.get(0)
+    )?;
+    Ok(enabled.unwrap_or(0) != 0)
+}
+
+// 设置项目是否在完成活动时自动记录时间线事件
+pub fn set_activity_completion_logging(project_id: i32, enabled: bool) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE projects SET log_activity_completion = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![enabled as i32, project_id],
+    )?;
+    Ok(())
+}
+
+// 活动完成后在项目时间线上记录“完成活动：X”事件（项目未开启时返回 None）
+pub fn record_activity_completion_event(activity_id: i32) -> Result<Option<i64>> {
+    let (activity, project_name, enabled) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let activity = conn.query_row(
+            &format!("SELECT {} FROM project_activities a WHERE a.id = ?1", ACTIVITY_COLUMNS),
+            [activity_id],
+            activity_from_row
+        )?;
+        let (project_name, enabled): (String, Option<i32>) = conn.query_row(
+            "SELECT name, log_activity_completion FROM projects WHERE id = ?1",
+            [activity.project_id],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        )?;
+        (activity, project_name, enabled.unwrap_or(0) != 0)
+    };
+    
+    if !enabled {
+        return Ok(None);
+    }
+    
+    // 事件时间取完成时间（精确到分钟）
+    let event_date = activity.completed_at.as_deref()
+        .and_then(|t| t.get(..16))
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d %H:%M").to_string());
+    let title = format!("完成活动：{}", activity.name);
+    
+    let event_id = insert_event(
+        &title,
+        activity.description.as_deref(),
+        &event_date,
+        Some(activity.project_id),
+        Some("完成"),
+        None,
+    )?;
+    
+    let assignees = fetch_assignees_for_activity(activity_id)?;
+    let contact_ids: Vec<i32> = assignees.iter().map(|c| c.id).collect();
+    link_contacts_to_event(event_id, &contact_ids)?;
+    
+    let contact_names: Vec<String> = assignees.into_iter().map(|c| c.name).collect();
+    let _ = log_event_creation(
+        event_id,
+        &title,
+        Some("完成"),
+        Some(activity.project_id),
+        Some(&project_name),
+        &contact_names,
+    );
+    
+    Ok(Some(event_id))
+}
+
+// ==================== 存储位置相关 ====================
+
+// 存储设置（保存在应用数据目录的 storage.json 中，因为需要在打开数据库之前读取）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageSettings {
+    pub db_dir: Option<String>,  // 自定义数据库目录，None 表示使用默认位置
+}
+
+// 存储健康检查结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageHealth {
+    pub db_path: String,
+    pub sync_provider: Option<String>,  // 检测到的同步盘，如 Dropbox、OneDrive、iCloud
+    pub conflict_files: Vec<String>,    // 同步冲突产生的副本
+    pub locked: bool,                   // 数据库文件是否被其他进程锁定
+    pub warnings: Vec<String>,
+    pub suggested_dir: Option<String>,  // 建议迁移到的本地目录
+}
+
+fn storage_settings_path() -> PathBuf {
+    app_data_dir().join("storage.json")
+}
+
+// 读取存储设置（文件不存在或格式错误时使用默认值）
+pub fn load_storage_settings() -> StorageSettings {
+    std::fs::read_to_string(storage_settings_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 保存存储设置
+pub fn save_storage_settings(settings: &StorageSettings) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(settings)?;
+    std::fs::write(storage_settings_path(), content)
+}
+
+// 根据路径判断是否位于常见同步盘中
+fn detect_sync_provider(path: &std::path::Path) -> Option<String> {
+    let path_str = path.to_string_lossy().to_lowercase();
+    let providers = [
+        ("dropbox", "Dropbox"),
+        ("onedrive", "OneDrive"),
+        ("mobile documents", "iCloud"),
+        ("icloud", "iCloud"),
+        ("google drive", "Google Drive"),
+        ("googledrive", "Google Drive"),
+        ("nutstore", "坚果云"),
+        ("坚果云", "坚果云"),
+        ("baidunetdisk", "百度网盘"),
+    ];
+    providers.iter()
+        .find(|(pattern, _)| path_str.contains(pattern))
+        .map(|(_, name)| name.to_string())
+}
+
+// 查找数据库同目录下的同步冲突副本
+fn find_conflict_copies(db_path: &std::path::Path) -> Vec<String> {
+    let (Some(dir), Some(stem)) = (db_path.parent(), db_path.file_stem().and_then(|s| s.to_str())) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    
+    let main_name = db_path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+    entries.filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().map(|n| n.to_string()))
+        .filter(|name| *name != main_name && name.starts_with(stem))
+        .filter(|name| {
+            let lower = name.to_lowercase();
+            lower.contains("conflict") || lower.contains("冲突") || lower.contains("(1)")
+                || lower.contains("'s conflicted copy")
+                // OneDrive 冲突副本形如 mindmirror_local-DESKTOP-XXXX.db
+                || (lower.ends_with(".db") && lower.starts_with(&format!("{}-", stem.to_lowercase())))
+        })
+        .map(|name| dir.join(name).to_string_lossy().to_string())
+        .collect()
+}
+
+// 数据库文件是否被其他进程占用（尝试获取写锁，不等待）
+fn is_db_locked(db_path: &std::path::Path) -> bool {
+    let Ok(probe) = Connection::open(db_path) else {
+        return true;
+    };
+    let _ = probe.busy_timeout(std::time::Duration::from_millis(0));
+    probe.execute_batch("BEGIN IMMEDIATE; ROLLBACK;").is_err()
+}
+
+// 检查数据库存储位置是否安全
+pub fn check_storage_health() -> StorageHealth {
+    let db_path = resolve_db_path();
+    // 内存数据库没有文件，不存在同步盘或占用问题
+    if is_memory_db() {
+        return StorageHealth {
+            db_path: db_path.to_string_lossy().to_string(),
+            sync_provider: None,
+            conflict_files: Vec::new(),
+            locked: false,
+            warnings: Vec::new(),
+            suggested_dir: None,
+        };
+    }
+    let sync_provider = detect_sync_provider(&db_path);
+    let conflict_files = find_conflict_copies(&db_path);
+    let locked = db_path.exists() && is_db_locked(&db_path);
+    
+    let mut warnings = Vec::new();
+    if let Some(ref provider) = sync_provider {
+        warnings.push(format!("数据库位于 {} 同步目录中，多设备同时使用可能导致数据损坏或冲突副本", provider));
+    }
+    if !conflict_files.is_empty() {
+        warnings.push(format!("发现 {} 个同步冲突副本，其中可能包含其他设备上的修改", conflict_files.len()));
+    }
+    if locked {
+        warnings.push("数据库文件正被其他程序占用（可能是同步客户端正在上传）".to_string());
+    }
+    
+    let suggested_dir = if sync_provider.is_some() {
+        Some(app_data_dir().to_string_lossy().to_string())
+    } else {
+        None
+    };
+    
+    StorageHealth {
+        db_path: db_path.to_string_lossy().to_string(),
+        sync_provider,
+        conflict_files,
+        locked,
+        warnings,
+        suggested_dir,
+    }
+}
+
+// 将数据库复制到新目录、写入存储设置，并把当前连接切换到新文件（之后的写入都进入新位置）
+pub fn relocate_database(target_dir: &str) -> Result<String> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    if std::env::var(DB_ENV_VAR).is_ok_and(|v| !v.trim().is_empty()) {
+        return Err(invalid(format!("数据库位置由环境变量 {} 指定，无法迁移", DB_ENV_VAR)));
+    }
+    let target_dir = PathBuf::from(target_dir);
+    std::fs::create_dir_all(&target_dir).map_err(|e| invalid(format!("创建目录失败: {}", e)))?;
+    
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| invalid(format!("锁失败: {}", e)))?;
+    
+    let target_path = target_dir.join(DB_FILE_NAME);
+    if target_path == connection_path(&conn) {
+        return Ok(target_path.to_string_lossy().to_string());
+    }
+    if target_path.exists() {
+        return Err(invalid(format!("目标位置已存在数据库文件: {}", target_path.display())));
+    }
+    
+    // 持有锁期间完成复制和切换，其间不会有写入落在旧文件中
+    // VACUUM INTO 生成一致的数据库副本
+    conn.execute("VACUUM INTO ?1", [target_path.to_string_lossy().to_string()])?;
+    let new_conn = match open_connection(&target_path) {
+        Ok(new_conn) => new_conn,
+        Err(e) => {
+            let _ = std::fs::remove_file(&target_path);
+            return Err(e);
+        }
+    };
+    
+    let mut settings = load_storage_settings();
+    settings.db_dir = Some(target_dir.to_string_lossy().to_string());
+    if let Err(e) = save_storage_settings(&settings) {
+        let _ = std::fs::remove_file(&target_path);
+        return Err(invalid(format!("保存存储设置失败: {}", e)));
+    }
+    
+    let old_conn = std::mem::replace(&mut *conn, new_conn);
+    let _ = old_conn.close();
+    Ok(target_path.to_string_lossy().to_string())
+}
+
+// ==================== 上传暂存相关 ====================
+
+// 上传暂存记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadStaging {
+    pub id: i64,
+    pub project_id: Option<i32>,
+    pub contact_id: Option<i32>,
+    pub target_path: String,
+    pub committed: bool,  // project_files / contact_files 中是否已有对应记录（即上传实际已完成）
+    pub created_at: String,
+}
+
+// 上传复制过程中使用的临时文件路径
+pub fn staging_partial_path(target_path: &str) -> String {
+    format!("{}.partial", target_path)
+}
+
+// 开始上传前登记暂存记录
+pub fn begin_upload_staging(project_id: Option<i32>, contact_id: Option<i32>, target_path: &str) -> Result<i64> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT INTO upload_staging (project_id, contact_id, target_path) VALUES (?1, ?2, ?3)",
+        rusqlite::params![project_id, contact_id, target_path],
+    )?;
+    
+    Ok(conn.last_insert_rowid())
+}
+
+// 上传完成或放弃后移除暂存记录
+pub fn finish_upload_staging(staging_id: i64) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM upload_staging WHERE id = ?1", [staging_id])?;
+    Ok(())
+}
+
+// 获取所有遗留的暂存记录（正常情况下应为空）
+pub fn fetch_upload_staging() -> Result<Vec<UploadStaging>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT s.id, s.project_id, s.contact_id, s.target_path, s.created_at,
+                EXISTS(SELECT 1 FROM project_files f WHERE f.file_path = s.target_path)
+                OR EXISTS(SELECT 1 FROM contact_files cf WHERE cf.file_path = s.target_path)
+         FROM upload_staging s ORDER BY s.id"
+    )?;
+    
+    let rows = stmt.query_map([], |row| {
+        Ok(UploadStaging {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            contact_id: row.get(2)?,
+            target_path: row.get(3)?,
+            created_at: row.get(4)?,
+            committed: row.get(5)?,
+        })
+    })?;
+    
+    rows.collect()
+}
+
+// ==================== 联系人模板相关 ====================
+
+// 按模板为联系人生成的文本
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedContactText {
+    pub contact_id: i32,
+    pub name: String,
+    pub text: String,
+}
+
+// ==================== 联系人文件相关 ====================
+
+// 联系人文件结构体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactFile {
+    pub id: i32,
+    pub contact_id: i32,
+    pub original_name: String,
+    pub stored_name: String,
+    pub file_path: String,
+    pub file_size: Option<i64>,
+    pub file_type: Option<String>,
+    pub version: i32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const CONTACT_FILE_COLUMNS: &str =
+    "id, contact_id, original_name, stored_name, file_path, file_size, file_type, version, created_at, updated_at";
+
+fn contact_file_from_row(row: &rusqlite::Row) -> rusqlite::Result<ContactFile> {
+    Ok(ContactFile {
+        id: row.get(0)?,
+        contact_id: row.get(1)?,
+        original_name: row.get(2)?,
+        stored_name: row.get(3)?,
+        file_path: row.get(4)?,
+        file_size: row.get(5)?,
+        file_type: row.get(6)?,
+        version: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+// 插入联系人文件记录
+pub fn insert_contact_file(
+    contact_id: i32,
+    original_name: &str,
+    stored_name: &str,
+    file_path: &str,
+    file_size: Option<i64>,
+    file_type: Option<&str>,
+    version: i32,
+) -> Result<i64> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT INTO contact_files (contact_id, original_name, stored_name, file_path, file_size, file_type, version) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![contact_id, original_name, stored_name, file_path, file_size, file_type, version],
+    )?;
+    
+    Ok(conn.last_insert_rowid())
+}
+
+// 获取联系人的所有文件（按更新时间倒序）
+pub fn fetch_files_for_contact(contact_id: i32) -> Result<Vec<ContactFile>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM contact_files WHERE contact_id = ?1 ORDER BY updated_at DESC",
+        CONTACT_FILE_COLUMNS
+    ))?;
+    
+    let files = stmt.query_map([contact_id], contact_file_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(files)
+}
+
+// 获取联系人文件的最新版本号
+pub fn get_latest_contact_file_version(contact_id: i32, original_name: &str) -> Result<i32> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    get_latest_contact_file_version_with(&conn, contact_id, original_name)
+}
+
+pub fn get_latest_contact_file_version_with(conn: &Connection, contact_id: i32, original_name: &str) -> Result<i32> {
+    let version: rusqlite::Result<i32> = conn.query_row(
+        "SELECT MAX(version) FROM contact_files WHERE contact_id = ?1 AND original_name = ?2",
+        rusqlite::params![contact_id, original_name],
+        |row| row.get(0)
+    );
+    
+    Ok(version.unwrap_or(0))
+}
+
+// 根据ID获取联系人文件
+pub fn get_contact_file_by_id(file_id: i32) -> Result<Option<ContactFile>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let result = conn.query_row(
+        &format!("SELECT {} FROM contact_files WHERE id = ?1", CONTACT_FILE_COLUMNS),
+        [file_id],
+        contact_file_from_row
+    );
+    
+    match result {
+        Ok(file) => Ok(Some(file)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 删除联系人文件记录
+pub fn delete_contact_file(file_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM contact_files WHERE id = ?1", [file_id])?;
+    Ok(())
+}
+
+// ==================== 相关条目关联 ====================
+
+// 相关条目（用于"另请参阅"面板）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelatedItem {
+    pub link_id: i64,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub title: String,
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
+// 可关联的实体类型 -> (表名, 标题字段)
+fn entity_table(entity_type: &str) -> Option<(&'static str, &'static str)> {
+    match entity_type {
+        "project" => Some(("projects", "name")),
+        "contact" => Some(("contacts", "name")),
+        "event" => Some(("events", "title")),
+        "activity" => Some(("project_activities", "name")),
+        "file" => Some(("project_files", "original_name")),
+        "contact_file" => Some(("contact_files", "original_name")),
+        "summary" => Some(("summaries", "title")),
+        _ => None,
+    }
+}
+
+fn unknown_entity_type(entity_type: &str) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("不支持的关联类型: {}", entity_type))
+    )
+}
+
+// 获取实体标题（记录不存在时返回 None）
+fn fetch_entity_title(conn: &Connection, entity_type: &str, entity_id: i32) -> Result<Option<String>> {
+    let (table, column) = entity_table(entity_type).ok_or_else(|| unknown_entity_type(entity_type))?;
+    let result = conn.query_row(
+        &format!("SELECT {} FROM {} WHERE id = ?1", column, table),
+        [entity_id],
+        |row| row.get(0)
+    );
+    match result {
+        Ok(title) => Ok(Some(title)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 获取实体标题（用于窗口标题等，记录不存在时返回 None）
+pub fn get_entity_title(entity_type: &str, entity_id: i32) -> Result<Option<String>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_entity_title(&conn, entity_type, entity_id)
+}
+
+// 关联两条记录（已关联时更新关系说明），返回关联ID
+pub fn link_entities(
+    source_type: &str,
+    source_id: i32,
+    target_type: &str,
+    target_id: i32,
+    label: Option<&str>,
+) -> Result<i64> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    if source_type == target_type && source_id == target_id {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("不能关联到自身".to_string())
+        ));
+    }
+    for (entity_type, entity_id) in [(source_type, source_id), (target_type, target_id)] {
+        if fetch_entity_title(&conn, entity_type, entity_id)?.is_none() {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("记录不存在: {} {}", entity_type, entity_id))
+            ));
+        }
+    }
+    
+    // 关联无方向：已存在反向关联时直接更新
+    let existing: Option<i64> = conn.query_row(
+        "SELECT id FROM entity_links
+         WHERE (source_type = ?1 AND source_id = ?2 AND target_type = ?3 AND target_id = ?4)
+            OR (source_type = ?3 AND source_id = ?4 AND target_type = ?1 AND target_id = ?2)",
+        rusqlite::params![source_type, source_id, target_type, target_id],
+        |row| row.get(0)
+    ).ok();
+    
+    if let Some(link_id) = existing {
+        conn.execute("UPDATE entity_links SET label = ?1 WHERE id = ?2", rusqlite::params![label, link_id])?;
+        return Ok(link_id);
+    }
+    
+    conn.execute(
+        "INSERT INTO entity_links (source_type, source_id, target_type, target_id, label) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![source_type, source_id, target_type, target_id, label],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 取消关联
+pub fn unlink_entities(link_id: i64) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM entity_links WHERE id = ?1", [link_id])?;
+    Ok(())
+}
+
+// 获取与某条记录相关的所有条目（已删除的记录会被跳过）
+pub fn fetch_related_items(entity_type: &str, entity_id: i32) -> Result<Vec<RelatedItem>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    if entity_table(entity_type).is_none() {
+        return Err(unknown_entity_type(entity_type));
+    }
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, target_type, target_id, label, created_at FROM entity_links
+         WHERE source_type = ?1 AND source_id = ?2
+         UNION ALL
+         SELECT id, source_type, source_id, label, created_at FROM entity_links
+         WHERE target_type = ?1 AND target_id = ?2
+         ORDER BY created_at DESC"
+    )?;
+    
+    let links: Vec<(i64, String, i32, Option<String>, String)> = stmt.query_map(
+        rusqlite::params![entity_type, entity_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    )?.filter_map(|r| r.ok()).collect();
+    
+    let mut items = Vec::new();
+    for (link_id, related_type, related_id, label, created_at) in links {
+        let Ok(Some(title)) = fetch_entity_title(&conn, &related_type, related_id) else {
+            continue;
+        };
+        items.push(RelatedItem {
+            link_id,
+            entity_type: related_type,
+            entity_id: related_id,
+            title,
+            label,
+            created_at,
+        });
+    }
+    Ok(items)
+}
+
+// ==================== 关系图谱导出 ====================
+
+// 图谱节点（id 形如 project:1、contact:2、event:3）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub node_type: String,
+    pub label: String,
+}
+
+// 图谱连线
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub edge_type: String,      // member（项目成员）/ attendee（事件参与人）/ event_project / related
+    pub label: Option<String>,  // 项目角色或关联说明
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspaceGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+// 读取整个工作区的项目、联系人、事件及其关系
+pub fn fetch_workspace_graph() -> Result<WorkspaceGraph> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut nodes = Vec::new();
+    for (node_type, sql) in [
+        ("project", "SELECT id, name FROM projects ORDER BY id"),
+        ("contact", "SELECT id, name FROM contacts ORDER BY id"),
+        ("event", "SELECT id, title FROM events WHERE is_private = 0 ORDER BY id"),
+    ] {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?;
+        for (id, label) in rows.flatten() {
+            nodes.push(GraphNode { id: format!("{}:{}", node_type, id), node_type: node_type.to_string(), label });
+        }
+    }
+    
+    let mut edges = Vec::new();
+    for (edge_type, sql) in [
+        ("member", "SELECT 'project', project_id, 'contact', contact_id, role FROM projects_contacts"),
+        ("attendee", "SELECT 'event', event_id, 'contact', contact_id, NULL FROM events_contacts"),
+        ("event_project", "SELECT 'event', event_id, 'project', project_id, NULL FROM events_projects"),
+        ("related", "SELECT source_type, source_id, target_type, target_id, label FROM entity_links"),
+    ] {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                format!("{}:{}", row.get::<_, String>(0)?, row.get::<_, i32>(1)?),
+                format!("{}:{}", row.get::<_, String>(2)?, row.get::<_, i32>(3)?),
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+        for (source, target, label) in rows.flatten() {
+            edges.push(GraphEdge { source, target, edge_type: edge_type.to_string(), label });
+        }
+    }
+    
+    // 只保留两端都在图中的连线（跳过已删除的记录和图谱不包含的类型）
+    let node_ids: std::collections::HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+    edges.retain(|e| node_ids.contains(e.source.as_str()) && node_ids.contains(e.target.as_str()));
+    
+    Ok(WorkspaceGraph { nodes, edges })
+}
+
+// ==================== 参与人推荐 ====================
+
+// 推荐的事件参与人
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactSuggestion {
+    pub contact: Contact,
+    pub score: f64,
+    pub reasons: Vec<String>,
+}
+
+// 最多返回的推荐数量
+const MAX_CONTACT_SUGGESTIONS: usize = 10;
+
+// 按项目成员、历史事件共同出现、标题/描述中提到姓名为联系人打分
+pub fn suggest_event_contacts(
+    project_id: Option<i32>,
+    text: &str,
+    selected_contact_ids: &[i32],
+) -> Result<Vec<ContactSuggestion>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM contacts c", CONTACT_COLUMNS))?;
+    let contacts: Vec<Contact> = stmt.query_map([], contact_from_row)?.filter_map(|r| r.ok()).collect();
+    
+    // 项目成员及其在该项目事件中出现的次数
+    let mut members = std::collections::HashSet::new();
+    let mut project_event_counts = std::collections::HashMap::new();
+    if let Some(pid) = project_id {
+        let mut stmt = conn.prepare_cached("SELECT contact_id FROM projects_contacts WHERE project_id = ?1")?;
+        members.extend(stmt.query_map([pid], |row| row.get::<_, i32>(0))?.flatten());
+        
+        let mut stmt = conn.prepare_cached(
+            "SELECT ec.contact_id, COUNT(DISTINCT ec.event_id)
+             FROM events_contacts ec
+             INNER JOIN events_projects ep ON ep.event_id = ec.event_id
+             WHERE ep.project_id = ?1
+             GROUP BY ec.contact_id"
+        )?;
+        project_event_counts.extend(stmt.query_map([pid], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?)))?.flatten());
+    }
+    
+    // 与已选参与人共同参加过的事件数
+    let mut co_occurrence: std::collections::HashMap<i32, i32> = std::collections::HashMap::new();
+    let mut stmt = conn.prepare_cached(
+        "SELECT other.contact_id, COUNT(DISTINCT other.event_id)
+         FROM events_contacts chosen
+         INNER JOIN events_contacts other ON other.event_id = chosen.event_id AND other.contact_id != chosen.contact_id
+         WHERE chosen.contact_id = ?1
+         GROUP BY other.contact_id"
+    )?;
+    for selected_id in selected_contact_ids {
+        for (contact_id, count) in stmt.query_map([selected_id], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?)))?.flatten() {
+            *co_occurrence.entry(contact_id).or_insert(0) += count;
+        }
+    }
+    
+    let text = text.to_lowercase();
+    let mut suggestions: Vec<ContactSuggestion> = contacts.into_iter()
+        .filter(|c| !selected_contact_ids.contains(&c.id))
+        .filter_map(|contact| {
+            let mut score = 0.0;
+            let mut reasons = Vec::new();
+            
+            let name = contact.name.trim().to_lowercase();
+            if name.chars().count() >= 2 && text.contains(&name) {
+                score += 5.0;
+                reasons.push("标题或描述中提到".to_string());
+            }
+            if members.contains(&contact.id) {
+                score += 2.0;
+                reasons.push("项目成员".to_string());
+            }
+            if let Some(&count) = project_event_counts.get(&contact.id) {
+                // 次数越多加分越少，避免老成员永远排在最前
+                score += (count as f64).ln_1p();
+                reasons.push(format!("参加过该项目 {} 次事件", count));
+            }
+            if let Some(&count) = co_occurrence.get(&contact.id) {
+                score += 1.5 * (count as f64).ln_1p();
+                reasons.push(format!("与已选参与人共同出现 {} 次", count));
+            }
+            
+            (score > 0.0).then_some(ContactSuggestion { contact, score, reasons })
+        })
+        .collect();
+    
+    suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| a.contact.name.cmp(&b.contact.name)));
+    suggestions.truncate(MAX_CONTACT_SUGGESTIONS);
+    Ok(suggestions)
+}
+
+// ==================== 联系人提及识别 ====================
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// 在文本中查找提到的联系人姓名，返回联系人ID
+// 中文姓名直接按子串匹配；英文姓名要求前后不是字母数字（避免 "Al" 匹配到 "Also"）；
+// 优先匹配较长的姓名，被长姓名覆盖的位置不再匹配短姓名（"张三丰" 不会同时命中 "张三"）
+pub fn detect_mentions(text: &str, contacts: &[Contact]) -> Vec<i32> {
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    let mut covered = vec![false; chars.len()];
+    
+    let mut candidates: Vec<(Vec<char>, i32)> = contacts.iter()
+        .map(|c| (c.name.trim().to_lowercase().chars().collect::<Vec<char>>(), c.id))
+        .filter(|(name, _)| name.len() >= 2)
+        .collect();
+    candidates.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    
+    let mut found = Vec::new();
+    for (name, contact_id) in candidates {
+        let needs_boundary = name.iter().all(|c| c.is_ascii());
+        let mut matched = false;
+        
+        for start in 0..chars.len().saturating_sub(name.len() - 1) {
+            let end = start + name.len();
+            if chars[start..end] != name[..] || covered[start..end].iter().any(|&c| c) {
+                continue;
+            }
+            if needs_boundary {
+                let before_ok = start == 0 || !is_word_char(chars[start - 1]);
+                let after_ok = end == chars.len() || !is_word_char(chars[end]);
+                if !before_ok || !after_ok {
+                    continue;
+                }
+            }
+            covered[start..end].iter_mut().for_each(|c| *c = true);
+            matched = true;
+        }
+        
+        if matched && !found.contains(&contact_id) {
+            found.push(contact_id);
+        }
+    }
+    found
+}
+
+// 识别事件标题和描述中提到、但尚未关联到事件的联系人
+pub fn detect_event_mentions(event_id: i32) -> Result<Vec<Contact>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let (title, description): (String, Option<String>) = conn.query_row(
+        "SELECT title, description FROM events WHERE id = ?1",
+        [event_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM contacts c
+         WHERE c.id NOT IN (SELECT contact_id FROM events_contacts WHERE event_id = ?1)",
+        CONTACT_COLUMNS
+    ))?;
+    let contacts: Vec<Contact> = stmt.query_map([event_id], contact_from_row)?.filter_map(|r| r.ok()).collect();
+    
+    let text = format!("{}\n{}", title, description.unwrap_or_default());
+    let mentioned = detect_mentions(&text, &contacts);
+    
+    Ok(contacts.into_iter().filter(|c| mentioned.contains(&c.id)).collect())
+}
+
+// ==================== 项目健康度 ====================
+
+// 项目健康度
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectHealth {
+    pub project_id: i32,
+    pub project_name: String,
+    pub score: i32,                        // 0-100，越高越健康
+    pub level: String,                     // 良好 / 关注 / 风险
+    pub overdue_activities: i64,
+    pub days_since_last_event: Option<i64>, // 从未有事件时为 None
+    pub stalled_reminders: i64,            // 已过期超过一天仍未处理的提醒
+    pub flags: Vec<String>,
+}
+
+// 根据各项指标计算健康分和风险提示
+fn score_project_health(overdue_activities: i64, days_since_last_event: Option<i64>, stalled_reminders: i64) -> (i32, String, Vec<String>) {
+    let mut score = 100;
+    let mut flags = Vec::new();
+    
+    if overdue_activities > 0 {
+        score -= (overdue_activities * 10).min(40) as i32;
+        flags.push(format!("{} 个活动已逾期", overdue_activities));
+    }
+    
+    match days_since_last_event {
+        Some(days) if days > 60 => {
+            score -= 40;
+            flags.push(format!("已 {} 天没有新事件", days));
+        }
+        Some(days) if days > 30 => {
+            score -= 25;
+            flags.push(format!("已 {} 天没有新事件", days));
+        }
+        Some(days) if days > 14 => {
+            score -= 10;
+            flags.push(format!("已 {} 天没有新事件", days));
+        }
+        Some(_) => {}
+        None => {
+            score -= 20;
+            flags.push("还没有任何事件记录".to_string());
+        }
+    }
+    
+    if stalled_reminders > 0 {
+        score -= (stalled_reminders * 5).min(20) as i32;
+        flags.push(format!("{} 个提醒过期未处理", stalled_reminders));
+    }
+    
+    let score = score.max(0);
+    let level = if score >= 80 {
+        "良好"
+    } else if score >= 50 {
+        "关注"
+    } else {
+        "风险"
+    };
+    (score, level.to_string(), flags)
+}
+
+// 计算所有项目的健康度（按分数从低到高，需要关注的排在前面；include_archived 为 false 时不含已归档项目）
+pub fn fetch_project_health(include_archived: bool) -> Result<Vec<ProjectHealth>> {
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let today_str = today.format("%Y-%m-%d").to_string();
+    let stalled_before = (now - chrono::Duration::days(1)).format("%Y-%m-%d %H:%M:%S").to_string();
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    // ?1 为今天，?2 为提醒视为停滞的时间点
+    let query = ListQuery::new(
+            "SELECT p.id, p.name,
+                (SELECT MAX(substr(e.event_date, 1, 10)) FROM events e
+                 INNER JOIN events_projects ep ON ep.event_id = e.id
+                 WHERE ep.project_id = p.id AND substr(e.event_date, 1, 10) <= ?1),
+                (SELECT COUNT(*) FROM events e
+                 INNER JOIN events_projects ep ON ep.event_id = e.id
+                 WHERE ep.project_id = p.id
+                 AND e.reminder_time IS NOT NULL
+                 AND e.reminder_time < ?2
+                 AND e.reminder_acknowledged_at IS NULL
+                 AND (e.reminder_triggered = 0 OR e.reminder_insistent = 1))
+             FROM projects p"
+        )
+        .archived("p.archived", include_archived);
+    let mut stmt = conn.prepare_cached(&query.sql())?;
+    
+    let rows = stmt.query_map(rusqlite::params![today_str, stalled_before], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    let overdue_counts = overdue_activity_counts(&conn, &today_str)?;
+    
+    let mut results = Vec::new();
+    for (project_id, project_name, last_event_date, stalled_reminders) in rows.flatten() {
+        let overdue_activities = overdue_counts.get(&project_id).copied().unwrap_or(0);
+        let days_since_last_event = last_event_date
+            .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+            .map(|d| (today - d).num_days());
+        let (score, level, flags) = score_project_health(overdue_activities, days_since_last_event, stalled_reminders);
+        results.push(ProjectHealth {
+            project_id,
+            project_name,
+            score,
+            level,
+            overdue_activities,
+            days_since_last_event,
+            stalled_reminders,
+            flags,
+        });
+    }
+    
+    results.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.project_name.cmp(&b.project_name)));
+    Ok(results)
+}
+
+// ==================== 项目速度 ====================
+
+// 每周完成的活动数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeeklyCompletion {
+    pub week_start: String,  // 该周第一天 YYYY-MM-DD（周起始日见设置）
+    pub completed: i64,
+    pub cumulative: i64,     // 截至本周累计完成数（燃起图）
+}
+
+// 项目速度与完成预测
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectVelocity {
+    pub project_id: i32,
+    pub weeks: Vec<WeeklyCompletion>,
+    pub average_per_week: f64,
+    pub trend: f64,                          // 每周完成数的线性趋势（正数表示加快）
+    pub remaining: i64,                      // 未完成的活动数
+    pub estimated_weeks_remaining: Option<f64>,
+    pub estimated_finish_date: Option<String>,
+}
+
+// 最小二乘法计算斜率
+fn linear_trend(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (mut num, mut den) = (0.0, 0.0);
+    for (i, y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        num += dx * (y - mean_y);
+        den += dx * dx;
+    }
+    if den == 0.0 { 0.0 } else { num / den }
+}
+
+// 统计项目最近若干周（含本周）每周完成的活动数，并按平均速度预测剩余活动的完成时间
+pub fn fetch_project_velocity(project_id: i32, weeks: u32) -> Result<ProjectVelocity> {
+    let weeks = weeks.clamp(1, 104);
+    let today = chrono::Local::now().date_naive();
+    let week_start = load_display_preferences()?.week_start;
+    let this_week_start = today - chrono::Duration::days(days_since_week_start(today, week_start));
+    let first_week_start = this_week_start - chrono::Duration::weeks(weeks as i64 - 1);
+    let range_start = format!("{} 00:00:00", first_week_start.format("%Y-%m-%d"));
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    // 统计区间开始前已完成的数量，作为累计起点
+    let completed_before: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM project_activities
+         WHERE project_id = ?1 AND status = '已完成' AND completed_at IS NOT NULL AND completed_at < ?2",
+        rusqlite::params![project_id, range_start],
+        |row| row.get(0)
+    )?;
+    
+    let remaining: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM project_activities WHERE project_id = ?1 AND status != '已完成'",
+        [project_id],
+        |row| row.get(0)
+    )?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT substr(completed_at, 1, 10) FROM project_activities
+         WHERE project_id = ?1 AND status = '已完成' AND completed_at IS NOT NULL AND completed_at >= ?2"
+    )?;
+    let completed_dates: Vec<String> = stmt.query_map(rusqlite::params![project_id, range_start], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    let mut counts = vec![0i64; weeks as usize];
+    for date in completed_dates {
+        if let Ok(d) = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            let index = ((d - first_week_start).num_days() / 7) as usize;
+            if let Some(count) = counts.get_mut(index) {
+                *count += 1;
+            }
+        }
+    }
+    
+    let mut cumulative = completed_before;
+    let weekly: Vec<WeeklyCompletion> = counts.iter().enumerate().map(|(i, &completed)| {
+        cumulative += completed;
+        WeeklyCompletion {
+            week_start: (first_week_start + chrono::Duration::weeks(i as i64)).format("%Y-%m-%d").to_string(),
+            completed,
+            cumulative,
+        }
+    }).collect();
+    
+    let values: Vec<f64> = counts.iter().map(|&c| c as f64).collect();
+    let average_per_week = values.iter().sum::<f64>() / values.len() as f64;
+    let estimated_weeks_remaining = (average_per_week > 0.0).then(|| remaining as f64 / average_per_week);
+    let estimated_finish_date = estimated_weeks_remaining.map(|w| {
+        (today + chrono::Duration::days((w * 7.0).ceil() as i64)).format("%Y-%m-%d").to_string()
+    });
+    
+    Ok(ProjectVelocity {
+        project_id,
+        weeks: weekly,
+        average_per_week,
+        trend: linear_trend(&values),
+        remaining,
+        estimated_weeks_remaining,
+        estimated_finish_date,
+    })
+}
+
+// ==================== 节假日与工作日 ====================
+
+// 节假日记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: String,
+    pub name: String,
+    pub is_workday: bool,
+}
+
+// 查找相邻工作日时最多检查的天数
+const WORKING_DAY_SEARCH_DAYS: usize = 31;
+
+// 工作日日历：周一至周五上班，周末休息，节假日表中的记录优先
+pub struct WorkCalendar {
+    overrides: std::collections::HashMap<chrono::NaiveDate, bool>,  // 日期 -> 是否上班
+}
+
+impl WorkCalendar {
+    fn load(conn: &Connection) -> Result<Self> {
+        let mut stmt = conn.prepare_cached("SELECT date, is_workday FROM holidays")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?)))?;
+        let overrides = rows.flatten()
+            .filter_map(|(date, workday)| {
+                chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok().map(|d| (d, workday != 0))
+            })
+            .collect();
+        Ok(WorkCalendar { overrides })
+    }
+    
+    pub fn is_working_day(&self, date: chrono::NaiveDate) -> bool {
+        self.overrides.get(&date).copied()
+            .unwrap_or(!matches!(date.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun))
+    }
+    
+    // 之后（不含当天）的第一个工作日；最长的连休不会超过一个月，一个月内都不是工作日时返回 None
+    pub fn next_working_day(&self, date: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+        let mut day = date;
+        for _ in 0..WORKING_DAY_SEARCH_DAYS {
+            day = day.succ_opt()?;
+            if self.is_working_day(day) {
+                return Some(day);
+            }
+        }
+        None
+    }
+    
+    // 之前（不含当天）的最后一个工作日，一个月内都不是工作日时返回 None
+    pub fn previous_working_day(&self, date: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+        let mut day = date;
+        for _ in 0..WORKING_DAY_SEARCH_DAYS {
+            day = day.pred_opt()?;
+            if self.is_working_day(day) {
+                return Some(day);
+            }
+        }
+        None
+    }
+    
+    // 当天是工作日则返回当天，否则返回之后的第一个工作日（找不到时返回当天）
+    pub fn on_or_after(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+        if self.is_working_day(date) { date } else { self.next_working_day(date).unwrap_or(date) }
+    }
+}
+
+// 各项目的逾期活动数：截止日落在节假日时顺延到下一个工作日再判断是否逾期
+fn overdue_activity_counts(conn: &Connection, today: &str) -> Result<std::collections::HashMap<i32, i64>> {
+    let Ok(today_date) = chrono::NaiveDate::parse_from_str(today, "%Y-%m-%d") else {
+        return Ok(std::collections::HashMap::new());
+    };
+    let calendar = WorkCalendar::load(conn)?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT project_id, substr(estimated_completion_date, 1, 10) FROM project_activities
+         WHERE estimated_completion_date IS NOT NULL
+         AND estimated_completion_date != ''
+         AND substr(estimated_completion_date, 1, 10) < ?1
+         AND status != '已完成'"
+    )?;
+    let rows = stmt.query_map([today], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?;
+    
+    let mut counts = std::collections::HashMap::new();
+    for (project_id, due) in rows.flatten() {
+        let Ok(due_date) = chrono::NaiveDate::parse_from_str(&due, "%Y-%m-%d") else {
+            continue;
+        };
+        if calendar.on_or_after(due_date) < today_date {
+            *counts.entry(project_id).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+// 获取一段时间内的节假日和调休记录（按日期排序）
+pub fn fetch_holidays(start_date: &str, end_date: &str) -> Result<Vec<Holiday>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT date, name, is_workday FROM holidays WHERE date >= ?1 AND date <= ?2 ORDER BY date"
+    )?;
+    let holidays = stmt.query_map(rusqlite::params![start_date, end_date], |row| {
+        Ok(Holiday {
+            date: row.get(0)?,
+            name: row.get(1)?,
+            is_workday: row.get::<_, i32>(2)? != 0,
+        })
+    })?.filter_map(|r| r.ok()).collect();
+    
+    Ok(holidays)
+}
+
+// 批量保存节假日（同一日期覆盖），返回保存数量
+pub fn save_holidays(holidays: &[ImportedHoliday]) -> Result<usize> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let tx = conn.transaction()?;
+    for holiday in holidays {
+        tx.execute(
+            "INSERT OR REPLACE INTO holidays (date, name, is_workday) VALUES (?1, ?2, ?3)",
+            rusqlite::params![holiday.date, holiday.name, holiday.is_workday as i32],
+        )?;
+    }
+    tx.commit()?;
+    
+    Ok(holidays.len())
+}
+
+// 删除某天的节假日记录
+pub fn delete_holiday(date: &str) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM holidays WHERE date = ?1", [date])?;
+    Ok(())
+}
+
+// 加载工作日日历
+pub fn load_work_calendar() -> Result<WorkCalendar> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    WorkCalendar::load(&conn)
+}
+
+// ==================== 应用设置 ====================
+
+// 设置项键名
+pub const SETTING_WEEK_START: &str = "week_start";          // 周起始日：monday ... sunday
+pub const SETTING_DATE_FORMAT: &str = "date_format";        // 日期显示格式（chrono 格式串）
+pub const SETTING_DATETIME_FORMAT: &str = "datetime_format"; // 日期时间显示格式
+pub const SETTING_ME_CONTACT_ID: &str = "me_contact_id";    // 代表"我"的联系人ID
+pub const SETTING_NOTIFICATION_SOUND: &str = "notification_sound"; // 提醒通知是否播放声音：on / off
+pub const SETTING_BRIEFING_NOTIFICATION: &str = "briefing_notification"; // 生成每日简报后是否发送通知：on / off（默认 off）
+pub const SETTING_ASSIGNMENT_NOTIFICATION: &str = "assignment_notification"; // 分配活动负责人后是否发送通知：on / off（默认 off）
+pub const SETTING_AGING_THRESHOLD_DAYS: &str = "activity_aging_threshold_days"; // 进行中活动超过多少天（扣除暂停）视为老化
+
+// 读取单个设置项
+pub fn get_setting(key: &str) -> Result<Option<String>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let result = conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0));
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 读取所有设置项
+pub fn fetch_settings() -> Result<std::collections::HashMap<String, String>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_settings_with(&conn)
+}
+
+pub fn fetch_settings_with(conn: &Connection) -> Result<std::collections::HashMap<String, String>> {
+    let mut stmt = conn.prepare_cached("SELECT key, value FROM settings")?;
+    let settings = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(settings)
+}
+
+// 校验设置值（已知设置项格式不正确时返回错误信息）
+fn validate_setting(key: &str, value: &str) -> std::result::Result<(), String> {
+    match key {
+        SETTING_WEEK_START => parse_weekday(value).map(|_| ()).ok_or(format!("无效的周起始日: {}", value)),
+        SETTING_DATE_FORMAT | SETTING_DATETIME_FORMAT => {
+            let invalid = chrono::format::StrftimeItems::new(value)
+                .any(|item| matches!(item, chrono::format::Item::Error));
+            if invalid || value.trim().is_empty() {
+                Err(format!("无效的日期格式: {}", value))
+            } else {
+                Ok(())
+            }
+        }
+        SETTING_ME_CONTACT_ID => value.trim().parse::<i32>().map(|_| ()).map_err(|_| format!("无效的联系人ID: {}", value)),
+        SETTING_NOTIFICATION_SOUND => parse_switch(value).map(|_| ()).ok_or(format!("无效的声音开关: {}", value)),
+        SETTING_BRIEFING_NOTIFICATION => parse_switch(value).map(|_| ()).ok_or(format!("无效的简报通知开关: {}", value)),
+        SETTING_ASSIGNMENT_NOTIFICATION => parse_switch(value).map(|_| ()).ok_or(format!("无效的分配通知开关: {}", value)),
+        SETTING_AGING_THRESHOLD_DAYS => value.trim().parse::<i64>().ok().filter(|d| *d > 0).map(|_| ()).ok_or(format!("无效的老化天数: {}", value)),
+        crate::format::SETTING_NUMBER_FORMAT => crate::format::parse_number_format(value).map(|_| ()).ok_or(format!("无效的数字格式: {}", value)),
+        crate::file_policy::SETTING_BLOCKED_EXTENSIONS => crate::file_policy::parse_extensions(value).map(|_| ()),
+        crate::redaction::SETTING_REDACTION_PROFILES => crate::redaction::parse_profiles(value).map(|_| ()),
+        crate::search::SETTING_SEARCH_RANKING => crate::search::parse_ranking(value).map(|_| ()),
+        crate::money::SETTING_DEFAULT_CURRENCY => crate::money::parse_currency(value).map(|_| ()).ok_or(format!("无效的币种: {}", value)),
+        crate::format::SETTING_FILE_SIZE_UNITS => crate::format::parse_size_units(value).map(|_| ()).ok_or(format!("无效的文件大小单位: {}", value)),
+        _ if key.starts_with(crate::windows::WINDOW_STATE_PREFIX) => crate::windows::parse_state(value).map(|_| ()),
+        _ if key.starts_with(crate::shortcuts::SHORTCUT_PREFIX) => {
+            let id = &key[crate::shortcuts::SHORTCUT_PREFIX.len()..];
+            if crate::shortcuts::find_action(id).is_none() {
+                return Err(format!("未知的快捷键动作: {}", id));
+            }
+            // 空字符串表示停用
+            if value.trim().is_empty() {
+                return Ok(());
+            }
+            crate::shortcuts::normalize_accelerator(value).map(|_| ())
+        }
+        _ if key.starts_with(crate::onboarding::ONBOARDING_PREFIX) => {
+            let id = &key[crate::onboarding::ONBOARDING_PREFIX.len()..];
+            if crate::onboarding::find_step(id).is_none() {
+                return Err(format!("未知的引导步骤: {}", id));
+            }
+            crate::onboarding::parse_status(value).map(|_| ())
+        }
+        _ if key.starts_with(crate::scheduler::JOB_SCHEDULE_PREFIX) => {
+            let name = &key[crate::scheduler::JOB_SCHEDULE_PREFIX.len()..];
+            if crate::scheduler::find_job(name).is_none() {
+                return Err(format!("未知的后台任务: {}", name));
+            }
+            crate::scheduler::CronSchedule::parse(value).map(|_| ())
+        }
+        _ => Ok(()),
+    }
+}
+
+// 保存设置项（value 为 None 时删除，恢复默认值）
+pub fn set_setting(key: &str, value: Option<&str>) -> Result<()> {
+    if let Some(v) = value {
+        validate_setting(key, v).map_err(|msg| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(msg)
+        ))?;
+    }
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    match value {
+        Some(v) => conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+            rusqlite::params![key, v],
+        )?,
+        None => conn.execute("DELETE FROM settings WHERE key = ?1", [key])?,
+    };
+    Ok(())
+}
+
+// 解析周几（支持英文名和 1-7，1 表示周一）
+fn parse_weekday(value: &str) -> Option<chrono::Weekday> {
+    match value.trim().to_lowercase().as_str() {
+        "monday" | "mon" | "1" => Some(chrono::Weekday::Mon),
+        "tuesday" | "tue" | "2" => Some(chrono::Weekday::Tue),
+        "wednesday" | "wed" | "3" => Some(chrono::Weekday::Wed),
+        "thursday" | "thu" | "4" => Some(chrono::Weekday::Thu),
+        "friday" | "fri" | "5" => Some(chrono::Weekday::Fri),
+        "saturday" | "sat" | "6" => Some(chrono::Weekday::Sat),
+        "sunday" | "sun" | "7" | "0" => Some(chrono::Weekday::Sun),
+        _ => None,
+    }
+}
+
+// 解析开关类设置值
+fn parse_switch(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "on" | "true" | "1" => Some(true),
+        "off" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+// 某天距离本周第一天的天数
+pub fn days_since_week_start(date: chrono::NaiveDate, week_start: chrono::Weekday) -> i64 {
+    ((date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7) as i64
+}
+
+// 日期和数字显示相关设置
+pub struct DisplayPreferences {
+    pub week_start: chrono::Weekday,
+    pub date_format: String,
+    pub datetime_format: String,
+    pub numbers: crate::format::NumberFormat,
+}
+
+impl DisplayPreferences {
+    // 根据设置项构造（未设置的项使用默认值）
+    pub fn from_settings(settings: &std::collections::HashMap<String, String>) -> Self {
+        DisplayPreferences {
+            week_start: settings.get(SETTING_WEEK_START)
+                .and_then(|v| parse_weekday(v))
+                .unwrap_or(chrono::Weekday::Mon),
+            date_format: settings.get(SETTING_DATE_FORMAT)
+                .cloned()
+                .unwrap_or_else(|| "%Y-%m-%d".to_string()),
+            datetime_format: settings.get(SETTING_DATETIME_FORMAT)
+                .cloned()
+                .unwrap_or_else(|| "%Y年%m月%d日 %H:%M".to_string()),
+            numbers: crate::format::NumberFormat::from_settings(settings),
+        }
+    }
+
+    // 按设置格式显示 YYYY-MM-DD 日期（无法解析时原样返回）
+    pub fn format_date(&self, date: &str) -> String {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| d.format(&self.date_format).to_string())
+            .unwrap_or_else(|_| date.to_string())
+    }
+}
+
+// 读取日期显示设置（未设置时为周一开始、YYYY-MM-DD）
+pub fn load_display_preferences() -> Result<DisplayPreferences> {
+    Ok(DisplayPreferences::from_settings(&fetch_settings()?))
+}
+
+// ==================== 我的视图 ====================
+
+// 设置中指定为"我"的联系人（未设置时为 None）
+pub fn get_me_contact_id() -> Result<Option<i32>> {
+    Ok(get_setting(SETTING_ME_CONTACT_ID)?.and_then(|v| v.trim().parse().ok()))
+}
+
+// 分配给"我"的未完成活动（按预计完成日期排序，没有日期的排在最后）
+pub fn fetch_my_activities(me_contact_id: i32, include_archived: bool) -> Result<Vec<(ActivityWithDetails, String)>> {
+    let (activities, project_names) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let activities = ListQuery::new(format!(
+                "SELECT {} FROM project_activities a INNER JOIN activities_contacts ac ON ac.activity_id = a.id",
+                ACTIVITY_COLUMNS
+            ))
+            .filter("ac.contact_id = ?", me_contact_id)
+            .condition("a.status != '已完成'")
+            .archived_project("a.project_id", include_archived)
+            .order_by("a.estimated_completion_date IS NULL OR a.estimated_completion_date = ''")
+            .order_by("a.estimated_completion_date")
+            .order_by("a.created_at DESC")
+            .fetch(&conn, activity_from_row)?;
+        
+        let mut p_stmt = conn.prepare_cached("SELECT id, name FROM projects")?;
+        let project_names: std::collections::HashMap<i32, String> = p_stmt
+            .query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        
+        (activities, project_names)
+    };
+    
+    let mut results = Vec::new();
+    for activity in activities {
+        let assignees = fetch_assignees_for_activity(activity.id)?;
+        let project_name = project_names.get(&activity.project_id).cloned().unwrap_or_default();
+        results.push((ActivityWithDetails::new(activity, assignees), project_name));
+    }
+    Ok(results)
+}
+
+// "我"参与的事件（日期范围可选，按日期升序）
+pub fn fetch_my_events(me_contact_id: i32, start_date: Option<&str>, end_date: Option<&str>, include_archived: bool) -> Result<Vec<EventWithDetails>> {
+    let (events, ctx) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let events = ListQuery::new(format!(
+                "SELECT DISTINCT {} FROM events e INNER JOIN events_contacts ec ON e.id = ec.event_id",
+                EVENT_COLUMNS
+            ))
+            .filter("ec.contact_id = ?", me_contact_id)
+            .filter_opt("substr(e.event_date, 1, 10) >= ?", start_date.map(str::to_string))
+            .filter_opt("substr(e.event_date, 1, 10) <= ?", end_date.map(str::to_string))
+            .archived_project("e.project_id", include_archived)
+            .order_by("e.event_date")
+            .fetch(&conn, event_from_row)?;
+        
+        (events, EventDisplayContext::load(&conn)?)
+    };
+    
+    assemble_event_details(events, &ctx)
+}
+
+// ==================== 活动模板与周期活动 ====================
+
+// 活动模板
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityTemplate {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub due_offset_days: Option<i32>,
+    pub recurrence: Option<String>,
+    pub last_generated_on: Option<String>,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const TEMPLATE_COLUMNS: &str =
+    "id, project_id, name, description, due_offset_days, recurrence, last_generated_on, active, created_at, updated_at";
+
+fn template_from_row(row: &rusqlite::Row) -> Result<ActivityTemplate> {
+    Ok(ActivityTemplate {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        due_offset_days: row.get(4)?,
+        recurrence: row.get(5)?,
+        last_generated_on: row.get(6)?,
+        active: row.get::<_, Option<i32>>(7)?.unwrap_or(1) != 0,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+// 周期规则
+enum Recurrence {
+    Daily,
+    Weekly(chrono::Weekday),
+    Monthly(u32),  // 每月几号，超过当月天数时取月末
+}
+
+// 解析周期规则：daily、weekly:1-7（1 为周一）、monthly:1-31
+fn parse_recurrence(value: &str) -> Option<Recurrence> {
+    let value = value.trim().to_lowercase();
+    if value == "daily" {
+        return Some(Recurrence::Daily);
+    }
+    let (kind, arg) = value.split_once(':')?;
+    match kind {
+        "weekly" => parse_weekday(arg).map(Recurrence::Weekly),
+        "monthly" => arg.trim().parse::<u32>().ok().filter(|d| (1..=31).contains(d)).map(Recurrence::Monthly),
+        _ => None,
+    }
+}
+
+// 某月的第 day 天（超过月末时取月末）
+fn clamp_month_day(year: i32, month: u32, day: u32) -> Option<chrono::NaiveDate> {
+    (1..=day).rev().find_map(|d| chrono::NaiveDate::from_ymd_opt(year, month, d))
+}
+
+// 不晚于 today 的最近一次周期日期
+fn latest_occurrence(recurrence: &Recurrence, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+    match recurrence {
+        Recurrence::Daily => Some(today),
+        Recurrence::Weekly(weekday) => {
+            let back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            Some(today - chrono::Duration::days(back as i64))
+        }
+        Recurrence::Monthly(day) => {
+            let this_month = clamp_month_day(today.year(), today.month(), *day)?;
+            if this_month <= today {
+                return Some(this_month);
+            }
+            let (year, month) = if today.month() == 1 { (today.year() - 1, 12) } else { (today.year(), today.month() - 1) };
+            clamp_month_day(year, month, *day)
+        }
+    }
+}
+
+fn invalid_recurrence(value: &str) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("无效的周期规则: {}（支持 daily、weekly:1-7、monthly:1-31）", value))
+    )
+}
+
+// 模板生成的活动最多多少天后到期（约 10 年）
+const MAX_TEMPLATE_DUE_OFFSET_DAYS: i32 = 3650;
+
+// 校验模板的到期天数和周期规则
+fn validate_template_schedule(due_offset_days: Option<i32>, recurrence: Option<&str>) -> Result<()> {
+    if let Some(days) = due_offset_days.filter(|d| !(0..=MAX_TEMPLATE_DUE_OFFSET_DAYS).contains(d)) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的到期天数: {}（应为 0-{}）", days, MAX_TEMPLATE_DUE_OFFSET_DAYS))
+        ));
+    }
+    if let Some(r) = recurrence {
+        parse_recurrence(r).ok_or_else(|| invalid_recurrence(r))?;
+    }
+    Ok(())
+}
+
+// 按周期日期和到期天数计算预计完成日期，超出日期范围时报错
+fn template_due_date(occurrence: chrono::NaiveDate, due_offset_days: i32) -> Result<String> {
+    chrono::Duration::try_days(due_offset_days as i64)
+        .and_then(|days| occurrence.checked_add_signed(days))
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .ok_or_else(|| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("到期天数超出日期范围: {}", due_offset_days))
+        ))
+}
+
+// 新建活动模板
+pub fn insert_activity_template(
+    project_id: i32,
+    name: &str,
+    description: Option<&str>,
+    due_offset_days: Option<i32>,
+    recurrence: Option<&str>,
+) -> Result<i64> {
+    let recurrence = recurrence.map(|r| r.trim()).filter(|r| !r.is_empty());
+    validate_template_schedule(due_offset_days, recurrence)?;
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT INTO activity_templates (project_id, name, description, due_offset_days, recurrence) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![project_id, name, description, due_offset_days, recurrence],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 更新活动模板
+pub fn update_activity_template(
+    template_id: i32,
+    name: &str,
+    description: Option<&str>,
+    due_offset_days: Option<i32>,
+    recurrence: Option<&str>,
+    active: bool,
+) -> Result<()> {
+    let recurrence = recurrence.map(|r| r.trim()).filter(|r| !r.is_empty());
+    validate_template_schedule(due_offset_days, recurrence)?;
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE activity_templates SET name = ?1, description = ?2, due_offset_days = ?3, recurrence = ?4, active = ?5, updated_at = CURRENT_TIMESTAMP WHERE id = ?6",
+        rusqlite::params![name, description, due_offset_days, recurrence, active as i32, template_id],
+    )?;
+    Ok(())
+}
+
+// 删除活动模板（已生成的活动保留，只解除来源关联）
+pub fn delete_activity_template(template_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("UPDATE project_activities SET template_id = NULL WHERE template_id = ?1", [template_id])?;
+    conn.execute("DELETE FROM activity_templates WHERE id = ?1", [template_id])?;
+    Ok(())
+}
+
+// 获取活动模板（可按项目过滤）
+pub fn fetch_activity_templates(project_id: Option<i32>) -> Result<Vec<ActivityTemplate>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    ListQuery::new(format!("SELECT {} FROM activity_templates", TEMPLATE_COLUMNS))
+        .filter_opt("project_id = ?", project_id)
+        .order_by("project_id")
+        .order_by("name")
+        .fetch(&conn, template_from_row)
+}
+
+// 获取由模板生成的所有活动
+pub fn fetch_activities_for_template(template_id: i32) -> Result<Vec<ActivityWithDetails>> {
+    let activities = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM project_activities a WHERE a.template_id = ?1 ORDER BY a.created_at DESC",
+            ACTIVITY_COLUMNS
+        ))?;
+        let activities: Vec<ProjectActivity> = stmt.query_map([template_id], activity_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        activities
+    };
+    
+    let mut results = Vec::new();
+    for activity in activities {
+        let assignees = fetch_assignees_for_activity(activity.id)?;
+        results.push(ActivityWithDetails::new(activity, assignees));
+    }
+    Ok(results)
+}
+
+// 按模板创建一条活动（待分配状态），occurrence_date 为周期日期（手动创建时为今天）
+pub fn instantiate_activity_template(template_id: i32, occurrence_date: Option<chrono::NaiveDate>) -> Result<i64> {
+    let is_scheduled = occurrence_date.is_some();
+    let occurrence = occurrence_date.unwrap_or_else(|| chrono::Local::now().date_naive());
+    
+    let (activity_id, template, project_name) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let template = conn.query_row(
+            &format!("SELECT {} FROM activity_templates WHERE id = ?1", TEMPLATE_COLUMNS),
+            [template_id],
+            template_from_row
+        )?;
+        
+        let due_date = template.due_offset_days
+            .map(|days| template_due_date(occurrence, days))
+            .transpose()?;
+        
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, description, estimated_completion_date, status, template_id) 
+             VALUES (?1, ?2, ?3, ?4, '待分配', ?5)",
+            rusqlite::params![template.project_id, template.name, template.description, due_date, template_id],
+        )?;
+        let activity_id = conn.last_insert_rowid();
+        
+        if is_scheduled {
+            conn.execute(
+                "UPDATE activity_templates SET last_generated_on = ?1 WHERE id = ?2",
+                rusqlite::params![occurrence.format("%Y-%m-%d").to_string(), template_id],
+            )?;
+        }
+        
+        let project_name: String = conn.query_row(
+            "SELECT name FROM projects WHERE id = ?1",
+            [template.project_id],
+            |row| row.get(0)
+        ).unwrap_or_default();
+        
+        (activity_id, template, project_name)
+    };
+    
+    log_activity_creation(activity_id, &template.name, template.project_id, &project_name, &[])?;
+    Ok(activity_id)
+}
+
+// 按周期生成到期的活动（后台任务定期调用；错过的周期只补生成最近一次）
+pub fn generate_due_recurring_activities() -> Result<Vec<(i64, String)>> {
+    let today = chrono::Local::now().date_naive();
+    let templates = fetch_activity_templates(None)?;
+    
+    let mut generated = Vec::new();
+    for template in templates.into_iter().filter(|t| t.active) {
+        let Some(recurrence) = template.recurrence.as_deref().and_then(parse_recurrence) else {
+            continue;
+        };
+        let Some(occurrence) = latest_occurrence(&recurrence, today) else {
+            continue;
+        };
+        
+        // 模板创建之前的周期不补生成
+        let created_on = template.created_at.get(..10)
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        if created_on.is_some_and(|c| occurrence < c) {
+            continue;
+        }
+        let last = template.last_generated_on.as_deref()
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        if last.is_some_and(|l| l >= occurrence) {
+            continue;
+        }
+        
+        let activity_id = instantiate_activity_template(template.id, Some(occurrence))?;
+        generated.push((activity_id, template.name));
+    }
+    Ok(generated)
+}
+
+// ==================== 模板包 ====================
+
+// 内置模板包：一组事件类型和活动模板，安装到某个项目（或为其新建的项目）中
+pub struct TemplatePack {
+    pub name: &'static str,
+    pub description: &'static str,
+    color: &'static str,
+    icon: &'static str,
+    // 事件类型：名称、颜色、图标、默认提前提醒分钟数
+    event_types: &'static [(&'static str, &'static str, &'static str, Option<i32>)],
+    // 活动模板：名称、说明、生成后多少天到期、周期规则
+    activities: &'static [(&'static str, &'static str, Option<i32>, Option<&'static str>)],
+}
+
+pub const TEMPLATE_PACKS: &[TemplatePack] = &[
+    TemplatePack {
+        name: "销售跟进",
+        description: "从线索到回款的客户跟进流程",
+        color: "#fa541c",
+        icon: "💼",
+        event_types: &[
+            ("客户拜访", "#fa541c", "🤝", Some(60)),
+            ("报价", "#faad14", "💰", None),
+            ("签约", "#389e0d", "✍️", Some(24 * 60)),
+        ],
+        activities: &[
+            ("确认线索", "确认客户需求、预算和决策人", Some(3), None),
+            ("需求调研", "整理客户现状和痛点", Some(7), None),
+            ("方案演示", "向客户演示产品和解决方案", Some(14), None),
+            ("提交报价", "准备并提交报价单", Some(21), None),
+            ("合同谈判", "商定合同条款和付款方式", Some(30), None),
+            ("签约回款", "完成签约并跟进首笔回款", Some(45), None),
+            ("每周客户跟进", "回顾本周联系情况，安排下周跟进", Some(2), Some("weekly:1")),
+        ],
+    },
+    TemplatePack {
+        name: "招聘流程",
+        description: "从发布职位到候选人入职",
+        color: "#13c2c2",
+        icon: "🧑‍💼",
+        event_types: &[
+            ("面试", "#13c2c2", "🗣️", Some(30)),
+            ("Offer", "#52c41a", "📨", None),
+        ],
+        activities: &[
+            ("发布职位", "确定职位描述并在招聘渠道发布", Some(2), None),
+            ("简历筛选", "筛选简历并安排初试", Some(7), None),
+            ("初试", "完成初试并记录评价", Some(14), None),
+            ("复试", "安排复试和背景调查", Some(21), None),
+            ("发放 Offer", "确定薪资并发放 Offer", Some(28), None),
+            ("入职准备", "准备设备、账号和入职资料", Some(35), None),
+            ("招聘进展同步", "同步各职位的候选人进展", Some(0), Some("weekly:5")),
+        ],
+    },
+    TemplatePack {
+        name: "咨询项目",
+        description: "调研、诊断、方案到交付的咨询项目",
+        color: "#2f54eb",
+        icon: "🧭",
+        event_types: &[
+            ("访谈", "#2f54eb", "🎙️", Some(30)),
+            ("工作坊", "#722ed1", "🧩", Some(60)),
+            ("汇报", "#f5222d", "📊", Some(24 * 60)),
+        ],
+        activities: &[
+            ("项目启动会", "明确目标、范围、分工和沟通机制", Some(3), None),
+            ("现状调研", "访谈关键人员，收集资料", Some(14), None),
+            ("问题诊断", "分析调研结果，形成诊断结论", Some(28), None),
+            ("方案设计", "设计改进方案并与客户确认", Some(42), None),
+            ("成果汇报", "向管理层汇报项目成果", Some(56), None),
+            ("项目周报", "整理本周进展、风险和下周计划", Some(1), Some("weekly:5")),
+            ("月度回顾", "与客户回顾当月进展和满意度", Some(3), Some("monthly:1")),
+        ],
+    },
+];
+
+// 模板包说明（用于模板库列表）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplatePackInfo {
+    pub name: String,
+    pub description: String,
+    pub event_types: Vec<String>,
+    pub activity_templates: Vec<String>,
+}
+
+// 安装模板包的结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplatePackInstall {
+    pub pack: String,
+    pub project_id: i32,
+    pub project_created: bool,
+    pub event_types_created: usize,
+    pub activity_templates_created: usize,  // 项目中已有同名模板的不重复创建
+}
+
+// 列出内置模板包
+pub fn list_template_packs() -> Vec<TemplatePackInfo> {
+    TEMPLATE_PACKS.iter().map(|pack| TemplatePackInfo {
+        name: pack.name.to_string(),
+        description: pack.description.to_string(),
+        event_types: pack.event_types.iter().map(|(name, ..)| name.to_string()).collect(),
+        activity_templates: pack.activities.iter().map(|(name, ..)| name.to_string()).collect(),
+    }).collect()
+}
+
+// 安装模板包：添加事件类型（已存在的不覆盖），并在项目中创建活动模板；
+// 未指定项目时新建一个以模板包命名的项目
+pub fn install_template_pack(name: &str, project_id: Option<i32>) -> Result<TemplatePackInstall> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    install_template_pack_with(&mut conn, name, project_id)
+}
+
+pub fn install_template_pack_with(conn: &mut Connection, name: &str, project_id: Option<i32>) -> Result<TemplatePackInstall> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let pack = TEMPLATE_PACKS.iter().find(|p| p.name == name.trim()).ok_or_else(|| invalid(format!(
+        "未知的模板包: {}（可选 {}）",
+        name,
+        TEMPLATE_PACKS.iter().map(|p| p.name).collect::<Vec<_>>().join("、")
+    )))?;
+
+    let tx = conn.transaction()?;
+    let (project_id, project_created) = match project_id {
+        Some(id) => {
+            let exists: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)", [id], |row| row.get(0))?;
+            if !exists {
+                return Err(invalid(format!("项目不存在: {}", id)));
+            }
+            (id, false)
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO projects (name, description, color, icon) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![pack.name, pack.description, pack.color, pack.icon],
+            )?;
+            let id = tx.last_insert_rowid() as i32;
+            let desc = format!("{}，按模板包新增项目「{}」", chrono::Local::now().format("%Y年%m月%d日 %H:%M"), pack.name);
+            tx.execute(
+                "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description)
+                 VALUES ('create', 'project', ?1, ?2, ?1, ?2, ?3)",
+                rusqlite::params![id, pack.name, desc],
+            )?;
+            (id, true)
+        }
+    };
+
+    let mut event_types_created = 0;
+    for (type_name, color, icon, offset) in pack.event_types {
+        event_types_created += tx.execute(
+            "INSERT OR IGNORE INTO event_types (name, color, icon, sort_order, default_reminder_offset_minutes)
+             VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM event_types), ?4)",
+            rusqlite::params![type_name, color, icon, offset],
+        )?;
+    }
+
+    let mut activity_templates_created = 0;
+    for (template_name, description, due_offset_days, recurrence) in pack.activities {
+        activity_templates_created += tx.execute(
+            "INSERT INTO activity_templates (project_id, name, description, due_offset_days, recurrence)
+             SELECT ?1, ?2, ?3, ?4, ?5
+             WHERE NOT EXISTS(SELECT 1 FROM activity_templates WHERE project_id = ?1 AND name = ?2)",
+            rusqlite::params![project_id, template_name, description, due_offset_days, recurrence],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(TemplatePackInstall {
+        pack: pack.name.to_string(),
+        project_id,
+        project_created,
+        event_types_created,
+        activity_templates_created,
+    })
+}
+
+// ==================== 活动工时 ====================
+
+// 活动工时记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i32,
+    pub activity_id: i32,
+    pub contact_id: Option<i32>,
+    pub contact_name: Option<String>,
+    pub hours: f64,
+    pub entry_date: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+// 校验预估工时（不能为负数）
+fn validate_estimated_hours(estimated_hours: Option<f64>) -> Result<()> {
+    if estimated_hours.is_some_and(|h| !h.is_finite() || h < 0.0) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("预估工时不能为负数".to_string())
+        ));
+    }
+    Ok(())
+}
+
+// 设置活动预估工时（None 表示清除）
+pub fn set_activity_estimated_hours(activity_id: i32, estimated_hours: Option<f64>) -> Result<()> {
+    validate_estimated_hours(estimated_hours)?;
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE project_activities SET estimated_hours = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![estimated_hours, activity_id],
+    )?;
+    Ok(())
+}
+
+// 添加工时记录（日期为空时记为今天）
+pub fn insert_time_entry(
+    activity_id: i32,
+    contact_id: Option<i32>,
+    hours: f64,
+    entry_date: Option<&str>,
+    note: Option<&str>,
+) -> Result<i64> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    insert_time_entry_with(&conn, activity_id, contact_id, hours, entry_date, note)
+}
+
+pub fn insert_time_entry_with(
+    conn: &Connection,
+    activity_id: i32,
+    contact_id: Option<i32>,
+    hours: f64,
+    entry_date: Option<&str>,
+    note: Option<&str>,
+) -> Result<i64> {
+    if !hours.is_finite() || hours <= 0.0 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("工时必须大于 0".to_string())
+        ));
+    }
+    let entry_date = entry_date
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    
+    conn.execute(
+        "INSERT INTO activity_time_entries (activity_id, contact_id, hours, entry_date, note) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![activity_id, contact_id, hours, entry_date, note],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 删除工时记录
+pub fn delete_time_entry(entry_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM activity_time_entries WHERE id = ?1", [entry_id])?;
+    Ok(())
+}
+
+// 获取活动的工时记录（按日期倒序）
+pub fn fetch_time_entries(activity_id: i32) -> Result<Vec<TimeEntry>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT t.id, t.activity_id, t.contact_id, c.name, t.hours, t.entry_date, t.note, t.created_at
+         FROM activity_time_entries t
+         LEFT JOIN contacts c ON c.id = t.contact_id
+         WHERE t.activity_id = ?1
+         ORDER BY t.entry_date DESC, t.id DESC"
+    )?;
+    let entries = stmt.query_map([activity_id], |row| {
+        Ok(TimeEntry {
+            id: row.get(0)?,
+            activity_id: row.get(1)?,
+            contact_id: row.get(2)?,
+            contact_name: row.get(3)?,
+            hours: row.get(4)?,
+            entry_date: row.get(5)?,
+            note: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    Ok(entries)
+}
+
+// 单个活动的预估与实际对比
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityEstimate {
+    pub activity_id: i32,
+    pub name: String,
+    pub project_id: i32,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub variance: f64,  // 实际 - 预估
+}
+
+// 按项目汇总的预估准确度
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectEstimation {
+    pub project_id: i32,
+    pub project_name: String,
+    pub activity_count: usize,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub accuracy_ratio: f64,  // 实际 / 预估，>1 表示低估
+    pub estimated_hours_display: String,
+    pub actual_hours_display: String,
+}
+
+// 预估准确度报告（只统计已完成且有预估工时的活动）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimationAccuracyReport {
+    pub activity_count: usize,
+    pub estimated_hours: f64,
+    pub actual_hours: f64,
+    pub accuracy_ratio: Option<f64>,         // 实际 / 预估
+    pub mean_absolute_error_pct: Option<f64>, // 单个活动偏差百分比的平均值
+    pub estimated_hours_display: String,      // 按数字格式设置格式化的工时，如 "1,234.5 h"
+    pub actual_hours_display: String,
+    pub by_project: Vec<ProjectEstimation>,
+    pub largest_overruns: Vec<ActivityEstimate>,  // 超出最多的活动
+}
+
+// 偏差最大的活动展示数量
+const MAX_ESTIMATE_OVERRUNS: usize = 5;
+
+// 生成预估准确度报告（可按项目过滤）
+pub fn fetch_estimation_accuracy_report(project_id: Option<i32>) -> Result<EstimationAccuracyReport> {
+    let (estimates, project_names, numbers) = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM project_activities a
+             WHERE a.status = '已完成' AND a.estimated_hours > 0 AND (?1 IS NULL OR a.project_id = ?1)",
+            ACTIVITY_COLUMNS
+        ))?;
+        let estimates: Vec<ActivityEstimate> = stmt.query_map([project_id], activity_from_row)?
+            .filter_map(|r| r.ok())
+            .map(|a| {
+                let estimated = a.estimated_hours.unwrap_or(0.0);
+                ActivityEstimate {
+                    activity_id: a.id,
+                    name: a.name,
+                    project_id: a.project_id,
+                    estimated_hours: estimated,
+                    actual_hours: a.actual_hours,
+                    variance: a.actual_hours - estimated,
+                }
+            })
+            .collect();
+        
+        let mut stmt = conn.prepare_cached("SELECT id, name FROM projects")?;
+        let project_names: std::collections::HashMap<i32, String> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(&conn)?);
+        (estimates, project_names, numbers)
+    };
+    
+    let estimated_hours: f64 = estimates.iter().map(|e| e.estimated_hours).sum();
+    let actual_hours: f64 = estimates.iter().map(|e| e.actual_hours).sum();
+    let accuracy_ratio = (estimated_hours > 0.0).then(|| actual_hours / estimated_hours);
+    let mean_absolute_error_pct = (!estimates.is_empty()).then(|| {
+        estimates.iter().map(|e| e.variance.abs() / e.estimated_hours * 100.0).sum::<f64>() / estimates.len() as f64
+    });
+    
+    // 按项目汇总
+    let mut per_project: std::collections::HashMap<i32, (usize, f64, f64)> = std::collections::HashMap::new();
+    for e in &estimates {
+        let entry = per_project.entry(e.project_id).or_insert((0, 0.0, 0.0));
+        entry.0 += 1;
+        entry.1 += e.estimated_hours;
+        entry.2 += e.actual_hours;
+    }
+    let mut by_project: Vec<ProjectEstimation> = per_project.into_iter()
+        .map(|(pid, (count, estimated, actual))| ProjectEstimation {
+            project_id: pid,
+            project_name: project_names.get(&pid).cloned().unwrap_or_default(),
+            activity_count: count,
+            estimated_hours: estimated,
+            actual_hours: actual,
+            accuracy_ratio: actual / estimated,
+            estimated_hours_display: numbers.format_hours(estimated),
+            actual_hours_display: numbers.format_hours(actual),
+        })
+        .collect();
+    by_project.sort_by(|a, b| a.project_name.cmp(&b.project_name));
+    
+    let activity_count = estimates.len();
+    let mut largest_overruns: Vec<ActivityEstimate> = estimates.into_iter().filter(|e| e.variance > 0.0).collect();
+    largest_overruns.sort_by(|a, b| b.variance.total_cmp(&a.variance));
+    largest_overruns.truncate(MAX_ESTIMATE_OVERRUNS);
+    
+    Ok(EstimationAccuracyReport {
+        activity_count,
+        estimated_hours,
+        actual_hours,
+        accuracy_ratio,
+        mean_absolute_error_pct,
+        estimated_hours_display: numbers.format_hours(estimated_hours),
+        actual_hours_display: numbers.format_hours(actual_hours),
+        by_project,
+        largest_overruns,
+    })
+}
+
+// ==================== 数据库统计 ====================
+
+// 单表行数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+// 项目数据量
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectDataSize {
+    pub project_id: i32,
+    pub name: String,
+    pub event_count: i64,
+    pub file_count: i64,
+    pub file_bytes: i64,
+    pub file_size_display: String,  // 按数字格式设置格式化，如 "12.5 MB"
+}
+
+// 每月新增数据量
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthlyGrowth {
+    pub month: String,  // YYYY-MM
+    pub events: i64,
+    pub contacts: i64,
+    pub activities: i64,
+    pub files: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    pub db_path: String,
+    pub db_size_bytes: u64,   // 数据库文件大小（含 WAL 文件）
+    pub db_size_display: String,
+    pub tables: Vec<TableRowCount>,
+    pub largest_projects: Vec<ProjectDataSize>,
+    pub monthly_growth: Vec<MonthlyGrowth>,
+}
+
+// 最大项目的展示数量
+const MAX_STATS_PROJECTS: usize = 10;
+// 增长统计的月份数
+const STATS_GROWTH_MONTHS: i64 = 12;
+
+// 统计数据库各表行数、文件大小、最大项目和每月增长
+pub fn fetch_database_stats() -> Result<DatabaseStats> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    // 使用当前打开的数据库文件（而不是存储设置中的位置）
+    let db_path = connection_path(&conn);
+    let db_size_bytes = ["", "-wal"].iter()
+        .filter_map(|suffix| std::fs::metadata(format!("{}{}", db_path.display(), suffix)).ok())
+        .map(|m| m.len())
+        .sum();
+    let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(&conn)?);
+    
+    // 各表行数
+    let table_names: Vec<String> = {
+        let mut stmt = conn.prepare_cached("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")?;
+        let names = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+        names
+    };
+    let mut tables = Vec::new();
+    for table in table_names {
+        let rows: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| row.get(0))?;
+        tables.push(TableRowCount { table, rows });
+    }
+    tables.sort_by_key(|t| std::cmp::Reverse(t.rows));
+    
+    // 事件和文件最多的项目
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name,
+                (SELECT COUNT(*) FROM events_projects ep WHERE ep.project_id = p.id) AS event_count,
+                (SELECT COUNT(*) FROM project_files f WHERE f.project_id = p.id) AS file_count,
+                (SELECT COALESCE(SUM(f.file_size), 0) FROM project_files f WHERE f.project_id = p.id) AS file_bytes
+         FROM projects p
+         ORDER BY event_count + file_count DESC, file_bytes DESC
+         LIMIT ?1"
+    )?;
+    let largest_projects = stmt.query_map([MAX_STATS_PROJECTS as i64], |row| {
+        let file_bytes: i64 = row.get(4)?;
+        Ok(ProjectDataSize {
+            project_id: row.get(0)?,
+            name: row.get(1)?,
+            event_count: row.get(2)?,
+            file_count: row.get(3)?,
+            file_bytes,
+            file_size_display: numbers.format_file_size(file_bytes.max(0) as u64),
+        })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    // 近 12 个月每月新增量（按 created_at 的月份统计）
+    let today = chrono::Local::now().date_naive();
+    let mut monthly_growth = Vec::new();
+    for back in (0..STATS_GROWTH_MONTHS).rev() {
+        let months = today.year() as i64 * 12 + today.month0() as i64 - back;
+        let month = format!("{:04}-{:02}", months / 12, months % 12 + 1);
+        let count = |table: &str| -> Result<i64> {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE substr(created_at, 1, 7) = ?1", table),
+                [&month],
+                |row| row.get(0)
+            )
+        };
+        monthly_growth.push(MonthlyGrowth {
+            events: count("events")?,
+            contacts: count("contacts")?,
+            activities: count("project_activities")?,
+            files: count("project_files")? + count("contact_files")?,
+            month,
+        });
+    }
+    
+    Ok(DatabaseStats {
+        db_path: db_path.display().to_string(),
+        db_size_bytes,
+        db_size_display: numbers.format_file_size(db_size_bytes),
+        tables,
+        largest_projects,
+        monthly_growth,
+    })
+}
+
+// ==================== 表数据导出（供 BI 工具使用） ====================
+
+// 导出过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableExportFilters {
+    pub project_id: Option<i32>,     // 只导出与该项目相关的记录
+    pub created_from: Option<String>, // YYYY-MM-DD，事件按 event_date，其余按 created_at
+    pub created_to: Option<String>,   // YYYY-MM-DD（含当天）
+}
+
+// 导出字段的值类型
+#[derive(Clone, Copy)]
+enum ExportField {
+    Int,
+    Real,
+    Text,
+    Bool,
+    UtcTime,    // CURRENT_TIMESTAMP 写入的 UTC 时间
+    LocalTime,  // 程序写入的本地时间或事件日期
+    Phones,     // JSON 数组字符串
+    Tags,       // 逗号分隔字符串
+    IdList,     // GROUP_CONCAT 得到的逗号分隔 ID
+}
+
+// 各表导出字段：(字段名, SQL 表达式, 类型)，字段名固定不随表结构变化
+const EXPORT_PROJECT_FIELDS: &[(&str, &str, ExportField)] = &[
+    ("id", "p.id", ExportField::Int),
+    ("name", "p.name", ExportField::Text),
+    ("description", "p.description", ExportField::Text),
+    ("color", "p.color", ExportField::Text),
+    ("icon", "p.icon", ExportField::Text),
+    ("created_at", "p.created_at", ExportField::UtcTime),
+    ("updated_at", "p.updated_at", ExportField::UtcTime),
+];
+
+const EXPORT_CONTACT_FIELDS: &[(&str, &str, ExportField)] = &[
+    ("id", "c.id", ExportField::Int),
+    ("name", "c.name", ExportField::Text),
+    ("title", "c.title", ExportField::Text),
+    ("company", "c.company", ExportField::Text),
+    ("phones", "c.phone", ExportField::Phones),
+    ("email", "c.email", ExportField::Text),
+    ("address", "c.address", ExportField::Text),
+    ("city", "c.city", ExportField::Text),
+    ("tags", "c.tags", ExportField::Tags),
+    ("notes", "c.notes", ExportField::Text),
+    ("pinned", "c.pinned", ExportField::Bool),
+    ("project_ids", "(SELECT GROUP_CONCAT(pc.project_id) FROM projects_contacts pc WHERE pc.contact_id = c.id)", ExportField::IdList),
+    ("created_at", "c.created_at", ExportField::UtcTime),
+    ("updated_at", "c.updated_at", ExportField::UtcTime),
+];
+
+const EXPORT_EVENT_FIELDS: &[(&str, &str, ExportField)] = &[
+    ("id", "e.id", ExportField::Int),
+    ("title", "e.title", ExportField::Text),
+    ("description", "e.description", ExportField::Text),
+    ("event_date", "e.event_date", ExportField::LocalTime),
+    ("event_type", "e.event_type", ExportField::Text),
+    ("channel", "e.channel", ExportField::Text),
+    ("primary_project_id", "e.project_id", ExportField::Int),
+    ("project_ids", "(SELECT GROUP_CONCAT(ep.project_id) FROM events_projects ep WHERE ep.event_id = e.id)", ExportField::IdList),
+    ("contact_ids", "(SELECT GROUP_CONCAT(ec.contact_id) FROM events_contacts ec WHERE ec.event_id = e.id)", ExportField::IdList),
+    ("reminder_time", "e.reminder_time", ExportField::LocalTime),
+    ("pinned", "e.pinned", ExportField::Bool),
+    ("created_at", "e.created_at", ExportField::UtcTime),
+    ("updated_at", "e.updated_at", ExportField::UtcTime),
+];
+
+const EXPORT_ACTIVITY_FIELDS: &[(&str, &str, ExportField)] = &[
+    ("id", "a.id", ExportField::Int),
+    ("project_id", "a.project_id", ExportField::Int),
+    ("name", "a.name", ExportField::Text),
+    ("description", "a.description", ExportField::Text),
+    ("status", "a.status", ExportField::Text),
+    ("estimated_completion_date", "a.estimated_completion_date", ExportField::LocalTime),
+    ("estimated_hours", "a.estimated_hours", ExportField::Real),
+    ("actual_hours", "(SELECT COALESCE(SUM(t.hours), 0) FROM activity_time_entries t WHERE t.activity_id = a.id)", ExportField::Real),
+    ("assignee_ids", "(SELECT GROUP_CONCAT(ac.contact_id) FROM activities_contacts ac WHERE ac.activity_id = a.id)", ExportField::IdList),
+    ("template_id", "a.template_id", ExportField::Int),
+    ("activated_at", "a.activated_at", ExportField::LocalTime),
+    ("paused_at", "a.paused_at", ExportField::LocalTime),
+    ("completed_at", "a.completed_at", ExportField::LocalTime),
+    ("created_at", "a.created_at", ExportField::UtcTime),
+    ("updated_at", "a.updated_at", ExportField::UtcTime),
+];
+
+// UTC 时间 "YYYY-MM-DD HH:MM:SS" 转为 ISO 8601（带 Z）
+fn iso_from_utc(value: &str) -> String {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+// 本地时间转为带时区偏移的 ISO 8601，纯日期保持 YYYY-MM-DD
+fn iso_from_local(value: &str) -> String {
+    use chrono::TimeZone;
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(value, fmt).ok())
+        .and_then(|dt| chrono::Local.from_local_datetime(&dt).earliest())
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn export_value(row: &rusqlite::Row, index: usize, field: ExportField) -> Result<serde_json::Value> {
+    use serde_json::Value;
+    let text = |row: &rusqlite::Row| row.get::<_, Option<String>>(index);
+    Ok(match field {
+        ExportField::Int => row.get::<_, Option<i64>>(index)?.map(Value::from).unwrap_or(Value::Null),
+        ExportField::Real => row.get::<_, Option<f64>>(index)?.map(Value::from).unwrap_or(Value::Null),
+        ExportField::Bool => Value::Bool(row.get::<_, Option<i64>>(index)?.unwrap_or(0) != 0),
+        ExportField::Text => text(row)?.map(Value::from).unwrap_or(Value::Null),
+        ExportField::UtcTime => text(row)?.map(|v| Value::from(iso_from_utc(&v))).unwrap_or(Value::Null),
+        ExportField::LocalTime => text(row)?.map(|v| Value::from(iso_from_local(&v))).unwrap_or(Value::Null),
+        ExportField::Phones => Value::from(parse_phone_list(text(row)?.as_deref())),
+        ExportField::Tags => Value::from(
+            text(row)?.unwrap_or_default()
+                .split([',', '，'])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+        ),
+        ExportField::IdList => {
+            let mut ids: Vec<i64> = text(row)?.unwrap_or_default()
+                .split(',')
+                .filter_map(|id| id.trim().parse().ok())
+                .collect();
+            ids.sort_unstable();
+            Value::from(ids)
+        }
+    })
+}
+
+fn export_io_error(e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(format!("写入文件失败: {}", e)))
+}
+
+// 将 projects / contacts / events / activities 表逐行写入 JSON 数组文件，返回导出行数；
+// redacted_fields 中的字段（脱敏方案隐藏的字段）导出为 null，提供 pseudonymizer 时联系人化名
+pub fn export_table_json(
+    table: &str,
+    filters: &TableExportFilters,
+    redacted_fields: &[&str],
+    pseudonymizer: Option<&crate::export::Pseudonymizer>,
+    path: &str,
+) -> Result<usize> {
+    use std::io::Write;
+    
+    // 私密事件不导出
+    let (fields, from, date_column, project_condition, visible_condition) = match table {
+        "projects" => (EXPORT_PROJECT_FIELDS, "projects p", "p.created_at", "p.id = :project_id", "1"),
+        "contacts" => (EXPORT_CONTACT_FIELDS, "contacts c", "c.created_at",
+            "EXISTS(SELECT 1 FROM projects_contacts pc WHERE pc.contact_id = c.id AND pc.project_id = :project_id)", "1"),
+        "events" => (EXPORT_EVENT_FIELDS, "events e", "e.event_date",
+            "EXISTS(SELECT 1 FROM events_projects ep WHERE ep.event_id = e.id AND ep.project_id = :project_id)", "e.is_private = 0"),
+        "activities" => (EXPORT_ACTIVITY_FIELDS, "project_activities a", "a.created_at", "a.project_id = :project_id", "1"),
+        other => return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("不支持导出的表: {}（支持 projects、contacts、events、activities）", other))
+        )),
+    };
+    
+    let columns: Vec<&str> = fields.iter().map(|(_, expr, _)| *expr).collect();
+    let sql = format!(
+        "SELECT {} FROM {}
+         WHERE (:project_id IS NULL OR {})
+           AND (:created_from IS NULL OR substr({}, 1, 10) >= :created_from)
+           AND (:created_to IS NULL OR substr({}, 1, 10) <= :created_to)
+           AND {}
+         ORDER BY 1",
+        columns.join(", "), from, project_condition, date_column, date_column, visible_condition
+    );
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(rusqlite::named_params! {
+        ":project_id": filters.project_id,
+        ":created_from": filters.created_from,
+        ":created_to": filters.created_to,
+    })?;
+    
+    // 逐行写入，避免整表加载到内存
+    let file = std::fs::File::create(path).map_err(export_io_error)?;
+    let mut out = std::io::BufWriter::new(file);
+    out.write_all(b"[").map_err(export_io_error)?;
+    
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let mut record = serde_json::Map::new();
+        for (i, (name, _, field)) in fields.iter().enumerate() {
+            let value = if redacted_fields.contains(name) { serde_json::Value::Null } else { export_value(row, i, *field)? };
+            record.insert(name.to_string(), value);
+        }
+        if let Some(p) = pseudonymizer {
+            p.record(table, &mut record);
+        }
+        out.write_all(if count == 0 { b"\n  " } else { b",\n  " }).map_err(export_io_error)?;
+        serde_json::to_writer(&mut out, &record)
+            .map_err(|e| export_io_error(std::io::Error::other(e)))?;
+        count += 1;
+    }
+    
+    out.write_all(b"\n]\n").map_err(export_io_error)?;
+    out.flush().map_err(export_io_error)?;
+    Ok(count)
+}
+
+// ==================== 外部工具导入（Trello / Notion / Markdown） ====================
+
+// 按姓名在已有记录中查找（忽略首尾空格和英文大小写）
+fn find_id_by_name<'a>(name: &str, candidates: impl IntoIterator<Item = (i32, &'a str)>) -> Option<i32> {
+    let name = name.trim().to_lowercase();
+    candidates.into_iter()
+        .find(|(_, n)| n.trim().to_lowercase() == name)
+        .map(|(id, _)| id)
+}
+
+// Trello 导入预览中的一张卡片
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityImportPreviewRow {
+    pub imported: ImportedActivity,
+    pub assignee_ids: Vec<i32>,             // 按成员姓名匹配到的联系人
+    pub missing_members: Vec<String>,       // 未匹配到联系人的成员
+    pub duplicate_activity_id: Option<i32>, // 项目中已有同名活动（导入时跳过）
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrelloImportPreview {
+    pub board_name: String,
+    pub project_id: i32,
+    pub rows: Vec<ActivityImportPreviewRow>,
+    pub committed: usize,  // 实际导入的活动数（预览时为 0）
+}
+
+// 生成 Trello 看板导入预览
+pub fn preview_trello_import(project_id: i32, board_name: String, activities: Vec<ImportedActivity>) -> Result<TrelloImportPreview> {
+    let contacts = fetch_contacts()?;
+    let existing: Vec<(i32, String)> = fetch_activities_for_project(project_id)?
+        .into_iter()
+        .map(|a| (a.activity.id, a.activity.name))
+        .collect();
+    
+    let rows = activities.into_iter().map(|imported| {
+        let mut assignee_ids = Vec::new();
+        let mut missing_members = Vec::new();
+        for member in &imported.member_names {
+            match find_id_by_name(member, contacts.iter().map(|c| (c.id, c.name.as_str()))) {
+                Some(id) => assignee_ids.push(id),
+                None => missing_members.push(member.clone()),
+            }
+        }
+        let duplicate_activity_id = find_id_by_name(&imported.name, existing.iter().map(|(id, n)| (*id, n.as_str())));
+        ActivityImportPreviewRow { imported, assignee_ids, missing_members, duplicate_activity_id }
+    }).collect();
+    
+    Ok(TrelloImportPreview { board_name, project_id, rows, committed: 0 })
+}
+
+// 按预览导入 Trello 卡片为项目活动（跳过重名活动），返回导入数量
+pub fn commit_trello_import(preview: &TrelloImportPreview) -> Result<usize> {
+    let project_name = get_project_name(preview.project_id)?;
+    let mut created = Vec::new();
+    {
+        let db = get_db()?;
+        let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let tx = conn.transaction()?;
+        for row in preview.rows.iter().filter(|r| r.duplicate_activity_id.is_none()) {
+            let imported = &row.imported;
+            // 没有负责人的未开始卡片保持待分配，有负责人则为未激活
+            let status = match imported.status.as_str() {
+                "待分配" if !row.assignee_ids.is_empty() => "未激活",
+                other => other,
+            };
+            let activated_at = (status == "进行中").then_some(now.as_str());
+            let completed_at = (status == "已完成").then_some(now.as_str());
+            // 卡片标签附在描述末尾
+            let description = match (imported.description.as_deref(), imported.labels.is_empty()) {
+                (desc, true) => desc.map(|d| d.to_string()),
+                (None, false) => Some(format!("标签：{}", imported.labels.join("、"))),
+                (Some(desc), false) => Some(format!("{}\n标签：{}", desc, imported.labels.join("、"))),
+            };
+            
+            tx.execute(
+                "INSERT INTO project_activities (project_id, name, description, estimated_completion_date, status, activated_at, completed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![preview.project_id, imported.name, description, imported.due_date, status, activated_at, completed_at],
+            )?;
+            let activity_id = tx.last_insert_rowid();
+            for contact_id in &row.assignee_ids {
+                tx.execute(
+                    "INSERT OR IGNORE INTO activities_contacts (activity_id, contact_id) VALUES (?1, ?2)",
+                    rusqlite::params![activity_id, contact_id],
+                )?;
+            }
+            created.push((activity_id, imported.name.clone(), imported.member_names.clone()));
+        }
+        tx.commit()?;
+    }
+    
+    for (activity_id, name, members) in &created {
+        let _ = log_activity_creation(*activity_id, name, preview.project_id, &project_name, members);
+    }
+    Ok(created.len())
+}
+
+// Notion 导入预览中的一条事件
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventImportPreviewRow {
+    pub imported: ImportedEvent,
+    pub project_id: Option<i32>,
+    pub missing_project: bool,             // 填了项目名但未找到对应项目
+    pub contact_ids: Vec<i32>,
+    pub missing_contacts: Vec<String>,
+    pub duplicate_event_id: Option<i32>,   // 已有同标题同日期的事件（导入时跳过）
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotionImportPreview {
+    pub events: Vec<EventImportPreviewRow>,
+    pub contacts: Vec<ContactImportPreviewRow>,
+    pub skipped_rows: usize,
+    pub committed_events: usize,                      // 实际导入的事件数（预览时为 0）
+    pub committed_contacts: Option<ContactImportResult>,
+}
+
+// 生成 Notion CSV 导入预览（联系人沿用联系人导入的去重建议）
+pub fn preview_notion_import(data: NotionImport) -> Result<NotionImportPreview> {
+    let contacts = fetch_contacts()?;
+    let projects = fetch_projects(true)?;
+    let existing_events: Vec<(i32, String, String)> = {
+        let db = get_db()?;
+        let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("锁失败: {}", e))
+        ))?;
+        let mut stmt = conn.prepare_cached("SELECT id, title, event_date FROM events")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    
+    let events = data.events.into_iter().map(|imported| {
+        let project_id = imported.project_name.as_deref()
+            .and_then(|name| find_id_by_name(name, projects.iter().map(|p| (p.id, p.name.as_str()))));
+        let missing_project = imported.project_name.is_some() && project_id.is_none();
+        
+        let mut contact_ids = Vec::new();
+        let mut missing_contacts = Vec::new();
+        for name in &imported.contact_names {
+            match find_id_by_name(name, contacts.iter().map(|c| (c.id, c.name.as_str()))) {
+                Some(id) => contact_ids.push(id),
+                None => missing_contacts.push(name.clone()),
+            }
+        }
+        
+        let duplicate_event_id = existing_events.iter()
+            .find(|(_, title, date)| *title == imported.title && *date == imported.event_date)
+            .map(|(id, _, _)| *id);
+        
+        EventImportPreviewRow { imported, project_id, missing_project, contact_ids, missing_contacts, duplicate_event_id }
+    }).collect();
+    
+    Ok(NotionImportPreview {
+        events,
+        contacts: preview_contact_import(data.contacts)?,
+        skipped_rows: data.skipped_rows,
+        committed_events: 0,
+        committed_contacts: None,
+    })
+}
+
+// 按预览导入 Notion 事件和联系人（联系人按建议的新建/合并/跳过处理；整批在同一事务中，失败时全部回滚）
+pub fn commit_notion_import(preview: &mut NotionImportPreview) -> Result<()> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    commit_notion_import_with(&mut conn, preview)
+}
+
+pub fn commit_notion_import_with(conn: &mut Connection, preview: &mut NotionImportPreview) -> Result<()> {
+    let tx = conn.transaction()?;
+    
+    let mut committed_contacts = None;
+    if !preview.contacts.is_empty() {
+        let decisions: Vec<ContactImportDecision> = preview.contacts.iter()
+            .map(|row| ContactImportDecision {
+                imported: row.imported.clone(),
+                action: row.suggested_action.clone(),
+                target_contact_id: row.suggested_target_id,
+            })
+            .collect();
+        committed_contacts = Some(apply_contact_import(&tx, &decisions)?);
+    }
+    
+    let projects = fetch_projects_with(&tx, true)?;
+    let contacts: Vec<(i32, String)> = {
+        let mut stmt = tx.prepare_cached("SELECT id, name FROM contacts")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    let mut committed_events = 0;
+    for row in preview.events.iter().filter(|r| r.duplicate_event_id.is_none()) {
+        let imported = &row.imported;
+        let event_id = insert_event_with(
+            &tx,
+            &imported.title,
+            imported.description.as_deref(),
+            &imported.event_date,
+            row.project_id,
+            imported.event_type.as_deref(),
+            None,
+        )?;
+        link_contacts_to_event_with(&tx, event_id, &row.contact_ids)?;
+        
+        let project_name = row.project_id.and_then(|pid| projects.iter().find(|p| p.id == pid)).map(|p| p.name.as_str());
+        let contact_names: Vec<String> = contacts.iter()
+            .filter(|(id, _)| row.contact_ids.contains(id))
+            .map(|(_, name)| name.clone())
+            .collect();
+        log_event_creation_with(&tx, event_id, &imported.title, imported.event_type.as_deref(), row.project_id, project_name, &contact_names)?;
+        committed_events += 1;
+    }
+    
+    tx.commit()?;
+    preview.committed_contacts = committed_contacts;
+    preview.committed_events += committed_events;
+    Ok(())
+}
+
+// 生成 Markdown 笔记导入预览：事件都归入 project_id，联系人按“联系人：”行的姓名和正文中提到的姓名匹配，
+// 未标注类型时标题第一个词是已有事件类型的按该类型导入；导入沿用 commit_notion_import
+pub fn preview_markdown_import(project_id: Option<i32>, data: MarkdownEventImport) -> Result<NotionImportPreview> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    preview_markdown_import_with(&conn, project_id, data)
+}
+
+pub fn preview_markdown_import_with(conn: &Connection, project_id: Option<i32>, data: MarkdownEventImport) -> Result<NotionImportPreview> {
+    let project_name = match project_id {
+        Some(pid) => Some(conn.query_row("SELECT name FROM projects WHERE id = ?1", [pid], |row| row.get::<_, String>(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(1),
+                    Some(format!("项目不存在: {}", pid))
+                ),
+                e => e,
+            })?),
+        None => None,
+    };
+    let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM contacts c", CONTACT_COLUMNS))?;
+    let contacts: Vec<Contact> = stmt.query_map([], contact_from_row)?.filter_map(|r| r.ok()).collect();
+    let mut stmt = conn.prepare_cached("SELECT name FROM event_types")?;
+    let event_types: Vec<String> = stmt.query_map([], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+    let mut stmt = conn.prepare_cached("SELECT id, title, event_date FROM events")?;
+    let existing_events: Vec<(i32, String, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let events = data.events.into_iter().map(|mut imported| {
+        if imported.event_type.is_none() {
+            let first_word = imported.title.split_whitespace().next().unwrap_or("");
+            imported.event_type = event_types.iter().find(|t| t.as_str() == first_word).cloned();
+        }
+        imported.project_name = project_name.clone();
+
+        let mut contact_ids = Vec::new();
+        let mut missing_contacts = Vec::new();
+        for name in &imported.contact_names {
+            match find_id_by_name(name, contacts.iter().map(|c| (c.id, c.name.as_str()))) {
+                Some(id) => contact_ids.push(id),
+                None => missing_contacts.push(name.clone()),
+            }
+        }
+        let text = format!("{}\n{}", imported.title, imported.description.as_deref().unwrap_or(""));
+        for id in detect_mentions(&text, &contacts) {
+            if !contact_ids.contains(&id) {
+                contact_ids.push(id);
+            }
+        }
+
+        let duplicate_event_id = existing_events.iter()
+            .find(|(_, title, date)| *title == imported.title && *date == imported.event_date)
+            .map(|(id, _, _)| *id);
+
+        EventImportPreviewRow { imported, project_id, missing_project: false, contact_ids, missing_contacts, duplicate_event_id }
+    }).collect();
+
+    Ok(NotionImportPreview {
+        events,
+        contacts: Vec::new(),
+        skipped_rows: data.skipped_lines,
+        committed_events: 0,
+        committed_contacts: None,
+    })
+}
+
+// ==================== 拨号链接 ====================
+
+// 联系人某个电话的拨号链接
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DialUris {
+    pub raw: String,
+    pub e164: Option<String>,
+    pub tel: String,             // tel:+8613800138000
+    pub facetime: Option<String>,       // facetime:（仅限能规范化的号码）
+    pub facetime_audio: Option<String>, // facetime-audio:
+    pub wechat: String,          // 打开微信（微信没有按手机号直接发起通话的公开链接）
+}
+
+// 生成联系人第 which 个电话（从 0 开始）的拨号链接
+pub fn fetch_contact_dial_uris(contact_id: i32, which: usize) -> Result<DialUris> {
+    let contact = fetch_contact_by_id(contact_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+    let phones = parse_phone_list(contact.phone.as_deref());
+    let raw = phones.get(which).cloned().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("联系人「{}」没有第 {} 个电话", contact.name, which + 1))
+    ))?;
+    
+    // 优先使用保存时的规范化结果
+    let e164 = contact.phone_normalized.as_deref()
+        .and_then(|json| serde_json::from_str::<Vec<String>>(json).ok())
+        .and_then(|list| list.get(which).cloned())
+        .filter(|n| !n.is_empty())
+        .or_else(|| normalize_phone(&raw));
+    let dial_target = e164.clone().unwrap_or_else(|| raw.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect());
+    
+    Ok(DialUris {
+        tel: format!("tel:{}", dial_target),
+        facetime: e164.as_ref().map(|n| format!("facetime:{}", n)),
+        facetime_audio: e164.as_ref().map(|n| format!("facetime-audio:{}", n)),
+        wechat: "weixin://".to_string(),
+        raw,
+        e164,
+    })
+}
+
+// ==================== 邮箱校验与单位推荐 ====================
+
+// 个人邮箱域名，不用于推断单位
+const PERSONAL_EMAIL_DOMAINS: &[&str] = &[
+    "qq.com", "foxmail.com", "163.com", "126.com", "yeah.net", "sina.com", "sina.cn", "sohu.com", "139.com",
+    "aliyun.com", "gmail.com", "outlook.com", "hotmail.com", "live.com", "yahoo.com", "icloud.com", "me.com",
+];
+
+// 校验邮箱格式（本地部分@域名，域名至少包含一个点）
+pub fn validate_email(email: &str) -> std::result::Result<(), String> {
+    let email = email.trim();
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && !email.contains(char::is_whitespace)
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        }
+        None => false,
+    };
+    if valid { Ok(()) } else { Err(format!("邮箱格式不正确: {}", email)) }
+}
+
+// 邮箱的小写域名（个人邮箱返回 None）
+fn company_email_domain(email: &str) -> Option<String> {
+    let domain = email.trim().rsplit_once('@')?.1.to_lowercase();
+    (!domain.is_empty() && !PERSONAL_EMAIL_DOMAINS.contains(&domain.as_str())).then_some(domain)
+}
+
+// 按邮箱推荐的单位
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompanySuggestion {
+    pub company: String,
+    pub domain: String,
+    pub source: String,  // domains（对照表）/ contacts（已有联系人中同域名最常见的单位）
+}
+
+// 按邮箱域名推荐单位：先查对照表，再看已有联系人
+pub fn suggest_company_for_email(email: &str) -> Result<Option<CompanySuggestion>> {
+    let Some(domain) = company_email_domain(email) else {
+        return Ok(None);
+    };
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let known: Option<String> = conn.query_row(
+        "SELECT company FROM company_domains WHERE domain = ?1",
+        [&domain],
+        |row| row.get(0)
+    ).ok();
+    if let Some(company) = known {
+        return Ok(Some(CompanySuggestion { company, domain, source: "domains".to_string() }));
+    }
+    
+    let from_contacts: Option<String> = conn.query_row(
+        "SELECT company FROM contacts
+         WHERE lower(email) LIKE '%@' || ?1 AND company IS NOT NULL AND company != ''
+         GROUP BY company ORDER BY COUNT(*) DESC LIMIT 1",
+        [&domain],
+        |row| row.get(0)
+    ).ok();
+    Ok(from_contacts.map(|company| CompanySuggestion { company, domain, source: "contacts".to_string() }))
+}
+
+// 记住邮箱域名对应的单位（已有对照的不覆盖）
+pub fn remember_company_domain(email: &str, company: &str) -> Result<()> {
+    let (Some(domain), company) = (company_email_domain(email), company.trim()) else {
+        return Ok(());
+    };
+    if company.is_empty() {
+        return Ok(());
+    }
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT OR IGNORE INTO company_domains (domain, company) VALUES (?1, ?2)",
+        rusqlite::params![domain, company],
+    )?;
+    Ok(())
+}
+
+// 邮箱域名对照
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompanyDomain {
+    pub domain: String,
+    pub company: String,
+    pub created_at: String,
+}
+
+// 获取所有邮箱域名对照
+pub fn fetch_company_domains() -> Result<Vec<CompanyDomain>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached("SELECT domain, company, created_at FROM company_domains ORDER BY domain")?;
+    let domains = stmt.query_map([], |row| {
+        Ok(CompanyDomain { domain: row.get(0)?, company: row.get(1)?, created_at: row.get(2)? })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    Ok(domains)
+}
+
+// 新增或修改邮箱域名对照
+pub fn set_company_domain(domain: &str, company: &str) -> Result<()> {
+    let domain = domain.trim().trim_start_matches('@').to_lowercase();
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "INSERT INTO company_domains (domain, company) VALUES (?1, ?2)
+         ON CONFLICT(domain) DO UPDATE SET company = excluded.company",
+        rusqlite::params![domain, company.trim()],
+    )?;
+    Ok(())
+}
+
+// 删除邮箱域名对照
+pub fn delete_company_domain(domain: &str) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM company_domains WHERE domain = ?1", [domain.trim().to_lowercase()])?;
+    Ok(())
+}
+
+// ==================== 沟通渠道 ====================
+
+// 常用沟通渠道
+pub const EVENT_CHANNELS: [&str; 5] = ["电话", "微信", "邮件", "面谈", "视频"];
+
+// 按事件类型推断沟通渠道
+pub fn channel_for_event_type(event_type: &str) -> Option<&'static str> {
+    match event_type {
+        "电话" => Some("电话"),
+        "微信" => Some("微信"),
+        "邮件" => Some("邮件"),
+        "会议" => Some("面谈"),
+        "视频会议" => Some("视频"),
+        _ => None,
+    }
+}
+
+// 设置事件的沟通渠道（None 或空字符串表示清除）
+pub fn set_event_channel(event_id: i32, channel: Option<&str>) -> Result<()> {
+    let channel = channel.map(|c| c.trim()).filter(|c| !c.is_empty());
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute(
+        "UPDATE events SET channel = ?1 WHERE id = ?2",
+        rusqlite::params![channel, event_id],
+    )?;
+    Ok(())
+}
+
+// 单个渠道的沟通次数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelCount {
+    pub channel: String,
+    pub count: i64,
+    pub last_date: String,  // 最近一次通过该渠道沟通的事件日期
+}
+
+// 联系人的沟通渠道分布
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactChannelStats {
+    pub contact_id: i32,
+    pub name: String,
+    pub total_events: i64,
+    pub unrecorded_events: i64,        // 未记录渠道的事件数
+    pub channels: Vec<ChannelCount>,   // 按次数降序
+    pub primary_channel: Option<String>,
+    pub single_channel: bool,          // 有记录的沟通都通过同一个渠道
+}
+
+// 统计联系人的沟通渠道分布（contact_id 为空时统计所有有事件的联系人；only_channel 只保留仅通过该渠道沟通的联系人）
+pub fn fetch_contact_channel_stats(contact_id: Option<i32>, only_channel: Option<&str>) -> Result<Vec<ContactChannelStats>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.id, c.name, e.channel, COUNT(*), MAX(e.event_date)
+         FROM contacts c
+         INNER JOIN events_contacts ec ON ec.contact_id = c.id
+         INNER JOIN events e ON e.id = ec.event_id
+         WHERE ?1 IS NULL OR c.id = ?1
+         GROUP BY c.id, e.channel
+         ORDER BY c.id"
+    )?;
+    let rows: Vec<(i32, String, Option<String>, i64, String)> = stmt.query_map([contact_id], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    let mut stats: Vec<ContactChannelStats> = Vec::new();
+    for (id, name, channel, count, last_date) in rows {
+        if stats.last().is_none_or(|s| s.contact_id != id) {
+            stats.push(ContactChannelStats {
+                contact_id: id,
+                name,
+                total_events: 0,
+                unrecorded_events: 0,
+                channels: Vec::new(),
+                primary_channel: None,
+                single_channel: false,
+            });
+        }
+        let Some(entry) = stats.last_mut() else { continue };
+        entry.total_events += count;
+        match channel.filter(|c| !c.is_empty()) {
+            Some(channel) => entry.channels.push(ChannelCount { channel, count, last_date }),
+            None => entry.unrecorded_events += count,
+        }
+    }
+    
+    for entry in stats.iter_mut() {
+        entry.channels.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_date.cmp(&a.last_date)));
+        entry.primary_channel = entry.channels.first().map(|c| c.channel.clone());
+        entry.single_channel = entry.channels.len() == 1;
+    }
+    
+    if let Some(only) = only_channel {
+        stats.retain(|s| s.single_channel && s.primary_channel.as_deref() == Some(only));
+    }
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total_events));
+    Ok(stats)
+}
+
+// ==================== 按城市查看联系人 ====================
+
+// 地址中常省略"市"字的城市
+const MUNICIPALITIES: [&str; 6] = ["北京", "上海", "天津", "重庆", "香港", "澳门"];
+
+// 从地址中解析城市名（去掉"省""市"等行政后缀），无法识别时返回 None
+pub fn parse_city(address: &str) -> Option<String> {
+    let address = address.trim().trim_start_matches("中国").trim_start();
+    if let Some(city) = MUNICIPALITIES.iter().find(|m| address.starts_with(*m)) {
+        return Some(city.to_string());
+    }
+    
+    // 去掉开头的省份/自治区
+    let province_end = ["省", "自治区", "特别行政区"].iter()
+        .filter_map(|suffix| address.find(suffix).map(|i| i + suffix.len()))
+        .filter(|&end| address[..end].chars().count() <= 8)
+        .min();
+    let rest = province_end.map(|end| &address[end..]).unwrap_or(address);
+    
+    let (city_end, _) = ["市", "自治州", "地区", "盟"].iter()
+        .filter_map(|suffix| rest.find(suffix).map(|i| (i, suffix.len())))
+        .min()?;
+    let city = rest[..city_end].trim();
+    // "市"前面出现街道、门牌等字样时多半是"超市""菜市场"之类，不当作城市
+    let len = city.chars().count();
+    let looks_like_street = ["路", "街", "道", "号", "区", "县"].iter().any(|k| city.contains(k));
+    ((2..=6).contains(&len) && !looks_like_street).then(|| city.to_string())
+}
+
+// 规范化查询用的城市名（"杭州市" 与 "杭州" 视为同一城市）
+fn normalize_city(city: &str) -> String {
+    let city = city.trim();
+    city.strip_suffix('市').unwrap_or(city).to_string()
+}
+
+// 为旧数据补充城市字段
+fn backfill_contact_cities(conn: &Connection) -> Result<()> {
+    let rows: Vec<(i32, String)> = {
+        let mut stmt = conn.prepare_cached("SELECT id, address FROM contacts WHERE city IS NULL AND address IS NOT NULL AND address != ''")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.filter_map(|r| r.ok()).collect();
+        rows
+    };
+    for (id, address) in rows {
+        if let Some(city) = parse_city(&address) {
+            conn.execute("UPDATE contacts SET city = ?1 WHERE id = ?2", rusqlite::params![city, id])?;
+        }
+    }
+    Ok(())
+}
+
+// 某城市的联系人（附带参与的项目和最近一次沟通日期）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CityContact {
+    pub contact: Contact,
+    pub projects: Vec<String>,            // 参与的项目名称
+    pub last_event_date: Option<String>,  // 最近一次关联事件的日期
+}
+
+// 获取某城市的所有联系人（城市未能解析的联系人按地址模糊匹配）
+pub fn fetch_contacts_by_city(city: &str) -> Result<Vec<CityContact>> {
+    let city = normalize_city(city);
+    if city.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM contacts c
+         WHERE c.city = ?1 OR ((c.city IS NULL OR c.city = '') AND c.address LIKE '%' || ?1 || '%')
+         ORDER BY c.pinned DESC, c.name",
+        CONTACT_COLUMNS
+    ))?;
+    let contacts: Vec<Contact> = stmt.query_map([&city], contact_from_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    let mut project_stmt = conn.prepare_cached(
+        "SELECT p.name FROM projects p
+         INNER JOIN projects_contacts pc ON pc.project_id = p.id
+         WHERE pc.contact_id = ?1
+         ORDER BY p.name"
+    )?;
+    let mut result = Vec::new();
+    for contact in contacts {
+        let projects: Vec<String> = project_stmt.query_map([contact.id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let last_event_date: Option<String> = conn.query_row(
+            "SELECT MAX(e.event_date) FROM events e
+             INNER JOIN events_contacts ec ON ec.event_id = e.id
+             WHERE ec.contact_id = ?1",
+            [contact.id],
+            |row| row.get(0),
+        )?;
+        result.push(CityContact { contact, projects, last_event_date });
+    }
+    
+    // 置顶联系人优先，其次是参与项目多的
+    result.sort_by(|a, b| {
+        b.contact.pinned.cmp(&a.contact.pinned)
+            .then(b.projects.len().cmp(&a.projects.len()))
+    });
+    Ok(result)
+}
+
+// 某城市中关联的项目
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegionProject {
+    pub project_id: i32,
+    pub name: String,
+    pub contact_count: i64,  // 该项目在此城市的联系人数
+}
+
+// 按城市汇总的项目分布
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegionProjects {
+    pub city: String,
+    pub contact_count: i64,            // 此城市的联系人总数
+    pub projects: Vec<RegionProject>,  // 按联系人数降序
+}
+
+// 按联系人所在城市汇总项目，城市按联系人数降序
+pub fn fetch_projects_by_region() -> Result<Vec<RegionProjects>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT city, COUNT(*) FROM contacts
+         WHERE city IS NOT NULL AND city != ''
+         GROUP BY city
+         ORDER BY COUNT(*) DESC, city"
+    )?;
+    let mut regions: Vec<RegionProjects> = stmt.query_map([], |row| {
+        Ok(RegionProjects { city: row.get(0)?, contact_count: row.get(1)?, projects: Vec::new() })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.city, p.id, p.name, COUNT(DISTINCT c.id)
+         FROM contacts c
+         INNER JOIN projects_contacts pc ON pc.contact_id = c.id
+         INNER JOIN projects p ON p.id = pc.project_id
+         WHERE c.city IS NOT NULL AND c.city != ''
+         GROUP BY c.city, p.id
+         ORDER BY COUNT(DISTINCT c.id) DESC, p.name"
+    )?;
+    let rows: Vec<(String, RegionProject)> = stmt.query_map([], |row| {
+        Ok((row.get(0)?, RegionProject { project_id: row.get(1)?, name: row.get(2)?, contact_count: row.get(3)? }))
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    for (city, project) in rows {
+        if let Some(region) = regions.iter_mut().find(|r| r.city == city) {
+            region.projects.push(project);
+        }
+    }
+    Ok(regions)
+}
+
+// ==================== 提醒收件箱 ====================
+
+// 已发出但尚未确认的提醒
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReminderInboxItem {
+    pub id: i32,
+    pub event_id: i32,
+    pub title: String,
+    pub body: Option<String>,
+    pub notify_count: i32,
+    pub first_notified_at: String,
+    pub last_notified_at: String,
+    pub event_date: String,
+    pub project_id: Option<i32>,
+}
+
+// 记录一次已发出的提醒通知（同一事件未确认的记录只保留一条，重复通知时累加次数）
+pub fn record_reminder_notification(event_id: i32, title: &str, body: &str) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let updated = conn.execute(
+        "UPDATE reminder_inbox SET title = ?1, body = ?2, last_notified_at = ?3, notify_count = notify_count + 1
+         WHERE event_id = ?4 AND acknowledged_at IS NULL",
+        rusqlite::params![title, body, now, event_id],
+    )?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO reminder_inbox (event_id, title, body, first_notified_at, last_notified_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            rusqlite::params![event_id, title, body, now],
+        )?;
+    }
+    
+    Ok(())
+}
+
+// 获取已触发但尚未确认的提醒，最近通知的在前
+pub fn fetch_reminder_inbox() -> Result<Vec<ReminderInboxItem>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_reminder_inbox_with(&conn)
+}
+
+pub fn fetch_reminder_inbox_with(conn: &Connection) -> Result<Vec<ReminderInboxItem>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT r.id, r.event_id, r.title, r.body, r.notify_count, r.first_notified_at, r.last_notified_at, e.event_date, e.project_id
+         FROM reminder_inbox r
+         INNER JOIN events e ON e.id = r.event_id
+         WHERE r.acknowledged_at IS NULL
+         ORDER BY r.last_notified_at DESC"
+    )?;
+    let items = stmt.query_map([], |row| {
+        Ok(ReminderInboxItem {
+            id: row.get(0)?,
+            event_id: row.get(1)?,
+            title: row.get(2)?,
+            body: row.get(3)?,
+            notify_count: row.get::<_, Option<i32>>(4)?.unwrap_or(1),
+            first_notified_at: row.get(5)?,
+            last_notified_at: row.get(6)?,
+            event_date: row.get(7)?,
+            project_id: row.get(8)?,
+        })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    Ok(items)
+}
+
+// 确认收件箱中的全部提醒，返回确认的数量
+pub fn acknowledge_all_reminders() -> Result<usize> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "UPDATE events SET reminder_acknowledged_at = ?1, reminder_triggered = 1
+         WHERE id IN (SELECT event_id FROM reminder_inbox WHERE acknowledged_at IS NULL)",
+        [&now],
+    )?;
+    let count = conn.execute(
+        "UPDATE reminder_inbox SET acknowledged_at = ?1 WHERE acknowledged_at IS NULL",
+        [&now],
+    )?;
+    
+    Ok(count)
+}
+
+// ==================== 提醒通知样式 ====================
+
+// 可选的通知紧急程度
+pub const NOTIFICATION_URGENCIES: [&str; 3] = ["低", "普通", "紧急"];
+
+// 单条提醒通知的发送方式
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationStyle {
+    pub urgency: String,
+    pub play_sound: bool,
+}
+
+// 提醒任务每轮读取一次的通知设置
+pub struct NotificationPreferences {
+    pub sound_enabled: bool,                                      // 未设置时默认开启
+    pub urgency_by_type: std::collections::HashMap<String, String>,  // 事件类型 -> 紧急程度
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        NotificationPreferences { sound_enabled: true, urgency_by_type: std::collections::HashMap::new() }
+    }
+}
+
+impl NotificationPreferences {
+    // 按事件类型确定通知样式："低"不播放声音，其余在声音开启时播放
+    pub fn style_for(&self, event_type: Option<&str>) -> NotificationStyle {
+        let urgency = event_type
+            .and_then(|t| self.urgency_by_type.get(t))
+            .cloned()
+            .unwrap_or_else(|| "普通".to_string());
+        NotificationStyle {
+            play_sound: self.sound_enabled && urgency != "低",
+            urgency,
+        }
+    }
+}
+
+// 读取通知声音开关和各事件类型的紧急程度
+pub fn load_notification_preferences() -> Result<NotificationPreferences> {
+    let sound_enabled = get_setting(SETTING_NOTIFICATION_SOUND)?
+        .and_then(|v| parse_switch(&v))
+        .unwrap_or(true);
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT name, notification_urgency FROM event_types
+         WHERE notification_urgency IS NOT NULL AND notification_urgency != ''"
+    )?;
+    let urgency_by_type = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    Ok(NotificationPreferences { sound_enabled, urgency_by_type })
+}
+
+// ==================== 后台任务执行记录 ====================
+
+// 每个任务保留的执行记录条数
+const JOB_RUN_HISTORY_LIMIT: i64 = 100;
+
+// 每写入多少条执行记录清理一次超出保留条数的旧记录（频繁执行的任务不必每次都清理）
+const JOB_RUN_PRUNE_INTERVAL: i64 = 50;
+
+// 一次后台任务执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: i64,
+    pub job_name: String,
+    pub trigger: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+fn job_run_from_row(row: &rusqlite::Row) -> Result<JobRun> {
+    Ok(JobRun {
+        id: row.get(0)?,
+        job_name: row.get(1)?,
+        trigger: row.get(2)?,
+        started_at: row.get(3)?,
+        finished_at: row.get(4)?,
+        success: row.get::<_, i32>(5)? != 0,
+        message: row.get(6)?,
+    })
+}
+
+const JOB_RUN_COLUMNS: &str = "id, job_name, trigger, started_at, finished_at, success, message";
+
+// 记录一次任务执行，定期清理各任务超出保留条数的旧记录
+pub fn insert_job_run(
+    job_name: &str,
+    trigger: &str,
+    started_at: &str,
+    success: bool,
+    message: Option<&str>,
+) -> Result<JobRun> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    let finished_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    insert_job_run_with(&conn, job_name, trigger, started_at, &finished_at, success, message)
+}
+
+pub fn insert_job_run_with(
+    conn: &Connection,
+    job_name: &str,
+    trigger: &str,
+    started_at: &str,
+    finished_at: &str,
+    success: bool,
+    message: Option<&str>,
+) -> Result<JobRun> {
+    conn.execute(
+        "INSERT INTO job_runs (job_name, trigger, started_at, finished_at, success, message) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![job_name, trigger, started_at, finished_at, success as i32, message],
+    )?;
+    let id = conn.last_insert_rowid();
+    if id % JOB_RUN_PRUNE_INTERVAL == 0 {
+        conn.execute(
+            "DELETE FROM job_runs WHERE id IN (
+                 SELECT id FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY job_name ORDER BY id DESC) AS n FROM job_runs)
+                 WHERE n > ?1
+             )",
+            [JOB_RUN_HISTORY_LIMIT],
+        )?;
+    }
+    
+    Ok(JobRun {
+        id,
+        job_name: job_name.to_string(),
+        trigger: trigger.to_string(),
+        started_at: started_at.to_string(),
+        finished_at: finished_at.to_string(),
+        success,
+        message: message.map(|m| m.to_string()),
+    })
+}
+
+// 获取任务执行记录，最近的在前（job_name 为空时返回所有任务；page 从 0 开始，每页 limit 条）
+pub fn fetch_job_runs(job_name: Option<&str>, limit: i64, page: i64) -> Result<Vec<JobRun>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    ListQuery::new(format!("SELECT {} FROM job_runs", JOB_RUN_COLUMNS))
+        .filter_opt("job_name = ?", job_name.map(str::to_string))
+        .order_by("id DESC")
+        .page(page, limit)
+        .fetch(&conn, job_run_from_row)
+}
+
+// 每个任务最近一次执行和最近一次成功执行
+pub fn fetch_latest_job_runs() -> Result<std::collections::HashMap<String, (JobRun, Option<String>)>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM job_runs WHERE id IN (SELECT MAX(id) FROM job_runs GROUP BY job_name)",
+        JOB_RUN_COLUMNS
+    ))?;
+    let latest: Vec<JobRun> = stmt.query_map([], job_run_from_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT job_name, MAX(finished_at) FROM job_runs WHERE success = 1 GROUP BY job_name"
+    )?;
+    let last_success: std::collections::HashMap<String, String> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    Ok(latest.into_iter().map(|run| {
+        let success_at = last_success.get(&run.job_name).cloned();
+        (run.job_name.clone(), (run, success_at))
+    }).collect())
+}
+
+// ==================== 数据完整性检查 ====================
+
+// 孤立记录检查项：类型、说明、所在表、判定孤立的条件
+const ORPHAN_CHECKS: &[(&str, &str, &str, &str)] = &[
+    ("orphan_activities", "活动所属项目已删除", "project_activities",
+     "project_id NOT IN (SELECT id FROM projects)"),
+    ("dangling_event_contacts", "事件-联系人关联指向已删除的事件或联系人", "events_contacts",
+     "event_id NOT IN (SELECT id FROM events) OR contact_id NOT IN (SELECT id FROM contacts)"),
+    ("dangling_event_projects", "事件-项目关联指向已删除的事件或项目", "events_projects",
+     "event_id NOT IN (SELECT id FROM events) OR project_id NOT IN (SELECT id FROM projects)"),
+    ("dangling_project_contacts", "项目-联系人关联指向已删除的项目或联系人", "projects_contacts",
+     "project_id NOT IN (SELECT id FROM projects) OR contact_id NOT IN (SELECT id FROM contacts)"),
+    ("dangling_activity_contacts", "活动负责人关联指向已删除的活动或联系人", "activities_contacts",
+     "activity_id NOT IN (SELECT id FROM project_activities) OR contact_id NOT IN (SELECT id FROM contacts)"),
+    ("orphan_project_files", "项目文件所属项目已删除", "project_files",
+     "project_id NOT IN (SELECT id FROM projects)"),
+    ("orphan_contact_files", "联系人文件所属联系人已删除", "contact_files",
+     "contact_id NOT IN (SELECT id FROM contacts)"),
+    ("orphan_activity_templates", "活动模板所属项目已删除", "activity_templates",
+     "project_id NOT IN (SELECT id FROM projects)"),
+    ("orphan_activity_reviews", "审核记录所属活动已删除", "activity_reviews",
+     "activity_id NOT IN (SELECT id FROM project_activities)"),
+    ("orphan_time_entries", "工时记录所属活动已删除", "activity_time_entries",
+     "activity_id NOT IN (SELECT id FROM project_activities)"),
+    ("orphan_reminder_inbox", "提醒记录所属事件已删除", "reminder_inbox",
+     "event_id NOT IN (SELECT id FROM events)"),
+];
+
+// 可为空的引用指向已删除的记录（外键为 ON DELETE SET NULL）：表、列、被引用的表
+const NULLABLE_REFERENCES: &[(&str, &str, &str)] = &[
+    ("events", "project_id", "projects"),
+    ("project_files", "event_id", "events"),
+    ("activity_reviews", "reviewer_contact_id", "contacts"),
+    ("activity_time_entries", "contact_id", "contacts"),
+];
+const DANGLING_REFERENCES_KIND: &str = "dangling_references";
+
+// 文件记录存在但磁盘上的文件已丢失
+const MISSING_BLOBS_KIND: &str = "missing_file_blobs";
+
+// 单类完整性问题
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub kind: String,
+    pub label: String,
+    pub count: i64,
+    pub samples: Vec<String>,  // 部分受影响的记录（如丢失的文件名）
+    pub suggestion: String,
+}
+
+// 完整性检查结果（只包含发现问题的检查项）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checked_at: String,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+// 某类问题的修复结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntegrityRepair {
+    pub kind: String,
+    pub removed: usize,
+}
+
+// 磁盘上已不存在的文件记录：(表名, ID, 文件名)
+fn find_missing_blobs(conn: &Connection) -> Result<Vec<(&'static str, i32, String)>> {
+    let mut missing = Vec::new();
+    for table in ["project_files", "contact_files"] {
+        let mut stmt = conn.prepare(&format!("SELECT id, original_name, file_path FROM {}", table))?;
+        let rows: Vec<(i32, String, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (id, name, path) in rows {
+            if !std::path::Path::new(&path).exists() {
+                missing.push((table, id, name));
+            }
+        }
+    }
+    Ok(missing)
+}
+
+// 检查孤立记录和丢失的文件
+fn check_integrity(conn: &Connection) -> Result<Vec<IntegrityIssue>> {
+    let mut issues = Vec::new();
+    for (kind, label, table, condition) in ORPHAN_CHECKS {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE {}", table, condition),
+            [],
+            |row| row.get(0),
+        )?;
+        if count > 0 {
+            issues.push(IntegrityIssue {
+                kind: kind.to_string(),
+                label: label.to_string(),
+                count,
+                samples: Vec::new(),
+                suggestion: "删除这些孤立记录".to_string(),
+            });
+        }
+    }
+    
+    let mut dangling = 0;
+    for (table, column, referenced) in NULLABLE_REFERENCES {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {0} WHERE {1} IS NOT NULL AND {1} NOT IN (SELECT id FROM {2})", table, column, referenced),
+            [],
+            |row| row.get(0),
+        )?;
+        dangling += count;
+    }
+    if dangling > 0 {
+        issues.push(IntegrityIssue {
+            kind: DANGLING_REFERENCES_KIND.to_string(),
+            label: "记录引用了已删除的项目、事件或联系人".to_string(),
+            count: dangling,
+            samples: Vec::new(),
+            suggestion: "清空这些失效的引用".to_string(),
+        });
+    }
+    
+    let missing = find_missing_blobs(conn)?;
+    if !missing.is_empty() {
+        issues.push(IntegrityIssue {
+            kind: MISSING_BLOBS_KIND.to_string(),
+            label: "文件记录对应的文件已丢失".to_string(),
+            count: missing.len() as i64,
+            samples: missing.iter().take(10).map(|(_, _, name)| name.clone()).collect(),
+            suggestion: "找回文件后放回原位置，或删除这些文件记录".to_string(),
+        });
+    }
+    Ok(issues)
+}
+
+// 修复指定类型的问题（删除孤立记录或丢失文件的记录）
+fn apply_integrity_repairs(conn: &Connection, kinds: &[&str]) -> Result<Vec<IntegrityRepair>> {
+    let mut repairs = Vec::new();
+    // 按检查项顺序修复：先删孤立活动，其负责人关联随后作为孤立关联删除
+    for (kind, _, table, condition) in ORPHAN_CHECKS {
+        if kinds.contains(kind) {
+            let removed = conn.execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])?;
+            repairs.push(IntegrityRepair { kind: kind.to_string(), removed });
+        }
+    }
+    if kinds.contains(&DANGLING_REFERENCES_KIND) {
+        let mut removed = 0;
+        for (table, column, referenced) in NULLABLE_REFERENCES {
+            removed += conn.execute(
+                &format!("UPDATE {0} SET {1} = NULL WHERE {1} IS NOT NULL AND {1} NOT IN (SELECT id FROM {2})", table, column, referenced),
+                [],
+            )?;
+        }
+        repairs.push(IntegrityRepair { kind: DANGLING_REFERENCES_KIND.to_string(), removed });
+    }
+    if kinds.contains(&MISSING_BLOBS_KIND) {
+        let missing = find_missing_blobs(conn)?;
+        for (table, id, _) in &missing {
+            conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [id])?;
+        }
+        repairs.push(IntegrityRepair { kind: MISSING_BLOBS_KIND.to_string(), removed: missing.len() });
+    }
+    Ok(repairs)
+}
+
+// 获取数据完整性检查报告
+pub fn fetch_integrity_report() -> Result<IntegrityReport> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    Ok(IntegrityReport {
+        checked_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        issues: check_integrity(&conn)?,
+    })
+}
+
+// 修复指定类型的完整性问题（在一个事务中完成）
+pub fn repair_integrity(kinds: &[&str]) -> Result<Vec<IntegrityRepair>> {
+    let known = |k: &str| k == MISSING_BLOBS_KIND || k == DANGLING_REFERENCES_KIND || ORPHAN_CHECKS.iter().any(|(kind, ..)| *kind == k);
+    if let Some(unknown) = kinds.iter().find(|k| !known(k)) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("未知的检查项: {}", unknown))
+        ));
+    }
+    
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let tx = conn.transaction()?;
+    let repairs = apply_integrity_repairs(&tx, kinds)?;
+    tx.commit()?;
+    Ok(repairs)
+}
+
+// 开启外键约束前的数据库版本（PRAGMA user_version）
+const SCHEMA_VERSION_FOREIGN_KEYS: i32 = 1;
+
+// 一次性迁移：删除孤立记录、清空失效引用（丢失的文件只在完整性报告中提示，不自动删除），并记入操作日志
+fn migrate_foreign_keys(conn: &Connection) -> Result<()> {
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= SCHEMA_VERSION_FOREIGN_KEYS {
+        return Ok(());
+    }
+    
+    let mut kinds: Vec<&str> = ORPHAN_CHECKS.iter().map(|(kind, ..)| *kind).collect();
+    kinds.push(DANGLING_REFERENCES_KIND);
+    let repairs = apply_integrity_repairs(conn, &kinds)?;
+    
+    let removed: usize = repairs.iter().map(|r| r.removed).sum();
+    if removed > 0 {
+        let details: Vec<String> = repairs.iter()
+            .filter(|r| r.removed > 0)
+            .map(|r| {
+                let label = ORPHAN_CHECKS.iter()
+                    .find(|(kind, ..)| *kind == r.kind)
+                    .map(|(_, label, ..)| *label)
+                    .unwrap_or("失效引用");
+                format!("{} {} 条", label, r.removed)
+            })
+            .collect();
+        let desc = format!("开启外键约束前清理孤立记录：{}", details.join("，"));
+        println!("🧹 {}", desc);
+        conn.execute(
+            "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description)
+             VALUES ('delete', 'database', 0, '孤立记录', ?1)",
+            [&desc],
+        )?;
+    }
+    
+    conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION_FOREIGN_KEYS))?;
+    Ok(())
+}
+
+// ==================== 启动恢复 ====================
+
+// 备份目录（与数据库同目录下的 backups，按修改时间取最新的 .db 文件）、保留的备份个数
+const BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_KEEP_COUNT: usize = 7;
+
+// 备份数据库到 backups 目录（VACUUM INTO 生成一致的副本），只保留最近的几份，返回备份文件路径
+pub fn backup_database() -> Result<PathBuf> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    backup_database_with(&conn, &connection_path(&conn), chrono::Local::now().naive_local())
+}
+
+pub fn backup_database_with(conn: &Connection, db_path: &std::path::Path, now: chrono::NaiveDateTime) -> Result<PathBuf> {
+    let io_error = |e: std::io::Error| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(format!("备份失败: {}", e)));
+    let dir = db_path.parent().map(|p| p.join(BACKUP_DIR_NAME)).unwrap_or_else(|| PathBuf::from(BACKUP_DIR_NAME));
+    std::fs::create_dir_all(&dir).map_err(io_error)?;
+    let target = dir.join(format!("memorystack-{}.db", now.format("%Y%m%d-%H%M%S")));
+    // VACUUM INTO 要求目标文件不存在（同一秒内重复备份时覆盖）
+    if target.exists() {
+        std::fs::remove_file(&target).map_err(io_error)?;
+    }
+    conn.execute("VACUUM INTO ?1", [target.to_string_lossy().to_string()])?;
+    
+    // 文件名带时间，按名称倒序即从新到旧
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(&dir).map_err(io_error)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("memorystack-") && n.ends_with(".db")))
+        .collect();
+    backups.sort_by(|a, b| b.cmp(a));
+    for old in backups.into_iter().skip(BACKUP_KEEP_COUNT) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(target)
+}
+
+// 启动时数据库的打开情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupStatus {
+    pub ok: bool,                     // 数据库是否可用
+    pub db_path: String,
+    pub problem: Option<String>,      // locked 被占用 / corrupt 已损坏 / error 其他错误（如无权限、磁盘未挂载，不做改动）/
+                                      // db_dir_unavailable 设置的数据库目录不可用，本次使用默认位置
+    pub recovery: Option<String>,     // restored_backup 已从备份恢复 / fresh_database 已新建数据库
+    pub backup_used: Option<String>,  // 恢复所用的备份文件
+    pub moved_to: Option<String>,     // 损坏的数据库被重命名后的路径
+    pub message: String,
+}
+
+static STARTUP_STATUS: OnceCell<StartupStatus> = OnceCell::new();
+
+// 数据库文件检查结果
+enum ProbeResult {
+    Ok,
+    Locked,
+    Corrupt(String),
+    Error(String),  // 无法打开或读取（权限、IO 等），文件本身不一定损坏
+}
+
+// 以只读方式快速检查数据库文件：只有文件损坏或不是数据库时才视为损坏
+fn probe_database(path: &std::path::Path) -> ProbeResult {
+    let classify = |e: rusqlite::Error| match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DatabaseBusy) | Some(rusqlite::ErrorCode::DatabaseLocked) => ProbeResult::Locked,
+        Some(rusqlite::ErrorCode::DatabaseCorrupt) | Some(rusqlite::ErrorCode::NotADatabase) => ProbeResult::Corrupt(e.to_string()),
+        _ => ProbeResult::Error(e.to_string()),
+    };
+    let conn = match Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(conn) => conn,
+        Err(e) => return classify(e),
+    };
+    match conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)) {
+        Ok(result) if result == "ok" => ProbeResult::Ok,
+        Ok(result) => ProbeResult::Corrupt(result),
+        Err(e) => classify(e),
+    }
+}
+
+// 最新的可用备份
+fn find_latest_backup(db_path: &std::path::Path) -> Option<PathBuf> {
+    let dir = db_path.parent()?.join(BACKUP_DIR_NAME);
+    let mut backups: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(dir).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|path| Some((std::fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    backups.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    backups.into_iter()
+        .map(|(_, path)| path)
+        .find(|path| matches!(probe_database(path), ProbeResult::Ok))
+}
+
+// 将损坏的数据库（及 -wal/-shm 文件）重命名保留，返回新路径
+fn move_corrupt_database(db_path: &std::path::Path) -> std::io::Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let moved = db_path.with_file_name(format!("{}.corrupt-{}", DB_FILE_NAME, stamp));
+    std::fs::rename(db_path, &moved)?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+        if side.exists() {
+            let _ = std::fs::rename(&side, format!("{}{}", moved.display(), suffix));
+        }
+    }
+    Ok(moved)
+}
+
+// 启动时打开数据库：损坏时重命名保留原文件，从最新备份恢复或新建数据库；被占用时不做改动
+pub fn initialize_database() -> StartupStatus {
+    let (db_path, unavailable_dir) = resolve_db_location();
+    let mut status = StartupStatus {
+        ok: false,
+        db_path: db_path.to_string_lossy().to_string(),
+        problem: None,
+        recovery: None,
+        backup_used: None,
+        moved_to: None,
+        message: String::new(),
+    };
+    
+    if db_path.exists() {
+        match probe_database(&db_path) {
+            ProbeResult::Ok => {}
+            ProbeResult::Locked => {
+                status.problem = Some("locked".to_string());
+                status.message = "数据库文件正被其他程序占用，请关闭其他 MemoryStack 窗口或同步客户端后重启".to_string();
+                return remember_startup_status(status);
+            }
+            ProbeResult::Error(reason) => {
+                status.problem = Some("error".to_string());
+                status.message = format!("无法读取数据库文件（请检查文件权限和所在磁盘）: {}", reason);
+                return remember_startup_status(status);
+            }
+            ProbeResult::Corrupt(reason) => {
+                println!("⚠️ 数据库已损坏: {}", reason);
+                status.problem = Some("corrupt".to_string());
+                match move_corrupt_database(&db_path) {
+                    Ok(moved) => status.moved_to = Some(moved.to_string_lossy().to_string()),
+                    Err(e) => {
+                        status.message = format!("数据库已损坏，且无法重命名: {}", e);
+                        return remember_startup_status(status);
+                    }
+                }
+                match find_latest_backup(&db_path) {
+                    Some(backup) if std::fs::copy(&backup, &db_path).is_ok() => {
+                        status.recovery = Some("restored_backup".to_string());
+                        status.message = format!("数据库已损坏，已从备份 {} 恢复", backup.display());
+                        status.backup_used = Some(backup.to_string_lossy().to_string());
+                    }
+                    _ => {
+                        status.recovery = Some("fresh_database".to_string());
+                        status.message = "数据库已损坏且没有可用备份，已新建空数据库，原文件已重命名保留".to_string();
+                    }
+                }
+            }
+        }
+    }
+    
+    match get_db() {
+        Ok(_) => {
+            status.ok = true;
+            if let Some(dir) = unavailable_dir {
+                println!("⚠️ 设置的数据库目录不可用: {}", dir);
+                status.problem.get_or_insert_with(|| "db_dir_unavailable".to_string());
+                let notice = format!(
+                    "设置的数据库目录 {} 不可用（目录不存在或磁盘未连接），本次改用默认位置 {}；目录恢复后重启即可使用原数据库",
+                    dir, db_path.display()
+                );
+                status.message = if status.message.is_empty() { notice } else { format!("{}；{}", status.message, notice) };
+            }
+            if status.message.is_empty() {
+                status.message = "数据库正常".to_string();
+            }
+        }
+        Err(e) => {
+            status.problem.get_or_insert_with(|| "error".to_string());
+            status.message = format!("数据库初始化失败: {}", e);
+        }
+    }
+    remember_startup_status(status)
+}
+
+fn remember_startup_status(status: StartupStatus) -> StartupStatus {
+    let _ = STARTUP_STATUS.set(status.clone());
+    status
+}
+
+// 获取启动时数据库的打开情况
+pub fn get_startup_status() -> Option<StartupStatus> {
+    STARTUP_STATUS.get().cloned()
+}
+
+// ==================== 演示数据 ====================
+
+// 演示数据生成结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DemoDataSummary {
+    pub projects: usize,
+    pub contacts: usize,
+    pub events: usize,
+    pub activities: usize,
+}
+
+// 演示联系人：姓名、职位、标签、电话、邮箱、地址、单位
+const DEMO_CONTACTS: &[(&str, &str, &str, &str, &str, &str, &str)] = &[
+    ("王建国", "采购总监", "客户,决策人", "13800138001", "wangjg@huaxin-tech.com", "上海市浦东新区张江路 88 号", "华信科技"),
+    ("李晓雯", "产品经理", "客户", "13800138002", "lixw@huaxin-tech.com", "上海市浦东新区张江路 88 号", "华信科技"),
+    ("陈志强", "技术负责人", "技术,合作伙伴", "13900139003", "chenzq@yunfan.cn", "浙江省杭州市西湖区文三路 259 号", "云帆软件"),
+    ("刘芳", "财务经理", "财务", "13700137004", "liufang@yunfan.cn", "浙江省杭州市西湖区文三路 259 号", "云帆软件"),
+    ("赵磊", "项目经理", "同事", "13600136005", "zhaolei@example.com", "北京市海淀区中关村大街 1 号", "本公司"),
+    ("孙丽", "设计师", "同事,设计", "13500135006", "sunli@example.com", "北京市海淀区中关村大街 1 号", "本公司"),
+    ("周建华", "区域经理", "客户,渠道", "13300133007", "zhoujh@nanfang-dist.com", "广东省深圳市南山区科技园", "南方分销"),
+    ("吴敏", "法务顾问", "法务", "13200132008", "wumin@lawfirm.cn", "北京市朝阳区建国路 93 号", "金诚律师事务所"),
+];
+
+// 演示项目：名称、描述、成员（联系人下标、角色）
+type DemoProject = (&'static str, &'static str, &'static [(usize, &'static str)]);
+const DEMO_PROJECTS: &[DemoProject] = &[
+    ("华信CRM升级", "为华信科技升级客户管理系统，含数据迁移和培训", &[(0, "甲方负责人"), (1, "需求对接"), (4, "项目经理"), (5, "UI设计")]),
+    ("云帆数据平台合作", "与云帆软件联合开发数据分析平台", &[(2, "技术对接"), (3, "结算对接"), (4, "项目经理")]),
+    ("华南渠道拓展", "通过南方分销拓展华南市场", &[(6, "渠道负责人"), (7, "合同审核")]),
+];
+
+// 演示事件：相对今天的天数、标题、描述、项目下标、类型、参与人下标
+type DemoEvent = (i64, &'static str, &'static str, Option<usize>, &'static str, &'static [usize]);
+const DEMO_EVENTS: &[DemoEvent] = &[
+    (-30, "项目启动会", "确定范围、里程碑和双方对接人", Some(0), "会议", &[0, 1, 4]),
+    (-21, "需求访谈", "梳理销售和客服两条线的痛点", Some(0), "会议", &[1, 5]),
+    (-14, "电话沟通报价", "对方希望分两期付款", Some(0), "电话", &[0]),
+    (-10, "技术方案评审", "确认采用云帆的数据中台", Some(1), "视频会议", &[2, 4]),
+    (-7, "发送合作协议草稿", "附件为协议 v1", Some(1), "邮件", &[3]),
+    (-5, "渠道政策讨论", "返点比例待定", Some(2), "微信", &[6]),
+    (-3, "合同条款审核", "违约责任条款需要修改", Some(2), "电话", &[7]),
+    (-1, "设计稿确认", "首页和报表页通过", Some(0), "会议", &[1, 5]),
+    (0, "周例会", "同步各项目进展", None, "会议", &[4, 5]),
+    (2, "二期报价提交", "提交二期报价单", Some(0), "截止", &[0]),
+    (5, "深圳拜访", "拜访南方分销，面谈渠道政策", Some(2), "会议", &[6]),
+    (14, "数据平台上线", "第一阶段上线", Some(1), "里程碑", &[2, 3, 4]),
+];
+
+// 演示活动：项目下标、名称、相对今天的预计完成天数、状态、负责人下标、预估工时
+type DemoActivity = (usize, &'static str, i64, &'static str, &'static [usize], f64);
+const DEMO_ACTIVITIES: &[DemoActivity] = &[
+    (0, "整理需求文档", -15, "已完成", &[4], 16.0),
+    (0, "完成界面设计", -2, "已完成", &[5], 24.0),
+    (0, "准备二期报价", 2, "进行中", &[4], 6.0),
+    (0, "历史数据迁移", 20, "未激活", &[4], 40.0),
+    (1, "签订合作协议", -2, "进行中", &[4], 4.0),
+    (1, "搭建测试环境", 7, "已暂停", &[2], 12.0),
+    (2, "确定渠道返点政策", 5, "进行中", &[6], 8.0),
+    (2, "合同修改", 3, "待分配", &[], 3.0),
+];
+
+// 数据库中是否已有项目或联系人（用于判断是否为已在使用的数据库）
+pub fn has_workspace_data() -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    has_workspace_data_with(&conn)
+}
+
+pub fn has_workspace_data_with(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM projects) OR EXISTS(SELECT 1 FROM contacts)",
+        [],
+        |row| row.get(0)
+    )
+}
+
+// 在空数据库中生成演示数据（已有项目或联系人时拒绝，避免混入真实数据；整批在同一事务中，失败时不留下部分数据）
+pub fn seed_demo_data() -> Result<DemoDataSummary> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    seed_demo_data_with(&mut conn)
+}
+
+pub fn seed_demo_data_with(conn: &mut Connection) -> Result<DemoDataSummary> {
+    let tx = conn.transaction()?;
+    if has_workspace_data_with(&tx)? {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("数据库中已有数据，只能在空数据库中生成演示数据".to_string())
+        ));
+    }
+    
+    let today = chrono::Local::now().date_naive();
+    let date = |offset: i64| (today + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    
+    let mut contact_ids = Vec::new();
+    for (name, title, tags, phone, email, address, company) in DEMO_CONTACTS {
+        let phone = serde_json::to_string(&[phone]).unwrap_or_default();
+        let id = insert_contact_with(&tx, &NewContact {
+            name: name.to_string(),
+            title: Some(title.to_string()),
+            notes: None,
+            tags: Some(tags.to_string()),
+            phone: Some(phone),
+            email: Some(email.to_string()),
+            address: Some(address.to_string()),
+            company: Some(company.to_string()),
+        })?;
+        contact_ids.push(id as i32);
+    }
+    
+    let mut project_ids = Vec::new();
+    for (name, description, members) in DEMO_PROJECTS {
+        let project_id = insert_project_with(&tx, name, Some(description))? as i32;
+        for (contact, role) in *members {
+            link_contact_to_project_with(&tx, project_id, contact_ids[*contact], Some(role), None)?;
+        }
+        project_ids.push(project_id);
+    }
+    
+    for (offset, title, description, project, event_type, participants) in DEMO_EVENTS {
+        // 未来的事件当天 9 点提醒
+        let reminder = (*offset > 0).then(|| format!("{} 09:00:00", date(*offset)));
+        let event_id = insert_event_with(
+            &tx,
+            title,
+            Some(description),
+            &date(*offset),
+            project.map(|p| project_ids[p]),
+            Some(event_type),
+            reminder.as_deref(),
+        )?;
+        let participants: Vec<i32> = participants.iter().map(|c| contact_ids[*c]).collect();
+        link_contacts_to_event_with(&tx, event_id, &participants)?;
+    }
+    
+    for (project, name, due_offset, status, assignees, hours) in DEMO_ACTIVITIES {
+        let activity_id = insert_activity_with(&tx, project_ids[*project], name, None, Some(date(*due_offset).as_str()), Some(*hours))?;
+        let assignees: Vec<i32> = assignees.iter().map(|c| contact_ids[*c]).collect();
+        if !assignees.is_empty() {
+            assign_contacts_to_activity_with(&tx, activity_id, &assignees)?;
+        }
+        let activity_id = activity_id as i32;
+        match *status {
+            "进行中" => activate_activity_with(&tx, activity_id, &now)?,
+            "已暂停" => {
+                activate_activity_with(&tx, activity_id, &now)?;
+                pause_activity_with(&tx, activity_id, &now)?;
+            }
+            "已完成" => {
+                activate_activity_with(&tx, activity_id, &now)?;
+                insert_time_entry_with(&tx, activity_id, assignees.first().copied(), hours * 1.2, Some(date(*due_offset).as_str()), None)?;
+                complete_activity_with(&tx, activity_id, &now)?;
+            }
+            _ => {}
+        }
+    }
+    
+    tx.commit()?;
+    Ok(DemoDataSummary {
+        projects: DEMO_PROJECTS.len(),
+        contacts: DEMO_CONTACTS.len(),
+        events: DEMO_EVENTS.len(),
+        activities: DEMO_ACTIVITIES.len(),
+    })
+}
+
+// ==================== 命令请求结构 ====================
+
+// 新建联系人请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewContact {
+    pub name: String,
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Option<String>,
+    pub phone: Option<String>,      // JSON 数组格式
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub company: Option<String>,
+}
+
+impl NewContact {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("联系人姓名不能为空".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 新建事件请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewEvent {
+    pub title: String,
+    pub description: Option<String>,
+    pub event_date: String,
+    pub project_id: Option<i32>,
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub contact_ids: Vec<i32>,
+    pub reminder_time: Option<String>,
+    #[serde(default)]
+    pub extra_project_ids: Vec<i32>,  // 跨项目事件关联的其他项目
+    pub channel: Option<String>,      // 未指定时按事件类型推断
+}
+
+impl NewEvent {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        validate_event_fields(&self.title, &self.event_date, self.reminder_time.as_deref())?;
+        if self.contact_ids.is_empty() {
+            return Err("事件必须关联至少一个联系人".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 更新事件请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateEvent {
+    pub event_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub event_date: String,
+    pub project_id: Option<i32>,
+    pub event_type: Option<String>,
+    pub reminder_time: Option<String>,
+    #[serde(default)]
+    pub contact_ids: Vec<i32>,
+    pub extra_project_ids: Option<Vec<i32>>,  // 未传入时保留原有的其他关联项目
+    pub channel: Option<String>,
+}
+
+impl UpdateEvent {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        validate_event_fields(&self.title, &self.event_date, self.reminder_time.as_deref())
+    }
+}
+
+// 校验事件标题、日期和提醒时间
+fn validate_event_fields(title: &str, event_date: &str, reminder_time: Option<&str>) -> std::result::Result<(), String> {
+    if title.trim().is_empty() {
+        return Err("事件标题不能为空".to_string());
+    }
+    if parse_event_datetime(event_date).is_none() {
+        return Err(format!("无效的事件日期: {}", event_date));
+    }
+    if let Some(reminder) = reminder_time.filter(|r| !r.trim().is_empty()) {
+        if parse_event_datetime(reminder).is_none() {
+            return Err(format!("无效的提醒时间: {}", reminder));
+        }
+    }
+    Ok(())
+}
+
+// 独立窗口要打开的实体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityRef {
+    pub entity_type: String,  // project / contact
+    pub entity_id: i32,
+}
+
+impl EntityRef {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !crate::windows::ENTITY_WINDOW_TYPES.contains(&self.entity_type.as_str()) {
+            return Err(format!("不支持在独立窗口中打开: {}", self.entity_type));
+        }
+        Ok(())
+    }
+}
+
+// ==================== API 信息 ====================
+
+// 可选功能是否启用（尚未实现的功能为 false）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiFeatures {
+    pub sync: bool,        // 多设备同步
+    pub ai: bool,          // AI 辅助
+    pub encryption: bool,  // 数据库加密
+}
+
+// 命令支持的接口版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandVersions {
+    pub name: String,
+    pub versions: Vec<u32>,             // 支持的版本，如 [1, 2] 表示 create_event 和 create_event_v2 都可用
+    pub deprecated_versions: Vec<u32>,  // 已弃用、将来会移除的版本
+}
+
+// 前端（及插件）用于判断后端能力的接口信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiInfo {
+    pub api_version: u32,
+    pub app_version: String,
+    pub schema_version: i32,   // 数据库结构版本（已执行的迁移数：开启外键约束的版本号加上 schema_migrations 中的迁移数）
+    pub latest_migration: Option<String>,  // 最近一次执行的 schema_migrations 迁移
+    pub memory_db: bool,       // 是否使用内存数据库（数据不会保存）
+    pub features: ApiFeatures,
+    pub commands: Vec<CommandVersions>,
+}
+
+// 读取数据库结构版本和最近一次执行的迁移
+pub fn fetch_schema_version() -> Result<(i32, Option<String>)> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_schema_version_with(&conn)
+}
+
+pub fn fetch_schema_version_with(conn: &Connection) -> Result<(i32, Option<String>)> {
+    let user_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let (migrations, latest): (i32, Option<String>) = conn.query_row(
+        "SELECT COUNT(*), (SELECT name FROM schema_migrations ORDER BY rowid DESC LIMIT 1) FROM schema_migrations",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )?;
+    Ok((user_version + migrations, latest))
+}
+
+// ==================== 查询统计 ====================
+
+// 预编译语句缓存容量
+const STATEMENT_CACHE_CAPACITY: usize = 128;
+
+// 耗时分布的分桶上限（毫秒），超过最后一个上限的计入最后一桶
+const QUERY_TIME_BUCKETS_MS: &[u64] = &[1, 5, 20, 100, 500];
+
+// 单条语句的累计耗时
+#[derive(Default)]
+struct QueryTiming {
+    calls: u64,
+    total: std::time::Duration,
+    max: std::time::Duration,
+    buckets: [u64; 6],   // 对应 QUERY_TIME_BUCKETS_MS 各档及超出部分
+}
+
+static QUERY_TIMINGS: once_cell::sync::Lazy<Mutex<std::collections::HashMap<String, QueryTiming>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+
+// SQLite 每执行完一条语句调用一次（空白折叠后的 SQL 作为键）
+fn record_query_time(event: rusqlite::trace::TraceEvent<'_>) {
+    let rusqlite::trace::TraceEvent::Profile(stmt, elapsed) = event else {
+        return;
+    };
+    let key = stmt.sql().split_whitespace().collect::<Vec<_>>().join(" ");
+    let Ok(mut timings) = QUERY_TIMINGS.lock() else {
+        return;
+    };
+    let timing = timings.entry(key).or_default();
+    timing.calls += 1;
+    timing.total += elapsed;
+    timing.max = timing.max.max(elapsed);
+    let ms = elapsed.as_millis() as u64;
+    let bucket = QUERY_TIME_BUCKETS_MS.iter().position(|&limit| ms < limit).unwrap_or(QUERY_TIME_BUCKETS_MS.len());
+    timing.buckets[bucket] += 1;
+}
+
+// 耗时分布中的一档
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryTimeBucket {
+    pub below_ms: Option<u64>,   // None 表示超过最大分档
+    pub count: u64,
+}
+
+// 单条语句的耗时统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryMetric {
+    pub sql: String,
+    pub calls: u64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub histogram: Vec<QueryTimeBucket>,
+}
+
+// 按总耗时从高到低列出语句耗时统计
+pub fn fetch_query_metrics(limit: usize) -> Vec<QueryMetric> {
+    let Ok(timings) = QUERY_TIMINGS.lock() else {
+        return Vec::new();
+    };
+    let mut metrics: Vec<QueryMetric> = timings.iter().map(|(sql, timing)| {
+        let total_ms = timing.total.as_secs_f64() * 1000.0;
+        QueryMetric {
+            sql: sql.clone(),
+            calls: timing.calls,
+            total_ms,
+            avg_ms: total_ms / timing.calls.max(1) as f64,
+            max_ms: timing.max.as_secs_f64() * 1000.0,
+            histogram: timing.buckets.iter().enumerate().map(|(i, &count)| QueryTimeBucket {
+                below_ms: QUERY_TIME_BUCKETS_MS.get(i).copied(),
+                count,
+            }).collect(),
+        }
+    }).collect();
+    metrics.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    metrics.truncate(limit);
+    metrics
+}
+
+// 清空耗时统计
+pub fn reset_query_metrics() {
+    if let Ok(mut timings) = QUERY_TIMINGS.lock() {
+        timings.clear();
+    }
+}
+
+// ==================== 项目日志 ====================
+
+// 为未记录项目的旧日志补上项目（项目日志取自身，活动和事件取所属项目）
+fn backfill_log_projects(conn: &Connection) -> Result<()> {
+    let mut updated = conn.execute(
+        "UPDATE operation_logs SET project_id = entity_id, project_name = entity_name
+         WHERE project_id IS NULL AND entity_type = 'project'",
+        [],
+    )?;
+    updated += conn.execute(
+        "UPDATE operation_logs SET
+             project_id = (SELECT a.project_id FROM project_activities a WHERE a.id = operation_logs.entity_id),
+             project_name = (SELECT p.name FROM project_activities a INNER JOIN projects p ON p.id = a.project_id WHERE a.id = operation_logs.entity_id)
+         WHERE project_id IS NULL AND entity_type = 'activity'
+         AND entity_id IN (SELECT id FROM project_activities)",
+        [],
+    )?;
+    updated += conn.execute(
+        "UPDATE operation_logs SET
+             project_id = (SELECT e.project_id FROM events e WHERE e.id = operation_logs.entity_id),
+             project_name = (SELECT p.name FROM events e INNER JOIN projects p ON p.id = e.project_id WHERE e.id = operation_logs.entity_id)
+         WHERE project_id IS NULL AND entity_type = 'event'
+         AND entity_id IN (SELECT id FROM events WHERE project_id IS NOT NULL)",
+        [],
+    )?;
+    if updated > 0 {
+        println!("🔧 已为 {} 条旧操作日志补上所属项目", updated);
+    }
+    Ok(())
+}
+
+// 项目的操作日志（含关联到该项目的跨项目事件），按时间倒序；日期为 YYYY-MM-DD 闭区间
+pub fn fetch_project_log(project_id: i32, start_date: Option<&str>, end_date: Option<&str>) -> Result<Vec<OperationLog>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_project_log_with(&conn, project_id, start_date, end_date)
+}
+
+pub fn fetch_project_log_with(conn: &Connection, project_id: i32, start_date: Option<&str>, end_date: Option<&str>) -> Result<Vec<OperationLog>> {
+    let start_datetime = start_date.map(|d| format!("{} 00:00:00", d));
+    let end_datetime = end_date.map(|d| format!("{} 23:59:59", d));
+    
+    ListQuery::new(format!("SELECT {} FROM operation_logs", OPERATION_LOG_COLUMNS))
+        .filter(
+            "project_id = ? OR (entity_type = 'event' AND entity_id IN (SELECT event_id FROM events_projects WHERE project_id = ?))",
+            project_id,
+        )
+        .filter_opt("created_at >= ?", start_datetime)
+        .filter_opt("created_at <= ?", end_datetime)
+        .order_by("created_at DESC")
+        .order_by("id DESC")
+        .fetch(conn, operation_log_from_row)
+}
+
+// ==================== 时间线分组 ====================
+
+// 时间线中的一组事件
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineGroup {
+    pub key: String,     // 项目ID（未关联项目为 "none"）或 YYYY-MM
+    pub label: String,
+    pub count: usize,
+    pub events: Vec<EventWithDetails>,
+}
+
+// 按主项目或月份分组（组内保持原有顺序；项目组按最近事件排序，月份从新到旧）
+// 只有跨项目关联的事件归入第一个关联项目
+pub fn group_timeline(events: Vec<EventWithDetails>, group_by: &str) -> std::result::Result<Vec<TimelineGroup>, String> {
+    if group_by != "project" && group_by != "month" {
+        return Err(format!("不支持的分组方式: {}（可选 project、month）", group_by));
+    }
+    
+    let mut groups: Vec<TimelineGroup> = Vec::new();
+    for item in events {
+        let (key, label) = if group_by == "project" {
+            let project = item.event.project_id
+                .map(|pid| (pid, item.project_name.clone()))
+                .or_else(|| item.event.project_ids.first().map(|pid| (*pid, item.project_names.first().cloned())));
+            match project {
+                Some((pid, name)) => (pid.to_string(), name.unwrap_or_else(|| format!("项目 {}", pid))),
+                None => ("none".to_string(), "未关联项目".to_string()),
+            }
+        } else {
+            let month = item.event.event_date.get(..7).unwrap_or(&item.event.event_date).to_string();
+            let label = month.replacen('-', "年", 1) + "月";
+            (month, label)
+        };
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(group) => group.events.push(item),
+            None => groups.push(TimelineGroup { key, label, count: 0, events: vec![item] }),
+        }
+    }
+    
+    let latest = |group: &TimelineGroup| group.events.iter().map(|e| e.event.event_date.clone()).max().unwrap_or_default();
+    if group_by == "month" {
+        groups.sort_by(|a, b| b.key.cmp(&a.key));
+    } else {
+        groups.sort_by_key(|g| std::cmp::Reverse(latest(g)));
+    }
+    for group in &mut groups {
+        group.count = group.events.len();
+    }
+    Ok(groups)
+}
+
+// ==================== 草稿 ====================
+
+// 可保存草稿的表单类型
+pub const DRAFT_ENTITY_TYPES: &[&str] = &["event", "contact", "project", "activity"];
+
+// 草稿
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Draft {
+    pub id: i32,
+    pub entity_type: String,
+    pub entity_id: Option<i32>,
+    pub payload: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const DRAFT_COLUMNS: &str = "id, entity_type, entity_id, payload, created_at, updated_at";
+
+fn draft_from_row(row: &rusqlite::Row) -> Result<Draft> {
+    Ok(Draft {
+        id: row.get(0)?,
+        entity_type: row.get(1)?,
+        entity_id: row.get(2)?,
+        payload: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+// 保存草稿：传入 draft_id 时覆盖该草稿（自动保存），否则新建
+pub fn save_draft(draft_id: Option<i32>, entity_type: &str, entity_id: Option<i32>, payload: &str) -> Result<Draft> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    if !DRAFT_ENTITY_TYPES.contains(&entity_type) {
+        return Err(invalid(format!("不支持的草稿类型: {}", entity_type)));
+    }
+    if serde_json::from_str::<serde_json::Value>(payload).is_err() {
+        return Err(invalid("草稿内容不是有效的 JSON".to_string()));
+    }
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let id = match draft_id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE drafts SET entity_type = ?1, entity_id = ?2, payload = ?3, updated_at = ?4 WHERE id = ?5",
+                rusqlite::params![entity_type, entity_id, payload, now, id],
+            )?;
+            if updated == 0 {
+                return Err(invalid(format!("草稿不存在: {}", id)));
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO drafts (entity_type, entity_id, payload, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+                rusqlite::params![entity_type, entity_id, payload, now],
+            )?;
+            conn.last_insert_rowid() as i32
+        }
+    };
+    
+    conn.query_row(
+        &format!("SELECT {} FROM drafts WHERE id = ?1", DRAFT_COLUMNS),
+        [id],
+        draft_from_row,
+    )
+}
+
+// 列出草稿（可按类型筛选），最近保存的在前
+pub fn fetch_drafts(entity_type: Option<&str>) -> Result<Vec<Draft>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    ListQuery::new(format!("SELECT {} FROM drafts", DRAFT_COLUMNS))
+        .filter_opt("entity_type = ?", entity_type.map(str::to_string))
+        .order_by("updated_at DESC")
+        .order_by("id DESC")
+        .fetch(&conn, draft_from_row)
+}
+
+// 删除草稿（表单保存成功或用户放弃时）
+pub fn delete_draft(draft_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    Ok(conn.execute("DELETE FROM drafts WHERE id = ?1", [draft_id])? > 0)
+}
+
+// ==================== 每日简报 ====================
+
+// 每日简报的统计数据（存入 summaries.statistics）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BriefingStatistics {
+    pub events_today: i64,
+    pub due_activities: i64,      // 今天到期及已逾期的未完成活动
+    pub overdue_activities: i64,
+    pub pending_follow_ups: i64,  // 已提醒但尚未确认的事件
+    pub has_yesterday_summary: bool,
+}
+
+// 生成简报后是否发送通知（默认不发送）
+pub fn is_briefing_notification_enabled() -> Result<bool> {
+    Ok(get_setting(SETTING_BRIEFING_NOTIFICATION)?
+        .and_then(|v| parse_switch(&v))
+        .unwrap_or(false))
+}
+
+// 生成某天的每日简报（同一天已有简报时在原记录上更新）
+pub fn generate_daily_briefing(date: chrono::NaiveDate, is_auto: bool) -> Result<Summary> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    generate_daily_briefing_with(&conn, date, is_auto)
+}
+
+pub fn generate_daily_briefing_with(conn: &Connection, date: chrono::NaiveDate, is_auto: bool) -> Result<Summary> {
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let (title, content, statistics) = render_daily_briefing_with(conn, date)?;
+    
+    let existing = conn.query_row(
+        &format!("SELECT {} FROM summaries WHERE summary_type = 'briefing' AND start_date = ?1", SUMMARY_COLUMNS),
+        [&date_str],
+        summary_from_row
+    );
+    match existing {
+        Ok(existing) => {
+            conn.execute(
+                "UPDATE summaries SET title = ?1, content = ?2, statistics = ?3 WHERE id = ?4",
+                rusqlite::params![title, content, statistics, existing.id],
+            )?;
+            Ok(Summary {
+                title,
+                content,
+                statistics: Some(statistics),
+                ..existing
+            })
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => insert_summary_with(conn, "briefing", &date_str, &date_str, (title, content, statistics), is_auto),
+        Err(e) => Err(e),
+    }
+}
+
+// 渲染每日简报：今日事件、到期活动、待跟进提醒和昨日总结
+fn render_daily_briefing_with(conn: &Connection, date: chrono::NaiveDate) -> Result<(String, String, String)> {
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let prefs = DisplayPreferences::from_settings(&fetch_settings_with(conn)?);
+    let now = chrono::Local::now();
+    let date_display = prefs.format_date(&date_str);
+    let mut stats = BriefingStatistics::default();
+    
+    let title = format!("{} 每日简报", date_display);
+    let mut content = String::new();
+    content.push_str(&format!("# {} 每日简报\n\n", date_display));
+    content.push_str(&format!("生成时间：{}\n\n", now.format(&prefs.datetime_format)));
+    content.push_str("---\n\n");
+    
+    // 今日事件（按时间排序）
+    content.push_str("## 今日事件\n\n");
+    let mut stmt = conn.prepare_cached(
+        "SELECT e.event_date, e.title, p.name FROM events e
+         LEFT JOIN projects p ON p.id = e.project_id
+         WHERE substr(e.event_date, 1, 10) = ?1 AND e.is_private = 0
+         ORDER BY e.event_date"
+    )?;
+    let events: Vec<(String, String, Option<String>)> = stmt.query_map([&date_str], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    stats.events_today = events.len() as i64;
+    if events.is_empty() {
+        content.push_str("今天没有安排的事件。\n");
+    }
+    for (event_date, title, project_name) in &events {
+        let time = event_date.get(11..16).filter(|t| *t != "00:00").unwrap_or("全天");
+        match project_name {
+            Some(project) => content.push_str(&format!("- {} {}（{}）\n", time, title, project)),
+            None => content.push_str(&format!("- {} {}\n", time, title)),
+        }
+    }
+    
+    // 到期活动：截止日落在节假日时顺延到下一个工作日，与逾期统计一致
+    content.push_str("\n## 到期活动\n\n");
+    let calendar = WorkCalendar::load(conn)?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT a.name, p.name, substr(a.estimated_completion_date, 1, 10) FROM project_activities a
+         INNER JOIN projects p ON p.id = a.project_id
+         WHERE a.estimated_completion_date IS NOT NULL
+         AND a.estimated_completion_date != ''
+         AND substr(a.estimated_completion_date, 1, 10) <= ?1
+         AND a.status != '已完成'
+         ORDER BY a.estimated_completion_date, a.id"
+    )?;
+    let activities: Vec<(String, String, String)> = stmt.query_map([&date_str], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    for (name, project_name, due) in &activities {
+        let overdue = chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d")
+            .is_ok_and(|due_date| calendar.on_or_after(due_date) < date);
+        if overdue {
+            stats.overdue_activities += 1;
+            content.push_str(&format!("- 【逾期】{}（{}，预计完成 {}）\n", name, project_name, prefs.format_date(due)));
+        } else {
+            content.push_str(&format!("- {}（{}，预计完成 {}）\n", name, project_name, prefs.format_date(due)));
+        }
+    }
+    stats.due_activities = activities.len() as i64;
+    if activities.is_empty() {
+        content.push_str("没有到期的活动。\n");
+    }
+    
+    // 待跟进：已提醒但尚未确认的事件
+    content.push_str("\n## 待跟进\n\n");
+    let follow_ups = fetch_reminder_inbox_with(conn)?;
+    stats.pending_follow_ups = follow_ups.len() as i64;
+    if follow_ups.is_empty() {
+        content.push_str("没有待跟进的提醒。\n");
+    }
+    for item in &follow_ups {
+        if item.notify_count > 1 {
+            content.push_str(&format!("- {}（已提醒 {} 次）\n", item.title, item.notify_count));
+        } else {
+            content.push_str(&format!("- {}\n", item.title));
+        }
+    }
+    
+    // 昨日总结
+    content.push_str("\n## 昨日总结\n\n");
+    let yesterday = (date - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let yesterday_summary = conn.query_row(
+        &format!("SELECT {} FROM summaries WHERE summary_type = 'daily' AND start_date = ?1 ORDER BY created_at DESC LIMIT 1", SUMMARY_COLUMNS),
+        [&yesterday],
+        summary_from_row
+    );
+    match yesterday_summary {
+        Ok(summary) => {
+            stats.has_yesterday_summary = true;
+            content.push_str(&format!("{}\n\n", summary.title));
+            let period: PeriodStatistics = summary.statistics.as_deref()
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or_default();
+            content.push_str(&format!("- 总操作数：{}\n", period.total_operations));
+            content.push_str(&format!("- 新增事件：{}\n", period.new_events));
+            content.push_str(&format!("- 完成活动：{}\n", period.completed_activities));
+            content.push_str(&format!("- 新增文件：{}\n", period.files_added));
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => content.push_str("昨天没有生成日总结。\n"),
+        Err(e) => return Err(e),
+    }
+    
+    let statistics = serde_json::to_string(&stats).unwrap_or_default();
+    Ok((title, content, statistics))
+}
+
+// ==================== 每日记录 ====================
+
+// 每日记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayNote {
+    pub id: i32,
+    pub note_date: String,
+    pub content: String,
+    pub mood: Option<String>,
+    pub mood_rating: Option<i32>,    // 心情评分 1-5
+    pub energy_rating: Option<i32>,  // 精力评分 1-5
+    #[serde(default)]
+    pub is_private: bool,            // 私密：不出现在导出和自动总结中
+    pub created_at: String,
+}
+
+const DAY_NOTE_COLUMNS: &str = "id, note_date, content, mood, created_at, mood_rating, energy_rating, is_private";
+
+// 评分范围
+pub const WELLBEING_RATING_MIN: i32 = 1;
+pub const WELLBEING_RATING_MAX: i32 = 5;
+
+fn day_note_from_row(row: &rusqlite::Row) -> Result<DayNote> {
+    Ok(DayNote {
+        id: row.get(0)?,
+        note_date: row.get(1)?,
+        content: row.get(2)?,
+        mood: row.get(3)?,
+        mood_rating: row.get(5)?,
+        energy_rating: row.get(6)?,
+        is_private: row.get::<_, Option<i32>>(7)?.unwrap_or(0) != 0,
+        created_at: row.get(4)?,
+    })
+}
+
+// 记录某天的心情和感想（只打分时内容可以为空）
+pub fn insert_day_note(
+    note_date: &str,
+    content: &str,
+    mood: Option<&str>,
+    mood_rating: Option<i32>,
+    energy_rating: Option<i32>,
+) -> Result<DayNote> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    if chrono::NaiveDate::parse_from_str(note_date, "%Y-%m-%d").is_err() {
+        return Err(invalid(format!("无效的日期: {}", note_date)));
+    }
+    let content = content.trim();
+    if content.is_empty() && mood_rating.is_none() && energy_rating.is_none() {
+        return Err(invalid("记录内容不能为空".to_string()));
+    }
+    for rating in [mood_rating, energy_rating].into_iter().flatten() {
+        if !(WELLBEING_RATING_MIN..=WELLBEING_RATING_MAX).contains(&rating) {
+            return Err(invalid(format!("评分应在 {}-{} 之间: {}", WELLBEING_RATING_MIN, WELLBEING_RATING_MAX, rating)));
+        }
+    }
+    let mood = mood.map(|m| m.trim()).filter(|m| !m.is_empty());
+    
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO day_notes (note_date, content, mood, mood_rating, energy_rating, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![note_date, content, mood, mood_rating, energy_rating, now],
+    )?;
+    let id = conn.last_insert_rowid() as i32;
+    conn.query_row(
+        &format!("SELECT {} FROM day_notes WHERE id = ?1", DAY_NOTE_COLUMNS),
+        [id],
+        day_note_from_row,
+    )
+}
+
+// 获取一段时间内的每日记录（按日期和记录时间排序）
+pub fn fetch_day_notes(start_date: &str, end_date: &str) -> Result<Vec<DayNote>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_day_notes_with(&conn, start_date, end_date)
+}
+
+pub fn fetch_day_notes_with(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<DayNote>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM day_notes WHERE note_date >= ?1 AND note_date <= ?2 ORDER BY note_date, created_at, id",
+        DAY_NOTE_COLUMNS
+    ))?;
+    let notes = stmt.query_map([start_date, end_date], day_note_from_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(notes)
+}
+
+// 删除每日记录，返回是否存在
+pub fn delete_day_note(note_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let deleted = conn.execute("DELETE FROM day_notes WHERE id = ?1", [note_id])?;
+    Ok(deleted > 0)
+}
+
+// 设置每日记录是否私密，返回是否存在
+pub fn set_day_note_private(note_id: i32, is_private: bool) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let updated = conn.execute(
+        "UPDATE day_notes SET is_private = ?1 WHERE id = ?2",
+        rusqlite::params![is_private as i32, note_id],
+    )?;
+    Ok(updated > 0)
+}
+
+// ==================== 身心状态趋势 ====================
+
+// 某一天的评分和工作量
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WellbeingDay {
+    pub date: String,
+    pub mood_rating: Option<f64>,    // 当天多条记录取平均
+    pub energy_rating: Option<f64>,
+    pub event_count: i64,
+    pub overdue_activities: i64,     // 当天已逾期未完成的活动数
+}
+
+// 评分与工作量的相关系数（数据不足或没有变化时为 None）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WellbeingCorrelations {
+    pub mood_vs_events: Option<f64>,
+    pub mood_vs_overdue: Option<f64>,
+    pub energy_vs_events: Option<f64>,
+    pub energy_vs_overdue: Option<f64>,
+}
+
+// 身心状态趋势
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WellbeingTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub days: Vec<WellbeingDay>,
+    pub average_mood: Option<f64>,
+    pub average_energy: Option<f64>,
+    pub correlations: WellbeingCorrelations,
+}
+
+// 计算相关系数所需的最少天数
+const WELLBEING_MIN_SAMPLES: usize = 3;
+
+// 皮尔逊相关系数（保留两位小数）
+fn pearson_correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < WELLBEING_MIN_SAMPLES {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some((cov / (var_x.sqrt() * var_y.sqrt()) * 100.0).round() / 100.0)
+}
+
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 { None } else { Some((sum / count as f64 * 100.0).round() / 100.0) }
+}
+
+// 统计一段时间内每天的心情/精力评分、事件数和逾期压力，并计算相关性
+pub fn fetch_wellbeing_trend(start_date: &str, end_date: &str) -> Result<WellbeingTrend> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_wellbeing_trend_with(&conn, start_date, end_date)
+}
+
+pub fn fetch_wellbeing_trend_with(conn: &Connection, start_date: &str, end_date: &str) -> Result<WellbeingTrend> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| invalid(format!("无效的日期: {}", d)));
+    let (start, end) = (parse(start_date)?, parse(end_date)?);
+    if start > end || (end - start).num_days() > 366 {
+        return Err(invalid("时间范围无效（最长一年）".to_string()));
+    }
+    
+    // 每天的平均评分
+    let mut stmt = conn.prepare_cached(
+        "SELECT note_date, AVG(mood_rating), AVG(energy_rating) FROM day_notes
+         WHERE note_date >= ?1 AND note_date <= ?2
+         GROUP BY note_date"
+    )?;
+    let ratings: std::collections::HashMap<String, (Option<f64>, Option<f64>)> = stmt
+        .query_map([start_date, end_date], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    // 每天的事件数
+    let mut stmt = conn.prepare_cached(
+        "SELECT substr(event_date, 1, 10), COUNT(*) FROM events
+         WHERE substr(event_date, 1, 10) >= ?1 AND substr(event_date, 1, 10) <= ?2
+         GROUP BY substr(event_date, 1, 10)"
+    )?;
+    let event_counts: std::collections::HashMap<String, i64> = stmt
+        .query_map([start_date, end_date], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    // 有截止日的活动（截止日顺延规则与逾期统计一致），用于计算每天的逾期压力
+    let calendar = WorkCalendar::load(conn)?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT substr(estimated_completion_date, 1, 10), substr(completed_at, 1, 10) FROM project_activities
+         WHERE estimated_completion_date IS NOT NULL AND estimated_completion_date != ''
+         AND substr(estimated_completion_date, 1, 10) < ?1
+         AND (completed_at IS NULL OR substr(completed_at, 1, 10) >= ?2)"
+    )?;
+    let deadlines: Vec<(chrono::NaiveDate, Option<chrono::NaiveDate>)> = stmt
+        .query_map([end_date, start_date], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))?
+        .filter_map(|r| r.ok())
+        .filter_map(|(due, completed)| {
+            let due = chrono::NaiveDate::parse_from_str(&due, "%Y-%m-%d").ok()?;
+            let completed = completed.and_then(|c| chrono::NaiveDate::parse_from_str(&c, "%Y-%m-%d").ok());
+            Some((calendar.on_or_after(due), completed))
+        })
+        .collect();
+    
+    let mut days = Vec::new();
+    let mut day = start;
+    while day <= end {
+        let date = day.format("%Y-%m-%d").to_string();
+        let (mood_rating, energy_rating) = ratings.get(&date).copied().unwrap_or((None, None));
+        let overdue_activities = deadlines.iter()
+            .filter(|(due, completed)| *due < day && completed.is_none_or(|c| c >= day))
+            .count() as i64;
+        days.push(WellbeingDay {
+            event_count: event_counts.get(&date).copied().unwrap_or(0),
+            date,
+            mood_rating,
+            energy_rating,
+            overdue_activities,
+        });
+        day += chrono::Duration::days(1);
+    }
+    
+    let pairs = |rating: fn(&WellbeingDay) -> Option<f64>, load: fn(&WellbeingDay) -> i64| -> Vec<(f64, f64)> {
+        days.iter().filter_map(|d| rating(d).map(|r| (r, load(d) as f64))).collect()
+    };
+    let correlations = WellbeingCorrelations {
+        mood_vs_events: pearson_correlation(&pairs(|d| d.mood_rating, |d| d.event_count)),
+        mood_vs_overdue: pearson_correlation(&pairs(|d| d.mood_rating, |d| d.overdue_activities)),
+        energy_vs_events: pearson_correlation(&pairs(|d| d.energy_rating, |d| d.event_count)),
+        energy_vs_overdue: pearson_correlation(&pairs(|d| d.energy_rating, |d| d.overdue_activities)),
+    };
+    
+    Ok(WellbeingTrend {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        average_mood: average(days.iter().filter_map(|d| d.mood_rating)),
+        average_energy: average(days.iter().filter_map(|d| d.energy_rating)),
+        days,
+        correlations,
+    })
+}
+
+// ==================== 清理建议 ====================
+
+// 创建超过该天数仍为空的项目、超过该天数未更新的草稿才建议清理
+const CLEANUP_STALE_DAYS: i64 = 30;
+
+// 超过该天数没有互动的联系人建议清理
+const CLEANUP_INACTIVE_CONTACT_DAYS: i64 = 365;
+
+// 没有互动的联系人的筛选条件（?1 为事件日期的截止时间，?2 为 created_at 的 UTC 截止时间），
+// 置顶联系人和有未完成活动的联系人除外；删除前按同一条件重新检查
+const INACTIVE_CONTACT_CONDITION: &str = "c.created_at < ?2
+         AND COALESCE(c.pinned, 0) = 0
+         AND NOT EXISTS (
+             SELECT 1 FROM events_contacts ec INNER JOIN events e ON e.id = ec.event_id
+             WHERE ec.contact_id = c.id AND e.event_date >= ?1
+         )
+         AND NOT EXISTS (
+             SELECT 1 FROM activities_contacts ac INNER JOIN project_activities a ON a.id = ac.activity_id
+             WHERE ac.contact_id = c.id AND a.status != '已完成'
+         )";
+
+// 每个文件保留的最新版本数，更早的版本建议删除
+pub const CLEANUP_FILE_VERSIONS_TO_KEEP: i32 = 3;
+
+// 清理建议的类型
+pub const CLEANUP_EMPTY_PROJECT: &str = "empty_project";
+pub const CLEANUP_INACTIVE_CONTACT: &str = "inactive_contact";
+pub const CLEANUP_SUPERSEDED_FILE: &str = "superseded_file_versions";
+pub const CLEANUP_STALE_DRAFT: &str = "stale_draft";
+
+// 单条清理建议（entity_id 为要处理的记录；旧文件版本为最新版本的文件ID）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CleanupSuggestion {
+    pub kind: String,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub title: String,
+    pub reason: String,
+    pub actions: Vec<String>,  // 可执行的操作（apply_cleanup_suggestion 的 action 参数）
+}
+
+// 列出可清理的记录：空项目、一年没有互动的联系人、版本过多的文件和过期草稿
+pub fn fetch_cleanup_suggestions() -> Result<Vec<CleanupSuggestion>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    let now = chrono::Local::now();
+    fetch_cleanup_suggestions_with(&conn, now.naive_local(), now.naive_utc())
+}
+
+fn cleanup_cutoff(time: chrono::NaiveDateTime, days: i64) -> String {
+    (time - chrono::Duration::days(days)).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// "我"对应的联系人（未设置时为 None）
+fn me_contact_id_with(conn: &Connection) -> Option<i32> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [SETTING_ME_CONTACT_ID],
+        |row| row.get::<_, String>(0)
+    ).ok().and_then(|v| v.trim().parse().ok())
+}
+
+// now 为本机时间（事件日期、草稿保存时间），now_utc 用于比较 created_at 等 CURRENT_TIMESTAMP 写入的 UTC 时间
+pub fn fetch_cleanup_suggestions_with(conn: &Connection, now: chrono::NaiveDateTime, now_utc: chrono::NaiveDateTime) -> Result<Vec<CleanupSuggestion>> {
+    let stale_cutoff = cleanup_cutoff(now, CLEANUP_STALE_DAYS);
+    let stale_cutoff_utc = cleanup_cutoff(now_utc, CLEANUP_STALE_DAYS);
+    let inactive_cutoff = cleanup_cutoff(now, CLEANUP_INACTIVE_CONTACT_DAYS);
+    let inactive_cutoff_utc = cleanup_cutoff(now_utc, CLEANUP_INACTIVE_CONTACT_DAYS);
+    let delete = || vec!["delete".to_string()];
+    let mut suggestions = Vec::new();
+    
+    // 空项目（未归档）：没有事件、活动、文件、联系人和活动模板，可归档或删除
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, p.created_at FROM projects p
+         WHERE p.created_at < ?1 AND p.archived = 0
+         AND NOT EXISTS (SELECT 1 FROM events e WHERE e.project_id = p.id)
+         AND NOT EXISTS (SELECT 1 FROM events_projects ep WHERE ep.project_id = p.id)
+         AND NOT EXISTS (SELECT 1 FROM project_activities a WHERE a.project_id = p.id)
+         AND NOT EXISTS (SELECT 1 FROM project_files f WHERE f.project_id = p.id)
+         AND NOT EXISTS (SELECT 1 FROM projects_contacts pc WHERE pc.project_id = p.id)
+         AND NOT EXISTS (SELECT 1 FROM activity_templates t WHERE t.project_id = p.id)
+         ORDER BY p.created_at"
+    )?;
+    let rows = stmt.query_map([&stale_cutoff_utc], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+    for (id, name, created_at) in rows.flatten() {
+        suggestions.push(CleanupSuggestion {
+            kind: CLEANUP_EMPTY_PROJECT.to_string(),
+            entity_type: "project".to_string(),
+            entity_id: id,
+            title: name,
+            reason: format!("创建于 {}，至今没有任何事件、活动、文件或联系人", &created_at[..created_at.len().min(10)]),
+            actions: vec!["archive".to_string(), "delete".to_string()],
+        });
+    }
+    
+    // 一年没有互动的联系人（置顶联系人、"我"以及有未完成活动的联系人除外）
+    let me_contact_id = me_contact_id_with(conn);
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT c.id, c.name,
+                (SELECT MAX(e.event_date) FROM events_contacts ec INNER JOIN events e ON e.id = ec.event_id WHERE ec.contact_id = c.id)
+         FROM contacts c
+         WHERE {}
+         ORDER BY c.name",
+        INACTIVE_CONTACT_CONDITION
+    ))?;
+    let rows = stmt.query_map([&inactive_cutoff, &inactive_cutoff_utc], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)))?;
+    for (id, name, last_event) in rows.flatten() {
+        if Some(id) == me_contact_id {
+            continue;
+        }
+        let reason = match last_event {
+            Some(date) => format!("最近一次互动在 {}，已超过一年", &date[..date.len().min(10)]),
+            None => "从未有过互动记录，且已创建超过一年".to_string(),
+        };
+        suggestions.push(CleanupSuggestion {
+            kind: CLEANUP_INACTIVE_CONTACT.to_string(),
+            entity_type: "contact".to_string(),
+            entity_id: id,
+            title: name,
+            reason,
+            actions: delete(),
+        });
+    }
+    
+    // 版本过多的文件：保留最新的几个版本
+    let mut stmt = conn.prepare_cached(
+        "SELECT f.id, f.original_name, p.name, f.version,
+                (SELECT COUNT(*) FROM project_files old
+                 WHERE old.project_id = f.project_id AND old.original_name = f.original_name AND old.version <= f.version - ?1)
+         FROM project_files f
+         INNER JOIN projects p ON p.id = f.project_id
+         WHERE f.version = (SELECT MAX(version) FROM project_files latest
+                            WHERE latest.project_id = f.project_id AND latest.original_name = f.original_name)
+         ORDER BY p.name, f.original_name"
+    )?;
+    let rows = stmt.query_map([CLEANUP_FILE_VERSIONS_TO_KEEP], |row| Ok((
+        row.get::<_, i32>(0)?,
+        row.get::<_, String>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, i32>(3)?,
+        row.get::<_, i64>(4)?,
+    )))?;
+    for (id, original_name, project_name, version, superseded) in rows.flatten() {
+        if superseded == 0 {
+            continue;
+        }
+        suggestions.push(CleanupSuggestion {
+            kind: CLEANUP_SUPERSEDED_FILE.to_string(),
+            entity_type: "file".to_string(),
+            entity_id: id,
+            title: format!("{} / {}", project_name, original_name),
+            reason: format!("已更新到 v{}，可删除 {} 个早于最近 {} 个版本的旧版本", version, superseded, CLEANUP_FILE_VERSIONS_TO_KEEP),
+            actions: delete(),
+        });
+    }
+    
+    // 超过 30 天未更新的草稿
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM drafts WHERE updated_at < ?1 ORDER BY updated_at",
+        DRAFT_COLUMNS
+    ))?;
+    for draft in stmt.query_map([&stale_cutoff], draft_from_row)?.flatten() {
+        suggestions.push(CleanupSuggestion {
+            kind: CLEANUP_STALE_DRAFT.to_string(),
+            entity_type: "draft".to_string(),
+            entity_id: draft.id,
+            title: format!("{} 草稿", draft.entity_type),
+            reason: format!("最后保存于 {}，已超过 {} 天", &draft.updated_at[..draft.updated_at.len().min(10)], CLEANUP_STALE_DAYS),
+            actions: delete(),
+        });
+    }
+    
+    Ok(suggestions)
+}
+
+// 删除空项目（仍为空时才删除），返回项目名称
+pub fn delete_empty_project(project_id: i32) -> Result<String> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    delete_empty_project_with(&conn, project_id)
+}
+
+pub fn delete_empty_project_with(conn: &Connection, project_id: i32) -> Result<String> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [project_id], |row| row.get(0))
+        .map_err(|_| invalid(format!("项目不存在: {}", project_id)))?;
+    let in_use: i64 = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM events WHERE project_id = ?1)
+              + (SELECT COUNT(*) FROM events_projects WHERE project_id = ?1)
+              + (SELECT COUNT(*) FROM project_activities WHERE project_id = ?1)
+              + (SELECT COUNT(*) FROM project_files WHERE project_id = ?1)
+              + (SELECT COUNT(*) FROM projects_contacts WHERE project_id = ?1)
+              + (SELECT COUNT(*) FROM activity_templates WHERE project_id = ?1)",
+        [project_id],
+        |row| row.get(0)
+    )?;
+    if in_use > 0 {
+        return Err(invalid(format!("项目「{}」已有关联内容，不能作为空项目删除", name)));
+    }
+    
+    // 先写删除日志，删除触发器不再补写
+    let desc = format!("{}，删除空项目「{}」", chrono::Local::now().format("%Y年%m月%d日 %H:%M"), name);
+    conn.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description)
+         VALUES ('delete', 'project', ?1, ?2, ?3)",
+        rusqlite::params![project_id, name, desc],
+    )?;
+    conn.execute("DELETE FROM entity_links WHERE (source_type = 'project' AND source_id = ?1) OR (target_type = 'project' AND target_id = ?1)", [project_id])?;
+    conn.execute("DELETE FROM projects WHERE id = ?1", [project_id])?;
+    Ok(name)
+}
+
+// 删除项目的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDeletion {
+    pub project_id: i32,
+    pub name: String,
+    pub events_deleted: usize,     // 只属于该项目的事件
+    pub events_unlinked: usize,    // 同时关联其他项目的事件，只取消关联（主项目改为其余项目之一）
+    pub activities_deleted: usize,
+    pub files_deleted: usize,      // 文件记录（磁盘上的项目文件夹由调用方删除）
+    pub contacts_unlinked: usize,
+    pub deleted_event_ids: Vec<i32>,  // 调用方据此删除事件的内嵌图片
+}
+
+// 删除项目及其联系人关联、事件、活动和文件记录（在一个事务中完成，失败时不留下删除了一半的项目）
+pub fn delete_project(project_id: i32) -> Result<ProjectDeletion> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    delete_project_with(&mut conn, project_id)
+}
+
+pub fn delete_project_with(conn: &mut Connection, project_id: i32) -> Result<ProjectDeletion> {
+    let tx = conn.transaction()?;
+    let name: String = tx.query_row("SELECT name FROM projects WHERE id = ?1", [project_id], |row| row.get(0))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("项目不存在: {}", project_id))
+            ),
+            e => e,
+        })?;
+
+    let ids = |sql: &str| -> Result<Vec<i32>> {
+        let mut stmt = tx.prepare(sql)?;
+        let ids = stmt.query_map([project_id], |row| row.get(0))?.collect::<Result<Vec<i32>>>()?;
+        Ok(ids)
+    };
+    // 同时关联其他项目的事件保留，只属于该项目的事件删除
+    let event_ids = ids(
+        "SELECT id FROM events e
+         WHERE (e.project_id = ?1 OR EXISTS(SELECT 1 FROM events_projects ep WHERE ep.event_id = e.id AND ep.project_id = ?1))
+         AND (e.project_id IS NULL OR e.project_id = ?1)
+         AND NOT EXISTS(SELECT 1 FROM events_projects ep WHERE ep.event_id = e.id AND ep.project_id != ?1)"
+    )?;
+    let activity_ids = ids("SELECT id FROM project_activities WHERE project_id = ?1")?;
+    let file_ids = ids("SELECT id FROM project_files WHERE project_id = ?1")?;
+
+    // 相关条目关联和打开记录随记录一起删除
+    let project_ids = [project_id];
+    for (entity_type, entity_ids) in [("project", &project_ids[..]), ("event", &event_ids[..]), ("activity", &activity_ids[..]), ("file", &file_ids[..])] {
+        for id in entity_ids {
+            tx.execute(
+                "DELETE FROM entity_links WHERE (source_type = ?1 AND source_id = ?2) OR (target_type = ?1 AND target_id = ?2)",
+                rusqlite::params![entity_type, id],
+            )?;
+            tx.execute("DELETE FROM access_log WHERE entity_type = ?1 AND entity_id = ?2", rusqlite::params![entity_type, id])?;
+        }
+    }
+    tx.execute("DELETE FROM drafts WHERE entity_type = 'project' AND entity_id = ?1", [project_id])?;
+
+    let mut events_deleted = 0;
+    for id in &event_ids {
+        tx.execute("DELETE FROM events_projects WHERE event_id = ?1", [id])?;
+        events_deleted += tx.execute("DELETE FROM events WHERE id = ?1", [id])?;
+    }
+    // 剩下的都是保留事件的关联
+    let events_unlinked = tx.execute("DELETE FROM events_projects WHERE project_id = ?1", [project_id])?;
+    tx.execute(
+        "UPDATE events SET project_id = (SELECT MIN(ep.project_id) FROM events_projects ep WHERE ep.event_id = events.id)
+         WHERE project_id = ?1",
+        [project_id],
+    )?;
+    let activities_deleted = tx.execute("DELETE FROM project_activities WHERE project_id = ?1", [project_id])?;
+    let files_deleted = tx.execute("DELETE FROM project_files WHERE project_id = ?1", [project_id])?;
+    let contacts_unlinked = tx.execute("DELETE FROM projects_contacts WHERE project_id = ?1", [project_id])?;
+
+    // 先写删除日志，删除触发器不再补写
+    let desc = format!(
+        "{}，删除项目「{}」（事件 {} 个、活动 {} 个、文件 {} 个）",
+        chrono::Local::now().format("%Y年%m月%d日 %H:%M"), name, events_deleted, activities_deleted, files_deleted
+    );
+    tx.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description)
+         VALUES ('delete', 'project', ?1, ?2, ?3)",
+        rusqlite::params![project_id, name, desc],
+    )?;
+    tx.execute("DELETE FROM projects WHERE id = ?1", [project_id])?;
+    tx.commit()?;
+
+    Ok(ProjectDeletion {
+        project_id,
+        name,
+        events_deleted,
+        events_unlinked,
+        activities_deleted,
+        files_deleted,
+        contacts_unlinked,
+        deleted_event_ids: event_ids,
+    })
+}
+
+// 复制项目的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDuplicate {
+    pub project_id: i32,
+    pub name: String,
+    pub contacts_copied: usize,
+    pub activities_copied: usize,
+    pub files_copied: usize,  // 文件由调用方复制到新项目文件夹后计入
+}
+
+// 复制项目：项目信息、联系人关联（含角色、备注和汇报关系）、活动（重置为待分配，不带负责人），
+// 名称为空时使用「原名称 副本」；事件、文件和活动模板不复制
+pub fn duplicate_project(project_id: i32, name: Option<&str>) -> Result<ProjectDuplicate> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    duplicate_project_with(&mut conn, project_id, name)
+}
+
+pub fn duplicate_project_with(conn: &mut Connection, project_id: i32, name: Option<&str>) -> Result<ProjectDuplicate> {
+    let tx = conn.transaction()?;
+    let source_name: String = tx.query_row("SELECT name FROM projects WHERE id = ?1", [project_id], |row| row.get(0))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("项目不存在: {}", project_id))
+            ),
+            e => e,
+        })?;
+    let name = name.map(|n| n.trim()).filter(|n| !n.is_empty())
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| format!("{} 副本", source_name));
+
+    tx.execute(
+        "INSERT INTO projects (name, description, color, icon, log_activity_completion)
+         SELECT ?2, description, color, icon, log_activity_completion FROM projects WHERE id = ?1",
+        rusqlite::params![project_id, name],
+    )?;
+    let new_id = tx.last_insert_rowid() as i32;
+
+    let contacts_copied = tx.execute(
+        "INSERT INTO projects_contacts (project_id, contact_id, role, notes, reports_to)
+         SELECT ?2, contact_id, role, notes, reports_to FROM projects_contacts WHERE project_id = ?1",
+        [project_id, new_id],
+    )?;
+    let activities_copied = tx.execute(
+        "INSERT INTO project_activities (project_id, name, description, estimated_completion_date, estimated_hours, status)
+         SELECT ?2, name, description, estimated_completion_date, estimated_hours, '待分配'
+         FROM project_activities WHERE project_id = ?1 ORDER BY id",
+        [project_id, new_id],
+    )?;
+
+    let desc = format!("{}，复制项目「{}」为「{}」", chrono::Local::now().format("%Y年%m月%d日 %H:%M"), source_name, name);
+    tx.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description)
+         VALUES ('create', 'project', ?1, ?2, ?1, ?2, ?3)",
+        rusqlite::params![new_id, name, desc],
+    )?;
+    tx.commit()?;
+
+    Ok(ProjectDuplicate {
+        project_id: new_id,
+        name,
+        contacts_copied,
+        activities_copied,
+        files_copied: 0,
+    })
+}
+
+// 删除联系人（项目、事件、活动中的关联随之删除，事件本身保留），返回联系人姓名
+pub fn delete_contact_with(conn: &Connection, contact_id: i32) -> Result<Option<String>> {
+    let name: String = match conn.query_row("SELECT name FROM contacts WHERE id = ?1", [contact_id], |row| row.get(0)) {
+        Ok(name) => name,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    // 先写删除日志，删除触发器不再补写
+    let desc = format!("{}，删除联系人「{}」", chrono::Local::now().format("%Y年%m月%d日 %H:%M"), name);
+    conn.execute(
+        "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description)
+         VALUES ('delete', 'contact', ?1, ?2, ?3)",
+        rusqlite::params![contact_id, name, desc],
+    )?;
+    conn.execute("DELETE FROM entity_links WHERE (source_type = 'contact' AND source_id = ?1) OR (target_type = 'contact' AND target_id = ?1)", [contact_id])?;
+    conn.execute("UPDATE projects_contacts SET reports_to = NULL WHERE reports_to = ?1", [contact_id])?;
+    conn.execute("DELETE FROM contacts WHERE id = ?1", [contact_id])?;
+    Ok(Some(name))
+}
+
+// 按清理建议删除不活跃的联系人：在事务中重新检查仍符合条件（没有新的互动、未置顶、不是"我"）才删除，返回联系人姓名
+pub fn delete_inactive_contact(contact_id: i32) -> Result<String> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    let now = chrono::Local::now();
+    delete_inactive_contact_with(&mut conn, contact_id, now.naive_local(), now.naive_utc())
+}
+
+pub fn delete_inactive_contact_with(
+    conn: &mut Connection,
+    contact_id: i32,
+    now: chrono::NaiveDateTime,
+    now_utc: chrono::NaiveDateTime,
+) -> Result<String> {
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    let tx = conn.transaction()?;
+    let name: String = tx.query_row("SELECT name FROM contacts WHERE id = ?1", [contact_id], |row| row.get(0))
+        .map_err(|_| invalid(format!("联系人不存在: {}", contact_id)))?;
+    let inactive: bool = tx.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM contacts c WHERE c.id = ?3 AND {})", INACTIVE_CONTACT_CONDITION),
+        rusqlite::params![
+            cleanup_cutoff(now, CLEANUP_INACTIVE_CONTACT_DAYS),
+            cleanup_cutoff(now_utc, CLEANUP_INACTIVE_CONTACT_DAYS),
+            contact_id,
+        ],
+        |row| row.get(0)
+    )?;
+    if !inactive || me_contact_id_with(&tx) == Some(contact_id) {
+        return Err(invalid(format!("联系人「{}」已不符合清理条件，未删除", name)));
+    }
+    delete_contact_with(&tx, contact_id)?;
+    tx.commit()?;
+    Ok(name)
+}
+
+// 文件早于最近几个版本的旧版本（ID、项目ID 和存储路径）
+pub fn fetch_superseded_file_versions(file_id: i32) -> Result<Vec<(i32, i32, String)>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT old.id, old.project_id, old.file_path FROM project_files f
+         INNER JOIN project_files old ON old.project_id = f.project_id AND old.original_name = f.original_name
+         WHERE f.id = ?1
+         AND old.version <= (SELECT MAX(version) FROM project_files latest
+                             WHERE latest.project_id = f.project_id AND latest.original_name = f.original_name) - ?2
+         ORDER BY old.version"
+    )?;
+    let files = stmt.query_map(rusqlite::params![file_id, CLEANUP_FILE_VERSIONS_TO_KEEP], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(files)
+}
+
+// 可清理的旧文件版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrunableFileVersion {
+    pub file_id: i32,
+    pub original_name: String,
+    pub version: i32,
+    pub file_path: String,
+    pub file_size: i64,
+}
+
+// 旧版本清理结果（dry_run 时只统计，不删除）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileVersionPruneReport {
+    pub project_id: i32,
+    pub keep_last_n: i32,
+    pub dry_run: bool,
+    pub versions: Vec<PrunableFileVersion>,
+    pub reclaimable_bytes: i64,
+    pub reclaimable_display: String,
+    pub pruned: usize,        // 实际删除的版本数（dry_run 时为 0）
+    pub moved_to_trash: bool, // 文件移入回收站而不是直接删除
+}
+
+// 项目中每个文件保留最新的 keep_last_n 个版本，列出更早的版本
+pub fn fetch_prunable_file_versions(project_id: i32, keep_last_n: i32) -> Result<Vec<PrunableFileVersion>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_prunable_file_versions_with(&conn, project_id, keep_last_n)
+}
+
+pub fn fetch_prunable_file_versions_with(conn: &Connection, project_id: i32, keep_last_n: i32) -> Result<Vec<PrunableFileVersion>> {
+    if keep_last_n < 1 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("至少保留 1 个版本".to_string())
+        ));
+    }
+    conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [project_id], |_| Ok(()))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("项目不存在: {}", project_id))
+            ),
+            e => e,
+        })?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT f.id, f.original_name, f.version, f.file_path, COALESCE(f.file_size, 0) FROM project_files f
+         WHERE f.project_id = ?1
+         AND (SELECT COUNT(*) FROM project_files newer
+              WHERE newer.project_id = f.project_id AND newer.original_name = f.original_name
+              AND (newer.version > f.version OR (newer.version = f.version AND newer.id > f.id))) >= ?2
+         ORDER BY f.original_name, f.version, f.id"
+    )?;
+    let versions = stmt.query_map(rusqlite::params![project_id, keep_last_n], |row| Ok(PrunableFileVersion {
+        file_id: row.get(0)?,
+        original_name: row.get(1)?,
+        version: row.get(2)?,
+        file_path: row.get(3)?,
+        file_size: row.get(4)?,
+    }))?
+    .filter_map(|r| r.ok())
+    .collect();
+    Ok(versions)
+}
+
+// 在一个事务中删除多条文件记录，返回删除的条数
+pub fn delete_project_files(file_ids: &[i32]) -> Result<usize> {
+    let db = get_db()?;
+    let mut conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    delete_project_files_with(&mut conn, file_ids)
+}
+
+pub fn delete_project_files_with(conn: &mut Connection, file_ids: &[i32]) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let mut deleted = 0;
+    for file_id in file_ids {
+        deleted += tx.execute("DELETE FROM project_files WHERE id = ?1", [file_id])?;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+// ==================== 项目复盘 ====================
+
+// 复盘列出的关键事件上限（置顶事件优先）
+const RETROSPECTIVE_KEY_EVENTS: usize = 15;
+
+// 项目复盘的统计数据（存入 summaries.statistics，按 project_id 查找已有复盘）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetrospectiveStatistics {
+    pub project_id: i32,
+    pub duration_days: i64,
+    pub events: i64,
+    pub contributors: i64,
+    pub files: i64,              // 交付的文件数（同名文件多个版本算一个）
+    pub file_versions: i64,
+    pub completed_activities: i64,
+    pub open_activities: i64,
+    pub average_cycle_days: Option<f64>,  // 已完成活动从开始到完成的平均天数
+}
+
+// 解析日期时间字符串的日期部分
+fn parse_date_prefix(value: &str) -> Option<chrono::NaiveDate> {
+    value.get(..10).and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+// 复盘用的活动行：名称、状态、创建时间、开始时间、完成时间、累计暂停秒数
+type RetrospectiveActivityRow = (String, String, String, Option<String>, Option<String>, i64);
+
+// 生成项目复盘（同一项目已有复盘时在原记录上更新）
+pub fn generate_project_retrospective(project_id: i32) -> Result<Summary> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    generate_project_retrospective_with(&conn, project_id, chrono::Local::now().date_naive())
+}
+
+pub fn generate_project_retrospective_with(conn: &Connection, project_id: i32, today: chrono::NaiveDate) -> Result<Summary> {
+    let (name, created_at): (String, String) = conn.query_row(
+        "SELECT name, created_at FROM projects WHERE id = ?1",
+        [project_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("项目不存在: {}", project_id))
+        ),
+        e => e,
+    })?;
+    let prefs = DisplayPreferences::from_settings(&fetch_settings_with(conn)?);
+    let mut stats = RetrospectiveStatistics { project_id, ..Default::default() };
+    
+    // 项目事件（主项目或关联项目）
+    let mut stmt = conn.prepare_cached(
+        "SELECT e.id, e.event_date, e.title, e.event_type, COALESCE(e.pinned, 0) FROM events e
+         WHERE (e.project_id = ?1 OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?1))
+         AND e.is_private = 0
+         ORDER BY e.event_date, e.id"
+    )?;
+    let events: Vec<(i32, String, String, Option<String>, bool)> = stmt.query_map([project_id], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get::<_, i32>(4)? != 0,
+    )))?.filter_map(|r| r.ok()).collect();
+    stats.events = events.len() as i64;
+    
+    // 活动
+    let mut stmt = conn.prepare_cached(
+        "SELECT name, status, created_at, activated_at, completed_at, COALESCE(paused_seconds, 0) FROM project_activities
+         WHERE project_id = ?1 ORDER BY COALESCE(completed_at, created_at), id"
+    )?;
+    let activities: Vec<RetrospectiveActivityRow> = stmt.query_map([project_id], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?,
+    )))?.filter_map(|r| r.ok()).collect();
+    
+    // 文件（每个文件取最新版本）
+    let mut stmt = conn.prepare_cached(
+        "SELECT original_name, MAX(version), COUNT(*), MAX(created_at) FROM project_files
+         WHERE project_id = ?1 GROUP BY original_name ORDER BY MAX(created_at)"
+    )?;
+    let files: Vec<(String, i32, i64, String)> = stmt.query_map([project_id], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+    )))?.filter_map(|r| r.ok()).collect();
+    stats.files = files.len() as i64;
+    stats.file_versions = files.iter().map(|f| f.2).sum();
+    
+    // 参与人员：项目成员及参与事件/活动的联系人，按参与次数排序
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.name, pc.role,
+                (SELECT COUNT(*) FROM events_contacts ec INNER JOIN events e ON e.id = ec.event_id
+                 WHERE ec.contact_id = c.id AND e.is_private = 0
+                 AND (e.project_id = ?1 OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?1))) AS event_count,
+                (SELECT COUNT(*) FROM activities_contacts ac INNER JOIN project_activities a ON a.id = ac.activity_id
+                 WHERE ac.contact_id = c.id AND a.project_id = ?1) AS activity_count
+         FROM contacts c
+         LEFT JOIN projects_contacts pc ON pc.contact_id = c.id AND pc.project_id = ?1
+         WHERE pc.project_id IS NOT NULL OR event_count > 0 OR activity_count > 0
+         ORDER BY event_count + activity_count DESC, c.name"
+    )?;
+    let contributors: Vec<(String, Option<String>, i64, i64)> = stmt.query_map([project_id], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+    )))?.filter_map(|r| r.ok()).collect();
+    stats.contributors = contributors.len() as i64;
+    
+    // 项目周期：创建到最后一次动态（事件、活动完成、文件）
+    let start = parse_date_prefix(&created_at).unwrap_or(today);
+    let last_activity = events.iter().filter_map(|e| parse_date_prefix(&e.1))
+        .chain(activities.iter().filter_map(|a| a.4.as_deref().and_then(parse_date_prefix)))
+        .chain(files.iter().filter_map(|f| parse_date_prefix(&f.3)))
+        .max()
+        .unwrap_or(start)
+        .max(start);
+    stats.duration_days = (last_activity - start).num_days() + 1;
+    
+    // 活动周期（开始时间缺失时从创建时间算起，扣除暂停天数）
+    let mut cycles: Vec<(&str, i64)> = Vec::new();
+    for (name, status, created, activated, completed, paused_seconds) in &activities {
+        match completed.as_deref().and_then(parse_date_prefix) {
+            Some(done) if status == "已完成" => {
+                let begun = activated.as_deref().and_then(parse_date_prefix)
+                    .or_else(|| parse_date_prefix(created))
+                    .unwrap_or(done);
+                cycles.push((name, ((done - begun).num_days() - paused_seconds / 86400).max(0)));
+            }
+            _ => stats.open_activities += 1,
+        }
+    }
+    stats.completed_activities = cycles.len() as i64;
+    if !cycles.is_empty() {
+        let total: i64 = cycles.iter().map(|c| c.1).sum();
+        stats.average_cycle_days = Some((total as f64 / cycles.len() as f64 * 10.0).round() / 10.0);
+    }
+    
+    // 生成正文
+    let start_str = start.format("%Y-%m-%d").to_string();
+    let end_str = last_activity.format("%Y-%m-%d").to_string();
+    let title = format!("「{}」项目复盘", name);
+    let mut content = String::new();
+    content.push_str(&format!("# {}\n\n", title));
+    content.push_str(&format!("生成时间：{}\n\n", chrono::Local::now().format(&prefs.datetime_format)));
+    content.push_str("---\n\n");
+    
+    content.push_str("## 概况\n\n");
+    content.push_str(&format!("- 周期：{} 至 {}（{} 天）\n", prefs.format_date(&start_str), prefs.format_date(&end_str), stats.duration_days));
+    content.push_str(&format!("- 事件：{} 个\n", stats.events));
+    content.push_str(&format!("- 活动：完成 {} 项，未完成 {} 项\n", stats.completed_activities, stats.open_activities));
+    content.push_str(&format!("- 文件：{} 个（共 {} 个版本）\n", stats.files, stats.file_versions));
+    content.push_str(&format!("- 参与人员：{} 人\n", stats.contributors));
+    
+    content.push_str("\n## 关键事件\n\n");
+    if events.is_empty() {
+        content.push_str("没有事件记录。\n");
+    }
+    // 置顶事件全部列出，其余按时间补足
+    let mut key_events: Vec<&(i32, String, String, Option<String>, bool)> = events.iter().filter(|e| e.4).collect();
+    for event in events.iter().filter(|e| !e.4) {
+        if key_events.len() >= RETROSPECTIVE_KEY_EVENTS {
+            break;
+        }
+        key_events.push(event);
+    }
+    key_events.sort_by(|a, b| (&a.1, a.0).cmp(&(&b.1, b.0)));
+    for (_, event_date, event_title, event_type, pinned) in key_events {
+        let date = prefs.format_date(event_date.get(..10).unwrap_or(event_date));
+        let kind = event_type.as_deref().map(|t| format!("［{}］", t)).unwrap_or_default();
+        let mark = if *pinned { "⭐ " } else { "" };
+        content.push_str(&format!("- {} {}{}{}\n", date, mark, kind, event_title));
+    }
+    if events.len() > RETROSPECTIVE_KEY_EVENTS {
+        content.push_str(&format!("\n（共 {} 个事件，仅列出置顶和最早的事件）\n", events.len()));
+    }
+    
+    content.push_str("\n## 参与人员\n\n");
+    if contributors.is_empty() {
+        content.push_str("没有关联的联系人。\n");
+    }
+    for (contact_name, role, event_count, activity_count) in &contributors {
+        let role = role.as_deref().filter(|r| !r.is_empty()).map(|r| format!("（{}）", r)).unwrap_or_default();
+        content.push_str(&format!("- {}{}：参与 {} 个事件，负责 {} 项活动\n", contact_name, role, event_count, activity_count));
+    }
+    
+    content.push_str("\n## 交付文件\n\n");
+    if files.is_empty() {
+        content.push_str("没有上传文件。\n");
+    }
+    for (original_name, version, _, updated) in &files {
+        content.push_str(&format!("- {}（v{}，{}）\n", original_name, version, prefs.format_date(updated.get(..10).unwrap_or(updated))));
+    }
+    
+    content.push_str("\n## 活动周期\n\n");
+    match stats.average_cycle_days {
+        Some(average) => {
+            let longest = cycles.iter().max_by_key(|c| c.1).map(|c| c.1).unwrap_or(0);
+            let shortest = cycles.iter().min_by_key(|c| c.1).map(|c| c.1).unwrap_or(0);
+            content.push_str(&format!("平均 {} 天，最短 {} 天，最长 {} 天\n\n", average, shortest, longest));
+            for (activity_name, days) in &cycles {
+                content.push_str(&format!("- {}：{} 天\n", activity_name, days));
+            }
+        }
+        None => content.push_str("没有已完成的活动。\n"),
+    }
+    
+    let statistics = serde_json::to_string(&stats).unwrap_or_default();
+    
+    // 已有复盘时更新原记录
+    let existing = conn.query_row(
+        &format!(
+            "SELECT {} FROM summaries WHERE summary_type = 'retrospective' AND json_extract(statistics, '$.project_id') = ?1
+             ORDER BY id DESC LIMIT 1",
+            SUMMARY_COLUMNS
+        ),
+        [project_id],
+        summary_from_row
+    );
+    match existing {
+        Ok(existing) => {
+            conn.execute(
+                "UPDATE summaries SET title = ?1, start_date = ?2, end_date = ?3, content = ?4, statistics = ?5 WHERE id = ?6",
+                rusqlite::params![title, start_str, end_str, content, statistics, existing.id],
+            )?;
+            Ok(Summary {
+                title,
+                start_date: start_str,
+                end_date: end_str,
+                content,
+                statistics: Some(statistics),
+                ..existing
+            })
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => insert_summary_with(conn, "retrospective", &start_str, &end_str, (title, content, statistics), false),
+        Err(e) => Err(e),
+    }
+}
+
+// ==================== 文件分享 ====================
+
+// 分享的有效天数上限
+pub const MAX_SHARE_DAYS: i64 = 365;
+
+// 文件分享副本
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileShare {
+    pub id: i32,
+    pub file_id: Option<i32>,
+    pub original_name: String,
+    pub share_path: String,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+const FILE_SHARE_COLUMNS: &str = "id, file_id, original_name, share_path, expires_at, created_at";
+
+fn file_share_from_row(row: &rusqlite::Row) -> rusqlite::Result<FileShare> {
+    Ok(FileShare {
+        id: row.get(0)?,
+        file_id: row.get(1)?,
+        original_name: row.get(2)?,
+        share_path: row.get(3)?,
+        expires_at: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// 记录文件分享
+pub fn insert_file_share(file_id: i32, original_name: &str, share_path: &str, expires_at: &str) -> Result<FileShare> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    insert_file_share_with(&conn, file_id, original_name, share_path, expires_at)
+}
+
+pub fn insert_file_share_with(conn: &Connection, file_id: i32, original_name: &str, share_path: &str, expires_at: &str) -> Result<FileShare> {
+    conn.execute(
+        "INSERT INTO file_shares (file_id, original_name, share_path, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![file_id, original_name, share_path, expires_at],
+    )?;
+    conn.query_row(
+        &format!("SELECT {} FROM file_shares WHERE id = ?1", FILE_SHARE_COLUMNS),
+        [conn.last_insert_rowid()],
+        file_share_from_row
+    )
+}
+
+// 未过期的分享（可按来源文件过滤），最近创建的在前
+pub fn fetch_file_shares(file_id: Option<i32>) -> Result<Vec<FileShare>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    ListQuery::new(format!("SELECT {} FROM file_shares", FILE_SHARE_COLUMNS))
+        .filter("expires_at > ?", now)
+        .filter_opt("file_id = ?", file_id)
+        .order_by("created_at DESC")
+        .order_by("id DESC")
+        .fetch(&conn, file_share_from_row)
+}
+
+// 已过期的分享
+pub fn fetch_expired_file_shares() -> Result<Vec<FileShare>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_expired_file_shares_with(&conn, chrono::Local::now().naive_local())
+}
+
+pub fn fetch_expired_file_shares_with(conn: &Connection, now: chrono::NaiveDateTime) -> Result<Vec<FileShare>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM file_shares WHERE expires_at <= ?1 ORDER BY expires_at, id",
+        FILE_SHARE_COLUMNS
+    ))?;
+    let shares = stmt.query_map([now.format("%Y-%m-%d %H:%M:%S").to_string()], file_share_from_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(shares)
+}
+
+// 根据ID获取分享
+pub fn get_file_share(share_id: i32) -> Result<Option<FileShare>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    let result = conn.query_row(
+        &format!("SELECT {} FROM file_shares WHERE id = ?1", FILE_SHARE_COLUMNS),
+        [share_id],
+        file_share_from_row
+    );
+    match result {
+        Ok(share) => Ok(Some(share)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 删除分享记录（副本文件由调用方删除）
+pub fn delete_file_share(share_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    
+    conn.execute("DELETE FROM file_shares WHERE id = ?1", [share_id])?;
+    Ok(())
+}
+
+// ==================== 活动分配通知 ====================
+
+// 分配负责人后是否发送通知（默认不发送）
+pub fn is_assignment_notification_enabled() -> Result<bool> {
+    Ok(get_setting(SETTING_ASSIGNMENT_NOTIFICATION)?
+        .and_then(|v| parse_switch(&v))
+        .unwrap_or(false))
+}
+
+// URL 组件编码（与 JavaScript 的 encodeURIComponent 一致）
+pub fn encode_uri_component(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// 分配通知的收件人
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignmentRecipient {
+    pub contact_id: i32,
+    pub name: String,
+    pub email: Option<String>,
+}
+
+// 预先填好的分配通知（邮件或消息），发给在别处执行工作的负责人
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignmentMessage {
+    pub activity_id: i32,
+    pub recipients: Vec<AssignmentRecipient>,  // 不含"我"
+    pub assigned_to_me: bool,
+    pub subject: String,
+    pub body: String,
+    pub mailto: Option<String>,  // 有收件人邮箱时的 mailto: 链接
+}
+
+// 生成活动的分配通知
+pub fn fetch_assignment_message(activity_id: i32) -> Result<AssignmentMessage> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_assignment_message_with(&conn, activity_id)
+}
+
+pub fn fetch_assignment_message_with(conn: &Connection, activity_id: i32) -> Result<AssignmentMessage> {
+    let activity = conn.query_row(
+        &format!("SELECT {} FROM project_activities a WHERE a.id = ?1", ACTIVITY_COLUMNS),
+        [activity_id],
+        activity_from_row
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("活动不存在: {}", activity_id))
+        ),
+        e => e,
+    })?;
+    let project_name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [activity.project_id], |row| row.get(0))?;
+    let settings = fetch_settings_with(conn)?;
+    let me: Option<i32> = settings.get(SETTING_ME_CONTACT_ID).and_then(|v| v.trim().parse().ok());
+    let prefs = DisplayPreferences::from_settings(&settings);
+    
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.id, c.name, c.email FROM contacts c
+         INNER JOIN activities_contacts ac ON c.id = ac.contact_id
+         WHERE ac.activity_id = ?1
+         ORDER BY ac.assigned_at, c.id"
+    )?;
+    let assignees: Vec<AssignmentRecipient> = stmt.query_map([activity_id], |row| Ok(AssignmentRecipient {
+        contact_id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get::<_, Option<String>>(2)?.map(|e| e.trim().to_string()).filter(|e| !e.is_empty()),
+    }))?
+    .filter_map(|r| r.ok())
+    .collect();
+    let assigned_to_me = assignees.iter().any(|a| Some(a.contact_id) == me);
+    let recipients: Vec<AssignmentRecipient> = assignees.into_iter().filter(|a| Some(a.contact_id) != me).collect();
+    
+    let subject = format!("【{}】活动分配：{}", project_name, activity.name);
+    let mut body = String::new();
+    if !recipients.is_empty() {
+        let names: Vec<&str> = recipients.iter().map(|r| r.name.as_str()).collect();
+        body.push_str(&format!("{}，你好：\n\n", names.join("、")));
+    }
+    body.push_str(&format!("项目「{}」中的活动「{}」已分配给你负责。\n\n", project_name, activity.name));
+    if let Some(description) = activity.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        body.push_str(&format!("说明：{}\n", description));
+    }
+    if let Some(due) = activity.estimated_completion_date.as_deref().filter(|d| !d.is_empty()) {
+        body.push_str(&format!("预计完成日期：{}\n", prefs.format_date(due.get(..10).unwrap_or(due))));
+    }
+    if let Some(hours) = activity.estimated_hours.filter(|h| *h > 0.0) {
+        body.push_str(&format!("预估工时：{}\n", prefs.numbers.format_hours(hours)));
+    }
+    body.push_str("\n如有问题请随时联系，完成后请告知进展。谢谢！\n");
+    
+    let emails: Vec<&str> = recipients.iter().filter_map(|r| r.email.as_deref()).collect();
+    let mailto = (!emails.is_empty()).then(|| format!(
+        "mailto:{}?subject={}&body={}",
+        emails.join(","),
+        encode_uri_component(&subject),
+        encode_uri_component(&body)
+    ));
+    
+    Ok(AssignmentMessage {
+        activity_id,
+        recipients,
+        assigned_to_me,
+        subject,
+        body,
+        mailto,
+    })
+}
+
+// ==================== 活动老化报告 ====================
+
+// 未设置时的老化阈值（天）
+const DEFAULT_AGING_THRESHOLD_DAYS: i64 = 14;
+
+// 单个进行中活动的老化情况
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityAging {
+    pub activity_id: i32,
+    pub project_id: i32,
+    pub project_name: String,
+    pub name: String,
+    pub assignees: Vec<String>,
+    pub activated_at: String,
+    pub active_days: f64,   // 激活至今扣除暂停后的天数
+    pub paused_days: f64,   // 期间暂停的总天数
+    pub over_threshold: bool,
+}
+
+// 进行中活动的老化报告（按进行天数从长到短）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityAgingReport {
+    pub threshold_days: i64,
+    pub over_threshold_count: usize,
+    pub activities: Vec<ActivityAging>,
+}
+
+// 当前的老化阈值
+pub fn aging_threshold_days(settings: &std::collections::HashMap<String, String>) -> i64 {
+    settings.get(SETTING_AGING_THRESHOLD_DAYS)
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(DEFAULT_AGING_THRESHOLD_DAYS)
+}
+
+// 解析 YYYY-MM-DD HH:MM:SS（只有日期时按当天零点）
+fn parse_local_datetime(value: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+        .or_else(|| parse_date_prefix(value).and_then(|d| d.and_hms_opt(0, 0, 0)))
+}
+
+// 生成进行中活动的老化报告（threshold_days 为空时使用设置值）
+pub fn fetch_activity_aging_report(threshold_days: Option<i64>) -> Result<ActivityAgingReport> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_activity_aging_report_with(&conn, threshold_days, chrono::Local::now().naive_local())
+}
+
+pub fn fetch_activity_aging_report_with(conn: &Connection, threshold_days: Option<i64>, now: chrono::NaiveDateTime) -> Result<ActivityAgingReport> {
+    let threshold_days = threshold_days.filter(|d| *d > 0).unwrap_or_else(|| {
+        fetch_settings_with(conn).map(|s| aging_threshold_days(&s)).unwrap_or(DEFAULT_AGING_THRESHOLD_DAYS)
+    });
+    
+    // 暂停时长与活动详情的有效时长同样取自 paused_seconds
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}, p.name FROM project_activities a
+         INNER JOIN projects p ON p.id = a.project_id
+         WHERE a.status = '进行中'",
+        ACTIVITY_COLUMNS
+    ))?;
+    let rows: Vec<(ProjectActivity, String)> = stmt.query_map([], |row| Ok((
+        activity_from_row(row)?, row.get(15)?,
+    )))?.filter_map(|r| r.ok()).collect();
+    
+    let mut assignee_stmt = conn.prepare_cached(
+        "SELECT c.name FROM contacts c INNER JOIN activities_contacts ac ON c.id = ac.contact_id
+         WHERE ac.activity_id = ?1 ORDER BY ac.assigned_at, c.id"
+    )?;
+    let round = |days: f64| (days * 10.0).round() / 10.0;
+    let mut activities = Vec::new();
+    for (mut activity, project_name) in rows {
+        // 没有激活时间的旧数据按创建时间计算
+        if activity.activated_at.is_none() {
+            activity.activated_at = Some(activity.created_at.clone());
+        }
+        let Some(active) = active_duration_seconds(&activity, now) else {
+            continue;
+        };
+        let active_days = active as f64 / 86400.0;
+        let paused_days = paused_duration_seconds(&activity, now) as f64 / 86400.0;
+        let assignees = assignee_stmt.query_map([activity.id], |row| row.get(0))?.filter_map(|r| r.ok()).collect();
+        activities.push(ActivityAging {
+            activity_id: activity.id,
+            project_id: activity.project_id,
+            project_name,
+            name: activity.name,
+            assignees,
+            activated_at: activity.activated_at.unwrap_or_default(),
+            active_days: round(active_days),
+            paused_days: round(paused_days),
+            over_threshold: active_days > threshold_days as f64,
+        });
+    }
+    activities.sort_by(|a, b| b.active_days.total_cmp(&a.active_days).then(a.activity_id.cmp(&b.activity_id)));
+    
+    Ok(ActivityAgingReport {
+        threshold_days,
+        over_threshold_count: activities.iter().filter(|a| a.over_threshold).count(),
+        activities,
+    })
+}
+
+// ==================== 联系人档案导出 ====================
+
+// 联系人参与的项目及角色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DossierProject {
+    pub project_id: i32,
+    pub project_name: String,
+    pub role: Option<String>,
+    pub notes: Option<String>,
+    pub joined_at: Option<String>,
+}
+
+// 联系人参与的事件（附关联项目和其他参与人）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DossierEvent {
+    pub event: Event,
+    pub project_names: Vec<String>,
+    pub other_contacts: Vec<String>,
+}
+
+// 分配给联系人的活动
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DossierActivity {
+    pub activity_id: i32,
+    pub project_id: i32,
+    pub project_name: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub estimated_completion_date: Option<String>,
+    pub completed_at: Option<String>,
+    pub assigned_at: Option<String>,
+}
+
+// 总结中提到联系人的段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryMention {
+    pub summary_id: i32,
+    pub title: String,
+    pub summary_type: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub excerpts: Vec<String>,  // 包含联系人姓名的行
+}
+
+// 联系人的完整档案（数据可携带导出）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactDossier {
+    pub generated_at: String,
+    pub contact: Contact,
+    pub projects: Vec<DossierProject>,
+    pub events: Vec<DossierEvent>,
+    pub activities: Vec<DossierActivity>,
+    pub files: Vec<ContactFile>,
+    pub summary_mentions: Vec<SummaryMention>,
+}
+
+// 汇总联系人的字段、参与的事件（私密事件除外）、项目角色、负责的活动、文件和总结中的提及
+pub fn fetch_contact_dossier(contact_id: i32) -> Result<ContactDossier> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_contact_dossier_with(&conn, contact_id)
+}
+
+pub fn fetch_contact_dossier_with(conn: &Connection, contact_id: i32) -> Result<ContactDossier> {
+    let contact = conn.query_row(
+        &format!("SELECT {} FROM contacts c WHERE c.id = ?1", CONTACT_COLUMNS),
+        [contact_id],
+        contact_from_row,
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("联系人不存在: {}", contact_id))
+        ),
+        e => e,
+    })?;
+    
+    // 项目角色
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, pc.role, pc.notes, pc.created_at FROM projects_contacts pc
+         INNER JOIN projects p ON p.id = pc.project_id
+         WHERE pc.contact_id = ?1 ORDER BY pc.created_at, p.id"
+    )?;
+    let projects: Vec<DossierProject> = stmt.query_map([contact_id], |row| Ok(DossierProject {
+        project_id: row.get(0)?,
+        project_name: row.get(1)?,
+        role: row.get(2)?,
+        notes: row.get(3)?,
+        joined_at: row.get(4)?,
+    }))?.filter_map(|r| r.ok()).collect();
+    
+    // 参与的事件（按时间先后）
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM events e
+         INNER JOIN events_contacts ec ON ec.event_id = e.id
+         WHERE ec.contact_id = ?1 AND e.is_private = 0
+         ORDER BY e.event_date, e.id",
+        EVENT_COLUMNS
+    ))?;
+    let events: Vec<Event> = stmt.query_map([contact_id], event_from_row)?.filter_map(|r| r.ok()).collect();
+    let mut project_stmt = conn.prepare_cached(
+        "SELECT p.name FROM projects p
+         WHERE p.id IN (SELECT project_id FROM events_projects WHERE event_id = ?1) OR p.id = ?2
+         ORDER BY p.id = ?2 DESC, p.name"
+    )?;
+    let mut attendee_stmt = conn.prepare_cached(
+        "SELECT c.name FROM contacts c INNER JOIN events_contacts ec ON ec.contact_id = c.id
+         WHERE ec.event_id = ?1 AND c.id != ?2 ORDER BY c.name"
+    )?;
+    let mut dossier_events = Vec::new();
+    for event in events {
+        let project_names = project_stmt.query_map(rusqlite::params![event.id, event.project_id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let other_contacts = attendee_stmt.query_map(rusqlite::params![event.id, contact_id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        dossier_events.push(DossierEvent { event, project_names, other_contacts });
+    }
+    
+    // 负责的活动
+    let mut stmt = conn.prepare_cached(
+        "SELECT a.id, a.project_id, p.name, a.name, a.description, a.status, a.estimated_completion_date, a.completed_at, ac.assigned_at
+         FROM project_activities a
+         INNER JOIN activities_contacts ac ON ac.activity_id = a.id
+         INNER JOIN projects p ON p.id = a.project_id
+         WHERE ac.contact_id = ?1
+         ORDER BY a.created_at, a.id"
+    )?;
+    let activities: Vec<DossierActivity> = stmt.query_map([contact_id], |row| Ok(DossierActivity {
+        activity_id: row.get(0)?,
+        project_id: row.get(1)?,
+        project_name: row.get(2)?,
+        name: row.get(3)?,
+        description: row.get(4)?,
+        status: row.get(5)?,
+        estimated_completion_date: row.get(6)?,
+        completed_at: row.get(7)?,
+        assigned_at: row.get(8)?,
+    }))?.filter_map(|r| r.ok()).collect();
+    
+    // 联系人文件
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM contact_files WHERE contact_id = ?1 ORDER BY original_name, version",
+        CONTACT_FILE_COLUMNS
+    ))?;
+    let files: Vec<ContactFile> = stmt.query_map([contact_id], contact_file_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    
+    // 总结中提到联系人姓名的行
+    let mut summary_mentions = Vec::new();
+    let name = contact.name.trim();
+    if !name.is_empty() {
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM summaries WHERE instr(content, ?1) > 0 OR instr(title, ?1) > 0 ORDER BY start_date, id",
+            SUMMARY_COLUMNS
+        ))?;
+        let summaries: Vec<Summary> = stmt.query_map([name], summary_from_row)?.filter_map(|r| r.ok()).collect();
+        for summary in summaries {
+            let excerpts = summary.content.lines()
+                .filter(|line| line.contains(name))
+                .map(|line| line.trim().to_string())
+                .collect();
+            summary_mentions.push(SummaryMention {
+                summary_id: summary.id,
+                title: summary.title,
+                summary_type: summary.summary_type,
+                start_date: summary.start_date,
+                end_date: summary.end_date,
+                excerpts,
+            });
+        }
+    }
+    
+    Ok(ContactDossier {
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        contact,
+        projects,
+        events: dossier_events,
+        activities,
+        files,
+        summary_mentions,
+    })
+}
+
+// ==================== 联系人工作时间与会议时间推荐 ====================
+
+// 未设置工作时间的联系人按本机时区的默认工作时间计算
+const DEFAULT_WORK_START: &str = "09:00";
+const DEFAULT_WORK_END: &str = "18:00";
+const DEFAULT_WORK_DAYS: &[u32] = &[1, 2, 3, 4, 5];
+
+// 候选时间的步长（分钟）、未记录时长的事件按多长时间计为忙碌（分钟）、最多返回的推荐数
+const MEETING_SLOT_STEP_MINUTES: i64 = 30;
+const BUSY_EVENT_MINUTES: i64 = 60;
+const MAX_MEETING_SLOTS: usize = 10;
+// 会议时长上限（分钟）、查找范围上限（天）
+const MAX_MEETING_MINUTES: i64 = 24 * 60;
+const MAX_MEETING_RANGE_DAYS: i64 = 366;
+
+// 联系人的工作时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContactAvailability {
+    #[serde(default)]
+    pub contact_id: i32,
+    pub timezone: Option<String>,  // UTC 偏移，如 +08:00、-05:00（为空时与本机相同）
+    pub work_start: String,        // 当地时间 HH:MM
+    pub work_end: String,
+    pub work_days: Vec<u32>,       // 1 为周一、7 为周日
+}
+
+impl ContactAvailability {
+    fn default_for(contact_id: i32) -> Self {
+        ContactAvailability {
+            contact_id,
+            timezone: None,
+            work_start: DEFAULT_WORK_START.to_string(),
+            work_end: DEFAULT_WORK_END.to_string(),
+            work_days: DEFAULT_WORK_DAYS.to_vec(),
+        }
+    }
+}
+
+// 解析 UTC 偏移：+08:00、-0530、+8、UTC+8、UTC、Z
+pub fn parse_utc_offset(value: &str) -> Option<chrono::FixedOffset> {
+    let value = value.trim();
+    let value = value.strip_prefix("UTC").or_else(|| value.strip_prefix("GMT")).unwrap_or(value).trim();
+    if value.is_empty() || value == "Z" {
+        return chrono::FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match value.as_bytes()[0] {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 && rest.is_ascii() => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().ok().filter(|h| (0..=14).contains(h))?;
+    let minutes: i32 = minutes.parse().ok().filter(|m| (0..60).contains(m))?;
+    chrono::FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+fn parse_work_time(value: &str) -> Option<chrono::NaiveTime> {
+    chrono::NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+// 检查工作时间设置
+fn validate_availability(availability: &ContactAvailability) -> std::result::Result<(), String> {
+    if let Some(timezone) = availability.timezone.as_deref().filter(|t| !t.trim().is_empty()) {
+        parse_utc_offset(timezone).ok_or(format!("无效的时区（应为 UTC 偏移，如 +08:00）: {}", timezone))?;
+    }
+    let start = parse_work_time(&availability.work_start).ok_or(format!("无效的开始时间: {}", availability.work_start))?;
+    let end = parse_work_time(&availability.work_end).ok_or(format!("无效的结束时间: {}", availability.work_end))?;
+    if start >= end {
+        return Err("结束时间应晚于开始时间".to_string());
+    }
+    if availability.work_days.is_empty() || availability.work_days.iter().any(|d| !(1..=7).contains(d)) {
+        return Err("工作日应为 1（周一）到 7（周日）".to_string());
+    }
+    Ok(())
+}
+
+fn availability_from_row(row: &rusqlite::Row) -> Result<ContactAvailability> {
+    let work_days: String = row.get(4)?;
+    Ok(ContactAvailability {
+        contact_id: row.get(0)?,
+        timezone: row.get(1)?,
+        work_start: row.get(2)?,
+        work_end: row.get(3)?,
+        work_days: work_days.split(',').filter_map(|d| d.trim().parse().ok()).collect(),
+    })
+}
+
+// 获取联系人的工作时间（未设置时为 None）
+pub fn get_contact_availability(contact_id: i32) -> Result<Option<ContactAvailability>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    get_contact_availability_with(&conn, contact_id)
+}
+
+fn get_contact_availability_with(conn: &Connection, contact_id: i32) -> Result<Option<ContactAvailability>> {
+    match conn.query_row(
+        "SELECT contact_id, timezone, work_start, work_end, work_days FROM contact_availability WHERE contact_id = ?1",
+        [contact_id],
+        availability_from_row,
+    ) {
+        Ok(availability) => Ok(Some(availability)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 设置联系人的工作时间
+pub fn set_contact_availability(availability: &ContactAvailability) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    set_contact_availability_with(&conn, availability)
+}
+
+pub fn set_contact_availability_with(conn: &Connection, availability: &ContactAvailability) -> Result<()> {
+    validate_availability(availability)
+        .map_err(|msg| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg)))?;
+    let timezone = availability.timezone.as_deref().map(|t| t.trim()).filter(|t| !t.is_empty());
+    let mut days = availability.work_days.clone();
+    days.sort_unstable();
+    days.dedup();
+    let work_days = days.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",");
+    conn.execute(
+        "INSERT INTO contact_availability (contact_id, timezone, work_start, work_end, work_days) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(contact_id) DO UPDATE SET timezone = ?2, work_start = ?3, work_end = ?4, work_days = ?5",
+        rusqlite::params![availability.contact_id, timezone, availability.work_start.trim(), availability.work_end.trim(), work_days],
+    )?;
+    Ok(())
+}
+
+// 清除联系人的工作时间（恢复默认），返回是否存在
+pub fn delete_contact_availability(contact_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    Ok(conn.execute("DELETE FROM contact_availability WHERE contact_id = ?1", [contact_id])? > 0)
+}
+
+// 会议时间中某位联系人的当地时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactLocalTime {
+    pub contact_id: i32,
+    pub name: String,
+    pub local_start: String,  // 联系人当地时间 YYYY-MM-DD HH:MM
+}
+
+// 推荐的会议时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingSlot {
+    pub start: String,  // 本机时间 YYYY-MM-DD HH:MM
+    pub end: String,
+    pub contact_times: Vec<ContactLocalTime>,
+}
+
+// 推荐会议时间：在 start_date 到 end_date（本机日期，闭区间）内，找出所有联系人都在工作时间内、
+// 且联系人和"我"都没有其他事件的时段（事件按 60 分钟计为忙碌，全天事件不计）
+pub fn suggest_meeting_slots(contact_ids: &[i32], duration_minutes: i64, start_date: &str, end_date: &str) -> Result<Vec<MeetingSlot>> {
+    use chrono::Offset;
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    // 按当前的本机时区偏移换算（不处理区间内的夏令时切换）
+    let now = chrono::Local::now();
+    suggest_meeting_slots_with(&conn, contact_ids, duration_minutes, start_date, end_date, now.offset().fix(), now.naive_local())
+}
+
+pub fn suggest_meeting_slots_with(
+    conn: &Connection,
+    contact_ids: &[i32],
+    duration_minutes: i64,
+    start_date: &str,
+    end_date: &str,
+    local_offset: chrono::FixedOffset,
+    now: chrono::NaiveDateTime,
+) -> Result<Vec<MeetingSlot>> {
+    use chrono::Datelike;
+    let invalid = |msg: String| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg));
+    if contact_ids.is_empty() {
+        return Err(invalid("请至少选择一位联系人".to_string()));
+    }
+    if duration_minutes <= 0 || duration_minutes > MAX_MEETING_MINUTES {
+        return Err(invalid(format!("无效的会议时长: {}（最长 24 小时）", duration_minutes)));
+    }
+    let start = chrono::NaiveDate::parse_from_str(start_date, "%Y-%m-%d").map_err(|_| invalid(format!("无效的日期: {}", start_date)))?;
+    let end = chrono::NaiveDate::parse_from_str(end_date, "%Y-%m-%d").map_err(|_| invalid(format!("无效的日期: {}", end_date)))?;
+    if start > end || (end - start).num_days() > MAX_MEETING_RANGE_DAYS {
+        return Err(invalid("时间范围无效（最长一年）".to_string()));
+    }
+    
+    // 联系人姓名、工作时间（换算为相对本机的偏移）
+    struct Participant {
+        contact_id: i32,
+        name: String,
+        offset: chrono::Duration,  // 联系人当地时间 - 本机时间
+        work_start: chrono::NaiveTime,
+        work_end: chrono::NaiveTime,
+        work_days: Vec<u32>,
+    }
+    let mut participants = Vec::new();
+    for &contact_id in contact_ids {
+        let name: String = conn.query_row("SELECT name FROM contacts WHERE id = ?1", [contact_id], |row| row.get(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => invalid(format!("联系人不存在: {}", contact_id)),
+                e => e,
+            })?;
+        let availability = get_contact_availability_with(conn, contact_id)?
+            .unwrap_or_else(|| ContactAvailability::default_for(contact_id));
+        let contact_offset = availability.timezone.as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .and_then(parse_utc_offset)
+            .unwrap_or(local_offset);
+        participants.push(Participant {
+            contact_id,
+            name,
+            offset: chrono::Duration::seconds((contact_offset.local_minus_utc() - local_offset.local_minus_utc()) as i64),
+            work_start: parse_work_time(&availability.work_start).unwrap_or_default(),
+            work_end: parse_work_time(&availability.work_end).unwrap_or_default(),
+            work_days: availability.work_days,
+        });
+    }
+    
+    // 联系人和"我"已有的事件（本机时间）
+    let mut busy_ids: Vec<i32> = contact_ids.to_vec();
+    if let Some(me) = fetch_settings_with(conn)?.get(SETTING_ME_CONTACT_ID).and_then(|v| v.trim().parse().ok()) {
+        busy_ids.push(me);
+    }
+    let mut stmt = conn.prepare_cached(
+        "SELECT ec.contact_id, e.event_date FROM events e
+         INNER JOIN events_contacts ec ON ec.event_id = e.id
+         WHERE substr(e.event_date, 1, 10) >= ?1 AND substr(e.event_date, 1, 10) <= ?2"
+    )?;
+    let busy: Vec<chrono::NaiveDateTime> = stmt.query_map([start_date, end_date], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?
+        .filter_map(|r| r.ok())
+        .filter(|(contact_id, _)| busy_ids.contains(contact_id))
+        .filter_map(|(_, date)| ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"].iter()
+            .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(&date, fmt).ok()))
+        .collect();
+    
+    let duration = chrono::Duration::minutes(duration_minutes);
+    let busy_length = chrono::Duration::minutes(BUSY_EVENT_MINUTES);
+    let mut slots = Vec::new();
+    let mut slot_start = start.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let range_end = end.and_hms_opt(0, 0, 0).unwrap_or_default() + chrono::Duration::days(1);
+    while slot_start + duration <= range_end && slots.len() < MAX_MEETING_SLOTS {
+        let slot_end = slot_start + duration;
+        let candidate = slot_start;
+        slot_start += chrono::Duration::minutes(MEETING_SLOT_STEP_MINUTES);
+        if candidate < now {
+            continue;
+        }
+        // 每位联系人在当地时间都处于同一个工作日的工作时间内
+        let available = participants.iter().all(|p| {
+            let local_start = candidate + p.offset;
+            let local_end = slot_end + p.offset;
+            local_start.date() == local_end.date()
+                && p.work_days.contains(&local_start.weekday().number_from_monday())
+                && local_start.time() >= p.work_start
+                && local_end.time() <= p.work_end
+        });
+        if !available {
+            continue;
+        }
+        if busy.iter().any(|b| *b < slot_end && *b + busy_length > candidate) {
+            continue;
+        }
+        slots.push(MeetingSlot {
+            start: candidate.format("%Y-%m-%d %H:%M").to_string(),
+            end: slot_end.format("%Y-%m-%d %H:%M").to_string(),
+            contact_times: participants.iter().map(|p| ContactLocalTime {
+                contact_id: p.contact_id,
+                name: p.name.clone(),
+                local_start: (candidate + p.offset).format("%Y-%m-%d %H:%M").to_string(),
+            }).collect(),
+        });
+    }
+    Ok(slots)
+}
+
+// ==================== 合同/发票/付款台账 ====================
+
+pub const COMMITMENT_TYPES: &[&str] = &["合同", "发票", "付款"];
+pub const COMMITMENT_STATUSES: &[&str] = &["待处理", "已完成", "已取消"];
+pub const DEFAULT_COMMITMENT_REMIND_DAYS: i32 = 3;
+// 最多提前多少天提醒
+pub const MAX_COMMITMENT_REMIND_DAYS: i32 = 365;
+// 提醒在到期日前 N 天的这个时间发出
+const COMMITMENT_REMINDER_TIME: &str = "09:00:00";
+
+// 台账条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Commitment {
+    pub id: i32,
+    pub commitment_type: String,
+    pub title: String,
+    pub amount: Option<crate::money::Money>,
+    pub amount_display: Option<String>,  // 按数字格式显示的金额，如 "CNY 12,000.00"
+    pub due_date: String,
+    pub contact_id: Option<i32>,
+    pub contact_name: Option<String>,
+    pub project_id: Option<i32>,
+    pub project_name: Option<String>,
+    pub status: String,
+    pub remind_days_before: i32,
+    pub reminder_at: Option<String>,
+    pub reminded_at: Option<String>,
+    pub notes: Option<String>,
+    pub days_until_due: i64,  // 距到期日的天数（已过期为负）
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// 新建或修改台账条目的请求（commitment_id 为空时新建）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitmentInput {
+    #[serde(default)]
+    pub commitment_id: Option<i32>,
+    pub commitment_type: String,
+    pub title: String,
+    pub amount: Option<String>,    // 十进制金额文本，如 "12000.50"
+    pub currency: Option<String>,  // 未指定时使用工作区默认币种
+    pub due_date: String,
+    pub contact_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub remind_days_before: Option<i32>,
+    pub notes: Option<String>,
+}
+
+impl CommitmentInput {
+    // 解析金额（空白视为未填写）
+    pub fn money(&self, default_currency: &str) -> std::result::Result<Option<crate::money::Money>, String> {
+        let Some(amount) = self.amount.as_deref().filter(|a| !a.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let currency = self.currency.as_deref().filter(|c| !c.trim().is_empty()).unwrap_or(default_currency);
+        let money = crate::money::Money::parse(amount, currency)?;
+        if money.amount_minor < 0 {
+            return Err("金额不能为负数".to_string());
+        }
+        Ok(Some(money))
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !COMMITMENT_TYPES.contains(&self.commitment_type.as_str()) {
+            return Err(format!("无效的类型: {}（应为 {}）", self.commitment_type, COMMITMENT_TYPES.join("、")));
+        }
+        if self.title.trim().is_empty() {
+            return Err("标题不能为空".to_string());
+        }
+        if chrono::NaiveDate::parse_from_str(&self.due_date, "%Y-%m-%d").is_err() {
+            return Err(format!("无效的到期日: {}", self.due_date));
+        }
+        if self.remind_days_before.is_some_and(|d| !(0..=MAX_COMMITMENT_REMIND_DAYS).contains(&d)) {
+            return Err(format!("提前提醒天数应为 0-{}", MAX_COMMITMENT_REMIND_DAYS));
+        }
+        Ok(())
+    }
+}
+
+const COMMITMENT_COLUMNS: &str = "m.id, m.commitment_type, m.title, m.amount_minor, m.due_date, m.contact_id, c.name, m.project_id, p.name, m.status, m.remind_days_before, m.reminder_at, m.reminded_at, m.notes, m.created_at, m.updated_at, m.currency";
+const COMMITMENT_FROM: &str = "commitments m LEFT JOIN contacts c ON c.id = m.contact_id LEFT JOIN projects p ON p.id = m.project_id";
+
+fn commitment_from_row(row: &rusqlite::Row, today: chrono::NaiveDate, numbers: &crate::format::NumberFormat) -> Result<Commitment> {
+    let due_date: String = row.get(4)?;
+    let days_until_due = parse_date_prefix(&due_date).map(|d| (d - today).num_days()).unwrap_or(0);
+    let amount = match (row.get::<_, Option<i64>>(3)?, row.get::<_, Option<String>>(16)?) {
+        (Some(amount_minor), Some(currency)) => Some(crate::money::Money { amount_minor, currency }),
+        _ => None,
+    };
+    Ok(Commitment {
+        id: row.get(0)?,
+        commitment_type: row.get(1)?,
+        title: row.get(2)?,
+        amount_display: amount.as_ref().map(|m| m.format(numbers)),
+        amount,
+        due_date,
+        contact_id: row.get(5)?,
+        contact_name: row.get(6)?,
+        project_id: row.get(7)?,
+        project_name: row.get(8)?,
+        status: row.get(9)?,
+        remind_days_before: row.get(10)?,
+        reminder_at: row.get(11)?,
+        reminded_at: row.get(12)?,
+        notes: row.get(13)?,
+        days_until_due,
+        created_at: row.get(14)?,
+        updated_at: row.get(15)?,
+    })
+}
+
+// 到期日前 N 天 09:00
+fn commitment_reminder_at(due_date: &str, remind_days_before: i32) -> Option<String> {
+    let due = chrono::NaiveDate::parse_from_str(due_date, "%Y-%m-%d").ok()?;
+    let remind_on = due.checked_sub_signed(chrono::Duration::try_days(remind_days_before as i64)?)?;
+    Some(format!("{} {}", remind_on.format("%Y-%m-%d"), COMMITMENT_REMINDER_TIME))
+}
+
+fn get_commitment_with(conn: &Connection, commitment_id: i32, today: chrono::NaiveDate) -> Result<Commitment> {
+    let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(conn)?);
+    conn.query_row(
+        &format!("SELECT {} FROM {} WHERE m.id = ?1", COMMITMENT_COLUMNS, COMMITMENT_FROM),
+        [commitment_id],
+        |row| commitment_from_row(row, today, &numbers),
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("台账条目不存在: {}", commitment_id))
+        ),
+        e => e,
+    })
+}
+
+// 新建或修改台账条目，修改到期日或提醒天数后重新安排提醒
+pub fn save_commitment(input: &CommitmentInput) -> Result<Commitment> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    save_commitment_with(&conn, input, chrono::Local::now().date_naive())
+}
+
+pub fn save_commitment_with(conn: &Connection, input: &CommitmentInput, today: chrono::NaiveDate) -> Result<Commitment> {
+    input.validate().map_err(|msg| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg)))?;
+    let amount = input.money(&crate::money::default_currency(&fetch_settings_with(conn)?))
+        .map_err(|msg| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg)))?;
+    let (amount_minor, currency) = match amount {
+        Some(money) => (Some(money.amount_minor), Some(money.currency)),
+        None => (None, None),
+    };
+    let remind_days = input.remind_days_before.unwrap_or(DEFAULT_COMMITMENT_REMIND_DAYS);
+    let reminder_at = commitment_reminder_at(&input.due_date, remind_days);
+    let title = input.title.trim();
+    let notes = input.notes.as_deref().map(|n| n.trim()).filter(|n| !n.is_empty());
+    let id = match input.commitment_id {
+        Some(id) => {
+            // 提醒时间变化时清除已提醒标记
+            let updated = conn.execute(
+                "UPDATE commitments SET commitment_type = ?1, title = ?2, amount_minor = ?3, currency = ?4, due_date = ?5, contact_id = ?6, project_id = ?7,
+                     remind_days_before = ?8, notes = ?9,
+                     reminded_at = CASE WHEN reminder_at IS ?10 THEN reminded_at ELSE NULL END,
+                     reminder_at = ?10, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?11",
+                rusqlite::params![input.commitment_type, title, amount_minor, currency, input.due_date, input.contact_id, input.project_id,
+                    remind_days, notes, reminder_at, id],
+            )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(1),
+                    Some(format!("台账条目不存在: {}", id))
+                ));
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO commitments (commitment_type, title, amount_minor, currency, due_date, contact_id, project_id, remind_days_before, reminder_at, notes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![input.commitment_type, title, amount_minor, currency, input.due_date, input.contact_id, input.project_id,
+                    remind_days, reminder_at, notes],
+            )?;
+            conn.last_insert_rowid() as i32
+        }
+    };
+    get_commitment_with(conn, id, today)
+}
+
+// 修改台账条目状态（待处理 / 已完成 / 已取消）
+pub fn set_commitment_status(commitment_id: i32, status: &str) -> Result<()> {
+    if !COMMITMENT_STATUSES.contains(&status) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的状态: {}", status))
+        ));
+    }
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    let updated = conn.execute(
+        "UPDATE commitments SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        rusqlite::params![status, commitment_id],
+    )?;
+    if updated == 0 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("台账条目不存在: {}", commitment_id))
+        ));
+    }
+    Ok(())
+}
+
+// 删除台账条目，返回是否存在
+pub fn delete_commitment(commitment_id: i32) -> Result<bool> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    Ok(conn.execute("DELETE FROM commitments WHERE id = ?1", [commitment_id])? > 0)
+}
+
+// 到期日在 start_date 到 end_date（闭区间）内的待处理条目，start_date 为空时包含已逾期的条目
+pub fn fetch_upcoming_commitments(start_date: Option<&str>, end_date: &str) -> Result<Vec<Commitment>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_upcoming_commitments_with(&conn, start_date, end_date, chrono::Local::now().date_naive())
+}
+
+pub fn fetch_upcoming_commitments_with(conn: &Connection, start_date: Option<&str>, end_date: &str, today: chrono::NaiveDate) -> Result<Vec<Commitment>> {
+    let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(conn)?);
+    ListQuery::new(format!("SELECT {} FROM {}", COMMITMENT_COLUMNS, COMMITMENT_FROM))
+        .condition("m.status = '待处理'")
+        .filter_opt("m.due_date >= ?", start_date.map(str::to_string))
+        .filter("m.due_date <= ?", end_date.to_string())
+        .order_by("m.due_date")
+        .order_by("m.id")
+        .fetch(conn, |row| commitment_from_row(row, today, &numbers))
+}
+
+// 待处理条目按类型的金额合计，不同币种分别合计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitmentTotal {
+    pub commitment_type: String,
+    pub count: usize,                 // 条目数（含未填写金额的条目）
+    pub totals: Vec<crate::money::Money>,
+    pub totals_display: Vec<String>,  // 如 ["CNY 12,000.00", "USD 300.00"]
+}
+
+// 到期日在 start_date 到 end_date（闭区间）内的待处理条目按类型合计
+pub fn fetch_commitment_totals(start_date: Option<&str>, end_date: &str) -> Result<Vec<CommitmentTotal>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_commitment_totals_with(&conn, start_date, end_date, chrono::Local::now().date_naive())
+}
+
+pub fn fetch_commitment_totals_with(conn: &Connection, start_date: Option<&str>, end_date: &str, today: chrono::NaiveDate) -> Result<Vec<CommitmentTotal>> {
+    let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(conn)?);
+    let commitments = fetch_upcoming_commitments_with(conn, start_date, end_date, today)?;
+    let mut totals = Vec::new();
+    for commitment_type in COMMITMENT_TYPES {
+        let items: Vec<&Commitment> = commitments.iter().filter(|c| c.commitment_type == *commitment_type).collect();
+        if items.is_empty() {
+            continue;
+        }
+        let sums = crate::money::sum_by_currency(items.iter().filter_map(|c| c.amount.as_ref()))
+            .map_err(|msg| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(msg)))?;
+        totals.push(CommitmentTotal {
+            commitment_type: commitment_type.to_string(),
+            count: items.len(),
+            totals_display: sums.iter().map(|m| m.format(&numbers)).collect(),
+            totals: sums,
+        });
+    }
+    Ok(totals)
+}
+
+// 已到提醒时间、尚未提醒的待处理条目（发送成功后用 mark_commitment_reminded 标记）
+pub fn fetch_due_commitment_reminders() -> Result<Vec<Commitment>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    fetch_due_commitment_reminders_with(&conn, chrono::Local::now().naive_local())
+}
+
+pub fn fetch_due_commitment_reminders_with(conn: &Connection, now: chrono::NaiveDateTime) -> Result<Vec<Commitment>> {
+    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let numbers = crate::format::NumberFormat::from_settings(&fetch_settings_with(conn)?);
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM {}
+         WHERE m.status = '待处理' AND m.reminded_at IS NULL
+         AND m.reminder_at IS NOT NULL AND m.reminder_at <= ?1
+         ORDER BY m.due_date, m.id",
+        COMMITMENT_COLUMNS, COMMITMENT_FROM
+    ))?;
+    let due: Vec<Commitment> = stmt.query_map([&now_str], |row| commitment_from_row(row, now.date(), &numbers))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(due)
+}
+
+// 标记台账条目已提醒
+pub fn mark_commitment_reminded(commitment_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    mark_commitment_reminded_with(&conn, commitment_id, chrono::Local::now().naive_local())
+}
+
+pub fn mark_commitment_reminded_with(conn: &Connection, commitment_id: i32, now: chrono::NaiveDateTime) -> Result<()> {
+    conn.execute(
+        "UPDATE commitments SET reminded_at = ?1 WHERE id = ?2",
+        rusqlite::params![now.format("%Y-%m-%d %H:%M:%S").to_string(), commitment_id],
+    )?;
+    Ok(())
+}
+
+// ==================== 项目进展邮件 ====================
+
+// 下一步计划列出的活动和事件上限
+const STATUS_EMAIL_NEXT_STEPS: usize = 8;
+
+// 下一步计划包含今后多少天内的事件
+const STATUS_EMAIL_LOOKAHEAD_DAYS: i64 = 14;
+
+// 项目进展邮件草稿（纯文本，可直接粘贴到邮件客户端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusEmailDraft {
+    pub project_id: i32,
+    pub start_date: String,
+    pub end_date: String,
+    pub subject: String,
+    pub body: String,
+    pub polished: bool,  // 正文是否经过润色程序处理
+}
+
+// 生成项目在 start_date 到 end_date（闭区间）内的进展邮件：近期事件、已完成活动、下一步计划
+pub fn draft_status_email(project_id: i32, start_date: &str, end_date: &str) -> Result<StatusEmailDraft> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    draft_status_email_with(&conn, project_id, start_date, end_date)
+}
+
+pub fn draft_status_email_with(conn: &Connection, project_id: i32, start_date: &str, end_date: &str) -> Result<StatusEmailDraft> {
+    let (Some(start), Some(end)) = (parse_date_prefix(start_date), parse_date_prefix(end_date)) else {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的日期范围: {} - {}", start_date, end_date))
+        ));
+    };
+    if start > end {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some("开始日期不能晚于结束日期".to_string())
+        ));
+    }
+    let name: String = conn.query_row(
+        "SELECT name FROM projects WHERE id = ?1",
+        [project_id],
+        |row| row.get(0)
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("项目不存在: {}", project_id))
+        ),
+        e => e,
+    })?;
+    let prefs = DisplayPreferences::from_settings(&fetch_settings_with(conn)?);
+    let start_str = start.format("%Y-%m-%d").to_string();
+    let end_str = end.format("%Y-%m-%d").to_string();
+    let lookahead_str = (end + chrono::Duration::days(STATUS_EMAIL_LOOKAHEAD_DAYS)).format("%Y-%m-%d").to_string();
+
+    // 项目事件（主项目或关联项目，不含私密事件）
+    let mut stmt = conn.prepare_cached(
+        "SELECT e.event_date, e.title, e.event_type FROM events e
+         WHERE (e.project_id = ?1 OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?1))
+         AND e.is_private = 0 AND date(e.event_date) BETWEEN ?2 AND ?3
+         ORDER BY e.event_date, e.id"
+    )?;
+    let recent_events: Vec<(String, String, Option<String>)> = stmt.query_map(rusqlite::params![project_id, start_str, end_str], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?,
+    )))?.filter_map(|r| r.ok()).collect();
+    let upcoming_events: Vec<(String, String, Option<String>)> = stmt.query_map(rusqlite::params![project_id, (end + chrono::Duration::days(1)).format("%Y-%m-%d").to_string(), lookahead_str], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?,
+    )))?.filter_map(|r| r.ok()).collect();
+
+    // 期间完成的活动
+    let mut stmt = conn.prepare_cached(
+        "SELECT name, completed_at FROM project_activities
+         WHERE project_id = ?1 AND status = '已完成' AND date(completed_at) BETWEEN ?2 AND ?3
+         ORDER BY completed_at, id"
+    )?;
+    let completed: Vec<(String, String)> = stmt.query_map(rusqlite::params![project_id, start_str, end_str], |row| Ok((
+        row.get(0)?, row.get(1)?,
+    )))?.filter_map(|r| r.ok()).collect();
+
+    // 未完成的活动，有预计完成日期的在前
+    let mut stmt = conn.prepare_cached(
+        "SELECT a.name, a.status, a.estimated_completion_date,
+                (SELECT GROUP_CONCAT(c.name, '、') FROM activities_contacts ac INNER JOIN contacts c ON c.id = ac.contact_id
+                 WHERE ac.activity_id = a.id)
+         FROM project_activities a
+         WHERE a.project_id = ?1 AND a.status != '已完成'
+         ORDER BY a.estimated_completion_date IS NULL, a.estimated_completion_date, a.id"
+    )?;
+    let open: Vec<(String, String, Option<String>, Option<String>)> = stmt.query_map([project_id], |row| Ok((
+        row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+    )))?.filter_map(|r| r.ok()).collect();
+
+    let period = format!("{} 至 {}", prefs.format_date(&start_str), prefs.format_date(&end_str));
+    let subject = format!("【{}】项目进展（{}）", name, period);
+    let mut body = String::new();
+    body.push_str("各位好，\n\n");
+    body.push_str(&format!("以下是「{}」{}的进展情况。\n\n", name, period));
+
+    body.push_str("一、近期进展\n");
+    if recent_events.is_empty() {
+        body.push_str("  本期没有新的事件记录。\n");
+    }
+    for (event_date, title, event_type) in &recent_events {
+        let kind = event_type.as_deref().map(|t| format!("［{}］", t)).unwrap_or_default();
+        body.push_str(&format!("  - {} {}{}\n", prefs.format_date(event_date.get(..10).unwrap_or(event_date)), kind, title));
+    }
+
+    body.push_str("\n二、已完成工作\n");
+    if completed.is_empty() {
+        body.push_str("  本期没有完成的活动。\n");
+    }
+    for (activity_name, completed_at) in &completed {
+        body.push_str(&format!("  - {}（{} 完成）\n", activity_name, prefs.format_date(completed_at.get(..10).unwrap_or(completed_at))));
+    }
+
+    body.push_str("\n三、下一步计划\n");
+    if open.is_empty() && upcoming_events.is_empty() {
+        body.push_str("  暂无待办事项。\n");
+    }
+    for (activity_name, status, due, assignees) in open.iter().take(STATUS_EMAIL_NEXT_STEPS) {
+        let mut details = vec![status.clone()];
+        if let Some(assignees) = assignees.as_deref().filter(|a| !a.is_empty()) {
+            details.push(format!("负责人：{}", assignees));
+        }
+        if let Some(due) = due.as_deref().filter(|d| !d.is_empty()) {
+            details.push(format!("预计 {} 完成", prefs.format_date(due.get(..10).unwrap_or(due))));
+        }
+        body.push_str(&format!("  - {}（{}）\n", activity_name, details.join("，")));
+    }
+    if open.len() > STATUS_EMAIL_NEXT_STEPS {
+        body.push_str(&format!("  - 另有 {} 项未完成的活动\n", open.len() - STATUS_EMAIL_NEXT_STEPS));
+    }
+    for (event_date, title, _) in upcoming_events.iter().take(STATUS_EMAIL_NEXT_STEPS) {
+        body.push_str(&format!("  - {} {}\n", prefs.format_date(event_date.get(..10).unwrap_or(event_date)), title));
+    }
+
+    body.push_str("\n如有问题请随时联系。\n\n谢谢！\n");
+
+    Ok(StatusEmailDraft {
+        project_id,
+        start_date: start_str,
+        end_date: end_str,
+        subject,
+        body,
+        polished: false,
+    })
+}
+
+// ==================== 打开记录与全局搜索 ====================
+
+// 可记录打开的对象类型
+pub const ACCESS_ENTITY_TYPES: &[&str] = &["contact", "project", "event", "file"];
+
+// 打开记录保留的天数
+const ACCESS_LOG_RETENTION_DAYS: i64 = 365;
+
+// 同一对象在该时间内重复打开只记一次（刷新页面不重复计数）
+const ACCESS_DEDUP_MINUTES: i64 = 10;
+
+// 全局搜索返回的结果上限
+const GLOBAL_SEARCH_LIMIT: usize = 30;
+
+// 全局搜索的一条结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entity_type: String,          // contact / project / event / file
+    pub entity_id: i32,
+    pub title: String,
+    pub subtitle: Option<String>,     // 联系人的单位、事件日期、文件所属项目
+    pub score: f64,
+    pub last_used_at: Option<String>, // 最近一次打开或互动
+    pub uses: i64,                    // 统计窗口内的打开和互动次数
+    pub highlights: Vec<crate::search::MatchHighlight>,  // 命中字段的摘要
+}
+
+// 记录一次打开联系人、项目、事件或文件
+pub fn record_access(entity_type: &str, entity_id: i32) -> Result<()> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    record_access_with(&conn, entity_type, entity_id, chrono::Local::now().naive_local())
+}
+
+pub fn record_access_with(conn: &Connection, entity_type: &str, entity_id: i32, now: chrono::NaiveDateTime) -> Result<()> {
+    if !ACCESS_ENTITY_TYPES.contains(&entity_type) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的对象类型: {}", entity_type))
+        ));
+    }
+    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let dedup_since = (now - chrono::Duration::minutes(ACCESS_DEDUP_MINUTES)).format("%Y-%m-%d %H:%M:%S").to_string();
+    let recent: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM access_log WHERE entity_type = ?1 AND entity_id = ?2 AND accessed_at > ?3",
+        rusqlite::params![entity_type, entity_id, dedup_since],
+        |row| row.get(0)
+    )?;
+    if recent > 0 {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO access_log (entity_type, entity_id, accessed_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![entity_type, entity_id, now_str],
+    )?;
+    let expired = (now - chrono::Duration::days(ACCESS_LOG_RETENTION_DAYS)).format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "DELETE FROM access_log WHERE entity_type = ?1 AND entity_id = ?2 AND accessed_at < ?3",
+        rusqlite::params![entity_type, entity_id, expired],
+    )?;
+    Ok(())
+}
+
+// 全局搜索联系人、项目、事件（不含私密事件）和文件，按匹配程度、最近使用时间和使用频率排序
+// include_archived 为 false 时不含已归档的项目及其事件和文件；is_cancelled 返回 true 时停止读取结果（被更新的搜索取代）
+pub fn search_global(keyword: &str, include_archived: bool, is_cancelled: &dyn Fn() -> bool) -> Result<Vec<SearchHit>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    search_global_with(&conn, keyword, include_archived, chrono::Local::now().naive_local(), is_cancelled)
+}
+
+pub fn search_global_with(conn: &Connection, keyword: &str, include_archived: bool, now: chrono::NaiveDateTime, is_cancelled: &dyn Fn() -> bool) -> Result<Vec<SearchHit>> {
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        return Ok(Vec::new());
+    }
+    let weights = crate::search::RankingWeights::from_settings(&fetch_settings_with(conn)?);
+    let pattern = format!("%{}%", keyword);
+    let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let window_start = chrono::Duration::try_days(weights.frequency_window_days)
+        .and_then(|days| now.checked_sub_signed(days))
+        .ok_or_else(|| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(1),
+            Some(format!("无效的使用次数统计窗口: {} 天", weights.frequency_window_days))
+        ))?
+        .format("%Y-%m-%d %H:%M:%S").to_string();
+
+    // 每行：ID、标题、副标题、最近打开、窗口内打开次数、最近互动（事件为发生时间，文件为最近上传）、窗口内互动次数、
+    // 标题以外参与匹配的字段名和内容；?1 为关键词模式，?2 为统计窗口开始，?3 为现在
+    type HitRow = (i32, String, Option<String>, Option<String>, i64, Option<String>, i64, Option<String>);
+    let queries: [(&str, &str, ListQuery); 4] = [
+        ("contact", "company", ListQuery::new("SELECT c.id, c.name, c.company,
+                (SELECT MAX(a.accessed_at) FROM access_log a WHERE a.entity_type = 'contact' AND a.entity_id = c.id),
+                (SELECT COUNT(*) FROM access_log a WHERE a.entity_type = 'contact' AND a.entity_id = c.id AND a.accessed_at >= ?2),
+                (SELECT MAX(e.event_date) FROM events_contacts ec INNER JOIN events e ON e.id = ec.event_id
+                 WHERE ec.contact_id = c.id AND e.event_date <= ?3),
+                (SELECT COUNT(*) FROM events_contacts ec INNER JOIN events e ON e.id = ec.event_id
+                 WHERE ec.contact_id = c.id AND e.event_date >= ?2 AND e.event_date <= ?3),
+                c.company
+             FROM contacts c")
+            .condition("c.name LIKE ?1 OR c.company LIKE ?1")),
+        ("project", "description", ListQuery::new("SELECT p.id, p.name, NULL,
+                (SELECT MAX(a.accessed_at) FROM access_log a WHERE a.entity_type = 'project' AND a.entity_id = p.id),
+                (SELECT COUNT(*) FROM access_log a WHERE a.entity_type = 'project' AND a.entity_id = p.id AND a.accessed_at >= ?2),
+                (SELECT MAX(e.event_date) FROM events_projects ep INNER JOIN events e ON e.id = ep.event_id
+                 WHERE ep.project_id = p.id AND e.event_date <= ?3),
+                (SELECT COUNT(*) FROM events_projects ep INNER JOIN events e ON e.id = ep.event_id
+                 WHERE ep.project_id = p.id AND e.event_date >= ?2 AND e.event_date <= ?3),
+                p.description
+             FROM projects p")
+            .condition("p.name LIKE ?1 OR p.description LIKE ?1")
+            .archived("p.archived", include_archived)),
+        ("event", "description", ListQuery::new("SELECT e.id, e.title, substr(e.event_date, 1, 16),
+                (SELECT MAX(a.accessed_at) FROM access_log a WHERE a.entity_type = 'event' AND a.entity_id = e.id),
+                (SELECT COUNT(*) FROM access_log a WHERE a.entity_type = 'event' AND a.entity_id = e.id AND a.accessed_at >= ?2),
+                CASE WHEN e.event_date <= ?3 THEN e.event_date END, 0,
+                e.description
+             FROM events e")
+            .condition("e.is_private = 0 AND (e.title LIKE ?1 OR e.description LIKE ?1)")
+            .archived_project("e.project_id", include_archived)),
+        ("file", "", ListQuery::new("SELECT f.id, f.original_name, pr.name,
+                (SELECT MAX(a.accessed_at) FROM access_log a WHERE a.entity_type = 'file' AND a.entity_id = f.id),
+                (SELECT COUNT(*) FROM access_log a WHERE a.entity_type = 'file' AND a.entity_id = f.id AND a.accessed_at >= ?2),
+                f.updated_at, CASE WHEN f.updated_at >= ?2 AND f.updated_at <= ?3 THEN 1 ELSE 0 END,
+                NULL
+             FROM project_files f INNER JOIN projects pr ON pr.id = f.project_id")
+            .condition("f.original_name LIKE ?1")
+            .condition("f.version = (SELECT MAX(version) FROM project_files latest
+                                     WHERE latest.project_id = f.project_id AND latest.original_name = f.original_name)")
+            .archived("pr.archived", include_archived)),
+    ];
+
+    let mut hits = Vec::new();
+    for (entity_type, detail_field, query) in &queries {
+        let mut stmt = conn.prepare_cached(&query.sql())?;
+        let rows = stmt.query_map(rusqlite::params![pattern, window_start, now_str], |row| -> Result<HitRow> {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?))
+        })?;
+        for row in rows {
+            if is_cancelled() {
+                return Err(search_cancelled());
+            }
+            let (entity_id, title, subtitle, last_access, accesses, last_interaction, interactions, detail) = row?;
+            // 只有标题以外的字段（如单位名称、描述）匹配时按较低的匹配程度计算
+            let quality = crate::search::match_quality(&title, keyword)
+                .max(detail.as_deref().map_or(0.0, |d| crate::search::match_quality(d, keyword) * 0.5));
+            let highlights = crate::search::highlights(&[("title", Some(title.as_str())), (detail_field, detail.as_deref())], keyword);
+            let last_used_at = last_access.max(last_interaction);
+            let days_since_use = last_used_at.as_deref()
+                .and_then(parse_local_datetime)
+                .map(|t| (now - t).num_minutes() as f64 / 1440.0);
+            let uses = accesses + interactions;
+            hits.push(SearchHit {
+                entity_type: entity_type.to_string(),
+                entity_id,
+                title,
+                subtitle,
+                score: weights.score(quality, days_since_use, uses),
+                last_used_at,
+                uses,
+                highlights,
+            });
+        }
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+    hits.truncate(GLOBAL_SEARCH_LIMIT);
+    Ok(hits)
+}
+
+// ==================== 审计触发器 ====================
+
+// 更新语句没有修改 updated_at 时由触发器补上（所有带 updated_at 列的表，已存在的触发器跳过）
+fn create_updated_at_triggers(conn: &Connection) -> Result<()> {
+    let tables: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT m.name FROM sqlite_master m
+             WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+             AND EXISTS(SELECT 1 FROM pragma_table_info(m.name) c WHERE c.name = 'updated_at')"
+        )?;
+        let tables = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+        tables
+    };
+    for table in tables {
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS touch_{0}_updated_at AFTER UPDATE ON {0}
+             WHEN NEW.updated_at IS OLD.updated_at
+             BEGIN
+                UPDATE {0} SET updated_at = CURRENT_TIMESTAMP WHERE rowid = NEW.rowid;
+             END",
+            table
+        ))?;
+    }
+    Ok(())
+}
+
+// 删除时自动写入操作日志的表：(表名, 实体类型, 名称, 日志中的名称, 所属项目 ID)
+const DELETE_LOG_TABLES: &[(&str, &str, &str, &str, &str)] = &[
+    ("projects", "project", "项目", "OLD.name", "OLD.id"),
+    ("contacts", "contact", "联系人", "OLD.name", "NULL"),
+    // 私密事件不记录标题
+    ("events", "event", "事件", "CASE WHEN OLD.is_private = 1 THEN '私密事件' ELSE OLD.title END", "OLD.project_id"),
+    ("project_activities", "activity", "活动", "OLD.name", "OLD.project_id"),
+    ("project_files", "file", "文件", "OLD.original_name", "OLD.project_id"),
+    ("summaries", "summary", "总结", "OLD.title", "NULL"),
+    ("commitments", "commitment", "台账条目", "OLD.title", "OLD.project_id"),
+];
+
+// 删除记录时补写一条简要的删除日志（代码中已先写入该记录的删除日志时跳过），保证每次删除都有记录
+fn create_delete_log_triggers(conn: &Connection) -> Result<()> {
+    for (table, entity_type, label, name_expr, project_expr) in DELETE_LOG_TABLES {
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS log_{table}_delete AFTER DELETE ON {table}
+             WHEN NOT EXISTS(SELECT 1 FROM operation_logs WHERE operation_type = 'delete' AND entity_type = '{entity_type}' AND entity_id = OLD.id)
+             BEGIN
+                INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description)
+                VALUES ('delete', '{entity_type}', OLD.id, {name_expr}, {project_expr},
+                        (SELECT name FROM projects WHERE id = {project_expr}),
+                        strftime('%Y年%m月%d日 %H:%M', 'now', 'localtime') || '，删除{label}「' || {name_expr} || '」');
+             END"
+        ))?;
+    }
+    Ok(())
+}
+
+// ==================== 变更订阅 ====================
+
+// 写入变更记录的表：(表名, 实体类型)，增删改都记录
+pub const CHANGE_FEED_TABLES: &[(&str, &str)] = &[
+    ("projects", "project"),
+    ("contacts", "contact"),
+    ("events", "event"),
+    ("project_activities", "activity"),
+    ("project_files", "file"),
+    ("summaries", "summary"),
+    ("commitments", "commitment"),
+];
+
+// 关联表的增删记为所属实体的 update：(表名, 实体类型, 实体 ID 列)
+const CHANGE_FEED_LINK_TABLES: &[(&str, &str, &str)] = &[
+    ("projects_contacts", "project", "project_id"),
+    ("events_contacts", "event", "event_id"),
+    ("events_projects", "event", "event_id"),
+    ("activities_contacts", "activity", "activity_id"),
+];
+
+// 一条实体变更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityChange {
+    pub id: i64,               // 变更序号（递增）
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub op: String,            // insert / update / delete
+    pub changed_at: String,
+}
+
+// 为 CHANGE_FEED_TABLES 和关联表创建写入 change_feed 的触发器（已存在的跳过）
+fn create_change_feed_triggers(conn: &Connection) -> Result<()> {
+    let now = "strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')";
+    let mut triggers = Vec::new();
+    for (table, entity_type) in CHANGE_FEED_TABLES {
+        for (op, event, row) in [("insert", "INSERT", "NEW"), ("update", "UPDATE", "NEW"), ("delete", "DELETE", "OLD")] {
+            triggers.push((format!("change_feed_{}_{}", table, op), event, *table, *entity_type, format!("{}.id", row), op));
+        }
+    }
+    for (table, entity_type, id_column) in CHANGE_FEED_LINK_TABLES {
+        for (event, row) in [("INSERT", "NEW"), ("DELETE", "OLD")] {
+            triggers.push((format!("change_feed_{}_{}", table, event.to_lowercase()), event, *table, *entity_type, format!("{}.{}", row, id_column), "update"));
+        }
+    }
+    for (name, event, table, entity_type, id_expr, op) in triggers {
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS {} AFTER {} ON {} BEGIN
+                INSERT INTO change_feed (entity_type, entity_id, op, changed_at) VALUES ('{}', {}, '{}', {});
+             END",
+            name, event, table, entity_type, id_expr, op, now
+        ))?;
+    }
+    Ok(())
+}
+
+// 取出并删除目前的全部变更记录（按发生顺序）
+pub fn take_changes() -> Result<Vec<EntityChange>> {
+    let db = get_db()?;
+    let conn = db.lock().map_err(|e| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(1),
+        Some(format!("锁失败: {}", e))
+    ))?;
+    take_changes_with(&conn)
+}
+
+pub fn take_changes_with(conn: &Connection) -> Result<Vec<EntityChange>> {
+    let mut stmt = conn.prepare_cached("SELECT id, entity_type, entity_id, op, changed_at FROM change_feed ORDER BY id")?;
+    let mut changes: Vec<EntityChange> = stmt.query_map([], |row| Ok(EntityChange {
+        id: row.get(0)?,
+        entity_type: row.get(1)?,
+        entity_id: row.get(2)?,
+        op: row.get(3)?,
+        changed_at: row.get(4)?,
+    }))?
+    .filter_map(|r| r.ok())
+    .collect();
+    if let Some(last) = changes.last() {
+        conn.execute("DELETE FROM change_feed WHERE id <= ?1", [last.id])?;
+    }
+    // 同一条记录连续的相同变更只推送一次（如触发器补写 updated_at 时的第二次 update）
+    changes.dedup_by(|b, a| a.entity_type == b.entity_type && a.entity_id == b.entity_id && a.op == b.op);
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 内存数据库，表结构与正式数据库一致
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn at(datetime: &str) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn insert_project(conn: &Connection, name: &str) -> i32 {
+        conn.execute("INSERT INTO projects (name) VALUES (?1)", [name]).unwrap();
+        conn.last_insert_rowid() as i32
+    }
+
+    fn insert_project_file(conn: &Connection, project_id: i32, original_name: &str, version: i32, created_at: &str) {
+        conn.execute(
+            "INSERT INTO project_files (project_id, original_name, stored_name, file_path, version, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![project_id, original_name, format!("v{}_{}", version, original_name), format!("/tmp/{}", original_name), version, created_at],
+        ).unwrap();
+    }
+
+    // 插入带提醒的事件，返回事件 ID
+    fn insert_reminder_event(conn: &Connection, title: &str, reminder_time: &str, insistent: bool) -> i32 {
+        conn.execute(
+            "INSERT INTO events (title, event_date, reminder_time, reminder_insistent) VALUES (?1, ?2, ?2, ?3)",
+            rusqlite::params![title, reminder_time, insistent as i32],
+        ).unwrap();
+        conn.last_insert_rowid() as i32
+    }
+
+    fn insert_log(conn: &Connection, operation_type: &str, entity_type: &str, description: &str, created_at: &str) {
+        conn.execute(
+            "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description, created_at)
+             VALUES (?1, ?2, 1, ?3, ?3, ?4)",
+            rusqlite::params![operation_type, entity_type, description, created_at],
+        ).unwrap();
+    }
+
+    fn event_titles(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.title.as_str()).collect()
+    }
+
+    #[test]
+    fn init_schema_is_idempotent() {
+        let conn = test_conn();
+        init_schema(&conn).unwrap();
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION_FOREIGN_KEYS);
+    }
+
+    #[test]
+    fn schema_version_counts_applied_migrations() {
+        let conn = test_conn();
+        let (version, latest) = fetch_schema_version_with(&conn).unwrap();
+        assert_eq!(latest.as_deref(), Some("seed_builtin_event_types"));
+
+        run_migration_once(&conn, "test_migration", |_| Ok(())).unwrap();
+        run_migration_once(&conn, "test_migration", |_| Ok(())).unwrap();
+        assert_eq!(fetch_schema_version_with(&conn).unwrap(), (version + 1, Some("test_migration".to_string())));
+    }
+
+    #[test]
+    fn channel_backfill_runs_only_once() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO events (id, title, event_date, event_type) VALUES (1, '回访', '2024-03-01', '电话')", []).unwrap();
+        // 用户清空的渠道在重新启动后保持为空
+        init_schema(&conn).unwrap();
+        let channel: Option<String> = conn.query_row("SELECT channel FROM events WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert!(channel.is_none());
+    }
+
+    #[test]
+    fn deleted_builtin_event_types_stay_deleted() {
+        let conn = test_conn();
+        let count = |name: &str| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM event_types WHERE name = ?1", [name], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count("工单"), 1);
+        conn.execute("DELETE FROM event_types WHERE name = '工单'", []).unwrap();
+        init_schema(&conn).unwrap();
+        assert_eq!(count("工单"), 0);
+        assert_eq!(count("会议"), 1);
+    }
+
+    #[test]
+    fn working_day_search_gives_up_after_a_month() {
+        let conn = test_conn();
+        let day = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        conn.execute("INSERT INTO holidays (date, name, is_workday) VALUES ('2024-10-07', '国庆节', 0), ('2024-10-12', '调休', 1)", []).unwrap();
+        let calendar = WorkCalendar::load(&conn).unwrap();
+        assert_eq!(calendar.next_working_day(day("2024-10-04")), Some(day("2024-10-08")));
+        assert_eq!(calendar.previous_working_day(day("2024-10-13")), Some(day("2024-10-12")));
+
+        // 连续两个月都是休息日时找不到工作日
+        let mut date = day("2025-01-01");
+        while date <= day("2025-02-28") {
+            conn.execute("INSERT INTO holidays (date, name, is_workday) VALUES (?1, '长假', 0)", [date.format("%Y-%m-%d").to_string()]).unwrap();
+            date = date.succ_opt().unwrap();
+        }
+        let calendar = WorkCalendar::load(&conn).unwrap();
+        assert_eq!(calendar.next_working_day(day("2025-01-10")), None);
+        assert_eq!(calendar.previous_working_day(day("2025-02-20")), None);
+        assert_eq!(calendar.on_or_after(day("2025-01-10")), day("2025-01-10"));
+        assert_eq!(calendar.next_working_day(chrono::NaiveDate::MAX), None);
+    }
+
+    #[test]
+    fn latest_file_version_per_project_and_name() {
+        let conn = test_conn();
+        let a = insert_project(&conn, "项目A");
+        let b = insert_project(&conn, "项目B");
+        assert_eq!(get_latest_file_version_with(&conn, a, "方案.docx").unwrap(), 0);
+
+        insert_project_file(&conn, a, "方案.docx", 1, "2024-03-01 10:00:00");
+        insert_project_file(&conn, a, "方案.docx", 2, "2024-03-02 10:00:00");
+        insert_project_file(&conn, b, "方案.docx", 1, "2024-03-02 11:00:00");
+        assert_eq!(get_latest_file_version_with(&conn, a, "方案.docx").unwrap(), 2);
+        assert_eq!(get_latest_file_version_with(&conn, b, "方案.docx").unwrap(), 1);
+        assert_eq!(get_latest_file_version_with(&conn, a, "报价.xlsx").unwrap(), 0);
+    }
+
+    #[test]
+    fn latest_contact_file_version() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO contacts (name) VALUES ('张三')", []).unwrap();
+        let contact_id = conn.last_insert_rowid() as i32;
+        for version in 1..=3 {
+            conn.execute(
+                "INSERT INTO contact_files (contact_id, original_name, stored_name, file_path, version) VALUES (?1, '名片.png', ?2, '/tmp/名片.png', ?3)",
+                rusqlite::params![contact_id, format!("v{}", version), version],
+            ).unwrap();
+        }
+        assert_eq!(get_latest_contact_file_version_with(&conn, contact_id, "名片.png").unwrap(), 3);
+    }
+
+    #[test]
+    fn pending_reminders_within_last_minute() {
+        let conn = test_conn();
+        let now = at("2024-03-01 09:00:30");
+        insert_reminder_event(&conn, "刚到期", "2024-03-01 09:00:00", false);
+        insert_reminder_event(&conn, "已过期", "2024-03-01 08:50:00", false);
+        insert_reminder_event(&conn, "未到期", "2024-03-01 09:05:00", false);
+        insert_reminder_event(&conn, "持续提醒", "2024-03-01 09:00:00", true);
+        let triggered = insert_reminder_event(&conn, "已触发", "2024-03-01 09:00:10", false);
+        conn.execute("UPDATE events SET reminder_triggered = 1 WHERE id = ?1", [triggered]).unwrap();
+
+        let events = fetch_pending_reminder_events_with(&conn, now).unwrap();
+        assert_eq!(event_titles(&events), vec!["刚到期"]);
+    }
+
+    #[test]
+    fn insistent_reminders_repeat_until_acknowledged() {
+        let conn = test_conn();
+        let id = insert_reminder_event(&conn, "回电话", "2024-03-01 09:00:00", true);
+        conn.execute("UPDATE events SET reminder_repeat_minutes = 5, channel = '电话' WHERE id = ?1", [id]).unwrap();
+
+        // 从未通知过：到期即提醒
+        let due = fetch_due_insistent_reminder_events_with(&conn, at("2024-03-01 09:00:00")).unwrap();
+        assert_eq!(event_titles(&due), vec!["回电话"]);
+
+        // 距上次通知不足重复间隔
+        conn.execute("UPDATE events SET reminder_last_notified_at = '2024-03-01 09:00:00' WHERE id = ?1", [id]).unwrap();
+        assert!(fetch_due_insistent_reminder_events_with(&conn, at("2024-03-01 09:03:00")).unwrap().is_empty());
+        assert_eq!(fetch_due_insistent_reminder_events_with(&conn, at("2024-03-01 09:05:00")).unwrap().len(), 1);
+
+        // 确认后不再提醒
+        conn.execute("UPDATE events SET reminder_acknowledged_at = '2024-03-01 09:06:00' WHERE id = ?1", [id]).unwrap();
+        assert!(fetch_due_insistent_reminder_events_with(&conn, at("2024-03-01 09:30:00")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn period_statistics_counts_logs_files_and_activities() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "项目A");
+        insert_log(&conn, "create", "project", "创建项目 项目A", "2024-03-01 09:00:00");
+        insert_log(&conn, "create", "contact", "新增联系人 张三", "2024-03-02 09:00:00");
+        insert_log(&conn, "update", "project", "修改项目 项目A", "2024-03-03 09:00:00");
+        insert_log(&conn, "create", "event", "范围外", "2024-04-01 09:00:00");
+        insert_project_file(&conn, project, "方案.docx", 1, "2024-03-01 10:00:00");
+        insert_project_file(&conn, project, "方案.docx", 2, "2024-03-05 10:00:00");
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status, completed_at) VALUES (?1, '交付', '已完成', '2024-03-10 18:00:00')",
+            [project],
+        ).unwrap();
+
+        let stats = compute_period_statistics_with(&conn, "2024-03-01", "2024-03-31").unwrap();
+        assert_eq!(stats.total_operations, 3);
+        assert_eq!(stats.new_projects, 1);
+        assert_eq!(stats.new_contacts, 1);
+        assert_eq!(stats.new_events, 0);
+        assert_eq!(stats.files_added, 1);
+        assert_eq!(stats.files_updated, 1);
+        assert_eq!(stats.completed_activities, 1);
+    }
+
+    #[test]
+    fn quarterly_summary_includes_rankings() {
+        let conn = test_conn();
+        let busy = insert_project(&conn, "忙碌项目");
+        let quiet = insert_project(&conn, "清闲项目");
+        insert_project_file(&conn, busy, "a.docx", 1, "2024-02-01 10:00:00");
+        insert_project_file(&conn, busy, "b.docx", 1, "2024-02-02 10:00:00");
+        insert_project_file(&conn, quiet, "c.docx", 1, "2024-02-03 10:00:00");
+
+        let (_, content, statistics) = render_summary_with(&conn, "quarterly", "2024-01-01", "2024-03-31").unwrap();
+        assert!(content.contains("该时间段内没有操作记录"));
+        assert!(content.contains("- 新增文件：3"));
+        assert!(content.contains("1. 忙碌项目（2 项动态）"));
+
+        let stats: PeriodStatistics = serde_json::from_str(&statistics).unwrap();
+        assert_eq!(stats.top_projects.iter().map(|p| p.id).collect::<Vec<_>>(), vec![busy, quiet]);
+
+        // 周报不含排行
+        let (_, weekly, _) = render_summary_with(&conn, "weekly", "2024-01-29", "2024-02-04").unwrap();
+        assert!(!weekly.contains("最活跃项目"));
+    }
+
+    #[test]
+    fn summary_uses_display_date_format() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '%Y/%m/%d')", [SETTING_DATE_FORMAT]).unwrap();
+        let summary = generate_summary_with(&conn, "custom", "2024-03-01", "2024-03-07", false).unwrap();
+        assert!(summary.content.starts_with("# 2024/03/01 至 2024/03/07 工作总结"));
+
+        let stored: String = conn.query_row("SELECT content FROM summaries WHERE id = ?1", [summary.id], |row| row.get(0)).unwrap();
+        assert_eq!(stored, summary.content);
+    }
+
+    #[test]
+    fn event_requests_are_validated() {
+        let event = NewEvent {
+            title: "周会".to_string(),
+            description: None,
+            event_date: "2024-03-01T10:00".to_string(),
+            project_id: None,
+            event_type: None,
+            contact_ids: vec![1],
+            reminder_time: Some("2024-03-01 09:50:00".to_string()),
+            extra_project_ids: Vec::new(),
+            channel: None,
+        };
+        assert!(event.validate().is_ok());
+        assert!(NewEvent { contact_ids: Vec::new(), ..event.clone() }.validate().is_err());
+        assert!(NewEvent { title: "  ".to_string(), ..event.clone() }.validate().is_err());
+        assert!(NewEvent { event_date: "下周五".to_string(), ..event.clone() }.validate().is_err());
+        assert!(NewEvent { reminder_time: Some("25:00".to_string()), ..event.clone() }.validate().is_err());
+
+        // 缺省字段按 None / 空列表处理
+        let update: UpdateEvent = serde_json::from_str(r#"{"event_id": 1, "title": "周会", "event_date": "2024-03-01"}"#).unwrap();
+        assert!(update.validate().is_ok());
+        assert!(update.contact_ids.is_empty() && update.extra_project_ids.is_none());
+    }
+
+    #[test]
+    fn project_log_includes_backfilled_and_cross_project_entries() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "项目A");
+        let other = insert_project(&conn, "项目B");
+        // 旧版本记录的日志没有 project_id
+        conn.execute(
+            "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description, created_at)
+             VALUES ('create', 'project', ?1, '项目A', '新增项目「项目A」', '2024-03-01 09:00:00')",
+            [project],
+        ).unwrap();
+        // 主项目为项目B、同时关联项目A的事件
+        conn.execute("INSERT INTO events (title, event_date, project_id) VALUES ('联合评审', '2024-03-05', ?1)", [other]).unwrap();
+        let event = conn.last_insert_rowid() as i32;
+        conn.execute("INSERT INTO events_projects (event_id, project_id) VALUES (?1, ?2)", [event, project]).unwrap();
+        conn.execute(
+            "INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, project_name, description, created_at)
+             VALUES ('create', 'event', ?1, '联合评审', ?2, '项目B', '新增事件「联合评审」', '2024-03-05 10:00:00')",
+            [event, other],
+        ).unwrap();
+        insert_log(&conn, "create", "contact", "新增联系人 张三", "2024-03-06 09:00:00");
+
+        backfill_log_projects(&conn).unwrap();
+        let logs = fetch_project_log_with(&conn, project, None, None).unwrap();
+        assert_eq!(logs.iter().map(|l| l.entity_name.as_str()).collect::<Vec<_>>(), vec!["联合评审", "项目A"]);
+        assert_eq!(logs[1].project_id, Some(project));
+
+        let ranged = fetch_project_log_with(&conn, project, Some("2024-03-02"), Some("2024-03-31")).unwrap();
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(fetch_project_log_with(&conn, other, None, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn daily_briefing_collects_the_day_and_updates_in_place() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "项目A");
+        conn.execute("INSERT INTO events (title, event_date, project_id) VALUES ('周会', '2024-03-05 10:30', ?1)", [project]).unwrap();
+        conn.execute("INSERT INTO events (title, event_date) VALUES ('明天的事', '2024-03-06 09:00')", []).unwrap();
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, estimated_completion_date, status) VALUES
+             (?1, '写方案', '2024-03-01', '进行中'), (?1, '交报价', '2024-03-05', '进行中'), (?1, '已交付', '2024-03-01', '已完成')",
+            [project],
+        ).unwrap();
+        let event = insert_reminder_event(&conn, "回电客户", "2024-03-04 15:00:00", false);
+        conn.execute(
+            "INSERT INTO reminder_inbox (event_id, title, first_notified_at, last_notified_at) VALUES (?1, '事件提醒: 回电客户', '2024-03-04 15:00:00', '2024-03-04 15:00:00')",
+            [event],
+        ).unwrap();
+        generate_summary_with(&conn, "daily", "2024-03-04", "2024-03-04", true).unwrap();
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let briefing = generate_daily_briefing_with(&conn, date, true).unwrap();
+        assert_eq!(briefing.summary_type, "briefing");
+        assert!(briefing.content.contains("10:30 周会（项目A）"));
+        assert!(!briefing.content.contains("明天的事"));
+        assert!(briefing.content.contains("【逾期】写方案"));
+        assert!(briefing.content.contains("- 交报价"));
+        assert!(!briefing.content.contains("已交付"));
+        assert!(briefing.content.contains("事件提醒: 回电客户"));
+        let stats: BriefingStatistics = serde_json::from_str(briefing.statistics.as_deref().unwrap()).unwrap();
+        assert_eq!((stats.events_today, stats.due_activities, stats.overdue_activities, stats.pending_follow_ups), (1, 2, 1, 1));
+        assert!(stats.has_yesterday_summary);
+
+        let again = generate_daily_briefing_with(&conn, date, false).unwrap();
+        assert_eq!(again.id, briefing.id);
+        let count: i32 = conn.query_row("SELECT COUNT(*) FROM summaries WHERE summary_type = 'briefing'", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn day_notes_are_folded_into_summaries() {
+        let conn = test_conn();
+        conn.execute(
+            "INSERT INTO day_notes (note_date, content, mood, created_at) VALUES
+             ('2024-03-05', '方案评审顺利', '不错', '2024-03-05 18:00:00'),
+             ('2024-03-06', '加班赶报价', NULL, '2024-03-06 21:00:00')",
+            [],
+        ).unwrap();
+
+        let (_, daily, _) = render_summary_with(&conn, "daily", "2024-03-05", "2024-03-05").unwrap();
+        assert!(daily.contains("## 每日记录"));
+        assert!(daily.contains("- 【不错】方案评审顺利"));
+        assert!(!daily.contains("加班赶报价"));
+
+        let (_, weekly, _) = render_summary_with(&conn, "weekly", "2024-03-04", "2024-03-10").unwrap();
+        assert!(weekly.contains("2024-03-06 加班赶报价"));
+    }
+
+    #[test]
+    fn wellbeing_trend_correlates_ratings_with_workload() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "项目A");
+        // 3 月 4-7 日：事件越多心情越差
+        for (date, mood, events) in [("2024-03-04", 5, 0), ("2024-03-05", 4, 1), ("2024-03-06", 2, 3), ("2024-03-07", 1, 4)] {
+            conn.execute(
+                "INSERT INTO day_notes (note_date, content, mood_rating, energy_rating) VALUES (?1, '', ?2, 3)",
+                rusqlite::params![date, mood],
+            ).unwrap();
+            for i in 0..events {
+                conn.execute("INSERT INTO events (title, event_date) VALUES (?1, ?2)", rusqlite::params![format!("会议{}", i), format!("{} 10:00", date)]).unwrap();
+            }
+        }
+        // 3 月 5 日到期、3 月 7 日完成的活动：6、7 日算逾期
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, estimated_completion_date, status, completed_at) VALUES (?1, '写方案', '2024-03-05', '已完成', '2024-03-07 16:00:00')",
+            [project],
+        ).unwrap();
+
+        let trend = fetch_wellbeing_trend_with(&conn, "2024-03-03", "2024-03-08").unwrap();
+        assert_eq!(trend.days.len(), 6);
+        assert_eq!(trend.days[0].mood_rating, None);
+        assert_eq!(trend.days.iter().map(|d| d.overdue_activities).collect::<Vec<_>>(), vec![0, 0, 0, 1, 1, 0]);
+        assert_eq!(trend.average_mood, Some(3.0));
+        assert!(trend.correlations.mood_vs_events.unwrap() < -0.9);
+        assert!(trend.correlations.mood_vs_overdue.unwrap() < 0.0);
+        // 精力评分没有变化，无法计算相关性
+        assert_eq!(trend.correlations.energy_vs_events, None);
+        assert!(fetch_wellbeing_trend_with(&conn, "2024-03-08", "2024-03-01").is_err());
+    }
+
+    #[test]
+    fn cleanup_suggestions_cover_each_kind() {
+        let conn = test_conn();
+        let now = at("2024-06-01 12:00:00");
+        conn.execute("INSERT INTO projects (name, created_at) VALUES ('空项目', '2024-01-01 09:00:00'), ('新项目', '2024-05-25 09:00:00')", []).unwrap();
+        let busy = insert_project(&conn, "进行中");
+        conn.execute("UPDATE projects SET created_at = '2024-01-01 09:00:00' WHERE id = ?1", [busy]).unwrap();
+        for version in 1..=5 {
+            insert_project_file(&conn, busy, "方案.docx", version, &format!("2024-0{}-01 10:00:00", version));
+        }
+        conn.execute(
+            "INSERT INTO contacts (name, created_at) VALUES ('老朋友', '2022-01-01 09:00:00'), ('常联系', '2022-01-01 09:00:00')",
+            [],
+        ).unwrap();
+        conn.execute("INSERT INTO events (title, event_date) VALUES ('去年聚会', '2023-03-01'), ('上周会议', '2024-05-20')", []).unwrap();
+        conn.execute(
+            "INSERT INTO events_contacts (event_id, contact_id)
+             SELECT e.id, c.id FROM events e, contacts c
+             WHERE (e.title = '去年聚会' AND c.name = '老朋友') OR (e.title = '上周会议' AND c.name = '常联系')",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO drafts (entity_type, payload, created_at, updated_at) VALUES
+             ('event', '{}', '2024-04-01 09:00:00', '2024-04-01 09:00:00'), ('event', '{}', '2024-05-30 09:00:00', '2024-05-30 09:00:00')",
+            [],
+        ).unwrap();
+
+        let suggestions = fetch_cleanup_suggestions_with(&conn, now, now - chrono::Duration::hours(8)).unwrap();
+        let found: Vec<(&str, &str)> = suggestions.iter().map(|s| (s.kind.as_str(), s.title.as_str())).collect();
+        assert_eq!(found, vec![
+            (CLEANUP_EMPTY_PROJECT, "空项目"),
+            (CLEANUP_INACTIVE_CONTACT, "老朋友"),
+            (CLEANUP_SUPERSEDED_FILE, "进行中 / 方案.docx"),
+            (CLEANUP_STALE_DRAFT, "event 草稿"),
+        ]);
+        assert!(suggestions[1].reason.contains("2023-03-01"));
+        assert!(suggestions[2].reason.contains("可删除 2 个"));
+
+        assert_eq!(suggestions[0].actions, vec!["archive", "delete"]);
+
+        // 已归档的空项目不再提示
+        let empty_id = suggestions[0].entity_id;
+        set_project_archived_with(&conn, empty_id, true).unwrap();
+        let suggestions = fetch_cleanup_suggestions_with(&conn, now, now - chrono::Duration::hours(8)).unwrap();
+        assert!(suggestions.iter().all(|s| s.kind != CLEANUP_EMPTY_PROJECT));
+        // 创建时间按 UTC 比较：本机 2024-05-25 00:30（+08:00）创建的项目，到本机 06-24 00:30 才满 30 天
+        conn.execute("INSERT INTO projects (name, created_at) VALUES ('时区项目', '2024-05-24 16:30:00')", []).unwrap();
+        let empty_projects = |now: chrono::NaiveDateTime| -> Vec<String> {
+            fetch_cleanup_suggestions_with(&conn, now, now - chrono::Duration::hours(8)).unwrap().into_iter()
+                .filter(|s| s.kind == CLEANUP_EMPTY_PROJECT)
+                .map(|s| s.title)
+                .collect()
+        };
+        assert!(empty_projects(at("2024-06-23 20:00:00")).is_empty());
+        assert_eq!(empty_projects(at("2024-06-24 00:40:00")), vec!["时区项目"]);
+        assert_eq!(delete_empty_project_with(&conn, empty_id).unwrap(), "空项目");
+        assert!(delete_empty_project_with(&conn, busy).is_err());
+    }
+
+    #[test]
+    fn job_run_history_is_pruned_in_batches() {
+        let conn = test_conn();
+        let total = JOB_RUN_HISTORY_LIMIT + JOB_RUN_PRUNE_INTERVAL;
+        for _ in 1..total {
+            insert_job_run_with(&conn, "reminders", "scheduled", "2026-03-01 09:00:00", "2026-03-01 09:00:01", true, Some("发送 0 条提醒")).unwrap();
+        }
+        let count = |job_name: &str| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM job_runs WHERE job_name = ?1", [job_name], |row| row.get(0)).unwrap()
+        };
+        // 未到清理间隔前记录照常累积
+        assert_eq!(count("reminders"), total - 1);
+
+        // 到达清理间隔时清理所有任务超出保留条数的旧记录，保留最新的记录
+        let run = insert_job_run_with(&conn, "backup", "manual", "2026-03-01 10:00:00", "2026-03-01 10:00:01", true, None).unwrap();
+        assert_eq!(run.id % JOB_RUN_PRUNE_INTERVAL, 0);
+        assert_eq!((count("reminders"), count("backup")), (JOB_RUN_HISTORY_LIMIT, 1));
+        let oldest: i64 = conn.query_row("SELECT MIN(id) FROM job_runs WHERE job_name = 'reminders'", [], |row| row.get(0)).unwrap();
+        assert_eq!(oldest, total - JOB_RUN_HISTORY_LIMIT);
+    }
+
+    #[test]
+    fn inactive_contacts_are_rechecked_before_deleting() {
+        let mut conn = test_conn();
+        let now = at("2024-06-01 12:00:00");
+        let now_utc = now - chrono::Duration::hours(8);
+        conn.execute(
+            "INSERT INTO contacts (id, name, created_at) VALUES (1, '老朋友', '2022-01-01 09:00:00'), (2, '常联系', '2022-01-01 09:00:00')",
+            [],
+        ).unwrap();
+        conn.execute("INSERT INTO events (id, title, event_date) VALUES (1, '上周会议', '2024-05-20')", []).unwrap();
+        conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (1, 2)", []).unwrap();
+
+        // 有新互动、被设为"我"的联系人不删除
+        assert!(delete_inactive_contact_with(&mut conn, 2, now, now_utc).is_err());
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '1')", [SETTING_ME_CONTACT_ID]).unwrap();
+        assert!(delete_inactive_contact_with(&mut conn, 1, now, now_utc).is_err());
+        conn.execute("DELETE FROM settings WHERE key = ?1", [SETTING_ME_CONTACT_ID]).unwrap();
+        assert!(delete_inactive_contact_with(&mut conn, 99, now, now_utc).is_err());
+
+        assert_eq!(delete_inactive_contact_with(&mut conn, 1, now, now_utc).unwrap(), "老朋友");
+        let remaining: Vec<String> = conn.prepare("SELECT name FROM contacts").unwrap()
+            .query_map([], |row| row.get(0)).unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(remaining, vec!["常联系"]);
+    }
+
+    #[test]
+    fn project_retrospective_summarizes_and_updates_in_place() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute("UPDATE projects SET created_at = '2024-03-01 09:00:00' WHERE id = ?1", [project]).unwrap();
+        conn.execute("INSERT INTO contacts (name) VALUES ('张三'), ('李四')", []).unwrap();
+        conn.execute("INSERT INTO projects_contacts (project_id, contact_id, role) VALUES (?1, 1, '设计')", [project]).unwrap();
+        conn.execute(
+            "INSERT INTO events (title, event_date, project_id, event_type, pinned) VALUES
+             ('启动会', '2024-03-02 10:00', ?1, '会议', 1), ('验收', '2024-03-20 15:00', ?1, NULL, 0)",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (1, 1), (2, 1), (2, 2)", []).unwrap();
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status, created_at, activated_at, completed_at) VALUES
+             (?1, '设计稿', '已完成', '2024-03-01 09:00:00', '2024-03-03 09:00:00', '2024-03-08 18:00:00'),
+             (?1, '开发', '已完成', '2024-03-05 09:00:00', NULL, '2024-03-15 18:00:00'),
+             (?1, '复盘会', '进行中', '2024-03-20 09:00:00', NULL, NULL)",
+            [project],
+        ).unwrap();
+        insert_project_file(&conn, project, "设计稿.fig", 1, "2024-03-05 10:00:00");
+        insert_project_file(&conn, project, "设计稿.fig", 2, "2024-03-08 10:00:00");
+
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 4, 1).unwrap();
+        let retro = generate_project_retrospective_with(&conn, project, today).unwrap();
+        assert_eq!(retro.summary_type, "retrospective");
+        assert_eq!((retro.start_date.as_str(), retro.end_date.as_str()), ("2024-03-01", "2024-03-20"));
+        let stats: RetrospectiveStatistics = serde_json::from_str(retro.statistics.as_deref().unwrap()).unwrap();
+        assert_eq!((stats.duration_days, stats.events, stats.contributors), (20, 2, 2));
+        assert_eq!((stats.files, stats.file_versions, stats.completed_activities, stats.open_activities), (1, 2, 2, 1));
+        assert_eq!(stats.average_cycle_days, Some(7.5));
+        assert!(retro.content.contains("⭐ ［会议］启动会"));
+        assert!(retro.content.contains("- 张三（设计）：参与 2 个事件"));
+        assert!(retro.content.contains("- 设计稿.fig（v2"));
+
+        let again = generate_project_retrospective_with(&conn, project, today).unwrap();
+        assert_eq!(again.id, retro.id);
+        assert!(generate_project_retrospective_with(&conn, 999, today).is_err());
+    }
+
+    #[test]
+    fn module_settings_are_validated_before_saving() {
+        assert!(validate_setting(crate::format::SETTING_NUMBER_FORMAT, "xx").is_err());
+        assert!(validate_setting(crate::format::SETTING_FILE_SIZE_UNITS, "binary").is_ok());
+        assert!(validate_setting(crate::file_policy::SETTING_BLOCKED_EXTENSIONS, "exe, *.js").is_err());
+        assert!(validate_setting(crate::redaction::SETTING_REDACTION_PROFILES, r#"[{"name": "a"}, {"name": "a"}]"#).is_err());
+        assert!(validate_setting(crate::redaction::SETTING_REDACTION_PROFILES, r#"[{"name": ""}]"#).is_err());
+        assert!(validate_setting(crate::redaction::SETTING_REDACTION_PROFILES, "").is_ok());
+        assert!(validate_setting("onboarding.me_contact", "done").is_ok());
+        assert!(validate_setting("onboarding.me_contact", "later").is_err());
+        assert!(validate_setting("onboarding.tour", "done").is_err());
+
+        let mut settings = std::collections::HashMap::new();
+        settings.insert(crate::format::SETTING_NUMBER_FORMAT.to_string(), "de".to_string());
+        assert_eq!(DisplayPreferences::from_settings(&settings).numbers.format_hours(1234.5), "1.234,5 h");
+    }
+
+    #[test]
+    fn expired_file_shares_are_listed_for_purging() {
+        let conn = test_conn();
+        insert_file_share_with(&conn, 1, "方案.docx", "/tmp/shares/a1/方案.docx", "2024-03-01 12:00:00").unwrap();
+        let later = insert_file_share_with(&conn, 2, "报价.xlsx", "/tmp/shares/b2/报价.xlsx", "2024-03-08 12:00:00").unwrap();
+        assert_eq!(later.file_id, Some(2));
+
+        assert!(fetch_expired_file_shares_with(&conn, at("2024-03-01 11:59:00")).unwrap().is_empty());
+        let expired = fetch_expired_file_shares_with(&conn, at("2024-03-05 09:00:00")).unwrap();
+        assert_eq!(expired.iter().map(|s| s.original_name.as_str()).collect::<Vec<_>>(), vec!["方案.docx"]);
+        assert_eq!(fetch_expired_file_shares_with(&conn, at("2024-03-09 00:00:00")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn similar_activities_rank_same_project_first() {
+        let conn = test_conn();
+        let web = insert_project(&conn, "官网");
+        let app = insert_project(&conn, "App");
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status) VALUES
+             (?1, '首页设计稿', '进行中'), (?1, '服务器采购', '待分配'), (?2, '首页 设计稿', '已完成'), (?2, 'Login page review', '待分配')",
+            [web, app],
+        ).unwrap();
+
+        assert_eq!(name_similarity("首页设计稿", "首页 设计稿！"), 1.0);
+        assert!(name_similarity("首页设计稿", "服务器采购") < SIMILAR_ACTIVITY_THRESHOLD);
+
+        let similar = find_similar_activities_with(&conn, "首页设计", Some(app)).unwrap();
+        assert_eq!(similar.iter().map(|a| (a.project_id, a.same_project)).collect::<Vec<_>>(), vec![(app, true), (web, false)]);
+        let similar = find_similar_activities_with(&conn, "login page reviews", None).unwrap();
+        assert_eq!(similar.len(), 1);
+        assert!(find_similar_activities_with(&conn, " ！", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn assignment_message_excludes_me_and_builds_mailto() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网");
+        conn.execute("INSERT INTO contacts (name, email) VALUES ('我', 'me@example.com'), ('王五', 'wang@example.com'), ('赵六', NULL)", []).unwrap();
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status, estimated_completion_date) VALUES (?1, '首页设计', '未激活', '2024-05-10')",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO activities_contacts (activity_id, contact_id) VALUES (1, 1), (1, 2), (1, 3)", []).unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '1')", [SETTING_ME_CONTACT_ID]).unwrap();
+
+        let message = fetch_assignment_message_with(&conn, 1).unwrap();
+        assert!(message.assigned_to_me);
+        assert_eq!(message.recipients.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["王五", "赵六"]);
+        assert_eq!(message.subject, "【官网】活动分配：首页设计");
+        assert!(message.body.contains("预计完成日期：2024-05-10"));
+        assert!(message.mailto.as_deref().unwrap().starts_with("mailto:wang@example.com?subject=%E3%80%90"));
+        assert_eq!(encode_uri_component("a b&c"), "a%20b%26c");
+        assert!(fetch_assignment_message_with(&conn, 99).is_err());
+    }
+
+    #[test]
+    fn aging_report_excludes_paused_time() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网");
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status) VALUES (?1, '首页设计', '未激活'), (?1, '接口联调', '未激活')",
+            [project],
+        ).unwrap();
+        activate_activity_with(&conn, 1, "2024-03-01 09:00:00").unwrap();
+        pause_activity_with(&conn, 1, "2024-03-05 09:00:00").unwrap();
+        activate_activity_with(&conn, 1, "2024-03-15 09:00:00").unwrap();
+        activate_activity_with(&conn, 2, "2024-03-18 09:00:00").unwrap();
+
+        let activated_at: String = conn.query_row("SELECT activated_at FROM project_activities WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(activated_at, "2024-03-01 09:00:00");
+
+        let report = fetch_activity_aging_report_with(&conn, None, at("2024-03-21 09:00:00")).unwrap();
+        assert_eq!(report.threshold_days, DEFAULT_AGING_THRESHOLD_DAYS);
+        let summary: Vec<(i32, f64, f64, bool)> = report.activities.iter()
+            .map(|a| (a.activity_id, a.active_days, a.paused_days, a.over_threshold))
+            .collect();
+        assert_eq!(summary, vec![(1, 10.0, 10.0, false), (2, 3.0, 0.0, false)]);
+
+        let report = fetch_activity_aging_report_with(&conn, Some(7), at("2024-03-21 09:00:00")).unwrap();
+        assert_eq!(report.over_threshold_count, 1);
+    }
+
+    #[test]
+    fn pauses_accumulate_and_are_excluded_from_active_duration() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网");
+        conn.execute("INSERT INTO project_activities (project_id, name, status) VALUES (?1, '首页设计', '未激活')", [project]).unwrap();
+        let load = |conn: &Connection| conn.query_row(
+            &format!("SELECT {} FROM project_activities a WHERE a.id = 1", ACTIVITY_COLUMNS), [], activity_from_row
+        ).unwrap();
+
+        activate_activity_with(&conn, 1, "2024-03-01 09:00:00").unwrap();
+        pause_activity_with(&conn, 1, "2024-03-01 12:00:00").unwrap();
+        // 暂停中：有效时长停在暂停开始时
+        let paused = load(&conn);
+        assert_eq!(active_duration_seconds(&paused, at("2024-03-02 09:00:00")), Some(3 * 3600));
+        assert_eq!(paused_duration_seconds(&paused, at("2024-03-02 09:00:00")), 21 * 3600);
+
+        activate_activity_with(&conn, 1, "2024-03-02 09:00:00").unwrap();
+        let resumed = load(&conn);
+        assert_eq!(resumed.paused_seconds, 21 * 3600);
+        assert_eq!(paused_duration_seconds(&resumed, at("2024-03-02 10:00:00")), 21 * 3600);
+        assert_eq!(active_duration_seconds(&resumed, at("2024-03-02 10:00:00")), Some(4 * 3600));
+
+        conn.execute("INSERT INTO project_activities (project_id, name, status) VALUES (?1, '未开始', '未激活')", [project]).unwrap();
+        let not_started = conn.query_row(
+            &format!("SELECT {} FROM project_activities a WHERE a.id = 2", ACTIVITY_COLUMNS), [], activity_from_row
+        ).unwrap();
+        assert_eq!(active_duration_seconds(&not_started, at("2024-03-02 10:00:00")), None);
+    }
+
+    #[test]
+    fn private_events_and_notes_stay_out_of_summaries() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO events (id, title, event_date, is_private) VALUES (1, '私下谈话', '2024-03-02 10:00:00', 1)", []).unwrap();
+        insert_log(&conn, "create", "event", "新增事件 私下谈话", "2024-03-02 10:00:00");
+        insert_log(&conn, "create", "contact", "新增联系人 张三", "2024-03-02 11:00:00");
+        conn.execute(
+            "INSERT INTO day_notes (note_date, content, is_private) VALUES ('2024-03-02', '不便公开的想法', 1), ('2024-03-02', '今天很顺利', 0)",
+            [],
+        ).unwrap();
+
+        let (_, content, statistics) = render_summary_with(&conn, "daily", "2024-03-02", "2024-03-02").unwrap();
+        assert!(!content.contains("私下谈话"));
+        assert!(!content.contains("不便公开的想法"));
+        assert!(content.contains("新增联系人 张三"));
+        assert!(content.contains("今天很顺利"));
+        let stats: PeriodStatistics = serde_json::from_str(&statistics).unwrap();
+        assert_eq!(stats.new_events, 0);
+
+        let event = conn.query_row(&format!("SELECT {} FROM events e WHERE e.id = 1", EVENT_COLUMNS), [], event_from_row).unwrap();
+        assert!(event.is_private);
+    }
+
+    #[test]
+    fn contact_dossier_collects_everything_linked_to_the_contact() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三'), (2, '李四')", []).unwrap();
+        conn.execute("INSERT INTO projects_contacts (project_id, contact_id, role) VALUES (?1, 1, '甲方负责人')", [project]).unwrap();
+        conn.execute(
+            "INSERT INTO events (id, title, event_date, project_id, is_private) VALUES
+             (1, '需求沟通', '2024-03-01 10:00:00', ?1, 0), (2, '私下交流', '2024-03-02 10:00:00', NULL, 1)",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (1, 1), (1, 2), (2, 1)", []).unwrap();
+        conn.execute("INSERT INTO project_activities (id, project_id, name, status) VALUES (1, ?1, '确认需求', '进行中')", [project]).unwrap();
+        conn.execute("INSERT INTO activities_contacts (activity_id, contact_id) VALUES (1, 1)", []).unwrap();
+        conn.execute(
+            "INSERT INTO contact_files (contact_id, original_name, stored_name, file_path, version) VALUES (1, '名片.png', 'v1_名片.png', '/tmp/名片.png', 1)",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO summaries (title, summary_type, start_date, end_date, content) VALUES
+             ('周报', 'weekly', '2024-03-01', '2024-03-07', '- 与张三沟通需求\n- 整理文档'), ('月报', 'monthly', '2024-03-01', '2024-03-31', '无')",
+            [],
+        ).unwrap();
+
+        let dossier = fetch_contact_dossier_with(&conn, 1).unwrap();
+        assert_eq!(dossier.contact.name, "张三");
+        assert_eq!(dossier.projects.iter().map(|p| p.role.as_deref()).collect::<Vec<_>>(), vec![Some("甲方负责人")]);
+        assert_eq!(dossier.events.len(), 1);
+        assert_eq!(dossier.events[0].project_names, vec!["官网改版"]);
+        assert_eq!(dossier.events[0].other_contacts, vec!["李四"]);
+        assert_eq!(dossier.activities.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["确认需求"]);
+        assert_eq!(dossier.files.len(), 1);
+        assert_eq!(dossier.summary_mentions.len(), 1);
+        assert_eq!(dossier.summary_mentions[0].excerpts, vec!["- 与张三沟通需求"]);
+
+        assert!(fetch_contact_dossier_with(&conn, 99).is_err());
+    }
+
+    #[test]
+    fn project_events_and_reminders_shift_together() {
+        let mut conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute(
+            "INSERT INTO events (id, title, event_date, project_id, reminder_time, reminder_triggered) VALUES
+             (1, '启动会', '2024-03-01 10:00', ?1, '2024-03-01 09:30:00', 1),
+             (2, '评审', '2024-03-15', ?1, NULL, 0),
+             (3, '验收', '2024-03-20 14:00:00', NULL, '2024-03-20 13:00:00', 0)",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO events_projects (event_id, project_id) VALUES (3, ?1)", [project]).unwrap();
+
+        let result = reschedule_project_events_with(&mut conn, project, "2024-03-10", 14).unwrap();
+        assert_eq!((result.events_shifted, result.reminders_shifted), (2, 1));
+        let dates: Vec<(String, Option<String>)> = conn.prepare("SELECT event_date, reminder_time FROM events ORDER BY id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .map(|r| r.unwrap()).collect();
+        assert_eq!(dates[0], ("2024-03-01 10:00".to_string(), Some("2024-03-01 09:30:00".to_string())));
+        assert_eq!(dates[1], ("2024-03-29".to_string(), None));
+        assert_eq!(dates[2], ("2024-04-03 14:00:00".to_string(), Some("2024-04-03 13:00:00".to_string())));
+
+        let result = shift_reminders_with(&mut conn, &[1, 2], 90).unwrap();
+        assert_eq!(result.reminders_shifted, 1);
+        let (reminder, triggered): (String, i32) = conn.query_row(
+            "SELECT reminder_time, reminder_triggered FROM events WHERE id = 1", [], |row| Ok((row.get(0)?, row.get(1)?))
+        ).unwrap();
+        assert_eq!((reminder.as_str(), triggered), ("2024-03-01 11:00:00", 0));
+
+        // 任一事件不存在时整体回滚
+        assert!(shift_reminders_with(&mut conn, &[1, 99], 30).is_err());
+        let reminder: String = conn.query_row("SELECT reminder_time FROM events WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(reminder, "2024-03-01 11:00:00");
+
+        // 以 T 分隔的事件时间按原格式平移；平移量过大时报错而不是 panic
+        conn.execute("INSERT INTO events (id, title, event_date, project_id) VALUES (4, '复盘', '2024-03-22T16:30', ?1)", [project]).unwrap();
+        reschedule_project_events_with(&mut conn, project, "2024-03-21", 1).unwrap();
+        let event_date: String = conn.query_row("SELECT event_date FROM events WHERE id = 4", [], |row| row.get(0)).unwrap();
+        assert_eq!(event_date, "2024-03-23T16:30");
+        assert!(reschedule_project_events_with(&mut conn, project, "2024-03-01", i64::MAX).is_err());
+        assert!(shift_reminders_with(&mut conn, &[1], i64::MIN).is_err());
+    }
+
+    #[test]
+    fn meeting_slots_intersect_working_hours_across_timezones() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三'), (2, 'Anna')", []).unwrap();
+        set_contact_availability_with(&conn, &ContactAvailability {
+            contact_id: 2,
+            timezone: Some("UTC+1".to_string()),
+            work_start: "09:00".to_string(),
+            work_end: "18:00".to_string(),
+            work_days: vec![5, 1, 2, 3, 4, 1],
+        }).unwrap();
+        assert_eq!(get_contact_availability_with(&conn, 2).unwrap().unwrap().work_days, vec![1, 2, 3, 4, 5]);
+        conn.execute("INSERT INTO events (id, title, event_date) VALUES (1, '例会', '2024-03-04 16:00:00')", []).unwrap();
+        conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (1, 1)", []).unwrap();
+
+        let local = parse_utc_offset("+08:00").unwrap();
+        let slots = suggest_meeting_slots_with(&conn, &[1, 2], 60, "2024-03-04", "2024-03-04", local, at("2024-03-01 00:00:00")).unwrap();
+        assert_eq!(slots.iter().map(|s| s.start.as_str()).collect::<Vec<_>>(), vec!["2024-03-04 17:00"]);
+        assert_eq!(slots[0].contact_times[1].local_start, "2024-03-04 10:00");
+
+        // 周六没有共同工作时间
+        assert!(suggest_meeting_slots_with(&conn, &[1, 2], 30, "2024-03-09", "2024-03-09", local, at("2024-03-01 00:00:00")).unwrap().is_empty());
+        // 时长、范围超出上限时报错
+        assert!(suggest_meeting_slots_with(&conn, &[1, 2], i64::MAX, "2024-03-04", "2024-03-04", local, at("2024-03-01 00:00:00")).is_err());
+        assert!(suggest_meeting_slots_with(&conn, &[1, 2], 60, "2024-03-04", "2099-03-04", local, at("2024-03-01 00:00:00")).is_err());
+        assert!(suggest_meeting_slots_with(&conn, &[1, 2], 60, "2024-03-05", "2024-03-04", local, at("2024-03-01 00:00:00")).is_err());
+        assert_eq!(parse_utc_offset("-0530").map(|o| o.local_minus_utc()), Some(-(5 * 3600 + 30 * 60)));
+        assert!(parse_utc_offset("Asia/Shanghai").is_none());
+        assert!(set_contact_availability_with(&conn, &ContactAvailability {
+            contact_id: 1, timezone: None, work_start: "18:00".to_string(), work_end: "09:00".to_string(), work_days: vec![1],
+        }).is_err());
+    }
+
+    #[test]
+    fn commitments_schedule_reminders_before_due_date() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '王五')", []).unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let input = CommitmentInput {
+            commitment_id: None,
+            commitment_type: "发票".to_string(),
+            title: " 二期款发票 ".to_string(),
+            amount: Some("12000".to_string()),
+            currency: None,
+            due_date: "2024-03-10".to_string(),
+            contact_id: Some(1),
+            project_id: None,
+            remind_days_before: None,
+            notes: None,
+        };
+        let saved = save_commitment_with(&conn, &input, today).unwrap();
+        assert_eq!((saved.title.as_str(), saved.contact_name.as_deref()), ("二期款发票", Some("王五")));
+        assert_eq!(saved.reminder_at.as_deref(), Some("2024-03-07 09:00:00"));
+        assert_eq!(saved.days_until_due, 9);
+        assert!(save_commitment_with(&conn, &CommitmentInput { commitment_type: "报销".to_string(), ..input.clone() }, today).is_err());
+
+        assert!(save_commitment_with(&conn, &CommitmentInput { remind_days_before: Some(366), ..input.clone() }, today).is_err());
+        assert_eq!(commitment_reminder_at("2024-03-10", i32::MAX), None);
+
+        assert!(fetch_due_commitment_reminders_with(&conn, at("2024-03-07 08:59:00")).unwrap().is_empty());
+        // 标记为已提醒之前每次都会取到（发送失败时下次重试）
+        assert_eq!(fetch_due_commitment_reminders_with(&conn, at("2024-03-07 09:00:00")).unwrap().len(), 1);
+        assert_eq!(fetch_due_commitment_reminders_with(&conn, at("2024-03-07 09:05:00")).unwrap().len(), 1);
+        mark_commitment_reminded_with(&conn, saved.id, at("2024-03-07 09:05:00")).unwrap();
+        assert!(fetch_due_commitment_reminders_with(&conn, at("2024-03-08 09:00:00")).unwrap().is_empty());
+
+        // 推迟到期日后重新提醒
+        let moved = save_commitment_with(&conn, &CommitmentInput { commitment_id: Some(saved.id), due_date: "2024-03-20".to_string(), ..input }, today).unwrap();
+        assert!(moved.reminded_at.is_none());
+        assert_eq!(fetch_upcoming_commitments_with(&conn, None, "2024-03-15", today).unwrap().len(), 0);
+        assert_eq!(fetch_upcoming_commitments_with(&conn, None, "2024-03-31", today).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn commitment_totals_use_default_currency() {
+        // 台账按类型合计，未指定币种时使用默认币种
+        let conn = test_conn();
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, 'usd')", [crate::money::SETTING_DEFAULT_CURRENCY]).unwrap();
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let input = CommitmentInput {
+            commitment_id: None,
+            commitment_type: "付款".to_string(),
+            title: "尾款".to_string(),
+            amount: Some("1,000.25".to_string()),
+            currency: None,
+            due_date: "2024-03-10".to_string(),
+            contact_id: None,
+            project_id: None,
+            remind_days_before: None,
+            notes: None,
+        };
+        let saved = save_commitment_with(&conn, &input, today).unwrap();
+        assert_eq!(saved.amount_display.as_deref(), Some("USD 1,000.25"));
+        save_commitment_with(&conn, &CommitmentInput { amount: Some("0.75".to_string()), ..input.clone() }, today).unwrap();
+        save_commitment_with(&conn, &CommitmentInput { amount: Some("300".to_string()), currency: Some("CNY".to_string()), ..input.clone() }, today).unwrap();
+        save_commitment_with(&conn, &CommitmentInput { amount: None, ..input.clone() }, today).unwrap();
+        assert!(save_commitment_with(&conn, &CommitmentInput { amount: Some("-1".to_string()), ..input.clone() }, today).is_err());
+        let totals = fetch_commitment_totals_with(&conn, None, "2024-03-31", today).unwrap();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].count, 4);
+        assert_eq!(totals[0].totals_display, vec!["CNY 300.00", "USD 1,001.00"]);
+    }
+
+    #[test]
+    fn status_email_lists_progress_and_next_steps() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '王五')", []).unwrap();
+        conn.execute(
+            "INSERT INTO events (title, event_date, event_type, project_id) VALUES
+             ('需求评审', '2024-03-04 10:00:00', '会议', ?1),
+             ('上线发布会', '2024-03-12 15:00:00', NULL, ?1),
+             ('更早的事件', '2024-02-01 10:00:00', NULL, ?1)",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO events (title, event_date, project_id, is_private) VALUES ('私下沟通', '2024-03-05 10:00:00', ?1, 1)", [project]).unwrap();
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status, completed_at, estimated_completion_date) VALUES
+             (?1, '首页设计', '已完成', '2024-03-06 18:00:00', NULL),
+             (?1, '接口联调', '进行中', NULL, '2024-03-15')",
+            [project],
+        ).unwrap();
+        conn.execute("INSERT INTO activities_contacts (activity_id, contact_id) VALUES (2, 1)", []).unwrap();
+
+        let draft = draft_status_email_with(&conn, project, "2024-03-01", "2024-03-07").unwrap();
+        assert!(draft.subject.contains("官网改版"));
+        assert!(draft.body.contains("需求评审"));
+        assert!(draft.body.contains("首页设计"));
+        assert!(draft.body.contains("接口联调（进行中，负责人：王五"));
+        assert!(draft.body.contains("上线发布会"));
+        assert!(!draft.body.contains("更早的事件"));
+        assert!(!draft.body.contains("私下沟通"));
+        assert!(!draft.polished);
+        assert!(draft_status_email_with(&conn, project, "2024-03-07", "2024-03-01").is_err());
+        assert!(draft_status_email_with(&conn, 999, "2024-03-01", "2024-03-07").is_err());
+    }
+
+    #[test]
+    fn markdown_notes_import_as_events_with_contacts() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三'), (2, '李四'), (3, 'Alice')", []).unwrap();
+        conn.execute("INSERT INTO events (title, event_date) VALUES ('已导入的事件', '2024-05-01')", []).unwrap();
+
+        let content = "\u{feff}# 工作笔记\n\
+            - 2024-05-03 会议 与张三 讨论首页方案\n\
+            \x20 - 确认配色\n\
+            \x20 - 下周给出初稿\n\
+            - 2024/05/04 14:30 【电话】回访\n\
+            - 没有日期的一行\n\
+            - 2024-05-01 已导入的事件\n\
+            \n\
+            ## 2024-05\n\
+            \n\
+            ### 2024-05-06 10:00 【会议】需求评审\n\
+            \n\
+            - 联系人：李四、王五\n\
+            - 同时关联：其他项目\n\
+            \n\
+            > 和 Alice 一起评审\n";
+        let parsed = crate::import::parse_events_markdown(content);
+        assert_eq!(parsed.events.len(), 4);
+        assert_eq!(parsed.skipped_lines, 1);
+        assert_eq!(parsed.events[0].description.as_deref(), Some("确认配色\n下周给出初稿"));
+        assert_eq!((parsed.events[1].event_date.as_str(), parsed.events[1].event_type.as_deref(), parsed.events[1].title.as_str()),
+            ("2024-05-04 14:30", Some("电话"), "回访"));
+        assert_eq!(parsed.events[3].contact_names, vec!["李四", "王五"]);
+        assert_eq!(parsed.events[3].description.as_deref(), Some("和 Alice 一起评审"));
+
+        let preview = preview_markdown_import_with(&conn, Some(project), parsed).unwrap();
+        let rows = &preview.events;
+        assert_eq!(rows[0].imported.event_type.as_deref(), Some("会议"));
+        assert_eq!(rows[0].contact_ids, vec![1]);
+        assert_eq!(rows[0].project_id, Some(project));
+        assert!(rows[1].contact_ids.is_empty());
+        assert!(rows[2].duplicate_event_id.is_some());
+        assert_eq!(rows[3].contact_ids, vec![2, 3]);
+        assert_eq!(rows[3].missing_contacts, vec!["王五"]);
+        assert!(preview_markdown_import_with(&conn, Some(999), MarkdownEventImport::default()).is_err());
+    }
+
+    #[test]
+    fn prunable_file_versions_keep_the_latest_n() {
+        let mut conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        let other = insert_project(&conn, "其他项目");
+        for version in 1..=4 {
+            insert_project_file(&conn, project, "方案.docx", version, "2024-03-01 10:00:00");
+        }
+        insert_project_file(&conn, project, "报价.xlsx", 1, "2024-03-01 10:00:00");
+        insert_project_file(&conn, other, "方案.docx", 1, "2024-03-01 10:00:00");
+        conn.execute("UPDATE project_files SET file_size = 100", []).unwrap();
+
+        let versions = fetch_prunable_file_versions_with(&conn, project, 2).unwrap();
+        assert_eq!(versions.iter().map(|v| (v.original_name.as_str(), v.version)).collect::<Vec<_>>(), vec![("方案.docx", 1), ("方案.docx", 2)]);
+        assert_eq!(versions.iter().map(|v| v.file_size).sum::<i64>(), 200);
+        assert!(fetch_prunable_file_versions_with(&conn, project, 0).is_err());
+        assert!(fetch_prunable_file_versions_with(&conn, 999, 2).is_err());
+
+        let ids: Vec<i32> = versions.iter().map(|v| v.file_id).collect();
+        assert_eq!(delete_project_files_with(&mut conn, &ids).unwrap(), 2);
+        assert!(fetch_prunable_file_versions_with(&conn, project, 2).unwrap().is_empty());
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM project_files", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 4);
+    }
+
+    #[test]
+    fn global_search_prefers_recently_and_frequently_used_entries() {
+        let conn = test_conn();
+        let now = at("2024-03-15 12:00:00");
+        conn.execute("INSERT INTO contacts (id, name, company) VALUES (1, '王一', NULL), (2, '王二', NULL), (3, '李四', '王氏集团')", []).unwrap();
+        // 王一只在 2022 年打开过一次；王二最近每周都有互动
+        conn.execute("INSERT INTO access_log (entity_type, entity_id, accessed_at) VALUES ('contact', 1, '2022-05-01 10:00:00')", []).unwrap();
+        for (i, date) in ["2024-02-23", "2024-03-01", "2024-03-08", "2024-03-14"].iter().enumerate() {
+            conn.execute("INSERT INTO events (id, title, event_date) VALUES (?1, '周会', ?2)", rusqlite::params![i as i32 + 1, format!("{} 10:00:00", date)]).unwrap();
+            conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (?1, 2)", [i as i32 + 1]).unwrap();
+        }
+        record_access_with(&conn, "contact", 2, at("2024-03-15 09:00:00")).unwrap();
+        record_access_with(&conn, "contact", 2, at("2024-03-15 09:05:00")).unwrap();
+        let logged: i64 = conn.query_row("SELECT COUNT(*) FROM access_log WHERE entity_id = 2", [], |row| row.get(0)).unwrap();
+        assert_eq!(logged, 1);
+        assert!(record_access_with(&conn, "summary", 1, now).is_err());
+
+        let hits = search_global_with(&conn, "王", false, now, &|| false).unwrap();
+        assert_eq!(hits.iter().map(|h| h.entity_id).collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert_eq!(hits[0].uses, 5);
+        assert_eq!(hits[0].last_used_at.as_deref(), Some("2024-03-15 09:00:00"));
+        assert!(search_global_with(&conn, " ", false, now, &|| false).unwrap().is_empty());
+
+        // 只看匹配程度时按名称排序
+        conn.execute("INSERT INTO settings (key, value) VALUES (?1, '{\"recency_weight\": 0, \"frequency_weight\": 0}')", [crate::search::SETTING_SEARCH_RANKING]).unwrap();
+        let hits = search_global_with(&conn, "王", false, now, &|| false).unwrap();
+        assert_eq!(hits.iter().map(|h| h.entity_id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(crate::search::parse_ranking("{\"recency_half_life_days\": 0}").is_err());
+        assert!(crate::search::parse_ranking("{\"frequency_window_days\": 100000000}").is_err());
+        assert!(crate::search::parse_ranking("{\"frequency_window_days\": 3650}").is_ok());
+    }
+
+    #[test]
+    fn search_hits_carry_match_snippets() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO events (id, title, description, event_date) VALUES (1, '周会', '讨论预算和排期', '2024-03-01 10:00:00')", []).unwrap();
+        conn.execute("INSERT INTO events (id, title, description, event_date, is_private) VALUES (2, '预算私下沟通', NULL, '2024-03-02 10:00:00', 1)", []).unwrap();
+        let hits = search_global_with(&conn, "预算", false, at("2024-03-15 12:00:00"), &|| false).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].entity_type.as_str(), hits[0].subtitle.as_deref()), ("event", Some("2024-03-01 10:00")));
+        assert_eq!(hits[0].highlights.len(), 1);
+        assert_eq!(hits[0].highlights[0].field, "description");
+        assert_eq!(hits[0].highlights[0].ranges, vec![(2, 4)]);
+    }
+
+    #[test]
+    fn list_query_composes_filters_sort_and_paging() {
+        let query = ListQuery::new("SELECT id FROM events e")
+            .condition("e.is_private = 0")
+            .filter_opt("e.event_type = ?", None::<String>)
+            .filter("e.project_id = ? OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?)", 3)
+            .filter_opt("e.title LIKE ?", Some("%周%".to_string()))
+            .order_by("e.event_date DESC")
+            .order_by("e.id");
+        assert_eq!(
+            query.sql(),
+            "SELECT id FROM events e WHERE (e.is_private = 0) AND (e.project_id = ?1 OR e.id IN (SELECT event_id FROM events_projects WHERE project_id = ?1)) AND (e.title LIKE ?2) ORDER BY e.event_date DESC, e.id"
+        );
+        assert_eq!(query.params().len(), 2);
+        assert_eq!(ListQuery::new("SELECT id FROM events").sql(), "SELECT id FROM events");
+        // 引号内的 ? 不是占位符
+        let quoted = ListQuery::new("SELECT id FROM events e")
+            .filter("e.title != 'why?' AND e.id = ?", 1)
+            .filter("\"e\".\"title?\" = ? OR e.title = 'it''s ?'", "周会".to_string());
+        assert_eq!(
+            quoted.sql(),
+            "SELECT id FROM events e WHERE (e.title != 'why?' AND e.id = ?1) AND (\"e\".\"title?\" = ?2 OR e.title = 'it''s ?')"
+        );
+
+        let conn = test_conn();
+        for (i, title) in ["周会", "月会", "周报", "周末团建", "私下周聊"].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO events (id, title, event_date, is_private) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![i as i32 + 1, title, format!("2024-03-0{} 10:00:00", i + 1), (i == 4) as i32],
+            ).unwrap();
+        }
+        let page = |n: i64| ListQuery::new("SELECT title FROM events e")
+            .condition("e.is_private = 0")
+            .filter_opt("e.title LIKE ?", Some("周%".to_string()))
+            .order_by("e.event_date DESC")
+            .page(n, 2)
+            .fetch(&conn, |row| row.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(page(0), vec!["周末团建", "周报"]);
+        assert_eq!(page(1), vec!["周会"]);
+        assert!(page(2).is_empty());
+
+        // 改用查询构造后，原有列表函数的筛选结果不变
+        let project_id = insert_project(&conn, "官网改版");
+        conn.execute("INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, description, created_at) VALUES ('create', 'activity', 1, '设计稿', ?1, '新增活动', '2024-03-02 09:00:00')", [project_id]).unwrap();
+        conn.execute("INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, project_id, description, created_at) VALUES ('update', 'activity', 1, '设计稿', ?1, '完成活动', '2024-03-05 09:00:00')", [project_id]).unwrap();
+        conn.execute("INSERT INTO events_projects (event_id, project_id) VALUES (2, ?1)", [project_id]).unwrap();
+        conn.execute("INSERT INTO operation_logs (operation_type, entity_type, entity_id, entity_name, description, created_at) VALUES ('create', 'event', 2, '月会', '新增事件', '2024-03-03 09:00:00')", []).unwrap();
+        let logs = fetch_project_log_with(&conn, project_id, Some("2024-03-03"), None).unwrap();
+        assert_eq!(logs.iter().map(|l| l.description.as_str()).collect::<Vec<_>>(), vec!["完成活动", "新增事件"]);
+        assert_eq!(fetch_project_log_with(&conn, project_id, None, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn delete_project_removes_everything_owned_by_the_project() {
+        let mut conn = test_conn();
+        let project_id = insert_project(&conn, "官网改版");
+        let other_id = insert_project(&conn, "品牌升级");
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三')", []).unwrap();
+        conn.execute("INSERT INTO projects_contacts (project_id, contact_id) VALUES (?1, 1), (?2, 1)", [project_id, other_id]).unwrap();
+        // 1 只属于本项目；2 同时关联另一个项目；3 属于另一个项目
+        conn.execute(
+            "INSERT INTO events (id, title, event_date, project_id) VALUES (1, '需求评审', '2024-03-01 10:00:00', ?1), (2, '联合评审', '2024-03-02 10:00:00', ?1), (3, '品牌会', '2024-03-03 10:00:00', ?2)",
+            [project_id, other_id],
+        ).unwrap();
+        conn.execute("INSERT INTO events_projects (event_id, project_id) VALUES (1, ?1), (2, ?1), (2, ?2), (3, ?2)", [project_id, other_id]).unwrap();
+        conn.execute("INSERT INTO events_contacts (event_id, contact_id) VALUES (1, 1), (2, 1)", []).unwrap();
+        conn.execute("INSERT INTO project_activities (id, project_id, name) VALUES (1, ?1, '设计稿')", [project_id]).unwrap();
+        conn.execute("INSERT INTO activities_contacts (activity_id, contact_id) VALUES (1, 1)", []).unwrap();
+        insert_project_file(&conn, project_id, "方案.md", 1, "2024-03-01 10:00:00");
+        conn.execute("INSERT INTO entity_links (source_type, source_id, target_type, target_id) VALUES ('project', ?1, 'contact', 1), ('event', 1, 'event', 3)", [project_id]).unwrap();
+
+        let deletion = delete_project_with(&mut conn, project_id).unwrap();
+        assert_eq!(deletion.name, "官网改版");
+        assert_eq!((deletion.events_deleted, deletion.events_unlinked), (1, 1));
+        assert_eq!((deletion.activities_deleted, deletion.files_deleted, deletion.contacts_unlinked), (1, 1, 1));
+        assert_eq!(deletion.deleted_event_ids, vec![1]);
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM projects"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM events"), 2);
+        assert_eq!(count("SELECT project_id FROM events WHERE id = 2"), other_id as i64);
+        assert_eq!(count("SELECT COUNT(*) FROM events_projects WHERE event_id = 2"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM events_contacts"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM project_activities") + count("SELECT COUNT(*) FROM activities_contacts"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM project_files") + count("SELECT COUNT(*) FROM entity_links"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM projects_contacts"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM operation_logs WHERE operation_type = 'delete' AND entity_type = 'project'"), 1);
+
+        assert!(delete_project_with(&mut conn, project_id).is_err());
+    }
+
+    #[test]
+    fn change_feed_records_changes_for_subscribers() {
+        let conn = test_conn();
+        take_changes_with(&conn).unwrap();
+        let project_id = insert_project(&conn, "官网改版");
+        conn.execute("UPDATE projects SET description = '第二期' WHERE id = ?1", [project_id]).unwrap();
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三')", []).unwrap();
+        conn.execute("INSERT INTO projects_contacts (project_id, contact_id) VALUES (?1, 1)", [project_id]).unwrap();
+        conn.execute("DELETE FROM contacts WHERE id = 1", []).unwrap();
+
+        let changes = take_changes_with(&conn).unwrap();
+        let summary: Vec<(&str, i64, &str)> = changes.iter()
+            .map(|c| (c.entity_type.as_str(), c.entity_id, c.op.as_str()))
+            .collect();
+        let project_id = project_id as i64;
+        // 删除联系人时关联随之级联删除，记为项目的 update（与关联时的 update 相邻，合并为一条）
+        assert_eq!(summary, vec![
+            ("project", project_id, "insert"),
+            ("project", project_id, "update"),
+            ("contact", 1, "insert"),
+            ("project", project_id, "update"),
+            ("contact", 1, "delete"),
+        ]);
+        assert!(changes.windows(2).all(|w| w[0].id < w[1].id));
+        assert!(take_changes_with(&conn).unwrap().is_empty());
+
+        let projects_only = crate::changes::subscribe(&["project".to_string()]).unwrap();
+        let everything = crate::changes::subscribe(&[]).unwrap();
+        assert!(crate::changes::subscribe(&["database".to_string()]).is_err());
+        let batches: Vec<(u64, usize)> = crate::changes::dispatch(&changes).iter()
+            .filter(|b| b.subscription_id == projects_only || b.subscription_id == everything)
+            .map(|b| (b.subscription_id, b.changes.len()))
+            .collect();
+        assert_eq!(batches, vec![(projects_only, 3), (everything, 5)]);
+        assert!(crate::changes::unsubscribe(projects_only));
+        assert!(!crate::changes::unsubscribe(projects_only));
+        assert!(crate::changes::unsubscribe(everything));
+    }
+
+    #[test]
+    fn archived_projects_are_hidden_unless_requested() {
+        let conn = test_conn();
+        let active = insert_project(&conn, "官网改版");
+        let finished = insert_project(&conn, "年会筹备");
+        let names = |include_archived: bool| -> Vec<String> {
+            let mut names: Vec<String> = fetch_projects_with(&conn, include_archived).unwrap().into_iter().map(|p| p.name).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(set_project_archived_with(&conn, finished, true).unwrap(), "年会筹备");
+        assert_eq!(names(false), vec!["官网改版"]);
+        assert_eq!(names(true), vec!["官网改版", "年会筹备"]);
+        let archived = fetch_projects_with(&conn, true).unwrap().into_iter().find(|p| p.id == finished).unwrap();
+        assert!(archived.archived && archived.archived_at.is_some());
+        // 归档时自动生成复盘，再次归档时更新原复盘
+        let retrospectives = || -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM summaries WHERE summary_type = 'retrospective' AND json_extract(statistics, '$.project_id') = ?1", [finished], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(retrospectives(), 1);
+
+        // 重复归档不再记录日志
+        set_project_archived_with(&conn, finished, true).unwrap();
+        set_project_archived_with(&conn, finished, false).unwrap();
+        assert_eq!(names(false), vec!["官网改版", "年会筹备"]);
+        let logs: i64 = conn.query_row("SELECT COUNT(*) FROM operation_logs WHERE entity_type = 'project' AND entity_id = ?1", [finished], |row| row.get(0)).unwrap();
+        assert_eq!(logs, 2);
+        assert_eq!(retrospectives(), 1);
+        assert!(!fetch_projects_with(&conn, false).unwrap().iter().any(|p| p.archived));
+        assert!(set_project_archived_with(&conn, active + finished + 1, true).is_err());
+    }
+
+    #[test]
+    fn adding_contacts_to_a_project_keeps_existing_roles() {
+        let conn = test_conn();
+        let project = insert_project(&conn, "官网改版");
+        conn.execute("INSERT INTO contacts (name) VALUES ('张三'), ('李四')", []).unwrap();
+        conn.execute("INSERT INTO projects_contacts (project_id, contact_id, role, notes) VALUES (?1, 1, '设计', '负责首页')", [project]).unwrap();
+
+        assert_eq!(add_contacts_to_project_with(&conn, project, &[1, 2]).unwrap(), 1);
+        let members: Vec<(i32, Option<String>, Option<String>)> = conn.prepare(
+            "SELECT contact_id, role, notes FROM projects_contacts WHERE project_id = ?1 ORDER BY contact_id"
+        ).unwrap().query_map([project], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(members, vec![(1, Some("设计".to_string()), Some("负责首页".to_string())), (2, None, None)]);
+        assert_eq!(add_contacts_to_project_with(&conn, project, &[1, 2]).unwrap(), 0);
+    }
+
+    #[test]
+    fn archived_projects_are_left_out_of_cross_project_lists() {
+        let conn = test_conn();
+        let active = insert_project(&conn, "官网改版");
+        let finished = insert_project(&conn, "年会筹备");
+        conn.execute(
+            "INSERT INTO events (title, event_date, project_id) VALUES
+             ('官网评审', '2024-03-02 10:00', ?1), ('年会评审', '2024-03-03 10:00', ?2), ('个人评审', '2024-03-04 10:00', NULL)",
+            [active, finished],
+        ).unwrap();
+        insert_project_file(&conn, active, "评审纪要.docx", 1, "2024-03-02 11:00:00");
+        insert_project_file(&conn, finished, "评审名单.xlsx", 1, "2024-03-03 11:00:00");
+        set_project_archived_with(&conn, finished, true).unwrap();
+
+        let titles = |include_archived: bool| -> Vec<String> {
+            fetch_all_events_with(&conn, include_archived).unwrap().into_iter().map(|e| e.title).collect()
+        };
+        assert_eq!(titles(false), vec!["个人评审", "官网评审"]);
+        assert_eq!(titles(true), vec!["个人评审", "年会评审", "官网评审"]);
+
+        let now = at("2024-03-10 12:00:00");
+        let hits = |keyword: &str, include_archived: bool| -> Vec<String> {
+            let mut titles: Vec<String> = search_global_with(&conn, keyword, include_archived, now, &|| false).unwrap()
+                .into_iter().map(|h| h.title).collect();
+            titles.sort();
+            titles
+        };
+        assert_eq!(hits("评审", false), vec!["个人评审", "官网评审", "评审纪要.docx"]);
+        assert_eq!(hits("评审", true), vec!["个人评审", "官网评审", "年会评审", "评审名单.xlsx", "评审纪要.docx"]);
+        assert!(hits("年会", false).is_empty());
+        assert_eq!(hits("年会", true), vec!["年会筹备", "年会评审"]);
+    }
+
+    #[test]
+    fn project_status_follows_allowed_transitions() {
+        let conn = test_conn();
+        let project_id = insert_project(&conn, "官网改版");
+        let status = || fetch_projects_with(&conn, true).unwrap()[0].status.clone();
+        assert_eq!(status(), "进行中");
+
+        set_project_status_with(&conn, project_id, "已搁置").unwrap();
+        set_project_status_with(&conn, project_id, "已完成").unwrap();
+        assert_eq!(status(), "已完成");
+        // 已完成只能重新开始
+        assert!(set_project_status_with(&conn, project_id, "规划中").is_err());
+        assert!(set_project_status_with(&conn, project_id, "已取消").is_err());
+        set_project_status_with(&conn, project_id, "已完成").unwrap();
+        set_project_status_with(&conn, project_id, "进行中").unwrap();
+        assert_eq!(status(), "进行中");
+
+        let mut stmt = conn.prepare("SELECT old_value, new_value FROM operation_logs WHERE entity_type = 'project' AND entity_id = ?1 ORDER BY id").unwrap();
+        let changes: Vec<(String, String)> = stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(changes, vec![
+            ("进行中".to_string(), "已搁置".to_string()),
+            ("已搁置".to_string(), "已完成".to_string()),
+            ("已完成".to_string(), "进行中".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn triggers_maintain_updated_at_and_log_deletes() {
+        let conn = test_conn();
+        conn.execute("INSERT INTO contacts (id, name, updated_at) VALUES (1, '张三', '2020-01-01 00:00:00')", []).unwrap();
+        conn.execute("UPDATE contacts SET title = '经理' WHERE id = 1", []).unwrap();
+        let updated_at: String = conn.query_row("SELECT updated_at FROM contacts WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert!(updated_at.as_str() > "2020-01-01 00:00:00");
+        // 显式写入的 updated_at 保持不变
+        conn.execute("UPDATE contacts SET title = '总监', updated_at = '2021-06-01 08:00:00' WHERE id = 1", []).unwrap();
+        let updated_at: String = conn.query_row("SELECT updated_at FROM contacts WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(updated_at, "2021-06-01 08:00:00");
+
+        let project_id = insert_project(&conn, "官网改版");
+        conn.execute(
+            "INSERT INTO events (id, title, event_date, project_id, is_private) VALUES (1, '需求评审', '2024-03-01 10:00:00', ?1, 0), (2, '体检', '2024-03-02 10:00:00', NULL, 1)",
+            [project_id],
+        ).unwrap();
+        conn.execute("DELETE FROM events", []).unwrap();
+        let mut stmt = conn.prepare("SELECT entity_id, entity_name, project_name, description FROM operation_logs WHERE operation_type = 'delete' AND entity_type = 'event' ORDER BY entity_id").unwrap();
+        let logs: Vec<(i32, String, Option<String>, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(logs.len(), 2);
+        assert_eq!((logs[0].1.as_str(), logs[0].2.as_deref()), ("需求评审", Some("官网改版")));
+        assert!(logs[0].3.ends_with("，删除事件「需求评审」"));
+        assert_eq!(logs[1].1, "私密事件");
+        assert!(!logs[1].3.contains("体检"));
+
+        // 代码中已写删除日志的不重复记录
+        delete_empty_project_with(&conn, project_id).unwrap();
+        let project_logs: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM operation_logs WHERE operation_type = 'delete' AND entity_type = 'project' AND entity_id = ?1",
+            [project_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(project_logs, 1);
+    }
+
+    #[test]
+    fn org_chart_nests_members_by_reporting_line() {
+        let conn = test_conn();
+        let project_id = insert_project(&conn, "官网改版");
+        conn.execute(
+            "INSERT INTO contacts (id, name, title, company) VALUES (1, '王总', '总经理', '甲方'), (2, '李经理', '产品经理', '甲方'), (3, '小赵', '设计师', '甲方'), (4, '陈工', '顾问', ' ')",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO projects_contacts (project_id, contact_id, role) VALUES (?1, 1, '决策人'), (?1, 2, '对接人'), (?1, 3, NULL), (?1, 4, '技术顾问')",
+            [project_id],
+        ).unwrap();
+        set_project_reports_to_with(&conn, project_id, 2, Some(1)).unwrap();
+        set_project_reports_to_with(&conn, project_id, 3, Some(2)).unwrap();
+        assert!(set_project_reports_to_with(&conn, project_id, 1, Some(3)).is_err());
+        assert!(set_project_reports_to_with(&conn, project_id, 1, Some(1)).is_err());
+        assert!(set_project_reports_to_with(&conn, project_id, 1, Some(99)).is_err());
+
+        let chart = fetch_project_org_chart_with(&conn, project_id).unwrap();
+        assert_eq!(chart.member_count, 4);
+        let roots: Vec<i32> = chart.roots.iter().map(|n| n.contact_id).collect();
+        assert_eq!(roots, vec![1, 4]);
+        let boss = &chart.roots[0];
+        assert_eq!(boss.role.as_deref(), Some("决策人"));
+        assert_eq!(boss.children[0].contact_id, 2);
+        assert_eq!(boss.children[0].children[0].contact_id, 3);
+        assert_eq!(chart.companies.len(), 1);
+        assert_eq!((chart.companies[0].company.as_str(), chart.companies[0].contact_ids.clone()), ("甲方", vec![1, 2, 3]));
+
+        // 上级被移出项目后，下属成为根
+        conn.execute("DELETE FROM projects_contacts WHERE project_id = ?1 AND contact_id = 2", [project_id]).unwrap();
+        let chart = fetch_project_org_chart_with(&conn, project_id).unwrap();
+        let roots: Vec<i32> = chart.roots.iter().map(|n| n.contact_id).collect();
+        assert_eq!(roots, vec![1, 3, 4]);
+        assert!(fetch_project_org_chart_with(&conn, 999).is_err());
+    }
+
+    #[test]
+    fn duplicate_project_copies_members_and_resets_activities() {
+        let mut conn = test_conn();
+        let project_id = insert_project(&conn, "年度审计");
+        conn.execute("UPDATE projects SET color = '#ff0000', description = '每年一次' WHERE id = ?1", [project_id]).unwrap();
+        conn.execute("INSERT INTO contacts (id, name) VALUES (1, '张三'), (2, '李四')", []).unwrap();
+        conn.execute(
+            "INSERT INTO projects_contacts (project_id, contact_id, role, reports_to) VALUES (?1, 1, '负责人', NULL), (?1, 2, '审计员', 1)",
+            [project_id],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO project_activities (project_id, name, status, activated_at, completed_at, estimated_hours)
+             VALUES (?1, '现场盘点', '已完成', '2024-03-01 09:00:00', '2024-03-02 18:00:00', 8), (?1, '出具报告', '进行中', '2024-03-03 09:00:00', NULL, NULL)",
+            [project_id],
+        ).unwrap();
+        conn.execute("INSERT INTO activities_contacts (activity_id, contact_id) SELECT id, 2 FROM project_activities", []).unwrap();
+        insert_project_file(&conn, project_id, "底稿.xlsx", 1, "2024-03-01 10:00:00");
+
+        let duplicate = duplicate_project_with(&mut conn, project_id, None).unwrap();
+        assert_eq!(duplicate.name, "年度审计 副本");
+        assert_eq!((duplicate.contacts_copied, duplicate.activities_copied, duplicate.files_copied), (2, 2, 0));
+        let new_id = duplicate.project_id;
+        let (color, description): (Option<String>, Option<String>) = conn.query_row(
+            "SELECT color, description FROM projects WHERE id = ?1", [new_id], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((color.as_deref(), description.as_deref()), (Some("#ff0000"), Some("每年一次")));
+        let reports_to: Option<i32> = conn.query_row(
+            "SELECT reports_to FROM projects_contacts WHERE project_id = ?1 AND contact_id = 2", [new_id], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(reports_to, Some(1));
+
+        {
+            let mut stmt = conn.prepare(
+                "SELECT name, status, activated_at IS NULL AND completed_at IS NULL, estimated_hours FROM project_activities WHERE project_id = ?1 ORDER BY id",
+            ).unwrap();
+            let activities: Vec<(String, String, bool, Option<f64>)> = stmt.query_map([new_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            assert_eq!(activities, vec![
+                ("现场盘点".to_string(), "待分配".to_string(), true, Some(8.0)),
+                ("出具报告".to_string(), "待分配".to_string(), true, None),
+            ]);
+            let count = |sql: &str| -> i64 { conn.query_row(sql, [new_id], |row| row.get(0)).unwrap() };
+            assert_eq!(count("SELECT COUNT(*) FROM activities_contacts ac JOIN project_activities a ON a.id = ac.activity_id WHERE a.project_id = ?1"), 0);
+            assert_eq!(count("SELECT COUNT(*) FROM project_files WHERE project_id = ?1"), 0);
+        }
+
+        let named = duplicate_project_with(&mut conn, project_id, Some("  2025 年度审计 ")).unwrap();
+        assert_eq!(named.name, "2025 年度审计");
+        assert!(duplicate_project_with(&mut conn, 999, None).is_err());
+    }
+
+    #[test]
+    fn template_packs_install_event_types_and_activity_templates() {
+        let mut conn = test_conn();
+        for pack in TEMPLATE_PACKS {
+            for (_, _, _, recurrence) in pack.activities {
+                assert!(recurrence.is_none_or(|r| parse_recurrence(r).is_some()));
+            }
+        }
+
+        let installed = install_template_pack_with(&mut conn, "销售跟进", None).unwrap();
+        assert!(installed.project_created);
+        assert_eq!((installed.event_types_created, installed.activity_templates_created), (3, 7));
+        let project_name: String = conn.query_row("SELECT name FROM projects WHERE id = ?1", [installed.project_id], |row| row.get(0)).unwrap();
+        assert_eq!(project_name, "销售跟进");
+        let recurrence: Option<String> = conn.query_row(
+            "SELECT recurrence FROM activity_templates WHERE project_id = ?1 AND name = '每周客户跟进'",
+            [installed.project_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(recurrence.as_deref(), Some("weekly:1"));
+
+        // 再次安装到同一项目时不重复创建
+        let again = install_template_pack_with(&mut conn, "销售跟进", Some(installed.project_id)).unwrap();
+        assert!(!again.project_created);
+        assert_eq!((again.event_types_created, again.activity_templates_created), (0, 0));
+
+        let project_id = insert_project(&conn, "校园招聘");
+        let hiring = install_template_pack_with(&mut conn, "招聘流程", Some(project_id)).unwrap();
+        assert_eq!((hiring.project_id, hiring.activity_templates_created), (project_id, 7));
+
+        assert!(install_template_pack_with(&mut conn, "年会筹备", None).is_err());
+        assert!(install_template_pack_with(&mut conn, "咨询项目", Some(999)).is_err());
+    }
+
+    #[test]
+    fn activity_template_due_offsets_are_bounded() {
+        assert!(validate_template_schedule(Some(0), Some("weekly:1")).is_ok());
+        assert!(validate_template_schedule(Some(MAX_TEMPLATE_DUE_OFFSET_DAYS), None).is_ok());
+        assert!(validate_template_schedule(Some(-1), None).is_err());
+        assert!(validate_template_schedule(Some(i32::MAX), None).is_err());
+        assert!(validate_template_schedule(None, Some("yearly")).is_err());
+
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(template_due_date(day, 30).unwrap(), "2024-03-01");
+        // 旧数据中的超大天数报错而不是 panic
+        assert!(template_due_date(day, i32::MAX).is_err());
+    }
+
+    #[test]
+    fn backups_are_written_rotated_and_found_for_recovery() {
+        let dir = std::env::temp_dir().join(format!("memorystack-backup-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join(DB_FILE_NAME);
+        let conn = test_conn();
+        insert_project(&conn, "备份项目");
+
+        for day in 1..=(BACKUP_KEEP_COUNT as u32 + 2) {
+            backup_database_with(&conn, &db_path, at(&format!("2024-03-{:02} 03:00:00", day))).unwrap();
+        }
+        let names: Vec<String> = std::fs::read_dir(dir.join(BACKUP_DIR_NAME)).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), BACKUP_KEEP_COUNT);
+        assert!(!names.contains(&"memorystack-20240301-030000.db".to_string()));
+
+        // 备份可以直接用于启动恢复
+        let latest = find_latest_backup(&db_path).unwrap();
+        let restored = Connection::open(&latest).unwrap();
+        let name: String = restored.query_row("SELECT name FROM projects", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "备份项目");
+        drop(restored);
+
+        // 只有内容损坏才视为损坏，打不开的文件原样报告
+        let garbage = dir.join("garbage.db");
+        std::fs::write(&garbage, "这不是数据库文件".repeat(200)).unwrap();
+        assert!(matches!(probe_database(&garbage), ProbeResult::Corrupt(_)));
+        assert!(matches!(probe_database(&dir.join("missing").join(DB_FILE_NAME)), ProbeResult::Error(_)));
+        assert!(matches!(probe_database(&latest), ProbeResult::Ok));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn contact_import_rolls_back_when_any_decision_fails() {
+        let mut conn = test_conn();
+        let imported = |name: &str| ImportedContact { name: name.to_string(), ..Default::default() };
+        let decision = |name: &str, action: &str, target: Option<i32>| ContactImportDecision {
+            imported: imported(name),
+            action: action.to_string(),
+            target_contact_id: target,
+        };
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM contacts", [], |row| row.get(0)).unwrap()
+        };
+
+        // 未知操作和不存在的合并目标都报错，前面已创建的联系人一并回滚
+        assert!(commit_contact_import_with(&mut conn, &[decision("张三", "create", None), decision("李四", "delete", None)]).is_err());
+        assert!(commit_contact_import_with(&mut conn, &[decision("张三", "create", None), decision("李四", "merge", Some(999))]).is_err());
+        assert_eq!(count(&conn), 0);
+
+        let result = commit_contact_import_with(&mut conn, &[decision("张三", "create", None), decision("李四", "skip", None)]).unwrap();
+        assert_eq!((result.created, result.merged, result.skipped), (1, 0, 1));
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn notion_import_rolls_back_events_when_a_contact_fails() {
+        let mut conn = test_conn();
+        let event_row = |title: &str| EventImportPreviewRow {
+            imported: ImportedEvent {
+                title: title.to_string(),
+                description: None,
+                event_date: "2024-05-01".to_string(),
+                event_type: None,
+                project_name: None,
+                contact_names: Vec::new(),
+            },
+            project_id: None,
+            missing_project: false,
+            contact_ids: Vec::new(),
+            missing_contacts: Vec::new(),
+            duplicate_event_id: None,
+        };
+        let mut preview = NotionImportPreview {
+            events: vec![event_row("启动会")],
+            contacts: vec![ContactImportPreviewRow {
+                row_index: 0,
+                imported: ImportedContact { name: "王五".to_string(), ..Default::default() },
+                matches: Vec::new(),
+                suggested_action: "merge".to_string(),
+                suggested_target_id: Some(999),
+            }],
+            skipped_rows: 0,
+            committed_events: 0,
+            committed_contacts: None,
+        };
+        let count = |conn: &Connection, table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+
+        assert!(commit_notion_import_with(&mut conn, &mut preview).is_err());
+        assert_eq!((count(&conn, "events"), count(&conn, "operation_logs"), preview.committed_events), (0, 0, 0));
+
+        preview.contacts[0].suggested_action = "create".to_string();
+        commit_notion_import_with(&mut conn, &mut preview).unwrap();
+        assert_eq!((count(&conn, "events"), count(&conn, "contacts"), preview.committed_events), (1, 1, 1));
+    }
+
+    #[test]
+    fn demo_data_is_seeded_all_or_nothing() {
+        let mut conn = test_conn();
+        let count = |conn: &Connection, table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+
+        // 中途失败时已插入的联系人和项目一并回滚
+        conn.execute_batch("CREATE TRIGGER fail_activity BEFORE INSERT ON project_activities BEGIN SELECT RAISE(ABORT, '写入失败'); END;").unwrap();
+        assert!(seed_demo_data_with(&mut conn).is_err());
+        assert_eq!((count(&conn, "contacts"), count(&conn, "projects"), count(&conn, "events")), (0, 0, 0));
+
+        conn.execute_batch("DROP TRIGGER fail_activity").unwrap();
+        let summary = seed_demo_data_with(&mut conn).unwrap();
+        assert_eq!(count(&conn, "projects"), summary.projects as i64);
+        assert_eq!(count(&conn, "project_activities"), summary.activities as i64);
+        // 已有数据时拒绝再次生成
+        assert!(seed_demo_data_with(&mut conn).is_err());
+        assert_eq!(count(&conn, "projects"), summary.projects as i64);
+    }
+}
@@ -0,0 +1,176 @@
+// src-tauri/src/diff.rs
+// 文本文件版本对比：按行计算差异（最长公共子序列），输出 unified diff 格式（与 git diff / diff -u 相同），
+// 只处理文本类文件，文件过大或差异区域过大时不计算
+use serde::{Deserialize, Serialize};
+
+// 可以对比的文件扩展名
+pub const TEXT_DIFF_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "csv"];
+
+// 参与对比的单个文件的最大字节数
+pub const MAX_DIFF_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+// 去掉首尾相同的行后，剩余部分的行数乘积上限（控制 LCS 表的内存）
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+// 每个差异块前后保留的上下文行数
+const CONTEXT_LINES: usize = 3;
+
+// 两个版本的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub file_id_a: i32,
+    pub file_id_b: i32,
+    pub label_a: String,   // 如 "方案.md v3"
+    pub label_b: String,
+    pub unified: String,   // unified diff 文本，内容相同时为空
+    pub added: usize,      // 新增行数
+    pub removed: usize,    // 删除行数
+    pub identical: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+// 扩展名是否为可对比的文本文件
+pub fn is_text_file(name: &str) -> bool {
+    std::path::Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| TEXT_DIFF_EXTENSIONS.contains(&e.as_str()))
+}
+
+// 把文件内容解码为文本（去掉 UTF-8 BOM，不是 UTF-8 时报错）
+pub fn decode_text(bytes: Vec<u8>, name: &str) -> Result<String, String> {
+    let text = String::from_utf8(bytes).map_err(|_| format!("不是 UTF-8 编码的文本文件: {}", name))?;
+    Ok(text.strip_prefix('\u{feff}').map(|t| t.to_string()).unwrap_or(text))
+}
+
+// 逐行对比，返回每一行的操作（Equal 同时消耗两边各一行）
+fn diff_ops(a: &[&str], b: &[&str]) -> Result<Vec<Op>, String> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let middle_a = &a[prefix..a.len() - suffix];
+    let middle_b = &b[prefix..b.len() - suffix];
+    let (n, m) = (middle_a.len(), middle_b.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        return Err(format!("差异过大，无法对比（{} 行 / {} 行有改动）", n, m));
+    }
+
+    // lcs[i][j]：middle_a[i..] 与 middle_b[j..] 的最长公共子序列长度
+    let width = m + 1;
+    let mut lcs = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * width + j] = if middle_a[i] == middle_b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![Op::Equal; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && middle_a[i] == middle_b[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            // 同一位置的改动先列出删除的行，再列出新增的行
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    Ok(ops)
+}
+
+// 差异块头部的行号范围（unified 格式：空范围的起始行为前一行）
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+// 生成 unified diff（内容相同时为空字符串）
+pub fn unified_diff(old: &str, new: &str, label_old: &str, label_new: &str) -> Result<(String, usize, usize), String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&a, &b)?;
+    let added = ops.iter().filter(|op| **op == Op::Insert).count();
+    let removed = ops.iter().filter(|op| **op == Op::Delete).count();
+    if added == 0 && removed == 0 {
+        return Ok((String::new(), 0, 0));
+    }
+
+    // 每个操作对应的旧/新行号
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut ai, mut bi) = (0, 0);
+    for op in &ops {
+        positions.push((ai, bi));
+        match op {
+            Op::Equal => { ai += 1; bi += 1; }
+            Op::Delete => ai += 1,
+            Op::Insert => bi += 1,
+        }
+    }
+
+    // 把相距不超过 2 倍上下文的改动合并为一个差异块
+    let changed: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Equal).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &k in &changed {
+        let start = k.saturating_sub(CONTEXT_LINES);
+        let end = (k + CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", label_old, label_new);
+    for (start, end) in hunks {
+        let old_count = ops[start..end].iter().filter(|op| **op != Op::Insert).count();
+        let new_count = ops[start..end].iter().filter(|op| **op != Op::Delete).count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!("@@ -{} +{} @@\n", hunk_range(old_start, old_count), hunk_range(new_start, new_count)));
+        for (op, &(ai, bi)) in ops[start..end].iter().zip(&positions[start..end]) {
+            match op {
+                Op::Equal => out.push_str(&format!(" {}\n", a[ai])),
+                Op::Delete => out.push_str(&format!("-{}\n", a[ai])),
+                Op::Insert => out.push_str(&format!("+{}\n", b[bi])),
+            }
+        }
+    }
+    Ok((out, added, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_between_text_versions() {
+        assert!(is_text_file("需求.MD") && is_text_file("名单.csv"));
+        assert!(!is_text_file("方案.docx") && !is_text_file("README"));
+
+        let old = "标题\n第一段\n第二段\n第三段\n第四段\n第五段\n第六段\n第七段\n第八段\n第九段\n";
+        let new = "标题\n第一段（修订）\n第二段\n第三段\n第四段\n第五段\n第六段\n第七段\n第八段\n第九段\n附录\n";
+        let (unified, added, removed) = unified_diff(old, new, "需求.md v3", "需求.md v4").unwrap();
+        assert_eq!((added, removed), (2, 1));
+        assert_eq!(unified, "--- 需求.md v3\n+++ 需求.md v4\n\
+            @@ -1,5 +1,5 @@\n 标题\n-第一段\n+第一段（修订）\n 第二段\n 第三段\n 第四段\n\
+            @@ -8,3 +8,4 @@\n 第七段\n 第八段\n 第九段\n+附录\n");
+        assert_eq!(unified_diff(old, old, "a", "b").unwrap(), (String::new(), 0, 0));
+        assert_eq!(unified_diff("", "x\n", "a", "b").unwrap().0, "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n");
+    }
+}
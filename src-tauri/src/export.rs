@@ -0,0 +1,632 @@
+// src-tauri/src/export.rs
+// 数据导出为外部文件格式（Excel 等），只负责写文件，数据由调用方从数据库读取
+use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, XlsxError};
+
+use serde::Deserialize;
+
+use crate::db::{self, ActivityWithDetails, Contact, ContactDossier, DisplayPreferences, EventWithDetails, WorkspaceGraph};
+use crate::redaction::{mask_email, mask_phone};
+
+// 项目通讯录的列：表头和列宽
+const ROSTER_COLUMNS: [(&str, f64); 7] = [
+    ("姓名", 12.0),
+    ("项目角色", 14.0),
+    ("单位", 24.0),
+    ("职位", 14.0),
+    ("电话", 18.0),
+    ("邮箱", 26.0),
+    ("项目备注", 30.0),
+];
+
+// 将项目联系人写入 Excel（联系人、角色、项目备注），返回导出行数
+pub fn write_project_roster_xlsx(
+    path: &str,
+    project_name: &str,
+    contacts: &[(Contact, Option<String>, Option<String>)],
+) -> Result<usize, String> {
+    build_roster_workbook(project_name, contacts)
+        .and_then(|mut workbook| workbook.save(path))
+        .map_err(|e| format!("写入 Excel 失败: {}", e))?;
+    Ok(contacts.len())
+}
+
+fn build_roster_workbook(
+    project_name: &str,
+    contacts: &[(Contact, Option<String>, Option<String>)],
+) -> Result<Workbook, XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("项目通讯录")?;
+
+    let title_format = Format::new()
+        .set_bold()
+        .set_font_size(14)
+        .set_align(FormatAlign::Center);
+    let header_format = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xD9E1F2))
+        .set_border(FormatBorder::Thin);
+    let cell_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_text_wrap();
+
+    let last_col = (ROSTER_COLUMNS.len() - 1) as u16;
+
+    // 第一行：项目名称标题
+    worksheet.merge_range(0, 0, 0, last_col, &format!("{} - 联系人", project_name), &title_format)?;
+
+    // 第二行：表头
+    for (col, (header, width)) in ROSTER_COLUMNS.iter().enumerate() {
+        worksheet.write_string_with_format(1, col as u16, *header, &header_format)?;
+        worksheet.set_column_width(col as u16, *width)?;
+    }
+
+    for (i, (contact, role, project_notes)) in contacts.iter().enumerate() {
+        let row = (i + 2) as u32;
+        // 多个电话在单元格内换行显示
+        let phones = db::parse_phone_list(contact.phone.as_deref()).join("\n");
+        let values = [
+            contact.name.as_str(),
+            role.as_deref().unwrap_or(""),
+            contact.company.as_deref().unwrap_or(""),
+            contact.title.as_deref().unwrap_or(""),
+            phones.as_str(),
+            contact.email.as_deref().unwrap_or(""),
+            project_notes.as_deref().unwrap_or(""),
+        ];
+        for (col, value) in values.iter().enumerate() {
+            worksheet.write_string_with_format(row, col as u16, *value, &cell_format)?;
+        }
+    }
+
+    // 冻结表头并开启筛选
+    worksheet.set_freeze_panes(2, 0)?;
+    worksheet.autofilter(1, 0, (contacts.len() + 1) as u32, last_col)?;
+
+    Ok(workbook)
+}
+
+// 按模板为单个联系人生成文本，支持的占位符：
+// {{name}} {{title}} {{company}} {{phone}} {{email}} {{address}} {{tags}} {{role_in_project}} {{project_name}}
+// 未识别的占位符原样保留，缺失的字段替换为空字符串
+pub fn render_contact_template(
+    template: &str,
+    contact: &Contact,
+    role_in_project: Option<&str>,
+    project_name: Option<&str>,
+) -> String {
+    let phone = db::parse_phone_list(contact.phone.as_deref()).into_iter().next();
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let key = after[..end].trim();
+        let value = match key {
+            "name" => Some(contact.name.as_str()),
+            "title" => Some(contact.title.as_deref().unwrap_or("")),
+            "company" => Some(contact.company.as_deref().unwrap_or("")),
+            "phone" => Some(phone.as_deref().unwrap_or("")),
+            "email" => Some(contact.email.as_deref().unwrap_or("")),
+            "address" => Some(contact.address.as_deref().unwrap_or("")),
+            "tags" => Some(contact.tags.as_deref().unwrap_or("")),
+            "role_in_project" => Some(role_in_project.unwrap_or("")),
+            "project_name" => Some(project_name.unwrap_or("")),
+            _ => None,
+        };
+        match value {
+            Some(v) => result.push_str(v),
+            None => result.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// 转义 XML 特殊字符
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// 将关系图谱转换为 GraphML（可直接导入 Gephi）
+pub fn graph_to_graphml(graph: &WorkspaceGraph) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+         \x20 <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"edge_type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"edge_label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n\
+         \x20 <graph id=\"workspace\" edgedefault=\"undirected\">\n"
+    );
+
+    for node in &graph.nodes {
+        xml.push_str(&format!(
+            "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"type\">{}</data>\n    </node>\n",
+            escape_xml(&node.id), escape_xml(&node.label), escape_xml(&node.node_type)
+        ));
+    }
+
+    for (i, edge) in graph.edges.iter().enumerate() {
+        xml.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"edge_type\">{}</data>\n",
+            i, escape_xml(&edge.source), escape_xml(&edge.target), escape_xml(&edge.edge_type)
+        ));
+        if let Some(ref label) = edge.label {
+            xml.push_str(&format!("      <data key=\"edge_label\">{}</data>\n", escape_xml(label)));
+        }
+        xml.push_str("    </edge>\n");
+    }
+
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+// 将项目时间线渲染为 Markdown（按时间先后排列，按月份分组）
+pub fn render_project_timeline_md(
+    project_name: &str,
+    events: &[EventWithDetails],
+    prefs: &DisplayPreferences,
+) -> String {
+    let mut sorted: Vec<&EventWithDetails> = events.iter().collect();
+    sorted.sort_by(|a, b| a.event.event_date.cmp(&b.event.event_date));
+
+    let mut md = format!("# {} 时间线\n\n", project_name);
+    md.push_str(&format!(
+        "导出时间：{}，共 {} 条事件\n",
+        chrono::Local::now().format(&prefs.datetime_format),
+        sorted.len()
+    ));
+
+    let mut current_month = "";
+    for item in sorted {
+        let event = &item.event;
+        let date = event.event_date.get(..10).unwrap_or(&event.event_date);
+        let month = event.event_date.get(..7).unwrap_or("");
+        if month != current_month {
+            current_month = month;
+            md.push_str(&format!("\n## {}\n", month));
+        }
+
+        // 标题行：日期 时间 类型 标题
+        let time = event.event_date.get(11..16).map(|t| format!(" {}", t)).unwrap_or_default();
+        let event_type = event.event_type.as_deref().map(|t| format!("【{}】", t)).unwrap_or_default();
+        md.push_str(&format!("\n### {}{} {}{}\n\n", prefs.format_date(date), time, event_type, event.title));
+
+        if !item.contacts.is_empty() {
+            let names: Vec<&str> = item.contacts.iter().map(|c| c.name.as_str()).collect();
+            md.push_str(&format!("- 联系人：{}\n", names.join("、")));
+        }
+        // 跨项目事件列出其他关联项目
+        let other_projects: Vec<&str> = item.project_names.iter()
+            .map(|n| n.as_str())
+            .filter(|n| *n != project_name)
+            .collect();
+        if !other_projects.is_empty() {
+            md.push_str(&format!("- 同时关联：{}\n", other_projects.join("、")));
+        }
+        if let Some(description) = event.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            md.push('\n');
+            for line in description.lines() {
+                md.push_str(&format!("> {}\n", line));
+            }
+        }
+    }
+
+    md
+}
+
+// 将联系人档案渲染为 Markdown（字段、项目角色、事件、活动、文件和总结中的提及）
+pub fn render_contact_dossier_md(dossier: &ContactDossier, prefs: &DisplayPreferences) -> String {
+    let contact = &dossier.contact;
+    let mut md = format!("# {} 个人档案\n\n", contact.name);
+    md.push_str(&format!(
+        "导出时间：{}\n\n## 基本信息\n\n",
+        chrono::Local::now().format(&prefs.datetime_format)
+    ));
+    let phones = db::parse_phone_list(contact.phone.as_deref()).join("、");
+    let fields = [
+        ("单位", contact.company.as_deref()),
+        ("职位", contact.title.as_deref()),
+        ("电话", Some(phones.as_str())),
+        ("邮箱", contact.email.as_deref()),
+        ("地址", contact.address.as_deref()),
+        ("城市", contact.city.as_deref()),
+        ("标签", contact.tags.as_deref()),
+        ("备注", contact.notes.as_deref()),
+        ("创建时间", Some(contact.created_at.as_str())),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            md.push_str(&format!("- {}：{}\n", label, value));
+        }
+    }
+
+    md.push_str(&format!("\n## 参与项目（{}）\n\n", dossier.projects.len()));
+    for project in &dossier.projects {
+        let role = project.role.as_deref().map(|r| format!("（{}）", r)).unwrap_or_default();
+        md.push_str(&format!("- {}{}\n", project.project_name, role));
+        if let Some(notes) = project.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            md.push_str(&format!("  - 备注：{}\n", notes));
+        }
+    }
+
+    md.push_str(&format!("\n## 事件（{}）\n", dossier.events.len()));
+    for item in &dossier.events {
+        let event = &item.event;
+        let date = event.event_date.get(..10).unwrap_or(&event.event_date);
+        let time = event.event_date.get(11..16).map(|t| format!(" {}", t)).unwrap_or_default();
+        let event_type = event.event_type.as_deref().map(|t| format!("【{}】", t)).unwrap_or_default();
+        md.push_str(&format!("\n### {}{} {}{}\n\n", prefs.format_date(date), time, event_type, event.title));
+        if !item.project_names.is_empty() {
+            md.push_str(&format!("- 项目：{}\n", item.project_names.join("、")));
+        }
+        if !item.other_contacts.is_empty() {
+            md.push_str(&format!("- 其他参与人：{}\n", item.other_contacts.join("、")));
+        }
+        if let Some(description) = event.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            md.push('\n');
+            for line in description.lines() {
+                md.push_str(&format!("> {}\n", line));
+            }
+        }
+    }
+
+    md.push_str(&format!("\n## 负责的活动（{}）\n\n", dossier.activities.len()));
+    for activity in &dossier.activities {
+        let due = activity.estimated_completion_date.as_deref()
+            .map(|d| format!("，预计完成 {}", prefs.format_date(d)))
+            .unwrap_or_default();
+        md.push_str(&format!("- {}（{}，{}{}）\n", activity.name, activity.project_name, activity.status, due));
+    }
+
+    md.push_str(&format!("\n## 文件（{}）\n\n", dossier.files.len()));
+    for file in &dossier.files {
+        md.push_str(&format!("- {} v{}（{}）\n", file.original_name, file.version, file.created_at));
+    }
+
+    md.push_str(&format!("\n## 总结中的提及（{}）\n", dossier.summary_mentions.len()));
+    for mention in &dossier.summary_mentions {
+        md.push_str(&format!("\n### {}\n\n", mention.title));
+        for excerpt in &mention.excerpts {
+            md.push_str(&format!("> {}\n", excerpt));
+        }
+    }
+
+    md
+}
+
+// 项目分享页选项
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ShareHtmlOptions {
+    pub redact_notes: bool,       // 隐藏项目备注，以及项目、事件和活动描述
+    pub redact_phones: bool,      // 电话只保留前三位和后四位
+    pub mask_emails: bool,        // 邮箱只保留首字符和域名
+    pub redact_emails: bool,      // 隐藏邮箱
+    pub anonymize: bool,          // 联系人化名（见 Pseudonymizer）
+    pub profile: Option<String>,  // 脱敏方案名称，指定时以方案为准（见 redaction）
+}
+
+// 活动看板的列顺序
+const BOARD_STATUSES: [&str; 6] = ["待分配", "未激活", "进行中", "已暂停", "待审核", "已完成"];
+
+// 分享页内联样式（生成单文件 HTML，不依赖外部资源）
+const SHARE_HTML_STYLE: &str = "\
+body{font-family:-apple-system,'PingFang SC','Microsoft YaHei',sans-serif;max-width:960px;margin:32px auto;padding:0 16px;color:#262626}\
+h1{border-bottom:2px solid #1677ff;padding-bottom:8px}h2{margin-top:32px;color:#1677ff}\
+table{border-collapse:collapse;width:100%}th,td{border:1px solid #d9d9d9;padding:6px 8px;text-align:left;vertical-align:top}th{background:#f0f5ff}\
+.timeline{list-style:none;padding-left:0;border-left:3px solid #d6e4ff}.timeline li{margin:0 0 16px 16px}\
+.date{color:#8c8c8c;font-size:13px}.tag{display:inline-block;background:#f0f0f0;border-radius:4px;padding:0 6px;margin-right:6px;font-size:12px}\
+.board{display:flex;gap:12px;overflow-x:auto}.column{flex:1;min-width:140px;background:#fafafa;border-radius:6px;padding:8px}\
+.card{background:#fff;border:1px solid #f0f0f0;border-radius:4px;padding:6px;margin-bottom:8px;font-size:14px}\
+.muted{color:#8c8c8c;font-size:12px}footer{margin-top:40px;color:#bfbfbf;font-size:12px}";
+
+// 生成项目分享页（时间线、联系人、活动看板）的单文件 HTML
+pub fn render_project_share_html(
+    project_name: &str,
+    project_description: Option<&str>,
+    roster: &[(Contact, Option<String>, Option<String>)],
+    events: &[EventWithDetails],
+    activities: &[ActivityWithDetails],
+    options: &ShareHtmlOptions,
+    prefs: &DisplayPreferences,
+) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape_xml(project_name), SHARE_HTML_STYLE, escape_xml(project_name)
+    );
+    if let Some(description) = project_description.filter(|d| !options.redact_notes && !d.trim().is_empty()) {
+        html.push_str(&format!("<p>{}</p>\n", escape_xml(description)));
+    }
+
+    // 时间线（按时间先后）
+    let mut sorted: Vec<&EventWithDetails> = events.iter().collect();
+    sorted.sort_by(|a, b| a.event.event_date.cmp(&b.event.event_date));
+    html.push_str(&format!("<h2>时间线（{}）</h2>\n<ul class=\"timeline\">\n", sorted.len()));
+    for item in sorted {
+        let event = &item.event;
+        let date = event.event_date.get(..10).unwrap_or(&event.event_date);
+        let time = event.event_date.get(11..16).map(|t| format!(" {}", t)).unwrap_or_default();
+        html.push_str(&format!("<li><div class=\"date\">{}{}</div><div>", prefs.format_date(date), time));
+        if let Some(ref event_type) = event.event_type {
+            html.push_str(&format!("<span class=\"tag\">{}</span>", escape_xml(event_type)));
+        }
+        html.push_str(&format!("<strong>{}</strong></div>", escape_xml(&event.title)));
+        if !item.contacts.is_empty() {
+            let names: Vec<String> = item.contacts.iter().map(|c| escape_xml(&c.name)).collect();
+            html.push_str(&format!("<div class=\"muted\">联系人：{}</div>", names.join("、")));
+        }
+        if let Some(description) = event.description.as_deref().filter(|d| !options.redact_notes && !d.trim().is_empty()) {
+            html.push_str(&format!("<div>{}</div>", escape_xml(description).replace('\n', "<br>")));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+
+    // 联系人
+    html.push_str(&format!("<h2>项目联系人（{}）</h2>\n<table>\n<tr><th>姓名</th><th>项目角色</th><th>单位</th><th>职位</th><th>电话</th><th>邮箱</th>", roster.len()));
+    if !options.redact_notes {
+        html.push_str("<th>项目备注</th>");
+    }
+    html.push_str("</tr>\n");
+    for (contact, role, notes) in roster {
+        let phones: Vec<String> = db::parse_phone_list(contact.phone.as_deref())
+            .iter()
+            .map(|p| if options.redact_phones { mask_phone(p) } else { p.clone() })
+            .map(|p| escape_xml(&p))
+            .collect();
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+            escape_xml(&contact.name),
+            escape_xml(role.as_deref().unwrap_or("")),
+            escape_xml(contact.company.as_deref().unwrap_or("")),
+            escape_xml(contact.title.as_deref().unwrap_or("")),
+            phones.join("<br>"),
+            escape_xml(&match contact.email.as_deref().filter(|_| !options.redact_emails) {
+                Some(email) if options.mask_emails => mask_email(email),
+                Some(email) => email.to_string(),
+                None => String::new(),
+            }),
+        ));
+        if !options.redact_notes {
+            html.push_str(&format!("<td>{}</td>", escape_xml(notes.as_deref().unwrap_or(""))));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    // 活动看板（按状态分列）
+    html.push_str(&format!("<h2>活动看板（{}）</h2>\n<div class=\"board\">\n", activities.len()));
+    for status in BOARD_STATUSES {
+        let cards: Vec<&ActivityWithDetails> = activities.iter().filter(|a| a.activity.status == status).collect();
+        html.push_str(&format!("<div class=\"column\"><strong>{}（{}）</strong>\n", status, cards.len()));
+        for card in cards {
+            let activity = &card.activity;
+            html.push_str(&format!("<div class=\"card\">{}", escape_xml(&activity.name)));
+            if !card.assignees.is_empty() {
+                let names: Vec<String> = card.assignees.iter().map(|c| escape_xml(&c.name)).collect();
+                html.push_str(&format!("<div class=\"muted\">负责人：{}</div>", names.join("、")));
+            }
+            if let Some(ref due) = activity.estimated_completion_date {
+                html.push_str(&format!("<div class=\"muted\">预计完成：{}</div>", escape_xml(&prefs.format_date(due))));
+            }
+            if !options.redact_notes {
+                if let Some(description) = activity.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                    html.push_str(&format!("<div>{}</div>", escape_xml(description)));
+                }
+            }
+            html.push_str("</div>\n");
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+
+    html.push_str(&format!(
+        "<footer>生成于 {}</footer>\n</body>\n</html>\n",
+        chrono::Local::now().format(&prefs.datetime_format)
+    ));
+    html
+}
+
+// 导出时的联系人化名：姓名、电话、邮箱按联系人 ID 替换为固定化名（同一联系人在各次导出中一致），
+// 文本字段（事件标题、描述、备注等）中出现的真实姓名也一并替换
+pub struct Pseudonymizer {
+    names: Vec<(String, String)>,  // 真实姓名 -> 化名，按姓名长度降序（优先替换长名）
+}
+
+impl Pseudonymizer {
+    pub fn new(contacts: &[Contact]) -> Self {
+        let mut names: Vec<(String, String)> = contacts.iter()
+            .filter(|c| !c.name.trim().is_empty())
+            .map(|c| (c.name.clone(), Self::alias(c.id)))
+            .collect();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.chars().count()));
+        Pseudonymizer { names }
+    }
+
+    fn alias(contact_id: i32) -> String {
+        format!("联系人{:03}", contact_id)
+    }
+
+    fn phone_aliases(contact_id: i32, count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("100{:04}{:04}", contact_id, i + 1)).collect()
+    }
+
+    fn email_alias(contact_id: i32) -> String {
+        format!("contact{:03}@example.com", contact_id)
+    }
+
+    // 替换文本中出现的联系人姓名
+    pub fn text(&self, text: &str) -> String {
+        let mut result = text.to_string();
+        for (name, alias) in &self.names {
+            if result.contains(name.as_str()) {
+                result = result.replace(name.as_str(), alias);
+            }
+        }
+        result
+    }
+
+    fn text_opt(&self, text: &mut Option<String>) {
+        if let Some(value) = text.as_mut() {
+            *value = self.text(value);
+        }
+    }
+
+    pub fn contact(&self, contact: &mut Contact) {
+        let phones = Self::phone_aliases(contact.id, db::parse_phone_list(contact.phone.as_deref()).len());
+        contact.name = Self::alias(contact.id);
+        contact.phone = (!phones.is_empty()).then(|| serde_json::to_string(&phones).unwrap_or_default());
+        contact.phone_normalized = None;
+        contact.email = contact.email.as_ref().map(|_| Self::email_alias(contact.id));
+        contact.address = None;
+        contact.city = None;
+        self.text_opt(&mut contact.notes);
+    }
+
+    // 单表 JSON 导出的一行（字段名见 db::export_table_json）：联系人表替换姓名、电话、邮箱并清空地址，
+    // 其余表替换文本字段中出现的姓名
+    pub fn record(&self, table: &str, record: &mut serde_json::Map<String, serde_json::Value>) {
+        use serde_json::Value;
+        if table == "contacts" {
+            if let Some(id) = record.get("id").and_then(Value::as_i64).map(|id| id as i32) {
+                record.insert("name".to_string(), Value::from(Self::alias(id)));
+                if let Some(Value::Array(phones)) = record.get("phones") {
+                    let aliases = Self::phone_aliases(id, phones.len());
+                    record.insert("phones".to_string(), Value::from(aliases));
+                }
+                if record.get("email").is_some_and(|v| !v.is_null()) {
+                    record.insert("email".to_string(), Value::from(Self::email_alias(id)));
+                }
+                for field in ["address", "city"] {
+                    if record.contains_key(field) {
+                        record.insert(field.to_string(), Value::Null);
+                    }
+                }
+            }
+        }
+        for field in ["name", "title", "description", "notes"] {
+            if let Some(Value::String(text)) = record.get_mut(field) {
+                *text = self.text(text);
+            }
+        }
+    }
+
+    pub fn roster(&self, roster: &mut [(Contact, Option<String>, Option<String>)]) {
+        for (contact, _, notes) in roster.iter_mut() {
+            self.contact(contact);
+            self.text_opt(notes);
+        }
+    }
+
+    pub fn events(&self, events: &mut [EventWithDetails]) {
+        for item in events.iter_mut() {
+            item.event.title = self.text(&item.event.title);
+            self.text_opt(&mut item.event.description);
+            item.contacts.iter_mut().for_each(|c| self.contact(c));
+        }
+    }
+
+    pub fn activities<'a>(&self, activities: impl IntoIterator<Item = &'a mut ActivityWithDetails>) {
+        for item in activities {
+            item.activity.name = self.text(&item.activity.name);
+            self.text_opt(&mut item.activity.description);
+            item.assignees.iter_mut().for_each(|c| self.contact(c));
+        }
+    }
+
+    pub fn graph(&self, graph: &mut WorkspaceGraph) {
+        for node in graph.nodes.iter_mut() {
+            node.label = self.text(&node.label);
+        }
+        for edge in graph.edges.iter_mut() {
+            self.text_opt(&mut edge.label);
+        }
+    }
+}
+
+// 转义 vCard 字段值
+fn escape_vcard(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+}
+
+// 生成联系人的 vCard 3.0 文本（不含备注等内部信息）
+pub fn contact_to_vcard(contact: &Contact) -> String {
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        format!("FN:{}", escape_vcard(&contact.name)),
+        format!("N:{};;;;", escape_vcard(&contact.name)),
+    ];
+    if let Some(ref company) = contact.company {
+        lines.push(format!("ORG:{}", escape_vcard(company)));
+    }
+    if let Some(ref title) = contact.title {
+        lines.push(format!("TITLE:{}", escape_vcard(title)));
+    }
+    for phone in db::parse_phone_list(contact.phone.as_deref()) {
+        lines.push(format!("TEL;TYPE=CELL:{}", escape_vcard(&phone)));
+    }
+    if let Some(ref email) = contact.email {
+        lines.push(format!("EMAIL:{}", escape_vcard(email)));
+    }
+    if let Some(ref address) = contact.address {
+        lines.push(format!("ADR:;;{};;;;", escape_vcard(address)));
+    }
+    lines.push("END:VCARD".to_string());
+    lines.join("\r\n") + "\r\n"
+}
+
+// 二维码每个模块的像素大小和四周留白（模块数）
+const QR_MODULE_PIXELS: usize = 8;
+const QR_QUIET_ZONE: usize = 4;
+
+// 将文本编码为二维码 PNG 图片
+pub fn render_qr_png(data: &str) -> Result<Vec<u8>, String> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| format!("生成二维码失败: {}", e))?;
+    let width = code.width();
+    let colors = code.to_colors();
+
+    let modules = width + QR_QUIET_ZONE * 2;
+    let size = modules * QR_MODULE_PIXELS;
+    let mut pixels = vec![255u8; size * size];
+    for y in 0..width {
+        for x in 0..width {
+            if colors[y * width + x] != qrcode::Color::Dark {
+                continue;
+            }
+            let (px, py) = ((x + QR_QUIET_ZONE) * QR_MODULE_PIXELS, (y + QR_QUIET_ZONE) * QR_MODULE_PIXELS);
+            for row in py..py + QR_MODULE_PIXELS {
+                pixels[row * size + px..row * size + px + QR_MODULE_PIXELS].fill(0);
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("写入 PNG 失败: {}", e))?;
+        writer.write_image_data(&pixels).map_err(|e| format!("写入 PNG 失败: {}", e))?;
+    }
+    Ok(bytes)
+}
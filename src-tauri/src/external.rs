@@ -0,0 +1,98 @@
+// src-tauri/src/external.rs
+// 调用外部程序（上传扫描程序、文本润色程序等）：可选地从标准输入写入文本，收集标准输出和标准错误，
+// 超过最长运行时间时结束进程
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+// 外部程序的运行结果
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    // 退出码文本（被信号结束时为 -）
+    pub fn exit_code(&self) -> String {
+        self.status.code().map(|c| c.to_string()).unwrap_or_else(|| "-".to_string())
+    }
+}
+
+// 外部程序没有正常结束的原因
+#[derive(Debug)]
+pub enum RunError {
+    Spawn(std::io::Error),  // 无法启动
+    Wait(std::io::Error),   // 等待退出失败
+    Timeout,                // 超时（进程已结束）
+}
+
+// 运行外部程序直到退出或超时；input 为 None 时标准输入为空
+pub fn run_with_timeout(program: &str, args: &[&OsStr], input: Option<&str>, timeout: Duration) -> Result<CommandOutput, RunError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(RunError::Spawn)?;
+
+    // 写入输入和读取输出都放在单独的线程，避免管道写满时互相阻塞
+    let stdin = child.stdin.take();
+    let input = input.unwrap_or_default().to_string();
+    let writer = std::thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>| std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    });
+    let stdout_reader = read_pipe(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr_reader = read_pipe(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < timeout => std::thread::sleep(Duration::from_millis(100)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(RunError::Timeout);
+            }
+            Err(e) => return Err(RunError::Wait(e)),
+        }
+    };
+    let _ = writer.join();
+    Ok(CommandOutput {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_commands_get_input_and_time_out() {
+        let output = run_with_timeout("cat", &[], Some("周报草稿"), Duration::from_secs(10)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "周报草稿");
+
+        let output = run_with_timeout("sh", &[OsStr::new("-c"), OsStr::new("echo 出错 >&2; exit 3")], None, Duration::from_secs(10)).unwrap();
+        assert_eq!((output.exit_code().as_str(), output.stderr.trim()), ("3", "出错"));
+
+        let started = Instant::now();
+        assert!(matches!(run_with_timeout("sleep", &[OsStr::new("5")], None, Duration::from_millis(200)), Err(RunError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(matches!(run_with_timeout("/nonexistent/scanner", &[], None, Duration::from_secs(1)), Err(RunError::Spawn(_))));
+    }
+}
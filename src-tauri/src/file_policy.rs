@@ -0,0 +1,162 @@
+// src-tauri/src/file_policy.rs
+// 上传文件检查：项目文件夹会被同步并转发给他人，上传前拒绝高风险的文件类型（blocked_extensions），
+// 还可以配置外部扫描程序（upload_scan_command，如杀毒软件的命令行工具），以文件路径为参数调用，
+// 退出码非 0 视为不通过；被拒绝时上传命令返回结构化的 UploadRejection 而不是错误字符串
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::external::{run_with_timeout, RunError};
+
+pub const SETTING_BLOCKED_EXTENSIONS: &str = "blocked_extensions";  // 禁止上传的扩展名，逗号分隔（空字符串表示不限制）
+pub const SETTING_SCAN_COMMAND: &str = "upload_scan_command";      // 外部扫描程序（空或未设置表示不扫描）
+
+// 未设置时禁止的扩展名（可执行文件和脚本）
+pub const DEFAULT_BLOCKED_EXTENSIONS: &[&str] = &[
+    "exe", "msi", "com", "scr", "pif", "bat", "cmd", "ps1", "vbs", "vbe", "js", "jse", "wsf", "hta", "jar", "reg", "lnk",
+];
+
+// 扫描程序的最长运行时间，超时视为扫描失败
+const SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+// 拒绝原因中扫描程序输出的最大长度
+const MAX_SCAN_OUTPUT_CHARS: usize = 500;
+
+// 上传被拒绝的原因
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadRejection {
+    pub code: String,  // blocked_extension（扩展名被禁止）/ scan_failed（扫描不通过）/ scan_error（扫描程序无法运行或超时）
+    pub file_name: String,
+    pub reason: String,
+}
+
+// 上传命令的错误：被拒绝时为结构化对象，其余错误仍为字符串
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum UploadError {
+    Rejected(UploadRejection),
+    Failed(String),
+}
+
+impl From<String> for UploadError {
+    fn from(message: String) -> Self {
+        UploadError::Failed(message)
+    }
+}
+
+impl From<&str> for UploadError {
+    fn from(message: &str) -> Self {
+        UploadError::Failed(message.to_string())
+    }
+}
+
+impl From<UploadRejection> for UploadError {
+    fn from(rejection: UploadRejection) -> Self {
+        UploadError::Rejected(rejection)
+    }
+}
+
+// 规范化扩展名（去掉开头的点，转小写）
+fn normalize_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+// 解析扩展名列表，如 ".exe, JS" -> ["exe", "js"]
+pub fn parse_extensions(value: &str) -> Result<Vec<String>, String> {
+    let mut extensions = Vec::new();
+    for item in value.split(',') {
+        let extension = normalize_extension(item);
+        if extension.is_empty() {
+            continue;
+        }
+        if !extension.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("无效的扩展名: {}", item.trim()));
+        }
+        if !extensions.contains(&extension) {
+            extensions.push(extension);
+        }
+    }
+    Ok(extensions)
+}
+
+// 当前禁止的扩展名
+pub fn blocked_extensions(settings: &HashMap<String, String>) -> Vec<String> {
+    match settings.get(SETTING_BLOCKED_EXTENSIONS) {
+        Some(value) => parse_extensions(value).unwrap_or_default(),
+        None => DEFAULT_BLOCKED_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+// 检查扩展名是否被禁止
+pub fn check_extension(path: &Path, settings: &HashMap<String, String>) -> Result<(), UploadRejection> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let Some(extension) = path.extension().map(|e| normalize_extension(&e.to_string_lossy())) else {
+        return Ok(());
+    };
+    if blocked_extensions(settings).contains(&extension) {
+        return Err(UploadRejection {
+            code: "blocked_extension".to_string(),
+            reason: format!("不允许上传 .{} 文件: {}", extension, file_name),
+            file_name,
+        });
+    }
+    Ok(())
+}
+
+// 调用外部扫描程序检查文件（未配置时直接通过）
+pub fn scan_file(path: &Path, settings: &HashMap<String, String>) -> Result<(), UploadRejection> {
+    let Some(command) = settings.get(SETTING_SCAN_COMMAND).map(|c| c.trim()).filter(|c| !c.is_empty()) else {
+        return Ok(());
+    };
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let rejection = |code: &str, reason: String| UploadRejection {
+        code: code.to_string(),
+        file_name: file_name.clone(),
+        reason,
+    };
+
+    let output = match run_with_timeout(command, &[path.as_os_str()], None, SCAN_TIMEOUT) {
+        Ok(output) => output,
+        Err(RunError::Spawn(e)) => return Err(rejection("scan_error", format!("无法运行扫描程序 {}: {}", command, e))),
+        Err(RunError::Wait(e)) => return Err(rejection("scan_error", format!("等待扫描程序失败: {}", e))),
+        Err(RunError::Timeout) => return Err(rejection("scan_error", format!("扫描超时（{} 秒）: {}", SCAN_TIMEOUT.as_secs(), file_name))),
+    };
+
+    if output.status.success() {
+        return Ok(());
+    }
+    let detail: String = output.stdout.trim().chars().take(MAX_SCAN_OUTPUT_CHARS).collect();
+    let reason = if detail.is_empty() {
+        format!("文件未通过安全扫描（退出码 {}）: {}", output.exit_code(), file_name)
+    } else {
+        format!("文件未通过安全扫描: {}（{}）", file_name, detail)
+    };
+    Err(rejection("scan_failed", reason))
+}
+
+// 上传前的完整检查：先检查扩展名，再调用扫描程序
+pub fn check_upload(path: &Path, settings: &HashMap<String, String>) -> Result<(), UploadRejection> {
+    check_extension(path, settings)?;
+    scan_file(path, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload_policy_blocks_risky_extensions() {
+        let mut settings = HashMap::new();
+        assert!(check_upload(Path::new("/tmp/报价.pdf"), &settings).is_ok());
+        let rejection = check_upload(Path::new("/tmp/invoice.pdf.EXE"), &settings).unwrap_err();
+        assert_eq!((rejection.code.as_str(), rejection.file_name.as_str()), ("blocked_extension", "invoice.pdf.EXE"));
+
+        assert_eq!(parse_extensions(" .Zip, rar,,zip").unwrap(), vec!["zip", "rar"]);
+        assert!(parse_extensions("tar.gz").is_err());
+        settings.insert(SETTING_BLOCKED_EXTENSIONS.to_string(), "zip".to_string());
+        assert!(check_upload(Path::new("/tmp/setup.exe"), &settings).is_ok());
+        assert!(check_upload(Path::new("/tmp/backup.zip"), &settings).is_err());
+        assert!(parse_extensions("exe, *.js").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// src-tauri/src/format.rs
+// 数字格式化：返回文件大小、工时等数值的命令同时附带按设置格式化好的文字（*_display 字段），
+// 千位分隔符和小数点由 number_format 决定，文件大小按 1024 还是 1000 进位由 file_size_units 决定
+use std::collections::HashMap;
+
+pub const SETTING_NUMBER_FORMAT: &str = "number_format";      // 数字格式：en（1,234.5）/ de（1.234,5）/ fr（1 234,5）/ plain（1234.5）
+pub const SETTING_FILE_SIZE_UNITS: &str = "file_size_units";  // 文件大小进位：binary（1 KB = 1024 B）/ decimal（1 KB = 1000 B）
+
+// 文件大小单位
+const SIZE_UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+
+// 数字显示格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub thousands_separator: &'static str,
+    pub decimal_separator: char,
+    pub size_base: u64,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat { thousands_separator: ",", decimal_separator: '.', size_base: 1024 }
+    }
+}
+
+// 解析数字格式，返回（千位分隔符，小数点）
+pub fn parse_number_format(value: &str) -> Option<(&'static str, char)> {
+    match value.trim().to_lowercase().as_str() {
+        "en" => Some((",", '.')),
+        "de" => Some((".", ',')),
+        "fr" => Some(("\u{a0}", ',')),  // 不换行空格，避免数字被折行
+        "plain" => Some(("", '.')),
+        _ => None,
+    }
+}
+
+// 解析文件大小进位
+pub fn parse_size_units(value: &str) -> Option<u64> {
+    match value.trim().to_lowercase().as_str() {
+        "binary" => Some(1024),
+        "decimal" => Some(1000),
+        _ => None,
+    }
+}
+
+impl NumberFormat {
+    // 根据设置项构造（未设置或无效的项使用默认值）
+    pub fn from_settings(settings: &HashMap<String, String>) -> Self {
+        let default = NumberFormat::default();
+        let (thousands_separator, decimal_separator) = settings.get(SETTING_NUMBER_FORMAT)
+            .and_then(|v| parse_number_format(v))
+            .unwrap_or((default.thousands_separator, default.decimal_separator));
+        NumberFormat {
+            thousands_separator,
+            decimal_separator,
+            size_base: settings.get(SETTING_FILE_SIZE_UNITS)
+                .and_then(|v| parse_size_units(v))
+                .unwrap_or(default.size_base),
+        }
+    }
+
+    // 固定小数位数，如 1234.5 保留 2 位 -> "1,234.50"
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+        let mut out = String::new();
+        // 四舍五入后为 0 时不显示负号
+        if value < 0.0 && text.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            out.push('-');
+        }
+        out.push_str(&self.group_digits(integer));
+        if let Some(fraction) = fraction {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    // 最多保留指定位数的小数，去掉末尾的 0，如 7.50 -> "7.5"、12.0 -> "12"
+    pub fn format_decimal(&self, value: f64, max_decimals: usize) -> String {
+        let text = self.format_number(value, max_decimals);
+        if max_decimals == 0 {
+            return text;
+        }
+        text.trim_end_matches('0').trim_end_matches(self.decimal_separator).to_string()
+    }
+
+    // 整数，如 1234567 -> "1,234,567"
+    pub fn format_integer(&self, value: i64) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{}{}", sign, self.format_integer_unsigned(value.unsigned_abs()))
+    }
+
+    // 无符号整数，如 1234567 -> "1,234,567"
+    pub fn format_integer_unsigned(&self, value: u64) -> String {
+        self.group_digits(&value.to_string())
+    }
+
+    // 文件大小，如 1536 -> "1.5 KB"
+    pub fn format_file_size(&self, bytes: u64) -> String {
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= self.size_base as f64 && unit < SIZE_UNITS.len() - 1 {
+            value /= self.size_base as f64;
+            unit += 1;
+        }
+        if unit == 0 {
+            return format!("{} {}", self.format_integer(bytes as i64), SIZE_UNITS[0]);
+        }
+        format!("{} {}", self.format_decimal(value, 1), SIZE_UNITS[unit])
+    }
+
+    // 工时，如 7.5 -> "7.5 h"
+    pub fn format_hours(&self, hours: f64) -> String {
+        format!("{} h", self.format_decimal(hours, 1))
+    }
+
+    // 整数部分每三位插入千位分隔符
+    fn group_digits(&self, digits: &str) -> String {
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3 * self.thousands_separator.len());
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push_str(self.thousands_separator);
+            }
+            out.push(c);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_format_follows_settings() {
+        let mut settings = std::collections::HashMap::new();
+        let numbers = NumberFormat::from_settings(&settings);
+        assert_eq!(numbers.format_number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(numbers.format_number(-0.001, 1), "0.0");
+        assert_eq!(numbers.format_integer(-1234), "-1,234");
+        assert_eq!(numbers.format_file_size(512), "512 B");
+        assert_eq!(numbers.format_file_size(1536), "1.5 KB");
+        assert_eq!(numbers.format_file_size(10 * 1024 * 1024), "10 MB");
+        assert_eq!(numbers.format_hours(7.5), "7.5 h");
+
+        settings.insert(SETTING_NUMBER_FORMAT.to_string(), "de".to_string());
+        settings.insert(SETTING_FILE_SIZE_UNITS.to_string(), "decimal".to_string());
+        let numbers = NumberFormat::from_settings(&settings);
+        assert_eq!(numbers.format_hours(1234.5), "1.234,5 h");
+        assert_eq!(numbers.format_file_size(2_500_000), "2,5 MB");
+        assert!(parse_number_format("xx").is_none());
+        assert_eq!(parse_size_units("binary"), Some(1024));
+    }
+}
@@ -0,0 +1,825 @@
+// src-tauri/src/import.rs
+// 外部数据导入解析（CSV / vCard / 节假日 / Trello / Notion / Markdown 笔记 / 名片文本），只负责把文件内容解析为结构化数据，不直接写数据库
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// 从外部文件解析出的联系人
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportedContact {
+    pub name: String,
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Option<String>,
+    pub phones: Vec<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub company: Option<String>,
+}
+
+// 根据扩展名选择解析器读取联系人文件
+pub fn parse_contacts_file(path: &str) -> Result<Vec<ImportedContact>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    // 去掉 Excel 导出 CSV 时常见的 BOM
+    let content = content.trim_start_matches('\u{feff}');
+
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let contacts = match extension.as_str() {
+        "vcf" | "vcard" => parse_vcard(content),
+        "csv" => parse_contacts_csv(content)?,
+        _ => return Err(format!("不支持的文件格式: {}", extension)),
+    };
+
+    Ok(contacts.into_iter().filter(|c| !c.name.trim().is_empty()).collect())
+}
+
+// 解析 CSV 文本为行列表（支持双引号包裹、转义双引号和字段内换行）
+pub fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // 忽略完全空白的行
+    rows.into_iter()
+        .filter(|r| r.iter().any(|f| !f.trim().is_empty()))
+        .collect()
+}
+
+// 表头名称 -> 联系人字段（同时支持中英文表头）
+fn map_contact_header(header: &str) -> Option<&'static str> {
+    match header.trim().to_lowercase().as_str() {
+        "姓名" | "名字" | "name" | "full name" => Some("name"),
+        "职位" | "头衔" | "title" | "job title" => Some("title"),
+        "单位" | "公司" | "单位名称" | "company" | "organization" => Some("company"),
+        "电话" | "手机" | "phone" | "mobile" | "tel" => Some("phone"),
+        "邮箱" | "电子邮件" | "email" | "e-mail" => Some("email"),
+        "地址" | "address" => Some("address"),
+        "标签" | "tags" => Some("tags"),
+        "备注" | "notes" | "note" => Some("notes"),
+        _ => None,
+    }
+}
+
+// 解析联系人 CSV（第一行为表头）
+fn parse_contacts_csv(content: &str) -> Result<Vec<ImportedContact>, String> {
+    let mut rows = parse_csv(content).into_iter();
+    let headers: Vec<Option<&'static str>> = rows
+        .next()
+        .ok_or("CSV 文件为空")?
+        .iter()
+        .map(|h| map_contact_header(h))
+        .collect();
+
+    if !headers.contains(&Some("name")) {
+        return Err("CSV 缺少姓名列（姓名/name）".to_string());
+    }
+
+    let mut contacts = Vec::new();
+    for row in rows {
+        let mut contact = ImportedContact::default();
+        for (i, value) in row.iter().enumerate() {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match headers.get(i).copied().flatten() {
+                Some("name") => contact.name = value.to_string(),
+                Some("title") => contact.title = Some(value.to_string()),
+                Some("company") => contact.company = Some(value.to_string()),
+                Some("phone") => contact.phones.extend(split_multi_value(value)),
+                Some("email") => contact.email = Some(value.to_string()),
+                Some("address") => contact.address = Some(value.to_string()),
+                Some("tags") => contact.tags = Some(value.to_string()),
+                Some("notes") => contact.notes = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        contacts.push(contact);
+    }
+
+    Ok(contacts)
+}
+
+// 拆分一个单元格中的多个值（如多个电话用 ; 或 / 分隔）
+fn split_multi_value(value: &str) -> Vec<String> {
+    value.split([';', '；', '/', '、'])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// 解析 vCard 文本（支持 2.1 / 3.0 / 4.0 常用字段）
+fn parse_vcard(content: &str) -> Vec<ImportedContact> {
+    // 先展开折行：以空格或制表符开头的行是上一行的延续
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        if (raw.starts_with(' ') || raw.starts_with('\t')) && !lines.is_empty() {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&raw[1..]);
+            }
+        } else {
+            lines.push(raw.to_string());
+        }
+    }
+
+    let mut contacts = Vec::new();
+    let mut current: Option<ImportedContact> = None;
+    let mut structured_name: Option<String> = None;
+
+    for line in lines {
+        let Some((key_part, value)) = line.split_once(':') else {
+            continue;
+        };
+        // 去掉 item1.TEL 这样的分组前缀和 ;TYPE=CELL 这样的参数
+        let key = key_part
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .rsplit('.')
+            .next()
+            .unwrap_or("")
+            .to_uppercase();
+        let value = unescape_vcard_value(value.trim());
+
+        match key.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => {
+                current = Some(ImportedContact::default());
+                structured_name = None;
+            }
+            "END" if value.eq_ignore_ascii_case("VCARD") => {
+                if let Some(mut contact) = current.take() {
+                    if contact.name.is_empty() {
+                        contact.name = structured_name.take().unwrap_or_default();
+                    }
+                    contacts.push(contact);
+                }
+            }
+            _ => {
+                let Some(contact) = current.as_mut() else {
+                    continue;
+                };
+                if value.is_empty() {
+                    continue;
+                }
+                match key.as_str() {
+                    "FN" => contact.name = value,
+                    "N" => {
+                        // N:姓;名;中间名;前缀;后缀，中文名直接拼接姓和名
+                        let parts: Vec<&str> = value.split(';').collect();
+                        let family = parts.first().copied().unwrap_or("");
+                        let given = parts.get(1).copied().unwrap_or("");
+                        let joined = if family.is_ascii() && given.is_ascii() && !family.is_empty() && !given.is_empty() {
+                            format!("{} {}", given, family)
+                        } else {
+                            format!("{}{}", family, given)
+                        };
+                        structured_name = Some(joined.trim().to_string());
+                    }
+                    "ORG" => contact.company = Some(value.split(';').next().unwrap_or("").to_string()),
+                    "TITLE" => contact.title = Some(value),
+                    "TEL" => contact.phones.push(value.trim_start_matches("tel:").to_string()),
+                    // 多个邮箱时保留第一个
+                    "EMAIL" if contact.email.is_none() => contact.email = Some(value),
+                    "ADR" => {
+                        // ADR:邮箱;扩展地址;街道;城市;省份;邮编;国家
+                        let parts: Vec<&str> = value.split(';').filter(|p| !p.trim().is_empty()).collect();
+                        if !parts.is_empty() {
+                            contact.address = Some(parts.join(" "));
+                        }
+                    }
+                    "NOTE" => contact.notes = Some(value),
+                    "CATEGORIES" => contact.tags = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    contacts
+}
+
+// 还原 vCard 中的转义字符
+fn unescape_vcard_value(value: &str) -> String {
+    value
+        .replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+// 从外部文件解析出的节假日 / 调休日
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedHoliday {
+    pub date: String,      // YYYY-MM-DD
+    pub name: String,
+    pub is_workday: bool,  // true 表示调休上班（周末补班）
+}
+
+// holiday-cn 项目的 JSON 格式：{"year": 2025, "days": [{"name": "元旦", "date": "2025-01-01", "isOffDay": true}]}
+#[derive(Deserialize)]
+struct HolidayCnFile {
+    days: Vec<HolidayCnDay>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HolidayCnDay {
+    name: String,
+    date: String,
+    is_off_day: bool,
+}
+
+// 解析节假日文件：JSON（holiday-cn 格式）或 CSV（日期,名称,是否调休上班）
+pub fn parse_holiday_file(path: &str) -> Result<Vec<ImportedHoliday>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let holidays = match extension.as_str() {
+        "json" => {
+            let file: HolidayCnFile = serde_json::from_str(content)
+                .map_err(|e| format!("节假日 JSON 格式错误: {}", e))?;
+            file.days.into_iter()
+                .map(|d| ImportedHoliday { date: d.date, name: d.name, is_workday: !d.is_off_day })
+                .collect()
+        }
+        "csv" => parse_holiday_csv(content),
+        _ => return Err(format!("不支持的文件格式: {}", extension)),
+    };
+
+    // 只保留日期格式正确的行
+    Ok(holidays.into_iter()
+        .filter(|h| chrono::NaiveDate::parse_from_str(&h.date, "%Y-%m-%d").is_ok())
+        .collect())
+}
+
+// 解析节假日 CSV（首行为表头时自动跳过）
+fn parse_holiday_csv(content: &str) -> Vec<ImportedHoliday> {
+    parse_csv(content).into_iter()
+        .filter(|row| row.first().is_some_and(|d| d.trim().starts_with(|c: char| c.is_ascii_digit())))
+        .map(|row| {
+            let field = |i: usize| row.get(i).map(|f| f.trim().to_string()).unwrap_or_default();
+            let workday = field(2).to_lowercase();
+            ImportedHoliday {
+                date: field(0),
+                name: field(1),
+                is_workday: matches!(workday.as_str(), "1" | "true" | "是" | "班" | "上班" | "调休"),
+            }
+        })
+        .collect()
+}
+
+// 从看板工具导入的活动（Trello 卡片）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedActivity {
+    pub name: String,
+    pub description: Option<String>,
+    pub due_date: Option<String>,   // YYYY-MM-DD
+    pub list_name: String,          // 来源列表
+    pub status: String,             // 按列表名推断：待分配 / 进行中 / 已完成
+    pub member_names: Vec<String>,  // 卡片成员（按姓名匹配负责人）
+    pub labels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TrelloBoard {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    lists: Vec<TrelloList>,
+    #[serde(default)]
+    cards: Vec<TrelloCard>,
+    #[serde(default)]
+    members: Vec<TrelloMember>,
+}
+
+#[derive(Deserialize)]
+struct TrelloList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloCard {
+    name: String,
+    #[serde(default)]
+    desc: String,
+    id_list: String,
+    due: Option<String>,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    id_members: Vec<String>,
+    #[serde(default)]
+    labels: Vec<TrelloLabel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloMember {
+    id: String,
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct TrelloLabel {
+    #[serde(default)]
+    name: String,
+}
+
+// 按 Trello 列表名推断活动状态
+fn status_from_list_name(list_name: &str) -> &'static str {
+    let name = list_name.to_lowercase();
+    if ["done", "complete", "完成"].iter().any(|k| name.contains(k)) {
+        "已完成"
+    } else if ["doing", "progress", "进行", "处理中"].iter().any(|k| name.contains(k)) {
+        "进行中"
+    } else {
+        "待分配"
+    }
+}
+
+// 解析 Trello 看板导出的 JSON（忽略已归档的列表和卡片），返回看板名称和活动列表
+pub fn parse_trello_board(path: &str) -> Result<(String, Vec<ImportedActivity>), String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let board: TrelloBoard = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Trello JSON 格式错误: {}", e))?;
+
+    let members: std::collections::HashMap<&str, &str> = board.members.iter()
+        .map(|m| (m.id.as_str(), m.full_name.as_str()))
+        .collect();
+
+    let mut activities = Vec::new();
+    for list in board.lists.iter().filter(|l| !l.closed) {
+        for card in board.cards.iter().filter(|c| c.id_list == list.id && !c.closed) {
+            if card.name.trim().is_empty() {
+                continue;
+            }
+            let status = if card.due_complete { "已完成" } else { status_from_list_name(&list.name) };
+            activities.push(ImportedActivity {
+                name: card.name.trim().to_string(),
+                description: Some(card.desc.trim().to_string()).filter(|d| !d.is_empty()),
+                due_date: card.due.as_deref().and_then(|d| d.get(..10)).map(|d| d.to_string()),
+                list_name: list.name.clone(),
+                status: status.to_string(),
+                member_names: card.id_members.iter()
+                    .filter_map(|id| members.get(id.as_str()).map(|n| n.to_string()))
+                    .collect(),
+                labels: card.labels.iter()
+                    .map(|l| l.name.trim().to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect(),
+            });
+        }
+    }
+
+    Ok((board.name, activities))
+}
+
+// 从笔记工具导入的事件（Notion 数据库导出的 CSV 行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedEvent {
+    pub title: String,
+    pub description: Option<String>,
+    pub event_date: String,          // YYYY-MM-DD 或 YYYY-MM-DD HH:MM
+    pub event_type: Option<String>,
+    pub project_name: Option<String>,
+    pub contact_names: Vec<String>,
+}
+
+// Notion CSV 的解析结果：有日期列时按事件导入，否则按联系人导入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotionImport {
+    pub events: Vec<ImportedEvent>,
+    pub contacts: Vec<ImportedContact>,
+    pub skipped_rows: usize,  // 缺少标题或日期无法识别的行
+}
+
+// 表头名称 -> 事件字段
+fn map_event_header(header: &str) -> Option<&'static str> {
+    match header.trim().to_lowercase().as_str() {
+        "name" | "title" | "名称" | "标题" | "事件" => Some("title"),
+        "date" | "when" | "日期" | "时间" | "日期时间" => Some("date"),
+        "type" | "category" | "类型" | "分类" => Some("type"),
+        "project" | "项目" => Some("project"),
+        "people" | "person" | "attendees" | "participants" | "assignee" | "联系人" | "参与人" | "相关人员" => Some("contacts"),
+        "description" | "notes" | "content" | "描述" | "备注" | "内容" => Some("description"),
+        _ => None,
+    }
+}
+
+// 解析 Notion 导出的日期（如 "October 14, 2026 2:00 PM"、"2026/10/14"，范围取开始时间）
+fn parse_notion_date(value: &str) -> Option<String> {
+    let value = value.split('→').next().unwrap_or("").trim();
+    let datetime_formats = ["%B %d, %Y %I:%M %p", "%Y/%m/%d %H:%M", "%Y-%m-%d %H:%M", "%Y年%m月%d日 %H:%M"];
+    if let Some(dt) = datetime_formats.iter().find_map(|f| chrono::NaiveDateTime::parse_from_str(value, f).ok()) {
+        return Some(dt.format("%Y-%m-%d %H:%M").to_string());
+    }
+    let date_formats = ["%B %d, %Y", "%b %d, %Y", "%Y/%m/%d", "%Y-%m-%d", "%Y年%m月%d日"];
+    date_formats.iter()
+        .find_map(|f| chrono::NaiveDate::parse_from_str(value, f).ok())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+// 去掉 Notion 关系字段附带的页面链接，如 "官网改版 (https://www.notion.so/...)"
+fn strip_notion_link(value: &str) -> String {
+    value.split(" (http").next().unwrap_or(value).trim().to_string()
+}
+
+// 解析 Notion 数据库导出的 CSV
+pub fn parse_notion_csv(path: &str) -> Result<NotionImport, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    let mut rows = parse_csv(content).into_iter();
+    let raw_headers = rows.next().ok_or("CSV 文件为空")?;
+    let headers: Vec<Option<&'static str>> = raw_headers.iter().map(|h| map_event_header(h)).collect();
+
+    // 没有日期列时视为联系人表
+    if !headers.contains(&Some("date")) {
+        let contacts = parse_contacts_csv(content)?;
+        let total = contacts.len();
+        let contacts: Vec<ImportedContact> = contacts.into_iter().filter(|c| !c.name.trim().is_empty()).collect();
+        return Ok(NotionImport { skipped_rows: total - contacts.len(), contacts, ..Default::default() });
+    }
+
+    let mut result = NotionImport::default();
+    for row in rows {
+        let field = |key: &str| -> Option<String> {
+            headers.iter().position(|h| *h == Some(key))
+                .and_then(|i| row.get(i))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let (Some(title), Some(event_date)) = (field("title"), field("date").as_deref().and_then(parse_notion_date)) else {
+            result.skipped_rows += 1;
+            continue;
+        };
+        result.events.push(ImportedEvent {
+            title,
+            description: field("description"),
+            event_date,
+            event_type: field("type"),
+            project_name: field("project").map(|p| strip_notion_link(&p)),
+            contact_names: field("contacts")
+                .map(|v| v.split([',', '，', '、']).map(strip_notion_link).filter(|n| !n.is_empty()).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    Ok(result)
+}
+
+// Markdown 笔记的解析结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkdownEventImport {
+    pub events: Vec<ImportedEvent>,
+    pub skipped_lines: usize,  // 以列表符号开头但没有可识别日期的行
+}
+
+// 解析一行开头的日期和可选的时间，返回（YYYY-MM-DD[ HH:MM]，剩余文本）
+fn split_leading_datetime(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    let date_end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let date_formats = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%Y年%m月%d日"];
+    let date = date_formats.iter()
+        .find_map(|f| chrono::NaiveDate::parse_from_str(&text[..date_end], f).ok())?;
+    let rest = text[date_end..].trim_start();
+    let time_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    match chrono::NaiveTime::parse_from_str(&rest[..time_end], "%H:%M") {
+        Ok(time) => Some((format!("{} {}", date.format("%Y-%m-%d"), time.format("%H:%M")), rest[time_end..].trim())),
+        Err(_) => Some((date.format("%Y-%m-%d").to_string(), rest.trim())),
+    }
+}
+
+// 去掉标题开头用【】或 [] 标注的事件类型，如 "【会议】需求评审"
+fn split_bracketed_type(text: &str) -> (Option<String>, String) {
+    for (open, close) in [('【', '】'), ('[', ']')] {
+        if let Some(rest) = text.strip_prefix(open) {
+            if let Some((event_type, title)) = rest.split_once(close) {
+                let event_type = event_type.trim();
+                if !event_type.is_empty() && !title.trim().is_empty() {
+                    return (Some(event_type.to_string()), title.trim().to_string());
+                }
+            }
+        }
+    }
+    (None, text.trim().to_string())
+}
+
+// 结束当前事件，收集到的描述行作为事件描述
+fn finish_markdown_event(current: &mut Option<(ImportedEvent, bool)>, description: &mut Vec<String>, result: &mut MarkdownEventImport) {
+    if let Some((mut event, _)) = current.take() {
+        let text = description.join("\n").trim().to_string();
+        event.description = (!text.is_empty()).then_some(text);
+        result.events.push(event);
+    }
+    description.clear();
+}
+
+// 解析纯文本笔记中的事件，支持两种写法：
+// 列表项 "- 2024-05-03 会议 与张三 讨论方案"（日期后可跟 HH:MM），下一行起缩进的内容作为描述；
+// 项目时间线导出的格式 "### 2024-05-03 10:00 【会议】标题"，其后的 "- 联系人：" 行和引用行分别作为联系人和描述
+pub fn parse_events_markdown(content: &str) -> MarkdownEventImport {
+    let mut result = MarkdownEventImport::default();
+    let mut current: Option<(ImportedEvent, bool)> = None;  // （事件，是否为时间线标题格式）
+    let mut description: Vec<String> = Vec::new();
+
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let trimmed = line.trim();
+        let indented = line.starts_with([' ', '\t']);
+        let bullet = ["- ", "* ", "+ "].iter().find_map(|b| trimmed.strip_prefix(b));
+
+        // 时间线导出的事件标题
+        if let Some(heading) = trimmed.strip_prefix("### ") {
+            finish_markdown_event(&mut current, &mut description, &mut result);
+            if let Some((event_date, rest)) = split_leading_datetime(heading) {
+                let (event_type, title) = split_bracketed_type(rest);
+                if !title.is_empty() {
+                    current = Some((ImportedEvent { title, description: None, event_date, event_type, project_name: None, contact_names: Vec::new() }, true));
+                }
+            }
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            finish_markdown_event(&mut current, &mut description, &mut result);
+            continue;
+        }
+
+        // 列表项：顶格且以日期开头的是新事件
+        if let Some(item) = bullet.filter(|_| !indented) {
+            if let Some((event_date, rest)) = split_leading_datetime(item) {
+                finish_markdown_event(&mut current, &mut description, &mut result);
+                let (event_type, title) = split_bracketed_type(rest);
+                if title.is_empty() {
+                    result.skipped_lines += 1;
+                } else {
+                    current = Some((ImportedEvent { title, description: None, event_date, event_type, project_name: None, contact_names: Vec::new() }, false));
+                }
+                continue;
+            }
+        }
+
+        match current.as_mut() {
+            Some((event, true)) => {
+                if let Some(names) = bullet.and_then(|b| b.strip_prefix("联系人：").or_else(|| b.strip_prefix("联系人:"))) {
+                    event.contact_names = names.split(['、', ',', '，']).map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect();
+                } else if bullet.is_some_and(|b| b.starts_with("同时关联")) {
+                    // 跨项目关联由导入时选择的项目决定
+                } else if let Some(quote) = trimmed.strip_prefix('>') {
+                    description.push(quote.trim().to_string());
+                } else if !trimmed.is_empty() {
+                    description.push(trimmed.to_string());
+                }
+            }
+            Some((_, false)) if indented && !trimmed.is_empty() => {
+                description.push(bullet.unwrap_or(trimmed).to_string());
+            }
+            _ => {
+                if bullet.is_some() {
+                    result.skipped_lines += 1;
+                }
+                if !trimmed.is_empty() {
+                    finish_markdown_event(&mut current, &mut description, &mut result);
+                }
+            }
+        }
+    }
+    finish_markdown_event(&mut current, &mut description, &mut result);
+    result
+}
+
+// 读取并解析 Markdown / 纯文本笔记中的事件
+pub fn parse_events_markdown_file(path: &str) -> Result<MarkdownEventImport, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(parse_events_markdown(&content))
+}
+
+// 名片/签名中的字段标签 -> 联系人字段
+fn map_card_label(label: &str) -> Option<&'static str> {
+    match label.trim().to_lowercase().as_str() {
+        "姓名" | "名字" | "name" => Some("name"),
+        "公司" | "单位" | "企业" | "机构" | "company" | "org" => Some("company"),
+        "职位" | "职务" | "头衔" | "title" | "position" => Some("title"),
+        "电话" | "手机" | "手机号" | "座机" | "tel" | "mobile" | "phone" | "m" | "t" => Some("phone"),
+        "邮箱" | "邮件" | "电子邮件" | "email" | "e-mail" | "mail" | "e" => Some("email"),
+        "地址" | "办公地址" | "address" | "addr" | "a" => Some("address"),
+        _ => None,
+    }
+}
+
+// 常见的职位关键词
+const CARD_TITLE_KEYWORDS: &[&str] = &[
+    "经理", "总监", "主管", "总裁", "总经理", "董事", "主任", "专员", "工程师", "设计师", "顾问", "负责人",
+    "合伙人", "助理", "秘书", "处长", "科长", "局长", "院长", "教授", "律师", "会计",
+    "ceo", "cto", "cfo", "coo", "manager", "director", "engineer", "founder", "president",
+];
+
+// 常见的单位关键词
+const CARD_COMPANY_KEYWORDS: &[&str] = &[
+    "公司", "集团", "有限", "科技", "大学", "学院", "研究院", "研究所", "银行", "医院", "事务所", "工作室", "中心",
+    "inc", "ltd", "co.", "corp", "llc", "gmbh",
+];
+
+// 从一段文本中提取电话号码（至少 7 位数字，允许 + - 空格和括号作为分隔）
+fn extract_phones(text: &str) -> Vec<String> {
+    let mut phones = Vec::new();
+    let mut current = String::new();
+    let flush = |current: &mut String, phones: &mut Vec<String>| {
+        let candidate = current.trim().trim_end_matches(['-', '(']).trim().to_string();
+        if candidate.chars().filter(|c| c.is_ascii_digit()).count() >= 7 {
+            phones.push(candidate);
+        }
+        current.clear();
+    };
+    for c in text.chars() {
+        if c.is_ascii_digit() || (matches!(c, '+' | '(' ) && current.trim().is_empty()) || (!current.is_empty() && matches!(c, '-' | ' ' | '(' | ')')) {
+            current.push(c);
+        } else {
+            flush(&mut current, &mut phones);
+        }
+    }
+    flush(&mut current, &mut phones);
+    phones
+}
+
+// 从文本中提取邮箱地址
+fn extract_email(text: &str) -> Option<String> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '：' | ':' | '，' | ',' | '；' | ';' | '<' | '>' | '(' | ')' | '（' | '）'))
+        .find(|token| {
+            let Some((local, domain)) = token.split_once('@') else {
+                return false;
+            };
+            !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+        })
+        .map(|token| token.to_string())
+}
+
+// 像不像姓名：2-4 个汉字，或 1-3 个英文单词
+fn looks_like_name(text: &str) -> bool {
+    let text = text.trim();
+    let cjk = text.chars().filter(|c| ('\u{4e00}'..='\u{9fff}').contains(c)).count();
+    if cjk > 0 {
+        return (2..=4).contains(&cjk) && cjk == text.chars().filter(|c| !c.is_whitespace()).count();
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    (1..=3).contains(&words.len()) && words.iter().all(|w| w.chars().all(|c| c.is_alphabetic() || c == '.' || c == '-'))
+}
+
+// 解析粘贴的名片、微信/飞书名片或邮件签名文本，尽量识别姓名、单位、职位、电话、邮箱、地址
+pub fn parse_contact_card(text: &str) -> ImportedContact {
+    let mut contact = ImportedContact::default();
+
+    // 按行和常见分隔符切分成片段，去掉 [名片] 之类的前缀
+    let segments: Vec<String> = text
+        .lines()
+        .flat_map(|line| line.split(['|', '｜', '丨', '·', '•']))
+        .map(|s| s.trim().trim_start_matches("[名片]").trim_start_matches("【名片】").trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut unlabeled = Vec::new();
+    for segment in segments {
+        // 带标签的字段，如 "手机：138..."、"Email: a@b.com"
+        if let Some((label, value)) = segment.split_once([':', '：']) {
+            if let Some(field) = map_card_label(label) {
+                let value = value.trim().to_string();
+                match field {
+                    "name" => contact.name = value,
+                    "company" => contact.company = Some(value),
+                    "title" => contact.title = Some(value),
+                    "phone" => contact.phones.extend(extract_phones(&value)),
+                    "email" => contact.email = extract_email(&value).or(Some(value)),
+                    "address" => contact.address = Some(value),
+                    _ => {}
+                }
+                // 同一行可能还带有其他字段，如 "M: 138... E: a@b.com"
+                if contact.email.is_none() {
+                    contact.email = extract_email(&segment);
+                }
+                continue;
+            }
+        }
+
+        if contact.email.is_none() {
+            if let Some(email) = extract_email(&segment) {
+                contact.email = Some(email);
+                continue;
+            }
+        }
+        let phones = extract_phones(&segment);
+        if !phones.is_empty() {
+            contact.phones.extend(phones);
+            continue;
+        }
+        unlabeled.push(segment);
+    }
+
+    // 未带标签的片段按关键词判断单位和职位，剩下的第一个像姓名的片段作为姓名
+    for segment in unlabeled {
+        let lower = segment.to_lowercase();
+        if contact.company.is_none() && CARD_COMPANY_KEYWORDS.iter().any(|k| lower.contains(k)) {
+            contact.company = Some(segment);
+        } else if contact.title.is_none() && CARD_TITLE_KEYWORDS.iter().any(|k| lower.contains(k)) {
+            contact.title = Some(segment);
+        } else if contact.name.is_empty() && looks_like_name(&segment) {
+            contact.name = segment;
+        } else if contact.address.is_none() && ["省", "市", "区", "路", "号", "街"].iter().filter(|k| segment.contains(*k)).count() >= 2 {
+            contact.address = Some(segment);
+        }
+    }
+
+    contact.phones.dedup();
+    contact
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labeled_wechat_cards_fill_every_field() {
+        let contact = parse_contact_card("[名片]\n姓名：张伟\n公司：北京星辰科技有限公司\n职位：产品经理\n手机：138 1234 5678\n邮箱：zhangwei@xingchen.com\n");
+        assert_eq!(contact.name, "张伟");
+        assert_eq!(contact.company.as_deref(), Some("北京星辰科技有限公司"));
+        assert_eq!(contact.title.as_deref(), Some("产品经理"));
+        assert_eq!(contact.phones, vec!["138 1234 5678"]);
+        assert_eq!(contact.email.as_deref(), Some("zhangwei@xingchen.com"));
+    }
+
+    #[test]
+    fn unlabeled_feishu_signatures_are_split_by_keywords() {
+        let contact = parse_contact_card("李娜 | 市场总监\n上海云帆集团\nM: +86 139-0000-1111  E: lina@yunfan.cn\n上海市浦东新区世纪大道100号");
+        assert_eq!(contact.name, "李娜");
+        assert_eq!(contact.title.as_deref(), Some("市场总监"));
+        assert_eq!(contact.company.as_deref(), Some("上海云帆集团"));
+        // 同一行的电话和邮箱都能识别
+        assert_eq!(contact.phones, vec!["+86 139-0000-1111"]);
+        assert_eq!(contact.email.as_deref(), Some("lina@yunfan.cn"));
+        assert_eq!(contact.address.as_deref(), Some("上海市浦东新区世纪大道100号"));
+    }
+
+    #[test]
+    fn email_signatures_skip_greetings_and_short_numbers() {
+        let contact = parse_contact_card("Best regards,\nJohn Smith\nTel: (010) 8888-6666\n分机 123\njohn.smith@acme.com");
+        assert_eq!(contact.name, "John Smith");
+        assert_eq!(contact.phones, vec!["(010) 8888-6666"]);
+        assert_eq!(contact.email.as_deref(), Some("john.smith@acme.com"));
+        assert_eq!((contact.company, contact.title), (None, None));
+
+        let empty = parse_contact_card("  \n");
+        assert!(empty.name.is_empty() && empty.phones.is_empty() && empty.email.is_none());
+    }
+}
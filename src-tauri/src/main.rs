@@ -1,5 +1,21 @@
 // src-tauri/src/main.rs
+mod changes;
 mod db;
+mod diff;
+mod export;
+mod external;
+mod file_policy;
+mod format;
+mod import;
+mod money;
+mod onboarding;
+mod polish;
+mod quick_add;
+mod redaction;
+mod scheduler;
+mod search;
+mod shortcuts;
+mod windows;
 
 use std::path::PathBuf;
 use std::fs;
@@ -15,14 +31,42 @@ fn create_project(name: String, description: Option<String>) -> Result<(), Strin
     Ok(())
 }
 
+// 获取项目列表（默认不含已归档的项目）
 #[tauri::command]
-fn get_projects() -> Result<Vec<db::Project>, String> {
+fn get_projects(include_archived: Option<bool>) -> Result<Vec<db::Project>, String> {
     println!("🔄 正在获取项目列表...");
-    let projects = db::fetch_projects().map_err(|e| e.to_string())?;
+    let projects = db::fetch_projects(include_archived.unwrap_or(false)).map_err(|e| e.to_string())?;
     println!("✅ 获取到 {} 个项目", projects.len());
     Ok(projects)
 }
 
+// 修改项目阶段（规划中、进行中、已搁置、已完成，只允许规定的转换）
+#[tauri::command]
+fn set_project_status(project_id: i32, status: String) -> Result<(), String> {
+    println!("🔄 正在将项目 {} 改为「{}」...", project_id, status);
+    let name = db::set_project_status(project_id, &status).map_err(|e| e.to_string())?;
+    println!("✅ 项目「{}」已改为「{}」", name, status.trim());
+    Ok(())
+}
+
+// 归档项目（不再出现在默认的项目列表中，数据保留，同时生成项目复盘）
+#[tauri::command]
+fn archive_project(project_id: i32) -> Result<(), String> {
+    println!("🔄 正在归档项目 {}...", project_id);
+    let name = db::set_project_archived(project_id, true).map_err(|e| e.to_string())?;
+    println!("✅ 项目「{}」已归档", name);
+    Ok(())
+}
+
+// 取消归档项目
+#[tauri::command]
+fn unarchive_project(project_id: i32) -> Result<(), String> {
+    println!("🔄 正在取消归档项目 {}...", project_id);
+    let name = db::set_project_archived(project_id, false).map_err(|e| e.to_string())?;
+    println!("✅ 项目「{}」已取消归档", name);
+    Ok(())
+}
+
 // 更新项目
 #[tauri::command]
 fn update_project(project_id: i32, name: String, description: Option<String>) -> Result<(), String> {
@@ -33,8 +77,106 @@ fn update_project(project_id: i32, name: String, description: Option<String>) ->
     Ok(())
 }
 
+// 删除项目（联系人关联、事件、活动和文件记录在一个事务中删除），然后删除项目文件夹和事件的内嵌图片
+#[tauri::command]
+fn delete_project(project_id: i32) -> Result<db::ProjectDeletion, String> {
+    println!("🔄 正在删除项目 {}...", project_id);
+    // 文件夹名包含项目名称，需在删除记录前取得
+    let folder = get_project_folder(project_id)?;
+    let deletion = db::delete_project(project_id).map_err(|e| e.to_string())?;
+    // 记录已删除，磁盘文件删除失败时只提示
+    if folder.exists() {
+        if let Err(e) = fs::remove_dir_all(&folder) {
+            println!("⚠️ 删除项目文件夹失败: {}", e);
+        }
+    }
+    for event_id in &deletion.deleted_event_ids {
+        if let Err(e) = remove_inline_images(*event_id) {
+            println!("⚠️ {}", e);
+        }
+    }
+    println!(
+        "✅ 已删除项目「{}」（事件 {} 个、活动 {} 个、文件 {} 个）",
+        deletion.name, deletion.events_deleted, deletion.activities_deleted, deletion.files_deleted
+    );
+    Ok(deletion)
+}
+
+// 复制项目（联系人关联、活动，include_files 为 true 时连同文件），返回新项目
+#[tauri::command]
+fn duplicate_project(project_id: i32, name: Option<String>, include_files: Option<bool>) -> Result<db::ProjectDuplicate, String> {
+    println!("🔄 正在复制项目 {}...", project_id);
+    let mut duplicate = db::duplicate_project(project_id, name.as_deref()).map_err(|e| e.to_string())?;
+    // 项目已复制，文件复制失败时只提示，不影响返回结果
+    if include_files.unwrap_or(false) {
+        match copy_project_files(project_id, duplicate.project_id) {
+            Ok(copied) => duplicate.files_copied = copied,
+            Err(e) => println!("⚠️ 复制项目文件失败: {}", e),
+        }
+    }
+    println!(
+        "✅ 已复制为项目「{}」（联系人 {} 个、活动 {} 个、文件 {} 个）",
+        duplicate.name, duplicate.contacts_copied, duplicate.activities_copied, duplicate.files_copied
+    );
+    Ok(duplicate)
+}
+
+// 把项目的文件复制到另一个项目（按版本从旧到新复制，保留原有版本号），返回复制成功的文件数；
+// 单个文件复制或登记失败时跳过该文件
+fn copy_project_files(from_project_id: i32, to_project_id: i32) -> Result<usize, String> {
+    let files = db::fetch_files_for_project(from_project_id).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Ok(0);
+    }
+    let folder = get_project_folder(to_project_id)?;
+    fs::create_dir_all(&folder).map_err(|e| format!("创建项目文件夹失败: {}", e))?;
+    let mut copied = 0;
+    for file in files.iter().rev() {
+        let dest_path = folder.join(&file.stored_name);
+        if let Err(e) = fs::copy(&file.file_path, &dest_path) {
+            println!("⚠️ 复制文件「{}」v{} 失败: {}", file.original_name, file.version, e);
+            continue;
+        }
+        if let Err(e) = db::insert_project_file(
+            to_project_id,
+            &file.original_name,
+            &file.stored_name,
+            &dest_path.to_string_lossy(),
+            file.file_size,
+            file.file_type.as_deref(),
+            file.version,
+        ) {
+            println!("⚠️ 登记文件「{}」v{} 失败: {}", file.original_name, file.version, e);
+            let _ = fs::remove_file(&dest_path);
+            continue;
+        }
+        copied += 1;
+    }
+    Ok(copied)
+}
+
 // 创建联系人
 #[tauri::command]
+fn create_contact_v2(contact: db::NewContact) -> Result<(), String> {
+    println!("🔄 正在创建联系人: {}", contact.name);
+    contact.validate()?;
+    let company = prepare_contact_email(contact.email.as_deref(), contact.company.clone())?;
+    let _ = db::insert_contact(
+        &contact.name,
+        contact.title.as_deref(),
+        contact.notes.as_deref(),
+        contact.tags.as_deref(),
+        contact.phone.as_deref(),
+        contact.email.as_deref(),
+        contact.address.as_deref(),
+        company.as_deref(),
+    ).map_err(|e| e.to_string())?;
+    println!("✅ 联系人创建成功: {}", contact.name);
+    Ok(())
+}
+
+// 旧版创建联系人接口（已弃用，请改用 create_contact_v2）
+#[tauri::command]
 fn create_contact(
     name: String,
     title: Option<String>,
@@ -45,18 +187,60 @@ fn create_contact(
     address: Option<String>,
     company: Option<String>,
 ) -> Result<(), String> {
-    println!("🔄 正在创建联系人: {}", name);
-    let _ = db::insert_contact(
-        &name,
-        title.as_deref(),
-        notes.as_deref(),
-        tags.as_deref(),
-        phone.as_deref(),
-        email.as_deref(),
-        address.as_deref(),
-        company.as_deref(),
-    ).map_err(|e| e.to_string())?;
-    println!("✅ 联系人创建成功: {}", name);
+    println!("⚠️ create_contact 已弃用，请改用 create_contact_v2");
+    create_contact_v2(db::NewContact { name, title, notes, tags, phone, email, address, company })
+}
+
+// 保存联系人前校验邮箱；未填单位时按邮箱域名自动补全，填了单位则记住域名对照
+fn prepare_contact_email(email: Option<&str>, company: Option<String>) -> Result<Option<String>, String> {
+    let Some(email) = email.map(|e| e.trim()).filter(|e| !e.is_empty()) else {
+        return Ok(company);
+    };
+    db::validate_email(email)?;
+    
+    match company.as_deref().map(|c| c.trim()).filter(|c| !c.is_empty()) {
+        Some(c) => {
+            let _ = db::remember_company_domain(email, c);
+            Ok(company)
+        }
+        None => {
+            let suggestion = db::suggest_company_for_email(email).map_err(|e| e.to_string())?;
+            if let Some(ref s) = suggestion {
+                println!("💡 按邮箱域名 {} 自动填写单位: {}", s.domain, s.company);
+            }
+            Ok(suggestion.map(|s| s.company).or(company))
+        }
+    }
+}
+
+// 按邮箱域名推荐单位
+#[tauri::command]
+fn suggest_company_for_email(email: String) -> Result<Option<db::CompanySuggestion>, String> {
+    db::validate_email(&email)?;
+    db::suggest_company_for_email(&email).map_err(|e| e.to_string())
+}
+
+// 获取邮箱域名与单位对照表
+#[tauri::command]
+fn get_company_domains() -> Result<Vec<db::CompanyDomain>, String> {
+    db::fetch_company_domains().map_err(|e| e.to_string())
+}
+
+// 新增或修改邮箱域名对照
+#[tauri::command]
+fn set_company_domain(domain: String, company: String) -> Result<(), String> {
+    println!("🔄 正在保存域名对照: {} -> {}", domain, company);
+    db::set_company_domain(&domain, &company).map_err(|e| e.to_string())?;
+    println!("✅ 域名对照已保存");
+    Ok(())
+}
+
+// 删除邮箱域名对照
+#[tauri::command]
+fn delete_company_domain(domain: String) -> Result<(), String> {
+    println!("🔄 正在删除域名对照: {}", domain);
+    db::delete_company_domain(&domain).map_err(|e| e.to_string())?;
+    println!("✅ 域名对照已删除");
     Ok(())
 }
 
@@ -83,6 +267,7 @@ fn update_contact(
     company: Option<String>,
 ) -> Result<(), String> {
     println!("🔄 正在更新联系人 {}...", contact_id);
+    let company = prepare_contact_email(email.as_deref(), company)?;
     db::update_contact(
         contact_id,
         &name,
@@ -128,6 +313,24 @@ fn get_project_contacts(project_id: i32) -> Result<Vec<(db::Contact, Option<Stri
     Ok(contacts)
 }
 
+// 获取项目组织架构（角色、汇报关系、单位）
+#[tauri::command]
+fn get_project_org_chart(project_id: i32) -> Result<db::ProjectOrgChart, String> {
+    println!("🔄 正在获取项目 {} 的组织架构...", project_id);
+    let chart = db::fetch_project_org_chart(project_id).map_err(|e| e.to_string())?;
+    println!("✅ 组织架构包含 {} 位成员、{} 个单位", chart.member_count, chart.companies.len());
+    Ok(chart)
+}
+
+// 设置联系人在项目中的上级（reports_to 为空表示清除）
+#[tauri::command]
+fn set_project_reports_to(project_id: i32, contact_id: i32, reports_to: Option<i32>) -> Result<(), String> {
+    println!("🔄 正在设置项目 {} 中联系人 {} 的上级: {:?}", project_id, contact_id, reports_to);
+    db::set_project_reports_to(project_id, contact_id, reports_to).map_err(|e| e.to_string())?;
+    println!("✅ 汇报关系已更新");
+    Ok(())
+}
+
 // 取消联系人与项目的关联
 #[tauri::command]
 fn unlink_contact_project(project_id: i32, contact_id: i32) -> Result<(), String> {
@@ -140,22 +343,12 @@ fn unlink_contact_project(project_id: i32, contact_id: i32) -> Result<(), String
 
 // ==================== 事件相关命令 ====================
 
-// 创建事件并关联联系人
+// 创建事件并关联联系人，返回描述中提到但未关联的联系人
 #[tauri::command]
-fn create_event(
-    title: String,
-    description: Option<String>,
-    event_date: String,
-    project_id: Option<i32>,
-    event_type: Option<String>,
-    contact_ids: Vec<i32>,
-    reminder_time: Option<String>,
-) -> Result<(), String> {
-    println!("🔄 正在创建事件: {}", title);
-    
-    if contact_ids.is_empty() {
-        return Err("事件必须关联至少一个联系人".to_string());
-    }
+fn create_event_v2(event: db::NewEvent) -> Result<Vec<db::Contact>, String> {
+    println!("🔄 正在创建事件: {}", event.title);
+    event.validate()?;
+    let db::NewEvent { title, description, event_date, project_id, event_type, contact_ids, reminder_time, extra_project_ids, channel } = event;
     
     let event_id = db::insert_event(
         &title,
@@ -169,6 +362,18 @@ fn create_event(
     db::link_contacts_to_event(event_id, &contact_ids)
         .map_err(|e| e.to_string())?;
     
+    // 未指定渠道时保留按事件类型推断的结果
+    if channel.is_some() {
+        db::set_event_channel(event_id as i32, channel.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+    
+    // 跨项目事件：关联其他项目
+    if !extra_project_ids.is_empty() {
+        db::update_event_projects(event_id as i32, &extra_project_ids)
+            .map_err(|e| e.to_string())?;
+    }
+    
     // 获取项目名称（如果有）
     let project_name = if let Some(pid) = project_id {
         db::get_project_name(pid).ok()
@@ -193,42 +398,214 @@ fn create_event(
         &contact_names,
     );
     
-    // 如果事件关联了项目，自动将联系人绑定到项目（跳过已存在的）
-    if let Some(pid) = project_id {
-        for contact_id in &contact_ids {
-            // 使用 INSERT OR REPLACE，已存在的联系人会被静默跳过
-            let _ = db::link_contact_to_project(pid, *contact_id, None, None);
+    // 如果事件关联了项目，自动将联系人绑定到项目（已在项目中的联系人保留原有角色和备注）
+    for pid in project_id.iter().chain(extra_project_ids.iter()) {
+        match db::add_contacts_to_project(*pid, &contact_ids) {
+            Ok(added) => println!("✅ 已自动将 {} 个联系人绑定到项目 {}", added, pid),
+            Err(e) => println!("⚠️ 自动绑定联系人到项目 {} 失败: {}", pid, e),
         }
-        println!("✅ 已自动将 {} 个联系人绑定到项目 {}", contact_ids.len(), pid);
     }
     
+    // 保存后识别描述中提到但未关联的联系人，返回给前端提示用户确认（确认后调用 apply_detected_mentions）
+    let mentioned = detect_mentions_after_save(event_id as i32);
+    
     println!("✅ 事件创建成功: {}, 关联 {} 个联系人", title, contact_ids.len());
+    Ok(mentioned)
+}
+
+// 识别已保存事件描述中提到的联系人；事件已经保存，识别失败时只提示并返回空列表，避免前端重试时重复创建
+fn detect_mentions_after_save(event_id: i32) -> Vec<db::Contact> {
+    match db::detect_event_mentions(event_id) {
+        Ok(mentioned) => {
+            if !mentioned.is_empty() {
+                println!("💡 描述中提到 {} 位未关联的联系人", mentioned.len());
+            }
+            mentioned
+        }
+        Err(e) => {
+            println!("⚠️ 识别提到的联系人失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// 旧版创建事件接口（已弃用，请改用 create_event_v2）
+#[tauri::command]
+fn create_event(
+    title: String,
+    description: Option<String>,
+    event_date: String,
+    project_id: Option<i32>,
+    event_type: Option<String>,
+    contact_ids: Vec<i32>,
+    reminder_time: Option<String>,
+    extra_project_ids: Option<Vec<i32>>,
+    channel: Option<String>,
+) -> Result<Vec<db::Contact>, String> {
+    println!("⚠️ create_event 已弃用，请改用 create_event_v2");
+    create_event_v2(db::NewEvent {
+        title,
+        description,
+        event_date,
+        project_id,
+        event_type,
+        contact_ids,
+        reminder_time,
+        extra_project_ids: extra_project_ids.unwrap_or_default(),
+        channel,
+    })
+}
+
+// 创建事件时推荐参与人（根据项目成员、历史共同参与和标题/描述中的姓名）
+#[tauri::command]
+fn suggest_event_contacts(
+    project_id: Option<i32>,
+    title_text: String,
+    selected_contact_ids: Option<Vec<i32>>,
+) -> Result<Vec<db::ContactSuggestion>, String> {
+    println!("🔄 正在推荐事件参与人...");
+    let suggestions = db::suggest_event_contacts(
+        project_id,
+        &title_text,
+        &selected_contact_ids.unwrap_or_default(),
+    ).map_err(|e| e.to_string())?;
+    println!("✅ 推荐 {} 位联系人", suggestions.len());
+    Ok(suggestions)
+}
+
+// 识别事件描述中提到但未关联的联系人（保存事件后由前端调用，供用户确认）
+#[tauri::command]
+fn detect_event_mentions(event_id: i32) -> Result<Vec<db::Contact>, String> {
+    println!("🔄 正在识别事件 {} 中提到的联系人...", event_id);
+    let contacts = db::detect_event_mentions(event_id).map_err(|e| e.to_string())?;
+    println!("✅ 识别到 {} 位未关联的联系人", contacts.len());
+    Ok(contacts)
+}
+
+// 将确认的提及联系人关联到事件
+#[tauri::command]
+fn apply_detected_mentions(event_id: i32, contact_ids: Vec<i32>) -> Result<(), String> {
+    println!("🔄 正在为事件 {} 关联 {} 位提到的联系人...", event_id, contact_ids.len());
+    db::link_contacts_to_event(event_id as i64, &contact_ids).map_err(|e| e.to_string())?;
+    println!("✅ 关联成功");
     Ok(())
 }
 
-// 获取联系人时间线
+// 快速录入：解析 "周五 14:00 和 @李雷 开会 #项目A !提醒30m" 这样的文本
+// save 为 false 时只返回解析结果供确认，为 true 时直接创建事件
+#[tauri::command]
+fn quick_add(text: String, save: Option<bool>) -> Result<quick_add::QuickAddResult, String> {
+    println!("🔄 正在解析快速录入: {}", text);
+    
+    let today = Local::now().date_naive();
+    let week_start = db::load_display_preferences().map_err(|e| e.to_string())?.week_start;
+    let tokens = quick_add::parse_quick_add(&text, today, week_start);
+    
+    let contacts = db::fetch_contacts().map_err(|e| e.to_string())?;
+    let projects = db::fetch_projects(false).map_err(|e| e.to_string())?;
+    let mut result = quick_add::resolve_quick_add(tokens, &contacts, &projects, today);
+    
+    if save.unwrap_or(false) {
+        create_event_v2(db::NewEvent {
+            title: result.title.clone(),
+            description: None,
+            event_date: result.event_date.clone(),
+            project_id: result.project_id,
+            event_type: result.event_type.clone(),
+            contact_ids: result.contacts.iter().map(|c| c.id).collect(),
+            reminder_time: result.reminder_time.clone(),
+            extra_project_ids: Vec::new(),
+            channel: None,
+        })?;
+        result.saved = true;
+    }
+    
+    println!("✅ 解析完成: {} @ {}", result.title, result.event_date);
+    Ok(result)
+}
+
+// 在阻塞线程池中执行数据库查询：同步命令在主线程上运行，时间线等较重的查询、上传扫描等耗时操作放在这里，界面不会卡住
+async fn run_blocking<T, E, F>(query: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    tokio::task::spawn_blocking(query).await.map_err(|e| E::from(e.to_string()))?
+}
+
+// 获取联系人时间线（可按事件类型、项目和日期范围筛选，默认不含已归档项目的事件）
 #[tauri::command]
-fn get_contact_timeline(contact_id: i32) -> Result<Vec<db::EventWithDetails>, String> {
+async fn get_contact_timeline(
+    contact_id: i32,
+    event_type: Option<String>,
+    project_id: Option<i32>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    include_archived: Option<bool>,
+) -> Result<Vec<db::EventWithDetails>, String> {
     println!("🔄 正在获取联系人 {} 的时间线...", contact_id);
-    let events = db::fetch_events_for_contact(contact_id).map_err(|e| e.to_string())?;
+    let filter = db::TimelineFilter { event_type, project_id, start_date, end_date, include_archived: include_archived.unwrap_or(false) };
+    let events = run_blocking(move || {
+        let events = db::fetch_events_for_contact(contact_id, &filter).map_err(|e| e.to_string())?;
+        if let Err(e) = db::record_access("contact", contact_id) {
+            println!("⚠️ 记录打开失败: {}", e);
+        }
+        Ok::<_, String>(events)
+    }).await?;
     println!("✅ 获取到 {} 个事件", events.len());
     Ok(events)
 }
 
+// 获取按项目或月份分组的联系人时间线（group_by 为 project 或 month）
+#[tauri::command]
+async fn get_contact_timeline_groups(
+    contact_id: i32,
+    group_by: String,
+    event_type: Option<String>,
+    project_id: Option<i32>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    include_archived: Option<bool>,
+) -> Result<Vec<db::TimelineGroup>, String> {
+    println!("🔄 正在按 {} 分组获取联系人 {} 的时间线...", group_by, contact_id);
+    let filter = db::TimelineFilter { event_type, project_id, start_date, end_date, include_archived: include_archived.unwrap_or(false) };
+    let events = run_blocking(move || db::fetch_events_for_contact(contact_id, &filter).map_err(|e| e.to_string())).await?;
+    let groups = db::group_timeline(events, &group_by)?;
+    println!("✅ 获取到 {} 组", groups.len());
+    Ok(groups)
+}
+
+// 获取项目操作日志（含跨项目事件），可按日期范围筛选
+#[tauri::command]
+fn get_project_log(project_id: i32, start_date: Option<String>, end_date: Option<String>) -> Result<Vec<db::OperationLog>, String> {
+    println!("🔄 正在获取项目 {} 的操作日志...", project_id);
+    let logs = db::fetch_project_log(project_id, start_date.as_deref(), end_date.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 条日志", logs.len());
+    Ok(logs)
+}
+
 // 获取项目时间线
 #[tauri::command]
-fn get_project_timeline(project_id: i32) -> Result<Vec<db::EventWithDetails>, String> {
+async fn get_project_timeline(project_id: i32) -> Result<Vec<db::EventWithDetails>, String> {
     println!("🔄 正在获取项目 {} 的时间线...", project_id);
-    let events = db::fetch_events_for_project(project_id).map_err(|e| e.to_string())?;
+    let events = run_blocking(move || {
+        let events = db::fetch_events_for_project(project_id).map_err(|e| e.to_string())?;
+        if let Err(e) = db::record_access("project", project_id) {
+            println!("⚠️ 记录打开失败: {}", e);
+        }
+        Ok::<_, String>(events)
+    }).await?;
     println!("✅ 获取到 {} 个事件", events.len());
     Ok(events)
 }
 
-// 获取所有事件
+// 获取所有事件（默认不含已归档项目的事件）
 #[tauri::command]
-fn get_all_events() -> Result<Vec<db::EventWithDetails>, String> {
+async fn get_all_events(include_archived: Option<bool>) -> Result<Vec<db::EventWithDetails>, String> {
     println!("🔄 正在获取所有事件...");
-    let events = db::fetch_all_events().map_err(|e| e.to_string())?;
+    let include_archived = include_archived.unwrap_or(false);
+    let events = run_blocking(move || db::fetch_all_events(include_archived).map_err(|e| e.to_string())).await?;
     println!("✅ 获取到 {} 个事件", events.len());
     Ok(events)
 }
@@ -238,23 +615,20 @@ fn get_all_events() -> Result<Vec<db::EventWithDetails>, String> {
 fn delete_event(event_id: i32) -> Result<(), String> {
     println!("🔄 正在删除事件 {}...", event_id);
     db::delete_event(event_id).map_err(|e| e.to_string())?;
+    // 事件已删除，图片删除失败时只提示
+    if let Err(e) = remove_inline_images(event_id) {
+        println!("⚠️ {}", e);
+    }
     println!("✅ 事件删除成功");
     Ok(())
 }
 
-// 更新事件
+// 更新事件，返回描述中提到但未关联的联系人
 #[tauri::command]
-fn update_event(
-    event_id: i32,
-    title: String,
-    description: Option<String>,
-    event_date: String,
-    project_id: Option<i32>,
-    event_type: Option<String>,
-    reminder_time: Option<String>,
-    contact_ids: Vec<i32>,
-) -> Result<(), String> {
-    println!("🔄 正在更新事件 {}...", event_id);
+fn update_event_v2(event: db::UpdateEvent) -> Result<Vec<db::Contact>, String> {
+    println!("🔄 正在更新事件 {}...", event.event_id);
+    event.validate()?;
+    let db::UpdateEvent { event_id, title, description, event_date, project_id, event_type, reminder_time, contact_ids, extra_project_ids, channel } = event;
     
     // 更新事件基本信息
     db::update_event(
@@ -271,125 +645,541 @@ fn update_event(
     db::update_event_contacts(event_id, &contact_ids)
         .map_err(|e| e.to_string())?;
     
+    // 未指定渠道时按新的事件类型推断（无法推断则保留原渠道）
+    let channel = channel.or_else(|| event_type.as_deref().and_then(db::channel_for_event_type).map(|c| c.to_string()));
+    if channel.is_some() {
+        db::set_event_channel(event_id, channel.as_deref()).map_err(|e| e.to_string())?;
+    }
+    
+    // 未传入时保留原有的其他关联项目（兼容只支持单项目的调用方）
+    if let Some(ids) = extra_project_ids {
+        db::update_event_projects(event_id, &ids).map_err(|e| e.to_string())?;
+    }
+    
+    // 保存后识别描述中提到但未关联的联系人，返回给前端提示用户确认（确认后调用 apply_detected_mentions）
+    let mentioned = detect_mentions_after_save(event_id);
+    
     println!("✅ 事件更新成功");
-    Ok(())
+    Ok(mentioned)
 }
 
-// ==================== 项目文件管理相关命令 ====================
+// 旧版更新事件接口（已弃用，请改用 update_event_v2）
+#[tauri::command]
+fn update_event(
+    event_id: i32,
+    title: String,
+    description: Option<String>,
+    event_date: String,
+    project_id: Option<i32>,
+    event_type: Option<String>,
+    reminder_time: Option<String>,
+    contact_ids: Vec<i32>,
+    extra_project_ids: Option<Vec<i32>>,
+    channel: Option<String>,
+) -> Result<Vec<db::Contact>, String> {
+    println!("⚠️ update_event 已弃用，请改用 update_event_v2");
+    update_event_v2(db::UpdateEvent {
+        event_id,
+        title,
+        description,
+        event_date,
+        project_id,
+        event_type,
+        reminder_time,
+        contact_ids,
+        extra_project_ids,
+        channel,
+    })
+}
 
-// 获取项目文件存储的根目录
-fn get_files_root_dir() -> Result<PathBuf, String> {
-    let app_data_dir = dirs::data_local_dir()
-        .ok_or("无法获取应用数据目录")?;
-    let files_dir = app_data_dir.join("mindmirror").join("project_files");
-    Ok(files_dir)
+// ==================== 快捷键相关命令 ====================
+
+// 获取所有动作的全局快捷键
+#[tauri::command]
+fn get_shortcuts() -> Result<Vec<shortcuts::ShortcutBinding>, String> {
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    Ok(shortcuts::load_bindings(&settings))
 }
 
-// 清理文件夹名称，移除不允许的字符
-fn sanitize_folder_name(name: &str) -> String {
-    // 替换文件系统不允许的字符
-    let sanitized: String = name.chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-            _ => c
-        })
-        .collect();
+// 设置动作的快捷键（accelerator 为 None 时恢复默认，为空字符串时停用），返回更新后的列表
+#[tauri::command]
+fn set_shortcut(
+    app_handle: tauri::AppHandle,
+    action_id: String,
+    accelerator: Option<String>,
+) -> Result<Vec<shortcuts::ShortcutBinding>, String> {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
     
-    // 移除首尾空格和点
-    let trimmed = sanitized.trim().trim_matches('.');
+    println!("🔄 正在设置快捷键 {}: {:?}", action_id, accelerator);
+    let action = shortcuts::find_action(&action_id).ok_or(format!("未知的快捷键动作: {}", action_id))?;
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    let bindings = shortcuts::load_bindings(&settings);
     
-    // 如果结果为空，使用默认名称
-    if trimmed.is_empty() {
-        "unnamed_project".to_string()
-    } else {
-        trimmed.to_string()
+    // 新快捷键：None 为默认值，空字符串为停用
+    let new_accelerator = match accelerator.as_deref().map(|a| a.trim()) {
+        None => Some(action.default_accelerator.to_string()),
+        Some("") => None,
+        Some(a) => Some(shortcuts::normalize_accelerator(a)?),
+    };
+    if let Some(ref a) = new_accelerator {
+        if let Some(other) = shortcuts::find_conflict(&bindings, action.id, a) {
+            return Err(format!("快捷键 {} 已被「{}」使用", a, other.label));
+        }
     }
-}
-
-// 获取项目的文件夹路径（使用项目名称作为文件夹名）
-fn get_project_folder(project_id: i32) -> Result<PathBuf, String> {
-    let root = get_files_root_dir()?;
-    
-    // 获取项目名称
-    let project_name = db::get_project_name(project_id)
-        .map_err(|e| format!("获取项目名称失败: {}", e))?;
     
-    // 清理项目名称作为文件夹名
-    let folder_name = sanitize_folder_name(&project_name);
+    // 先注册新快捷键，成功后再注销旧的并保存设置
+    let old_accelerator = bindings.iter().find(|b| b.id == action.id).and_then(|b| b.accelerator.clone());
+    if new_accelerator != old_accelerator {
+        if let Some(ref a) = new_accelerator {
+            app_handle.global_shortcut().register(a.as_str())
+                .map_err(|e| format!("注册快捷键 {} 失败（可能已被其他程序占用）: {}", a, e))?;
+        }
+        if let Some(ref a) = old_accelerator {
+            let _ = app_handle.global_shortcut().unregister(a.as_str());
+        }
+    }
     
-    // 添加项目ID后缀以确保唯一性（避免重名项目冲突）
-    let unique_folder_name = format!("{}_{}", folder_name, project_id);
+    // 恢复默认时删除设置项，停用时保存空字符串
+    let value = accelerator.map(|_| new_accelerator.clone().unwrap_or_default());
+    db::set_setting(&shortcuts::setting_key(action.id), value.as_deref()).map_err(|e| e.to_string())?;
     
-    Ok(root.join(unique_folder_name))
+    println!("✅ 快捷键设置成功: {} -> {}", action.label, new_accelerator.as_deref().unwrap_or("停用"));
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    Ok(shortcuts::load_bindings(&settings))
 }
 
-// 上传文件到项目
+// ==================== 窗口相关命令 ====================
+
+// 在独立窗口中打开项目或联系人（已打开时切换到该窗口），返回窗口标签
 #[tauri::command]
-fn upload_file_to_project(
-    project_id: i32,
-    source_path: String,
-    contact_id: Option<i32>,
-) -> Result<db::ProjectFile, String> {
-    println!("🔄 正在上传文件到项目 {}: {}", project_id, source_path);
+fn open_entity_window(app_handle: tauri::AppHandle, entity: db::EntityRef) -> Result<String, String> {
+    use tauri::Manager;
     
-    let source = PathBuf::from(&source_path);
-    if !source.exists() {
-        return Err(format!("源文件不存在: {}", source_path));
+    entity.validate()?;
+    let label = windows::window_label(&entity.entity_type, entity.entity_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(label);
     }
     
-    // 获取原始文件名
-    let original_name = source.file_name()
-        .and_then(|n| n.to_str())
-        .ok_or("无法获取文件名")?
-        .to_string();
+    println!("🔄 正在打开独立窗口: {}", label);
+    let title = db::get_entity_title(&entity.entity_type, entity.entity_id)
+        .map_err(|e| e.to_string())?
+        .ok_or(format!("记录不存在: {} {}", entity.entity_type, entity.entity_id))?;
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    let state = windows::load_state(&settings, &label).filter(|s| is_on_screen(&app_handle, s));
     
-    // 获取文件扩展名
-    let extension = source.extension()
-        .and_then(|e| e.to_str())
-        .map(|s| s.to_string());
+    let url = tauri::WebviewUrl::App(windows::window_url(&entity.entity_type, entity.entity_id).into());
+    let builder = tauri::WebviewWindowBuilder::new(&app_handle, &label, url)
+        .title(format!("{} - MemoryStack", title))
+        .min_inner_size(windows::MIN_WIDTH, windows::MIN_HEIGHT);
+    let builder = match state {
+        Some(s) => builder.inner_size(s.width, s.height).position(s.x, s.y),
+        None => builder.inner_size(windows::DEFAULT_WIDTH, windows::DEFAULT_HEIGHT).center(),
+    };
+    let window = builder.build().map_err(|e| format!("打开窗口失败: {}", e))?;
     
-    // 获取文件大小
-    let metadata = fs::metadata(&source).map_err(|e| e.to_string())?;
-    let file_size = metadata.len() as i64;
+    match state {
+        Some(s) => {
+            windows::remember(&label, s);
+            if s.maximized {
+                let _ = window.maximize();
+            }
+        }
+        None => track_window_state(&window),
+    }
     
-    // 获取或创建项目文件夹
-    let project_folder = get_project_folder(project_id)?;
-    fs::create_dir_all(&project_folder).map_err(|e| format!("创建项目文件夹失败: {}", e))?;
+    // 移动/缩放时记录状态，窗口关闭时写入设置
+    let tracked = window.clone();
+    window.on_window_event(move |event| match event {
+        tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => track_window_state(&tracked),
+        tauri::WindowEvent::Destroyed => save_window_state(tracked.label()),
+        _ => {}
+    });
     
-    // 检查是否存在同名文件，获取版本号
-    let current_version = db::get_latest_file_version(project_id, &original_name)
-        .map_err(|e| e.to_string())?;
-    let new_version = current_version + 1;
-    
-    // 生成存储文件名（如果是新版本，添加时间戳）
-    let stored_name = if new_version > 1 {
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        if let Some(ref ext) = extension {
-            let name_without_ext = original_name.strip_suffix(&format!(".{}", ext)).unwrap_or(&original_name);
-            format!("{}_{}.{}", name_without_ext, timestamp, ext)
-        } else {
-            format!("{}_{}", original_name, timestamp)
+    println!("✅ 独立窗口已打开: {}", label);
+    Ok(label)
+}
+
+// 保存的位置是否还在某个显示器上（如第二块屏幕已断开则不恢复位置）
+fn is_on_screen(app_handle: &tauri::AppHandle, state: &windows::WindowState) -> bool {
+    let Ok(monitors) = app_handle.available_monitors() else {
+        return true;
+    };
+    monitors.iter().any(|monitor| {
+        let scale = monitor.scale_factor();
+        let position = monitor.position().to_logical::<f64>(scale);
+        let size = monitor.size().to_logical::<f64>(scale);
+        state.x >= position.x && state.x < position.x + size.width
+            && state.y >= position.y && state.y < position.y + size.height
+    })
+}
+
+// 记录独立窗口当前的位置和大小（最小化时不记录，最大化时保留之前的位置和大小）
+fn track_window_state(window: &tauri::WebviewWindow) {
+    let label = window.label();
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let state = if window.is_maximized().unwrap_or(false) {
+        match windows::pending(label) {
+            Some(previous) => windows::WindowState { maximized: true, ..previous },
+            None => return,
         }
     } else {
-        original_name.clone()
+        let (Ok(scale), Ok(position), Ok(size)) = (window.scale_factor(), window.outer_position(), window.inner_size()) else {
+            return;
+        };
+        let position = position.to_logical::<f64>(scale);
+        let size = size.to_logical::<f64>(scale);
+        windows::WindowState { x: position.x, y: position.y, width: size.width, height: size.height, maximized: false }
+    };
+    windows::remember(label, state);
+}
+
+// 窗口关闭时保存最后记录的状态
+fn save_window_state(label: &str) {
+    let Some(state) = windows::take_pending(label) else {
+        return;
+    };
+    let value = match serde_json::to_string(&state) {
+        Ok(value) => value,
+        Err(e) => {
+            println!("⚠️ 保存窗口状态失败 {}: {}", label, e);
+            return;
+        }
     };
+    if let Err(e) = db::set_setting(&windows::setting_key(label), Some(&value)) {
+        println!("⚠️ 保存窗口状态失败 {}: {}", label, e);
+    }
+}
+
+// ==================== 草稿相关命令 ====================
+
+// 保存表单草稿（前端定时自动保存，传入 draft_id 时覆盖原草稿）
+#[tauri::command]
+fn save_draft(
+    entity_type: String,
+    payload: String,
+    draft_id: Option<i32>,
+    entity_id: Option<i32>,
+) -> Result<db::Draft, String> {
+    let draft = db::save_draft(draft_id, &entity_type, entity_id, &payload).map_err(|e| e.to_string())?;
+    println!("✅ 草稿已保存: {} #{}", draft.entity_type, draft.id);
+    Ok(draft)
+}
+
+// 列出草稿（启动或打开表单时用于恢复）
+#[tauri::command]
+fn list_drafts(entity_type: Option<String>) -> Result<Vec<db::Draft>, String> {
+    db::fetch_drafts(entity_type.as_deref()).map_err(|e| e.to_string())
+}
+
+// 删除草稿
+#[tauri::command]
+fn delete_draft(draft_id: i32) -> Result<(), String> {
+    if !db::delete_draft(draft_id).map_err(|e| e.to_string())? {
+        return Err(format!("草稿不存在: {}", draft_id));
+    }
+    println!("✅ 草稿已删除: #{}", draft_id);
+    Ok(())
+}
+
+// ==================== 每日记录相关命令 ====================
+
+// 记录今天（或指定日期）的心情和感想，可附带 1-5 的心情/精力评分
+#[tauri::command]
+fn log_day_note(
+    text: String,
+    mood: Option<String>,
+    date: Option<String>,
+    mood_rating: Option<i32>,
+    energy_rating: Option<i32>,
+) -> Result<db::DayNote, String> {
+    let date = date.unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
+    println!("🔄 正在记录 {} 的每日记录...", date);
+    let note = db::insert_day_note(&date, &text, mood.as_deref(), mood_rating, energy_rating).map_err(|e| e.to_string())?;
+    println!("✅ 每日记录已保存: #{}", note.id);
+    Ok(note)
+}
+
+// 获取一段时间内的每日记录
+#[tauri::command]
+fn get_day_notes(start_date: String, end_date: String) -> Result<Vec<db::DayNote>, String> {
+    db::fetch_day_notes(&start_date, &end_date).map_err(|e| e.to_string())
+}
+
+// 获取身心状态趋势（默认最近 30 天）：每天的评分、事件数和逾期活动数及其相关性
+#[tauri::command]
+fn get_wellbeing_trend(start_date: Option<String>, end_date: Option<String>) -> Result<db::WellbeingTrend, String> {
+    let today = Local::now().date_naive();
+    let end_date = end_date.unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    let start_date = start_date.unwrap_or_else(|| (today - chrono::Duration::days(29)).format("%Y-%m-%d").to_string());
+    println!("🔄 正在统计身心状态趋势 ({} - {})...", start_date, end_date);
+    let trend = db::fetch_wellbeing_trend(&start_date, &end_date).map_err(|e| e.to_string())?;
+    println!("✅ 身心状态趋势统计完成，共 {} 天", trend.days.len());
+    Ok(trend)
+}
+
+// 删除每日记录
+#[tauri::command]
+fn delete_day_note(note_id: i32) -> Result<(), String> {
+    if !db::delete_day_note(note_id).map_err(|e| e.to_string())? {
+        return Err(format!("每日记录不存在: {}", note_id));
+    }
+    println!("✅ 每日记录已删除: #{}", note_id);
+    Ok(())
+}
+
+// 设置每日记录是否私密（私密记录不写入总结）
+#[tauri::command]
+fn set_day_note_private(note_id: i32, is_private: bool) -> Result<(), String> {
+    if !db::set_day_note_private(note_id, is_private).map_err(|e| e.to_string())? {
+        return Err(format!("每日记录不存在: {}", note_id));
+    }
+    println!("✅ 每日记录 #{} {}", note_id, if is_private { "已设为私密" } else { "已取消私密" });
+    Ok(())
+}
+
+// ==================== 清理建议相关命令 ====================
+
+// 获取清理建议
+#[tauri::command]
+fn get_cleanup_suggestions() -> Result<Vec<db::CleanupSuggestion>, String> {
+    println!("🔄 正在检查可清理的记录...");
+    let suggestions = db::fetch_cleanup_suggestions().map_err(|e| e.to_string())?;
+    println!("✅ 共 {} 条清理建议", suggestions.len());
+    Ok(suggestions)
+}
+
+// 执行一条清理建议（delete 删除；空项目还可以 archive 归档），返回处理结果说明
+#[tauri::command]
+fn apply_cleanup_suggestion(kind: String, entity_id: i32, action: Option<String>) -> Result<String, String> {
+    let action = action.unwrap_or_else(|| "delete".to_string());
+    match (kind.as_str(), action.as_str()) {
+        (_, "delete") | (db::CLEANUP_EMPTY_PROJECT, "archive") => {}
+        _ => return Err(format!("不支持的清理操作: {}", action)),
+    }
+    println!("🔄 正在清理 {} #{}（{}）...", kind, entity_id, action);
     
-    // 复制文件到项目文件夹
-    let dest_path = project_folder.join(&stored_name);
-    fs::copy(&source, &dest_path).map_err(|e| format!("复制文件失败: {}", e))?;
+    let message = match kind.as_str() {
+        db::CLEANUP_EMPTY_PROJECT if action == "archive" => {
+            let name = db::set_project_archived(entity_id, true).map_err(|e| e.to_string())?;
+            format!("已归档空项目「{}」", name)
+        }
+        db::CLEANUP_EMPTY_PROJECT => {
+            let folder = get_project_folder(entity_id).ok();
+            let name = db::delete_empty_project(entity_id).map_err(|e| e.to_string())?;
+            // 空项目的文件夹也是空的，删除失败（如用户手动放了文件）时保留
+            if let Some(folder) = folder {
+                let _ = fs::remove_dir(&folder);
+            }
+            format!("已删除空项目「{}」", name)
+        }
+        db::CLEANUP_INACTIVE_CONTACT => {
+            let folder = get_contact_folder(entity_id)?;
+            // 删除前重新检查联系人仍不活跃，记录删除成功后才删除文件夹
+            let name = db::delete_inactive_contact(entity_id).map_err(|e| e.to_string())?;
+            if folder.exists() {
+                if let Err(e) = fs::remove_dir_all(&folder) {
+                    println!("⚠️ 删除联系人文件夹失败: {}", e);
+                }
+            }
+            format!("已删除联系人「{}」", name)
+        }
+        db::CLEANUP_SUPERSEDED_FILE => {
+            // 旧版本移入回收站，移动失败的保留记录以便重试
+            let old_versions = db::fetch_superseded_file_versions(entity_id).map_err(|e| e.to_string())?;
+            let mut removed_ids = Vec::new();
+            for (file_id, project_id, file_path) in &old_versions {
+                let path = PathBuf::from(file_path);
+                let result = if path.exists() {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    move_to_trash(&path, &format!("{}_{}_{}", project_id, file_id, name))
+                } else {
+                    Ok(())
+                };
+                match result {
+                    Ok(()) => removed_ids.push(*file_id),
+                    Err(e) => println!("⚠️ 旧版本 {}: {}", file_id, e),
+                }
+            }
+            let removed = db::delete_project_files(&removed_ids).map_err(|e| e.to_string())?;
+            format!("已将 {} 个旧版本移入回收站", removed)
+        }
+        db::CLEANUP_STALE_DRAFT => {
+            if !db::delete_draft(entity_id).map_err(|e| e.to_string())? {
+                return Err(format!("草稿不存在: {}", entity_id));
+            }
+            "已删除草稿".to_string()
+        }
+        _ => return Err(format!("未知的清理建议类型: {}", kind)),
+    };
+    
+    println!("✅ {}", message);
+    Ok(message)
+}
+
+// ==================== 项目文件管理相关命令 ====================
+
+// 获取项目文件存储的根目录
+fn get_files_root_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?;
+    let files_dir = app_data_dir.join("mindmirror").join("project_files");
+    Ok(files_dir)
+}
+
+// 清理文件夹名称，移除不允许的字符
+fn sanitize_folder_name(name: &str) -> String {
+    // 替换文件系统不允许的字符
+    let sanitized: String = name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c
+        })
+        .collect();
+    
+    // 移除首尾空格和点
+    let trimmed = sanitized.trim().trim_matches('.');
     
+    // 如果结果为空，使用默认名称
+    if trimmed.is_empty() {
+        "unnamed_project".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// 获取项目的文件夹路径（使用项目名称作为文件夹名）
+fn get_project_folder(project_id: i32) -> Result<PathBuf, String> {
+    let root = get_files_root_dir()?;
+    
+    // 获取项目名称
+    let project_name = db::get_project_name(project_id)
+        .map_err(|e| format!("获取项目名称失败: {}", e))?;
+    
+    // 清理项目名称作为文件夹名
+    let folder_name = sanitize_folder_name(&project_name);
+    
+    // 添加项目ID后缀以确保唯一性（避免重名项目冲突）
+    let unique_folder_name = format!("{}_{}", folder_name, project_id);
+    
+    Ok(root.join(unique_folder_name))
+}
+
+// 生成存储文件名（新版本在文件名后添加时间戳，避免覆盖旧版本）
+fn versioned_stored_name(original_name: &str, extension: Option<&str>, version: i32) -> String {
+    if version <= 1 {
+        return original_name.to_string();
+    }
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    if let Some(ext) = extension {
+        let name_without_ext = original_name.strip_suffix(&format!(".{}", ext)).unwrap_or(original_name);
+        format!("{}_{}.{}", name_without_ext, timestamp, ext)
+    } else {
+        format!("{}_{}", original_name, timestamp)
+    }
+}
+
+// 复制上传文件并写入数据库记录（record 闭包负责插入记录并返回ID）
+// 先登记暂存记录并复制到 .partial 临时文件，写入数据库后再清除记录，
+// 中途崩溃时由启动清理删除残留文件
+fn copy_with_staging(
+    source: &std::path::Path,
+    dest_path: &std::path::Path,
+    project_id: Option<i32>,
+    contact_id: Option<i32>,
+    record: impl FnOnce(&str) -> Result<i64, String>,
+) -> Result<i64, String> {
     let dest_path_str = dest_path.to_string_lossy().to_string();
+    let staging_id = db::begin_upload_staging(project_id, contact_id, &dest_path_str)
+        .map_err(|e| e.to_string())?;
+    let partial_path = PathBuf::from(db::staging_partial_path(&dest_path_str));
     
-    // 插入数据库记录
-    let file_id = db::insert_project_file(
-        project_id,
-        &original_name,
-        &stored_name,
-        &dest_path_str,
-        Some(file_size),
-        extension.as_deref(),
-        new_version,
-    ).map_err(|e| e.to_string())?;
+    let stored = fs::copy(source, &partial_path)
+        .and_then(|_| fs::rename(&partial_path, dest_path))
+        .map_err(|e| format!("复制文件失败: {}", e))
+        .and_then(|_| record(&dest_path_str));
+    
+    if stored.is_err() {
+        // 回滚：删除已复制的文件
+        let _ = fs::remove_file(&partial_path);
+        let _ = fs::remove_file(dest_path);
+    }
+    let _ = db::finish_upload_staging(staging_id);
+    stored
+}
+
+// 上传文件到项目（外部扫描程序可能运行较长时间，在后台线程执行）
+#[tauri::command]
+async fn upload_file_to_project(
+    project_id: i32,
+    source_path: String,
+    contact_id: Option<i32>,
+) -> Result<db::ProjectFile, file_policy::UploadError> {
+    run_blocking(move || store_project_upload(project_id, source_path, contact_id)).await
+}
+
+fn store_project_upload(
+    project_id: i32,
+    source_path: String,
+    contact_id: Option<i32>,
+) -> Result<db::ProjectFile, file_policy::UploadError> {
+    println!("🔄 正在上传文件到项目 {}: {}", project_id, source_path);
+    
+    let source = PathBuf::from(&source_path);
+    if !source.exists() {
+        return Err(format!("源文件不存在: {}", source_path).into());
+    }
+    
+    // 拒绝被禁止的文件类型和未通过扫描的文件
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    if let Err(rejection) = file_policy::check_upload(&source, &settings) {
+        println!("⛔ 文件被拒绝上传: {}", rejection.reason);
+        return Err(rejection.into());
+    }
+    
+    // 获取原始文件名
+    let original_name = source.file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("无法获取文件名")?
+        .to_string();
+    
+    // 获取文件扩展名
+    let extension = source.extension()
+        .and_then(|e| e.to_str())
+        .map(|s| s.to_string());
+    
+    // 获取文件大小
+    let metadata = fs::metadata(&source).map_err(|e| e.to_string())?;
+    let file_size = metadata.len() as i64;
+    
+    // 获取或创建项目文件夹
+    let project_folder = get_project_folder(project_id)?;
+    fs::create_dir_all(&project_folder).map_err(|e| format!("创建项目文件夹失败: {}", e))?;
+    
+    // 检查是否存在同名文件，获取版本号
+    let current_version = db::get_latest_file_version(project_id, &original_name)
+        .map_err(|e| e.to_string())?;
+    let new_version = current_version + 1;
+    
+    // 生成存储文件名（如果是新版本，添加时间戳）
+    let stored_name = versioned_stored_name(&original_name, extension.as_deref(), new_version);
+    let dest_path = project_folder.join(&stored_name);
+    
+    let file_id = copy_with_staging(&source, &dest_path, Some(project_id), None, |dest_path_str| {
+        db::insert_project_file(
+            project_id,
+            &original_name,
+            &stored_name,
+            dest_path_str,
+            Some(file_size),
+            extension.as_deref(),
+            new_version,
+        ).map_err(|e| e.to_string())
+    })?;
     
     // 自动创建事件
     let event_title = if new_version > 1 {
@@ -410,7 +1200,9 @@ fn upload_file_to_project(
             Some("文件"),
             None,  // 文件上传事件不设置提醒
         ).and_then(|event_id| {
-            db::link_contacts_to_event(event_id, &[cid])
+            db::link_contacts_to_event(event_id, &[cid])?;
+            // 文件作为该事件的附件
+            db::attach_file_to_event(file_id, event_id)
         });
     }
     
@@ -423,6 +1215,25 @@ fn upload_file_to_project(
     Ok(file)
 }
 
+// 清理上次运行中断的上传：删除未写入数据库的残留文件和暂存记录
+fn cleanup_interrupted_uploads() -> Result<usize, String> {
+    let stagings = db::fetch_upload_staging().map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    
+    for staging in &stagings {
+        let _ = fs::remove_file(db::staging_partial_path(&staging.target_path));
+        if !staging.committed && fs::remove_file(&staging.target_path).is_ok() {
+            removed += 1;
+        }
+        db::finish_upload_staging(staging.id).map_err(|e| e.to_string())?;
+    }
+    
+    if !stagings.is_empty() {
+        println!("🧹 已清理 {} 条中断的上传记录，删除 {} 个残留文件", stagings.len(), removed);
+    }
+    Ok(removed)
+}
+
 // 获取项目的所有文件
 #[tauri::command]
 fn get_project_files(project_id: i32) -> Result<Vec<db::ProjectFile>, String> {
@@ -510,15 +1321,39 @@ fn show_in_folder(file_path: String) -> Result<(), String> {
     Ok(())
 }
 
-// 全局搜索文件
+// 全局搜索文件（默认不含已归档项目的文件）
+// 连续输入时相同关键词复用结果，旧查询被新查询取代时返回 search::SUPERSEDED_MESSAGE
 #[tauri::command]
-fn search_files(keyword: String) -> Result<Vec<db::ProjectFileWithProject>, String> {
+async fn search_files(keyword: String, include_archived: Option<bool>) -> Result<Vec<search::Highlighted<db::ProjectFileWithProject>>, String> {
     println!("🔄 正在搜索文件: {}", keyword);
-    let files = db::search_files_global(&keyword).map_err(|e| e.to_string())?;
+    let include_archived = include_archived.unwrap_or(false);
+    let files = search::run(&search::FILE_SEARCH, search::archived_scope(include_archived), keyword, move |keyword, is_cancelled| {
+        db::search_files_global(keyword, include_archived, is_cancelled)
+    }).await?;
     println!("✅ 找到 {} 个匹配文件", files.len());
     Ok(files)
 }
 
+// 全局搜索联系人、项目、事件和文件，最近常用的排在前面（权重见设置 search_ranking），结果附带命中摘要
+// 默认不含已归档的项目及其事件和文件
+#[tauri::command]
+async fn search_global(keyword: String, include_archived: Option<bool>) -> Result<Vec<db::SearchHit>, String> {
+    println!("🔄 正在全局搜索: {}", keyword);
+    let keyword = keyword.trim().to_string();
+    let include_archived = include_archived.unwrap_or(false);
+    let hits = search::run(&search::GLOBAL_SEARCH, search::archived_scope(include_archived), keyword, move |keyword, is_cancelled| {
+        db::search_global(keyword, include_archived, is_cancelled)
+    }).await?;
+    println!("✅ 找到 {} 条结果", hits.len());
+    Ok(hits)
+}
+
+// 记录打开了联系人、项目、事件或文件（entity_type 为 contact / project / event / file），用于全局搜索排序
+#[tauri::command]
+fn record_access(entity_type: String, entity_id: i32) -> Result<(), String> {
+    db::record_access(&entity_type, entity_id).map_err(|e| e.to_string())
+}
+
 // 删除项目文件
 #[tauri::command]
 fn delete_project_file(file_id: i32) -> Result<(), String> {
@@ -542,61 +1377,506 @@ fn delete_project_file(file_id: i32) -> Result<(), String> {
     Ok(())
 }
 
-// ==================== 项目活动管理相关命令 ====================
+// 读取用于对比的文本文件
+fn read_text_version(file: &db::ProjectFile) -> Result<String, String> {
+    if !diff::is_text_file(&file.original_name) {
+        return Err(format!("只能对比文本文件（{}）: {}", diff::TEXT_DIFF_EXTENSIONS.join("、"), file.original_name));
+    }
+    let path = PathBuf::from(&file.file_path);
+    let size = fs::metadata(&path).map_err(|e| format!("读取文件失败: {}", e))?.len();
+    if size > diff::MAX_DIFF_FILE_BYTES {
+        return Err(format!("文件过大，无法对比: {}（超过 {} MB）", file.original_name, diff::MAX_DIFF_FILE_BYTES / 1024 / 1024));
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    diff::decode_text(bytes, &file.original_name)
+}
 
-// 创建活动
+// 对比两个文本文件版本（txt / md / csv），返回 unified diff
 #[tauri::command]
-fn create_activity(
+fn diff_file_versions(file_id_a: i32, file_id_b: i32) -> Result<diff::FileDiff, String> {
+    println!("🔄 正在对比文件 {} 和 {}...", file_id_a, file_id_b);
+    let file_a = db::get_file_by_id(file_id_a).map_err(|e| e.to_string())?.ok_or(format!("文件不存在: {}", file_id_a))?;
+    let file_b = db::get_file_by_id(file_id_b).map_err(|e| e.to_string())?.ok_or(format!("文件不存在: {}", file_id_b))?;
+    let text_a = read_text_version(&file_a)?;
+    let text_b = read_text_version(&file_b)?;
+    let label_a = format!("{} v{}", file_a.original_name, file_a.version);
+    let label_b = format!("{} v{}", file_b.original_name, file_b.version);
+    let (unified, added, removed) = diff::unified_diff(&text_a, &text_b, &label_a, &label_b)?;
+    println!("✅ 新增 {} 行，删除 {} 行", added, removed);
+    Ok(diff::FileDiff {
+        file_id_a,
+        file_id_b,
+        label_a,
+        label_b,
+        identical: unified.is_empty(),
+        unified,
+        added,
+        removed,
+    })
+}
+
+// 文件回收站目录（清理旧版本时文件先移到这里，清空回收站后才真正释放空间）
+fn get_trash_root_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?;
+    Ok(app_data_dir.join("mindmirror").join("trash"))
+}
+
+// 把文件移入回收站（跨磁盘时改为复制后删除）
+fn move_to_trash(path: &std::path::Path, trash_name: &str) -> Result<(), String> {
+    let trash_dir = get_trash_root_dir()?;
+    fs::create_dir_all(&trash_dir).map_err(|e| format!("创建回收站失败: {}", e))?;
+    let target = trash_dir.join(trash_name);
+    if fs::rename(path, &target).is_err() {
+        fs::copy(path, &target).map_err(|e| format!("移入回收站失败: {}", e))?;
+        fs::remove_file(path).map_err(|e| format!("删除文件失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// 清理项目中的旧文件版本：每个文件保留最新的 keep_last_n 个版本（默认 3），
+// dry_run 默认为 true，只统计可释放的空间；旧版本默认移入回收站，skip_trash 为 true 时直接删除
+#[tauri::command]
+fn prune_file_versions(
     project_id: i32,
-    name: String,
-    description: Option<String>,
-    estimated_completion_date: Option<String>,
-    contact_ids: Vec<i32>,
-) -> Result<(), String> {
-    println!("🔄 正在创建活动: {}", name);
+    keep_last_n: Option<i32>,
+    dry_run: Option<bool>,
+    skip_trash: Option<bool>,
+) -> Result<db::FileVersionPruneReport, String> {
+    let keep_last_n = keep_last_n.unwrap_or(db::CLEANUP_FILE_VERSIONS_TO_KEEP);
+    let dry_run = dry_run.unwrap_or(true);
+    let use_trash = !skip_trash.unwrap_or(false);
+    println!("🔄 正在清理项目 {} 的旧文件版本（保留 {} 个）...", project_id, keep_last_n);
+    let versions = db::fetch_prunable_file_versions(project_id, keep_last_n).map_err(|e| e.to_string())?;
+    let reclaimable_bytes: i64 = versions.iter().map(|v| v.file_size).sum();
+    let numbers = format::NumberFormat::from_settings(&db::fetch_settings().map_err(|e| e.to_string())?);
     
-    let activity_id = db::insert_activity(
-        project_id,
-        &name,
-        description.as_deref(),
-        estimated_completion_date.as_deref(),
-    ).map_err(|e| e.to_string())?;
-    
-    if !contact_ids.is_empty() {
-        db::assign_contacts_to_activity(activity_id, &contact_ids)
-            .map_err(|e| e.to_string())?;
+    let mut pruned = 0;
+    if !dry_run {
+        // 文件处理成功的版本才删除记录，失败时保留记录以便重试
+        let mut removed_ids = Vec::new();
+        for version in &versions {
+            let path = PathBuf::from(&version.file_path);
+            let result = if !path.exists() {
+                Ok(())
+            } else if use_trash {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                move_to_trash(&path, &format!("{}_{}_{}", project_id, version.file_id, name))
+            } else {
+                fs::remove_file(&path).map_err(|e| format!("删除文件失败: {}", e))
+            };
+            match result {
+                Ok(()) => removed_ids.push(version.file_id),
+                Err(e) => println!("⚠️ {} v{}: {}", version.original_name, version.version, e),
+            }
+        }
+        pruned = db::delete_project_files(&removed_ids).map_err(|e| e.to_string())?;
     }
     
-    // 获取项目名称和负责人名称用于日志
-    let project_name = db::get_project_name(project_id).unwrap_or_default();
-    let contacts = db::fetch_contacts().map_err(|e| e.to_string())?;
-    let assignee_names: Vec<String> = contacts.iter()
-        .filter(|c| contact_ids.contains(&c.id))
-        .map(|c| c.name.clone())
-        .collect();
-    
-    // 记录操作日志
-    let _ = db::log_activity_creation(
-        activity_id,
-        &name,
+    let reclaimable_display = numbers.format_file_size(reclaimable_bytes.max(0) as u64);
+    if dry_run {
+        println!("✅ 可清理 {} 个旧版本，共 {}（预览）", versions.len(), reclaimable_display);
+    } else {
+        println!("✅ 已清理 {} 个旧版本", pruned);
+    }
+    Ok(db::FileVersionPruneReport {
         project_id,
-        &project_name,
-        &assignee_names,
-    );
-    
-    println!("✅ 活动创建成功: {}", name);
-    Ok(())
+        keep_last_n,
+        dry_run,
+        reclaimable_display,
+        reclaimable_bytes,
+        versions,
+        pruned,
+        moved_to_trash: !dry_run && use_trash,
+    })
 }
 
-// 获取项目的所有活动
+// 清空文件回收站，返回释放的字节数
 #[tauri::command]
-fn get_project_activities(project_id: i32) -> Result<Vec<db::ActivityWithDetails>, String> {
-    println!("🔄 正在获取项目 {} 的活动列表...", project_id);
-    let activities = db::fetch_activities_for_project(project_id).map_err(|e| e.to_string())?;
-    println!("✅ 获取到 {} 个活动", activities.len());
-    Ok(activities)
-}
-
+fn empty_file_trash() -> Result<u64, String> {
+    println!("🔄 正在清空回收站...");
+    let trash_dir = get_trash_root_dir()?;
+    let Ok(entries) = fs::read_dir(&trash_dir) else {
+        return Ok(0);
+    };
+    let mut freed = 0;
+    for entry in entries.flatten() {
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        match fs::remove_file(entry.path()) {
+            Ok(()) => freed += size,
+            Err(e) => println!("⚠️ 删除 {} 失败: {}", entry.path().display(), e),
+        }
+    }
+    println!("✅ 回收站已清空，释放 {} 字节", freed);
+    Ok(freed)
+}
+
+// ==================== 文件分享相关命令 ====================
+
+// 分享副本的根目录（与项目文件分开，可以整体同步或清空）
+fn get_share_root_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?;
+    Ok(app_data_dir.join("mindmirror").join("shares"))
+}
+
+// 随机名称（32 位十六进制，不可猜测）
+fn random_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    // 每个 RandomState 使用不同的随机密钥
+    let mut token = String::new();
+    for salt in 0..2u8 {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u8(salt);
+        token.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    token
+}
+
+// 删除分享副本及其随机文件夹
+fn remove_share_copy(share: &db::FileShare) -> Result<(), String> {
+    let path = PathBuf::from(&share.share_path);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除分享文件失败: {}", e))?;
+    }
+    // 只删除分享根目录下的随机文件夹
+    if let (Some(folder), Ok(root)) = (path.parent(), get_share_root_dir()) {
+        if folder.parent() == Some(root.as_path()) {
+            let _ = fs::remove_dir(folder);
+        }
+    }
+    Ok(())
+}
+
+// 删除已过期的分享，返回删除数量
+fn purge_expired_file_shares() -> Result<usize, String> {
+    let expired = db::fetch_expired_file_shares().map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    for share in &expired {
+        match remove_share_copy(share) {
+            Ok(()) => {
+                db::delete_file_share(share.id).map_err(|e| e.to_string())?;
+                removed += 1;
+            }
+            // 文件被占用时保留记录，下次再试
+            Err(e) => println!("⚠️ {}: {}", e, share.share_path),
+        }
+    }
+    Ok(removed)
+}
+
+// 准备文件分享：把文件复制到分享文件夹下的随机文件夹中，过期后自动删除
+#[tauri::command]
+fn prepare_file_share(file_id: i32, expires_days: i64) -> Result<db::FileShare, String> {
+    println!("🔄 正在准备分享文件 {}（{} 天后过期）...", file_id, expires_days);
+    if !(1..=db::MAX_SHARE_DAYS).contains(&expires_days) {
+        return Err(format!("有效天数应在 1 到 {} 之间", db::MAX_SHARE_DAYS));
+    }
+    
+    let file = db::get_file_by_id(file_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("文件不存在")?;
+    let source = PathBuf::from(&file.file_path);
+    if !source.exists() {
+        return Err(format!("文件不存在: {}", file.file_path));
+    }
+    
+    let folder = get_share_root_dir()?.join(random_token());
+    fs::create_dir_all(&folder).map_err(|e| format!("创建分享文件夹失败: {}", e))?;
+    let share_path = folder.join(&file.original_name);
+    if let Err(e) = fs::copy(&source, &share_path) {
+        let _ = fs::remove_dir_all(&folder);
+        return Err(format!("复制文件失败: {}", e));
+    }
+    
+    let expires_at = (Local::now() + chrono::Duration::days(expires_days))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let share = db::insert_file_share(file_id, &file.original_name, &share_path.to_string_lossy(), &expires_at)
+        .map_err(|e| {
+            let _ = fs::remove_dir_all(&folder);
+            e.to_string()
+        })?;
+    
+    println!("✅ 分享文件已准备: {}（{} 过期）", share.share_path, share.expires_at);
+    Ok(share)
+}
+
+// 获取未过期的分享（可按文件过滤）
+#[tauri::command]
+fn get_file_shares(file_id: Option<i32>) -> Result<Vec<db::FileShare>, String> {
+    let shares = db::fetch_file_shares(file_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个分享", shares.len());
+    Ok(shares)
+}
+
+// 提前撤销分享（删除副本）
+#[tauri::command]
+fn revoke_file_share(share_id: i32) -> Result<(), String> {
+    println!("🔄 正在撤销分享 {}...", share_id);
+    let share = db::get_file_share(share_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("分享不存在")?;
+    remove_share_copy(&share)?;
+    db::delete_file_share(share_id).map_err(|e| e.to_string())?;
+    println!("✅ 分享已撤销");
+    Ok(())
+}
+
+// ==================== 事件内嵌图片相关命令 ====================
+
+// 单张内嵌图片的大小上限
+const MAX_INLINE_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+// 内嵌图片的根目录，每个事件一个子文件夹（inline_images/<事件ID>/），删除事件时整个删除
+// 该目录已在 tauri.conf.json 的 assetProtocol.scope 中开放给前端
+fn get_inline_images_root_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?;
+    Ok(app_data_dir.join("mindmirror").join("inline_images"))
+}
+
+// 根据文件头识别图片格式，返回扩展名（不支持的格式为 None）
+fn detect_image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+// 本地文件对应的 asset 协议地址（与前端 convertFileSrc 的结果一致）
+fn asset_url(path: &std::path::Path) -> String {
+    let encoded = db::encode_uri_component(&path.to_string_lossy());
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+// 删除事件的内嵌图片文件夹
+fn remove_inline_images(event_id: i32) -> Result<(), String> {
+    let folder = get_inline_images_root_dir()?.join(event_id.to_string());
+    if folder.exists() {
+        fs::remove_dir_all(&folder).map_err(|e| format!("删除内嵌图片失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// 保存粘贴到事件描述中的图片，返回可直接用于 <img src> 的 asset 地址
+#[tauri::command]
+fn save_inline_image(event_id: i32, bytes: Vec<u8>) -> Result<String, String> {
+    println!("🔄 正在保存事件 {} 的内嵌图片（{} 字节）...", event_id, bytes.len());
+    if bytes.len() > MAX_INLINE_IMAGE_BYTES {
+        return Err(format!("图片不能超过 {} MB", MAX_INLINE_IMAGE_BYTES / 1024 / 1024));
+    }
+    let extension = detect_image_extension(&bytes).ok_or("不支持的图片格式（支持 PNG、JPEG、GIF、WebP）")?;
+    if !db::event_exists(event_id).map_err(|e| e.to_string())? {
+        return Err(format!("事件不存在: {}", event_id));
+    }
+    
+    let folder = get_inline_images_root_dir()?.join(event_id.to_string());
+    fs::create_dir_all(&folder).map_err(|e| format!("创建图片文件夹失败: {}", e))?;
+    let path = folder.join(format!("{}.{}", random_token(), extension));
+    fs::write(&path, &bytes).map_err(|e| format!("保存图片失败: {}", e))?;
+    
+    println!("✅ 内嵌图片已保存: {}", path.display());
+    Ok(asset_url(&path))
+}
+
+// ==================== 联系人文件管理相关命令 ====================
+
+// 获取联系人文件存储的根目录
+fn get_contact_files_root_dir() -> Result<PathBuf, String> {
+    let app_data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?;
+    let files_dir = app_data_dir.join("mindmirror").join("contact_files");
+    Ok(files_dir)
+}
+
+// 获取联系人的文件夹路径（使用联系人姓名作为文件夹名）
+fn get_contact_folder(contact_id: i32) -> Result<PathBuf, String> {
+    let root = get_contact_files_root_dir()?;
+    
+    let contact = db::fetch_contact_by_id(contact_id)
+        .map_err(|e| format!("获取联系人失败: {}", e))?
+        .ok_or("联系人不存在")?;
+    
+    // 添加联系人ID后缀以确保唯一性（避免重名联系人冲突）
+    let unique_folder_name = format!("{}_{}", sanitize_folder_name(&contact.name), contact_id);
+    
+    Ok(root.join(unique_folder_name))
+}
+
+// 上传文件到联系人（合同、证件扫描件等），与项目上传一样在后台线程执行
+#[tauri::command]
+async fn upload_file_to_contact(contact_id: i32, source_path: String) -> Result<db::ContactFile, file_policy::UploadError> {
+    run_blocking(move || store_contact_upload(contact_id, source_path)).await
+}
+
+fn store_contact_upload(contact_id: i32, source_path: String) -> Result<db::ContactFile, file_policy::UploadError> {
+    println!("🔄 正在上传文件到联系人 {}: {}", contact_id, source_path);
+    
+    let source = PathBuf::from(&source_path);
+    if !source.exists() {
+        return Err(format!("源文件不存在: {}", source_path).into());
+    }
+    
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    if let Err(rejection) = file_policy::check_upload(&source, &settings) {
+        println!("⛔ 文件被拒绝上传: {}", rejection.reason);
+        return Err(rejection.into());
+    }
+    
+    let original_name = source.file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("无法获取文件名")?
+        .to_string();
+    let extension = source.extension()
+        .and_then(|e| e.to_str())
+        .map(|s| s.to_string());
+    let file_size = fs::metadata(&source).map_err(|e| e.to_string())?.len() as i64;
+    
+    // 获取或创建联系人文件夹
+    let contact_folder = get_contact_folder(contact_id)?;
+    fs::create_dir_all(&contact_folder).map_err(|e| format!("创建联系人文件夹失败: {}", e))?;
+    
+    // 检查是否存在同名文件，获取版本号
+    let new_version = db::get_latest_contact_file_version(contact_id, &original_name)
+        .map_err(|e| e.to_string())? + 1;
+    
+    let stored_name = versioned_stored_name(&original_name, extension.as_deref(), new_version);
+    let dest_path = contact_folder.join(&stored_name);
+    
+    let file_id = copy_with_staging(&source, &dest_path, None, Some(contact_id), |dest_path_str| {
+        db::insert_contact_file(
+            contact_id,
+            &original_name,
+            &stored_name,
+            dest_path_str,
+            Some(file_size),
+            extension.as_deref(),
+            new_version,
+        ).map_err(|e| e.to_string())
+    })?;
+    
+    let file = db::get_contact_file_by_id(file_id as i32)
+        .map_err(|e| e.to_string())?
+        .ok_or("文件创建后无法找到")?;
+    
+    println!("✅ 文件上传成功: {} (版本 {})", original_name, new_version);
+    Ok(file)
+}
+
+// 获取联系人的所有文件
+#[tauri::command]
+fn get_contact_files(contact_id: i32) -> Result<Vec<db::ContactFile>, String> {
+    println!("🔄 正在获取联系人 {} 的文件列表...", contact_id);
+    let files = db::fetch_files_for_contact(contact_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个文件", files.len());
+    Ok(files)
+}
+
+// 删除联系人文件
+#[tauri::command]
+fn delete_contact_file(file_id: i32) -> Result<(), String> {
+    println!("🔄 正在删除联系人文件 {}...", file_id);
+    
+    let file = db::get_contact_file_by_id(file_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("文件不存在")?;
+    
+    // 删除物理文件
+    let path = PathBuf::from(&file.file_path);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除文件失败: {}", e))?;
+    }
+    
+    db::delete_contact_file(file_id).map_err(|e| e.to_string())?;
+    
+    println!("✅ 文件删除成功");
+    Ok(())
+}
+
+// ==================== 项目活动管理相关命令 ====================
+
+// 查找名称相近的已有活动（创建前提示可能重复，同项目的排在前面）
+#[tauri::command]
+fn find_similar_activities(name: String, project_id: Option<i32>) -> Result<Vec<db::SimilarActivity>, String> {
+    let similar = db::find_similar_activities(&name, project_id).map_err(|e| e.to_string())?;
+    println!("✅ 找到 {} 个相似活动", similar.len());
+    Ok(similar)
+}
+
+// 创建活动
+#[tauri::command]
+fn create_activity(
+    app_handle: tauri::AppHandle,
+    project_id: i32,
+    name: String,
+    description: Option<String>,
+    estimated_completion_date: Option<String>,
+    contact_ids: Vec<i32>,
+    estimated_hours: Option<f64>,
+) -> Result<(), String> {
+    println!("🔄 正在创建活动: {}", name);
+    
+    let activity_id = db::insert_activity(
+        project_id,
+        &name,
+        description.as_deref(),
+        estimated_completion_date.as_deref(),
+        estimated_hours,
+    ).map_err(|e| e.to_string())?;
+    
+    if !contact_ids.is_empty() {
+        db::assign_contacts_to_activity(activity_id, &contact_ids)
+            .map_err(|e| e.to_string())?;
+        notify_assignment(&app_handle, activity_id as i32, &contact_ids);
+    }
+    
+    // 获取项目名称和负责人名称用于日志
+    let project_name = db::get_project_name(project_id).unwrap_or_default();
+    let contacts = db::fetch_contacts().map_err(|e| e.to_string())?;
+    let assignee_names: Vec<String> = contacts.iter()
+        .filter(|c| contact_ids.contains(&c.id))
+        .map(|c| c.name.clone())
+        .collect();
+    
+    // 记录操作日志
+    let _ = db::log_activity_creation(
+        activity_id,
+        &name,
+        project_id,
+        &project_name,
+        &assignee_names,
+    );
+    
+    println!("✅ 活动创建成功: {}", name);
+    Ok(())
+}
+
+// 获取项目的所有活动
+#[tauri::command]
+fn get_project_activities(project_id: i32) -> Result<Vec<db::ActivityWithDetails>, String> {
+    println!("🔄 正在获取项目 {} 的活动列表...", project_id);
+    let activities = db::fetch_activities_for_project(project_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个活动", activities.len());
+    Ok(activities)
+}
+
 // 更新活动信息
 #[tauri::command]
 fn update_activity(
@@ -616,211 +1896,2025 @@ fn update_activity(
     Ok(())
 }
 
-// 分配活动负责人
+// 分配活动负责人
+#[tauri::command]
+fn assign_activity_contacts(
+    app_handle: tauri::AppHandle,
+    activity_id: i32,
+    contact_ids: Vec<i32>,
+) -> Result<(), String> {
+    println!("🔄 正在为活动 {} 分配负责人...", activity_id);
+    let existing: Vec<i32> = db::fetch_assignees_for_activity(activity_id)
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    db::assign_contacts_to_activity(activity_id as i64, &contact_ids)
+        .map_err(|e| e.to_string())?;
+    // 只通知新增的负责人
+    let added: Vec<i32> = contact_ids.into_iter().filter(|id| !existing.contains(id)).collect();
+    notify_assignment(&app_handle, activity_id, &added);
+    println!("✅ 负责人分配成功");
+    Ok(())
+}
+
+// 开启分配通知时提醒新分配的负责人（分配给"我"时提醒查看，分配给他人时提醒发送通知邮件）
+fn notify_assignment(app_handle: &tauri::AppHandle, activity_id: i32, added: &[i32]) {
+    if added.is_empty() || !db::is_assignment_notification_enabled().unwrap_or(false) {
+        return;
+    }
+    let message = match db::fetch_assignment_message(activity_id) {
+        Ok(message) => message,
+        Err(e) => {
+            println!("⚠️ 生成分配通知失败: {}", e);
+            return;
+        }
+    };
+    let me = db::get_me_contact_id().ok().flatten();
+    let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+    
+    let result = if me.is_some_and(|id| added.contains(&id)) {
+        send_reminder_notification(app_handle, "有新的活动分配给你", &message.subject, &style)
+    } else {
+        let names: Vec<&str> = message.recipients.iter()
+            .filter(|r| added.contains(&r.contact_id))
+            .map(|r| r.name.as_str())
+            .collect();
+        let body = format!("{}\n负责人: {}（可在活动详情中生成通知邮件）", message.subject, names.join("、"));
+        send_reminder_notification(app_handle, "活动已分配", &body, &style)
+    };
+    if let Err(e) = result {
+        println!("⚠️ 发送分配通知失败: {}", e);
+    }
+}
+
+// 获取活动的分配通知（预先填好的邮件/消息，含 mailto 链接）
+#[tauri::command]
+fn get_assignment_message(activity_id: i32) -> Result<db::AssignmentMessage, String> {
+    println!("🔄 正在生成活动 {} 的分配通知...", activity_id);
+    let message = db::fetch_assignment_message(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 分配通知已生成: {} 位收件人", message.recipients.len());
+    Ok(message)
+}
+
+// 移除活动负责人
+#[tauri::command]
+fn unassign_activity_contact(
+    activity_id: i32,
+    contact_id: i32,
+) -> Result<(), String> {
+    println!("🔄 正在移除活动 {} 的负责人 {}...", activity_id, contact_id);
+    db::unassign_contact_from_activity(activity_id, contact_id)
+        .map_err(|e| e.to_string())?;
+    println!("✅ 负责人移除成功");
+    Ok(())
+}
+
+// 激活活动
+#[tauri::command]
+fn activate_activity(activity_id: i32) -> Result<(), String> {
+    println!("🔄 正在激活活动 {}...", activity_id);
+    db::activate_activity(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 活动已激活");
+    Ok(())
+}
+
+// 暂停活动
+#[tauri::command]
+fn pause_activity(activity_id: i32) -> Result<(), String> {
+    println!("🔄 正在暂停活动 {}...", activity_id);
+    db::pause_activity(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 活动已暂停");
+    Ok(())
+}
+
+// 设置活动预估工时（None 表示清除）
+#[tauri::command]
+fn set_activity_estimated_hours(activity_id: i32, estimated_hours: Option<f64>) -> Result<(), String> {
+    println!("🔄 正在设置活动 {} 的预估工时...", activity_id);
+    db::set_activity_estimated_hours(activity_id, estimated_hours).map_err(|e| e.to_string())?;
+    println!("✅ 预估工时已保存");
+    Ok(())
+}
+
+// 添加工时记录
+#[tauri::command]
+fn add_time_entry(
+    activity_id: i32,
+    contact_id: Option<i32>,
+    hours: f64,
+    entry_date: Option<String>,
+    note: Option<String>,
+) -> Result<i64, String> {
+    println!("🔄 正在为活动 {} 记录 {} 小时工时...", activity_id, hours);
+    let id = db::insert_time_entry(activity_id, contact_id, hours, entry_date.as_deref(), note.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 工时记录成功");
+    Ok(id)
+}
+
+// 删除工时记录
+#[tauri::command]
+fn delete_time_entry(entry_id: i32) -> Result<(), String> {
+    println!("🔄 正在删除工时记录 {}...", entry_id);
+    db::delete_time_entry(entry_id).map_err(|e| e.to_string())?;
+    println!("✅ 工时记录删除成功");
+    Ok(())
+}
+
+// 获取活动的工时记录
+#[tauri::command]
+fn get_time_entries(activity_id: i32) -> Result<Vec<db::TimeEntry>, String> {
+    println!("🔄 正在获取活动 {} 的工时记录...", activity_id);
+    let entries = db::fetch_time_entries(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 条工时记录", entries.len());
+    Ok(entries)
+}
+
+// 预估准确度报告（已完成活动的预估与实际工时对比）
+#[tauri::command]
+fn get_estimation_accuracy_report(project_id: Option<i32>) -> Result<db::EstimationAccuracyReport, String> {
+    println!("🔄 正在生成预估准确度报告...");
+    let report = db::fetch_estimation_accuracy_report(project_id).map_err(|e| e.to_string())?;
+    println!("✅ 统计 {} 个活动", report.activity_count);
+    Ok(report)
+}
+
+// 进行中活动的老化报告（扣除暂停时间，超过阈值的标出；threshold_days 为空时使用设置值）
+#[tauri::command]
+fn get_activity_aging_report(threshold_days: Option<i64>) -> Result<db::ActivityAgingReport, String> {
+    println!("🔄 正在生成活动老化报告...");
+    let report = db::fetch_activity_aging_report(threshold_days).map_err(|e| e.to_string())?;
+    println!("✅ 进行中活动 {} 项，超过 {} 天的 {} 项", report.activities.len(), report.threshold_days, report.over_threshold_count);
+    Ok(report)
+}
+
+// 创建活动模板（recurrence: daily / weekly:1-7 / monthly:1-31，为空表示仅手动使用）
+#[tauri::command]
+fn create_activity_template(
+    project_id: i32,
+    name: String,
+    description: Option<String>,
+    due_offset_days: Option<i32>,
+    recurrence: Option<String>,
+) -> Result<i64, String> {
+    println!("🔄 正在创建活动模板: {}", name);
+    let id = db::insert_activity_template(project_id, &name, description.as_deref(), due_offset_days, recurrence.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 活动模板创建成功");
+    Ok(id)
+}
+
+// 更新活动模板
+#[tauri::command]
+fn update_activity_template(
+    template_id: i32,
+    name: String,
+    description: Option<String>,
+    due_offset_days: Option<i32>,
+    recurrence: Option<String>,
+    active: bool,
+) -> Result<(), String> {
+    println!("🔄 正在更新活动模板 {}...", template_id);
+    db::update_activity_template(template_id, &name, description.as_deref(), due_offset_days, recurrence.as_deref(), active)
+        .map_err(|e| e.to_string())?;
+    println!("✅ 活动模板更新成功");
+    Ok(())
+}
+
+// 删除活动模板（已生成的活动保留）
+#[tauri::command]
+fn delete_activity_template(template_id: i32) -> Result<(), String> {
+    println!("🔄 正在删除活动模板 {}...", template_id);
+    db::delete_activity_template(template_id).map_err(|e| e.to_string())?;
+    println!("✅ 活动模板删除成功");
+    Ok(())
+}
+
+// 获取活动模板（可按项目过滤）
+#[tauri::command]
+fn get_activity_templates(project_id: Option<i32>) -> Result<Vec<db::ActivityTemplate>, String> {
+    println!("🔄 正在获取活动模板...");
+    let templates = db::fetch_activity_templates(project_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个模板", templates.len());
+    Ok(templates)
+}
+
+// 列出内置模板包（销售跟进、招聘流程、咨询项目）
+#[tauri::command]
+fn get_template_packs() -> Vec<db::TemplatePackInfo> {
+    db::list_template_packs()
+}
+
+// 安装模板包：添加事件类型和活动模板（未指定项目时新建项目）
+#[tauri::command]
+fn install_template_pack(name: String, project_id: Option<i32>) -> Result<db::TemplatePackInstall, String> {
+    println!("🔄 正在安装模板包: {}", name);
+    let installed = db::install_template_pack(&name, project_id).map_err(|e| e.to_string())?;
+    println!(
+        "✅ 模板包「{}」已安装到项目 {}（事件类型 {} 个、活动模板 {} 个）",
+        installed.pack, installed.project_id, installed.event_types_created, installed.activity_templates_created
+    );
+    Ok(installed)
+}
+
+// 按模板立即创建一条活动
+#[tauri::command]
+fn create_activity_from_template(template_id: i32) -> Result<i64, String> {
+    println!("🔄 正在按模板 {} 创建活动...", template_id);
+    let activity_id = db::instantiate_activity_template(template_id, None).map_err(|e| e.to_string())?;
+    println!("✅ 活动创建成功");
+    Ok(activity_id)
+}
+
+// 获取由模板生成的活动
+#[tauri::command]
+fn get_template_activities(template_id: i32) -> Result<Vec<db::ActivityWithDetails>, String> {
+    println!("🔄 正在获取模板 {} 生成的活动...", template_id);
+    let activities = db::fetch_activities_for_template(template_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个活动", activities.len());
+    Ok(activities)
+}
+
+// 完成活动
+#[tauri::command]
+fn complete_activity(activity_id: i32) -> Result<(), String> {
+    println!("🔄 正在完成活动 {}...", activity_id);
+    db::complete_activity(activity_id).map_err(|e| e.to_string())?;
+    
+    // 项目开启了完成记录时，在时间线上记录一条事件
+    match db::record_activity_completion_event(activity_id) {
+        Ok(Some(event_id)) => println!("📝 已记录活动完成事件 {}", event_id),
+        Ok(None) => {}
+        Err(e) => println!("⚠️ 记录活动完成事件失败: {}", e),
+    }
+    
+    println!("✅ 活动已完成");
+    Ok(())
+}
+
+// 提交活动审核
+#[tauri::command]
+fn submit_activity_for_review(activity_id: i32, reviewer_contact_id: Option<i32>, note: Option<String>) -> Result<(), String> {
+    println!("🔄 正在提交活动 {} 审核...", activity_id);
+    db::submit_activity_for_review(activity_id, reviewer_contact_id, note.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 活动已提交审核");
+    Ok(())
+}
+
+// 审核通过活动（活动随之完成）
+#[tauri::command]
+fn approve_activity(activity_id: i32, reviewer_contact_id: i32, note: Option<String>) -> Result<(), String> {
+    println!("🔄 正在审核通过活动 {}...", activity_id);
+    db::approve_activity(activity_id, reviewer_contact_id, note.as_deref()).map_err(|e| e.to_string())?;
+    
+    match db::record_activity_completion_event(activity_id) {
+        Ok(Some(event_id)) => println!("📝 已记录活动完成事件 {}", event_id),
+        Ok(None) => {}
+        Err(e) => println!("⚠️ 记录活动完成事件失败: {}", e),
+    }
+    
+    println!("✅ 活动审核通过");
+    Ok(())
+}
+
+// 审核退回活动（回到进行中）
+#[tauri::command]
+fn reject_activity(activity_id: i32, reviewer_contact_id: i32, note: Option<String>) -> Result<(), String> {
+    println!("🔄 正在退回活动 {}...", activity_id);
+    db::reject_activity(activity_id, reviewer_contact_id, note.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 活动已退回");
+    Ok(())
+}
+
+// 获取活动审核记录
+#[tauri::command]
+fn get_activity_reviews(activity_id: i32) -> Result<Vec<db::ActivityReview>, String> {
+    println!("🔄 正在获取活动 {} 的审核记录...", activity_id);
+    let reviews = db::fetch_activity_reviews(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 条审核记录", reviews.len());
+    Ok(reviews)
+}
+
+// 获取项目是否在完成活动时自动记录时间线事件
+#[tauri::command]
+fn get_activity_completion_logging(project_id: i32) -> Result<bool, String> {
+    db::get_activity_completion_logging(project_id).map_err(|e| e.to_string())
+}
+
+// 设置项目是否在完成活动时自动记录时间线事件
+#[tauri::command]
+fn set_activity_completion_logging(project_id: i32, enabled: bool) -> Result<(), String> {
+    println!("🔄 正在设置项目 {} 的活动完成记录: {}", project_id, enabled);
+    db::set_activity_completion_logging(project_id, enabled).map_err(|e| e.to_string())?;
+    println!("✅ 设置已保存");
+    Ok(())
+}
+
+// 删除活动
+#[tauri::command]
+fn delete_activity(activity_id: i32) -> Result<(), String> {
+    println!("🔄 正在删除活动 {}...", activity_id);
+    db::delete_activity(activity_id).map_err(|e| e.to_string())?;
+    println!("✅ 活动删除成功");
+    Ok(())
+}
+
+// 导出时需要化名则按全部联系人建立化名表
+fn export_pseudonymizer(anonymize: Option<bool>) -> Result<Option<export::Pseudonymizer>, String> {
+    if !anonymize.unwrap_or(false) {
+        return Ok(None);
+    }
+    let contacts = db::fetch_contacts().map_err(|e| e.to_string())?;
+    Ok(Some(export::Pseudonymizer::new(&contacts)))
+}
+
+// 按名称读取脱敏方案（未指定时不脱敏）
+fn export_redaction(profile: Option<&str>) -> Result<Option<redaction::RedactionProfile>, String> {
+    let Some(name) = profile.map(|n| n.trim()).filter(|n| !n.is_empty()) else {
+        return Ok(None);
+    };
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    redaction::find_profile(&settings, name)
+        .map(Some)
+        .ok_or_else(|| format!("脱敏方案不存在: {}", name))
+}
+
+// 获取全部脱敏方案（内置方案和设置中的自定义方案）
+#[tauri::command]
+fn get_redaction_profiles() -> Result<Vec<redaction::RedactionProfile>, String> {
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    Ok(redaction::profiles(&settings))
+}
+
+// 导出所有活动为JSON（前端会转换为Excel），anonymize 为 true 时联系人化名，profile 为脱敏方案名称，
+// include_archived 为 true 时包含已归档项目的活动
+#[tauri::command]
+fn export_activities(anonymize: Option<bool>, profile: Option<String>, include_archived: Option<bool>) -> Result<Vec<(db::ActivityWithDetails, String)>, String> {
+    println!("🔄 正在导出所有活动...");
+    let redaction = export_redaction(profile.as_deref())?;
+    let anonymize = anonymize.unwrap_or(false) || redaction.as_ref().is_some_and(|r| r.anonymize);
+    let mut activities = db::fetch_all_activities_with_project(include_archived.unwrap_or(false)).map_err(|e| e.to_string())?;
+    if let Some(p) = export_pseudonymizer(Some(anonymize))? {
+        p.activities(activities.iter_mut().map(|(a, _)| a));
+    }
+    if let Some(r) = redaction {
+        r.activities(activities.iter_mut().map(|(a, _)| a));
+    }
+    println!("✅ 导出 {} 个活动", activities.len());
+    Ok(activities)
+}
+
+// 导出项目联系人通讯录为 Excel（角色、单位、电话、邮箱），anonymize 为 true 时联系人化名，profile 为脱敏方案名称
+#[tauri::command]
+fn export_project_roster_xlsx(project_id: i32, path: String, anonymize: Option<bool>, profile: Option<String>) -> Result<usize, String> {
+    println!("🔄 正在导出项目 {} 的通讯录到: {}", project_id, path);
+    let redaction = export_redaction(profile.as_deref())?;
+    let anonymize = anonymize.unwrap_or(false) || redaction.as_ref().is_some_and(|r| r.anonymize);
+    let project_name = db::get_project_name(project_id).map_err(|e| e.to_string())?;
+    let mut contacts = db::fetch_contacts_for_project(project_id).map_err(|e| e.to_string())?;
+    if let Some(p) = export_pseudonymizer(Some(anonymize))? {
+        p.roster(&mut contacts);
+    }
+    if let Some(r) = redaction {
+        r.roster(&mut contacts);
+    }
+    let count = export::write_project_roster_xlsx(&path, &project_name, &contacts)?;
+    println!("✅ 导出 {} 位联系人", count);
+    Ok(count)
+}
+
+// 导出项目时间线为 Markdown（按时间先后排列，含联系人和描述），返回导出事件数
+#[tauri::command]
+fn export_project_timeline_md(project_id: i32, path: String, anonymize: Option<bool>, profile: Option<String>) -> Result<usize, String> {
+    println!("🔄 正在导出项目 {} 的时间线到: {}", project_id, path);
+    let redaction = export_redaction(profile.as_deref())?;
+    let anonymize = anonymize.unwrap_or(false) || redaction.as_ref().is_some_and(|r| r.anonymize);
+    let project_name = db::get_project_name(project_id).map_err(|e| e.to_string())?;
+    let mut events = db::fetch_events_for_project(project_id).map_err(|e| e.to_string())?;
+    events.retain(|e| !e.event.is_private);
+    if let Some(p) = export_pseudonymizer(Some(anonymize))? {
+        p.events(&mut events);
+    }
+    if let Some(r) = redaction {
+        r.events(&mut events);
+    }
+    let prefs = db::load_display_preferences().map_err(|e| e.to_string())?;
+    
+    let content = export::render_project_timeline_md(&project_name, &events, &prefs);
+    fs::write(&path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    
+    println!("✅ 导出 {} 条事件", events.len());
+    Ok(events.len())
+}
+
+// 导出项目分享页（单文件 HTML：时间线、联系人、活动看板），可隐藏备注、遮盖电话、联系人化名，
+// options.profile 指定脱敏方案时按方案脱敏
+#[tauri::command]
+fn export_project_share_html(
+    project_id: i32,
+    path: String,
+    options: Option<export::ShareHtmlOptions>,
+) -> Result<(), String> {
+    println!("🔄 正在导出项目 {} 的分享页到: {}", project_id, path);
+    let options = options.unwrap_or_default();
+    let options = match export_redaction(options.profile.as_deref())? {
+        Some(redaction) => redaction.share_options(),
+        None => options,
+    };
+    let project = db::fetch_projects(true).map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| format!("项目 {} 不存在", project_id))?;
+    let mut roster = db::fetch_contacts_for_project(project_id).map_err(|e| e.to_string())?;
+    let mut events = db::fetch_events_for_project(project_id).map_err(|e| e.to_string())?;
+    events.retain(|e| !e.event.is_private);
+    let mut activities = db::fetch_activities_for_project(project_id).map_err(|e| e.to_string())?;
+    if let Some(p) = export_pseudonymizer(Some(options.anonymize))? {
+        p.roster(&mut roster);
+        p.events(&mut events);
+        p.activities(activities.iter_mut());
+    }
+    let prefs = db::load_display_preferences().map_err(|e| e.to_string())?;
+    
+    let html = export::render_project_share_html(
+        &project.name,
+        project.description.as_deref(),
+        &roster,
+        &events,
+        &activities,
+        &options,
+        &prefs,
+    );
+    fs::write(&path, html).map_err(|e| format!("写入文件失败: {}", e))?;
+    
+    println!("✅ 分享页导出成功");
+    Ok(())
+}
+
+// 导出单表为 JSON 文件（table: projects / contacts / events / activities），字段名固定、日期为 ISO 8601，
+// anonymize 为 true 时联系人化名，profile 为脱敏方案名称（隐藏的字段导出为 null）
+#[tauri::command]
+fn export_table_json(
+    table: String,
+    filters: Option<db::TableExportFilters>,
+    path: String,
+    anonymize: Option<bool>,
+    profile: Option<String>,
+) -> Result<usize, String> {
+    println!("🔄 正在导出 {} 表到: {}", table, path);
+    let redaction = export_redaction(profile.as_deref())?;
+    let anonymize = anonymize.unwrap_or(false) || redaction.as_ref().is_some_and(|r| r.anonymize);
+    let redacted_fields = redaction
+        .map(|r| r.redacted_export_fields())
+        .unwrap_or_default();
+    let pseudonymizer = export_pseudonymizer(Some(anonymize))?;
+    let count = db::export_table_json(&table, &filters.unwrap_or_default(), &redacted_fields, pseudonymizer.as_ref(), &path)
+        .map_err(|e| e.to_string())?;
+    println!("✅ 导出 {} 条记录", count);
+    Ok(count)
+}
+
+// 导出联系人的完整档案（数据可携带）：在 path 目录下生成 dossier.json、dossier.md，
+// 并将联系人文件复制到 files/ 子目录，返回导出的文件数
+#[tauri::command]
+fn export_contact_dossier(contact_id: i32, path: String) -> Result<usize, String> {
+    println!("🔄 正在导出联系人 {} 的档案到: {}", contact_id, path);
+    let dossier = db::fetch_contact_dossier(contact_id).map_err(|e| e.to_string())?;
+    let prefs = db::load_display_preferences().map_err(|e| e.to_string())?;
+    
+    let dir = PathBuf::from(&path);
+    let files_dir = dir.join("files");
+    fs::create_dir_all(&files_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    let json = serde_json::to_string_pretty(&dossier).map_err(|e| e.to_string())?;
+    fs::write(dir.join("dossier.json"), json).map_err(|e| format!("写入文件失败: {}", e))?;
+    fs::write(dir.join("dossier.md"), export::render_contact_dossier_md(&dossier, &prefs))
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+    
+    // 同名文件的各个版本以 v<版本>_ 前缀区分；源文件已丢失的跳过
+    let mut copied = 0;
+    for file in &dossier.files {
+        let target = files_dir.join(format!("v{}_{}", file.version, file.original_name));
+        match fs::copy(&file.file_path, &target) {
+            Ok(_) => copied += 1,
+            Err(e) => println!("⚠️ 复制文件失败 {}: {}", file.file_path, e),
+        }
+    }
+    
+    println!("✅ 档案导出完成：{} 条事件、{} 个文件", dossier.events.len(), copied);
+    Ok(copied)
+}
+
+// 按模板为多个联系人批量生成文本（邮件合并），提供 project_id 时可使用 {{role_in_project}}
+#[tauri::command]
+fn render_contact_template(
+    contact_ids: Vec<i32>,
+    template: String,
+    project_id: Option<i32>,
+) -> Result<Vec<db::RenderedContactText>, String> {
+    println!("🔄 正在为 {} 位联系人生成模板文本...", contact_ids.len());
+    
+    // 项目内的联系人及角色
+    let (project_name, project_contacts) = match project_id {
+        Some(pid) => (
+            Some(db::get_project_name(pid).map_err(|e| e.to_string())?),
+            db::fetch_contacts_for_project(pid).map_err(|e| e.to_string())?,
+        ),
+        None => (None, Vec::new()),
+    };
+    
+    let mut results = Vec::new();
+    for contact_id in contact_ids {
+        let (contact, role) = match project_contacts.iter().find(|(c, _, _)| c.id == contact_id) {
+            Some((c, role, _)) => (c.clone(), role.clone()),
+            None => match db::fetch_contact_by_id(contact_id).map_err(|e| e.to_string())? {
+                Some(c) => (c, None),
+                None => continue,
+            },
+        };
+        
+        let text = export::render_contact_template(&template, &contact, role.as_deref(), project_name.as_deref());
+        results.push(db::RenderedContactText {
+            contact_id,
+            name: contact.name,
+            text,
+        });
+    }
+    
+    println!("✅ 生成 {} 条文本", results.len());
+    Ok(results)
+}
+
+// ==================== 事件提醒相关命令 ====================
+
+// 更新事件提醒时间
+#[tauri::command]
+fn update_event_reminder(event_id: i32, reminder_time: Option<String>) -> Result<(), String> {
+    println!("🔄 正在更新事件 {} 的提醒时间...", event_id);
+    db::update_event_reminder(event_id, reminder_time.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 提醒时间更新成功");
+    Ok(())
+}
+
+// 批量平移事件提醒时间（delta_minutes 可为负）
+#[tauri::command]
+fn shift_reminders(event_ids: Vec<i32>, delta_minutes: i64) -> Result<db::RescheduleResult, String> {
+    println!("🔄 正在平移 {} 个事件的提醒（{} 分钟）...", event_ids.len(), delta_minutes);
+    let result = db::shift_reminders(&event_ids, delta_minutes).map_err(|e| e.to_string())?;
+    println!("✅ 已平移 {} 个提醒", result.reminders_shifted);
+    Ok(result)
+}
+
+// 项目整体顺延：from_date（YYYY-MM-DD）及之后的事件和提醒平移 delta_days 天
+#[tauri::command]
+fn reschedule_project_events(project_id: i32, from_date: String, delta_days: i64) -> Result<db::RescheduleResult, String> {
+    println!("🔄 正在将项目 {} 自 {} 起的事件平移 {} 天...", project_id, from_date, delta_days);
+    let result = db::reschedule_project_events(project_id, &from_date, delta_days).map_err(|e| e.to_string())?;
+    println!("✅ 已平移 {} 个事件、{} 个提醒", result.events_shifted, result.reminders_shifted);
+    Ok(result)
+}
+
+// 获取当天有提醒的事件ID列表
+#[tauri::command]
+fn get_today_reminder_events() -> Result<Vec<i32>, String> {
+    println!("🔄 正在获取当天有提醒的事件...");
+    let ids = db::fetch_today_reminder_event_ids().map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个有提醒的事件", ids.len());
+    Ok(ids)
+}
+
+// 设置事件提醒模式（持续提醒直到确认）
+#[tauri::command]
+fn set_event_reminder_mode(event_id: i32, insistent: bool, repeat_minutes: Option<i32>) -> Result<(), String> {
+    println!("🔄 正在设置事件 {} 的提醒模式...", event_id);
+    db::update_event_reminder_mode(event_id, insistent, repeat_minutes)
+        .map_err(|e| e.to_string())?;
+    println!("✅ 提醒模式设置成功");
+    Ok(())
+}
+
+// 确认提醒
+#[tauri::command]
+fn acknowledge_reminder(event_id: i32) -> Result<(), String> {
+    println!("🔄 正在确认事件 {} 的提醒...", event_id);
+    db::acknowledge_reminder(event_id).map_err(|e| e.to_string())?;
+    println!("✅ 提醒已确认");
+    Ok(())
+}
+
+// 获取提醒收件箱（已触发但尚未确认的提醒）
+#[tauri::command]
+fn get_reminder_inbox() -> Result<Vec<db::ReminderInboxItem>, String> {
+    println!("🔄 正在获取提醒收件箱...");
+    let items = db::fetch_reminder_inbox().map_err(|e| e.to_string())?;
+    println!("✅ 未确认的提醒 {} 条", items.len());
+    Ok(items)
+}
+
+// 确认收件箱中的全部提醒
+#[tauri::command]
+fn acknowledge_all_reminders() -> Result<usize, String> {
+    println!("🔄 正在确认全部提醒...");
+    let count = db::acknowledge_all_reminders().map_err(|e| e.to_string())?;
+    println!("✅ 已确认 {} 条提醒", count);
+    Ok(count)
+}
+
+// 获取今日待处理数量（今日提醒 + 逾期活动）
+#[tauri::command]
+fn get_pending_count() -> Result<db::PendingCount, String> {
+    println!("🔄 正在统计今日待处理事项...");
+    let count = db::fetch_pending_count().map_err(|e| e.to_string())?;
+    println!("✅ 今日提醒 {}，逾期活动 {}", count.today_reminders, count.overdue_activities);
+    Ok(count)
+}
+
+// ==================== 总结相关命令 ====================
+
+// 手动生成总结
+#[tauri::command]
+fn generate_summary(
+    summary_type: String,
+    start_date: String,
+    end_date: String,
+) -> Result<db::Summary, String> {
+    println!("🔄 正在生成 {} 总结 ({} - {})...", summary_type, start_date, end_date);
+    let summary = db::generate_summary(&summary_type, &start_date, &end_date, false)
+        .map_err(|e| e.to_string())?;
+    println!("✅ 总结生成成功");
+    Ok(summary)
+}
+
+// 生成每日简报（默认今天，同一天重复生成时更新原简报）
+#[tauri::command]
+fn generate_daily_briefing(date: Option<String>) -> Result<db::Summary, String> {
+    let date = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|_| format!("无效的日期: {}", d))?,
+        None => Local::now().date_naive(),
+    };
+    println!("🔄 正在生成 {} 的每日简报...", date);
+    let briefing = db::generate_daily_briefing(date, false).map_err(|e| e.to_string())?;
+    println!("✅ 每日简报生成成功");
+    Ok(briefing)
+}
+
+// 生成项目复盘（同一项目重复生成时更新原复盘）
+#[tauri::command]
+fn generate_project_retrospective(project_id: i32) -> Result<db::Summary, String> {
+    println!("🔄 正在生成项目 {} 的复盘...", project_id);
+    let retrospective = db::generate_project_retrospective(project_id).map_err(|e| e.to_string())?;
+    println!("✅ 项目复盘生成成功: {}", retrospective.title);
+    Ok(retrospective)
+}
+
+// 生成项目进展邮件草稿（默认最近 7 天），polish 为 true 时再交给润色程序改写正文
+#[tauri::command]
+async fn draft_status_email(
+    project_id: i32,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    polish: Option<bool>,
+) -> Result<db::StatusEmailDraft, String> {
+    let today = Local::now().date_naive();
+    let end_date = end_date.unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    let start_date = start_date.unwrap_or_else(|| (today - chrono::Duration::days(6)).format("%Y-%m-%d").to_string());
+    println!("🔄 正在生成项目 {} 的进展邮件 ({} - {})...", project_id, start_date, end_date);
+    // 读库和润色程序（最长 2 分钟）都在后台线程执行
+    let draft = run_blocking(move || {
+        let mut draft = db::draft_status_email(project_id, &start_date, &end_date).map_err(|e| e.to_string())?;
+        if polish.unwrap_or(false) {
+            let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+            draft.body = polish::polish_text(&draft.body, &settings)?;
+            draft.polished = true;
+        }
+        Ok::<_, String>(draft)
+    }).await?;
+    println!("✅ 进展邮件生成成功: {}", draft.subject);
+    Ok(draft)
+}
+
+// 获取所有总结列表
+#[tauri::command]
+fn get_summaries() -> Result<Vec<db::Summary>, String> {
+    println!("🔄 正在获取总结列表...");
+    let summaries = db::fetch_summaries().map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个总结", summaries.len());
+    Ok(summaries)
+}
+
+// 获取总结详情
+#[tauri::command]
+fn get_summary_detail(summary_id: i32) -> Result<Option<db::Summary>, String> {
+    println!("🔄 正在获取总结 {} 详情...", summary_id);
+    let summary = db::fetch_summary_by_id(summary_id).map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// 删除总结
+#[tauri::command]
+fn delete_summary(summary_id: i32) -> Result<(), String> {
+    println!("🔄 正在删除总结 {}...", summary_id);
+    db::delete_summary(summary_id).map_err(|e| e.to_string())?;
+    println!("✅ 总结删除成功");
+    Ok(())
+}
+
+// 设置总结标签
+#[tauri::command]
+fn set_summary_tags(summary_id: i32, tags: Option<String>) -> Result<(), String> {
+    println!("🔄 正在设置总结 {} 的标签...", summary_id);
+    db::update_summary_tags(summary_id, tags.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 标签设置成功");
+    Ok(())
+}
+
+// 搜索总结
+#[tauri::command]
+async fn search_summaries(keyword: String) -> Result<Vec<search::Highlighted<db::Summary>>, String> {
+    println!("🔄 正在搜索总结: {}", keyword);
+    let keyword = keyword.trim().to_string();
+    let summaries = search::run(&search::SUMMARY_SEARCH, "", keyword, |keyword, is_cancelled| {
+        db::search_summaries(keyword, is_cancelled)
+    }).await?;
+    println!("✅ 找到 {} 个匹配总结", summaries.len());
+    Ok(summaries)
+}
+
+// 重新生成总结（保留原记录）
+#[tauri::command]
+fn regenerate_summary(summary_id: i32) -> Result<db::Summary, String> {
+    println!("🔄 正在重新生成总结 {}...", summary_id);
+    let summary = db::regenerate_summary(summary_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("总结不存在")?;
+    println!("✅ 总结已重新生成");
+    Ok(summary)
+}
+
+// 对比两个时间段的总结统计（period_a 为基准期）
+#[tauri::command]
+fn compare_summaries(
+    period_a: db::SummaryPeriod,
+    period_b: db::SummaryPeriod,
+) -> Result<db::SummaryComparison, String> {
+    println!("🔄 正在对比 {} - {} 与 {} - {}...",
+        period_a.start_date, period_a.end_date, period_b.start_date, period_b.end_date);
+    let comparison = db::compare_summaries(&period_a, &period_b).map_err(|e| e.to_string())?;
+    println!("✅ 对比完成");
+    Ok(comparison)
+}
+
+// ==================== 置顶相关命令 ====================
+
+// 切换事件置顶
+#[tauri::command]
+fn toggle_event_pin(event_id: i32) -> Result<bool, String> {
+    println!("🔄 正在切换事件 {} 的置顶状态...", event_id);
+    let pinned = db::toggle_event_pin(event_id).map_err(|e| e.to_string())?;
+    println!("✅ 事件{}", if pinned { "已置顶" } else { "已取消置顶" });
+    Ok(pinned)
+}
+
+// 设置事件是否私密（私密事件不出现在导出、分享快照和自动总结中）
+#[tauri::command]
+fn set_event_private(event_id: i32, is_private: bool) -> Result<(), String> {
+    if !db::set_event_private(event_id, is_private).map_err(|e| e.to_string())? {
+        return Err(format!("事件不存在: {}", event_id));
+    }
+    println!("✅ 事件 {} {}", event_id, if is_private { "已设为私密" } else { "已取消私密" });
+    Ok(())
+}
+
+// 切换联系人置顶
+#[tauri::command]
+fn toggle_contact_pin(contact_id: i32) -> Result<bool, String> {
+    println!("🔄 正在切换联系人 {} 的置顶状态...", contact_id);
+    let pinned = db::toggle_contact_pin(contact_id).map_err(|e| e.to_string())?;
+    println!("✅ 联系人{}", if pinned { "已置顶" } else { "已取消置顶" });
+    Ok(pinned)
+}
+
+// 获取所有置顶项
+#[tauri::command]
+fn get_pinned_items() -> Result<db::PinnedItems, String> {
+    println!("🔄 正在获取置顶项...");
+    let items = db::fetch_pinned_items().map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个置顶事件，{} 个置顶联系人", items.events.len(), items.contacts.len());
+    Ok(items)
+}
+
+// ==================== 事件类型与配色相关命令 ====================
+
+// 获取事件类型目录
+#[tauri::command]
+fn get_event_types() -> Result<Vec<db::EventType>, String> {
+    println!("🔄 正在获取事件类型列表...");
+    let types = db::fetch_event_types().map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个事件类型", types.len());
+    Ok(types)
+}
+
+// 新增或更新事件类型
+#[tauri::command]
+fn save_event_type(
+    name: String,
+    color: Option<String>,
+    icon: Option<String>,
+    sort_order: Option<i32>,
+    default_reminder_offset_minutes: Option<i32>,
+    notification_urgency: Option<String>,
+) -> Result<(), String> {
+    println!("🔄 正在保存事件类型: {}", name);
+    if name.trim().is_empty() {
+        return Err("事件类型名称不能为空".to_string());
+    }
+    db::save_event_type(
+        name.trim(),
+        color.as_deref(),
+        icon.as_deref(),
+        sort_order,
+        default_reminder_offset_minutes,
+        notification_urgency.as_deref().filter(|u| !u.is_empty()),
+    )
+        .map_err(|e| e.to_string())?;
+    println!("✅ 事件类型保存成功");
+    Ok(())
+}
+
+// 获取可选的提醒通知紧急程度
+#[tauri::command]
+fn get_notification_urgencies() -> Vec<&'static str> {
+    db::NOTIFICATION_URGENCIES.to_vec()
+}
+
+// 删除事件类型
+#[tauri::command]
+fn delete_event_type(name: String) -> Result<(), String> {
+    println!("🔄 正在删除事件类型: {}", name);
+    db::delete_event_type(&name).map_err(|e| e.to_string())?;
+    println!("✅ 事件类型删除成功");
+    Ok(())
+}
+
+// 设置项目颜色和图标
+#[tauri::command]
+fn set_project_style(project_id: i32, color: Option<String>, icon: Option<String>) -> Result<(), String> {
+    println!("🔄 正在设置项目 {} 的颜色和图标...", project_id);
+    db::update_project_style(project_id, color.as_deref(), icon.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 项目样式设置成功");
+    Ok(())
+}
+
+// ==================== 沟通渠道相关命令 ====================
+
+// 获取常用沟通渠道
+#[tauri::command]
+fn get_event_channels() -> Vec<&'static str> {
+    db::EVENT_CHANNELS.to_vec()
+}
+
+// 联系人沟通渠道统计（only_channel 如 "微信"：只列出仅通过该渠道沟通的联系人）
+#[tauri::command]
+fn get_contact_channel_stats(contact_id: Option<i32>, only_channel: Option<String>) -> Result<Vec<db::ContactChannelStats>, String> {
+    println!("🔄 正在统计联系人沟通渠道...");
+    let stats = db::fetch_contact_channel_stats(contact_id, only_channel.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 统计 {} 位联系人", stats.len());
+    Ok(stats)
+}
+
+// ==================== 启动状态相关命令 ====================
+
+// 获取启动时数据库的打开情况（是否损坏、是否已恢复）
+#[tauri::command]
+fn get_startup_status() -> Result<db::StartupStatus, String> {
+    db::get_startup_status().ok_or("数据库尚未初始化".to_string())
+}
+
+// 接口版本（接口有不兼容的变化时递增）
+const API_VERSION: u32 = 1;
+
+// 有多个版本的命令：(命令名, 支持的版本, 已弃用的版本)，v2 及以后的命令名带 _vN 后缀
+const COMMAND_VERSIONS: &[(&str, &[u32], &[u32])] = &[
+    ("create_contact", &[1, 2], &[1]),
+    ("create_event", &[1, 2], &[1]),
+    ("update_event", &[1, 2], &[1]),
+];
+
+// 获取接口信息：应用和数据库版本、可选功能、命令版本
+#[tauri::command]
+fn get_api_info() -> Result<db::ApiInfo, String> {
+    let (schema_version, latest_migration) = db::fetch_schema_version().map_err(|e| e.to_string())?;
+    Ok(db::ApiInfo {
+        api_version: API_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        latest_migration,
+        memory_db: db::is_memory_db(),
+        features: db::ApiFeatures {
+            sync: false,
+            ai: false,
+            encryption: false,
+        },
+        commands: COMMAND_VERSIONS.iter().map(|(name, versions, deprecated)| db::CommandVersions {
+            name: name.to_string(),
+            versions: versions.to_vec(),
+            deprecated_versions: deprecated.to_vec(),
+        }).collect(),
+    })
+}
+
+// 获取数据库语句耗时统计（按总耗时排序，默认前 50 条）
+#[tauri::command]
+fn get_query_metrics(limit: Option<usize>) -> Result<Vec<db::QueryMetric>, String> {
+    Ok(db::fetch_query_metrics(limit.unwrap_or(50)))
+}
+
+// 清空语句耗时统计
+#[tauri::command]
+fn reset_query_metrics() -> Result<(), String> {
+    db::reset_query_metrics();
+    println!("✅ 已清空查询耗时统计");
+    Ok(())
+}
+
+// 在空数据库中生成演示数据（配合 MEMORYSTACK_DB=:memory: 用于演示和截图）
+#[tauri::command]
+fn seed_demo_data() -> Result<db::DemoDataSummary, String> {
+    println!("🔄 正在生成演示数据...");
+    let summary = db::seed_demo_data().map_err(|e| e.to_string())?;
+    println!("✅ 演示数据生成完成：{} 个项目，{} 位联系人，{} 个事件", summary.projects, summary.contacts, summary.events);
+    Ok(summary)
+}
+
+// ==================== 数据完整性相关命令 ====================
+
+// 获取数据完整性检查报告
+#[tauri::command]
+fn get_integrity_report() -> Result<db::IntegrityReport, String> {
+    println!("🔄 正在检查数据完整性...");
+    let report = db::fetch_integrity_report().map_err(|e| e.to_string())?;
+    println!("✅ 检查完成，发现 {} 类问题", report.issues.len());
+    Ok(report)
+}
+
+// 修复指定类型的完整性问题（kinds 取自报告中的 kind）
+#[tauri::command]
+fn repair_integrity(kinds: Vec<String>) -> Result<Vec<db::IntegrityRepair>, String> {
+    println!("🔄 正在修复数据完整性问题: {:?}", kinds);
+    let kinds: Vec<&str> = kinds.iter().map(|k| k.as_str()).collect();
+    let repairs = db::repair_integrity(&kinds).map_err(|e| e.to_string())?;
+    let removed: usize = repairs.iter().map(|r| r.removed).sum();
+    println!("✅ 修复完成，删除 {} 条记录", removed);
+    Ok(repairs)
+}
+
+// ==================== 后台任务相关命令 ====================
+
+// 获取所有后台任务的执行时间
+#[tauri::command]
+fn get_job_schedules() -> Result<Vec<scheduler::JobSchedule>, String> {
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    Ok(scheduler::describe_schedules(&settings, Local::now().naive_local()))
+}
+
+// 设置后台任务的执行时间（cron 表达式，如 "0 9 * * 1-5"；为空时恢复默认）
+#[tauri::command]
+fn set_job_schedule(job_name: String, schedule: Option<String>) -> Result<(), String> {
+    println!("🔄 正在设置后台任务 {} 的执行时间: {:?}", job_name, schedule);
+    let job = scheduler::find_job(&job_name).ok_or(format!("未知的后台任务: {}", job_name))?;
+    let schedule = schedule.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    db::set_setting(&scheduler::schedule_setting_key(job.name), schedule.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 执行时间设置成功");
+    Ok(())
+}
+
+// 获取后台任务状态（执行时间、最近一次执行结果、是否正在执行）
+#[tauri::command]
+fn get_background_jobs_status() -> Result<Vec<scheduler::BackgroundJobStatus>, String> {
+    println!("🔄 正在获取后台任务状态...");
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    let status = scheduler::describe_status(&settings, Local::now().naive_local()).map_err(|e| e.to_string())?;
+    println!("✅ 共 {} 个后台任务", status.len());
+    Ok(status)
+}
+
+// 获取后台任务执行记录（job_name 为空时返回所有任务；page 从 0 开始）
+#[tauri::command]
+fn get_job_runs(job_name: Option<String>, limit: Option<i64>, page: Option<i64>) -> Result<Vec<db::JobRun>, String> {
+    db::fetch_job_runs(job_name.as_deref(), limit.unwrap_or(50), page.unwrap_or(0)).map_err(|e| e.to_string())
+}
+
+// 立即执行一个后台任务
+#[tauri::command]
+fn run_job_now(app_handle: tauri::AppHandle, job_name: String) -> Result<db::JobRun, String> {
+    println!("🔄 正在手动执行后台任务: {}", job_name);
+    let job = scheduler::find_job(&job_name).ok_or(format!("未知的后台任务: {}", job_name))?;
+    let run = execute_job(&app_handle, job, "manual")?;
+    if run.success {
+        println!("✅ 任务「{}」执行完成: {}", job.label, run.message.as_deref().unwrap_or(""));
+    } else {
+        println!("⚠️ 任务「{}」执行失败: {}", job.label, run.message.as_deref().unwrap_or(""));
+    }
+    Ok(run)
+}
+
+// ==================== 出差拜访相关命令 ====================
+
+// 获取某城市的联系人（出差前列出值得拜访的人）
+#[tauri::command]
+fn get_contacts_by_city(city: String) -> Result<Vec<db::CityContact>, String> {
+    println!("🔄 正在查询城市联系人: {}", city);
+    let contacts = db::fetch_contacts_by_city(&city).map_err(|e| e.to_string())?;
+    println!("✅ {} 共 {} 位联系人", city, contacts.len());
+    Ok(contacts)
+}
+
+// 按联系人所在城市汇总项目
+#[tauri::command]
+fn get_projects_by_region() -> Result<Vec<db::RegionProjects>, String> {
+    println!("🔄 正在按城市汇总项目...");
+    let regions = db::fetch_projects_by_region().map_err(|e| e.to_string())?;
+    println!("✅ 共 {} 个城市", regions.len());
+    Ok(regions)
+}
+
+// ==================== 工作时间与会议安排相关命令 ====================
+
+// 获取联系人的工作时间（未设置时为 null）
+#[tauri::command]
+fn get_contact_availability(contact_id: i32) -> Result<Option<db::ContactAvailability>, String> {
+    db::get_contact_availability(contact_id).map_err(|e| e.to_string())
+}
+
+// 设置联系人的工作时间和时区，availability 为 null 时清除（恢复默认的本机工作时间）
+#[tauri::command]
+fn set_contact_availability(contact_id: i32, availability: Option<db::ContactAvailability>) -> Result<(), String> {
+    println!("🔄 正在设置联系人 {} 的工作时间...", contact_id);
+    match availability {
+        Some(availability) => db::set_contact_availability(&db::ContactAvailability { contact_id, ..availability })
+            .map_err(|e| e.to_string())?,
+        None => {
+            db::delete_contact_availability(contact_id).map_err(|e| e.to_string())?;
+        }
+    }
+    println!("✅ 工作时间已保存");
+    Ok(())
+}
+
+// 推荐会议时间：所有联系人都在工作时间内且没有其他事件的时段
+#[tauri::command]
+fn suggest_meeting_slots(
+    contact_ids: Vec<i32>,
+    duration_minutes: i64,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<db::MeetingSlot>, String> {
+    println!("🔄 正在为 {} 位联系人推荐会议时间（{} - {}）...", contact_ids.len(), start_date, end_date);
+    let slots = db::suggest_meeting_slots(&contact_ids, duration_minutes, &start_date, &end_date).map_err(|e| e.to_string())?;
+    println!("✅ 推荐 {} 个时间段", slots.len());
+    Ok(slots)
+}
+
+// ==================== 合同/发票/付款台账相关命令 ====================
+
+// 新建或修改台账条目（commitment_id 为空时新建），到期前按 remind_days_before 天自动提醒
+#[tauri::command]
+fn save_commitment(commitment: db::CommitmentInput) -> Result<db::Commitment, String> {
+    println!("🔄 正在保存{}: {}", commitment.commitment_type, commitment.title);
+    let saved = db::save_commitment(&commitment).map_err(|e| e.to_string())?;
+    println!("✅ 已保存，将于 {} 提醒", saved.reminder_at.as_deref().unwrap_or("-"));
+    Ok(saved)
+}
+
+// 修改台账条目状态（待处理 / 已完成 / 已取消）
+#[tauri::command]
+fn set_commitment_status(commitment_id: i32, status: String) -> Result<(), String> {
+    db::set_commitment_status(commitment_id, &status).map_err(|e| e.to_string())
+}
+
+// 删除台账条目
+#[tauri::command]
+fn delete_commitment(commitment_id: i32) -> Result<(), String> {
+    if !db::delete_commitment(commitment_id).map_err(|e| e.to_string())? {
+        return Err(format!("台账条目不存在: {}", commitment_id));
+    }
+    Ok(())
+}
+
+// 获取即将到期的待处理条目（默认今后 30 天，包含已逾期的条目；指定 start_date 时只取该日期之后的）
+#[tauri::command]
+fn get_upcoming_commitments(start_date: Option<String>, end_date: Option<String>) -> Result<Vec<db::Commitment>, String> {
+    let end_date = end_date.unwrap_or_else(|| (Local::now().date_naive() + chrono::Duration::days(30)).format("%Y-%m-%d").to_string());
+    println!("🔄 正在获取 {} 前到期的台账条目...", end_date);
+    let commitments = db::fetch_upcoming_commitments(start_date.as_deref(), &end_date).map_err(|e| e.to_string())?;
+    println!("✅ 共 {} 条", commitments.len());
+    Ok(commitments)
+}
+
+// 即将到期的待处理条目按类型合计金额（不同币种分别合计，日期范围同 get_upcoming_commitments）
+#[tauri::command]
+fn get_commitment_totals(start_date: Option<String>, end_date: Option<String>) -> Result<Vec<db::CommitmentTotal>, String> {
+    let end_date = end_date.unwrap_or_else(|| (Local::now().date_naive() + chrono::Duration::days(30)).format("%Y-%m-%d").to_string());
+    println!("🔄 正在合计 {} 前到期的台账金额...", end_date);
+    let totals = db::fetch_commitment_totals(start_date.as_deref(), &end_date).map_err(|e| e.to_string())?;
+    println!("✅ 共 {} 类", totals.len());
+    Ok(totals)
+}
+
+// ==================== 项目健康度相关命令 ====================
+
+// 获取所有项目的健康度和风险提示（默认不含已归档项目）
+#[tauri::command]
+fn get_project_health(include_archived: Option<bool>) -> Result<Vec<db::ProjectHealth>, String> {
+    println!("🔄 正在计算项目健康度...");
+    let health = db::fetch_project_health(include_archived.unwrap_or(false)).map_err(|e| e.to_string())?;
+    let at_risk = health.iter().filter(|h| h.level == "风险").count();
+    println!("✅ 计算完成：{} 个项目，{} 个存在风险", health.len(), at_risk);
+    Ok(health)
+}
+
+// 获取项目最近若干周的完成速度和完成预测
+#[tauri::command]
+fn get_project_velocity(project_id: i32, weeks: Option<u32>) -> Result<db::ProjectVelocity, String> {
+    println!("🔄 正在计算项目 {} 的完成速度...", project_id);
+    let velocity = db::fetch_project_velocity(project_id, weeks.unwrap_or(8)).map_err(|e| e.to_string())?;
+    println!("✅ 平均每周完成 {:.1} 个活动，剩余 {} 个", velocity.average_per_week, velocity.remaining);
+    Ok(velocity)
+}
+
+// ==================== 节假日与工作日相关命令 ====================
+
+// 获取一段时间内的节假日和调休记录
+#[tauri::command]
+fn get_holidays(start_date: String, end_date: String) -> Result<Vec<db::Holiday>, String> {
+    println!("🔄 正在获取 {} 至 {} 的节假日...", start_date, end_date);
+    let holidays = db::fetch_holidays(&start_date, &end_date).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 条节假日记录", holidays.len());
+    Ok(holidays)
+}
+
+// 导入节假日文件（holiday-cn 格式的 JSON，或 日期,名称,是否调休上班 的 CSV）
+#[tauri::command]
+fn import_holiday_calendar(path: String) -> Result<usize, String> {
+    println!("🔄 正在导入节假日: {}", path);
+    let holidays = import::parse_holiday_file(&path)?;
+    let count = db::save_holidays(&holidays).map_err(|e| e.to_string())?;
+    println!("✅ 导入 {} 条节假日记录", count);
+    Ok(count)
+}
+
+// 设置单个节假日或调休上班日
+#[tauri::command]
+fn set_holiday(date: String, name: String, is_workday: bool) -> Result<(), String> {
+    println!("🔄 正在设置节假日: {} {}", date, name);
+    if chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+        return Err(format!("日期格式错误: {}", date));
+    }
+    db::save_holidays(&[import::ImportedHoliday { date, name, is_workday }])
+        .map_err(|e| e.to_string())?;
+    println!("✅ 节假日设置成功");
+    Ok(())
+}
+
+// 删除节假日记录
+#[tauri::command]
+fn delete_holiday(date: String) -> Result<(), String> {
+    println!("🔄 正在删除节假日 {}...", date);
+    db::delete_holiday(&date).map_err(|e| e.to_string())?;
+    println!("✅ 节假日删除成功");
+    Ok(())
+}
+
+// 获取指定日期之后的下一个工作日（跳过周末和节假日，计入调休上班日）
+#[tauri::command]
+fn next_working_day(date: String) -> Result<String, String> {
+    let day = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| format!("日期格式错误: {}", date))?;
+    let calendar = db::load_work_calendar().map_err(|e| e.to_string())?;
+    calendar.next_working_day(day)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .ok_or(format!("{} 之后一个月内没有工作日", date))
+}
+
+// ==================== 相关条目关联命令 ====================
+
+// 关联两条记录（实体类型：project / contact / event / activity / file / contact_file / summary）
+#[tauri::command]
+fn link_entities(
+    source_type: String,
+    source_id: i32,
+    target_type: String,
+    target_id: i32,
+    label: Option<String>,
+) -> Result<i64, String> {
+    println!("🔄 正在关联 {} {} 与 {} {}...", source_type, source_id, target_type, target_id);
+    let link_id = db::link_entities(&source_type, source_id, &target_type, target_id, label.as_deref())
+        .map_err(|e| e.to_string())?;
+    println!("✅ 关联成功");
+    Ok(link_id)
+}
+
+// 取消关联
+#[tauri::command]
+fn unlink_entities(link_id: i64) -> Result<(), String> {
+    println!("🔄 正在取消关联 {}...", link_id);
+    db::unlink_entities(link_id).map_err(|e| e.to_string())?;
+    println!("✅ 已取消关联");
+    Ok(())
+}
+
+// 获取相关条目（另请参阅）
+#[tauri::command]
+fn get_related_items(entity_type: String, entity_id: i32) -> Result<Vec<db::RelatedItem>, String> {
+    println!("🔄 正在获取 {} {} 的相关条目...", entity_type, entity_id);
+    let items = db::fetch_related_items(&entity_type, entity_id).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个相关条目", items.len());
+    Ok(items)
+}
+
+// 导出关系图谱（format: json / graphml），提供 path 时同时写入文件，anonymize 为 true 或脱敏方案要求化名时联系人化名
+#[tauri::command]
+fn export_graph(format: String, path: Option<String>, anonymize: Option<bool>, profile: Option<String>) -> Result<String, String> {
+    println!("🔄 正在导出关系图谱（{}）...", format);
+    let anonymize = anonymize.unwrap_or(false) || export_redaction(profile.as_deref())?.is_some_and(|r| r.anonymize);
+    let mut graph = db::fetch_workspace_graph().map_err(|e| e.to_string())?;
+    if let Some(p) = export_pseudonymizer(Some(anonymize))? {
+        p.graph(&mut graph);
+    }
+    
+    let content = match format.to_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(&graph).map_err(|e| e.to_string())?,
+        "graphml" => export::graph_to_graphml(&graph),
+        other => return Err(format!("不支持的图谱格式: {}", other)),
+    };
+    
+    if let Some(ref path) = path {
+        fs::write(path, &content).map_err(|e| format!("写入文件失败: {}", e))?;
+    }
+    
+    println!("✅ 导出 {} 个节点、{} 条连线", graph.nodes.len(), graph.edges.len());
+    Ok(content)
+}
+
+// ==================== 设置相关命令 ====================
+
+// 获取所有设置项
+#[tauri::command]
+fn get_settings() -> Result<std::collections::HashMap<String, String>, String> {
+    db::fetch_settings().map_err(|e| e.to_string())
+}
+
+// 保存设置项（value 为空时恢复默认值）
+#[tauri::command]
+fn set_setting(key: String, value: Option<String>) -> Result<(), String> {
+    println!("🔄 正在保存设置: {} = {:?}", key, value);
+    db::set_setting(&key, value.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 设置保存成功");
+    Ok(())
+}
+
+// 设置代表"我"的联系人（传空清除）
+#[tauri::command]
+fn set_me_contact(contact_id: Option<i32>) -> Result<(), String> {
+    println!("🔄 正在设置\"我\"的联系人: {:?}", contact_id);
+    let value = contact_id.map(|id| id.to_string());
+    db::set_setting(db::SETTING_ME_CONTACT_ID, value.as_deref()).map_err(|e| e.to_string())?;
+    println!("✅ 设置成功");
+    Ok(())
+}
+
+// ==================== 我的视图相关命令 ====================
+
+// 读取"我"的联系人ID，未设置时返回错误提示
+fn require_me_contact() -> Result<i32, String> {
+    db::get_me_contact_id()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "尚未在设置中指定代表\"我\"的联系人".to_string())
+}
+
+// 获取分配给我的未完成活动（默认不含已归档项目的活动）
+#[tauri::command]
+fn get_my_activities(include_archived: Option<bool>) -> Result<Vec<(db::ActivityWithDetails, String)>, String> {
+    println!("🔄 正在获取我的活动...");
+    let me = require_me_contact()?;
+    let activities = db::fetch_my_activities(me, include_archived.unwrap_or(false)).map_err(|e| e.to_string())?;
+    println!("✅ 获取到 {} 个活动", activities.len());
+    Ok(activities)
+}
+
+// 获取我参与的事件（可按日期范围过滤，默认不含已归档项目的事件）
 #[tauri::command]
-fn assign_activity_contacts(
-    activity_id: i32,
-    contact_ids: Vec<i32>,
-) -> Result<(), String> {
-    println!("🔄 正在为活动 {} 分配负责人...", activity_id);
-    db::assign_contacts_to_activity(activity_id as i64, &contact_ids)
+fn get_my_events(start_date: Option<String>, end_date: Option<String>, include_archived: Option<bool>) -> Result<Vec<db::EventWithDetails>, String> {
+    println!("🔄 正在获取我的事件...");
+    let me = require_me_contact()?;
+    let events = db::fetch_my_events(me, start_date.as_deref(), end_date.as_deref(), include_archived.unwrap_or(false))
         .map_err(|e| e.to_string())?;
-    println!("✅ 负责人分配成功");
-    Ok(())
+    println!("✅ 获取到 {} 个事件", events.len());
+    Ok(events)
 }
 
-// 移除活动负责人
+// ==================== 存储位置相关命令 ====================
+
+// 检查数据库存储位置（同步盘、冲突副本、文件锁）
 #[tauri::command]
-fn unassign_activity_contact(
-    activity_id: i32,
-    contact_id: i32,
-) -> Result<(), String> {
-    println!("🔄 正在移除活动 {} 的负责人 {}...", activity_id, contact_id);
-    db::unassign_contact_from_activity(activity_id, contact_id)
-        .map_err(|e| e.to_string())?;
-    println!("✅ 负责人移除成功");
-    Ok(())
+fn check_storage_health() -> Result<db::StorageHealth, String> {
+    println!("🔄 正在检查存储位置...");
+    let health = db::check_storage_health();
+    for warning in &health.warnings {
+        println!("⚠️ {}", warning);
+    }
+    println!("✅ 存储检查完成");
+    Ok(health)
 }
 
-// 激活活动
+// 获取存储设置
 #[tauri::command]
-fn activate_activity(activity_id: i32) -> Result<(), String> {
-    println!("🔄 正在激活活动 {}...", activity_id);
-    db::activate_activity(activity_id).map_err(|e| e.to_string())?;
-    println!("✅ 活动已激活");
-    Ok(())
+fn get_storage_settings() -> Result<db::StorageSettings, String> {
+    Ok(db::load_storage_settings())
 }
 
-// 暂停活动
+// 将数据库迁移到新目录（立即切换到新文件）
 #[tauri::command]
-fn pause_activity(activity_id: i32) -> Result<(), String> {
-    println!("🔄 正在暂停活动 {}...", activity_id);
-    db::pause_activity(activity_id).map_err(|e| e.to_string())?;
-    println!("✅ 活动已暂停");
-    Ok(())
+fn relocate_database(target_dir: String) -> Result<String, String> {
+    println!("🔄 正在将数据库迁移到: {}", target_dir);
+    let new_path = db::relocate_database(&target_dir).map_err(|e| e.to_string())?;
+    println!("✅ 数据库已迁移到 {}", new_path);
+    Ok(new_path)
 }
 
-// 完成活动
+// ==================== 首次使用引导相关命令 ====================
+
+// 读取引导进度
+fn load_onboarding_state() -> Result<onboarding::OnboardingState, String> {
+    let settings = db::fetch_settings().map_err(|e| e.to_string())?;
+    let has_data = db::has_workspace_data().map_err(|e| e.to_string())?;
+    Ok(onboarding::load_state(&settings, has_data))
+}
+
+// 记录引导步骤的状态（done / skipped），返回更新后的进度
+fn mark_onboarding_step(step: &str, status: &str) -> Result<onboarding::OnboardingState, String> {
+    db::set_setting(&onboarding::setting_key(step), Some(status)).map_err(|e| e.to_string())?;
+    load_onboarding_state()
+}
+
+// 获取引导进度
 #[tauri::command]
-fn complete_activity(activity_id: i32) -> Result<(), String> {
-    println!("🔄 正在完成活动 {}...", activity_id);
-    db::complete_activity(activity_id).map_err(|e| e.to_string())?;
-    println!("✅ 活动已完成");
-    Ok(())
+fn get_onboarding_state() -> Result<onboarding::OnboardingState, String> {
+    load_onboarding_state()
 }
 
-// 删除活动
+// 跳过引导步骤
 #[tauri::command]
-fn delete_activity(activity_id: i32) -> Result<(), String> {
-    println!("🔄 正在删除活动 {}...", activity_id);
-    db::delete_activity(activity_id).map_err(|e| e.to_string())?;
-    println!("✅ 活动删除成功");
-    Ok(())
+fn skip_onboarding_step(step: String) -> Result<onboarding::OnboardingState, String> {
+    let def = onboarding::find_step(&step).ok_or(format!("未知的引导步骤: {}", step))?;
+    println!("🔄 跳过引导步骤: {}", def.label);
+    mark_onboarding_step(def.id, onboarding::STATUS_SKIPPED)
 }
 
-// 导出所有活动为JSON（前端会转换为Excel）
+// 引导：生成示例工作区（只能在空数据库中进行）
 #[tauri::command]
-fn export_activities() -> Result<Vec<(db::ActivityWithDetails, String)>, String> {
-    println!("🔄 正在导出所有活动...");
-    let activities = db::fetch_all_activities_with_project().map_err(|e| e.to_string())?;
-    println!("✅ 导出 {} 个活动", activities.len());
-    Ok(activities)
+fn onboarding_create_sample_workspace() -> Result<onboarding::OnboardingState, String> {
+    println!("🔄 正在创建示例工作区...");
+    let summary = db::seed_demo_data().map_err(|e| e.to_string())?;
+    println!("✅ 示例工作区已创建：{} 个项目，{} 位联系人，{} 个事件", summary.projects, summary.contacts, summary.events);
+    mark_onboarding_step("sample_workspace", onboarding::STATUS_DONE)
 }
 
-// ==================== 事件提醒相关命令 ====================
+// 引导：按预览结果（preview_contact_import）导入已有联系人
+#[tauri::command]
+fn onboarding_import_contacts(decisions: Vec<db::ContactImportDecision>) -> Result<onboarding::OnboardingState, String> {
+    println!("🔄 正在导入 {} 个联系人...", decisions.len());
+    let result = db::commit_contact_import(&decisions).map_err(|e| e.to_string())?;
+    println!("✅ 导入完成: 新建 {}，合并 {}，跳过 {}", result.created, result.merged, result.skipped);
+    mark_onboarding_step("import_contacts", onboarding::STATUS_DONE)
+}
 
-// 更新事件提醒时间
+// 引导：选择数据库存储位置（target_dir 为空表示使用默认位置）
 #[tauri::command]
-fn update_event_reminder(event_id: i32, reminder_time: Option<String>) -> Result<(), String> {
-    println!("🔄 正在更新事件 {} 的提醒时间...", event_id);
-    db::update_event_reminder(event_id, reminder_time.as_deref())
-        .map_err(|e| e.to_string())?;
-    println!("✅ 提醒时间更新成功");
-    Ok(())
+fn onboarding_choose_storage(target_dir: Option<String>) -> Result<onboarding::OnboardingState, String> {
+    let target_dir = target_dir.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    match target_dir {
+        Some(target_dir) => {
+            println!("🔄 正在将数据库迁移到: {}", target_dir);
+            // 迁移后连接已切换到新文件，步骤进度写入新位置的数据库
+            let new_path = db::relocate_database(&target_dir).map_err(|e| e.to_string())?;
+            println!("✅ 数据库已迁移到 {}", new_path);
+        }
+        None => println!("✅ 使用默认存储位置"),
+    }
+    mark_onboarding_step("storage_location", onboarding::STATUS_DONE)
 }
 
-// 获取当天有提醒的事件ID列表
+// 引导：设置代表"我"的联系人（选择已有联系人，或新建一位）
 #[tauri::command]
-fn get_today_reminder_events() -> Result<Vec<i32>, String> {
-    println!("🔄 正在获取当天有提醒的事件...");
-    let ids = db::fetch_today_reminder_event_ids().map_err(|e| e.to_string())?;
-    println!("✅ 获取到 {} 个有提醒的事件", ids.len());
-    Ok(ids)
+fn onboarding_set_me_contact(contact_id: Option<i32>, contact: Option<db::NewContact>) -> Result<onboarding::OnboardingState, String> {
+    let me = match (contact_id, contact) {
+        (Some(id), _) => {
+            db::fetch_contact_by_id(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("联系人 {} 不存在", id))?;
+            id
+        }
+        (None, Some(contact)) => {
+            contact.validate()?;
+            let company = prepare_contact_email(contact.email.as_deref(), contact.company.clone())?;
+            db::insert_contact(
+                &contact.name,
+                contact.title.as_deref(),
+                contact.notes.as_deref(),
+                contact.tags.as_deref(),
+                contact.phone.as_deref(),
+                contact.email.as_deref(),
+                contact.address.as_deref(),
+                company.as_deref(),
+            ).map_err(|e| e.to_string())? as i32
+        }
+        (None, None) => return Err("请选择或新建代表\"我\"的联系人".to_string()),
+    };
+    println!("🔄 正在设置\"我\"的联系人: {}", me);
+    db::set_setting(db::SETTING_ME_CONTACT_ID, Some(&me.to_string())).map_err(|e| e.to_string())?;
+    mark_onboarding_step("me_contact", onboarding::STATUS_DONE)
 }
 
-// ==================== 总结相关命令 ====================
+// 引导：配置提醒通知（提醒声音、每日简报通知、活动分配通知）
+#[tauri::command]
+fn onboarding_configure_reminders(
+    notification_sound: bool,
+    briefing_notification: bool,
+    assignment_notification: bool,
+) -> Result<onboarding::OnboardingState, String> {
+    println!("🔄 正在保存提醒设置...");
+    let switch = |on: bool| if on { "on" } else { "off" };
+    for (key, on) in [
+        (db::SETTING_NOTIFICATION_SOUND, notification_sound),
+        (db::SETTING_BRIEFING_NOTIFICATION, briefing_notification),
+        (db::SETTING_ASSIGNMENT_NOTIFICATION, assignment_notification),
+    ] {
+        db::set_setting(key, Some(switch(on))).map_err(|e| e.to_string())?;
+    }
+    mark_onboarding_step("reminders", onboarding::STATUS_DONE)
+}
 
-// 手动生成总结
+// 数据库统计（各表行数、文件大小、最大项目、每月增长）
 #[tauri::command]
-fn generate_summary(
-    summary_type: String,
-    start_date: String,
-    end_date: String,
-) -> Result<db::Summary, String> {
-    println!("🔄 正在生成 {} 总结 ({} - {})...", summary_type, start_date, end_date);
-    let summary = db::generate_summary(&summary_type, &start_date, &end_date, false)
-        .map_err(|e| e.to_string())?;
-    println!("✅ 总结生成成功");
-    Ok(summary)
+fn get_database_stats() -> Result<db::DatabaseStats, String> {
+    println!("🔄 正在统计数据库...");
+    let stats = db::fetch_database_stats().map_err(|e| e.to_string())?;
+    println!("✅ 数据库大小 {} 字节，共 {} 张表", stats.db_size_bytes, stats.tables.len());
+    Ok(stats)
 }
 
-// 获取所有总结列表
+// ==================== 联系人导入相关命令 ====================
+
+// 预览联系人导入（CSV/vCard），列出可能重复的已有联系人
 #[tauri::command]
-fn get_summaries() -> Result<Vec<db::Summary>, String> {
-    println!("🔄 正在获取总结列表...");
-    let summaries = db::fetch_summaries().map_err(|e| e.to_string())?;
-    println!("✅ 获取到 {} 个总结", summaries.len());
-    Ok(summaries)
+fn preview_contact_import(path: String) -> Result<Vec<db::ContactImportPreviewRow>, String> {
+    println!("🔄 正在解析联系人文件: {}", path);
+    let rows = import::parse_contacts_file(&path)?;
+    let preview = db::preview_contact_import(rows).map_err(|e| e.to_string())?;
+    let duplicates = preview.iter().filter(|r| !r.matches.is_empty()).count();
+    println!("✅ 解析到 {} 个联系人，其中 {} 个可能重复", preview.len(), duplicates);
+    Ok(preview)
 }
 
-// 获取总结详情
+// 按逐行决定（新建/合并/跳过）执行联系人导入
 #[tauri::command]
-fn get_summary_detail(summary_id: i32) -> Result<Option<db::Summary>, String> {
-    println!("🔄 正在获取总结 {} 详情...", summary_id);
-    let summary = db::fetch_summary_by_id(summary_id).map_err(|e| e.to_string())?;
-    Ok(summary)
+fn commit_contact_import(decisions: Vec<db::ContactImportDecision>) -> Result<db::ContactImportResult, String> {
+    println!("🔄 正在导入 {} 个联系人...", decisions.len());
+    let result = db::commit_contact_import(&decisions).map_err(|e| e.to_string())?;
+    println!("✅ 导入完成: 新建 {}，合并 {}，跳过 {}", result.created, result.merged, result.skipped);
+    Ok(result)
 }
 
-// 删除总结
+// 获取联系人电话的拨号链接（which 为第几个电话，从 0 开始，默认第一个）
 #[tauri::command]
-fn delete_summary(summary_id: i32) -> Result<(), String> {
-    println!("🔄 正在删除总结 {}...", summary_id);
-    db::delete_summary(summary_id).map_err(|e| e.to_string())?;
-    println!("✅ 总结删除成功");
-    Ok(())
+fn get_contact_dial_uri(contact_id: i32, which: Option<usize>) -> Result<db::DialUris, String> {
+    db::fetch_contact_dial_uris(contact_id, which.unwrap_or(0)).map_err(|e| e.to_string())
+}
+
+// 生成联系人 vCard 二维码（PNG 字节），手机扫码即可保存联系人
+#[tauri::command]
+fn get_contact_qr(contact_id: i32) -> Result<Vec<u8>, String> {
+    println!("🔄 正在生成联系人 {} 的二维码...", contact_id);
+    let contact = db::fetch_contact_by_id(contact_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("联系人 {} 不存在", contact_id))?;
+    let png = export::render_qr_png(&export::contact_to_vcard(&contact))?;
+    println!("✅ 二维码生成成功（{} 字节）", png.len());
+    Ok(png)
+}
+
+// 解析粘贴的名片或签名文本，返回预填的联系人信息（不保存）
+#[tauri::command]
+fn parse_contact_card(text: String) -> Result<import::ImportedContact, String> {
+    let contact = import::parse_contact_card(&text);
+    println!("✅ 名片识别: {} / {:?} / {:?}", contact.name, contact.company, contact.title);
+    Ok(contact)
+}
+
+// 导入 Trello 看板（JSON）的卡片为项目活动，dry_run 默认为 true，只返回预览
+#[tauri::command]
+fn import_trello_board(json_path: String, project_id: i32, dry_run: Option<bool>) -> Result<db::TrelloImportPreview, String> {
+    println!("🔄 正在解析 Trello 看板: {}", json_path);
+    let (board_name, activities) = import::parse_trello_board(&json_path)?;
+    let mut preview = db::preview_trello_import(project_id, board_name, activities).map_err(|e| e.to_string())?;
+    
+    if !dry_run.unwrap_or(true) {
+        preview.committed = db::commit_trello_import(&preview).map_err(|e| e.to_string())?;
+        println!("✅ 已导入 {} 个活动", preview.committed);
+    } else {
+        println!("✅ 解析到 {} 张卡片（预览）", preview.rows.len());
+    }
+    Ok(preview)
+}
+
+// 导入 Notion 数据库导出的 CSV（有日期列时导入为事件，否则导入为联系人），dry_run 默认为 true，只返回预览
+#[tauri::command]
+fn import_notion_csv(path: String, dry_run: Option<bool>) -> Result<db::NotionImportPreview, String> {
+    println!("🔄 正在解析 Notion CSV: {}", path);
+    let data = import::parse_notion_csv(&path)?;
+    let mut preview = db::preview_notion_import(data).map_err(|e| e.to_string())?;
+    
+    if !dry_run.unwrap_or(true) {
+        db::commit_notion_import(&mut preview).map_err(|e| e.to_string())?;
+        println!("✅ 已导入 {} 条事件、{} 个联系人", preview.committed_events, preview.committed_contacts.as_ref().map_or(0, |c| c.created));
+    } else {
+        println!("✅ 解析到 {} 条事件、{} 个联系人（预览）", preview.events.len(), preview.contacts.len());
+    }
+    Ok(preview)
+}
+
+// 从 Markdown / 纯文本笔记导入事件（如 "- 2024-05-03 会议 与张三 ..."，也支持项目时间线导出的格式），
+// 事件归入 project_id，dry_run 默认为 true，只返回预览
+#[tauri::command]
+fn import_events_markdown(path: String, project_id: Option<i32>, dry_run: Option<bool>) -> Result<db::NotionImportPreview, String> {
+    println!("🔄 正在解析 Markdown 笔记: {}", path);
+    let data = import::parse_events_markdown_file(&path)?;
+    let mut preview = db::preview_markdown_import(project_id, data).map_err(|e| e.to_string())?;
+    
+    if !dry_run.unwrap_or(true) {
+        db::commit_notion_import(&mut preview).map_err(|e| e.to_string())?;
+        println!("✅ 已导入 {} 条事件", preview.committed_events);
+    } else {
+        println!("✅ 解析到 {} 条事件，跳过 {} 行（预览）", preview.events.len(), preview.skipped_rows);
+    }
+    Ok(preview)
+}
+
+// 托盘图标ID
+const TRAY_ID: &str = "main";
+
+// 更新托盘提示和任务栏角标上的待处理数量
+fn update_pending_indicators(app_handle: &tauri::AppHandle) {
+    use tauri::Manager;
+    
+    let Ok(count) = db::fetch_pending_count() else {
+        return;
+    };
+    
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let tooltip = format!(
+            "MemoryStack - 今日提醒 {} 项，逾期活动 {} 项",
+            count.today_reminders, count.overdue_activities
+        );
+        let _ = tray.set_tooltip(Some(tooltip));
+        // macOS 菜单栏会在图标旁显示标题
+        let _ = tray.set_title(if count.total > 0 { Some(count.total.to_string()) } else { None });
+    }
+    
+    // Windows 不支持角标，调用会返回错误，忽略即可
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.set_badge_count(if count.total > 0 { Some(count.total) } else { None });
+    }
+}
+
+// 注册设置中启用的全局快捷键（被其他程序占用时跳过）
+fn register_global_shortcuts(app_handle: &tauri::AppHandle) {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+    
+    let settings = db::fetch_settings().unwrap_or_default();
+    for binding in shortcuts::load_bindings(&settings) {
+        let Some(accelerator) = binding.accelerator else {
+            continue;
+        };
+        if let Err(e) = app_handle.global_shortcut().register(accelerator.as_str()) {
+            println!("⚠️ 注册快捷键 {}（{}）失败: {}", accelerator, binding.label, e);
+        }
+    }
+}
+
+// 按下全局快捷键：显示主窗口并通知前端执行对应动作
+fn handle_global_shortcut(
+    app_handle: &tauri::AppHandle,
+    shortcut: &tauri_plugin_global_shortcut::Shortcut,
+    event: tauri_plugin_global_shortcut::ShortcutEvent,
+) {
+    use tauri::{Emitter, Manager};
+    use tauri_plugin_global_shortcut::{Shortcut, ShortcutState};
+    
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let settings = db::fetch_settings().unwrap_or_default();
+    let bindings = shortcuts::load_bindings(&settings);
+    let Some(binding) = shortcuts::action_for_accelerator(&bindings, |a| a.parse::<Shortcut>().is_ok_and(|s| s == *shortcut)) else {
+        return;
+    };
+    
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app_handle.emit(shortcuts::SHORTCUT_EVENT, binding.id.clone());
+}
+
+// 组装提醒通知正文（项目和相关人员）
+fn build_reminder_body(event_detail: &db::EventWithDetails) -> String {
+    let mut body = String::new();
+    
+    if let Some(ref pname) = event_detail.project_name {
+        body.push_str(&format!("项目: {}\n", pname));
+    }
+    
+    if !event_detail.contacts.is_empty() {
+        let names: Vec<&str> = event_detail.contacts.iter().map(|c| c.name.as_str()).collect();
+        body.push_str(&format!("相关人员: {}", names.join("、")));
+    }
+    
+    body
 }
 
-// 后台提醒检查任务
-async fn reminder_check_task(app_handle: tauri::AppHandle) {
+// 按通知样式发送提醒（紧急提醒在标题前加标记，静音时不播放声音）
+fn send_reminder_notification(
+    app_handle: &tauri::AppHandle,
+    title: &str,
+    body: &str,
+    style: &db::NotificationStyle,
+) -> Result<(), String> {
     use tauri_plugin_notification::NotificationExt;
     
-    println!("🔔 提醒检查任务已启动");
+    let title = if style.urgency == "紧急" {
+        format!("【紧急】{}", title)
+    } else {
+        title.to_string()
+    };
+    let builder = app_handle.notification()
+        .builder()
+        .title(&title)
+        .body(body);
+    let builder = if style.play_sound {
+        builder.sound("default")
+    } else {
+        builder.silent()
+    };
+    builder.show().map_err(|e| e.to_string())
+}
+
+// 发送到期的提醒通知，返回发送的数量
+fn run_reminder_sweep(app_handle: &tauri::AppHandle) -> Result<usize, String> {
+    // 通知声音和各事件类型的紧急程度（每轮读取，设置修改后立即生效）
+    let notification_prefs = db::load_notification_preferences().unwrap_or_else(|e| {
+        println!("⚠️ 读取通知设置失败: {}", e);
+        db::NotificationPreferences::default()
+    });
+    let mut sent = 0;
     
-    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    // 检查待触发的提醒
+    let pending_reminders = db::fetch_pending_reminders().map_err(|e| e.to_string())?;
+    for event_detail in pending_reminders {
+        let event = &event_detail.event;
+        
+        // 发送系统通知
+        let title = format!("事件提醒: {}", event.title);
+        let body = build_reminder_body(&event_detail);
+        let style = notification_prefs.style_for(event.event_type.as_deref());
+        
+        // 发送通知
+        if let Err(e) = send_reminder_notification(app_handle, &title, &body, &style) {
+            println!("⚠️ 发送通知失败: {}", e);
+        } else {
+            println!("🔔 已发送提醒: {}", event.title);
+        }
+        
+        // 记入提醒收件箱（系统通知消失后仍可在应用内查看）
+        if let Err(e) = db::record_reminder_notification(event.id, &title, &body) {
+            println!("⚠️ 记录提醒失败: {}", e);
+        }
+        
+        // 标记提醒已触发
+        let _ = db::mark_reminder_triggered(event.id);
+        sent += 1;
+    }
     
-    loop {
-        interval.tick().await;
+    // 持续提醒：未确认前按间隔重复通知
+    let insistent_reminders = db::fetch_due_insistent_reminders().map_err(|e| e.to_string())?;
+    for event_detail in insistent_reminders {
+        let event = &event_detail.event;
+        let title = if event.reminder_triggered {
+            format!("再次提醒: {}", event.title)
+        } else {
+            format!("事件提醒: {}", event.title)
+        };
+        let body = build_reminder_body(&event_detail);
+        let style = notification_prefs.style_for(event.event_type.as_deref());
         
-        // 检查待触发的提醒
-        if let Ok(pending_reminders) = db::fetch_pending_reminders() {
-            for event_detail in pending_reminders {
-                let event = &event_detail.event;
-                
-                // 发送系统通知
-                let title = format!("事件提醒: {}", event.title);
-                let mut body = String::new();
-                
-                if let Some(ref pname) = event_detail.project_name {
-                    body.push_str(&format!("项目: {}\n", pname));
-                }
-                
-                if !event_detail.contacts.is_empty() {
-                    let names: Vec<&str> = event_detail.contacts.iter().map(|c| c.name.as_str()).collect();
-                    body.push_str(&format!("相关人员: {}", names.join("、")));
-                }
-                
-                // 发送通知
-                if let Err(e) = app_handle.notification()
-                    .builder()
-                    .title(&title)
-                    .body(&body)
-                    .show() {
-                    println!("⚠️ 发送通知失败: {}", e);
-                } else {
-                    println!("🔔 已发送提醒: {}", event.title);
+        if let Err(e) = send_reminder_notification(app_handle, &title, &body, &style) {
+            println!("⚠️ 发送通知失败: {}", e);
+        } else {
+            println!("🔔 已发送持续提醒: {}", event.title);
+        }
+        
+        if let Err(e) = db::record_reminder_notification(event.id, &title, &body) {
+            println!("⚠️ 记录提醒失败: {}", e);
+        }
+        let _ = db::mark_reminder_triggered(event.id);
+        sent += 1;
+    }
+    
+    Ok(sent)
+}
+
+// 执行一个后台任务，返回执行结果说明（没有需要处理的内容时也返回数量为 0 的说明）
+fn run_background_job(app_handle: &tauri::AppHandle, name: &str) -> Result<String, String> {
+    match name {
+        "reminders" => {
+            let sent = run_reminder_sweep(app_handle)?;
+            Ok(format!("发送 {} 条提醒", sent))
+        }
+        "recurring_activities" => {
+            // 按周期生成到期的活动
+            let generated = db::generate_due_recurring_activities().map_err(|e| e.to_string())?;
+            for (_, name) in &generated {
+                println!("🔁 已按周期创建活动: {}", name);
+            }
+            Ok(format!("创建 {} 个周期活动", generated.len()))
+        }
+        "pending_indicators" => {
+            // 刷新托盘和角标上的待处理数量
+            update_pending_indicators(app_handle);
+            Ok("已刷新".to_string())
+        }
+        "commitment_reminders" => {
+            // 到期日前按设定天数提醒（每个条目只提醒一次，发送成功后才标记，失败的下次重试）
+            let due = db::fetch_due_commitment_reminders().map_err(|e| e.to_string())?;
+            let mut sent = 0;
+            if !due.is_empty() {
+                let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+                for commitment in &due {
+                    let title = format!("{}即将到期：{}", commitment.commitment_type, commitment.title);
+                    let mut body = match &commitment.contact_name {
+                        Some(contact) => format!("{} 到期（{}）", commitment.due_date, contact),
+                        None => format!("{} 到期", commitment.due_date),
+                    };
+                    if let Some(amount) = &commitment.amount_display {
+                        body.push_str(&format!("，金额 {}", amount));
+                    }
+                    if let Err(e) = send_reminder_notification(app_handle, &title, &body, &style) {
+                        println!("⚠️ 发送到期提醒失败（{}）: {}", commitment.title, e);
+                        continue;
+                    }
+                    match db::mark_commitment_reminded(commitment.id) {
+                        Ok(()) => sent += 1,
+                        Err(e) => println!("⚠️ 标记已提醒失败（{}）: {}", commitment.title, e),
+                    }
                 }
-                
-                // 标记提醒已触发
-                let _ = db::mark_reminder_triggered(event.id);
             }
+            Ok(format!("发送 {} 条到期提醒", sent))
+        }
+        "overdue_check" => {
+            // 有逾期活动时发送一次汇总通知
+            let count = db::fetch_pending_count().map_err(|e| e.to_string())?;
+            if count.overdue_activities > 0 {
+                let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+                let body = format!("有 {} 项活动已超过预计完成日期", count.overdue_activities);
+                send_reminder_notification(app_handle, "逾期活动提醒", &body, &style)?;
+            }
+            Ok(format!("逾期活动 {} 项", count.overdue_activities))
+        }
+        "daily_briefing" => {
+            // 生成今天的简报，开启简报通知时提醒查看
+            let briefing = db::generate_daily_briefing(Local::now().date_naive(), true).map_err(|e| e.to_string())?;
+            let stats: db::BriefingStatistics = briefing.statistics.as_deref()
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or_default();
+            if db::is_briefing_notification_enabled().map_err(|e| e.to_string())? {
+                let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+                let body = format!(
+                    "今日 {} 个事件，{} 项活动到期，{} 条提醒待跟进",
+                    stats.events_today, stats.due_activities, stats.pending_follow_ups
+                );
+                send_reminder_notification(app_handle, &briefing.title, &body, &style)?;
+            }
+            Ok(format!("已生成简报: {}", briefing.title))
+        }
+        "day_note_prompt" => {
+            // 今天还没有记录时提醒写几句
+            let today = Local::now().format("%Y-%m-%d").to_string();
+            let notes = db::fetch_day_notes(&today, &today).map_err(|e| e.to_string())?;
+            if !notes.is_empty() {
+                return Ok(format!("今天已有 {} 条记录", notes.len()));
+            }
+            let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+            send_reminder_notification(app_handle, "今天过得怎么样？", "记下今天的心情和感想，会一并写入日总结", &style)?;
+            Ok("已发送记录提醒".to_string())
+        }
+        "cleanup_suggestions" => {
+            // 每周检查一次，有可清理的记录时提醒
+            let suggestions = db::fetch_cleanup_suggestions().map_err(|e| e.to_string())?;
+            if !suggestions.is_empty() {
+                let style = db::load_notification_preferences().unwrap_or_default().style_for(None);
+                let body = format!("有 {} 条记录可以清理（空项目、长期未联系的联系人、旧文件版本、过期草稿）", suggestions.len());
+                send_reminder_notification(app_handle, "清理建议", &body, &style)?;
+            }
+            Ok(format!("清理建议 {} 条", suggestions.len()))
+        }
+        "backup" => {
+            // 数据库损坏时启动恢复使用最新的备份
+            let path = db::backup_database().map_err(|e| e.to_string())?;
+            Ok(format!("已备份到 {}", path.display()))
+        }
+        "purge_file_shares" => {
+            let removed = purge_expired_file_shares()?;
+            Ok(format!("删除 {} 个过期分享", removed))
+        }
+        "auto_summary" => {
+            // 检查并生成自动总结
+            let generated = db::check_and_generate_auto_summaries().map_err(|e| e.to_string())?;
+            for summary in &generated {
+                println!("📊 自动生成总结: {}", summary.title);
+            }
+            Ok(format!("生成 {} 份总结", generated.len()))
+        }
+        _ => Err(format!("未知的后台任务: {}", name)),
+    }
+}
+
+// 执行任务并记录执行结果（trigger 为 scheduled 或 manual；任务正在执行时返回错误）
+fn execute_job(app_handle: &tauri::AppHandle, job: &'static scheduler::JobDefinition, trigger: &str) -> Result<db::JobRun, String> {
+    let Some(running) = scheduler::try_begin(job.name) else {
+        return Err(format!("任务「{}」正在执行", job.label));
+    };
+    let started_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let result = run_background_job(app_handle, job.name);
+    drop(running);
+    
+    let (success, message) = match &result {
+        Ok(message) => (true, message.as_str()),
+        Err(e) => (false, e.as_str()),
+    };
+    db::insert_job_run(job.name, trigger, &started_at, success, Some(message)).map_err(|e| e.to_string())
+}
+
+// 后台任务调度循环：每轮执行自上次检查以来到期的任务，收到退出信号后完成当前一轮再停止
+async fn background_job_task(app_handle: tauri::AppHandle) {
+    println!("🔔 后台任务调度已启动");
+    scheduler::set_loop_running(true);
+    let shutdown = scheduler::shutdown_token();
+    
+    // 间隔小于一分钟，保证每分钟都能检查到
+    let mut interval = tokio::time::interval(Duration::from_secs(20));
+    let mut last_checked = Local::now().naive_local() - chrono::Duration::minutes(1);
+    
+    loop {
+        if shutdown.run_until_cancelled(interval.tick()).await.is_none() {
+            break;
         }
         
-        // 检查并生成自动总结（每天凌晨检查一次）
-        let now = Local::now();
-        if now.format("%H:%M").to_string() == "00:10" {
-            if let Ok(generated) = db::check_and_generate_auto_summaries() {
-                for summary in generated {
-                    println!("📊 自动生成总结: {}", summary.title);
-                }
+        let now = Local::now().naive_local();
+        // 执行时间可在设置中修改，每轮重新读取
+        let settings = db::fetch_settings().unwrap_or_default();
+        for job in scheduler::JOBS {
+            if !scheduler::load_schedule(job, &settings).due_between(last_checked, now) {
+                continue;
+            }
+            match execute_job(&app_handle, job, "scheduled") {
+                Ok(run) if !run.success => println!("⚠️ 后台任务「{}」执行失败: {}", job.label, run.message.unwrap_or_default()),
+                Ok(_) => {}
+                Err(e) => println!("⚠️ 后台任务「{}」未执行: {}", job.label, e),
+            }
+        }
+        last_checked = now;
+    }
+    
+    println!("🛑 后台任务调度已停止");
+    scheduler::set_loop_running(false);
+}
+
+// 变更推送循环：定期取出触发器记录的变更，按订阅分组后推送给前端（没有订阅时直接丢弃）
+async fn change_feed_task(app_handle: tauri::AppHandle) {
+    use tauri::Emitter;
+    let shutdown = scheduler::shutdown_token();
+    let mut interval = tokio::time::interval(changes::POLL_INTERVAL);
+    loop {
+        if shutdown.run_until_cancelled(interval.tick()).await.is_none() {
+            break;
+        }
+        let changes = match db::take_changes() {
+            Ok(changes) => changes,
+            Err(e) => {
+                println!("⚠️ 读取变更记录失败: {}", e);
+                continue;
+            }
+        };
+        for batch in changes::dispatch(&changes) {
+            let _ = app_handle.emit(changes::CHANGE_EVENT, batch);
+        }
+    }
+}
+
+// 订阅实体变更（entity_types 为空时订阅全部类型），返回订阅 ID；
+// 之后的变更通过 entity-changes 事件推送，payload 为 { subscription_id, changes: [{ id, entity_type, entity_id, op, changed_at }] }
+#[tauri::command]
+fn subscribe_changes(entity_types: Vec<String>) -> Result<u64, String> {
+    let subscription_id = changes::subscribe(&entity_types)?;
+    println!("✅ 已订阅变更 #{}: {}", subscription_id, if entity_types.is_empty() { "全部".to_string() } else { entity_types.join(", ") });
+    Ok(subscription_id)
+}
+
+// 取消变更订阅，返回订阅是否存在
+#[tauri::command]
+fn unsubscribe_changes(subscription_id: u64) -> bool {
+    changes::unsubscribe(subscription_id)
+}
+
+// 应用退出：停止后台任务，等待当前一轮执行完，再关闭数据库
+fn shutdown_background_jobs() {
+    println!("🛑 正在退出，停止后台任务...");
+    scheduler::request_shutdown();
+    if !scheduler::wait_for_loop_stopped(Duration::from_secs(5)) {
+        println!("⚠️ 等待后台任务停止超时");
+    }
+    match db::close_db() {
+        Ok(()) => println!("✅ 数据库已关闭"),
+        Err(e) => println!("⚠️ 关闭数据库失败: {}", e),
+    }
+}
+
+// 数据库可用时的启动检查
+fn run_startup_checks() {
+    // 清理上次崩溃时中断的上传
+    if let Err(e) = cleanup_interrupted_uploads() {
+        println!("⚠️ 清理中断的上传失败: {}", e);
+    }
+    
+    // 数据库位于同步盘或存在冲突副本时给出警告
+    for warning in db::check_storage_health().warnings {
+        println!("⚠️ {}", warning);
+    }
+    
+    // 检查数据完整性（只提示，修复需用户在界面上确认）
+    match db::fetch_integrity_report() {
+        Ok(report) => {
+            for issue in &report.issues {
+                println!("⚠️ 数据完整性: {}（{} 条）", issue.label, issue.count);
             }
         }
+        Err(e) => println!("⚠️ 数据完整性检查失败: {}", e),
     }
 }
 
 fn main() {
-    // 预初始化数据库（这会触发首次连接）
-    let _ = db::get_db().expect("数据库初始化失败");
+    // 预初始化数据库（这会触发首次连接）；失败时仍启动界面，由前端通过 get_startup_status 提示
+    let startup = db::initialize_database();
+    if startup.ok {
+        if startup.problem.is_some() {
+            println!("⚠️ {}", startup.message);
+        }
+        run_startup_checks();
+    } else {
+        println!("❌ {}", startup.message);
+    }
     
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(handle_global_shortcut).build())
         .setup(|app| {
+            // 创建托盘图标（提示中显示今日待处理数量）
+            if let Some(icon) = app.default_window_icon().cloned() {
+                let _ = tauri::tray::TrayIconBuilder::with_id(TRAY_ID)
+                    .icon(icon)
+                    .tooltip("MemoryStack")
+                    .build(app);
+            }
+            
             let app_handle = app.handle().clone();
+            register_global_shortcuts(&app_handle);
             
-            // 启动后台提醒检查任务
-            tauri::async_runtime::spawn(async move {
-                reminder_check_task(app_handle).await;
-            });
+            // 启动后台任务调度（提醒、周期活动、自动总结等），数据库不可用时不启动
+            if db::get_startup_status().is_some_and(|status| status.ok) {
+                let feed_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    background_job_task(app_handle).await;
+                });
+                tauri::async_runtime::spawn(async move {
+                    change_feed_task(feed_handle).await;
+                });
+            }
             
             Ok(())
         })
@@ -828,41 +3922,204 @@ fn main() {
             create_project, 
             get_projects,
             update_project,
+            delete_project,
+            duplicate_project,
+            set_project_status,
+            archive_project,
+            unarchive_project,
             create_contact,
+            create_contact_v2,
             get_contacts,
             update_contact,
+            suggest_company_for_email,
+            get_company_domains,
+            set_company_domain,
+            delete_company_domain,
             link_contact_project,
             get_project_contacts,
+            get_project_org_chart,
+            set_project_reports_to,
             unlink_contact_project,
             create_event,
+            create_event_v2,
+            quick_add,
+            suggest_event_contacts,
+            detect_event_mentions,
+            apply_detected_mentions,
             get_contact_timeline,
+            get_contact_timeline_groups,
             get_project_timeline,
+            get_project_log,
             get_all_events,
             delete_event,
             update_event,
+            update_event_v2,
+            get_shortcuts,
+            set_shortcut,
+            open_entity_window,
+            save_draft,
+            list_drafts,
+            delete_draft,
+            log_day_note,
+            get_day_notes,
+            get_wellbeing_trend,
+            delete_day_note,
+            set_day_note_private,
+            get_cleanup_suggestions,
+            apply_cleanup_suggestion,
             upload_file_to_project,
             get_project_files,
             open_file,
             show_in_folder,
             search_files,
             delete_project_file,
+            prune_file_versions,
+            search_global,
+            record_access,
+            subscribe_changes,
+            unsubscribe_changes,
+            diff_file_versions,
+            empty_file_trash,
+            prepare_file_share,
+            get_file_shares,
+            revoke_file_share,
+            save_inline_image,
+            upload_file_to_contact,
+            get_contact_files,
+            delete_contact_file,
             create_activity,
+            find_similar_activities,
             get_project_activities,
             update_activity,
             assign_activity_contacts,
+            get_assignment_message,
             unassign_activity_contact,
             activate_activity,
             pause_activity,
             complete_activity,
+            submit_activity_for_review,
+            approve_activity,
+            reject_activity,
+            get_activity_reviews,
+            get_activity_completion_logging,
+            set_activity_completion_logging,
             delete_activity,
             export_activities,
+            set_activity_estimated_hours,
+            add_time_entry,
+            delete_time_entry,
+            get_time_entries,
+            get_estimation_accuracy_report,
+            get_activity_aging_report,
+            create_activity_template,
+            update_activity_template,
+            delete_activity_template,
+            get_activity_templates,
+            get_template_packs,
+            install_template_pack,
+            create_activity_from_template,
+            get_template_activities,
+            export_project_roster_xlsx,
+            export_project_timeline_md,
+            export_project_share_html,
+            export_table_json,
+            export_contact_dossier,
+            get_redaction_profiles,
+            render_contact_template,
             update_event_reminder,
+            shift_reminders,
+            reschedule_project_events,
             get_today_reminder_events,
+            set_event_reminder_mode,
+            acknowledge_reminder,
+            get_reminder_inbox,
+            acknowledge_all_reminders,
+            get_pending_count,
             generate_summary,
+            generate_daily_briefing,
+            generate_project_retrospective,
+            draft_status_email,
             get_summaries,
             get_summary_detail,
-            delete_summary
+            delete_summary,
+            set_summary_tags,
+            search_summaries,
+            regenerate_summary,
+            compare_summaries,
+            toggle_event_pin,
+            set_event_private,
+            toggle_contact_pin,
+            get_pinned_items,
+            get_event_types,
+            save_event_type,
+            get_notification_urgencies,
+            delete_event_type,
+            set_project_style,
+            get_event_channels,
+            get_contact_channel_stats,
+            get_contacts_by_city,
+            get_startup_status,
+            get_api_info,
+            get_query_metrics,
+            reset_query_metrics,
+            seed_demo_data,
+            get_integrity_report,
+            repair_integrity,
+            get_job_schedules,
+            set_job_schedule,
+            get_background_jobs_status,
+            get_job_runs,
+            run_job_now,
+            get_projects_by_region,
+            get_contact_availability,
+            set_contact_availability,
+            suggest_meeting_slots,
+            save_commitment,
+            set_commitment_status,
+            delete_commitment,
+            get_upcoming_commitments,
+            get_commitment_totals,
+            get_project_health,
+            get_project_velocity,
+            get_holidays,
+            import_holiday_calendar,
+            set_holiday,
+            delete_holiday,
+            next_working_day,
+            link_entities,
+            unlink_entities,
+            get_related_items,
+            export_graph,
+            get_settings,
+            set_setting,
+            set_me_contact,
+            get_my_activities,
+            get_my_events,
+            check_storage_health,
+            get_storage_settings,
+            relocate_database,
+            get_onboarding_state,
+            skip_onboarding_step,
+            onboarding_create_sample_workspace,
+            onboarding_import_contacts,
+            onboarding_choose_storage,
+            onboarding_set_me_contact,
+            onboarding_configure_reminders,
+            get_database_stats,
+            preview_contact_import,
+            commit_contact_import,
+            get_contact_dial_uri,
+            get_contact_qr,
+            parse_contact_card,
+            import_trello_board,
+            import_notion_csv,
+            import_events_markdown
         ])
-        .run(tauri::generate_context!())
-        .expect("运行 Tauri 应用时出错");
+        .build(tauri::generate_context!())
+        .expect("运行 Tauri 应用时出错")
+        .run(|_app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown_background_jobs();
+            }
+        });
 }
\ No newline at end of file
@@ -0,0 +1,128 @@
+// src-tauri/src/money.rs
+// 金额：以最小货币单位（如分）的整数加币种代码保存和计算，解析、合计和格式化都不经过浮点数；
+// 未指定币种时使用设置 default_currency（默认 CNY）
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::format::NumberFormat;
+
+pub const SETTING_DEFAULT_CURRENCY: &str = "default_currency";  // 工作区默认币种（ISO 4217 代码）
+pub const DEFAULT_CURRENCY: &str = "CNY";
+
+// 最小单位不是 1/100 的币种（其余币种按 2 位小数）
+const CURRENCY_EXPONENTS: &[(&str, u32)] = &[
+    ("JPY", 0), ("KRW", 0), ("VND", 0), ("CLP", 0), ("ISK", 0),
+    ("BHD", 3), ("KWD", 3), ("OMR", 3), ("JOD", 3), ("TND", 3),
+];
+
+// 金额
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount_minor: i64,  // 最小货币单位，如 1234.56 元为 123456
+    pub currency: String,   // ISO 4217 代码，如 CNY、USD
+}
+
+// 规范化并检查币种代码（3 个英文字母）
+pub fn parse_currency(value: &str) -> Option<String> {
+    let code = value.trim().to_uppercase();
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then_some(code)
+}
+
+// 币种的小数位数
+pub fn currency_exponent(currency: &str) -> u32 {
+    CURRENCY_EXPONENTS.iter()
+        .find(|(code, _)| *code == currency)
+        .map(|(_, exponent)| *exponent)
+        .unwrap_or(2)
+}
+
+// 工作区默认币种
+pub fn default_currency(settings: &HashMap<String, String>) -> String {
+    settings.get(SETTING_DEFAULT_CURRENCY)
+        .and_then(|v| parse_currency(v))
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string())
+}
+
+impl Money {
+    // 解析十进制金额文本，如 "1234.5"（小数点为 .，可带 , 或空格作为千位分隔），小数位数不能超过币种的最小单位
+    pub fn parse(text: &str, currency: &str) -> Result<Money, String> {
+        let currency = parse_currency(currency).ok_or(format!("无效的币种: {}", currency))?;
+        let exponent = currency_exponent(&currency);
+        let cleaned: String = text.trim().chars().filter(|c| !matches!(c, ',' | ' ' | '_' | '\u{a0}')).collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let invalid = || format!("无效的金额: {}", text.trim());
+        if integer.is_empty() && fraction.is_empty()
+            || !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        if fraction.len() > exponent as usize {
+            return Err(format!("{} 最多 {} 位小数: {}", currency, exponent, text.trim()));
+        }
+        let padded = format!("{}{:0<width$}", integer, fraction, width = exponent as usize);
+        let magnitude: i64 = padded.parse().map_err(|_| invalid())?;
+        Ok(Money { amount_minor: if negative { -magnitude } else { magnitude }, currency })
+    }
+
+    // 按数字格式显示，如 "CNY 1,234.50"
+    pub fn format(&self, numbers: &NumberFormat) -> String {
+        format!("{} {}", self.currency, self.render(numbers))
+    }
+
+    fn render(&self, numbers: &NumberFormat) -> String {
+        let exponent = currency_exponent(&self.currency);
+        let unit = 10u64.pow(exponent);
+        let magnitude = self.amount_minor.unsigned_abs();
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        // 整数部分按无符号数分组，避免 i64::MIN 取绝对值溢出
+        let integer_text = numbers.format_integer_unsigned(magnitude / unit);
+        if exponent == 0 {
+            return format!("{}{}", sign, integer_text);
+        }
+        format!("{}{}{}{:0width$}", sign, integer_text, numbers.decimal_separator, magnitude % unit, width = exponent as usize)
+    }
+}
+
+// 按币种分别合计（不同币种不相加），溢出时报错
+pub fn sum_by_currency<'a>(amounts: impl IntoIterator<Item = &'a Money>) -> Result<Vec<Money>, String> {
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for money in amounts {
+        let total = totals.entry(money.currency.as_str()).or_insert(0);
+        *total = total.checked_add(money.amount_minor).ok_or(format!("{} 合计金额超出范围", money.currency))?;
+    }
+    Ok(totals.into_iter()
+        .map(|(currency, amount_minor)| Money { amount_minor, currency: currency.to_string() })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_parses_formats_and_totals_without_floats() {
+        let plain = NumberFormat::default();
+        assert_eq!(Money::parse("1,234.5", "cny").unwrap(), Money { amount_minor: 123450, currency: "CNY".to_string() });
+        assert_eq!(Money::parse("0.1", "USD").unwrap().amount_minor + Money::parse("0.2", "USD").unwrap().amount_minor, 30);
+        assert_eq!(Money::parse("-3", "JPY").unwrap().amount_minor, -3);
+        assert!(Money::parse("1.5", "JPY").is_err());
+        assert!(Money::parse("1.2.3", "CNY").is_err());
+        assert!(Money::parse("12", "RMB1").is_err());
+        assert_eq!(Money::parse("1.234", "KWD").unwrap().format(&plain), "KWD 1.234");
+
+        let de = NumberFormat { thousands_separator: ".", decimal_separator: ',', ..Default::default() };
+        assert_eq!(Money { amount_minor: 123456789, currency: "EUR".to_string() }.format(&de), "EUR 1.234.567,89");
+        assert_eq!(Money { amount_minor: -5, currency: "CNY".to_string() }.format(&plain), "CNY -0.05");
+        let sums = sum_by_currency(&[
+            Money::parse("10.10", "USD").unwrap(),
+            Money::parse("5", "CNY").unwrap(),
+            Money::parse("0.90", "USD").unwrap(),
+        ]).unwrap();
+        assert_eq!(sums.iter().map(|m| m.format(&plain)).collect::<Vec<_>>(), vec!["CNY 5.00", "USD 11.00"]);
+        assert!(sum_by_currency(&[Money { amount_minor: i64::MAX, currency: "CNY".to_string() }, Money { amount_minor: 1, currency: "CNY".to_string() }]).is_err());
+    }
+}
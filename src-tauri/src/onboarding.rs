@@ -0,0 +1,115 @@
+// src-tauri/src/onboarding.rs
+// 首次使用引导：示例工作区、导入联系人、设置"我"、提醒配置、存储位置，每一步完成或跳过后
+// 保存在设置中（onboarding.<步骤> = done / skipped），前端按 get_onboarding_state 的结果决定显示哪一步
+use serde::{Deserialize, Serialize};
+
+// 引导步骤的设置项前缀，如 onboarding.me_contact = "done"
+pub const ONBOARDING_PREFIX: &str = "onboarding.";
+
+// 引导步骤定义
+pub struct OnboardingStepDef {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+// 所有步骤（按引导顺序）
+pub const STEPS: &[OnboardingStepDef] = &[
+    OnboardingStepDef { id: "sample_workspace", label: "创建示例工作区" },
+    OnboardingStepDef { id: "import_contacts", label: "导入已有联系人" },
+    OnboardingStepDef { id: "me_contact", label: "设置代表\"我\"的联系人" },
+    OnboardingStepDef { id: "reminders", label: "配置提醒" },
+    // 迁移数据库放在最后，前面步骤写入的设置随数据库一起迁移
+    OnboardingStepDef { id: "storage_location", label: "选择存储位置" },
+];
+
+// 步骤状态
+pub const STATUS_DONE: &str = "done";
+pub const STATUS_SKIPPED: &str = "skipped";
+
+// 按 ID 查找步骤
+pub fn find_step(id: &str) -> Option<&'static OnboardingStepDef> {
+    STEPS.iter().find(|step| step.id == id)
+}
+
+// 步骤对应的设置项键名
+pub fn setting_key(id: &str) -> String {
+    format!("{}{}", ONBOARDING_PREFIX, id)
+}
+
+// 校验步骤状态
+pub fn parse_status(value: &str) -> Result<&'static str, String> {
+    match value.trim() {
+        STATUS_DONE => Ok(STATUS_DONE),
+        STATUS_SKIPPED => Ok(STATUS_SKIPPED),
+        _ => Err(format!("无效的引导步骤状态: {}（应为 done 或 skipped）", value)),
+    }
+}
+
+// 一个步骤的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingStep {
+    pub id: String,
+    pub label: String,
+    pub status: String,  // pending / done / skipped
+}
+
+// 引导进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingState {
+    pub steps: Vec<OnboardingStep>,
+    pub next_step: Option<String>,  // 第一个未处理的步骤，None 表示引导已结束
+    pub finished: bool,
+}
+
+// 从设置中读取引导进度（设置值无效的步骤视为未处理）。
+// has_data 为数据库中是否已有项目或联系人：还没有处理过任何步骤但已有数据时，说明是引导功能上线前就在使用的数据库，
+// 各步骤视为已跳过，不再显示引导
+pub fn load_state(settings: &std::collections::HashMap<String, String>, has_data: bool) -> OnboardingState {
+    let recorded = |id: &str| settings.get(&setting_key(id)).and_then(|v| parse_status(v).ok());
+    let existing_workspace = has_data && STEPS.iter().all(|step| recorded(step.id).is_none());
+    let unrecorded = if existing_workspace { STATUS_SKIPPED } else { "pending" };
+    let steps: Vec<OnboardingStep> = STEPS.iter().map(|step| OnboardingStep {
+        id: step.id.to_string(),
+        label: step.label.to_string(),
+        status: recorded(step.id).unwrap_or(unrecorded).to_string(),
+    }).collect();
+    let next_step = steps.iter().find(|s| s.status == "pending").map(|s| s.id.clone());
+    OnboardingState {
+        finished: next_step.is_none(),
+        steps,
+        next_step,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn onboarding_progress_is_tracked_in_settings() {
+        let mut settings = std::collections::HashMap::new();
+        let state = load_state(&settings, false);
+        assert_eq!(state.next_step.as_deref(), Some("sample_workspace"));
+        assert!(!state.finished);
+
+        // 引导上线前就已有数据的数据库不再显示引导
+        let state = load_state(&settings, true);
+        assert!(state.finished && state.steps.iter().all(|s| s.status == STATUS_SKIPPED));
+
+        settings.insert(setting_key("sample_workspace"), "skipped".to_string());
+        settings.insert(setting_key("import_contacts"), "done".to_string());
+        settings.insert(setting_key("me_contact"), "bogus".to_string());
+        // 引导进行中创建的数据不影响后续步骤
+        let state = load_state(&settings, true);
+        let statuses: Vec<&str> = state.steps.iter().map(|s| s.status.as_str()).collect();
+        assert_eq!(statuses, vec!["skipped", "done", "pending", "pending", "pending"]);
+        assert_eq!(state.next_step.as_deref(), Some("me_contact"));
+        assert_eq!(state.steps.last().map(|s| s.id.as_str()), Some("storage_location"));
+
+        for step in ["me_contact", "reminders", "storage_location"] {
+            settings.insert(setting_key(step), "done".to_string());
+        }
+        let state = load_state(&settings, false);
+        assert!(state.finished && state.next_step.is_none());
+    }
+}
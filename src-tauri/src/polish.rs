@@ -0,0 +1,46 @@
+// src-tauri/src/polish.rs
+// 文本润色：生成的邮件草稿等文本可以交给外部润色程序（text_polish_command，如调用大模型接口的脚本）改写，
+// 原文从标准输入传入，标准输出作为润色结果；退出码非 0、超时或输出为空都视为失败
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::external::{run_with_timeout, RunError};
+
+pub const SETTING_POLISH_COMMAND: &str = "text_polish_command";  // 外部润色程序（空或未设置表示不可用）
+
+// 润色程序的最长运行时间
+const POLISH_TIMEOUT: Duration = Duration::from_secs(120);
+
+// 错误信息中润色程序输出的最大长度
+const MAX_ERROR_OUTPUT_CHARS: usize = 500;
+
+// 当前配置的润色程序
+pub fn polish_command(settings: &HashMap<String, String>) -> Option<&str> {
+    settings.get(SETTING_POLISH_COMMAND).map(|c| c.trim()).filter(|c| !c.is_empty())
+}
+
+// 调用润色程序改写文本（未配置时返回错误）
+pub fn polish_text(text: &str, settings: &HashMap<String, String>) -> Result<String, String> {
+    let command = polish_command(settings).ok_or("未配置润色程序（text_polish_command）")?;
+    let output = match run_with_timeout(command, &[], Some(text), POLISH_TIMEOUT) {
+        Ok(output) => output,
+        Err(RunError::Spawn(e)) => return Err(format!("无法运行润色程序 {}: {}", command, e)),
+        Err(RunError::Wait(e)) => return Err(format!("等待润色程序失败: {}", e)),
+        Err(RunError::Timeout) => return Err(format!("润色超时（{} 秒）", POLISH_TIMEOUT.as_secs())),
+    };
+
+    if !output.status.success() {
+        let detail: String = output.stderr.trim().chars().take(MAX_ERROR_OUTPUT_CHARS).collect();
+        let code = output.exit_code();
+        return Err(if detail.is_empty() {
+            format!("润色失败（退出码 {}）", code)
+        } else {
+            format!("润色失败（退出码 {}）: {}", code, detail)
+        });
+    }
+    let polished = output.stdout.trim();
+    if polished.is_empty() {
+        return Err("润色程序没有输出".to_string());
+    }
+    Ok(format!("{}\n", polished))
+}
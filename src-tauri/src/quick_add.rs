@@ -0,0 +1,304 @@
+// src-tauri/src/quick_add.rs
+// 快速录入语法解析：如 "周五 14:00 和 @李雷 开会 #项目A !提醒30m"
+// 只负责把文本拆成日期、时间、联系人、项目、提醒等部分，名称到ID的解析由调用方完成
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, Contact};
+
+// 解析出的各个部分
+#[derive(Debug, Default)]
+pub struct QuickAddTokens {
+    pub title: String,
+    pub date: Option<NaiveDate>,
+    pub time: Option<NaiveTime>,
+    pub contact_names: Vec<String>,
+    pub project_name: Option<String>,
+    pub reminder_offset_minutes: Option<i64>,  // 提前多少分钟提醒，0 表示事件开始时提醒
+    pub event_type: Option<String>,
+}
+
+// 返回给前端确认的解析结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuickAddResult {
+    pub title: String,
+    pub event_date: String,
+    pub event_type: Option<String>,
+    pub project_id: Option<i32>,
+    pub project_name: Option<String>,
+    pub contacts: Vec<Contact>,
+    pub unresolved_contacts: Vec<String>,  // 没有找到对应联系人的 @名称
+    pub reminder_time: Option<String>,
+    pub warnings: Vec<String>,
+    pub saved: bool,
+}
+
+// 连接词，不计入标题
+const CONNECTORS: [&str; 4] = ["和", "跟", "与", "同"];
+
+// 标题关键词 -> 事件类型
+const TYPE_KEYWORDS: [(&str, &str); 8] = [
+    ("视频", "视频会议"),
+    ("开会", "会议"),
+    ("会议", "会议"),
+    ("电话", "电话"),
+    ("邮件", "邮件"),
+    ("微信", "微信"),
+    ("截止", "截止"),
+    ("里程碑", "里程碑"),
+];
+
+fn parse_weekday_char(c: char) -> Option<Weekday> {
+    match c {
+        '一' | '1' => Some(Weekday::Mon),
+        '二' | '2' => Some(Weekday::Tue),
+        '三' | '3' => Some(Weekday::Wed),
+        '四' | '4' => Some(Weekday::Thu),
+        '五' | '5' => Some(Weekday::Fri),
+        '六' | '6' => Some(Weekday::Sat),
+        '日' | '天' | '7' => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// 解析日期词：今天/明天/后天、周五/星期五（最近的一个，含今天）、下周五、2025-10-01、10月1日、10/1
+fn parse_date_token(token: &str, today: NaiveDate, week_start: Weekday) -> Option<NaiveDate> {
+    match token {
+        "今天" | "今日" => return Some(today),
+        "明天" | "明日" => return Some(today + Duration::days(1)),
+        "后天" => return Some(today + Duration::days(2)),
+        "大后天" => return Some(today + Duration::days(3)),
+        _ => {}
+    }
+
+    let (next_week, rest) = match token.strip_prefix("下") {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    for prefix in ["周", "星期", "礼拜"] {
+        if let Some(day) = rest.strip_prefix(prefix) {
+            let mut chars = day.chars();
+            let weekday = parse_weekday_char(chars.next()?)?;
+            if chars.next().is_some() {
+                return None;
+            }
+            if next_week {
+                // 下周X：下一周内的那一天
+                let week_begin = today - Duration::days(db::days_since_week_start(today, week_start)) + Duration::weeks(1);
+                return Some(week_begin + Duration::days(days_between(week_begin.weekday(), weekday)));
+            }
+            return Some(today + Duration::days(days_between(today.weekday(), weekday)));
+        }
+    }
+    if next_week {
+        return None;
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    // 10月1日 / 10月1号 / 10/1 / 10-1（未写年份时取今天之后最近的一次）
+    let normalized = token.trim_end_matches(['日', '号']).replace(['月', '/'], "-");
+    let mut parts = normalized.split('-');
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if this_year >= today {
+        Some(this_year)
+    } else {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    }
+}
+
+// 从 from 到 to 需要往后数的天数（0-6）
+fn days_between(from: Weekday, to: Weekday) -> i64 {
+    ((to.num_days_from_monday() + 7 - from.num_days_from_monday()) % 7) as i64
+}
+
+// 解析时间词：14:00、14：00、3点、3点半、下午3点、晚上8:30
+fn parse_time_token(token: &str) -> Option<NaiveTime> {
+    let mut value = token.replace('：', ":");
+    let mut afternoon = false;
+    for (prefix, pm) in [("上午", false), ("早上", false), ("中午", true), ("下午", true), ("晚上", true)] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            afternoon = pm;
+            value = rest.to_string();
+            break;
+        }
+    }
+
+    let (hour, minute) = if let Some((h, m)) = value.split_once(':') {
+        (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)
+    } else if let Some(h) = value.strip_suffix("点半") {
+        (h.parse::<u32>().ok()?, 30)
+    } else if let Some(h) = value.strip_suffix('点') {
+        (h.parse::<u32>().ok()?, 0)
+    } else {
+        return None;
+    };
+
+    // 中午12点不再加12小时
+    let hour = if afternoon && hour < 12 { hour + 12 } else { hour };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+// 提醒最多提前 365 天
+const MAX_REMINDER_OFFSET_MINUTES: i64 = 365 * 24 * 60;
+
+// 解析提醒：!提醒30m、!30分钟、!1h、!1天、!提醒（事件开始时提醒）
+fn parse_reminder_token(token: &str) -> Option<i64> {
+    let value = token.strip_prefix(['!', '！'])?;
+    let value = value.strip_prefix("提醒").unwrap_or(value).trim();
+    if value.is_empty() {
+        return Some(0);
+    }
+
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let amount: i64 = digits.parse().ok()?;
+    let unit = value[digits.len()..].to_lowercase();
+    let multiplier = match unit.as_str() {
+        "" | "m" | "min" | "分" | "分钟" => 1,
+        "h" | "小时" | "时" => 60,
+        "d" | "天" => 24 * 60,
+        _ => return None,
+    };
+    // 超过上限的提前量视为无法识别，保留为标题中的词
+    amount.checked_mul(multiplier).filter(|minutes| *minutes <= MAX_REMINDER_OFFSET_MINUTES)
+}
+
+// 去掉名称末尾的标点（如 "@李雷，"）
+fn trim_name(name: &str) -> String {
+    name.trim_end_matches(|c: char| c.is_ascii_punctuation() || "，。、；：！？".contains(c))
+        .to_string()
+}
+
+// 按空白拆分并识别各个部分，无法识别的词组成标题
+pub fn parse_quick_add(text: &str, today: NaiveDate, week_start: Weekday) -> QuickAddTokens {
+    let mut tokens = QuickAddTokens::default();
+    let mut title_words = Vec::new();
+
+    for token in text.split_whitespace() {
+        if let Some(name) = token.strip_prefix(['@', '＠']) {
+            let name = trim_name(name);
+            if !name.is_empty() {
+                tokens.contact_names.push(name);
+            }
+        } else if let Some(project) = token.strip_prefix(['#', '＃']) {
+            let project = trim_name(project);
+            if !project.is_empty() {
+                tokens.project_name = Some(project);
+            }
+        } else if token.starts_with(['!', '！']) {
+            match parse_reminder_token(token) {
+                Some(offset) => tokens.reminder_offset_minutes = Some(offset),
+                None => title_words.push(token),
+            }
+        } else if tokens.date.is_none() && parse_date_token(token, today, week_start).is_some() {
+            tokens.date = parse_date_token(token, today, week_start);
+        } else if tokens.time.is_none() && parse_time_token(token).is_some() {
+            tokens.time = parse_time_token(token);
+        } else if !CONNECTORS.contains(&token) {
+            title_words.push(token);
+        }
+    }
+
+    tokens.title = title_words.join(" ");
+    tokens.event_type = TYPE_KEYWORDS.iter()
+        .find(|(keyword, _)| tokens.title.contains(keyword))
+        .map(|(_, event_type)| event_type.to_string());
+    tokens
+}
+
+// 按名称查找：优先完全匹配（忽略大小写），否则取唯一的包含匹配
+fn find_by_name<'a, T>(items: &'a [T], name: &str, get_name: impl Fn(&T) -> &str) -> Option<&'a T> {
+    let lower = name.to_lowercase();
+    if let Some(item) = items.iter().find(|i| get_name(i).to_lowercase() == lower) {
+        return Some(item);
+    }
+    let partial: Vec<&T> = items.iter().filter(|i| get_name(i).to_lowercase().contains(&lower)).collect();
+    if partial.len() == 1 { Some(partial[0]) } else { None }
+}
+
+// 将解析出的名称对应到已有联系人和项目，并计算事件日期和提醒时间
+pub fn resolve_quick_add(
+    tokens: QuickAddTokens,
+    contacts: &[Contact],
+    projects: &[db::Project],
+    today: NaiveDate,
+) -> QuickAddResult {
+    let mut warnings = Vec::new();
+
+    let mut resolved_contacts: Vec<Contact> = Vec::new();
+    let mut unresolved_contacts = Vec::new();
+    for name in &tokens.contact_names {
+        match find_by_name(contacts, name, |c| c.name.as_str()) {
+            Some(c) if !resolved_contacts.iter().any(|r| r.id == c.id) => resolved_contacts.push(c.clone()),
+            Some(_) => {}
+            None => unresolved_contacts.push(name.clone()),
+        }
+    }
+    if !unresolved_contacts.is_empty() {
+        warnings.push(format!("未找到联系人：{}", unresolved_contacts.join("、")));
+    }
+
+    let project = tokens.project_name.as_deref()
+        .and_then(|name| find_by_name(projects, name, |p| p.name.as_str()));
+    if let (Some(name), None) = (&tokens.project_name, project) {
+        warnings.push(format!("未找到项目：{}", name));
+    }
+
+    // 未写日期时默认今天
+    let date = tokens.date.unwrap_or(today);
+    let event_date = match tokens.time {
+        Some(time) => format!("{} {}", date.format("%Y-%m-%d"), time.format("%H:%M")),
+        None => date.format("%Y-%m-%d").to_string(),
+    };
+
+    let reminder_time = tokens.reminder_offset_minutes.and_then(|offset| {
+        db::parse_event_datetime(&event_date)
+            .and_then(|dt| dt.checked_sub_signed(Duration::try_minutes(offset)?))
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+    });
+
+    let title = if tokens.title.is_empty() {
+        warnings.push("没有识别到事件标题".to_string());
+        tokens.event_type.clone().unwrap_or_else(|| "新事件".to_string())
+    } else {
+        tokens.title
+    };
+
+    QuickAddResult {
+        title,
+        event_date,
+        event_type: tokens.event_type,
+        project_id: project.map(|p| p.id),
+        project_name: project.map(|p| p.name.clone()),
+        contacts: resolved_contacts,
+        unresolved_contacts,
+        reminder_time,
+        warnings,
+        saved: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_reminder_offsets_stay_in_the_title() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let tokens = parse_quick_add("明天 开会 !2h", today, Weekday::Mon);
+        assert_eq!(tokens.reminder_offset_minutes, Some(120));
+
+        for text in ["明天 开会 !100000000d", "明天 开会 !99999999999999999h"] {
+            let tokens = parse_quick_add(text, today, Weekday::Mon);
+            assert_eq!(tokens.reminder_offset_minutes, None);
+            assert!(tokens.title.contains('!'));
+        }
+    }
+}
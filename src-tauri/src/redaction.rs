@@ -0,0 +1,211 @@
+// src-tauri/src/redaction.rs
+// 导出脱敏方案：按对象命名一组脱敏规则（如“对外”隐藏电话、邮箱和备注），所有导出命令和分享页都可以按名称选用；
+// 自定义方案以 JSON 数组保存在设置 redaction_profiles 中，与内置方案同名时覆盖内置方案。
+// 私密事件（is_private）在任何方案下都不导出
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::{self, ActivityWithDetails, Contact, EventWithDetails};
+use crate::export::ShareHtmlOptions;
+
+pub const SETTING_REDACTION_PROFILES: &str = "redaction_profiles";  // 自定义脱敏方案（JSON 数组）
+
+// 脱敏方案
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionProfile {
+    pub name: String,
+    pub redact_notes: bool,   // 隐藏联系人备注、项目备注，以及项目、事件和活动描述
+    pub redact_phones: bool,  // 电话只保留前三位和后四位
+    pub mask_emails: bool,    // 邮箱只保留首字符和域名
+    pub redact_emails: bool,  // 隐藏邮箱
+    pub anonymize: bool,      // 联系人化名（见 Pseudonymizer）
+    #[serde(skip_deserializing)]
+    pub builtin: bool,
+}
+
+// 内置方案：client-facing（对外：隐藏备注和邮箱、遮盖电话）和 internal（内部：不脱敏）
+pub fn builtin_profiles() -> Vec<RedactionProfile> {
+    vec![
+        RedactionProfile {
+            name: "client-facing".to_string(),
+            redact_notes: true,
+            redact_phones: true,
+            mask_emails: false,
+            redact_emails: true,
+            anonymize: false,
+            builtin: true,
+        },
+        RedactionProfile {
+            name: "internal".to_string(),
+            builtin: true,
+            ..Default::default()
+        },
+    ]
+}
+
+// 解析自定义方案（名称不能为空或重复）
+pub fn parse_profiles(value: &str) -> Result<Vec<RedactionProfile>, String> {
+    if value.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut profiles: Vec<RedactionProfile> = serde_json::from_str(value)
+        .map_err(|e| format!("无效的脱敏方案: {}", e))?;
+    let mut names = Vec::new();
+    for profile in profiles.iter_mut() {
+        profile.name = profile.name.trim().to_string();
+        if profile.name.is_empty() {
+            return Err("脱敏方案名称不能为空".to_string());
+        }
+        if names.contains(&profile.name) {
+            return Err(format!("脱敏方案名称重复: {}", profile.name));
+        }
+        names.push(profile.name.clone());
+    }
+    Ok(profiles)
+}
+
+// 全部可用方案：内置方案在前，自定义方案覆盖同名内置方案
+pub fn profiles(settings: &HashMap<String, String>) -> Vec<RedactionProfile> {
+    let custom = settings.get(SETTING_REDACTION_PROFILES)
+        .and_then(|v| parse_profiles(v).ok())
+        .unwrap_or_default();
+    let mut all: Vec<RedactionProfile> = builtin_profiles().into_iter()
+        .filter(|b| !custom.iter().any(|c| c.name == b.name))
+        .collect();
+    all.extend(custom);
+    all
+}
+
+// 按名称查找方案
+pub fn find_profile(settings: &HashMap<String, String>, name: &str) -> Option<RedactionProfile> {
+    profiles(settings).into_iter().find(|p| p.name == name.trim())
+}
+
+// 遮盖电话号码中间部分
+pub fn mask_phone(phone: &str) -> String {
+    let chars: Vec<char> = phone.chars().collect();
+    if chars.len() <= 7 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..3].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}{}", head, "*".repeat(chars.len() - 7), tail)
+}
+
+// 遮盖邮箱用户名中首字符以外的部分
+pub fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((user, domain)) => {
+            let mut chars = user.chars();
+            let head: String = chars.next().map(String::from).unwrap_or_default();
+            format!("{}{}@{}", head, "*".repeat(chars.count()), domain)
+        }
+        None => "*".repeat(email.chars().count()),
+    }
+}
+
+impl RedactionProfile {
+    pub fn contact(&self, contact: &mut Contact) {
+        if self.redact_phones {
+            let phones: Vec<String> = db::parse_phone_list(contact.phone.as_deref()).iter().map(|p| mask_phone(p)).collect();
+            contact.phone = (!phones.is_empty()).then(|| serde_json::to_string(&phones).unwrap_or_default());
+            contact.phone_normalized = None;
+        }
+        if self.mask_emails {
+            contact.email = contact.email.as_deref().map(mask_email);
+        }
+        if self.redact_emails {
+            contact.email = None;
+        }
+        if self.redact_notes {
+            contact.notes = None;
+        }
+    }
+
+    pub fn roster(&self, roster: &mut [(Contact, Option<String>, Option<String>)]) {
+        for (contact, _, notes) in roster.iter_mut() {
+            self.contact(contact);
+            if self.redact_notes {
+                *notes = None;
+            }
+        }
+    }
+
+    pub fn events(&self, events: &mut [EventWithDetails]) {
+        for item in events.iter_mut() {
+            if self.redact_notes {
+                item.event.description = None;
+            }
+            item.contacts.iter_mut().for_each(|c| self.contact(c));
+        }
+    }
+
+    pub fn activities<'a>(&self, activities: impl IntoIterator<Item = &'a mut ActivityWithDetails>) {
+        for item in activities {
+            if self.redact_notes {
+                item.activity.description = None;
+            }
+            item.assignees.iter_mut().for_each(|c| self.contact(c));
+        }
+    }
+
+    // 分享页选项
+    pub fn share_options(&self) -> ShareHtmlOptions {
+        ShareHtmlOptions {
+            redact_notes: self.redact_notes,
+            redact_phones: self.redact_phones,
+            mask_emails: self.mask_emails,
+            redact_emails: self.redact_emails,
+            anonymize: self.anonymize,
+            profile: Some(self.name.clone()),
+        }
+    }
+
+    // 单表 JSON 导出时置空的字段（按字段整体置空，遮盖邮箱时邮箱也一并置空）
+    pub fn redacted_export_fields(&self) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if self.redact_phones {
+            fields.push("phones");
+        }
+        if self.redact_emails || self.mask_emails {
+            fields.push("email");
+        }
+        if self.redact_notes {
+            fields.extend(["notes", "description"]);
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction_profiles_merge_builtins_with_settings() {
+        let mut settings = std::collections::HashMap::new();
+        let client = find_profile(&settings, "client-facing").unwrap();
+        assert!(client.redact_phones && client.redact_notes && client.redact_emails && !client.anonymize);
+        assert_eq!(client.redacted_export_fields(), vec!["phones", "email", "notes", "description"]);
+        assert_eq!(mask_phone("13812345678"), "138****5678");
+        assert_eq!(mask_email("zhang@example.com"), "z****@example.com");
+        assert_eq!(mask_email("无效"), "**");
+
+        settings.insert(
+            SETTING_REDACTION_PROFILES.to_string(),
+            r#"[{"name": "client-facing", "redact_phones": true}, {"name": " 审计 ", "anonymize": true}, {"name": "邮箱", "mask_emails": true}]"#.to_string(),
+        );
+        let names: Vec<String> = profiles(&settings).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["internal", "client-facing", "审计", "邮箱"]);
+        let overridden = find_profile(&settings, "client-facing").unwrap();
+        assert!(overridden.redact_phones && !overridden.redact_notes && !overridden.builtin);
+        assert_eq!(overridden.redacted_export_fields(), vec!["phones"]);
+        assert!(find_profile(&settings, "审计").unwrap().anonymize);
+        // 遮盖邮箱与遮盖电话相互独立
+        let masked = find_profile(&settings, "邮箱").unwrap();
+        assert_eq!(masked.redacted_export_fields(), vec!["email"]);
+        let options = masked.share_options();
+        assert!(options.mask_emails && !options.redact_phones && !options.redact_emails);
+    }
+}
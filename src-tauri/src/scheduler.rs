@@ -0,0 +1,373 @@
+// src-tauri/src/scheduler.rs
+// 后台任务调度：每个后台任务对应一个类 cron 表达式（分 时 日 月 周），
+// 调度循环每轮检查自上次检查以来哪些任务到期，具体任务由调用方执行
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::sync::{Condvar, Mutex};
+use once_cell::sync::Lazy;
+use tokio_util::sync::CancellationToken;
+
+use crate::db::{self, JobRun};
+
+// 任务执行时间的设置项前缀，如 job_schedule.auto_summary = "10 0 * * *"
+pub const JOB_SCHEDULE_PREFIX: &str = "job_schedule.";
+
+// 后台任务定义
+pub struct JobDefinition {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub default_schedule: &'static str,
+}
+
+// 所有后台任务（按执行顺序）
+pub const JOBS: &[JobDefinition] = &[
+    JobDefinition { name: "reminders", label: "事件提醒", default_schedule: "* * * * *" },
+    JobDefinition { name: "recurring_activities", label: "生成周期活动", default_schedule: "* * * * *" },
+    JobDefinition { name: "pending_indicators", label: "刷新托盘待处理数量", default_schedule: "* * * * *" },
+    JobDefinition { name: "overdue_check", label: "逾期活动提醒", default_schedule: "0 9 * * *" },
+    JobDefinition { name: "auto_summary", label: "自动生成总结", default_schedule: "10 0 * * *" },
+    JobDefinition { name: "daily_briefing", label: "生成每日简报", default_schedule: "30 8 * * *" },
+    JobDefinition { name: "day_note_prompt", label: "提醒写每日记录", default_schedule: "0 18 * * 1-5" },
+    JobDefinition { name: "cleanup_suggestions", label: "每周清理建议", default_schedule: "0 10 * * 1" },
+    JobDefinition { name: "purge_file_shares", label: "删除过期分享", default_schedule: "15 * * * *" },
+    JobDefinition { name: "commitment_reminders", label: "合同/发票/付款到期提醒", default_schedule: "*/5 * * * *" },
+    JobDefinition { name: "backup", label: "备份数据库", default_schedule: "0 3 * * *" },
+];
+
+// 按名称查找任务
+pub fn find_job(name: &str) -> Option<&'static JobDefinition> {
+    JOBS.iter().find(|job| job.name == name)
+}
+
+// 任务执行时间对应的设置项键名
+pub fn schedule_setting_key(name: &str) -> String {
+    format!("{}{}", JOB_SCHEDULE_PREFIX, name)
+}
+
+// 解析后的 cron 表达式，每个字段记录允许的取值
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,   // 0-59
+    hours: Vec<bool>,     // 0-23
+    days: Vec<bool>,      // 1-31
+    months: Vec<bool>,    // 1-12
+    weekdays: Vec<bool>,  // 0-6，0 为周日
+    any_day: bool,        // 日字段为 *
+    any_weekday: bool,    // 周字段为 *
+}
+
+impl CronSchedule {
+    // 解析 "分 时 日 月 周" 五段式表达式，支持 *、*/n、a-b、a-b/n、逗号列表和 @daily 等别名
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("执行时间应为 5 段（分 时 日 月 周）: {}", expr));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // 7 和 0 都表示周日
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        weekdays.truncate(7);
+
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    // 某一分钟是否应执行
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        let day_ok = self.days[time.day() as usize];
+        let weekday_ok = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        // 与 cron 一致：日和周都有限制时满足其一即可
+        let date_ok = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day_ok,
+            (true, false) => weekday_ok,
+            (false, false) => day_ok || weekday_ok,
+        };
+        date_ok
+            && self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+    }
+
+    // (after, up_to] 之间是否有应执行的分钟（最多回溯一天，避免休眠唤醒后补跑过多）
+    pub fn due_between(&self, after: NaiveDateTime, up_to: NaiveDateTime) -> bool {
+        let start = after.max(up_to - Duration::days(1));
+        let mut minute = truncate_to_minute(start) + Duration::minutes(1);
+        let end = truncate_to_minute(up_to);
+        while minute <= end {
+            if self.matches(&minute) {
+                return true;
+            }
+            minute += Duration::minutes(1);
+        }
+        false
+    }
+
+    // 下一次执行时间（一年内没有则为 None，如 2 月 30 日）
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut minute = truncate_to_minute(time) + Duration::minutes(1);
+        let limit = time + Duration::days(366);
+        while minute <= limit {
+            if self.matches(&minute) {
+                return Some(minute);
+            }
+            minute += Duration::minutes(1);
+        }
+        None
+    }
+}
+
+// 去掉秒和纳秒
+fn truncate_to_minute(time: NaiveDateTime) -> NaiveDateTime {
+    time.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(time)
+}
+
+// 解析单个字段，返回下标 0..=max 的取值表
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    let invalid = || format!("无效的执行时间字段: {}", field);
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (a.parse().map_err(|_| invalid())?, b.parse().map_err(|_| invalid())?)
+        } else {
+            let value: u32 = range.parse().map_err(|_| invalid())?;
+            // "5/10" 表示从 5 开始每 10 个
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+// 读取任务的执行时间（未设置或设置无效时使用默认值）
+pub fn load_schedule(job: &JobDefinition, settings: &std::collections::HashMap<String, String>) -> CronSchedule {
+    if let Some(expr) = settings.get(&schedule_setting_key(job.name)) {
+        match CronSchedule::parse(expr) {
+            Ok(schedule) => return schedule,
+            Err(e) => println!("⚠️ 任务 {} 的执行时间无效，使用默认值: {}", job.name, e),
+        }
+    }
+    CronSchedule::parse(job.default_schedule).expect("内置任务的执行时间应有效")
+}
+
+// 任务的执行时间配置（用于设置界面）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobSchedule {
+    pub name: String,
+    pub label: String,
+    pub schedule: String,          // 当前生效的表达式
+    pub default_schedule: String,
+    pub next_run: Option<String>,  // 下一次执行时间
+}
+
+// 列出所有任务的执行时间和下一次执行时间
+pub fn describe_schedules(settings: &std::collections::HashMap<String, String>, now: NaiveDateTime) -> Vec<JobSchedule> {
+    JOBS.iter().map(|job| {
+        let configured = settings.get(&schedule_setting_key(job.name))
+            .filter(|expr| CronSchedule::parse(expr).is_ok());
+        let schedule = load_schedule(job, settings);
+        JobSchedule {
+            name: job.name.to_string(),
+            label: job.label.to_string(),
+            schedule: configured.cloned().unwrap_or_else(|| job.default_schedule.to_string()),
+            default_schedule: job.default_schedule.to_string(),
+            next_run: schedule.next_after(now).map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+        }
+    }).collect()
+}
+
+// 正在执行的任务（避免定时执行和手动执行同时进行）
+static RUNNING_JOBS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+// 任务的执行标记，离开作用域时（包括任务 panic 时）清除
+pub struct RunningJob(&'static str);
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        let mut running = RUNNING_JOBS.lock().unwrap_or_else(|e| e.into_inner());
+        running.retain(|n| *n != self.0);
+    }
+}
+
+// 标记任务开始执行，任务已在执行时返回 None；执行结束时丢弃返回的标记
+pub fn try_begin(name: &'static str) -> Option<RunningJob> {
+    let mut running = RUNNING_JOBS.lock().ok()?;
+    if running.contains(&name) {
+        return None;
+    }
+    running.push(name);
+    Some(RunningJob(name))
+}
+
+// 任务是否正在执行
+pub fn is_running(name: &str) -> bool {
+    RUNNING_JOBS.lock().map(|running| running.contains(&name)).unwrap_or(false)
+}
+
+// 后台任务的运行状态
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackgroundJobStatus {
+    #[serde(flatten)]
+    pub schedule: JobSchedule,
+    pub running: bool,
+    pub last_run: Option<JobRun>,
+    pub last_success_at: Option<String>,
+}
+
+// 汇总所有任务的执行时间、最近一次执行和当前是否在执行
+pub fn describe_status(settings: &std::collections::HashMap<String, String>, now: NaiveDateTime) -> rusqlite::Result<Vec<BackgroundJobStatus>> {
+    let mut latest = db::fetch_latest_job_runs()?;
+    Ok(describe_schedules(settings, now).into_iter().map(|schedule| {
+        let (last_run, last_success_at) = match latest.remove(&schedule.name) {
+            Some((run, success_at)) => (Some(run), success_at),
+            None => (None, None),
+        };
+        BackgroundJobStatus {
+            running: is_running(&schedule.name),
+            schedule,
+            last_run,
+            last_success_at,
+        }
+    }).collect())
+}
+
+// 退出信号：应用退出时取消，调度循环完成当前一轮后停止
+static SHUTDOWN: Lazy<CancellationToken> = Lazy::new(CancellationToken::new);
+
+// 调度循环是否在运行（用于退出时等待其停止）
+static LOOP_RUNNING: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+
+// 获取退出信号
+pub fn shutdown_token() -> CancellationToken {
+    SHUTDOWN.clone()
+}
+
+// 通知后台任务停止
+pub fn request_shutdown() {
+    SHUTDOWN.cancel();
+}
+
+// 调度循环开始/结束时调用
+pub fn set_loop_running(running: bool) {
+    let (lock, cvar) = &LOOP_RUNNING;
+    if let Ok(mut state) = lock.lock() {
+        *state = running;
+        cvar.notify_all();
+    }
+}
+
+// 等待调度循环停止，超时返回 false（循环未启动时立即返回 true）
+pub fn wait_for_loop_stopped(timeout: std::time::Duration) -> bool {
+    let (lock, cvar) = &LOOP_RUNNING;
+    let Ok(state) = lock.lock() else {
+        return false;
+    };
+    match cvar.wait_timeout_while(state, timeout, |running| *running) {
+        Ok((_, result)) => !result.timed_out(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_flag_is_released_when_a_job_panics() {
+        let running = try_begin("test_guard").unwrap();
+        assert!(is_running("test_guard"));
+        assert!(try_begin("test_guard").is_none());
+        drop(running);
+        assert!(!is_running("test_guard"));
+
+        let result = std::panic::catch_unwind(|| {
+            let _running = try_begin("test_guard").unwrap();
+            panic!("任务执行失败");
+        });
+        assert!(result.is_err());
+        assert!(!is_running("test_guard"));
+    }
+
+    fn at(datetime: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> Option<NaiveDateTime> {
+        CronSchedule::parse(expr).unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn cron_ranges_steps_and_lists_give_the_next_run() {
+        // 工作日 9-17 点每 15 分钟：周五收工后下一次是周一 9:00
+        assert_eq!(next("*/15 9-17 * * 1-5", "2024-03-01 17:50"), Some(at("2024-03-04 09:00")));
+        assert_eq!(next("*/15 9-17 * * 1-5", "2024-03-04 09:00"), Some(at("2024-03-04 09:15")));
+        // 起点加步长、区间加步长
+        assert_eq!(next("5/20 * * * *", "2024-03-01 10:46"), Some(at("2024-03-01 11:05")));
+        assert_eq!(next("10-30/10 * * * *", "2024-03-01 10:30"), Some(at("2024-03-01 11:10")));
+        // 列表：下一次严格晚于给定时间
+        assert_eq!(next("0 8,12,18 * * *", "2024-03-01 12:00"), Some(at("2024-03-01 18:00")));
+        assert_eq!(next("0 8,12,18 * * *", "2024-03-01 18:30"), Some(at("2024-03-02 08:00")));
+        // 月份限制、7 表示周日、别名
+        assert_eq!(next("0 0 1 6 *", "2024-03-10 00:00"), Some(at("2024-06-01 00:00")));
+        assert_eq!(next("0 0 * * 7", "2024-03-01 00:00"), Some(at("2024-03-03 00:00")));
+        assert_eq!(CronSchedule::parse(" @daily ").unwrap(), CronSchedule::parse("0 0 * * *").unwrap());
+        // 不存在的日期一年内找不到
+        assert_eq!(next("0 0 30 2 *", "2024-03-01 00:00"), None);
+    }
+
+    #[test]
+    fn cron_day_and_weekday_match_either_when_both_are_set() {
+        // 每月 13 日或每周五：2024-03-13 是周三，2024-03-15 是周五
+        assert_eq!(next("0 9 13 * 5", "2024-03-09 00:00"), Some(at("2024-03-13 09:00")));
+        assert_eq!(next("0 9 13 * 5", "2024-03-13 09:00"), Some(at("2024-03-15 09:00")));
+        // 只限制其一时只看该字段
+        assert_eq!(next("0 9 13 * *", "2024-03-13 09:00"), Some(at("2024-04-13 09:00")));
+        assert_eq!(next("0 9 * * 5", "2024-03-09 00:00"), Some(at("2024-03-15 09:00")));
+        // 日和周都满足时只执行一次，且仍受月份限制
+        assert_eq!(next("0 9 13 4 6", "2024-03-01 00:00"), Some(at("2024-04-06 09:00")));
+        assert!(CronSchedule::parse("0 9 13 4 6").unwrap().due_between(at("2024-04-13 08:00"), at("2024-04-13 09:30")));
+    }
+
+    #[test]
+    fn invalid_cron_fields_are_rejected() {
+        for expr in [
+            "", "* * * *", "* * * * * *",
+            "60 * * * *", "* 24 * * *", "* * 0 * *", "* * 32 * *", "* * * 13 *", "* * * * 8",
+            "*/0 * * * *", "5-1 * * * *", "a * * * *", "1,,2 * * * *", "1-2-3 * * * *", "@yearly",
+        ] {
+            assert!(CronSchedule::parse(expr).is_err(), "{:?} 应无效", expr);
+        }
+    }
+}
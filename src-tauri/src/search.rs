@@ -0,0 +1,274 @@
+// src-tauri/src/search.rs
+// 搜索服务：前端边输入边搜索时，短时间内会连续发起多次查询。
+// 每类搜索维护一个查询序号：新查询到达后旧查询在等待或读取结果时放弃；
+// 相同关键词在短时间内重复查询直接返回上次的结果。
+// 全局搜索的结果按匹配程度、最近使用时间和使用频率（打开记录和互动记录）综合排序，权重可通过设置 search_ranking 调整。
+// 搜索结果附带命中字段的摘要和命中位置（highlights），匹配规则与 SQL 的 LIKE 相同（英文不区分大小写），前端不必重新匹配
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+
+use crate::db::{ProjectFileWithProject, SearchHit, Summary};
+
+// 去抖等待：等待期间有更新的查询到达则放弃本次查询
+pub const DEBOUNCE_DELAY: Duration = Duration::from_millis(150);
+
+// 相同关键词在该时间内重复查询时返回缓存结果
+pub const REPEAT_WINDOW: Duration = Duration::from_millis(1500);
+
+// 被更新的查询取代时返回的错误信息（前端据此忽略旧查询的结果）
+pub const SUPERSEDED_MESSAGE: &str = "搜索已取消：有更新的查询";
+
+// 一类搜索的查询序号和最近一次结果
+pub struct SearchChannel<T> {
+    latest: AtomicU64,
+    last_result: Mutex<Option<(String, Instant, Vec<T>)>>,
+}
+
+impl<T: Clone> Default for SearchChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> SearchChannel<T> {
+    pub fn new() -> Self {
+        SearchChannel {
+            latest: AtomicU64::new(0),
+            last_result: Mutex::new(None),
+        }
+    }
+
+    // 登记一次新查询，返回其序号（之前的查询随之失效）
+    pub fn begin(&self) -> u64 {
+        self.latest.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    // 查询是否仍是最新的
+    pub fn is_current(&self, ticket: u64) -> bool {
+        self.latest.load(Ordering::SeqCst) == ticket
+    }
+
+    // 最近相同关键词的结果（超过 REPEAT_WINDOW 则视为过期）
+    pub fn cached(&self, keyword: &str) -> Option<Vec<T>> {
+        let last = self.last_result.lock().ok()?;
+        match last.as_ref() {
+            Some((k, at, results)) if k == keyword && at.elapsed() < REPEAT_WINDOW => Some(results.clone()),
+            _ => None,
+        }
+    }
+
+    // 记录最新结果
+    pub fn store(&self, keyword: &str, results: &[T]) {
+        if let Ok(mut last) = self.last_result.lock() {
+            *last = Some((keyword.to_string(), Instant::now(), results.to_vec()));
+        }
+    }
+}
+
+pub static FILE_SEARCH: Lazy<SearchChannel<Highlighted<ProjectFileWithProject>>> = Lazy::new(SearchChannel::new);
+pub static SUMMARY_SEARCH: Lazy<SearchChannel<Highlighted<Summary>>> = Lazy::new(SearchChannel::new);
+pub static GLOBAL_SEARCH: Lazy<SearchChannel<SearchHit>> = Lazy::new(SearchChannel::new);
+
+pub const SETTING_SEARCH_RANKING: &str = "search_ranking";  // 全局搜索排序权重（JSON，未填写的项使用默认值）
+
+// 全局搜索排序权重：得分 = 匹配权重 × 匹配程度 + 时间权重 × 时间衰减 + 频率权重 × 使用频率
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingWeights {
+    pub match_weight: f64,
+    pub recency_weight: f64,
+    pub frequency_weight: f64,
+    pub recency_half_life_days: f64,  // 最近使用时间每隔多少天权重减半
+    pub frequency_window_days: i64,   // 统计使用次数的时间窗口（1 到 MAX_FREQUENCY_WINDOW_DAYS 天）
+    pub frequency_saturation: f64,    // 窗口内使用多少次后频率得分不再增加
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        RankingWeights {
+            match_weight: 1.0,
+            recency_weight: 0.6,
+            frequency_weight: 0.6,
+            recency_half_life_days: 14.0,
+            frequency_window_days: 90,
+            frequency_saturation: 12.0,
+        }
+    }
+}
+
+// 统计使用次数的时间窗口上限（约 10 年）
+pub const MAX_FREQUENCY_WINDOW_DAYS: i64 = 3650;
+
+// 解析排序权重（权重不能为负，时间参数必须为正）
+pub fn parse_ranking(value: &str) -> Result<RankingWeights, String> {
+    let weights: RankingWeights = serde_json::from_str(value).map_err(|e| format!("无效的搜索排序权重: {}", e))?;
+    let valid = [weights.match_weight, weights.recency_weight, weights.frequency_weight].iter().all(|w| w.is_finite() && *w >= 0.0)
+        && weights.recency_half_life_days.is_finite() && weights.recency_half_life_days > 0.0
+        && (1..=MAX_FREQUENCY_WINDOW_DAYS).contains(&weights.frequency_window_days)
+        && weights.frequency_saturation.is_finite() && weights.frequency_saturation >= 1.0;
+    if !valid {
+        return Err(format!("无效的搜索排序权重：权重不能为负，天数和次数必须为正，统计窗口最多 {} 天", MAX_FREQUENCY_WINDOW_DAYS));
+    }
+    Ok(weights)
+}
+
+// 匹配程度：完全相同 > 开头相同 > 包含（不区分英文大小写）
+pub fn match_quality(text: &str, keyword: &str) -> f64 {
+    let text = text.trim().to_lowercase();
+    let keyword = keyword.trim().to_lowercase();
+    if text == keyword {
+        1.0
+    } else if text.starts_with(&keyword) {
+        0.7
+    } else if text.contains(&keyword) {
+        0.4
+    } else {
+        0.0
+    }
+}
+
+impl RankingWeights {
+    pub fn from_settings(settings: &HashMap<String, String>) -> Self {
+        settings.get(SETTING_SEARCH_RANKING)
+            .and_then(|v| parse_ranking(v).ok())
+            .unwrap_or_default()
+    }
+
+    // 综合得分：days_since_use 为距最近一次使用的天数（从未使用为 None），uses 为窗口内的使用次数
+    pub fn score(&self, match_quality: f64, days_since_use: Option<f64>, uses: i64) -> f64 {
+        let recency = days_since_use.map_or(0.0, |days| 0.5f64.powf(days.max(0.0) / self.recency_half_life_days));
+        let frequency = ((1.0 + uses.max(0) as f64).ln() / (1.0 + self.frequency_saturation).ln()).min(1.0);
+        self.match_weight * match_quality + self.recency_weight * recency + self.frequency_weight * frequency
+    }
+}
+
+// 关键词以外的查询范围（是否包含已归档项目），与关键词一起作为缓存键
+pub fn archived_scope(include_archived: bool) -> &'static str {
+    if include_archived { "archived" } else { "" }
+}
+
+// 执行一次搜索：命中缓存（相同范围和关键词）直接返回；否则去抖等待后在后台线程查询，
+// query 的第二个参数用于在读取结果时检查是否已被更新的查询取代
+pub async fn run<T, F>(channel: &'static SearchChannel<T>, scope: &str, keyword: String, query: F) -> Result<Vec<T>, String>
+where
+    T: Clone + Send + 'static,
+    F: FnOnce(&str, &dyn Fn() -> bool) -> rusqlite::Result<Vec<T>> + Send + 'static,
+{
+    let ticket = channel.begin();
+    let cache_key = format!("{}\n{}", scope, keyword);
+    if let Some(results) = channel.cached(&cache_key) {
+        return Ok(results);
+    }
+
+    tokio::time::sleep(DEBOUNCE_DELAY).await;
+    if !channel.is_current(ticket) {
+        return Err(SUPERSEDED_MESSAGE.to_string());
+    }
+
+    let results = tokio::task::spawn_blocking(move || {
+        let is_cancelled = || !channel.is_current(ticket);
+        query(&keyword, &is_cancelled)
+    }).await.map_err(|e| e.to_string())?;
+
+    if !channel.is_current(ticket) {
+        return Err(SUPERSEDED_MESSAGE.to_string());
+    }
+    let results = results.map_err(|e| e.to_string())?;
+    channel.store(&cache_key, &results);
+    Ok(results)
+}
+
+// 摘要在第一个命中位置之前保留的字数
+const SNIPPET_LEADING_CHARS: usize = 20;
+
+// 摘要的最大字数（不含省略号）
+const SNIPPET_MAX_CHARS: usize = 80;
+
+// 一个字段的命中摘要，ranges 为摘要中每处命中的 [开始, 结束) 字符位置（按 Unicode 字符计）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchHighlight {
+    pub field: String,
+    pub snippet: String,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+// 附带命中摘要的搜索结果（序列化时原结果的字段保持不变，另加 highlights）
+#[derive(Debug, Clone, Serialize)]
+pub struct Highlighted<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub highlights: Vec<MatchHighlight>,
+}
+
+// 查找关键词在文本中的所有位置（不重叠，英文不区分大小写）
+fn find_matches(text: &[char], keyword: &[char]) -> Vec<usize> {
+    let mut matches = Vec::new();
+    if keyword.is_empty() || keyword.len() > text.len() {
+        return matches;
+    }
+    let mut i = 0;
+    while i + keyword.len() <= text.len() {
+        if text[i..i + keyword.len()].iter().zip(keyword).all(|(a, b)| a.eq_ignore_ascii_case(b)) {
+            matches.push(i);
+            i += keyword.len();
+        } else {
+            i += 1;
+        }
+    }
+    matches
+}
+
+// 生成一个字段的命中摘要：从第一处命中前 SNIPPET_LEADING_CHARS 个字开始截取，换行替换为空格，截断处加省略号
+pub fn highlight(field: &str, text: &str, keyword: &str) -> Option<MatchHighlight> {
+    let chars: Vec<char> = text.chars().collect();
+    let keyword: Vec<char> = keyword.trim().chars().collect();
+    let matches = find_matches(&chars, &keyword);
+    let first = *matches.first()?;
+    let start = first.saturating_sub(SNIPPET_LEADING_CHARS);
+    let end = (start + SNIPPET_MAX_CHARS).max(first + keyword.len()).min(chars.len());
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    let body: String = chars[start..end].iter().map(|c| if c.is_whitespace() { ' ' } else { *c }).collect();
+    let offset = prefix.chars().count();
+    let ranges = matches.iter()
+        .filter(|m| **m + keyword.len() <= end)
+        .map(|m| (m - start + offset, m - start + offset + keyword.len()))
+        .collect();
+    Some(MatchHighlight { field: field.to_string(), snippet: format!("{}{}{}", prefix, body, suffix), ranges })
+}
+
+// 依次检查多个字段，返回有命中的字段摘要
+pub fn highlights(fields: &[(&str, Option<&str>)], keyword: &str) -> Vec<MatchHighlight> {
+    fields.iter()
+        .filter_map(|(field, text)| text.and_then(|t| highlight(field, t, keyword)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_results_carry_match_snippets() {
+        assert_eq!(highlight("title", "Weekly 周会 weekly", "WEEK"), Some(MatchHighlight {
+            field: "title".to_string(),
+            snippet: "Weekly 周会 weekly".to_string(),
+            ranges: vec![(0, 4), (10, 14)],
+        }));
+        assert!(highlight("title", "周会", "月会").is_none());
+        assert!(highlight("title", "周会", " ").is_none());
+
+        // 长文本从命中位置前截取，换行替换为空格
+        let long = format!("{}\n预算超支{}", "前".repeat(30), "后".repeat(100));
+        let snippet = highlight("content", &long, "预算").unwrap();
+        assert!(snippet.snippet.starts_with('…') && snippet.snippet.ends_with('…'));
+        let chars: Vec<char> = snippet.snippet.chars().collect();
+        let (start, end) = snippet.ranges[0];
+        assert_eq!(chars[start..end].iter().collect::<String>(), "预算");
+        assert_eq!(chars[start - 1], ' ');
+    }
+}
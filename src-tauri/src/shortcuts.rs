@@ -0,0 +1,176 @@
+// src-tauri/src/shortcuts.rs
+// 全局快捷键：每个动作对应一个快捷键，保存在设置中（shortcut.<动作>），
+// 按下时由 main.rs 显示主窗口并向前端发送 shortcut 事件，载荷为动作 ID
+use serde::{Deserialize, Serialize};
+
+// 快捷键的设置项前缀，如 shortcut.new_event = "CommandOrControl+Shift+N"（空字符串表示停用）
+pub const SHORTCUT_PREFIX: &str = "shortcut.";
+
+// 按下快捷键时发送给前端的事件名
+pub const SHORTCUT_EVENT: &str = "shortcut";
+
+// 快捷键动作定义
+pub struct ShortcutAction {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub default_accelerator: &'static str,
+}
+
+// 所有可配置的动作
+pub const ACTIONS: &[ShortcutAction] = &[
+    ShortcutAction { id: "new_event", label: "新建事件", default_accelerator: "CommandOrControl+Shift+N" },
+    ShortcutAction { id: "quick_capture", label: "快速录入", default_accelerator: "CommandOrControl+Shift+Space" },
+    ShortcutAction { id: "search", label: "搜索", default_accelerator: "CommandOrControl+Shift+F" },
+];
+
+// 按 ID 查找动作
+pub fn find_action(id: &str) -> Option<&'static ShortcutAction> {
+    ACTIONS.iter().find(|action| action.id == id)
+}
+
+// 动作对应的设置项键名
+pub fn setting_key(id: &str) -> String {
+    format!("{}{}", SHORTCUT_PREFIX, id)
+}
+
+// 规范化快捷键写法（修饰键按固定顺序、按键首字母大写），如 "shift+ctrl+n" -> "Control+Shift+N"
+pub fn normalize_accelerator(accelerator: &str) -> Result<String, String> {
+    let invalid = || format!("无效的快捷键: {}", accelerator);
+    // 修饰键规范名，按此顺序输出
+    const MODIFIERS: &[(&str, &[&str])] = &[
+        ("CommandOrControl", &["commandorcontrol", "cmdorctrl", "commandorctrl", "cmdorcontrol"]),
+        ("Control", &["control", "ctrl"]),
+        ("Alt", &["alt", "option"]),
+        ("Shift", &["shift"]),
+        ("Super", &["super", "cmd", "command", "meta"]),
+    ];
+
+    let mut modifiers = [false; 5];
+    let mut key: Option<String> = None;
+    for part in accelerator.split('+').map(|p| p.trim()) {
+        if part.is_empty() {
+            return Err(invalid());
+        }
+        let lower = part.to_lowercase();
+        match MODIFIERS.iter().position(|(_, aliases)| aliases.contains(&lower.as_str())) {
+            Some(i) => modifiers[i] = true,
+            None if key.is_none() => {
+                key = Some(if part.chars().count() == 1 { part.to_uppercase() } else { capitalize(part) });
+            }
+            None => return Err(invalid()),
+        }
+    }
+
+    let key = key.ok_or_else(invalid)?;
+    // 全局快捷键至少需要一个修饰键（功能键除外），避免占用普通按键
+    let is_function_key = key.starts_with('F') && key[1..].parse::<u8>().is_ok_and(|n| (1..=24).contains(&n));
+    if !modifiers.iter().any(|m| *m) && !is_function_key {
+        return Err(format!("快捷键需要包含 Ctrl/Alt/Shift 等修饰键: {}", accelerator));
+    }
+
+    let mut parts: Vec<&str> = MODIFIERS.iter().zip(modifiers)
+        .filter(|(_, used)| *used)
+        .map(|((name, _), _)| *name)
+        .collect();
+    parts.push(&key);
+    Ok(parts.join("+"))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// 冲突检测用的比较键：CommandOrControl 按当前平台展开，按键名不区分大小写
+fn comparison_key(accelerator: &str) -> Option<String> {
+    let normalized = normalize_accelerator(accelerator).ok()?;
+    let platform_modifier = if cfg!(target_os = "macos") { "Super" } else { "Control" };
+    let expanded = normalized.replace("CommandOrControl", platform_modifier);
+    normalize_accelerator(&expanded).ok().map(|a| a.to_lowercase())
+}
+
+// 动作当前的快捷键配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub id: String,
+    pub label: String,
+    pub accelerator: Option<String>,  // None 表示已停用
+    pub default_accelerator: String,
+    pub is_default: bool,
+}
+
+// 读取所有动作的快捷键（未设置时使用默认值，设置无效时视为停用）
+pub fn load_bindings(settings: &std::collections::HashMap<String, String>) -> Vec<ShortcutBinding> {
+    ACTIONS.iter().map(|action| {
+        let configured = settings.get(&setting_key(action.id));
+        let accelerator = match configured {
+            Some(value) if value.trim().is_empty() => None,
+            Some(value) => normalize_accelerator(value).ok(),
+            None => Some(action.default_accelerator.to_string()),
+        };
+        ShortcutBinding {
+            id: action.id.to_string(),
+            label: action.label.to_string(),
+            accelerator,
+            default_accelerator: action.default_accelerator.to_string(),
+            is_default: configured.is_none(),
+        }
+    }).collect()
+}
+
+// 查找已使用该快捷键的其他动作
+pub fn find_conflict<'a>(bindings: &'a [ShortcutBinding], id: &str, accelerator: &str) -> Option<&'a ShortcutBinding> {
+    let key = comparison_key(accelerator)?;
+    bindings.iter().find(|binding| {
+        binding.id != id && binding.accelerator.as_deref().and_then(comparison_key).is_some_and(|a| a == key)
+    })
+}
+
+// 根据按下的快捷键找到对应动作
+pub fn action_for_accelerator(bindings: &[ShortcutBinding], matches: impl Fn(&str) -> bool) -> Option<&ShortcutBinding> {
+    bindings.iter().find(|binding| binding.accelerator.as_deref().is_some_and(&matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn accelerators_are_normalized_or_rejected() {
+        assert_eq!(normalize_accelerator("shift+ctrl+n").unwrap(), "Control+Shift+N");
+        assert_eq!(normalize_accelerator(" cmdorctrl + Shift + space ").unwrap(), "CommandOrControl+Shift+Space");
+        assert_eq!(normalize_accelerator("meta+option+k").unwrap(), "Alt+Super+K");
+        // 功能键可以单独使用，普通按键必须带修饰键
+        assert_eq!(normalize_accelerator("f5").unwrap(), "F5");
+        assert!(normalize_accelerator("n").is_err());
+        assert!(normalize_accelerator("F25").is_err());
+        // 缺少按键、多个按键、空段都无效
+        assert!(normalize_accelerator("ctrl+shift").is_err());
+        assert!(normalize_accelerator("ctrl+a+b").is_err());
+        assert!(normalize_accelerator("ctrl++").is_err());
+        assert!(normalize_accelerator("").is_err());
+    }
+
+    #[test]
+    fn conflicts_ignore_the_same_action_and_disabled_bindings() {
+        let mut settings = HashMap::new();
+        settings.insert(setting_key("quick_capture"), String::new());
+        let bindings = load_bindings(&settings);
+        assert_eq!(bindings.iter().find(|b| b.id == "quick_capture").unwrap().accelerator, None);
+
+        // 写法不同但规范化后相同的快捷键视为冲突
+        let conflict = find_conflict(&bindings, "search", "shift+cmdorctrl+n").unwrap();
+        assert_eq!(conflict.id, "new_event");
+        let platform_modifier = if cfg!(target_os = "macos") { "super" } else { "ctrl" };
+        assert!(find_conflict(&bindings, "search", &format!("{}+shift+n", platform_modifier)).is_some());
+
+        // 修改自身的快捷键、占用已停用动作的默认快捷键都不算冲突
+        assert!(find_conflict(&bindings, "new_event", "CommandOrControl+Shift+N").is_none());
+        assert!(find_conflict(&bindings, "search", "CommandOrControl+Shift+Space").is_none());
+        assert!(find_conflict(&bindings, "search", "not a shortcut").is_none());
+    }
+}
@@ -0,0 +1,83 @@
+// src-tauri/src/windows.rs
+// 独立窗口：项目或联系人可以在单独的窗口中打开（如放到第二块屏幕上），
+// 窗口标签为 "<类型>-<ID>"，前端根据 URL 参数 entity/id 只渲染对应实体；
+// 窗口位置和大小保存在设置中（window_state.<窗口标签>），下次打开时恢复
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+
+// 窗口状态的设置项前缀，如 window_state.project-3 = {"x":100,"y":80,"width":900,"height":700,"maximized":false}
+pub const WINDOW_STATE_PREFIX: &str = "window_state.";
+
+// 可以在独立窗口中打开的实体类型
+pub const ENTITY_WINDOW_TYPES: &[&str] = &["project", "contact"];
+
+// 新窗口的默认大小（逻辑像素）
+pub const DEFAULT_WIDTH: f64 = 900.0;
+pub const DEFAULT_HEIGHT: f64 = 700.0;
+
+// 窗口最小尺寸，过小的保存值视为无效
+pub const MIN_WIDTH: f64 = 480.0;
+pub const MIN_HEIGHT: f64 = 360.0;
+
+// 窗口位置和大小（逻辑像素）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+// 解析保存的窗口状态
+pub fn parse_state(value: &str) -> Result<WindowState, String> {
+    let state: WindowState = serde_json::from_str(value).map_err(|e| format!("无效的窗口状态: {}", e))?;
+    let finite = [state.x, state.y, state.width, state.height].iter().all(|v| v.is_finite());
+    if !finite || state.width < MIN_WIDTH || state.height < MIN_HEIGHT {
+        return Err(format!("无效的窗口状态: {}", value));
+    }
+    Ok(state)
+}
+
+// 实体对应的窗口标签
+pub fn window_label(entity_type: &str, entity_id: i32) -> String {
+    format!("{}-{}", entity_type, entity_id)
+}
+
+// 实体窗口加载的页面地址
+pub fn window_url(entity_type: &str, entity_id: i32) -> String {
+    format!("index.html?entity={}&id={}", entity_type, entity_id)
+}
+
+// 窗口状态对应的设置项键名
+pub fn setting_key(label: &str) -> String {
+    format!("{}{}", WINDOW_STATE_PREFIX, label)
+}
+
+// 读取窗口上次的位置和大小（未保存或保存值无效时为 None）
+pub fn load_state(settings: &std::collections::HashMap<String, String>, label: &str) -> Option<WindowState> {
+    settings.get(&setting_key(label)).and_then(|value| parse_state(value).ok())
+}
+
+// 窗口移动/缩放时的最新状态，窗口关闭时再写入设置（避免拖动过程中频繁写库）
+static PENDING_STATES: Lazy<Mutex<std::collections::HashMap<String, WindowState>>> =
+    Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+
+// 记录窗口的最新状态
+pub fn remember(label: &str, state: WindowState) {
+    if let Ok(mut pending) = PENDING_STATES.lock() {
+        pending.insert(label.to_string(), state);
+    }
+}
+
+// 窗口尚未保存的最新状态
+pub fn pending(label: &str) -> Option<WindowState> {
+    PENDING_STATES.lock().ok().and_then(|pending| pending.get(label).copied())
+}
+
+// 取出窗口尚未保存的状态
+pub fn take_pending(label: &str) -> Option<WindowState> {
+    PENDING_STATES.lock().ok().and_then(|mut pending| pending.remove(label))
+}
@@ -10,8 +10,9 @@
  * 5. 编辑功能支持
  */
 
-import { useState, useRef } from 'react'; 
+import { useState, useRef, useEffect } from 'react'; 
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { ToastProvider, useToast } from './components/shared/ToastProvider';
 import { ProgressBar } from './components/shared/Loading';
 import { LanguageProvider, useTranslation, LANGUAGES } from './i18n';
@@ -19,10 +20,11 @@ import ProjectForm from './components/ProjectForm';
 import ProjectList, { ProjectListRef } from './components/ProjectList'; 
 import ContactForm from './components/ContactForm';
 import ContactList, { ContactListRef } from './components/ContactList';
-import EventForm, { EventWithDetails } from './components/EventForm';
+import EventForm, { EventWithDetails, EVENT_TITLE_INPUT_ID } from './components/EventForm';
 import EventList, { EventListRef } from './components/EventList';
-import FileSearch from './components/FileSearch';
+import FileSearch, { FILE_SEARCH_INPUT_ID } from './components/FileSearch';
 import Summary from './components/Summary';
+import EntityWindow, { parseEntityWindowParams } from './components/EntityWindow';
 import { colors, spacing, typography, borderRadius, shadows } from './styles/theme';
 import './App.css';
 
@@ -115,6 +117,19 @@ function AppContent() {
     eventListRef.current?.refresh();
   };
 
+  // 全局快捷键：后端已显示主窗口，载荷为动作 ID，切换到事件页并聚焦对应输入框
+  useEffect(() => {
+    const unlisten = listen<string>('shortcut', (event) => {
+      const inputId = event.payload === 'search' ? FILE_SEARCH_INPUT_ID : EVENT_TITLE_INPUT_ID;
+      setActiveTab('events');
+      // 等切换后的标签页渲染完成再聚焦
+      requestAnimationFrame(() => document.getElementById(inputId)?.focus());
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   /**
    * 导出所有活动数据
    */
@@ -403,10 +418,12 @@ function AppContent() {
  * 应用根组件 - 包裹 LanguageProvider 和 ToastProvider
  */
 function App() {
+  // 独立窗口（项目/联系人）只渲染对应实体
+  const entityWindow = parseEntityWindowParams(window.location.search);
   return (
     <LanguageProvider>
       <ToastProvider>
-        <AppContent />
+        {entityWindow ? <EntityWindow {...entityWindow} /> : <AppContent />}
       </ToastProvider>
     </LanguageProvider>
   );
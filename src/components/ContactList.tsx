@@ -68,6 +68,15 @@ const ContactList = forwardRef<ContactListRef, ContactListProps>(({ onEditContac
     setTimeout(() => setHighlightedContactId(null), 3000);
   };
 
+  /** 在独立窗口中打开联系人 */
+  const openInWindow = async (contactId: number) => {
+    try {
+      await invoke('open_entity_window', { entity: { entity_type: 'contact', entity_id: contactId } });
+    } catch (error) {
+      console.error(`${t.common.openWindowFailed}:`, error);
+    }
+  };
+
   const formatTags = (tags: string | null) => {
     if (!tags) return [];
     return tags.split(',').map(tag => tag.trim()).filter(tag => tag.length > 0);
@@ -342,6 +351,12 @@ const ContactList = forwardRef<ContactListRef, ContactListProps>(({ onEditContac
                     >
                       {t.contact.viewTimeline}
                     </button>
+                    <button
+                      onClick={() => openInWindow(contact.id)}
+                      style={styles.timelineBtn}
+                    >
+                      {t.common.openInWindow}
+                    </button>
                   </div>
                 </div>
               </div>
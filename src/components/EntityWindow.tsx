@@ -0,0 +1,51 @@
+// src/components/EntityWindow.tsx
+// 独立窗口内容：由 open_entity_window 打开，只显示一个项目或联系人的时间线
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import ProjectTimeline from './ProjectTimeline';
+import ContactTimeline from './ContactTimeline';
+
+export type EntityWindowType = 'project' | 'contact';
+
+interface EntityWindowProps {
+  entityType: EntityWindowType;
+  entityId: number;
+}
+
+/** 从 URL 参数（?entity=project&id=3）读取独立窗口要显示的实体，主窗口返回 null */
+export function parseEntityWindowParams(search: string): EntityWindowProps | null {
+  const params = new URLSearchParams(search);
+  const entityType = params.get('entity');
+  const entityId = Number(params.get('id'));
+  if ((entityType !== 'project' && entityType !== 'contact') || !Number.isInteger(entityId)) {
+    return null;
+  }
+  return { entityType, entityId };
+}
+
+function EntityWindow({ entityType, entityId }: EntityWindowProps) {
+  const [name, setName] = useState('');
+
+  useEffect(() => {
+    // 已归档的项目也可以在独立窗口中打开
+    const request = entityType === 'project'
+      ? invoke<{ id: number; name: string }[]>('get_projects', { includeArchived: true })
+      : invoke<{ id: number; name: string }[]>('get_contacts');
+    request
+      .then(items => setName(items.find(item => item.id === entityId)?.name ?? ''))
+      .catch(error => console.error('获取实体信息失败:', error));
+  }, [entityType, entityId]);
+
+  const closeWindow = () => {
+    getCurrentWindow().close();
+  };
+
+  return entityType === 'project' ? (
+    <ProjectTimeline projectId={entityId} projectName={name} isOpen onClose={closeWindow} />
+  ) : (
+    <ContactTimeline contactId={entityId} contactName={name} isOpen onClose={closeWindow} />
+  );
+}
+
+export default EntityWindow;
@@ -54,6 +54,9 @@ const EVENT_TYPE_KEYS = [
   'milestone', 'personal', 'other'
 ] as const;
 
+// 事件标题输入框的 ID（全局快捷键“新建事件”“快速录入”聚焦到此处）
+export const EVENT_TITLE_INPUT_ID = 'event-form-title';
+
 function EventForm({ onEventCreated, editingEvent, onEditComplete }: EventFormProps) {
   const { t } = useTranslation();
   const [title, setTitle] = useState('');
@@ -197,6 +200,32 @@ function EventForm({ onEventCreated, editingEvent, onEditComplete }: EventFormPr
     }
   };
 
+  // 粘贴图片：保存到事件的内嵌图片目录，在光标处插入 Markdown 图片链接（图片归属事件，新建事件需先保存）
+  const handleDescriptionPaste = async (e: React.ClipboardEvent<HTMLTextAreaElement>) => {
+    const item = Array.from(e.clipboardData.items).find(i => i.kind === 'file' && i.type.startsWith('image/'));
+    const file = item?.getAsFile();
+    if (!file) return;
+    e.preventDefault();
+
+    if (!editingEvent) {
+      showToast({ type: 'warning', message: t.event.pasteImageSaveFirst });
+      return;
+    }
+
+    const textarea = e.currentTarget;
+    const start = textarea.selectionStart;
+    const end = textarea.selectionEnd;
+    try {
+      const bytes = Array.from(new Uint8Array(await file.arrayBuffer()));
+      const url = await invoke<string>('save_inline_image', { eventId: editingEvent.event.id, bytes });
+      const markdown = `![](${url})`;
+      setDescription(prev => prev.slice(0, start) + markdown + prev.slice(end));
+    } catch (err) {
+      console.error('保存内嵌图片失败:', err);
+      showToast({ type: 'error', message: `${t.event.pasteImageFailed}: ${err}` });
+    }
+  };
+
   const resetForm = () => {
     const today = new Date().toISOString().split('T')[0];
     setTitle('');
@@ -227,6 +256,7 @@ function EventForm({ onEventCreated, editingEvent, onEditComplete }: EventFormPr
       <div style={styles.field}>
         <label style={styles.label}>{t.event.eventTitle} *</label>
         <input
+          id={EVENT_TITLE_INPUT_ID}
           type="text"
           value={title}
           onChange={(e) => setTitle(e.target.value)}
@@ -393,6 +423,7 @@ function EventForm({ onEventCreated, editingEvent, onEditComplete }: EventFormPr
         <textarea
           value={description}
           onChange={(e) => setDescription(e.target.value)}
+          onPaste={handleDescriptionPaste}
           placeholder={t.event.descriptionPlaceholder}
           style={styles.textarea}
           disabled={isLoading}
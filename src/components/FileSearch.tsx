@@ -29,6 +29,9 @@ interface ProjectFileWithProject {
   project_name: string;
 }
 
+// 搜索输入框的 ID（全局快捷键“搜索”聚焦到此处）
+export const FILE_SEARCH_INPUT_ID = 'file-search-keyword';
+
 function FileSearch() {
   const { t } = useTranslation();
   const [keyword, setKeyword] = useState('');
@@ -53,6 +56,10 @@ function FileSearch() {
         showToast({ type: 'info', message: t.search.noResults });
       }
     } catch (error) {
+      // 被更新的搜索取代，结果以新的搜索为准
+      if (String(error).startsWith('搜索已取消')) {
+        return;
+      }
       console.error('搜索文件失败:', error);
       showToast({ type: 'error', message: `${t.search.searchFailed}: ${error}` });
     } finally {
@@ -235,6 +242,7 @@ function FileSearch() {
       </h4>
       <div style={styles.searchBox}>
         <input
+          id={FILE_SEARCH_INPUT_ID}
           type="text"
           placeholder={t.search.placeholder}
           value={keyword}
@@ -144,6 +144,20 @@ function ProjectActivities({ projectId, projectName, isOpen, onClose }: ProjectA
       return;
     }
 
+    // 项目中已有名称相近的活动时先确认，避免重复记录
+    try {
+      const similar: { name: string; same_project: boolean }[] = await invoke('find_similar_activities', {
+        name: formName.trim(),
+        projectId,
+      });
+      const names = similar.filter(a => a.same_project).map(a => `"${a.name}"`);
+      if (names.length > 0 && !window.confirm(t.activity.confirmSimilar.replace('{names}', names.join(', ')))) {
+        return;
+      }
+    } catch (error) {
+      console.error('检查相似活动失败:', error);
+    }
+
     setIsSubmitting(true);
     try {
       await invoke('create_activity', {
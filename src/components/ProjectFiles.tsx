@@ -118,7 +118,11 @@ function ProjectFiles({ projectId, projectName, isOpen, onClose }: ProjectFilesP
       await fetchFiles();
     } catch (error) {
       console.error('上传文件失败:', error);
-      showToast({ type: 'error', message: `${t.file.uploadFailed}: ${error}` });
+      // 被拒绝的文件返回 { code, file_name, reason }，其他错误为字符串
+      const reason = typeof error === 'object' && error !== null && 'reason' in error
+        ? (error as { reason: string }).reason
+        : String(error);
+      showToast({ type: 'error', message: `${t.file.uploadFailed}: ${reason}` });
     } finally {
       setUploading(false);
     }
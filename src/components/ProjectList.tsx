@@ -67,6 +67,7 @@ const ProjectList = forwardRef<ProjectListRef, ProjectListProps>(({ onEditProjec
     { key: 'activities', label: t.project.activities, color: colors.primary.activity, icon: '⚡' },
     { key: 'files', label: t.project.files, color: colors.primary.file, icon: '📁' },
     { key: 'contacts', label: t.project.contacts, color: colors.primary.project, icon: '👥' },
+    { key: 'window', label: t.common.openInWindow, color: colors.primary.contact, icon: '🗗' },
   ] as const;
 
   /** 获取项目列表 */
@@ -113,6 +114,10 @@ const ProjectList = forwardRef<ProjectListRef, ProjectListProps>(({ onEditProjec
       case 'activities': setActivitiesProject(target); break;
       case 'files': setFilesProject(target); break;
       case 'contacts': setManagingProject(target); break;
+      case 'window':
+        invoke('open_entity_window', { entity: { entity_type: 'project', entity_id: project.id } })
+          .catch(error => showToast({ type: 'error', message: `${t.common.openWindowFailed}: ${error}` }));
+        break;
     }
   };
 
@@ -42,6 +42,8 @@ function Summary() {
       'monthly': t.summary.types.monthly,
       'yearly': t.summary.types.yearly,
       'custom': t.summary.types.custom,
+      'briefing': t.summary.types.briefing,
+      'retrospective': t.summary.types.retrospective,
     };
     return labels[type] || type;
   };
@@ -28,6 +28,8 @@ export const en: Translations = {
     info: 'Info',
     refreshing: 'Refreshing...',
     addedAt: 'Added',
+    openInWindow: 'Open in window',
+    openWindowFailed: 'Failed to open window',
   },
 
   // App title
@@ -121,6 +123,8 @@ export const en: Translations = {
     selectedCount: '{count} selected',
     description: 'Description',
     descriptionPlaceholder: 'Record event details...',
+    pasteImageSaveFirst: 'Save the event before pasting images',
+    pasteImageFailed: 'Failed to save image',
     saveBtn: 'Save Event',
     createSuccess: 'Event recorded successfully!',
     updateSuccess: 'Event updated successfully!',
@@ -186,6 +190,7 @@ export const en: Translations = {
     exportNoData: 'No activities to export in this project',
     generatingGantt: 'Generating Gantt chart...',
     confirmDelete: 'Are you sure you want to delete activity "{name}"? This cannot be undone.',
+    confirmSimilar: 'Similar activities already exist in this project: {names}. Create anyway?',
     assignFirst: 'Please assign someone first',
     // Status
     status: {
@@ -310,6 +315,8 @@ export const en: Translations = {
       monthly: 'Monthly',
       yearly: 'Yearly',
       custom: 'Custom',
+      briefing: 'Daily briefing',
+      retrospective: 'Retrospective',
     },
   },
 };
@@ -26,6 +26,8 @@ export const zh = {
     info: '提示',
     refreshing: '刷新中...',
     addedAt: '添加于',
+    openInWindow: '新窗口打开',
+    openWindowFailed: '打开窗口失败',
   },
 
   // 应用标题
@@ -119,6 +121,8 @@ export const zh = {
     selectedCount: '已选 {count} 人',
     description: '事件描述',
     descriptionPlaceholder: '详细记录事件内容...',
+    pasteImageSaveFirst: '请先保存事件，再粘贴图片',
+    pasteImageFailed: '图片保存失败',
     saveBtn: '保存事件',
     createSuccess: '事件记录成功！',
     updateSuccess: '事件更新成功！',
@@ -184,6 +188,7 @@ export const zh = {
     exportNoData: '当前项目没有活动可导出',
     generatingGantt: '正在生成甘特图...',
     confirmDelete: '确定要删除活动 "{name}" 吗？此操作不可恢复。',
+    confirmSimilar: '项目中已有相似的活动：{names}，仍要创建吗？',
     assignFirst: '请先分配负责人',
     // 状态
     status: {
@@ -308,6 +313,8 @@ export const zh = {
       monthly: '月总结',
       yearly: '年总结',
       custom: '自定义',
+      briefing: '每日简报',
+      retrospective: '项目复盘',
     },
   },
 };